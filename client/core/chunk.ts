@@ -14,6 +14,7 @@ type ChunkOptions = {
   size: number;
   subChunks: number;
   maxHeight: number;
+  minHeight: number;
   dimension: number;
 };
 
@@ -51,7 +52,11 @@ class Chunk {
   public isInitialized = false; // is populated with terrain info
   public isPending = false; // pending for client-side terrain generation
 
-  constructor(public engine: Engine, public coords: Coords2, { size, dimension, maxHeight, subChunks }: ChunkOptions) {
+  constructor(
+    public engine: Engine,
+    public coords: Coords2,
+    { size, dimension, maxHeight, minHeight, subChunks }: ChunkOptions,
+  ) {
     this.size = size;
     this.subChunks = subChunks;
     this.maxHeight = maxHeight;
@@ -80,7 +85,9 @@ class Chunk {
     vec3.scale(this.min, this.min, size);
     vec3.add(this.max, this.max, [1, 0, 1]);
     vec3.scale(this.max, this.max, size);
-    vec3.add(this.max, this.max, [0, maxHeight, 0]);
+    // the column starts at the bottom of the world, below 0 for deep worlds
+    vec3.add(this.min, this.min, [0, minHeight, 0]);
+    vec3.add(this.max, this.max, [0, minHeight + maxHeight, 0]);
   }
 
   setVoxel = (vx: number, vy: number, vz: number, type: number, rotation: number, yRotation: number) => {
@@ -259,12 +266,12 @@ class Chunk {
 
         const min = new Vector3(
           this.min[0] * this.dimension,
-          subChunkUnit * i * this.dimension,
+          (this.min[1] + subChunkUnit * i) * this.dimension,
           this.min[2] * this.dimension,
         );
         const max = new Vector3(
           this.max[0] * this.dimension,
-          subChunkUnit * (i + 1) * this.dimension,
+          (this.min[1] + subChunkUnit * (i + 1)) * this.dimension,
           this.max[2] * this.dimension,
         );
 
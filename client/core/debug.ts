@@ -411,10 +411,10 @@ class Debug {
     if (this.chunkHighlight.visible) {
       const { camChunkPosStr } = this.engine.world;
       const [cx, cz] = Helper.parseChunkName(camChunkPosStr, ' ');
-      const { chunkSize, maxHeight, dimension } = this.engine.world.options;
+      const { chunkSize, maxHeight, minHeight, dimension } = this.engine.world.options;
       this.chunkHighlight.position.set(
         (cx + 0.5) * chunkSize * dimension,
-        0.5 * maxHeight * dimension,
+        (minHeight + 0.5 * maxHeight) * dimension,
         (cz + 0.5) * chunkSize * dimension,
       );
     }
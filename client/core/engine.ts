@@ -294,6 +294,7 @@ class Engine extends EventEmitter {
       chunkSize,
       dimension,
       maxHeight,
+      minHeight = 0,
       subChunks,
       name,
       packs,
@@ -316,6 +317,7 @@ class Engine extends EventEmitter {
     world.chunkSize = chunkSize;
     world.dimension = dimension;
     world.maxHeight = maxHeight;
+    world.minHeight = minHeight;
     world.subChunks = subChunks;
   };
 
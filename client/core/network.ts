@@ -31,7 +31,7 @@ class Network {
     });
   }
 
-  connect = () => {
+  connect = async () => {
     const url = this.url.toString();
 
     if (this.server) {
@@ -48,6 +48,10 @@ class Network {
     socket.hash = '';
     socket.searchParams.set('world', this.engine.world.name);
 
+    // worlds split between servers are played on the shard owning their spawn
+    const shard = await this.fetchData('shard', { world: this.engine.world.name }).catch(() => null);
+    if (shard) socket.host = shard;
+
     const server = new WebSocket(socket.toString()) as CustomWebSocket;
     server.binaryType = 'arraybuffer';
     server.sendEvent = (event) => {
@@ -90,9 +94,12 @@ class Network {
   onInit = (event) => {
     const { world, player } = this.engine;
     const {
-      json: { id, time, tickSpeed, spawn, passables },
+      json: { id, time, tickSpeed, spawn, passables, dimensions },
     } = event;
 
+    // the server may be shaped differently than the one the page was loaded from
+    if (dimensions) world.setup(dimensions);
+
     player.id = id;
     player.teleport(spawn);
 
@@ -555,7 +555,7 @@ class Player {
 
   private updateLookBlock = () => {
     const { world, camera, registry } = this.engine;
-    const { dimension, maxHeight } = world.options;
+    const { dimension, maxHeight, minHeight } = world.options;
     const { reachDistance, lookBlockLerp } = this.options;
 
     const camDir = new Vector3();
@@ -581,7 +581,7 @@ class Player {
           return !!this.blockRay.intersectBox(tempBox, new Vector3());
         }
 
-        return y < maxHeight * dimension && world.getSolidityByWorld([Math.floor(x), Math.floor(y), Math.floor(z)]);
+        return y < (minHeight + maxHeight) * dimension && world.getSolidityByWorld([Math.floor(x), Math.floor(y), Math.floor(z)]);
       },
       [camPos.x, camPos.y, camPos.z],
       [camDir.x, camDir.y, camDir.z],
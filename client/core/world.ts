@@ -12,6 +12,7 @@ import { TargetBlock } from './player';
 type WorldOptionsType = {
   name?: string;
   maxHeight?: number;
+  minHeight?: number;
   chunkSize?: number;
   subChunks?: number;
   dimension?: number;
@@ -77,12 +78,24 @@ class World extends EventEmitter {
   }
 
   setup = (worldData: any) => {
-    const { chunk_size, dimension, max_height, sub_chunks } = worldData;
+    const { chunkSize, dimension, maxHeight, minHeight = 0, subChunks } = worldData;
+    const { options } = this;
 
-    this.options.chunkSize = chunk_size;
-    this.options.dimension = dimension;
-    this.options.maxHeight = max_height;
-    this.options.subChunks = sub_chunks;
+    const changed =
+      options.chunkSize !== chunkSize ||
+      options.dimension !== dimension ||
+      options.maxHeight !== maxHeight ||
+      options.minHeight !== minHeight ||
+      options.subChunks !== subChunks;
+
+    options.chunkSize = chunkSize;
+    options.dimension = dimension;
+    options.maxHeight = maxHeight;
+    options.minHeight = minHeight;
+    options.subChunks = subChunks;
+
+    // chunks of the old shape can't be reused
+    if (changed && this.chunks.size) this.reloadChunks();
   };
 
   tick = () => {
@@ -149,7 +162,10 @@ class World extends EventEmitter {
     const type = this.getVoxelByVoxel(vCoords);
     const block = this.engine.registry.getBlock(type);
     return (
-      vCoords[1] < this.options.maxHeight && !block?.isFluid && type !== 0 && !this.blockData.passables.includes(type)
+      vCoords[1] < this.options.minHeight + this.options.maxHeight &&
+      !block?.isFluid &&
+      type !== 0 &&
+      !this.blockData.passables.includes(type)
     );
   };
 
@@ -497,8 +513,8 @@ class World extends EventEmitter {
     let chunk = this.getChunkByCPos(coords);
 
     if (!chunk) {
-      const { chunkSize, subChunks, dimension, maxHeight } = this.options;
-      chunk = new Chunk(this.engine, coords, { size: chunkSize, subChunks, dimension, maxHeight });
+      const { chunkSize, subChunks, dimension, maxHeight, minHeight } = this.options;
+      chunk = new Chunk(this.engine, coords, { size: chunkSize, subChunks, dimension, maxHeight, minHeight });
       this.setChunk(chunk);
     }
 
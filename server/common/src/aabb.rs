@@ -106,7 +106,7 @@ impl Aabb {
     }
 
     pub fn touches(&self, aabb: &Aabb) -> bool {
-        let intersection = self.union(aabb);
+        let intersection = self.intersection(aabb);
         let zero = 0.0;
 
         if let Some(intersection) = intersection {
@@ -118,7 +118,16 @@ impl Aabb {
         }
     }
 
-    pub fn union(&self, aabb: &Aabb) -> Option<Self> {
+    /// The smallest box containing both boxes
+    pub fn union(&self, aabb: &Aabb) -> Self {
+        let min = self.base.min(&aabb.base);
+        let max = self.max.max(&aabb.max);
+
+        Aabb::new(&min, &max.sub(&min))
+    }
+
+    /// The box both boxes cover, if they intersect
+    pub fn intersection(&self, aabb: &Aabb) -> Option<Self> {
         if !self.intersects(aabb) {
             return None;
         }
@@ -136,6 +145,72 @@ impl Aabb {
         ))
     }
 
+    /// A copy grown by `margin` on every side, shrunk if negative
+    pub fn expanded(&self, margin: f32) -> Self {
+        let margin = Vec3(margin, margin, margin);
+
+        Aabb::new(
+            &self.base.sub(&margin),
+            &self.vec.add(&margin.scale(2.0)).max(&Vec3::default()),
+        )
+    }
+
+    /// Whether a point lies within the box or on its faces
+    pub fn contains_point(&self, point: &Vec3<f32>) -> bool {
+        (0..3).all(|axis| point[axis] >= self.base[axis] && point[axis] <= self.max[axis])
+    }
+
+    /// Where a ray first enters the box, as the multiple of `dir` from `origin`
+    ///
+    /// A ray starting inside the box hits it at 0, and one pointing away never hits it.
+    pub fn ray_intersect(&self, origin: &Vec3<f32>, dir: &Vec3<f32>) -> Option<f32> {
+        let mut near = 0.0_f32;
+        let mut far = f32::INFINITY;
+
+        for axis in 0..3 {
+            if approx_equals(&dir[axis], &0.0) {
+                if origin[axis] < self.base[axis] || origin[axis] > self.max[axis] {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let t0 = (self.base[axis] - origin[axis]) / dir[axis];
+            let t1 = (self.max[axis] - origin[axis]) / dir[axis];
+
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+
+    /// Every voxel the box overlaps, ordered by x, then z, then y
+    pub fn touching_voxels(&self) -> impl Iterator<Item = Vec3<i32>> {
+        let min = Vec3(
+            self.base.0.floor() as i32,
+            self.base.1.floor() as i32,
+            self.base.2.floor() as i32,
+        );
+        // a box of no size still sits in the voxel of its base
+        let max = Vec3(
+            (self.max.0.ceil() as i32).max(min.0 + 1),
+            (self.max.1.ceil() as i32).max(min.1 + 1),
+            (self.max.2.ceil() as i32).max(min.2 + 1),
+        );
+
+        let (x0, y0, z0, x1, y1, z1) = (min.0, min.1, min.2, max.0, max.1, max.2);
+
+        (x0..x1).flat_map(move |vx| {
+            (z0..z1).flat_map(move |vz| (y0..y1).map(move |vy| Vec3(vx, vy, vz)))
+        })
+    }
+
     pub fn copy(&mut self, other: &Aabb) -> &Self {
         for i in 0..3 {
             self.base[i] = other.base[i];
@@ -145,3 +220,41 @@ impl Aabb {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometry() {
+        let a = Aabb::new(&Vec3(0.0, 0.0, 0.0), &Vec3(1.0, 2.0, 1.0));
+        let b = Aabb::new(&Vec3(0.5, 1.0, 0.5), &Vec3(1.0, 1.0, 1.0));
+
+        assert!(a.intersects(&b));
+        assert_eq!(a.intersection(&b).unwrap().vec, Vec3(0.5, 1.0, 0.5));
+        assert_eq!(a.union(&b).vec, Vec3(1.5, 2.0, 1.5));
+        assert!(a
+            .intersection(&Aabb::new(&Vec3(3.0, 0.0, 0.0), &Vec3(1.0, 1.0, 1.0)))
+            .is_none());
+
+        let grown = a.expanded(0.5);
+        assert_eq!(grown.base, Vec3(-0.5, -0.5, -0.5));
+        assert_eq!(grown.max, Vec3(1.5, 2.5, 1.5));
+        assert!(grown.contains_point(&Vec3(1.5, 0.0, 0.0)));
+        assert!(!a.contains_point(&Vec3(1.5, 0.0, 0.0)));
+
+        let hit = a.ray_intersect(&Vec3(-2.0, 1.0, 0.5), &Vec3(1.0, 0.0, 0.0));
+        assert!(approx_equals(&hit.unwrap(), &2.0));
+        assert!(a
+            .ray_intersect(&Vec3(-2.0, 1.0, 0.5), &Vec3(-1.0, 0.0, 0.0))
+            .is_none());
+        let inside = a.ray_intersect(&Vec3(0.5, 1.0, 0.5), &Vec3(0.0, 1.0, 0.0));
+        assert!(approx_equals(&inside.unwrap(), &0.0));
+
+        let voxels = b.touching_voxels().collect::<Vec<_>>();
+        assert_eq!(voxels.len(), 4);
+        assert_eq!(voxels[0], Vec3(0, 1, 0));
+        assert_eq!(voxels[1], Vec3(0, 1, 1));
+        assert_eq!(a.touching_voxels().count(), 2);
+    }
+}
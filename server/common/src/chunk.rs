@@ -0,0 +1,213 @@
+use super::{
+    lights::{LightColor, Lights},
+    ndarray::{ndarray, Ndarray},
+    vec::{Vec2, Vec3},
+};
+
+/// Voxels of padding around a chunk's data on either horizontal side, mirroring its neighbors
+pub const DATA_PADDING: usize = 1;
+
+/// Offsets to the voxels sharing a face with a voxel
+pub const VOXEL_NEIGHBORS: [[i32; 3]; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+    [0, 1, 0],
+    [0, -1, 0],
+];
+
+/// Extract the bits in voxel that stores the voxel id
+#[inline]
+pub fn extract_id(voxel: u32) -> u32 {
+    voxel & 0xFFFF
+}
+
+/// Insert a voxel id into voxel value, keeping the rest of its bits
+#[inline]
+pub fn insert_id(voxel: u32, id: u32) -> u32 {
+    (voxel & 0xFFFF0000) | (id & 0xFFFF)
+}
+
+/// Voxels and lights of a chunk, padding included
+///
+/// Dimensions are `size + DATA_PADDING * 2` wide on either horizontal axis and `max_height`
+/// tall, the column starting at the world's `min_height`.
+#[derive(Clone, Debug)]
+pub struct ChunkData {
+    pub voxels: Ndarray<u32>,
+    pub lights: Ndarray<u32>,
+
+    /// Lowest voxel held, padding included
+    pub min: Vec3<i32>,
+
+    pub size: usize,
+    pub max_height: usize,
+}
+
+impl ChunkData {
+    pub fn new(coords: &Vec2<i32>, size: usize, max_height: usize, min_height: i32) -> Self {
+        let &Vec2(cx, cz) = coords;
+        let padding = DATA_PADDING as i32;
+
+        Self {
+            voxels: ndarray(Self::shape(size, max_height), 0),
+            lights: ndarray(Self::shape(size, max_height), 0),
+            min: Vec3(
+                cx * size as i32 - padding,
+                min_height,
+                cz * size as i32 - padding,
+            ),
+            size,
+            max_height,
+        }
+    }
+
+    /// Shape of the voxels and lights of a chunk
+    pub fn shape(size: usize, max_height: usize) -> Vec<usize> {
+        vec![size + DATA_PADDING * 2, max_height, size + DATA_PADDING * 2]
+    }
+
+    /// Whether a set of voxel coordinates is within the chunk, padding included
+    #[inline]
+    pub fn contains(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        let width = (self.size + DATA_PADDING * 2) as i32;
+        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
+
+        lx >= 0 && lx < width && ly >= 0 && ly < self.max_height as i32 && lz >= 0 && lz < width
+    }
+
+    /// Convert voxel coordinates to the coordinates within the chunk's data
+    #[inline]
+    pub fn to_local(&self, vx: i32, vy: i32, vz: i32) -> Vec3<i32> {
+        Vec3(vx, vy, vz).sub(&self.min)
+    }
+
+    /// Get the raw value of voxel
+    ///
+    /// Returns 0 if it's outside of the chunk.
+    pub fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        if !self.contains(vx, vy, vz) {
+            return 0;
+        }
+
+        self.voxels[&self.local(vx, vy, vz)]
+    }
+
+    /// Set the raw value of voxel
+    ///
+    /// Panics if the coordinates are outside of chunk.
+    pub fn set_raw_voxel(&mut self, vx: i32, vy: i32, vz: i32, value: u32) {
+        assert!(self.contains(vx, vy, vz));
+
+        let local = self.local(vx, vy, vz);
+        self.voxels[&local] = value;
+    }
+
+    /// Get a voxel type by voxel coordinates
+    ///
+    /// Returns 0 if it's outside of the chunk.
+    pub fn get_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        extract_id(self.get_raw_voxel(vx, vy, vz))
+    }
+
+    /// Get the raw light value of voxel, every color packed
+    ///
+    /// Returns 0 if it's outside of the chunk.
+    pub fn get_raw_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        if !self.contains(vx, vy, vz) {
+            return 0;
+        }
+
+        self.lights[&self.local(vx, vy, vz)]
+    }
+
+    /// Set the raw light value of voxel
+    ///
+    /// Panics if the coordinates are outside of chunk.
+    pub fn set_raw_light(&mut self, vx: i32, vy: i32, vz: i32, value: u32) {
+        assert!(self.contains(vx, vy, vz));
+
+        let local = self.local(vx, vy, vz);
+        self.lights[&local] = value;
+    }
+
+    /// Get the sunlight value for voxel by voxel coordinates
+    ///
+    /// Returns 0 if it's outside of the chunk.
+    pub fn get_sunlight(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        Lights::extract_sunlight(self.get_raw_light(vx, vy, vz))
+    }
+
+    /// Set the sunlight value for voxel by voxel coordinates
+    ///
+    /// Panics if it's outside of the chunk.
+    pub fn set_sunlight(&mut self, vx: i32, vy: i32, vz: i32, level: u32) {
+        let light = Lights::insert_sunlight(self.get_raw_light(vx, vy, vz), level);
+        self.set_raw_light(vx, vy, vz, light);
+    }
+
+    /// Get the torch light value for voxel by voxel coordinates by color
+    ///
+    /// Returns 0 if it's outside of the chunk.
+    pub fn get_torch_light(&self, vx: i32, vy: i32, vz: i32, color: &LightColor) -> u32 {
+        let light = self.get_raw_light(vx, vy, vz);
+
+        match color {
+            LightColor::Red => Lights::extract_red_light(light),
+            LightColor::Green => Lights::extract_green_light(light),
+            LightColor::Blue => Lights::extract_blue_light(light),
+            LightColor::None => panic!("Getting light of None"),
+        }
+    }
+
+    /// Set the torch light value for voxel by voxel coordinates by color
+    ///
+    /// Panics if it's outside of the chunk.
+    pub fn set_torch_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32, color: &LightColor) {
+        let light = self.get_raw_light(vx, vy, vz);
+
+        let light = match color {
+            LightColor::Red => Lights::insert_red_light(light, level),
+            LightColor::Green => Lights::insert_green_light(light, level),
+            LightColor::Blue => Lights::insert_blue_light(light, level),
+            LightColor::None => panic!("Setting light of None"),
+        };
+
+        self.set_raw_light(vx, vy, vz, light);
+    }
+
+    #[inline]
+    fn local(&self, vx: i32, vy: i32, vz: i32) -> [usize; 3] {
+        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
+        [lx as usize, ly as usize, lz as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn padding() {
+        let mut chunk = ChunkData::new(&Vec2(1, -1), 4, 8, -4);
+        assert_eq!(chunk.min, Vec3(3, -4, -5));
+
+        // the voxels beside the chunk are held as padding, the ones past it aren't
+        assert!(chunk.contains(3, -4, -5));
+        assert!(chunk.contains(8, 3, 0));
+        assert!(!chunk.contains(9, 0, 0));
+        assert!(!chunk.contains(4, 4, -2));
+
+        chunk.set_raw_voxel(4, 0, -2, insert_id(0xABCD_0000, 7));
+        assert_eq!(chunk.get_voxel(4, 0, -2), 7);
+        assert_eq!(chunk.get_raw_voxel(4, 0, -2) >> 16, 0xABCD);
+        assert_eq!(chunk.get_voxel(9, 0, 0), 0);
+
+        chunk.set_sunlight(4, 0, -2, 15);
+        chunk.set_torch_light(4, 0, -2, 9, &LightColor::Green);
+        assert_eq!(chunk.get_sunlight(4, 0, -2), 15);
+        assert_eq!(chunk.get_torch_light(4, 0, -2, &LightColor::Green), 9);
+        assert_eq!(chunk.get_torch_light(4, 0, -2, &LightColor::Red), 0);
+    }
+}
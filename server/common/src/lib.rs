@@ -1,6 +1,9 @@
 pub mod aabb;
+pub mod chunk;
+pub mod lights;
 pub mod math;
 pub mod ndarray;
+#[cfg(feature = "noise")]
 pub mod noise;
 pub mod quaternion;
 pub mod types;
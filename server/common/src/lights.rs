@@ -0,0 +1,460 @@
+use std::collections::VecDeque;
+
+use super::{chunk::VOXEL_NEIGHBORS, types::Block, vec::Vec3};
+
+/// Node of a light propagation queue
+#[derive(Debug)]
+pub struct LightNode {
+    pub voxel: Vec3<i32>,
+    pub level: u32,
+}
+
+/// Enum of light colors
+///
+/// `None` being sunlight
+pub enum LightColor {
+    None,
+    Red,
+    Green,
+    Blue,
+}
+
+/// The voxels light floods through, across whichever chunks hold them
+///
+/// Lights set on a voxel are expected to be set on the padding of the chunks beside it too.
+pub trait LightSpace {
+    /// Lowest voxel y of the world
+    fn min_height(&self) -> i32;
+
+    /// Voxel y right above the highest of the world
+    fn top(&self) -> i32;
+
+    fn max_light_level(&self) -> u32;
+
+    /// Get a block type from a voxel coordinate
+    fn get_block_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> &Block;
+
+    fn get_sunlight(&self, vx: i32, vy: i32, vz: i32) -> u32;
+
+    fn set_sunlight(&mut self, vx: i32, vy: i32, vz: i32, level: u32);
+
+    fn get_torch_light(&self, vx: i32, vy: i32, vz: i32, color: &LightColor) -> u32;
+
+    fn set_torch_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32, color: &LightColor);
+
+    /// Called for every voxel whose light changed, such as to save the chunks holding it
+    fn light_changed(&mut self, _vx: i32, _vy: i32, _vz: i32) {}
+}
+
+pub struct Lights;
+
+impl Lights {
+    /// Levels a light of a color loses entering a block, besides the one lost per block
+    #[inline]
+    pub fn attenuation(block: &Block, color: &LightColor) -> u32 {
+        block.light_attenuation
+            + match color {
+                LightColor::None => 0,
+                LightColor::Red => block.light_filter[0],
+                LightColor::Green => block.light_filter[1],
+                LightColor::Blue => block.light_filter[2],
+            }
+    }
+
+    /// Whether swapping one transparent block for another changes how light passes through
+    #[inline]
+    pub fn filters_differ(a: &Block, b: &Block) -> bool {
+        a.light_attenuation != b.light_attenuation || a.light_filter != b.light_filter
+    }
+
+    /// Extract the bits in light that stores sunlight
+    #[inline]
+    pub fn extract_sunlight(light: u32) -> u32 {
+        (light >> 12) & 0xF
+    }
+
+    /// Insert a value into the bits in light that stores sunlight
+    #[inline]
+    pub fn insert_sunlight(light: u32, level: u32) -> u32 {
+        (light & 0xFFF) | (level << 12)
+    }
+
+    /// Extract the bits in light that stores red light
+    #[inline]
+    pub fn extract_red_light(light: u32) -> u32 {
+        (light >> 8) & 0xF
+    }
+
+    /// Insert a value into the bits in light that stores red light
+    #[inline]
+    pub fn insert_red_light(light: u32, level: u32) -> u32 {
+        (light & 0xF0FF) | (level << 8)
+    }
+
+    /// Extract the bits in light that stores green light
+    #[inline]
+    pub fn extract_green_light(light: u32) -> u32 {
+        (light >> 4) & 0xF
+    }
+
+    /// Insert a value into the bits in light that stores green light
+    #[inline]
+    pub fn insert_green_light(light: u32, level: u32) -> u32 {
+        (light & 0xFF0F) | (level << 4)
+    }
+
+    /// Extract the bits in light that stores blue light
+    #[inline]
+    pub fn extract_blue_light(light: u32) -> u32 {
+        light & 0xF
+    }
+
+    /// Insert a value into the bits in light that stores blue light
+    #[inline]
+    pub fn insert_blue_light(light: u32, level: u32) -> u32 {
+        (light & 0xFFF0) | (level)
+    }
+
+    /// Remove a light source. Steps:
+    ///
+    /// 1. Remove the existing lights in a flood-fill fashion
+    /// 2. If external light source exists, flood fill them back
+    pub fn global_remove_light<S: LightSpace + ?Sized>(
+        space: &mut S,
+        vx: i32,
+        vy: i32,
+        vz: i32,
+        is_sunlight: bool,
+        color: &LightColor,
+    ) {
+        let min_height = space.min_height();
+        let top = space.top();
+        let max_light_level = space.max_light_level();
+
+        let mut fill = VecDeque::<LightNode>::new();
+        let mut queue = VecDeque::<LightNode>::new();
+
+        queue.push_back(LightNode {
+            voxel: Vec3(vx, vy, vz),
+            level: if is_sunlight {
+                space.get_sunlight(vx, vy, vz)
+            } else {
+                space.get_torch_light(vx, vy, vz, color)
+            },
+        });
+
+        if is_sunlight {
+            space.set_sunlight(vx, vy, vz, 0);
+        } else {
+            space.set_torch_light(vx, vy, vz, 0, color);
+        }
+
+        space.light_changed(vx, vy, vz);
+
+        while !queue.is_empty() {
+            let LightNode { voxel, level } = queue.pop_front().unwrap();
+            let Vec3(vx, vy, vz) = voxel;
+
+            for [ox, oy, oz] in VOXEL_NEIGHBORS.iter() {
+                let nvy = vy + oy;
+
+                if nvy < min_height || nvy >= top {
+                    continue;
+                }
+
+                let nvx = vx + ox;
+                let nvz = vz + oz;
+                let n_voxel = Vec3(nvx, nvy, nvz);
+
+                let nl = if is_sunlight {
+                    space.get_sunlight(nvx, nvy, nvz)
+                } else {
+                    space.get_torch_light(nvx, nvy, nvz, color)
+                };
+
+                if nl == 0 {
+                    continue;
+                }
+
+                // if level is less, or if sunlight is propagating downwards without stopping
+                if nl < level
+                    || (is_sunlight
+                        && *oy == -1
+                        && level == max_light_level
+                        && nl == max_light_level)
+                {
+                    queue.push_back(LightNode {
+                        voxel: n_voxel,
+                        level: nl,
+                    });
+
+                    if is_sunlight {
+                        space.set_sunlight(nvx, nvy, nvz, 0);
+                    } else {
+                        space.set_torch_light(nvx, nvy, nvz, 0, color);
+                    }
+
+                    space.light_changed(nvx, nvy, nvz);
+                } else if nl >= level && (!is_sunlight || *oy != -1 || nl > level) {
+                    fill.push_back(LightNode {
+                        voxel: n_voxel,
+                        level: nl,
+                    })
+                }
+            }
+        }
+
+        Lights::global_flood_light(space, fill, is_sunlight, color);
+    }
+
+    /// Flood fill light from a queue
+    pub fn global_flood_light<S: LightSpace + ?Sized>(
+        space: &mut S,
+        mut queue: VecDeque<LightNode>,
+        is_sunlight: bool,
+        color: &LightColor,
+    ) {
+        let min_height = space.min_height();
+        let top = space.top();
+        let max_light_level = space.max_light_level();
+
+        while !queue.is_empty() {
+            let LightNode { voxel, level } = queue.pop_front().unwrap();
+            let Vec3(vx, vy, vz) = voxel;
+
+            for [ox, oy, oz] in VOXEL_NEIGHBORS.iter() {
+                let nvy = vy + oy;
+
+                if nvy < min_height || nvy >= top {
+                    continue;
+                }
+
+                let nvx = vx + ox;
+                let nvz = vz + oz;
+                let sd = is_sunlight && *oy == -1 && level == max_light_level;
+                let n_voxel = Vec3(nvx, nvy, nvz);
+                let block_type = space.get_block_by_voxel(nvx, nvy, nvz);
+                let nl = level.saturating_sub(
+                    if sd { 0 } else { 1 } + Lights::attenuation(block_type, color),
+                );
+
+                if !block_type.is_transparent
+                    || (if is_sunlight {
+                        space.get_sunlight(nvx, nvy, nvz)
+                    } else {
+                        space.get_torch_light(nvx, nvy, nvz, color)
+                    } >= nl)
+                {
+                    continue;
+                }
+
+                if is_sunlight {
+                    space.set_sunlight(nvx, nvy, nvz, nl);
+                } else {
+                    space.set_torch_light(nvx, nvy, nvz, nl, color);
+                }
+
+                space.light_changed(nvx, nvy, nvz);
+
+                queue.push_back(LightNode {
+                    voxel: n_voxel,
+                    level: nl,
+                })
+            }
+        }
+    }
+
+    /// Update the lights around a voxel whose block was just swapped from `current` to
+    /// `updated`, its voxel set already
+    pub fn update_light<S: LightSpace + ?Sized>(
+        space: &mut S,
+        vx: i32,
+        vy: i32,
+        vz: i32,
+        current: &Block,
+        updated: &Block,
+    ) {
+        const RED: LightColor = LightColor::Red;
+        const GREEN: LightColor = LightColor::Green;
+        const BLUE: LightColor = LightColor::Blue;
+        const NONE: LightColor = LightColor::None;
+        const COLORS: [LightColor; 3] = [RED, GREEN, BLUE];
+
+        let min_height = space.min_height();
+        let top = space.top();
+        let max_light_level = space.max_light_level();
+
+        let voxel = Vec3(vx, vy, vz);
+
+        // light passes through both, but dims differently, such as air filled with water
+        let refilters = current.is_transparent
+            && updated.is_transparent
+            && Lights::filters_differ(current, updated);
+        let blocks_light = current.is_transparent && (!updated.is_transparent || refilters);
+        let lets_light = updated.is_transparent && (!current.is_transparent || refilters);
+
+        // the light the old block emitted goes with it
+        for (color, &level) in COLORS.iter().zip(current.emission().iter()) {
+            if level > 0 {
+                Lights::global_remove_light(space, vx, vy, vz, false, color);
+            }
+        }
+
+        if blocks_light {
+            // remove light if solid block is placed, or one dimming it differently
+            if space.get_sunlight(vx, vy, vz) != 0 {
+                Lights::global_remove_light(space, vx, vy, vz, true, &NONE);
+            }
+
+            for color in COLORS.iter() {
+                if space.get_torch_light(vx, vy, vz, color) != 0 {
+                    Lights::global_remove_light(space, vx, vy, vz, false, color);
+                }
+            }
+        }
+
+        // the light the new block emits floods out from it
+        for (color, &level) in COLORS.iter().zip(updated.emission().iter()) {
+            if level > 0 {
+                space.set_torch_light(vx, vy, vz, level, color);
+
+                Lights::global_flood_light(
+                    space,
+                    VecDeque::from(vec![LightNode {
+                        voxel: voxel.clone(),
+                        level,
+                    }]),
+                    false,
+                    color,
+                );
+            }
+        }
+
+        if lets_light {
+            // solid block removed, or the light around flooding back in through the new one
+            [false, true].iter().for_each(|&is_sunlight| {
+                let mut queue = VecDeque::<LightNode>::new();
+                let mut red_queue = VecDeque::<LightNode>::new();
+                let mut green_queue = VecDeque::<LightNode>::new();
+                let mut blue_queue = VecDeque::<LightNode>::new();
+
+                if is_sunlight && vy == top - 1 {
+                    // propagate sunlight down
+                    space.set_sunlight(vx, vy, vz, max_light_level);
+                    queue.push_back(LightNode {
+                        voxel: voxel.clone(),
+                        level: max_light_level,
+                    })
+                } else {
+                    for [ox, oy, oz] in VOXEL_NEIGHBORS.iter() {
+                        let nvy = vy + oy;
+
+                        if nvy < min_height || nvy >= top {
+                            return;
+                        }
+
+                        let nvx = vx + ox;
+                        let nvz = vz + oz;
+                        let n_voxel = Vec3(nvx, nvy, nvz);
+                        let &Block {
+                            is_light,
+                            is_transparent,
+                            ..
+                        } = space.get_block_by_voxel(nvx, nvy, nvz);
+
+                        // need propagation after solid block removed
+                        if is_sunlight {
+                            let level = space.get_sunlight(nvx, nvy, nvz);
+                            if level != 0 && is_transparent {
+                                queue.push_back(LightNode {
+                                    voxel: n_voxel,
+                                    level,
+                                })
+                            }
+                        } else {
+                            let red_level = space.get_torch_light(nvx, nvy, nvz, &RED);
+                            if red_level != 0 && (is_transparent || is_light) {
+                                red_queue.push_back(LightNode {
+                                    voxel: n_voxel.clone(),
+                                    level: red_level,
+                                })
+                            }
+
+                            let green_level = space.get_torch_light(nvx, nvy, nvz, &GREEN);
+                            if green_level != 0 && (is_transparent || is_light) {
+                                green_queue.push_back(LightNode {
+                                    voxel: n_voxel.clone(),
+                                    level: green_level,
+                                })
+                            }
+
+                            let blue_level = space.get_torch_light(nvx, nvy, nvz, &BLUE);
+                            if blue_level != 0 && (is_transparent || is_light) {
+                                blue_queue.push_back(LightNode {
+                                    voxel: n_voxel,
+                                    level: blue_level,
+                                })
+                            }
+                        }
+                    }
+                }
+
+                if is_sunlight {
+                    Lights::global_flood_light(space, queue, is_sunlight, &NONE);
+                } else {
+                    Lights::global_flood_light(space, red_queue, is_sunlight, &RED);
+                    Lights::global_flood_light(space, green_queue, is_sunlight, &GREEN);
+                    Lights::global_flood_light(space, blue_queue, is_sunlight, &BLUE);
+                }
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(extra: &str) -> Block {
+        serde_json::from_str(&format!(
+            r#"{{
+                "name": "Test", "rotatable": false, "yRotatable": false, "isBlock": true,
+                "isEmpty": false, "isFluid": false, "isPlant": false, "isSolid": false,
+                "isTransparent": true, "isPlantable": false, "transparentStandalone": false{}
+            }}"#,
+            extra
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn attenuation() {
+        let glass = block("");
+        let water = block(r#", "lightAttenuation": 2"#);
+        let red_glass = block(r#", "lightFilter": [0, 3, 3]"#);
+
+        assert_eq!(Lights::attenuation(&glass, &LightColor::None), 0);
+        assert_eq!(Lights::attenuation(&water, &LightColor::None), 2);
+        assert_eq!(Lights::attenuation(&water, &LightColor::Blue), 2);
+        assert_eq!(Lights::attenuation(&red_glass, &LightColor::Red), 0);
+        assert_eq!(Lights::attenuation(&red_glass, &LightColor::Green), 3);
+        assert_eq!(Lights::attenuation(&red_glass, &LightColor::None), 0);
+
+        assert!(Lights::filters_differ(&glass, &water));
+        assert!(Lights::filters_differ(&glass, &red_glass));
+        assert!(!Lights::filters_differ(&glass, &block("")));
+    }
+
+    #[test]
+    fn emission() {
+        let mut lamp = block(r#", "lightLevel": 12, "blueLightLevel": 15"#);
+        lamp.normalize_emission();
+        assert!(lamp.is_light);
+        assert_eq!(lamp.emission(), [12, 12, 15]);
+
+        let mut glass = block(r#", "isLight": true"#);
+        glass.normalize_emission();
+        assert!(!glass.is_light);
+        assert_eq!(glass.emission(), [0, 0, 0]);
+    }
+}
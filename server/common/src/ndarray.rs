@@ -1,7 +1,30 @@
-use std::ops::{Index, IndexMut};
+use std::{
+    iter::StepBy,
+    ops::{Index, IndexMut},
+    slice::Iter,
+};
 
 use num::Num;
 
+use serde::{
+    de::{self, Deserializer},
+    ser::{SerializeStruct, Serializer},
+    Deserialize, Serialize,
+};
+
+/// Strides of a row-major array, the last axis varying fastest
+fn strides(shape: &[usize]) -> Vec<usize> {
+    let mut stride = vec![0; shape.len()];
+
+    let mut s = 1;
+    for i in (0..shape.len()).rev() {
+        stride[i] = s;
+        s *= shape[i];
+    }
+
+    stride
+}
+
 #[derive(Debug, Clone)]
 pub struct Ndarray<T>
 where
@@ -17,20 +40,11 @@ where
     T: Num + Clone,
 {
     pub fn new(shape: Vec<usize>, default: T) -> Self {
-        let d = shape.len();
-
         let mut size = 1;
         shape.iter().for_each(|x| size *= x);
 
         let data = vec![default; size];
-
-        let mut stride = vec![0; d];
-
-        let mut s = 1;
-        for i in (0..d).rev() {
-            stride[i] = s;
-            s *= shape[i];
-        }
+        let stride = strides(&shape);
 
         Self {
             data,
@@ -50,23 +64,165 @@ where
     pub fn contains(&self, coords: &[usize]) -> bool {
         !coords.iter().zip(self.shape.iter()).any(|(&a, &b)| a >= b)
     }
+
+    /// Replace every value with what `f` makes of it
+    pub fn map_in_place<F: FnMut(&T) -> T>(&mut self, mut f: F) {
+        self.data.iter_mut().for_each(|value| *value = f(value));
+    }
+
+    /// A borrowed box of a 3D array, `shape` long on each axis from `min`
+    pub fn view(&self, min: [usize; 3], shape: [usize; 3]) -> NdarrayView<'_, T> {
+        assert!(
+            (0..3).all(|axis| min[axis] + shape[axis] <= self.shape[axis]),
+            "View out of bounds of Ndarray."
+        );
+
+        NdarrayView {
+            array: self,
+            min,
+            shape,
+        }
+    }
+
+    /// The values from the bottom to the top of a column of a 3D array
+    pub fn column(&self, x: usize, z: usize) -> StepBy<Iter<'_, T>> {
+        let start = self.index(&[x, 0, z]);
+        let end = self.index(&[x, self.shape[1] - 1, z]);
+
+        self.data[start..=end].iter().step_by(self.stride[1])
+    }
+
+    /// Every column of a 3D array with its coordinates, ordered by x then z
+    pub fn columns(&self) -> impl Iterator<Item = (usize, usize, StepBy<Iter<'_, T>>)> + '_ {
+        let (width, depth) = (self.shape[0], self.shape[2]);
+
+        (0..width).flat_map(move |x| (0..depth).map(move |z| (x, z, self.column(x, z))))
+    }
+}
+
+/// A box within a 3D `Ndarray`, indexed relative to its minimum corner
+pub struct NdarrayView<'a, T>
+where
+    T: Num + Clone,
+{
+    array: &'a Ndarray<T>,
+    pub min: [usize; 3],
+    pub shape: [usize; 3],
+}
+
+impl<'a, T: Num + Clone> NdarrayView<'a, T> {
+    /// Copy the box into an array of its own
+    pub fn to_ndarray(&self) -> Ndarray<T> {
+        let mut array = Ndarray::new(self.shape.to_vec(), T::zero());
+
+        for x in 0..self.shape[0] {
+            for y in 0..self.shape[1] {
+                for z in 0..self.shape[2] {
+                    array[(x, y, z)] = self[(x, y, z)].clone();
+                }
+            }
+        }
+
+        array
+    }
+}
+
+impl<'a, T: Num + Clone> Index<(usize, usize, usize)> for NdarrayView<'a, T> {
+    type Output = T;
+
+    fn index(&self, (x, y, z): (usize, usize, usize)) -> &Self::Output {
+        assert!(
+            x < self.shape[0] && y < self.shape[1] && z < self.shape[2],
+            "Index out of bounds of NdarrayView."
+        );
+
+        &self.array[(self.min[0] + x, self.min[1] + y, self.min[2] + z)]
+    }
+}
+
+/// Coordinates an `Ndarray` can be indexed with, `&[x, y, z]` or `(x, y, z)`
+pub trait NdIndex {
+    fn offset(&self, stride: &[usize]) -> usize;
+}
+
+impl NdIndex for &[usize] {
+    fn offset(&self, stride: &[usize]) -> usize {
+        self.iter().zip(stride.iter()).map(|(a, b)| a * b).sum()
+    }
+}
+
+impl<const N: usize> NdIndex for &[usize; N] {
+    fn offset(&self, stride: &[usize]) -> usize {
+        self.iter().zip(stride.iter()).map(|(a, b)| a * b).sum()
+    }
 }
 
-impl<T: Num + Clone> Index<&[usize]> for Ndarray<T> {
+impl NdIndex for (usize, usize) {
+    fn offset(&self, stride: &[usize]) -> usize {
+        self.0 * stride[0] + self.1 * stride[1]
+    }
+}
+
+impl NdIndex for (usize, usize, usize) {
+    fn offset(&self, stride: &[usize]) -> usize {
+        self.0 * stride[0] + self.1 * stride[1] + self.2 * stride[2]
+    }
+}
+
+impl<T: Num + Clone, I: NdIndex> Index<I> for Ndarray<T> {
     type Output = T;
 
-    fn index(&self, index: &[usize]) -> &Self::Output {
-        &self.data.get(self.index(index)).unwrap()
+    fn index(&self, index: I) -> &Self::Output {
+        self.data.get(index.offset(&self.stride)).unwrap()
     }
 }
 
-impl<T: Num + Clone> IndexMut<&[usize]> for Ndarray<T> {
-    fn index_mut(&mut self, index: &[usize]) -> &mut Self::Output {
-        let index = self.index(index);
+impl<T: Num + Clone, I: NdIndex> IndexMut<I> for Ndarray<T> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        let index = index.offset(&self.stride);
         self.data.get_mut(index).unwrap()
     }
 }
 
+impl<T: Num + Clone + Serialize> Serialize for Ndarray<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Ndarray", 2)?;
+        state.serialize_field("shape", &self.shape)?;
+        state.serialize_field("data", &self.data)?;
+        state.end()
+    }
+}
+
+impl<'de, T: Num + Clone + Deserialize<'de>> Deserialize<'de> for Ndarray<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Raw<T> {
+            shape: Vec<usize>,
+            data: Vec<T>,
+        }
+
+        let Raw { shape, data } = Raw::deserialize(deserializer)?;
+
+        let size = shape.iter().product::<usize>();
+        if size != data.len() {
+            return Err(de::Error::custom(format!(
+                "Ndarray of shape {:?} needs {} values, found {}.",
+                shape,
+                size,
+                data.len()
+            )));
+        }
+
+        let stride = strides(&shape);
+
+        Ok(Self {
+            data,
+            shape,
+            stride,
+        })
+    }
+}
+
 pub fn ndarray<T: Num + Clone>(shape: Vec<usize>, default: T) -> Ndarray<T> {
     Ndarray::new(shape, default)
 }
@@ -85,4 +241,29 @@ mod tests {
         data[&[1, 2, 3]] = 5;
         assert_eq!(data[&[1, 2, 3]], 5);
     }
+
+    #[test]
+    fn views_and_serde() {
+        let mut data = ndarray(vec![2, 3, 2], 0);
+        data[(1, 2, 1)] = 3;
+        data[(1, 0, 1)] = 1;
+        data.map_in_place(|v| v * 2);
+
+        assert_eq!(
+            data.column(1, 1).copied().collect::<Vec<_>>(),
+            vec![2, 0, 6]
+        );
+        assert_eq!(data.columns().count(), 4);
+
+        let view = data.view([1, 1, 1], [1, 2, 1]);
+        assert_eq!(view[(0, 1, 0)], 6);
+        assert_eq!(view.to_ndarray().data, vec![0, 6]);
+
+        let json = serde_json::to_string(&data).unwrap();
+        let parsed: Ndarray<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.data, data.data);
+        assert_eq!(parsed.stride, data.stride);
+
+        assert!(serde_json::from_str::<Ndarray<i32>>(r#"{"shape":[2,2],"data":[1]}"#).is_err());
+    }
 }
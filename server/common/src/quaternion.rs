@@ -1,2 +1,25 @@
+use crate::vec::Vec3;
+
 #[derive(Debug, PartialEq, Default, Clone)]
 pub struct Quaternion(pub f32, pub f32, pub f32, pub f32);
+
+impl Quaternion {
+    /// Create a rotation from a yaw around the y-axis followed by a pitch around the x-axis
+    pub fn from_yaw_pitch(yaw: f32, pitch: f32) -> Self {
+        let (sy, cy) = (yaw / 2.0).sin_cos();
+        let (sp, cp) = (pitch / 2.0).sin_cos();
+
+        Self(cy * sp, sy * cp, -sy * sp, cy * cp)
+    }
+
+    /// Direction the rotation looks towards, rotating -z like a camera
+    pub fn forward(&self) -> Vec3<f32> {
+        let Quaternion(x, y, z, w) = *self;
+
+        Vec3(
+            -2.0 * (w * y + x * z),
+            2.0 * (w * x - y * z),
+            2.0 * (x * x + y * y) - 1.0,
+        )
+    }
+}
@@ -24,27 +24,107 @@ pub struct Block {
     pub is_block: bool,
     pub is_empty: bool,
     pub is_fluid: bool,
+    #[serde(default)]
     pub is_light: bool,
     pub is_plant: bool,
     pub is_solid: bool,
     pub is_transparent: bool,
+    #[serde(default)]
     pub red_light_level: u32,
+    #[serde(default)]
     pub green_light_level: u32,
+    #[serde(default)]
     pub blue_light_level: u32,
+
+    /// White light emitted, shorthand for equal red, green and blue levels
+    #[serde(default)]
+    pub light_level: u32,
     pub is_plantable: bool,
     pub transparent_standalone: bool,
 
+    /// Whether minecarts follow this block
+    #[serde(default)]
+    pub is_rail: bool,
+
+    /// Whether bodies overlapping this block can climb it, such as ladders and vines
+    #[serde(default)]
+    pub is_climbable: bool,
+
+    /// Multiplier on the horizontal speed of bodies on or in this block
+    #[serde(default = "default_multiplier")]
+    pub speed_multiplier: f32,
+
+    /// Multiplier on the friction of bodies standing on this block
+    #[serde(default = "default_multiplier")]
+    pub friction_multiplier: f32,
+
+    /// Light levels lost entering this block on top of the one lost per block travelled,
+    /// sunlight and torch light alike, such as 2 for water
+    #[serde(default)]
+    pub light_attenuation: u32,
+
+    /// Further levels of red, green and blue torch light lost entering this block, tinting
+    /// the light that passes through it, such as `[0, 3, 3]` for red glass
+    #[serde(default)]
+    pub light_filter: [u32; 3],
+
+    /// How well this block mines when held as a tool, checked by loot table conditions
+    #[serde(default)]
+    pub tool_tier: u32,
+
     #[serde(default)]
     pub textures: HashMap<String, String>,
+
+    /// Sounds by event, such as `step` or `break`, as paths in the resource pack's `sounds`
+    #[serde(default)]
+    pub sounds: HashMap<String, String>,
+}
+
+impl Block {
+    /// Whether bodies pass through the block, as they do plants
+    pub fn is_walkable(&self) -> bool {
+        !self.is_solid || self.is_plant
+    }
+
+    /// Torch light levels emitted, red, green and blue, none unless the block is a light
+    pub fn emission(&self) -> [u32; 3] {
+        if self.is_light {
+            [
+                self.red_light_level,
+                self.green_light_level,
+                self.blue_light_level,
+            ]
+        } else {
+            [0; 3]
+        }
+    }
+
+    /// Fold `light_level` into the colored levels, making any block emitting light a light
+    pub fn normalize_emission(&mut self) {
+        if self.light_level > 0 {
+            self.red_light_level = self.red_light_level.max(self.light_level);
+            self.green_light_level = self.green_light_level.max(self.light_level);
+            self.blue_light_level = self.blue_light_level.max(self.light_level);
+        }
+
+        self.is_light =
+            self.red_light_level > 0 || self.green_light_level > 0 || self.blue_light_level > 0;
+    }
 }
 
-#[derive(Debug, Clone)]
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MeshType {
     pub positions: Vec<f32>,
     pub indices: Vec<i32>,
     pub uvs: Vec<f32>,
     pub aos: Vec<i32>,
     pub lights: Vec<i32>,
+    /// Per-quad centers `[x, y, z]`, only filled for translucent geometry
+    pub centroids: Vec<f32>,
 }
 
 pub type GetVoxel<'a> = &'a dyn Fn(i32, i32, i32) -> bool;
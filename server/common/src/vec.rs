@@ -1,10 +1,10 @@
 use num::{cast, Float, Num};
 
-use std::ops::{Index, IndexMut};
+use std::ops::{self, Index, IndexMut};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Eq, PartialEq, Clone, Default, Hash)]
+#[derive(Debug, Eq, PartialEq, Clone, Default, Hash, Serialize, Deserialize)]
 pub struct Vec2<T>(pub T, pub T);
 
 impl<T: Copy + 'static> Vec2<T> {
@@ -63,6 +63,18 @@ where
     pub fn from_arr(arr: [T; 3]) -> Self {
         Vec3(arr[0], arr[1], arr[2])
     }
+
+    pub fn dot(&self, other: &Self) -> T {
+        self.0 * other.0 + self.1 * other.1 + self.2 * other.2
+    }
+
+    pub fn cross(&self, other: &Self) -> Self {
+        Vec3(
+            self.1 * other.2 - self.2 * other.1,
+            self.2 * other.0 - self.0 * other.2,
+            self.0 * other.1 - self.1 * other.0,
+        )
+    }
 }
 
 impl<T> Vec3<T>
@@ -70,7 +82,16 @@ where
     T: Float,
 {
     pub fn len(&self) -> T {
-        (self.0 * self.0 + self.1 * self.1 + self.2 * self.2).sqrt()
+        self.dot(self).sqrt()
+    }
+
+    /// The vector scaled to a length of 1, or left as is if it has no length
+    pub fn normalize(&self) -> Self {
+        let len = self.len();
+        if len > T::zero() {
+            return self * (T::one() / len);
+        }
+        self.to_owned()
     }
 
     pub fn max(&self, other: &Self) -> Self {
@@ -91,6 +112,15 @@ where
 }
 
 impl Vec3<f32> {
+    /// The voxel a point lies in
+    pub fn floor(&self) -> Vec3<i32> {
+        Vec3(
+            self.0.floor() as i32,
+            self.1.floor() as i32,
+            self.2.floor() as i32,
+        )
+    }
+
     pub fn rotate_y(&self, origin: &Self, angle: f32) -> Self {
         let ox = origin[0];
         let oz = origin[2];
@@ -105,16 +135,6 @@ impl Vec3<f32> {
         // perform rotation and translate to correct position
         Self(ox + pz * sc + px * cc, self[1], oz + pz * cc - px * sc)
     }
-
-    pub fn normalize(&self) -> Self {
-        let Self(x, y, z) = self;
-        let len = x * x + y * y + z * z;
-        if len > 0.0 {
-            let len = 1.0 / len.sqrt();
-            return Self(self.0 * len, self.1 * len, self.2 * len);
-        }
-        self.to_owned()
-    }
 }
 
 impl<T: Num + Clone> Index<usize> for Vec3<T> {
@@ -156,3 +176,140 @@ impl<T: Num + Clone> From<Vec<T>> for Vec3<T> {
         Self(x, y, z)
     }
 }
+
+// the inherent `add` and `sub` above stay callable by reference wherever these operator
+// traits aren't imported by name
+
+impl<T: Num + Copy> ops::Add for Vec3<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Vec3(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl<T: Num + Copy> ops::Add for &Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn add(self, other: Self) -> Vec3<T> {
+        Vec3(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl<T: Num + Copy> ops::Sub for Vec3<T> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Vec3(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
+}
+
+impl<T: Num + Copy> ops::Sub for &Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn sub(self, other: Self) -> Vec3<T> {
+        Vec3(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
+}
+
+impl<T: Num + Copy> ops::Mul<T> for Vec3<T> {
+    type Output = Self;
+
+    fn mul(self, scale: T) -> Self {
+        Vec3(self.0 * scale, self.1 * scale, self.2 * scale)
+    }
+}
+
+impl<T: Num + Copy> ops::Mul<T> for &Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn mul(self, scale: T) -> Vec3<T> {
+        Vec3(self.0 * scale, self.1 * scale, self.2 * scale)
+    }
+}
+
+impl<T: Num + Copy + ops::Neg<Output = T>> ops::Neg for Vec3<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Vec3(-self.0, -self.1, -self.2)
+    }
+}
+
+impl<T: Num + Copy> ops::AddAssign for Vec3<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = Vec3(self.0 + other.0, self.1 + other.1, self.2 + other.2);
+    }
+}
+
+impl<T: Num + Copy> ops::SubAssign for Vec3<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = Vec3(self.0 - other.0, self.1 - other.1, self.2 - other.2);
+    }
+}
+
+impl<T> From<[T; 3]> for Vec3<T> {
+    fn from([x, y, z]: [T; 3]) -> Self {
+        Self(x, y, z)
+    }
+}
+
+impl<T> From<(T, T, T)> for Vec3<T> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Self(x, y, z)
+    }
+}
+
+impl<T> From<Vec3<T>> for [T; 3] {
+    fn from(Vec3(x, y, z): Vec3<T>) -> Self {
+        [x, y, z]
+    }
+}
+
+impl<T> From<Vec3<T>> for (T, T, T) {
+    fn from(Vec3(x, y, z): Vec3<T>) -> Self {
+        (x, y, z)
+    }
+}
+
+impl From<Vec3<i32>> for Vec3<f32> {
+    fn from(Vec3(x, y, z): Vec3<i32>) -> Self {
+        Self(x as f32, y as f32, z as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operators() {
+        let a = Vec3(1, 2, 3);
+        let b = Vec3(4, 5, 6);
+
+        assert_eq!(&a + &b, Vec3(5, 7, 9));
+        assert_eq!(b.clone() - a.clone(), Vec3(3, 3, 3));
+        assert_eq!(&a * 2, Vec3(2, 4, 6));
+        assert_eq!(-a.clone(), Vec3(-1, -2, -3));
+        assert_eq!(a.dot(&b), 32);
+        assert_eq!(Vec3(1, 0, 0).cross(&Vec3(0, 1, 0)), Vec3(0, 0, 1));
+        assert_eq!(Vec3(3.0, 0.0, 4.0).len(), 5.0);
+        let unit = Vec3(3.0_f32, 0.0, 4.0).normalize();
+        assert!((unit.0 - 0.6).abs() < 1e-6 && (unit.2 - 0.8).abs() < 1e-6);
+        assert_eq!(Vec3(0.0, 0.0, 0.0).normalize(), Vec3(0.0, 0.0, 0.0));
+
+        let mut c = a.clone();
+        c += b;
+        c -= Vec3(1, 1, 1);
+        assert_eq!(c, Vec3(4, 6, 8));
+
+        let tuple: (i32, i32, i32) = a.clone().into();
+        assert_eq!(tuple, (1, 2, 3));
+        let from_tuple: Vec3<i32> = (1, 2, 3).into();
+        assert_eq!(Vec3::from_arr([1, 2, 3]), from_tuple);
+
+        let float: Vec3<f32> = a.into();
+        assert_eq!(float.floor(), Vec3(1, 2, 3));
+        assert_eq!(Vec3(-0.5, 0.5, 1.5).floor(), Vec3(-1, 0, 1));
+    }
+}
@@ -1,7 +1,82 @@
+use std::fs;
+
+/// Serde derives of every generated type, allowing the self-describing JSON wire format
+const SERDE_DERIVE: &str =
+    "#[derive(serde::Serialize, serde::Deserialize)] #[serde(rename_all = \"camelCase\")]";
+
+/// Fully-qualified paths of the messages and enums declared in a proto file, nested ones included
+///
+/// Only the braces of `message` and `enum` blocks are tracked, which is all `messages.proto` has.
+fn declared_types(proto: &str) -> (Vec<String>, Vec<String>) {
+    let mut package = String::new();
+    let mut scopes: Vec<Option<String>> = vec![];
+    let (mut messages, mut enums) = (vec![], vec![]);
+
+    for line in proto.lines().map(str::trim) {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+
+        match words.as_slice() {
+            ["package", name, ..] => package = format!(".{}", name.trim_end_matches(';')),
+            [kind @ ("message" | "enum"), name, ..] => {
+                let parent = scopes
+                    .iter()
+                    .rev()
+                    .find_map(|scope| scope.clone())
+                    .unwrap_or_else(|| package.clone());
+                let path = format!("{}.{}", parent, name.trim_end_matches('{'));
+
+                if *kind == "message" {
+                    messages.push(path.clone());
+                } else {
+                    enums.push(path.clone());
+                }
+
+                scopes.push(Some(path));
+            }
+            _ if line.ends_with('{') => scopes.push(None),
+            _ => {}
+        }
+
+        if line.starts_with('}') {
+            scopes.pop();
+        }
+    }
+
+    (messages, enums)
+}
+
 fn main() {
-    prost_build::compile_protos(
-        &["../../protocol/messages.proto"],
-        &["../../protocol/", "../core/"],
-    )
-    .unwrap();
+    let proto = "../../protocol/messages.proto";
+    println!("cargo:rerun-if-changed={}", proto);
+
+    let (messages, enums) = declared_types(&fs::read_to_string(proto).unwrap());
+
+    let mut config = prost_build::Config::new();
+
+    // paths match exactly before matching by prefix, so every type is listed for nested enums
+    // not to take the attributes of the message holding them
+    for message in &messages {
+        // fields left out of a JSON message take their protobuf defaults
+        config.type_attribute(message, format!("{} #[serde(default)]", SERDE_DERIVE));
+    }
+    for enumeration in &enums {
+        config.type_attribute(enumeration, SERDE_DERIVE);
+    }
+
+    config
+        .compile_protos(&[proto], &["../../protocol/", "../core/"])
+        .unwrap();
+
+    #[cfg(feature = "ffi")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+
+        cbindgen::Builder::new()
+            .with_crate(&crate_dir)
+            .with_language(cbindgen::Language::C)
+            .with_include_guard("MINEJS_H")
+            .generate()
+            .expect("Unable to generate the C header.")
+            .write_to_file(format!("{}/include/minejs.h", crate_dir));
+    }
 }
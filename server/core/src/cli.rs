@@ -0,0 +1,202 @@
+use log::info;
+
+use crate::engine::{
+    config::Configs,
+    pregen::pregenerate,
+    sweep_recorder::replay_file,
+    trim::{trim_world, TrimRules},
+    verify::{verify_world, VerifyOptions},
+};
+
+/// Pregenerate the saved worlds, `pregen --radius N [--threads K] [--world NAME]`
+///
+/// Generating, lighting and saving the chunks around spawn before launch, so the first players
+/// don't wait on them. Run again after an interruption to pick up where it left off.
+fn pregen(args: &[String]) {
+    let mut radius = None;
+    let mut threads = None;
+    let mut world = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("Missing a value for {}.", arg))
+        };
+
+        match arg.as_str() {
+            "--radius" => radius = Some(value().parse::<i32>().expect("Invalid radius.")),
+            "--threads" => threads = Some(value().parse::<usize>().expect("Invalid threads.")),
+            "--world" => world = Some(value().to_owned()),
+            _ => panic!("Unknown pregen option: {}", arg),
+        }
+    }
+
+    let radius = radius.expect("Usage: pregen --radius N [--threads K] [--world NAME]");
+    let (configs, registry) = Configs::load_worlds("assets/metadata/worlds.json");
+
+    for (name, (_, config)) in configs {
+        if world.as_ref().map_or(!config.save, |world| world != &name) {
+            continue;
+        }
+
+        info!("Pregenerating \"{}\" within {} chunks.", name, radius);
+
+        let saved = pregenerate(
+            &name,
+            config,
+            registry.clone(),
+            radius,
+            threads,
+            &mut |done, total| info!("{}/{} chunks of \"{}\"", done, total, name),
+        );
+
+        info!("Saved {} chunks of \"{}\".", saved, name);
+    }
+}
+
+/// Trim the saved worlds, `trim [--radius N] [--before YYYY-MM-DD] [--world NAME]`
+///
+/// Deleting the stored chunks players never edited that are outside the radius or weren't saved
+/// since the date, for the server to stay stopped while it runs.
+fn trim(args: &[String]) {
+    let mut rules = TrimRules::default();
+    let mut world = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("Missing a value for {}.", arg))
+        };
+
+        match arg.as_str() {
+            "--radius" => rules.radius = Some(value().parse::<i32>().expect("Invalid radius.")),
+            "--before" => {
+                let date = chrono::NaiveDate::parse_from_str(value(), "%Y-%m-%d")
+                    .expect("Invalid date, expected YYYY-MM-DD.");
+                rules.before = Some(date.and_hms(0, 0, 0).timestamp_millis() as u64);
+            }
+            "--world" => world = Some(value().to_owned()),
+            _ => panic!("Unknown trim option: {}", arg),
+        }
+    }
+
+    if rules.radius.is_none() && rules.before.is_none() {
+        panic!("Usage: trim [--radius N] [--before YYYY-MM-DD] [--world NAME]");
+    }
+
+    let (configs, _) = Configs::load_worlds("assets/metadata/worlds.json");
+
+    for (name, (_, config)) in configs {
+        if world.as_ref().is_some_and(|world| world != &name) {
+            continue;
+        }
+
+        if let Some(report) = trim_world(&name, &config, &rules) {
+            info!(
+                "Removed {} chunks of \"{}\", kept {}.",
+                report.removed, name, report.kept
+            );
+        }
+    }
+}
+
+/// Verify the saved worlds, `verify-world [--fix] [--quarantine] [--world NAME]`
+///
+/// Checking that every stored chunk loads and holds sane data, logging the bad ones. `--fix`
+/// repairs the data of the chunks that load, `--quarantine` sets aside the ones that don't.
+fn verify(args: &[String]) {
+    let mut options = VerifyOptions::default();
+    let mut world = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fix" => options.fix = true,
+            "--quarantine" => options.quarantine = true,
+            "--world" => {
+                world = Some(
+                    args.next()
+                        .unwrap_or_else(|| panic!("Missing a value for {}.", arg))
+                        .to_owned(),
+                )
+            }
+            _ => panic!("Unknown verify-world option: {}", arg),
+        }
+    }
+
+    let (configs, registry) = Configs::load_worlds("assets/metadata/worlds.json");
+
+    for (name, (_, config)) in configs {
+        if world.as_ref().is_some_and(|world| world != &name) {
+            continue;
+        }
+
+        let report = verify_world(&name, &config, &registry, &options, &mut |chunk, issue| {
+            info!("Chunk \"{}\" of \"{}\": {}", chunk, name, issue)
+        });
+
+        if let Some(report) = report {
+            info!(
+                "Verified \"{}\": {} healthy, {} damaged ({} repaired), {} corrupt ({} quarantined).",
+                name,
+                report.healthy,
+                report.damaged,
+                report.repaired,
+                report.corrupt,
+                report.quarantined
+            );
+        }
+    }
+}
+
+/// Replay recorded sweeps against the current physics, `replay-sweeps FILE...`
+///
+/// Logs the sweeps that no longer come out the same, exiting with an error if any don't.
+fn replay_sweeps(files: &[String]) {
+    if files.is_empty() {
+        panic!("Usage: replay-sweeps FILE...");
+    }
+
+    let mut changed = 0;
+
+    for file in files {
+        match replay_file(std::path::Path::new(file)) {
+            Ok(differences) => {
+                differences
+                    .iter()
+                    .for_each(|difference| info!("{}: {}", file, difference));
+                changed += differences.len();
+            }
+            Err(e) => panic!("Unable to replay {}: {}", file, e),
+        }
+    }
+
+    info!(
+        "Replayed {} recordings, {} sweeps changed.",
+        files.len(),
+        changed
+    );
+
+    if changed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Run the maintenance tool named by the first argument, `false` if there isn't one
+///
+/// The tools work on the saved worlds directly, for the server to stay stopped while they run.
+pub fn run(args: &[String]) -> bool {
+    let tool: fn(&[String]) = match args.first().map(String::as_str) {
+        Some("pregen") => pregen,
+        Some("trim") => trim,
+        Some("verify-world") => verify,
+        Some("replay-sweeps") => replay_sweeps,
+        _ => return false,
+    };
+
+    tool(&args[1..]);
+
+    true
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use specs::{Component, VecStorage};
+
+use super::brain::BrainOptions;
+
+/// Stats of an entity that modifiers build on, resolved every tick by the attributes system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AttributeKind {
+    MaxHealth,
+    /// Top walking speed, in blocks per second
+    MovementSpeed,
+    SprintSpeed,
+    JumpImpulse,
+    /// Damage of a melee attack
+    AttackDamage,
+}
+
+/// How a modifier changes an attribute
+///
+/// Modifiers stack by kind of operation, giving
+/// `(base + adds) * (1 + sum of base multipliers) * product of (1 + total multipliers)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    /// Add to the base value
+    Add,
+    /// Add a fraction of the base value with the adds, such as 0.2 for 20% faster
+    MultiplyBase,
+    /// Multiply the whole value by one plus the amount, compounding with the others
+    MultiplyTotal,
+}
+
+/// A change to an attribute from a source, such as an effect, armor or a plugin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeModifier {
+    /// Who added the modifier, replacing its own modifiers when setting them again
+    pub source: String,
+    pub kind: AttributeKind,
+    pub operation: Operation,
+    pub amount: f32,
+}
+
+impl AttributeModifier {
+    pub fn new(source: &str, kind: AttributeKind, operation: Operation, amount: f32) -> Self {
+        Self {
+            source: source.to_owned(),
+            kind,
+            operation,
+            amount,
+        }
+    }
+}
+
+/// Attributes of an entity, base values along with the modifiers on them
+#[derive(Debug, Default, Component)]
+#[storage(VecStorage)]
+pub struct Attributes {
+    base: HashMap<AttributeKind, f32>,
+    modifiers: Vec<AttributeModifier>,
+    /// Values as of the last resolve
+    values: HashMap<AttributeKind, f32>,
+    dirty: bool,
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attributes based on movement options, such as for an entity that didn't have any
+    pub fn of_brain(options: &BrainOptions) -> Self {
+        Self::new()
+            .with_base(AttributeKind::MovementSpeed, options.max_speed)
+            .with_base(AttributeKind::SprintSpeed, options.sprint_speed)
+            .with_base(AttributeKind::JumpImpulse, options.jump_impulse)
+    }
+
+    pub fn with_base(mut self, kind: AttributeKind, value: f32) -> Self {
+        self.set_base(kind, value);
+        self
+    }
+
+    pub fn set_base(&mut self, kind: AttributeKind, value: f32) {
+        self.base.insert(kind, value);
+        self.dirty = true;
+    }
+
+    pub fn base(&self, kind: AttributeKind) -> Option<f32> {
+        self.base.get(&kind).copied()
+    }
+
+    /// Replace the modifiers of a source, removing them if there are none
+    pub fn set_modifiers(&mut self, source: &str, modifiers: Vec<AttributeModifier>) {
+        let count = self.modifiers.len();
+        self.modifiers.retain(|modifier| modifier.source != source);
+
+        self.dirty |= self.modifiers.len() != count || !modifiers.is_empty();
+        self.modifiers
+            .extend(modifiers.into_iter().map(|modifier| AttributeModifier {
+                source: source.to_owned(),
+                ..modifier
+            }));
+    }
+
+    pub fn modifiers(&self) -> &[AttributeModifier] {
+        &self.modifiers
+    }
+
+    /// Value of an attribute as of the last resolve, none if it has no base
+    pub fn value(&self, kind: AttributeKind) -> Option<f32> {
+        self.values.get(&kind).copied()
+    }
+
+    /// Work out the value of an attribute from its base and the modifiers on it
+    pub fn compute(&self, kind: AttributeKind) -> Option<f32> {
+        let base = self.base(kind)?;
+
+        let (mut added, mut multiplier, mut total) = (0.0, 1.0, 1.0);
+        for modifier in self.modifiers.iter().filter(|m| m.kind == kind) {
+            match modifier.operation {
+                Operation::Add => added += modifier.amount,
+                Operation::MultiplyBase => multiplier += modifier.amount,
+                Operation::MultiplyTotal => total *= 1.0 + modifier.amount,
+            }
+        }
+
+        Some(((base + added) * multiplier * total).max(0.0))
+    }
+
+    /// Work out the values again if the bases or modifiers changed, returning those that differ
+    pub fn resolve(&mut self) -> Vec<(AttributeKind, f32)> {
+        if !std::mem::replace(&mut self.dirty, false) {
+            return vec![];
+        }
+
+        let mut changed = vec![];
+
+        for &kind in self.base.keys() {
+            let value = self.compute(kind).unwrap();
+            if self.values.get(&kind) != Some(&value) {
+                changed.push((kind, value));
+            }
+        }
+
+        for &(kind, value) in changed.iter() {
+            self.values.insert(kind, value);
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacking() {
+        use AttributeKind::*;
+
+        let mut attributes = Attributes::new()
+            .with_base(MovementSpeed, 6.0)
+            .with_base(AttackDamage, 2.0);
+        assert_eq!(attributes.resolve().len(), 2);
+        assert_eq!(attributes.value(MovementSpeed), Some(6.0));
+        assert_eq!(attributes.value(MaxHealth), None);
+
+        attributes.set_modifiers(
+            "effects",
+            vec![AttributeModifier::new(
+                "effects",
+                MovementSpeed,
+                Operation::MultiplyBase,
+                0.5,
+            )],
+        );
+        attributes.set_modifiers(
+            "armor",
+            vec![
+                AttributeModifier::new("armor", MovementSpeed, Operation::Add, 2.0),
+                AttributeModifier::new("armor", MovementSpeed, Operation::MultiplyBase, -0.25),
+                AttributeModifier::new("armor", MovementSpeed, Operation::MultiplyTotal, 1.0),
+            ],
+        );
+
+        // (6 + 2) * (1 + 0.5 - 0.25) * 2
+        assert_eq!(attributes.resolve(), vec![(MovementSpeed, 20.0)]);
+        assert!(attributes.resolve().is_empty());
+
+        // setting a source's modifiers again replaces them instead of stacking
+        attributes.set_modifiers(
+            "effects",
+            vec![AttributeModifier::new(
+                "effects",
+                MovementSpeed,
+                Operation::MultiplyBase,
+                0.25,
+            )],
+        );
+        attributes.set_modifiers("armor", vec![]);
+        attributes.resolve();
+        assert_eq!(attributes.value(MovementSpeed), Some(7.5));
+    }
+}
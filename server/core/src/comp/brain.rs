@@ -9,6 +9,7 @@ use super::rigidbody::RigidBody;
 pub struct BrainState {
     pub heading: f32,
     pub running: bool,
+    pub sprinting: bool,
     pub jumping: bool,
 
     pub jump_count: u32,
@@ -21,6 +22,7 @@ impl Default for BrainState {
         Self {
             heading: 0.0,
             running: false,
+            sprinting: false,
             jumping: false,
 
             jump_count: 0,
@@ -34,6 +36,8 @@ impl Default for BrainState {
 #[serde(rename_all = "camelCase")]
 pub struct BrainOptions {
     pub max_speed: f32,
+    #[serde(default = "default_sprint_speed")]
+    pub sprint_speed: f32,
     pub move_force: f32,
     pub responsiveness: f32,
     pub running_friction: f32,
@@ -50,6 +54,7 @@ impl Default for BrainOptions {
     fn default() -> Self {
         Self {
             max_speed: 6.0,
+            sprint_speed: default_sprint_speed(),
             move_force: 12.0,
             responsiveness: 120.0,
             running_friction: 0.4,
@@ -64,6 +69,10 @@ impl Default for BrainOptions {
     }
 }
 
+fn default_sprint_speed() -> f32 {
+    8.0
+}
+
 #[derive(Default, Component)]
 #[storage(VecStorage)]
 pub struct Brain {
@@ -94,6 +103,11 @@ impl Brain {
         self.state.running = true;
     }
 
+    /// Mark entity to walk at its sprinting speed or not
+    pub fn sprint(&mut self, sprinting: bool) {
+        self.state.sprinting = sprinting;
+    }
+
     /// Mark entity to start jumping
     pub fn jump(&mut self) {
         self.state.jumping = true;
@@ -158,9 +172,12 @@ impl Brain {
         let m = &mut self.temp_vec;
         let push = &mut self.temp_vec2;
         if self.state.running {
-            let speed = self.options.max_speed;
-            // todo: add crouch/sprint modifiers if needed
-            // if (state.sprint) speed *= state.sprintMoveMult;
+            let speed = if self.state.sprinting {
+                self.options.sprint_speed
+            } else {
+                self.options.max_speed
+            };
+            // todo: add crouch modifiers if needed
             // if (state.crouch) speed *= state.crouchMoveMult;
             m.set(0.0, 0.0, speed);
 
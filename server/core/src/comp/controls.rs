@@ -0,0 +1,38 @@
+use specs::{Component, VecStorage};
+
+/// Latest movement input of a player, used in server-authoritative movement.
+///
+/// `yaw` is in radians, where `0` faces the positive z-axis.
+#[derive(Default, Component)]
+#[storage(VecStorage)]
+pub struct Controls {
+    pub sequence: u32,
+
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+    pub jump: bool,
+    pub sprint: bool,
+
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Controls {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the walking heading, or `None` if no direction is held
+    pub fn heading(&self) -> Option<f32> {
+        let forward = self.forward as i32 - self.backward as i32;
+        let strafe = self.left as i32 - self.right as i32;
+
+        if forward == 0 && strafe == 0 {
+            return None;
+        }
+
+        Some(self.yaw + (strafe as f32).atan2(forward as f32))
+    }
+}
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, VecStorage};
+
+use super::attributes::{AttributeKind, AttributeModifier, Operation};
+
+/// Source of the attribute modifiers of effects
+pub const EFFECTS_SOURCE: &str = "effects";
+
+/// Speed multiplier gained per amplifier level of `Speed`
+const SPEED_PER_LEVEL: f32 = 0.2;
+
+/// Jump impulse multiplier gained per amplifier level of `JumpBoost`
+const JUMP_PER_LEVEL: f32 = 0.25;
+
+/// Seconds between poison damages at amplifier 0, halved by every level
+const POISON_INTERVAL: f32 = 1.25;
+
+/// Damage dealt by a single poison hit
+pub const POISON_DAMAGE: f32 = 1.0;
+
+/// Kinds of status effects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EffectType {
+    Speed,
+    JumpBoost,
+    Poison,
+    /// Broken blocks drop as they are, see loot tables
+    SilkTouch,
+}
+
+impl EffectType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EffectType::Speed => "speed",
+            EffectType::JumpBoost => "jumpBoost",
+            EffectType::Poison => "poison",
+            EffectType::SilkTouch => "silkTouch",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "speed" => Some(EffectType::Speed),
+            "jumpBoost" | "jump_boost" => Some(EffectType::JumpBoost),
+            "poison" => Some(EffectType::Poison),
+            "silkTouch" | "silk_touch" => Some(EffectType::SilkTouch),
+            _ => None,
+        }
+    }
+}
+
+/// A single active effect, `amplifier` 0 being the first level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Effect {
+    pub kind: EffectType,
+    pub amplifier: u32,
+    /// Seconds left
+    pub remaining: f32,
+}
+
+/// Status effects on an entity, applied every tick by the effects system
+#[derive(Debug, Default, Component)]
+#[storage(VecStorage)]
+pub struct Effects {
+    pub active: Vec<Effect>,
+    poison_timer: f32,
+    dirty: bool,
+}
+
+impl Effects {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an effect, keeping the stronger or else the longer one of the same kind
+    pub fn add(&mut self, kind: EffectType, amplifier: u32, duration: f32) {
+        let effect = Effect {
+            kind,
+            amplifier,
+            remaining: duration,
+        };
+
+        match self.active.iter_mut().find(|e| e.kind == kind) {
+            Some(existing) => {
+                if amplifier > existing.amplifier
+                    || (amplifier == existing.amplifier && duration > existing.remaining)
+                {
+                    *existing = effect;
+                    self.dirty = true;
+                }
+            }
+            None => {
+                self.active.push(effect);
+                self.dirty = true;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, kind: EffectType) {
+        let count = self.active.len();
+        self.active.retain(|e| e.kind != kind);
+        self.dirty |= self.active.len() != count;
+    }
+
+    pub fn clear(&mut self) {
+        self.dirty |= !self.active.is_empty();
+        self.active.clear();
+    }
+
+    pub fn get(&self, kind: EffectType) -> Option<&Effect> {
+        self.active.iter().find(|e| e.kind == kind)
+    }
+
+    fn level(&self, kind: EffectType) -> Option<f32> {
+        self.get(kind).map(|e| e.amplifier as f32 + 1.0)
+    }
+
+    /// Multiplier applied to the max speed
+    pub fn speed_multiplier(&self) -> f32 {
+        1.0 + self.level(EffectType::Speed).unwrap_or(0.0) * SPEED_PER_LEVEL
+    }
+
+    /// Multiplier applied to the jump impulse
+    pub fn jump_multiplier(&self) -> f32 {
+        1.0 + self.level(EffectType::JumpBoost).unwrap_or(0.0) * JUMP_PER_LEVEL
+    }
+
+    /// Attribute modifiers of the active effects, set on the entity's attributes
+    pub fn modifiers(&self) -> Vec<AttributeModifier> {
+        let mut modifiers = vec![];
+        let mut add = |kind, amount| {
+            modifiers.push(AttributeModifier::new(
+                EFFECTS_SOURCE,
+                kind,
+                Operation::MultiplyBase,
+                amount,
+            ))
+        };
+
+        if let Some(level) = self.level(EffectType::Speed) {
+            add(AttributeKind::MovementSpeed, level * SPEED_PER_LEVEL);
+            add(AttributeKind::SprintSpeed, level * SPEED_PER_LEVEL);
+        }
+
+        if let Some(level) = self.level(EffectType::JumpBoost) {
+            add(AttributeKind::JumpImpulse, level * JUMP_PER_LEVEL);
+        }
+
+        modifiers
+    }
+
+    /// Count down the effects by `delta` seconds, dropping the expired ones
+    ///
+    /// Returns the poison damage dealt during this tick.
+    pub fn tick(&mut self, delta: f32) -> f32 {
+        let mut damage = 0.0;
+
+        if let Some(effect) = self.get(EffectType::Poison) {
+            let interval = POISON_INTERVAL / 2f32.powi(effect.amplifier.min(8) as i32);
+
+            self.poison_timer += delta;
+            while self.poison_timer >= interval {
+                self.poison_timer -= interval;
+                damage += POISON_DAMAGE;
+            }
+        } else {
+            self.poison_timer = 0.0;
+        }
+
+        self.active.iter_mut().for_each(|e| e.remaining -= delta);
+        self.remove_expired();
+
+        damage
+    }
+
+    fn remove_expired(&mut self) {
+        let count = self.active.len();
+        self.active.retain(|e| e.remaining > 0.0);
+        self.dirty |= self.active.len() != count;
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Check and clear whether the effects have changed since the last call
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects() {
+        let mut effects = Effects::new();
+
+        effects.add(EffectType::Speed, 1, 2.0);
+        effects.add(EffectType::Speed, 0, 10.0);
+        effects.add(EffectType::Poison, 0, 3.0);
+        assert!(effects.take_dirty());
+        assert!((effects.speed_multiplier() - 1.4).abs() < f32::EPSILON);
+        assert_eq!(effects.modifiers().len(), 2);
+
+        assert_eq!(effects.tick(1.3), POISON_DAMAGE);
+        assert!(!effects.take_dirty());
+
+        effects.tick(1.0);
+        assert!(effects.take_dirty());
+        assert_eq!(effects.get(EffectType::Speed), None);
+        assert!(effects.get(EffectType::Poison).is_some());
+    }
+}
@@ -0,0 +1,41 @@
+use specs::{Component, VecStorage};
+
+/// Health players spawn with
+pub const PLAYER_MAX_HEALTH: f32 = 20.0;
+
+/// Damage of a melee attack
+pub const ATTACK_DAMAGE: f32 = 2.0;
+
+/// How far players can hit, in blocks
+pub const ATTACK_REACH: f32 = 4.0;
+
+/// Ticks a player waits after landing an attack before the next one lands
+pub const ATTACK_COOLDOWN: i32 = 10;
+
+/// Health points of an entity, never above `max`
+#[derive(Debug, Clone, Component)]
+#[storage(VecStorage)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Take damage, not going below `min`
+    pub fn damage(&mut self, amount: f32, min: f32) {
+        self.current = (self.current - amount).max(min.min(self.current));
+    }
+
+    /// Heal up to the maximum health
+    pub fn heal(&mut self, amount: f32) {
+        self.current = (self.current + amount).min(self.max);
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+
+use server_common::vec::Vec3;
+use specs::{Component, VecStorage};
+
+/// How long positions are kept around, in milliseconds
+pub const HISTORY_DURATION: u64 = 1000;
+
+/// A short history of an entity's positions, used to rewind interaction
+/// checks by a client's round-trip time.
+///
+/// Stores `(unix milliseconds, position)` pairs, oldest first.
+#[derive(Default, Component)]
+#[storage(VecStorage)]
+pub struct History(pub VecDeque<(u64, Vec3<f32>)>);
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a position at `time`, dropping the samples that are too old
+    pub fn record(&mut self, time: u64, position: Vec3<f32>) {
+        self.0.push_back((time, position));
+
+        while let Some((oldest, _)) = self.0.front() {
+            if oldest + HISTORY_DURATION >= time {
+                break;
+            }
+
+            self.0.pop_front();
+        }
+    }
+
+    /// Get the interpolated position at `time`
+    ///
+    /// Clamps to the oldest or newest sample if `time` is out of range.
+    pub fn position_at(&self, time: u64) -> Option<Vec3<f32>> {
+        let index = self.0.iter().position(|(t, _)| *t >= time);
+
+        match index {
+            None => self.0.back().map(|(_, p)| p.clone()),
+            Some(0) => self.0.front().map(|(_, p)| p.clone()),
+            Some(i) => {
+                let (t0, p0) = &self.0[i - 1];
+                let (t1, p1) = &self.0[i];
+                let ratio = (time - t0) as f32 / (t1 - t0) as f32;
+
+                Some(p0.scale_and_add(&p1.sub(p0), ratio))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewind() {
+        let mut history = History::new();
+
+        history.record(1000, Vec3(0.0, 0.0, 0.0));
+        history.record(1100, Vec3(10.0, 0.0, 0.0));
+
+        assert_eq!(history.position_at(1050), Some(Vec3(5.0, 0.0, 0.0)));
+        assert_eq!(history.position_at(500), Some(Vec3(0.0, 0.0, 0.0)));
+        assert_eq!(history.position_at(2000), Some(Vec3(10.0, 0.0, 0.0)));
+
+        // samples older than the history duration get dropped
+        history.record(2100, Vec3(20.0, 0.0, 0.0));
+        assert_eq!(history.0.len(), 2);
+    }
+}
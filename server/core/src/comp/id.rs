@@ -1,4 +1,6 @@
-use specs::{Component, VecStorage};
+use specs::{Component, Entity, VecStorage};
+
+use super::uuid::Uuid;
 
 /// Id component for player entities
 #[derive(Default, Component)]
@@ -10,3 +12,12 @@ impl Id {
         Self(id)
     }
 }
+
+/// Id of an entity in the protocol, players are known by their player id and others by UUID
+pub fn protocol_id(entity: Entity, id: Option<&Id>, uuid: Option<&Uuid>) -> String {
+    match (id, uuid) {
+        (Some(id), _) => id.0.to_string(),
+        (None, Some(uuid)) => uuid.0.to_owned(),
+        (None, None) => entity.id().to_string(),
+    }
+}
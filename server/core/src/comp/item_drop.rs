@@ -0,0 +1,8 @@
+use specs::{Component, VecStorage};
+
+use crate::engine::items::ItemStack;
+
+/// Marks an entity as a stack of items lying in the world, such as the loot of a broken block
+#[derive(Debug, Clone, Component)]
+#[storage(VecStorage)]
+pub struct ItemDrop(pub ItemStack);
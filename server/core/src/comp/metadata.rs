@@ -0,0 +1,51 @@
+use hashbrown::HashSet;
+use specs::{Component, VecStorage};
+
+use std::collections::HashMap;
+
+/// Extensible key-value data that describes how a peer looks, such as
+/// `skin`, `held`, `crouching` or `sprinting`.
+///
+/// Keeps track of changed keys so only the differences are sent.
+#[derive(Default, Component)]
+#[storage(VecStorage)]
+pub struct Metadata {
+    pub data: HashMap<String, String>,
+    dirty: HashSet<String>,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a value, marking the key as dirty if it has changed
+    pub fn set(&mut self, key: &str, value: &str) {
+        if self.data.get(key).map(|v| v.as_str()) == Some(value) {
+            return;
+        }
+
+        self.data.insert(key.to_owned(), value.to_owned());
+        self.dirty.insert(key.to_owned());
+    }
+
+    /// Set multiple values at once
+    pub fn extend(&mut self, values: HashMap<String, String>) {
+        values.iter().for_each(|(key, value)| self.set(key, value));
+    }
+
+    /// Check if any of the values have changed since the last diff
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Take the changed values, clearing the dirty flags
+    pub fn take_dirty(&mut self) -> HashMap<String, String> {
+        let data = &self.data;
+
+        self.dirty
+            .drain()
+            .filter_map(|key| data.get(&key).map(|value| (key, value.to_owned())))
+            .collect()
+    }
+}
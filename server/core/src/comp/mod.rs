@@ -1,10 +1,22 @@
+pub mod attributes;
 pub mod brain;
+pub mod controls;
 pub mod curr_chunk;
+pub mod effects;
 pub mod etype;
+pub mod health;
+pub mod history;
 pub mod id;
+pub mod item_drop;
+pub mod metadata;
+pub mod mount;
+pub mod movement;
 pub mod name;
 pub mod rigidbody;
 pub mod rotation;
 pub mod target;
+pub mod trader;
+pub mod uuid;
+pub mod vehicle;
 pub mod view_radius;
 pub mod walk_towards;
@@ -0,0 +1,21 @@
+use server_common::vec::Vec3;
+use specs::{Component, Entity, HashMapStorage};
+
+/// How far players can reach to mount an entity, in blocks
+pub const MOUNT_REACH: f32 = 4.0;
+
+/// Attaches an entity to the entity it rides
+///
+/// The rider's own physics are skipped, it is kept at `offset` from the mount's position instead.
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+pub struct Mounted {
+    pub mount: Entity,
+    pub offset: Vec3<f32>,
+}
+
+impl Mounted {
+    pub fn new(mount: Entity, offset: Vec3<f32>) -> Self {
+        Self { mount, offset }
+    }
+}
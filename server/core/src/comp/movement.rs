@@ -0,0 +1,98 @@
+use specs::{Component, VecStorage};
+
+use crate::engine::physics::{GlideInput, Physics};
+
+use super::{controls::Controls, rigidbody::RigidBody};
+
+/// Pose of a body no mode is moving, walking or swimming as usual
+pub const DEFAULT_POSE: &str = "standing";
+
+/// A way of moving that takes over from walking, such as gliding
+pub trait MovementMode: Send + Sync {
+    /// Pose peers are shown while the mode moves the body, set as the `pose` metadata
+    fn pose(&self) -> &'static str;
+
+    /// Steer the body ahead of its next iteration, false once the mode is over
+    fn operate(
+        &mut self,
+        physics: &Physics,
+        body: &mut RigidBody,
+        controls: &Controls,
+        dt: f32,
+    ) -> bool;
+}
+
+/// Glide along the look direction until landing
+#[derive(Debug, Default)]
+pub struct Gliding;
+
+impl MovementMode for Gliding {
+    fn pose(&self) -> &'static str {
+        "gliding"
+    }
+
+    fn operate(
+        &mut self,
+        physics: &Physics,
+        body: &mut RigidBody,
+        controls: &Controls,
+        dt: f32,
+    ) -> bool {
+        let input = GlideInput {
+            yaw: controls.yaw,
+            pitch: controls.pitch,
+        };
+
+        physics.glide(body, dt, &input)
+    }
+}
+
+/// Movement mode of a player's body, walking if none
+#[derive(Default, Component)]
+#[storage(VecStorage)]
+pub struct Movement {
+    mode: Option<Box<dyn MovementMode>>,
+}
+
+impl Movement {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move by a mode from the next tick on, replacing the current one
+    pub fn set(&mut self, mode: Box<dyn MovementMode>) {
+        self.mode = Some(mode);
+    }
+
+    /// Go back to walking, false if no mode was moving the body
+    pub fn clear(&mut self) -> bool {
+        self.mode.take().is_some()
+    }
+
+    pub fn pose(&self) -> &'static str {
+        self.mode.as_ref().map_or(DEFAULT_POSE, |mode| mode.pose())
+    }
+
+    /// Let the mode steer the body, going back to walking once it's over
+    ///
+    /// Returns whether the mode moved the body, leaving walking out this tick.
+    pub fn operate(
+        &mut self,
+        physics: &Physics,
+        body: &mut RigidBody,
+        controls: &Controls,
+        dt: f32,
+    ) -> bool {
+        let mode = match &mut self.mode {
+            Some(mode) => mode,
+            None => return false,
+        };
+
+        if mode.operate(physics, body, controls, dt) {
+            return true;
+        }
+
+        self.mode = None;
+        false
+    }
+}
@@ -1,6 +1,6 @@
 use specs::{Component, VecStorage};
 
-use server_common::{aabb::Aabb, vec::Vec3};
+use server_common::{aabb::Aabb, math::approx_equals, vec::Vec3};
 
 #[derive(Default, Component)]
 #[storage(VecStorage)]
@@ -21,6 +21,9 @@ pub struct RigidBody {
     pub fluid_drag: f32,
 
     pub resting: Vec3<f32>,
+    /// Normals of the voxel faces the body ran into during its last iteration, every one of
+    /// them where `resting` only keeps the last of each sweep
+    pub contacts: Vec<Vec3<f32>>,
     pub velocity: Vec3<f32>,
     pub in_fluid: bool,
     pub ratio_in_fluid: f32,
@@ -69,6 +72,7 @@ impl RigidBody {
             fluid_drag: -1.0,
 
             resting: Vec3::default(),
+            contacts: vec![],
             velocity: Vec3::default(),
             in_fluid: false,
             ratio_in_fluid: 0.0,
@@ -145,6 +149,14 @@ impl RigidBody {
         self.resting[2]
     }
 
+    /// Normal of a wall the body ran into beside it, if any
+    pub fn wall_normal(&self) -> Option<Vec3<f32>> {
+        self.contacts
+            .iter()
+            .find(|normal| approx_equals(&normal.1, &0.0))
+            .cloned()
+    }
+
     /// Mark rigid body as active. Active bodies will be processed for
     /// physics each tick.
     pub fn mark_active(&mut self) {
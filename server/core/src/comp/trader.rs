@@ -0,0 +1,10 @@
+use specs::{Component, VecStorage};
+
+use crate::engine::trading::TradeOffer;
+
+/// Offers of an entity that players can trade with, and the stock left of each
+#[derive(Debug, Clone, Component)]
+#[storage(VecStorage)]
+pub struct Trader {
+    pub offers: Vec<TradeOffer>,
+}
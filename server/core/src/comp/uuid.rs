@@ -0,0 +1,47 @@
+use specs::{Component, VecStorage};
+
+/// Stable id of an entity, kept across saves and handoffs unlike its ECS index
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Component)]
+#[storage(VecStorage)]
+pub struct Uuid(pub String);
+
+impl Uuid {
+    /// Generate a random (version 4) UUID
+    pub fn new() -> Self {
+        let mut bits = rand::random::<u128>();
+
+        // version 4, variant 1
+        bits = (bits & !(0xf << 76)) | (0x4 << 76);
+        bits = (bits & !(0x3 << 62)) | (0x2 << 62);
+
+        Self(format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            bits >> 96,
+            (bits >> 80) & 0xffff,
+            (bits >> 64) & 0xffff,
+            (bits >> 48) & 0xffff,
+            bits & 0xffff_ffff_ffff
+        ))
+    }
+}
+
+impl Default for Uuid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version() {
+        let Uuid(uuid) = Uuid::new();
+
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"));
+        assert_ne!(Uuid::new(), Uuid::new());
+    }
+}
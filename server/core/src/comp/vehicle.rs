@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+use specs::{Component, VecStorage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VehicleKind {
+    /// Floats on fluid surfaces
+    Boat,
+    /// Follows rails, speeding up down slopes
+    Minecart,
+}
+
+/// Marks an entity as a vehicle moved by its own integrator before the physics sweep
+#[derive(Debug, Clone, Component)]
+#[storage(VecStorage)]
+pub struct Vehicle(pub VehicleKind);
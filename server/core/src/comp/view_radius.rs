@@ -1,3 +1,4 @@
+use server_common::vec::Vec2;
 use specs::{Component, VecStorage};
 
 /// A view radius defines how far an entity can see.
@@ -11,4 +12,30 @@ impl ViewRadius {
     pub fn new(r: i16) -> Self {
         Self(r)
     }
+
+    /// View radius rounded up to the unit of chunks
+    pub fn in_chunks(&self, chunk_size: usize) -> i16 {
+        (self.0 as f32 / chunk_size as f32).ceil() as i16
+    }
+
+    /// Check if a chunk is within view, seen from the chunk at `center`
+    pub fn covers_chunk(&self, center: &Vec2<i32>, coords: &Vec2<i32>, chunk_size: usize) -> bool {
+        let r = self.in_chunks(chunk_size) as i32;
+        let dx = coords.0 - center.0;
+        let dz = coords.1 - center.1;
+
+        dx * dx + dz * dz <= r * r
+    }
+
+    /// Same as `covers_chunk`, but with one chunk of slack since clients
+    /// may be slightly ahead of the server
+    pub fn loosely_covers_chunk(
+        &self,
+        center: &Vec2<i32>,
+        coords: &Vec2<i32>,
+        chunk_size: usize,
+    ) -> bool {
+        ViewRadius::new(self.0.saturating_add(chunk_size as i16))
+            .covers_chunk(center, coords, chunk_size)
+    }
 }
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+pub use server_common::chunk::{DATA_PADDING, VOXEL_NEIGHBORS};
+
 pub const LEVEL_SEED: u32 = 1021;
 // pub const LEVEL_SEED: u32 = 1021312;
 
@@ -273,15 +275,6 @@ pub const PLANT_FACES: [PlantFace; 2] = [
     },
 ];
 
-pub const VOXEL_NEIGHBORS: [[i32; 3]; 6] = [
-    [1, 0, 0],
-    [-1, 0, 0],
-    [0, 0, 1],
-    [0, 0, -1],
-    [0, 1, 0],
-    [0, -1, 0],
-];
-
 pub const CHUNK_NEIGHBORS: [[i32; 2]; 8] = [
     [-1, 0],
     [1, 0],
@@ -295,6 +288,7 @@ pub const CHUNK_NEIGHBORS: [[i32; 2]; 8] = [
 
 pub const CHUNK_HORIZONTAL_NEIGHBORS: [[i32; 2]; 4] = [[-1, 0], [1, 0], [0, -1], [0, 1]];
 
-pub const DATA_PADDING: usize = 1;
-
 pub const WORLD_DATA_FILE: &str = "world.json";
+
+/// Metadata the chunk dimensions a world was saved with are kept in
+pub const DIMENSIONS_FILE: &str = "dimensions.json";
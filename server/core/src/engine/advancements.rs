@@ -0,0 +1,177 @@
+use std::{collections::HashMap, fs::File, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use super::{registry::Registry, storage::Storage};
+
+/// Per-player progress is saved under this metadata key of the world's storage
+pub const ADVANCEMENTS_FILE: &str = "advancements.json";
+
+/// What needs to be done to complete an advancement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Trigger {
+    /// Break `count` blocks of a type
+    BreakBlock { block: String, count: u32 },
+    /// Stand within `radius` voxels of a horizontal location, or beyond it if `outside`
+    ReachLocation {
+        x: f32,
+        z: f32,
+        radius: f32,
+        #[serde(default)]
+        outside: bool,
+    },
+    /// Take `count` of an item out of a crafting or smelting output
+    CraftItem { item: String, count: u32 },
+    /// Emitted by plugins as `WorldEvent::Custom` with the same name
+    Custom { name: String, count: u32 },
+}
+
+impl Trigger {
+    /// Progress needed to complete the trigger
+    pub fn goal(&self) -> u32 {
+        match self {
+            Trigger::BreakBlock { count, .. }
+            | Trigger::CraftItem { count, .. }
+            | Trigger::Custom { count, .. } => (*count).max(1),
+            Trigger::ReachLocation { .. } => 1,
+        }
+    }
+}
+
+/// A data-driven advancement, loaded from `assets/metadata/advancements.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Advancement {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub trigger: Trigger,
+}
+
+/// Resource of all advancements and every player's progress, keyed by player name
+#[derive(Debug, Default)]
+pub struct Advancements {
+    pub list: Vec<Advancement>,
+    progress: HashMap<String, HashMap<String, u32>>,
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl Advancements {
+    /// Load the bundled advancements, along with the progress saved in `storage` if any
+    pub fn new(storage: Option<Arc<dyn Storage>>) -> Self {
+        let list: Vec<Advancement> =
+            serde_json::from_reader(File::open("assets/metadata/advancements.json").unwrap())
+                .expect("Unable to parse advancements.json");
+
+        let progress = storage
+            .as_ref()
+            .and_then(|storage| storage.get_metadata(ADVANCEMENTS_FILE))
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        Self {
+            list,
+            progress,
+            storage,
+        }
+    }
+
+    /// Register an advancement, such as one with a custom trigger from a plugin
+    pub fn register(&mut self, advancement: Advancement) {
+        self.list.retain(|a| a.id != advancement.id);
+        self.list.push(advancement);
+    }
+
+    /// Get the progress of a player towards an advancement
+    pub fn get_progress(&self, player: &str, id: &str) -> u32 {
+        self.progress
+            .get(player)
+            .and_then(|progress| progress.get(id))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn is_completed(&self, player: &str, advancement: &Advancement) -> bool {
+        self.get_progress(player, &advancement.id) >= advancement.trigger.goal()
+    }
+
+    /// Add progress to every matching, incomplete advancement of a player
+    ///
+    /// Returns the advancements completed by this progress.
+    pub fn progress<F: Fn(&Trigger) -> bool>(
+        &mut self,
+        player: &str,
+        amount: u32,
+        matches: F,
+    ) -> Vec<Advancement> {
+        let mut completed = vec![];
+
+        for advancement in self.list.iter() {
+            if !matches(&advancement.trigger) {
+                continue;
+            }
+
+            let goal = advancement.trigger.goal();
+            let progress = self
+                .progress
+                .entry(player.to_owned())
+                .or_default()
+                .entry(advancement.id.to_owned())
+                .or_insert(0);
+
+            if *progress >= goal {
+                continue;
+            }
+
+            *progress = (*progress + amount).min(goal);
+
+            if *progress >= goal {
+                completed.push(advancement.to_owned());
+            }
+        }
+
+        completed
+    }
+
+    /// Check if a block id matches the block name of a trigger
+    pub fn is_block(registry: &Registry, name: &str, id: u32) -> bool {
+        registry.try_get_id_by_name(name) == Some(id)
+    }
+
+    /// Save every player's progress into the world's storage
+    pub fn save(&self) {
+        if let Some(storage) = &self.storage {
+            let data = serde_json::to_vec(&self.progress).unwrap();
+            storage.put_metadata(ADVANCEMENTS_FILE, &data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress() {
+        let mut advancements = Advancements::default();
+        advancements.register(Advancement {
+            id: "ten".to_owned(),
+            title: "Ten".to_owned(),
+            description: String::new(),
+            trigger: Trigger::Custom {
+                name: "jump".to_owned(),
+                count: 10,
+            },
+        });
+
+        let is_jump = |t: &Trigger| matches!(t, Trigger::Custom { name, .. } if name == "jump");
+
+        assert!(advancements.progress("steve", 6, is_jump).is_empty());
+        assert_eq!(advancements.progress("steve", 6, is_jump).len(), 1);
+        assert!(advancements.progress("steve", 6, is_jump).is_empty());
+        assert_eq!(advancements.get_progress("steve", "ten"), 10);
+        assert_eq!(advancements.get_progress("alex", "ten"), 0);
+    }
+}
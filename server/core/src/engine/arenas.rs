@@ -0,0 +1,388 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use log::warn;
+
+use serde::{Deserialize, Serialize};
+
+use server_common::vec::Vec3;
+
+use crate::world_edit::{Region, Schematic};
+
+use super::{scheduler::Timers, storage::Storage};
+
+/// Arena definitions are saved under this metadata key of the world's storage
+pub const ARENAS_FILE: &str = "arenas.json";
+
+/// Prefix of the metadata keys holding the schematic of each arena
+pub const ARENAS_FOLDER: &str = "arenas";
+
+/// Metadata key of the schematic of an arena
+fn schematic_key(name: &str) -> String {
+    format!("{}/{}.mjsc", ARENAS_FOLDER, name)
+}
+
+/// Definition of an arena, its region is reset from its schematic after each round
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArenaConfig {
+    pub name: String,
+    pub min: Vec3<i32>,
+    pub max: Vec3<i32>,
+    /// Voxel players are sent to when a round starts
+    pub spawn: Vec3<i32>,
+    /// Voxel players are sent back to when a round ends
+    pub lobby: Vec3<i32>,
+    pub min_players: usize,
+    pub max_players: usize,
+    /// Seconds between enough players joining and the round starting
+    pub countdown: f32,
+    /// Seconds a round lasts
+    pub duration: f32,
+}
+
+impl ArenaConfig {
+    pub fn region(&self) -> Region {
+        Region::new(self.min.to_owned(), self.max.to_owned())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArenaState {
+    /// Waiting for enough players
+    Lobby,
+    Countdown,
+    Running,
+    /// Waiting for the world to restore the region
+    Resetting,
+}
+
+/// Lifecycle changes for the world to act upon, such as moving players and resetting regions
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArenaEvent {
+    CountdownStarted { arena: String, seconds: f32 },
+    CountdownCancelled { arena: String },
+    RoundStarted { arena: String },
+    RoundEnded { arena: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Arena {
+    pub config: ArenaConfig,
+    pub schematic: Schematic,
+    pub state: ArenaState,
+    /// Ids of the players in the arena, lobby included
+    pub players: Vec<usize>,
+}
+
+/// Resource coordinating the arenas of a world on the world ticks
+pub struct Arenas {
+    arenas: BTreeMap<String, Arena>,
+    timers: Timers<String>,
+    events: Vec<ArenaEvent>,
+    /// Milliseconds per world tick
+    tick_rate: u64,
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl Arenas {
+    /// Load the arenas saved in `storage`, if any
+    pub fn new(storage: Option<Arc<dyn Storage>>, tick_rate: u64) -> Self {
+        let mut arenas = BTreeMap::new();
+
+        if let Some(storage) = &storage {
+            let configs: Vec<ArenaConfig> = storage
+                .get_metadata(ARENAS_FILE)
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                .unwrap_or_default();
+
+            for config in configs {
+                let schematic = match storage.get_metadata(&schematic_key(&config.name)) {
+                    Some(bytes) => Schematic::from_native(&bytes),
+                    None => Err(std::io::ErrorKind::NotFound.into()),
+                };
+
+                match schematic {
+                    Ok(schematic) => {
+                        arenas.insert(config.name.to_owned(), Arena::new(config, schematic));
+                    }
+                    Err(e) => warn!("Skipping arena \"{}\": {}", config.name, e),
+                }
+            }
+        }
+
+        Self {
+            arenas,
+            timers: Timers::new(),
+            events: vec![],
+            tick_rate: tick_rate.max(1),
+            storage,
+        }
+    }
+
+    /// Add an arena along with the schematic its region resets to, returns false if the name is taken
+    pub fn define(&mut self, config: ArenaConfig, schematic: Schematic) -> bool {
+        if self.arenas.contains_key(&config.name) {
+            return false;
+        }
+
+        if let Some(storage) = &self.storage {
+            storage.put_metadata(
+                &schematic_key(&config.name),
+                &schematic.to_native().unwrap(),
+            );
+        }
+
+        self.arenas
+            .insert(config.name.to_owned(), Arena::new(config, schematic));
+
+        true
+    }
+
+    /// Remove an arena, returning it with the players that were in it
+    pub fn remove(&mut self, name: &str) -> Option<Arena> {
+        self.timers.cancel(&name.to_owned());
+        self.arenas.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arena> {
+        self.arenas.get(name)
+    }
+
+    pub fn all(&self) -> impl Iterator<Item = &Arena> {
+        self.arenas.values()
+    }
+
+    /// Get the arena a player is in
+    pub fn arena_of(&self, player_id: usize) -> Option<&Arena> {
+        self.arenas
+            .values()
+            .find(|arena| arena.players.contains(&player_id))
+    }
+
+    /// Seconds left on the countdown or round of an arena
+    pub fn remaining(&self, name: &str, tick: i32) -> Option<f32> {
+        self.timers
+            .remaining(&name.to_owned(), tick)
+            .map(|ticks| self.to_seconds(ticks))
+    }
+
+    /// Add a player to the lobby of an arena, leaving any previous arena
+    ///
+    /// Starts the countdown once enough players are in. Errors with a translation key.
+    pub fn join(&mut self, name: &str, player_id: usize, tick: i32) -> Result<(), &'static str> {
+        match self.arenas.get(name) {
+            None => return Err("arena.missing"),
+            Some(arena) if arena.players.contains(&player_id) => return Ok(()),
+            Some(arena) if arena.state == ArenaState::Running => return Err("arena.running"),
+            Some(arena) if arena.state == ArenaState::Resetting => return Err("arena.running"),
+            Some(arena) if arena.players.len() >= arena.config.max_players => {
+                return Err("arena.full")
+            }
+            _ => {}
+        }
+
+        self.leave(player_id);
+
+        let arena = self.arenas.get_mut(name).unwrap();
+        arena.players.push(player_id);
+
+        self.try_start_countdown(name, tick);
+
+        Ok(())
+    }
+
+    /// Remove a player from their arena, returning the arena's name
+    ///
+    /// Cancels the countdown without enough players, and ends a round nobody is left in.
+    pub fn leave(&mut self, player_id: usize) -> Option<String> {
+        let arena = self
+            .arenas
+            .values_mut()
+            .find(|arena| arena.players.contains(&player_id))?;
+
+        arena.players.retain(|id| *id != player_id);
+        let name = arena.config.name.to_owned();
+
+        match arena.state {
+            ArenaState::Countdown if arena.players.len() < arena.config.min_players => {
+                arena.state = ArenaState::Lobby;
+                self.timers.cancel(&name);
+                self.events.push(ArenaEvent::CountdownCancelled {
+                    arena: name.to_owned(),
+                });
+            }
+            ArenaState::Running if arena.players.is_empty() => {
+                arena.state = ArenaState::Resetting;
+                self.timers.cancel(&name);
+                self.events.push(ArenaEvent::RoundEnded {
+                    arena: name.to_owned(),
+                });
+            }
+            _ => {}
+        }
+
+        Some(name)
+    }
+
+    /// Mark an arena's region as restored, reopening its lobby
+    pub fn finish_reset(&mut self, name: &str, tick: i32) {
+        if let Some(arena) = self.arenas.get_mut(name) {
+            if arena.state == ArenaState::Resetting {
+                arena.state = ArenaState::Lobby;
+                self.try_start_countdown(name, tick);
+            }
+        }
+    }
+
+    /// Advance the arenas whose timers are due, and take every event since the last tick
+    pub fn tick(&mut self, tick: i32) -> Vec<ArenaEvent> {
+        for name in self.timers.take_due(tick) {
+            let duration = match self.arenas.get(&name) {
+                Some(arena) => self.to_ticks(arena.config.duration),
+                None => continue,
+            };
+
+            let arena = self.arenas.get_mut(&name).unwrap();
+
+            match arena.state {
+                ArenaState::Countdown => {
+                    arena.state = ArenaState::Running;
+                    self.timers.schedule(name.to_owned(), tick + duration);
+                    self.events.push(ArenaEvent::RoundStarted { arena: name });
+                }
+                ArenaState::Running => {
+                    arena.state = ArenaState::Resetting;
+                    self.events.push(ArenaEvent::RoundEnded { arena: name });
+                }
+                _ => {}
+            }
+        }
+
+        self.events.drain(..).collect()
+    }
+
+    /// Save the arena definitions into the world's storage, schematics are saved when defined
+    pub fn save(&self) {
+        if let Some(storage) = &self.storage {
+            let configs = self
+                .arenas
+                .values()
+                .map(|arena| &arena.config)
+                .collect::<Vec<_>>();
+
+            let data = serde_json::to_vec(&configs).unwrap();
+            storage.put_metadata(ARENAS_FILE, &data);
+        }
+    }
+
+    fn try_start_countdown(&mut self, name: &str, tick: i32) {
+        let countdown = match self.arenas.get(name) {
+            Some(arena) => arena.config.countdown,
+            None => return,
+        };
+        let ticks = self.to_ticks(countdown);
+
+        let arena = self.arenas.get_mut(name).unwrap();
+
+        if arena.state != ArenaState::Lobby || arena.players.len() < arena.config.min_players {
+            return;
+        }
+
+        arena.state = ArenaState::Countdown;
+        self.timers.schedule(name.to_owned(), tick + ticks);
+        self.events.push(ArenaEvent::CountdownStarted {
+            arena: name.to_owned(),
+            seconds: countdown,
+        });
+    }
+
+    fn to_ticks(&self, seconds: f32) -> i32 {
+        (seconds * 1000.0 / self.tick_rate as f32).ceil() as i32
+    }
+
+    fn to_seconds(&self, ticks: i32) -> f32 {
+        ticks as f32 * self.tick_rate as f32 / 1000.0
+    }
+}
+
+impl Arena {
+    fn new(config: ArenaConfig, schematic: Schematic) -> Self {
+        Self {
+            config,
+            schematic,
+            state: ArenaState::Lobby,
+            players: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifecycle() {
+        // a tick every 100ms
+        let mut arenas = Arenas::new(None, 100);
+
+        let schematic = Schematic {
+            width: 0,
+            height: 0,
+            length: 0,
+            palette: vec![],
+            blocks: vec![],
+        };
+
+        assert!(arenas.define(
+            ArenaConfig {
+                name: "pit".to_owned(),
+                min: Vec3(0, 0, 0),
+                max: Vec3(8, 8, 8),
+                spawn: Vec3(4, 1, 4),
+                lobby: Vec3(20, 1, 20),
+                min_players: 2,
+                max_players: 2,
+                countdown: 1.0,
+                duration: 2.0,
+            },
+            schematic,
+        ));
+
+        assert_eq!(arenas.join("pit", 1, 0), Ok(()));
+        assert!(arenas.tick(0).is_empty());
+
+        assert_eq!(arenas.join("pit", 2, 0), Ok(()));
+        assert_eq!(arenas.join("pit", 3, 0), Err("arena.full"));
+        assert_eq!(
+            arenas.tick(0),
+            vec![ArenaEvent::CountdownStarted {
+                arena: "pit".to_owned(),
+                seconds: 1.0
+            }]
+        );
+
+        assert!(arenas.tick(9).is_empty());
+        assert_eq!(
+            arenas.tick(10),
+            vec![ArenaEvent::RoundStarted {
+                arena: "pit".to_owned()
+            }]
+        );
+        assert_eq!(arenas.remaining("pit", 20), Some(1.0));
+
+        assert_eq!(
+            arenas.tick(30),
+            vec![ArenaEvent::RoundEnded {
+                arena: "pit".to_owned()
+            }]
+        );
+        assert_eq!(arenas.get("pit").unwrap().state, ArenaState::Resetting);
+
+        // both players are still in, so the next countdown starts right away
+        arenas.finish_reset("pit", 30);
+        assert_eq!(arenas.get("pit").unwrap().state, ArenaState::Countdown);
+
+        arenas.leave(2);
+        assert_eq!(arenas.get("pit").unwrap().state, ArenaState::Lobby);
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+
+use serde::{Deserialize, Serialize};
+
+/// Audit logs are written to this folder within the world folder
+pub const AUDIT_FOLDER: &str = "audit";
+
+/// Size in bytes past which the audit log is rotated
+pub const MAX_AUDIT_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Audit logs kept, the current one included, older ones being deleted as the log rotates
+pub const MAX_AUDIT_FILES: usize = 8;
+
+/// Something a player did, as logged
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum AuditAction {
+    /// A voxel set, placing or breaking a block, along with what it was before
+    Edit {
+        voxel: [i32; 3],
+        id: u32,
+        previous: u32,
+        #[serde(default)]
+        previous_rotation: u32,
+        #[serde(default)]
+        previous_y_rotation: u32,
+    },
+    Chat {
+        body: String,
+    },
+    /// A command, without its leading `/`
+    Command {
+        body: String,
+    },
+}
+
+/// A line of the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Unix time in milliseconds
+    pub time: u64,
+    pub player: String,
+    #[serde(flatten)]
+    pub action: AuditAction,
+}
+
+/// Resource appending what players do to JSON lines files, rotated as they grow
+///
+/// The current log is `audit/audit.log` within the world folder, the older ones numbered from
+/// `audit.1.log` on. Records are buffered and written as the world ticks.
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    folder: Option<PathBuf>,
+    pending: Vec<AuditRecord>,
+}
+
+impl AuditLog {
+    /// Log into `folder`, if any
+    pub fn new(folder: Option<PathBuf>) -> Self {
+        let folder = folder.map(|mut folder| {
+            folder.push(AUDIT_FOLDER);
+            folder
+        });
+
+        if let Some(folder) = &folder {
+            if let Err(e) = fs::create_dir_all(folder) {
+                warn!("Unable to create the audit folder {:?}: {}", folder, e);
+            }
+        }
+
+        Self {
+            folder,
+            pending: vec![],
+        }
+    }
+
+    fn file(folder: &Path, index: usize) -> PathBuf {
+        if index == 0 {
+            folder.join("audit.log")
+        } else {
+            folder.join(format!("audit.{}.log", index))
+        }
+    }
+
+    /// Queue a record to be written
+    pub fn record(&mut self, time: u64, player: &str, action: AuditAction) {
+        if self.folder.is_none() {
+            return;
+        }
+
+        self.pending.push(AuditRecord {
+            time,
+            player: player.to_owned(),
+            action,
+        });
+    }
+
+    /// Write the queued records, rotating the log first if it's grown too big
+    pub fn flush(&mut self) {
+        let folder = match &self.folder {
+            Some(folder) if !self.pending.is_empty() => folder.to_owned(),
+            _ => return,
+        };
+
+        let current = AuditLog::file(&folder, 0);
+        if fs::metadata(&current).is_ok_and(|meta| meta.len() >= MAX_AUDIT_FILE_SIZE) {
+            AuditLog::rotate(&folder);
+        }
+
+        let mut lines = String::new();
+        for record in self.pending.drain(..) {
+            lines.push_str(&serde_json::to_string(&record).unwrap());
+            lines.push('\n');
+        }
+
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&current)
+            .and_then(|mut file| file.write_all(lines.as_bytes()));
+
+        if let Err(e) = written {
+            warn!("Unable to write the audit log {:?}: {}", current, e);
+        }
+    }
+
+    fn rotate(folder: &Path) {
+        let _ = fs::remove_file(AuditLog::file(folder, MAX_AUDIT_FILES - 1));
+
+        for index in (0..MAX_AUDIT_FILES - 1).rev() {
+            let _ = fs::rename(
+                AuditLog::file(folder, index),
+                AuditLog::file(folder, index + 1),
+            );
+        }
+    }
+
+    /// Read the records logged at or after a time, oldest first, flushed ones only
+    pub fn read_since(&self, since: u64) -> Vec<AuditRecord> {
+        let folder = match &self.folder {
+            Some(folder) => folder,
+            None => return vec![],
+        };
+
+        let mut records = vec![];
+
+        for index in (0..MAX_AUDIT_FILES).rev() {
+            let file = match File::open(AuditLog::file(folder, index)) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+
+            records.extend(
+                BufReader::new(file)
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter_map(|line| serde_json::from_str::<AuditRecord>(&line).ok())
+                    .filter(|record| record.time >= since),
+            );
+        }
+
+        records
+    }
+}
+
+/// Parse a duration such as `30s`, `10m`, `2h` or `1d` into milliseconds
+pub fn parse_duration(value: &str) -> Option<u64> {
+    let unit = match value.chars().last()? {
+        's' => 1000,
+        'm' => 60 * 1000,
+        'h' => 60 * 60 * 1000,
+        'd' => 24 * 60 * 60 * 1000,
+        _ => return None,
+    };
+
+    value[..value.len() - 1]
+        .parse::<u64>()
+        .ok()
+        .and_then(|amount| amount.checked_mul(unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation() {
+        let folder = std::env::temp_dir().join(format!("audit-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+
+        let mut log = AuditLog::new(Some(folder.to_owned()));
+        log.record(1, "ian", AuditAction::Chat { body: "hi".into() });
+        log.flush();
+
+        // pretend the log filled up, the next flush starting a new one
+        fs::write(
+            folder.join(AUDIT_FOLDER).join("audit.1.log"),
+            "not a record\n",
+        )
+        .unwrap();
+        AuditLog::rotate(&folder.join(AUDIT_FOLDER));
+        log.record(
+            2,
+            "ian",
+            AuditAction::Command {
+                body: "undo".into(),
+            },
+        );
+        log.flush();
+
+        let records = log.read_since(0);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].time, 1);
+        assert_eq!(log.read_since(2).len(), 1);
+
+        fs::remove_dir_all(&folder).unwrap();
+
+        assert_eq!(parse_duration("10m"), Some(600_000));
+        assert_eq!(parse_duration("10"), None);
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration("18446744073709551615d"), None);
+    }
+}
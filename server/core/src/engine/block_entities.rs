@@ -0,0 +1,231 @@
+use hashbrown::HashMap;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use server_common::vec::Vec3;
+
+use crate::network::models::BlockEntityProtocol;
+
+use super::{items::ItemStack, recipes::Recipes};
+
+/// Most items a single slot can hold
+pub const MAX_STACK_SIZE: u32 = 64;
+
+/// Stateful data attached to a single voxel, such as the inventory of a chest
+///
+/// Block entities are stored as JSON, so any serde type can be one.
+pub trait BlockEntity: Serialize + DeserializeOwned {
+    /// Unique name of this kind of block entity
+    const KIND: &'static str;
+
+    /// Whether the block entity is advanced by the block tick scheduler once placed
+    const TICKS: bool = false;
+}
+
+/// A type-erased block entity, as saved in chunks and sent to clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockEntityData {
+    pub kind: String,
+    pub data: serde_json::Value,
+}
+
+impl BlockEntityData {
+    pub fn new<T: BlockEntity>(entity: &T) -> Self {
+        Self {
+            kind: T::KIND.to_owned(),
+            data: serde_json::to_value(entity).expect("Unable to serialize block entity."),
+        }
+    }
+
+    /// Get the typed block entity, `None` if it's of another kind
+    pub fn get<T: BlockEntity>(&self) -> Option<T> {
+        if self.kind != T::KIND {
+            return None;
+        }
+
+        serde_json::from_value(self.data.to_owned()).ok()
+    }
+
+    pub fn to_protocol(&self, voxel: &Vec3<i32>) -> BlockEntityProtocol {
+        BlockEntityProtocol {
+            voxel: voxel.to_owned(),
+            kind: self.kind.to_owned(),
+            data: self.data.to_string(),
+        }
+    }
+}
+
+/// Block entities of a chunk, keyed by voxel coordinates
+pub type BlockEntities = HashMap<Vec3<i32>, BlockEntityData>;
+
+/// Lines of text written on a sign
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sign {
+    pub lines: Vec<String>,
+}
+
+impl BlockEntity for Sign {
+    const KIND: &'static str = "sign";
+}
+
+/// Inventory of a chest, empty slots are `None`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Chest {
+    pub slots: Vec<Option<ItemStack>>,
+}
+
+impl BlockEntity for Chest {
+    const KIND: &'static str = "chest";
+}
+
+/// Smelts input items into outputs while burning fuel, one step per block tick
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Furnace {
+    pub input: Option<ItemStack>,
+    pub fuel: Option<ItemStack>,
+    pub output: Option<ItemStack>,
+    /// Ticks spent smelting the current input
+    pub progress: u32,
+    /// Ticks left for the fuel burning
+    pub burn_time: u32,
+    /// Ticks the fuel burning lasts in total, for displaying the flame
+    pub burn_total: u32,
+}
+
+impl BlockEntity for Furnace {
+    const KIND: &'static str = "furnace";
+    const TICKS: bool = true;
+}
+
+impl Furnace {
+    /// Whether the input can be smelted into the output slot
+    fn can_smelt(&self, recipes: &Recipes) -> bool {
+        let recipe = match self
+            .input
+            .as_ref()
+            .and_then(|input| recipes.get_smelting(input.id))
+        {
+            Some(recipe) => recipe,
+            None => return false,
+        };
+
+        match &self.output {
+            Some(output) => {
+                output.id == recipe.output && output.count + recipe.count <= MAX_STACK_SIZE
+            }
+            None => true,
+        }
+    }
+
+    /// Advance the furnace by a tick
+    ///
+    /// Returns whether the furnace needs to keep ticking.
+    pub fn tick(&mut self, recipes: &Recipes) -> bool {
+        let can_smelt = self.can_smelt(recipes);
+
+        // light up a new fuel only if there is something to smelt
+        if self.burn_time == 0 && can_smelt {
+            if let Some(time) = self.fuel.as_ref().and_then(|f| recipes.get_fuel(f.id)) {
+                let fuel = self.fuel.as_mut().unwrap();
+                fuel.count -= 1;
+
+                if fuel.count == 0 {
+                    self.fuel = None;
+                }
+
+                self.burn_time = time;
+                self.burn_total = time;
+            }
+        }
+
+        if self.burn_time == 0 {
+            self.progress = 0;
+            return false;
+        }
+
+        self.burn_time -= 1;
+
+        if !can_smelt {
+            self.progress = 0;
+            return self.burn_time > 0;
+        }
+
+        self.progress += 1;
+
+        let recipe = recipes
+            .get_smelting(self.input.as_ref().unwrap().id)
+            .unwrap();
+
+        if self.progress >= recipe.time {
+            self.progress = 0;
+
+            let input = self.input.as_mut().unwrap();
+            input.count -= 1;
+
+            if input.count == 0 {
+                self.input = None;
+            }
+
+            match self.output.as_mut() {
+                Some(output) => output.count += recipe.count,
+                None => self.output = Some(ItemStack::new(recipe.output, recipe.count)),
+            }
+        }
+
+        let has_fuel = self
+            .fuel
+            .as_ref()
+            .is_some_and(|fuel| recipes.get_fuel(fuel.id).is_some());
+
+        self.burn_time > 0 || (has_fuel && self.can_smelt(recipes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::engine::recipes::SmeltingRecipe;
+
+    #[test]
+    fn typed_access() {
+        let chest = Chest {
+            slots: vec![Some(ItemStack::new(3, 12)), None],
+        };
+
+        let data = BlockEntityData::new(&chest);
+        assert_eq!(data.kind, "chest");
+        assert_eq!(data.get::<Chest>(), Some(chest));
+        assert_eq!(data.get::<Sign>(), None);
+    }
+
+    #[test]
+    fn furnace() {
+        let mut recipes = Recipes::default();
+        recipes.add_smelting(SmeltingRecipe {
+            input: 4,
+            output: 40,
+            count: 1,
+            time: 2,
+        });
+        recipes.add_fuel(60, 3);
+
+        let mut furnace = Furnace {
+            input: Some(ItemStack::new(4, 2)),
+            fuel: Some(ItemStack::new(60, 1)),
+            ..Default::default()
+        };
+
+        assert!(furnace.tick(&recipes));
+        assert_eq!(furnace.fuel, None);
+        assert!(furnace.tick(&recipes));
+        assert_eq!(furnace.output, Some(ItemStack::new(40, 1)));
+
+        // the fuel burns out halfway through the second input
+        assert!(!furnace.tick(&recipes));
+        assert!(!furnace.tick(&recipes));
+        assert_eq!(furnace.input, Some(ItemStack::new(4, 1)));
+        assert_eq!(furnace.progress, 0);
+    }
+}
@@ -0,0 +1,218 @@
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "server")]
+use log::warn;
+
+use serde::Deserialize;
+
+use server_utils::secret::constant_time_eq;
+
+/// Most characters of a webhook post, Discord's limit on a message's content
+pub const MAX_WEBHOOK_CONTENT: usize = 2000;
+
+/// Least time between posts to the webhooks, lines in between being batched
+pub const BRIDGE_POST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most characters of a message taken from a bot into game chat
+pub const MAX_BRIDGE_MESSAGE_LENGTH: usize = 256;
+
+/// How the lines posted to webhooks and taken into chat are written
+///
+/// `{player}`, `{message}` and `{world}` are replaced by the name of the player, what was said
+/// and the name of the world.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BridgeTemplates {
+    pub chat: String,
+    pub join: String,
+    pub leave: String,
+    pub death: String,
+    /// Messages from the bot, as shown in game chat
+    pub incoming: String,
+}
+
+impl Default for BridgeTemplates {
+    fn default() -> Self {
+        Self {
+            chat: "**{player}**: {message}".to_owned(),
+            join: "**{player}** joined {world}".to_owned(),
+            leave: "**{player}** left {world}".to_owned(),
+            death: "**{player}** died".to_owned(),
+            incoming: "[{player}] {message}".to_owned(),
+        }
+    }
+}
+
+/// Where a world's chat and player events are forwarded, and who may talk back
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeConfig {
+    /// URLs posted `{"content": ...}` to, such as Discord webhooks
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+    /// Shared with the bot posting messages into the world, none to turn that off
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub templates: BridgeTemplates,
+}
+
+impl BridgeConfig {
+    /// Check the secret a bot's message came with
+    pub fn authorize(&self, secret: Option<&str>) -> bool {
+        match &self.secret {
+            Some(expected) => secret
+                .is_some_and(|secret| constant_time_eq(secret.as_bytes(), expected.as_bytes())),
+            None => false,
+        }
+    }
+}
+
+/// Something that happened in a world worth telling the outside about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeEvent {
+    Chat { player: String, message: String },
+    Join { player: String },
+    Leave { player: String },
+    Death { player: String },
+}
+
+/// Fill a template's placeholders in
+pub fn fill(template: &str, player: &str, message: &str, world: &str) -> String {
+    template
+        .replace("{player}", player)
+        .replace("{world}", world)
+        .replace("{message}", message)
+}
+
+impl BridgeEvent {
+    pub fn format(&self, templates: &BridgeTemplates, world: &str) -> String {
+        match self {
+            BridgeEvent::Chat { player, message } => fill(&templates.chat, player, message, world),
+            BridgeEvent::Join { player } => fill(&templates.join, player, "", world),
+            BridgeEvent::Leave { player } => fill(&templates.leave, player, "", world),
+            BridgeEvent::Death { player } => fill(&templates.death, player, "", world),
+        }
+    }
+}
+
+/// Resource of the lines waiting to be posted to a world's webhooks
+///
+/// Lines are batched into as few posts as fit, so that a busy chat doesn't run into the
+/// webhooks' rate limits.
+#[derive(Debug, Default)]
+pub struct Bridge {
+    pending: Vec<String>,
+    posted: Option<Instant>,
+}
+
+impl Bridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, line: String) {
+        self.pending.push(line);
+    }
+
+    /// Take the pending lines, joined into posts of up to `MAX_WEBHOOK_CONTENT` characters
+    ///
+    /// Nothing is taken until `BRIDGE_POST_INTERVAL` passed since the last posts.
+    pub fn take(&mut self, now: Instant) -> Vec<String> {
+        let waiting = matches!(self.posted, Some(posted) if now < posted + BRIDGE_POST_INTERVAL);
+        if self.pending.is_empty() || waiting {
+            return vec![];
+        }
+
+        self.posted = Some(now);
+        let mut posts: Vec<String> = vec![];
+
+        for line in self.pending.drain(..) {
+            let line = line.chars().take(MAX_WEBHOOK_CONTENT).collect::<String>();
+
+            match posts.last_mut() {
+                Some(post)
+                    if post.chars().count() + 1 + line.chars().count() <= MAX_WEBHOOK_CONTENT =>
+                {
+                    post.push('\n');
+                    post.push_str(&line);
+                }
+                _ => posts.push(line),
+            }
+        }
+
+        posts
+    }
+}
+
+/// Post to each webhook off the world loop, warning of the posts that fail
+///
+/// Mentions are turned off, so that players can't ping everyone through the bridge.
+#[cfg(feature = "server")]
+pub fn post(webhooks: &[String], content: &str) {
+    for url in webhooks {
+        let url = url.to_owned();
+        let body = serde_json::json!({
+            "content": content,
+            "allowed_mentions": { "parse": [] },
+        });
+
+        actix::spawn(async move {
+            match actix_web::client::Client::default()
+                .post(&url)
+                .send_json(&body)
+                .await
+            {
+                Ok(response) if !response.status().is_success() => {
+                    warn!("Webhook {} answered {}", url, response.status())
+                }
+                Err(e) => warn!("Unable to post to webhook {}: {}", url, e),
+                _ => {}
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batching() {
+        let templates = BridgeTemplates::default();
+
+        let chat = BridgeEvent::Chat {
+            player: "ian".into(),
+            message: "hi {player}".into(),
+        };
+        assert_eq!(chat.format(&templates, "lobby"), "**ian**: hi {player}");
+        assert_eq!(
+            BridgeEvent::Join {
+                player: "ian".into()
+            }
+            .format(&templates, "lobby"),
+            "**ian** joined lobby"
+        );
+
+        let mut bridge = Bridge::new();
+        bridge.push("a".into());
+        bridge.push("b".into());
+        bridge.push("x".repeat(MAX_WEBHOOK_CONTENT + 10));
+        bridge.push("c".into());
+
+        let now = Instant::now();
+        let posts = bridge.take(now);
+        assert_eq!(posts.len(), 3);
+        assert_eq!(posts[0], "a\nb");
+        assert_eq!(posts[1].len(), MAX_WEBHOOK_CONTENT);
+        assert_eq!(posts[2], "c");
+
+        // lines wait for the interval to pass
+        bridge.push("d".into());
+        assert!(bridge.take(now).is_empty());
+        assert_eq!(
+            bridge.take(now + BRIDGE_POST_INTERVAL),
+            vec!["d".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+/// Ticks slower than this fraction of the tick rate halve the budget
+const OVERLOADED: f32 = 0.8;
+/// Ticks faster than this fraction of the tick rate grow the budget by one
+const HEALTHY: f32 = 0.5;
+/// Weight of the latest tick in the smoothed tick duration
+const SMOOTHING: f32 = 0.2;
+
+/// How many chunks finished off-thread get merged back into the world per tick
+///
+/// Shrinks quickly when ticks run long and grows back slowly when they're healthy, so that
+/// a burst of exploration loads chunks later instead of slowing the whole world down.
+#[derive(Debug, Clone)]
+pub struct TickBudget {
+    /// Milliseconds per world tick
+    tick_rate: f32,
+    /// Smoothed tick duration in milliseconds
+    average: f32,
+    merges: usize,
+    max_merges: usize,
+}
+
+impl TickBudget {
+    pub fn new(tick_rate: u64, max_merges: usize) -> Self {
+        let max_merges = max_merges.max(1);
+
+        Self {
+            tick_rate: tick_rate.max(1) as f32,
+            average: 0.0,
+            merges: max_merges,
+            max_merges,
+        }
+    }
+
+    /// Account for how long the last tick took, resizing the budget
+    pub fn record(&mut self, duration: Duration) {
+        let millis = duration.as_secs_f32() * 1000.0;
+        self.average += (millis - self.average) * SMOOTHING;
+
+        if millis > self.tick_rate * OVERLOADED || self.average > self.tick_rate * OVERLOADED {
+            self.merges = (self.merges / 2).max(1);
+        } else if self.average < self.tick_rate * HEALTHY {
+            self.merges = (self.merges + 1).min(self.max_merges);
+        }
+    }
+
+    /// Number of chunks allowed to merge back this tick, always at least one
+    pub fn merges(&self) -> usize {
+        self.merges
+    }
+
+    /// Smoothed tick duration in milliseconds
+    pub fn average(&self) -> f32 {
+        self.average
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adapts() {
+        let mut budget = TickBudget::new(16, 8);
+        assert_eq!(budget.merges(), 8);
+
+        budget.record(Duration::from_millis(100));
+        assert_eq!(budget.merges(), 4);
+
+        // still slow on average after a single fast tick
+        budget.record(Duration::from_millis(1));
+        assert_eq!(budget.merges(), 2);
+
+        for _ in 0..32 {
+            budget.record(Duration::from_millis(1));
+        }
+        assert_eq!(budget.merges(), 8);
+
+        for _ in 0..8 {
+            budget.record(Duration::from_millis(200));
+        }
+        assert_eq!(budget.merges(), 1);
+    }
+}
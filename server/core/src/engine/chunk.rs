@@ -6,21 +6,19 @@ use serde::{Deserialize, Serialize};
 
 use hashbrown::HashSet;
 
-use std::{
-    fs::File,
-    io::{Read, Write},
-    path::Path,
-};
+use std::io::{Read, Write};
 
-use crate::gen::blocks::{BlockRotation, Blocks};
+use crate::gen::{
+    biomes::Biomes,
+    blocks::{BlockRotation, Blocks, VoxelState},
+};
 
 use super::super::{
-    engine::world::WorldConfig,
-    gen::lights::{LightColor, Lights},
-    network::models::ChunkProtocol,
+    engine::config::WorldConfig, gen::lights::LightColor, network::models::ChunkProtocol,
 };
 
 use server_common::{
+    chunk::ChunkData,
     ndarray::{ndarray, Ndarray},
     types::MeshType,
     vec::{Vec2, Vec3},
@@ -29,38 +27,74 @@ use server_utils::convert;
 
 use super::super::constants::DATA_PADDING;
 
+use super::block_entities::{BlockEntities, BlockEntityData};
 use super::chunks::MeshLevel;
+use super::clock::unix_millis;
+use super::migration::{migrate_chunk, CHUNK_FORMAT_VERSION};
+use super::shards::EntityHandoff;
+use super::storage::Storage;
 
 /// Prototype for storing chunk's meshes and sending them to client
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Meshes {
     pub sub_chunk: i32,
     pub opaque: Option<MeshType>,
+    /// Alpha-tested geometry, such as leaves and plants
     pub transparent: Option<MeshType>,
+    /// Alpha-blended geometry, such as water and glass
+    pub translucent: Option<MeshType>,
 }
 
 /// Prototype for chunk's internal data used to send to client
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ChunkFileData {
+    #[serde(default)]
+    version: u32,
     needs_propagation: bool,
     voxels: String,
     lights: String,
     height_map: String,
+    #[serde(default)]
+    block_entities: Vec<BlockEntityFileData>,
+    /// Missing from chunks saved before edits were tracked
+    #[serde(default)]
+    edited: Option<bool>,
+    /// Unix time in milliseconds, last visited as chunks are saved when unloaded
+    #[serde(default)]
+    saved_at: Option<u64>,
+    /// Entities standing in the chunk when it was saved
+    #[serde(default)]
+    entities: Vec<EntityHandoff>,
+}
+
+/// A block entity within a chunk file
+#[derive(Serialize, Deserialize)]
+struct BlockEntityFileData {
+    voxel: [i32; 3],
+    #[serde(flatten)]
+    entity: BlockEntityData,
 }
 
 /// Base unit column for voxels
 ///
-/// Dimensions are specified as `max_height * chunk_size * max_height`
+/// Dimensions are specified as `chunk_size * max_height * chunk_size`, the column starting at
+/// the world's `min_height`
 #[derive(Clone, Debug)]
 pub struct Chunk {
     pub name: String,
 
     pub coords: Vec2<i32>,
 
-    voxels: Ndarray<u32>,
-    lights: Ndarray<u32>,
+    /// Voxels and lights, laid out as clients mirror them
+    data: ChunkData,
     height_map: Ndarray<u32>,
+    /// Biome id of each voxel column, derived from the world's biomes rather than saved
+    biomes: Ndarray<u32>,
+    block_entities: BlockEntities,
+    /// Entities saved with the chunk, set just before it's saved and taken when it's loaded
+    pub entities: Vec<EntityHandoff>,
 
     pub min: Vec3<i32>,
     pub max: Vec3<i32>,
@@ -72,6 +106,9 @@ pub struct Chunk {
     pub needs_terrain: bool,
     pub needs_decoration: bool,
 
+    /// Whether players edited the chunk, unknown if it was saved before that was tracked
+    pub edited: Option<bool>,
+
     pub is_empty: bool,
     pub is_dirty: bool,
     pub dirty_levels: HashSet<u32>,
@@ -81,21 +118,21 @@ pub struct Chunk {
     pub max_height: usize,
 
     pub meshes: Vec<Meshes>,
-
-    pub file: String,
+    /// Whether the meshes are the ones cached alongside the chunk, so they aren't saved again
+    pub meshes_cached: bool,
 }
 
 impl Chunk {
-    /// Constructor for a chunk. Attempts to load from existing files,
+    /// Constructor for a chunk. Attempts to load from storage if any,
     /// otherwise is marked to be generated.
-    pub fn new(coords: Vec2<i32>, config: &WorldConfig, folder: &Path) -> Self {
+    pub fn new(coords: Vec2<i32>, config: &WorldConfig, storage: Option<&dyn Storage>) -> Self {
         let Vec2(cx, cz) = coords;
 
         let &WorldConfig {
             chunk_size: size,
             dimension,
             max_height,
-            save,
+            min_height,
             ..
         } = config;
 
@@ -103,49 +140,32 @@ impl Chunk {
 
         let name = convert::get_chunk_name(cx, cz);
 
-        let voxels = ndarray(
-            vec![
-                size + DATA_PADDING * 2,
-                max_height as usize,
-                size + DATA_PADDING * 2,
-            ],
-            0,
-        );
-        let lights = ndarray(
-            vec![
-                size + DATA_PADDING * 2,
-                max_height as usize,
-                size + DATA_PADDING * 2,
-            ],
-            0,
-        );
+        let data = ChunkData::new(&coords, size, max_height, min_height);
         let height_map = ndarray(vec![size + DATA_PADDING * 2, size + DATA_PADDING * 2], 0);
+        let biomes = ndarray(vec![size + DATA_PADDING * 2, size + DATA_PADDING * 2], 0);
 
         let coords3 = Vec3(cx, 0, cz);
 
         let paddings = Vec3(DATA_PADDING as i32, 0, DATA_PADDING as i32);
 
-        let min_inner = coords3.scale(size as i32);
+        let min_inner = coords3.scale(size as i32).add(&Vec3(0, min_height, 0));
         let min = min_inner.sub(&paddings);
-        let max_inner =
-            coords3
-                .add(&Vec3(1, 0, 1))
-                .scale(size as i32)
-                .add(&Vec3(0, max_height as i32, 0));
+        let max_inner = coords3.add(&Vec3(1, 0, 1)).scale(size as i32).add(&Vec3(
+            0,
+            min_height + max_height as i32,
+            0,
+        ));
         let max = max_inner.add(&paddings);
 
-        let mut path = folder.to_path_buf();
-        let mut file_name = name.clone();
-        file_name.push_str(".json");
-        path.push(file_name.as_str());
-
         let mut new_chunk = Self {
             name,
 
             coords,
-            voxels,
-            lights,
+            data,
             height_map,
+            biomes,
+            block_entities: BlockEntities::new(),
+            entities: Vec::new(),
 
             min,
             max,
@@ -157,6 +177,8 @@ impl Chunk {
             needs_terrain: true,
             needs_decoration: true,
 
+            edited: Some(false),
+
             is_empty: false,
             is_dirty: true,
             dirty_levels: HashSet::new(),
@@ -166,57 +188,108 @@ impl Chunk {
             dimension,
 
             meshes: Vec::new(),
-
-            file: path.into_os_string().into_string().unwrap(),
+            meshes_cached: false,
         };
 
-        if save {
-            new_chunk.try_load();
+        if let Some(storage) = storage {
+            new_chunk.try_load(storage);
         }
 
         new_chunk
     }
 
-    /// Try to load the chunk from saved data file
-    pub fn try_load(&mut self) {
-        // open a file for reading
-
-        if let Ok(chunk_data) = File::open(&self.file) {
-            let data: ChunkFileData = serde_json::from_reader(chunk_data)
-                .unwrap_or_else(|_| panic!("Couldn't load chunk file: {:?}", self.coords));
-
-            let ChunkFileData {
-                needs_propagation,
-                voxels,
-                lights,
-                height_map,
-            } = data;
-
-            self.needs_saving = false;
-            self.needs_terrain = false;
-            self.needs_decoration = false;
-            self.needs_propagation = needs_propagation;
-
-            let decode_base64 = |base: String| {
-                let decoded = base64::decode(base).unwrap();
-                let mut decoder = Decoder::new(&decoded[..]).unwrap();
-                let mut buf = Vec::new();
-                decoder.read_to_end(&mut buf).unwrap();
-                let mut data = vec![0; buf.len() / 4];
-                LittleEndian::read_u32_into(&buf, &mut data);
-                data
-            };
-
-            self.lights.data = decode_base64(lights);
-            self.voxels.data = decode_base64(voxels);
-            self.height_map.data = decode_base64(height_map);
+    /// Try to load the chunk from its saved data
+    pub fn try_load(&mut self, storage: &dyn Storage) {
+        if let Some(chunk_data) = storage.get_chunk(&self.name) {
+            self.load_data(&chunk_data);
         }
     }
 
-    /// Save the chunk into a JSON compressed file
-    pub fn save(&self) {
-        let mut file = File::create(&self.file).expect("Could not create chunk file.");
+    /// Load the chunk from data in the saved format, such as a chunk handed off by another shard
+    pub fn load_data(&mut self, chunk_data: &[u8]) {
+        if let Err(e) = self.try_load_data(chunk_data) {
+            panic!("Couldn't load chunk file {:?}: {}", self.coords, e);
+        }
+    }
+
+    /// Load the chunk from data in the saved format, leaving it untouched if the data is corrupt
+    ///
+    /// Arrays of the wrong size for the world's dimensions count as corrupt too.
+    pub fn try_load_data(&mut self, chunk_data: &[u8]) -> Result<(), String> {
+        let mut data: serde_json::Value =
+            serde_json::from_slice(chunk_data).map_err(|e| e.to_string())?;
+
+        // files saved by older servers are upgraded, then rewritten on the next save
+        let migrated = migrate_chunk(&mut data)?;
+
+        let data: ChunkFileData = serde_json::from_value(data).map_err(|e| e.to_string())?;
+
+        let ChunkFileData {
+            needs_propagation,
+            voxels,
+            lights,
+            height_map,
+            block_entities,
+            edited,
+            entities,
+            ..
+        } = data;
+
+        let decode_base64 = |field: &str, base: String, len: usize| -> Result<Vec<u32>, String> {
+            let decoded = base64::decode(base).map_err(|e| format!("{}: {}", field, e))?;
+            let mut decoder =
+                Decoder::new(&decoded[..]).map_err(|e| format!("{}: {}", field, e))?;
+            let mut buf = Vec::new();
+            decoder
+                .read_to_end(&mut buf)
+                .map_err(|e| format!("{}: {}", field, e))?;
+
+            if buf.len() != len * 4 {
+                return Err(format!(
+                    "{} has {} values instead of {}",
+                    field,
+                    buf.len() / 4,
+                    len
+                ));
+            }
+
+            let mut data = vec![0; len];
+            LittleEndian::read_u32_into(&buf, &mut data);
+            Ok(data)
+        };
+
+        let lights = decode_base64("lights", lights, self.data.lights.data.len())?;
+        let voxels = decode_base64("voxels", voxels, self.data.voxels.data.len())?;
+        let height_map = decode_base64("heightMap", height_map, self.height_map.data.len())?;
+
+        self.needs_saving = migrated;
+        self.needs_terrain = false;
+        self.needs_decoration = false;
+        self.needs_propagation = needs_propagation;
+        self.edited = edited;
+
+        self.data.lights.data = lights;
+        self.data.voxels.data = voxels;
+        self.height_map.data = height_map;
+        self.entities = entities;
+
+        self.block_entities = block_entities
+            .into_iter()
+            .map(|BlockEntityFileData { voxel, entity }| {
+                (Vec3(voxel[0], voxel[1], voxel[2]), entity)
+            })
+            .collect();
+
+        Ok(())
+    }
+
+    /// Save the chunk as compressed JSON
+    pub fn save(&self, storage: &dyn Storage) {
+        storage.put_chunk(&self.name, self.to_data().as_bytes());
+    }
 
+    /// Get the chunk's data in the saved format
+    pub fn to_data(&self) -> String {
         let to_base_64 = |data: &Vec<u32>| {
             let mut bytes = vec![0; data.len() * 4];
             LittleEndian::write_u32_into(data, &mut bytes);
@@ -228,38 +301,65 @@ impl Chunk {
         };
 
         let data = ChunkFileData {
+            version: CHUNK_FORMAT_VERSION,
             needs_propagation: self.needs_propagation,
-            lights: to_base_64(&self.lights.data),
-            voxels: to_base_64(&self.voxels.data),
+            lights: to_base_64(&self.data.lights.data),
+            voxels: to_base_64(&self.data.voxels.data),
             height_map: to_base_64(&self.height_map.data),
+            block_entities: self
+                .block_entities
+                .iter()
+                .map(|(Vec3(vx, vy, vz), entity)| BlockEntityFileData {
+                    voxel: [*vx, *vy, *vz],
+                    entity: entity.to_owned(),
+                })
+                .collect(),
+            edited: self.edited,
+            saved_at: Some(unix_millis()),
+            entities: self.entities.to_owned(),
         };
 
-        let j = serde_json::to_string(&data).unwrap();
+        serde_json::to_string(&data).unwrap()
+    }
+
+    /// Get the block entity at a voxel within chunk
+    pub fn get_block_entity(&self, vx: i32, vy: i32, vz: i32) -> Option<&BlockEntityData> {
+        self.block_entities.get(&Vec3(vx, vy, vz))
+    }
+
+    /// Attach a block entity to a voxel within chunk, replacing any existing one
+    ///
+    /// Panics if the coordinates are outside of chunk.
+    pub fn set_block_entity(&mut self, vx: i32, vy: i32, vz: i32, entity: BlockEntityData) {
+        assert!(self.contains(vx, vy, vz,));
 
-        file.write_all(j.as_bytes())
-            .expect("Unable to write to chunk file.");
+        self.block_entities.insert(Vec3(vx, vy, vz), entity);
+        self.needs_saving = true;
+    }
+
+    /// Remove the block entity at a voxel within chunk, if any
+    pub fn remove_block_entity(&mut self, vx: i32, vy: i32, vz: i32) -> Option<BlockEntityData> {
+        let removed = self.block_entities.remove(&Vec3(vx, vy, vz));
+
+        if removed.is_some() {
+            self.needs_saving = true;
+        }
+
+        removed
     }
 
     /// Get the raw value of voxel
     ///
     /// Returns 0 if it's outside of the chunk.
     pub fn get_raw_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
-        if !self.contains(vx, vy, vz) {
-            return 0;
-        }
-
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.voxels[&[lx as usize, ly as usize, lz as usize]]
+        self.data.get_raw_voxel(vx, vy, vz)
     }
 
     /// Set the raw value of voxel
     ///
     /// Panics if the coordinates are outside of chunk.
     pub fn set_raw_voxel(&mut self, vx: i32, vy: i32, vz: i32, value: u32) {
-        assert!(self.contains(vx, vy, vz,));
-
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.voxels[&[lx as usize, ly as usize, lz as usize]] = value;
+        self.data.set_raw_voxel(vx, vy, vz, value);
     }
 
     /// Get a voxel type within chunk by voxel coordinates
@@ -313,70 +413,59 @@ impl Chunk {
         self.set_raw_voxel(vx, vy, vz, value);
     }
 
-    /// Get the red light value for voxel by voxel coordinates
+    /// Get a voxel variant within chunk by voxel coordinates
     ///
-    /// Returns 0 if it's outside of the chunk.
-    pub fn get_red_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
-        if !self.contains(vx, vy, vz) {
-            return 0;
-        }
+    /// Panics if it's outside of chunk
+    pub fn get_voxel_variant(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        assert!(self.contains(vx, vy, vz));
 
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.get_local_red_light(lx as usize, ly as usize, lz as usize)
+        Blocks::extract_variant(self.get_raw_voxel(vx, vy, vz))
     }
 
-    /// Set the red light value for voxel by voxel coordinates
+    /// Set a voxel variant within chunk by voxel coordinates
     ///
-    /// Panics if it's outside of the chunk.
-    pub fn set_red_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32) {
-        assert!(self.contains(vx, vy, vz,));
-
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.set_local_red_light(lx as usize, ly as usize, lz as usize, level);
+    /// Panics if the coordinates are outside of chunk
+    pub fn set_voxel_variant(&mut self, vx: i32, vy: i32, vz: i32, variant: u32) {
+        let value = Blocks::insert_variant(self.get_raw_voxel(vx, vy, vz), variant);
+        self.set_raw_voxel(vx, vy, vz, value);
     }
 
-    /// Get the green light value for voxel by voxel coordinates
+    /// Get the rotation, stage and variant of a voxel within chunk by voxel coordinates
     ///
-    /// Returns 0 if it's outside of the chunk.
-    pub fn get_green_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
-        if !self.contains(vx, vy, vz) {
-            return 0;
-        }
+    /// Panics if it's outside of chunk
+    pub fn get_voxel_state(&self, vx: i32, vy: i32, vz: i32) -> VoxelState {
+        assert!(self.contains(vx, vy, vz));
 
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.get_local_green_light(lx as usize, ly as usize, lz as usize)
+        Blocks::extract_state(self.get_raw_voxel(vx, vy, vz))
     }
 
-    /// Set the green light value for voxel by voxel coordinates
+    /// Set the rotation, stage and variant of a voxel within chunk by voxel coordinates
     ///
-    /// Panics if it's outside of the chunk.
-    pub fn set_green_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32) {
-        assert!(self.contains(vx, vy, vz,));
-
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.set_local_green_light(lx as usize, ly as usize, lz as usize, level);
+    /// Panics if the coordinates are outside of chunk
+    pub fn set_voxel_state(&mut self, vx: i32, vy: i32, vz: i32, state: &VoxelState) {
+        let value = Blocks::insert_state(self.get_raw_voxel(vx, vy, vz), state);
+        self.set_raw_voxel(vx, vy, vz, value);
     }
 
-    /// Get the blue light value for voxel by voxel coordinates
+    /// Get the red light value for voxel by voxel coordinates
     ///
     /// Returns 0 if it's outside of the chunk.
-    pub fn get_blue_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
-        if !self.contains(vx, vy, vz) {
-            return 0;
-        }
-
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.get_local_blue_light(lx as usize, ly as usize, lz as usize)
+    pub fn get_red_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.data.get_torch_light(vx, vy, vz, &LightColor::Red)
     }
 
-    /// Set the blue light value for voxel by voxel coordinates
+    /// Get the green light value for voxel by voxel coordinates
     ///
-    /// Panics if it's outside of the chunk.
-    pub fn set_blue_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32) {
-        assert!(self.contains(vx, vy, vz,));
+    /// Returns 0 if it's outside of the chunk.
+    pub fn get_green_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.data.get_torch_light(vx, vy, vz, &LightColor::Green)
+    }
 
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.set_local_blue_light(lx as usize, ly as usize, lz as usize, level);
+    /// Get the blue light value for voxel by voxel coordinates
+    ///
+    /// Returns 0 if it's outside of the chunk.
+    pub fn get_blue_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.data.get_torch_light(vx, vy, vz, &LightColor::Blue)
     }
 
     /// Get the torch light value for voxel by voxel coordinates by color
@@ -384,12 +473,7 @@ impl Chunk {
     /// Returns 0 if it's outside of the chunk.
     #[inline]
     pub fn get_torch_light(&self, vx: i32, vy: i32, vz: i32, color: &LightColor) -> u32 {
-        match color {
-            LightColor::Red => self.get_red_light(vx, vy, vz),
-            LightColor::Green => self.get_green_light(vx, vy, vz),
-            LightColor::Blue => self.get_blue_light(vx, vy, vz),
-            LightColor::None => panic!("Getting light of None"),
-        }
+        self.data.get_torch_light(vx, vy, vz, color)
     }
 
     /// Set the torch light value for voxel by voxel coordinates by color
@@ -397,80 +481,93 @@ impl Chunk {
     /// Panics if it's outside of the chunk.
     #[inline]
     pub fn set_torch_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32, color: &LightColor) {
-        match color {
-            LightColor::Red => self.set_red_light(vx, vy, vz, level),
-            LightColor::Green => self.set_green_light(vx, vy, vz, level),
-            LightColor::Blue => self.set_blue_light(vx, vy, vz, level),
-            LightColor::None => panic!("Setting light of None"),
-        }
+        self.data.set_torch_light(vx, vy, vz, level, color);
     }
 
     /// Get the sunlight value for voxel by voxel coordinates
     ///
     /// Returns 0 if it's not within the chunk.
     pub fn get_sunlight(&self, vx: i32, vy: i32, vz: i32) -> u32 {
-        if !self.contains(vx, vy, vz) {
-            return 0;
-        }
-
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.get_local_sunlight(lx as usize, ly as usize, lz as usize)
+        self.data.get_sunlight(vx, vy, vz)
     }
 
     /// Set the sunlight value for voxel by voxel coordinates
     ///
     /// Panics if it's outside of the chunk.
     pub fn set_sunlight(&mut self, vx: i32, vy: i32, vz: i32, level: u32) {
-        assert!(self.contains(vx, vy, vz,));
-
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-        self.set_local_sunlight(lx as usize, ly as usize, lz as usize, level)
+        self.data.set_sunlight(vx, vy, vz, level);
     }
 
-    /// Get the max height of a voxel column
+    /// Get the max height of a voxel column, as the y of its highest voxel
     ///
-    /// Returns `max_height` if it's not within the chunk.
-    pub fn get_max_height(&self, vx: i32, vz: i32) -> u32 {
-        if !self.contains(vx, 0, vz) {
-            return self.max_height as u32;
+    /// Returns the top of the world if it's not within the chunk.
+    pub fn get_max_height(&self, vx: i32, vz: i32) -> i32 {
+        if !self.contains(vx, self.min.1, vz) {
+            return self.max.1;
         }
 
-        let Vec3(lx, _, lz) = self.to_local(vx, 0, vz);
-        self.height_map[&[lx as usize, lz as usize]]
+        // heights are stored up from the bottom of the world
+        let Vec3(lx, _, lz) = self.to_local(vx, self.min.1, vz);
+        self.height_map[&[lx as usize, lz as usize]] as i32 + self.min.1
     }
 
     /// Set the max height of a voxel column
     ///
     /// Panics if it's not within the chunk.
-    pub fn set_max_height(&mut self, vx: i32, vz: i32, height: u32) {
-        assert!(self.contains(vx, 0, vz,));
+    pub fn set_max_height(&mut self, vx: i32, vz: i32, height: i32) {
+        assert!(self.contains(vx, self.min.1, vz,));
+
+        let Vec3(lx, _, lz) = self.to_local(vx, self.min.1, vz);
+        self.height_map[&[lx as usize, lz as usize]] = (height - self.min.1).max(0) as u32;
+    }
+
+    /// Get the biome id of a voxel column
+    ///
+    /// Returns 0 if it's not within the chunk.
+    pub fn get_biome_id(&self, vx: i32, vz: i32) -> u32 {
+        if !self.contains(vx, self.min.1, vz) {
+            return 0;
+        }
+
+        let Vec3(lx, _, lz) = self.to_local(vx, self.min.1, vz);
+        self.biomes[&[lx as usize, lz as usize]]
+    }
+
+    /// Fill in the biome id of every voxel column, padding included
+    pub fn fill_biomes(&mut self, biomes: &Biomes) {
+        let Vec3(min_x, _, min_z) = self.min;
+        let Vec3(max_x, _, max_z) = self.max;
 
-        let Vec3(lx, _, lz) = self.to_local(vx, 0, vz);
-        self.height_map[&[lx as usize, lz as usize]] = height;
+        for vx in min_x..max_x {
+            for vz in min_z..max_z {
+                let Vec3(lx, _, lz) = self.to_local(vx, self.min.1, vz);
+                self.biomes[&[lx as usize, lz as usize]] = biomes.get_biome_id(vx, vz);
+            }
+        }
     }
 
     /// Getter the entire voxel ndarray
     #[inline]
     pub fn get_voxels(&self) -> &Ndarray<u32> {
-        &self.voxels
+        &self.data.voxels
     }
 
     /// Setter the entire voxel ndarray
     #[inline]
     pub fn set_voxels(&mut self, data: Ndarray<u32>) {
-        self.voxels = data;
+        self.data.voxels = data;
     }
 
     /// Getter for the entire lights ndarray
     #[inline]
     pub fn get_lights(&self) -> &Ndarray<u32> {
-        &self.lights
+        &self.data.lights
     }
 
     /// Setter for the entire lights ndarray
     #[inline]
     pub fn set_lights(&mut self, data: Ndarray<u32>) {
-        self.lights = data;
+        self.data.lights = data;
     }
 
     /// Getter for the entire height map
@@ -487,14 +584,15 @@ impl Chunk {
 
     /// Calculate and mark a sub-chunk as dirty at a certain height
     pub fn calc_dirty_levels(&mut self, vy: i32, max_height: u32, sub_chunks: u32) {
-        let vy = vy as u32;
+        let vy = (vy - self.min.1) as u32;
         let unit = max_height / sub_chunks;
         let level = vy / unit;
 
         self.dirty_levels.insert(level);
-        if vy % sub_chunks == 0 && level >= 1 {
+        // the voxels on a sub-chunk's edge are in the padding of the one next to it
+        if vy.is_multiple_of(unit) && level >= 1 {
             self.dirty_levels.insert(level - 1);
-        } else if vy % sub_chunks == sub_chunks - 1 && level < sub_chunks - 1 {
+        } else if vy % unit == unit - 1 && level < sub_chunks - 1 {
             self.dirty_levels.insert(level + 1);
         }
     }
@@ -526,85 +624,42 @@ impl Chunk {
                 None
             },
             voxels: if needs_voxels {
-                Some(self.voxels.to_owned())
+                Some(self.data.voxels.to_owned())
             } else {
                 None
             },
             lights: if needs_lights {
-                Some(self.lights.to_owned())
+                Some(self.data.lights.to_owned())
+            } else {
+                None
+            },
+            biomes: if needs_voxels {
+                Some(self.biomes.to_owned())
+            } else {
+                None
+            },
+            block_entities: if needs_voxels {
+                Some(
+                    self.block_entities
+                        .iter()
+                        .map(|(voxel, entity)| entity.to_protocol(voxel))
+                        .collect(),
+                )
             } else {
                 None
             },
         }
     }
 
-    /// Get the red light value locally
-    #[inline]
-    fn get_local_red_light(&self, lx: usize, ly: usize, lz: usize) -> u32 {
-        Lights::extract_red_light(self.lights[&[lx, ly, lz]])
-    }
-
-    /// Set the red light value locally
-    #[inline]
-    fn set_local_red_light(&mut self, lx: usize, ly: usize, lz: usize, level: u32) {
-        self.lights[&[lx, ly, lz]] = Lights::insert_red_light(self.lights[&[lx, ly, lz]], level);
-    }
-
-    /// Get the green light value locally
-    #[inline]
-    fn get_local_green_light(&self, lx: usize, ly: usize, lz: usize) -> u32 {
-        Lights::extract_green_light(self.lights[&[lx, ly, lz]])
-    }
-
-    /// Set the green light value locally
-    #[inline]
-    fn set_local_green_light(&mut self, lx: usize, ly: usize, lz: usize, level: u32) {
-        self.lights[&[lx, ly, lz]] = Lights::insert_green_light(self.lights[&[lx, ly, lz]], level);
-    }
-
-    /// Get the blue light value locally
-    #[inline]
-    fn get_local_blue_light(&self, lx: usize, ly: usize, lz: usize) -> u32 {
-        Lights::extract_blue_light(self.lights[&[lx, ly, lz]])
-    }
-
-    /// Set the blue light value locally
-    #[inline]
-    fn set_local_blue_light(&mut self, lx: usize, ly: usize, lz: usize, level: u32) {
-        self.lights[&[lx, ly, lz]] = Lights::insert_blue_light(self.lights[&[lx, ly, lz]], level);
-    }
-
-    /// Get the sunlight value locally
-    #[inline]
-    fn get_local_sunlight(&self, lx: usize, ly: usize, lz: usize) -> u32 {
-        Lights::extract_sunlight(self.lights[&[lx, ly, lz]])
-    }
-
-    /// Set the sunlight value locally
-    #[inline]
-    fn set_local_sunlight(&mut self, lx: usize, ly: usize, lz: usize, level: u32) {
-        self.lights[&[lx, ly, lz]] = Lights::insert_sunlight(self.lights[&[lx, ly, lz]], level);
-    }
-
     /// Convert voxel coordinates to local chunk coordinates
     #[inline]
     fn to_local(&self, vx: i32, vy: i32, vz: i32) -> Vec3<i32> {
-        Vec3(vx, vy, vz).sub(&self.min)
+        self.data.to_local(vx, vy, vz)
     }
 
     /// Returns whether a set of voxel coordinates is within the chunk padding.
     #[inline]
     fn contains(&self, vx: i32, vy: i32, vz: i32) -> bool {
-        let size = self.size as i32;
-        let max_height = self.max_height as i32;
-
-        let Vec3(lx, ly, lz) = self.to_local(vx, vy, vz);
-
-        lx >= 0
-            && lx < size + DATA_PADDING as i32 * 2
-            && ly >= 0
-            && ly < max_height
-            && lz >= 0
-            && lz < size + DATA_PADDING as i32 * 2
+        self.data.contains(vx, vy, vz)
     }
 }
@@ -1,25 +1,40 @@
 use hashbrown::{HashMap, HashSet};
-use std::{collections::VecDeque, path::PathBuf, sync::Arc};
+use std::{collections::VecDeque, path::PathBuf, sync::Arc, time::Instant};
 
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use log::{debug, info};
 use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
 
-use crate::gen::{biomes::Biomes, blocks::BlockRotation};
+use crate::gen::{
+    biomes::Biomes,
+    blocks::{BlockRotation, VoxelState},
+};
 
 use super::super::{
-    constants::{LEVEL_SEED, VOXEL_NEIGHBORS},
+    constants::{DIMENSIONS_FILE, LEVEL_SEED},
     engine::{
+        block_entities::BlockEntityData,
+        budget::TickBudget,
         chunk::{Chunk, Meshes},
+        config::{Dimensions, WorldConfig},
+        journal::{Journal, JournalEntry},
+        mesh_cache::{load_meshes, mesh_salt, save_meshes},
+        migration::upgrade_chunks,
+        observers::{Change, Observers},
+        physics::BlockModifiers,
+        profiler::Profiler,
         registry::Registry,
+        shards::EntityHandoff,
         space::Space,
-        world::WorldConfig,
+        storage::{open_storage, Storage, CHUNKS_FOLDER},
+        tags::CLIMBABLE,
     },
     gen::{
+        blocks::Blocks,
         builder::{Builder, VoxelUpdate},
         generator::Generator,
-        lights::{LightColor, LightNode, Lights},
-        mesher::Mesher,
+        lights::{self, LightColor, LightSpace, Lights},
+        mesher::{GeometryType, Mesher},
     },
 };
 
@@ -42,13 +57,31 @@ pub enum MeshLevel {
 pub struct Chunks {
     pub root_folder: PathBuf,
     pub chunk_folder: PathBuf,
+    /// Where chunks and world data are saved, none if the world isn't saved
+    pub storage: Option<Arc<dyn Storage>>,
 
     pub chunk_cache: HashSet<Vec2<i32>>,
+    /// Meshed chunks an edit made stale, their own or one in their padding, remeshed once a tick
+    pub invalidated: HashSet<Vec2<i32>>,
     pub to_generate: Vec<Chunk>,
     pub generating: HashSet<Vec2<i32>>,
     pub to_mesh: VecDeque<Vec2<i32>>,
     pub meshing: HashSet<Vec2<i32>>,
     pub activities: VecDeque<Vec2<i32>>,
+    /// Chunks unloaded since the last tick, saved once the world adds the entities standing in them
+    pub unloaded: Vec<Chunk>,
+    /// Entities of chunks loaded since the last tick, for the world to spawn
+    pub loaded_entities: Vec<EntityHandoff>,
+    /// Chunks loaded or whose surface changed, for the world map to draw again
+    pub surface_changed: HashSet<Vec2<i32>>,
+    /// Center chunk and view radius (in chunks) of every player, refreshed each tick
+    pub interests: Vec<(Vec2<i32>, i16)>,
+    /// Regions watched for voxel changes, told of every change as it's made
+    pub observers: Observers,
+    /// Limits how many chunks are merged back per tick, based on how long ticks take
+    pub budget: TickBudget,
+    /// Shared with the world, timing the lighting, meshing and generation done off-thread
+    pub profiler: Profiler,
 
     pub config: Arc<WorldConfig>,
     pub registry: Arc<Registry>,
@@ -58,6 +91,12 @@ pub struct Chunks {
     caching: bool,
     chunks: HashMap<Vec2<i32>, Chunk>,
     update_queue: HashMap<Vec2<i32>, Vec<VoxelUpdate>>,
+    /// Chunks generated or meshed off-thread, waiting on the budget to be merged back
+    finished: Vec<Chunk>,
+    /// Write-ahead log of the edits since the last save
+    journal: Journal,
+    /// Hash of the registry and config the cached meshes have to be made with
+    mesh_salt: u64,
     noise: Noise,
 
     pool: ThreadPool,
@@ -87,22 +126,69 @@ impl Chunks {
         root_folder.push(world_name);
 
         let mut chunk_folder = root_folder.clone();
-        chunk_folder.push("chunks");
+        chunk_folder.push(CHUNKS_FOLDER);
+
+        let storage = if config.save {
+            let storage =
+                open_storage(config.storage, config.object_storage.as_ref(), &root_folder);
+
+            upgrade_chunks(world_name, storage.as_ref());
+
+            // chunks stored in another shape can't be loaded, so the config can't change under them
+            let dimensions = config.dimensions();
+            match storage
+                .get_metadata(DIMENSIONS_FILE)
+                .and_then(|data| serde_json::from_slice::<Dimensions>(&data).ok())
+            {
+                Some(saved)
+                    if saved.chunk_size != dimensions.chunk_size
+                        || saved.max_height != dimensions.max_height
+                        || saved.min_height != dimensions.min_height =>
+                {
+                    panic!(
+                        "World \"{}\" was saved with chunks of {}x{} from y {}, not {}x{} from y {}. Change the config back or start a new world.",
+                        world_name,
+                        saved.chunk_size,
+                        saved.max_height,
+                        saved.min_height,
+                        dimensions.chunk_size,
+                        dimensions.max_height,
+                        dimensions.min_height
+                    );
+                }
+                _ => {
+                    storage.put_metadata(DIMENSIONS_FILE, &serde_json::to_vec(&dimensions).unwrap())
+                }
+            }
 
-        if config.save {
-            std::fs::create_dir_all(&chunk_folder).expect("Unable to create chunks directory...");
             info!(
                 "Storage for world \"{}\" is at \"./{}/{}\".",
                 world_name, config.chunk_root, world_name
             );
+
+            Some(storage)
         } else {
             info!("World \"{}\" is temporarily saved in memory.", world_name);
-        }
+            None
+        };
+
+        let mesh_salt = mesh_salt(&registry, &config);
+        let budget = TickBudget::new(config.server_tick_rate, config.max_per_thread * 2);
+        let journal = Journal::new(
+            if config.save {
+                Some(&root_folder)
+            } else {
+                None
+            },
+            config.chunk_size,
+        );
 
         Chunks {
             root_folder,
             chunk_folder,
+            storage,
             chunk_cache: HashSet::new(),
+            invalidated: HashSet::new(),
 
             config: Arc::new(config),
             registry: Arc::new(registry.to_owned()),
@@ -114,10 +200,20 @@ impl Chunks {
             to_mesh: VecDeque::new(),
             meshing: HashSet::new(),
             activities: VecDeque::new(),
+            unloaded: Vec::new(),
+            loaded_entities: Vec::new(),
+            surface_changed: HashSet::new(),
+            interests: Vec::new(),
+            observers: Observers::new(),
+            budget,
+            profiler: Profiler::new(),
 
             caching: false,
             chunks: HashMap::new(),
             update_queue: HashMap::new(),
+            finished: Vec::new(),
+            journal,
+            mesh_salt,
             noise: Noise::new(LEVEL_SEED),
 
             pool: ThreadPoolBuilder::new()
@@ -143,7 +239,16 @@ impl Chunks {
     /// are then sent to another thread to be meshed (lit and culled).
     /// 4. Checks if any thread is waiting to return a meshed chunk. If so, add
     /// them back into `chunks` itself.
+    ///
+    /// Returned chunks are merged back nearest to players first, only as many as the
+    /// tick budget allows.
     pub fn tick(&mut self) {
+        // the edits accepted since the last tick are made durable
+        self.journal.sync();
+
+        // unloaded chunks nobody added entities to are saved as they are
+        self.save_unloaded();
+
         if !self.to_mesh.is_empty() {
             let to_mesh = self
                 .to_mesh
@@ -165,37 +270,64 @@ impl Chunks {
             let sender = Arc::clone(&self.mesh_sender);
             let config = Arc::clone(&self.config);
             let registry = Arc::clone(&self.registry);
+            let profiler = self.profiler.clone();
 
             self.pool.spawn(move || {
                 let meshed = to_mesh
                     .into_iter()
                     .map(|(mut chunk, space)| {
                         if chunk.needs_propagation {
-                            let lights = Lights::calc_light(&space, &registry, &config);
+                            let started = Instant::now();
+                            let lights = lights::calc_light(&space, &registry, &config);
+                            profiler.record("lighting", started.elapsed());
+
                             chunk.needs_propagation = false;
                             chunk.needs_saving = true;
                             chunk.set_lights(lights);
                         }
 
+                        let started = Instant::now();
                         let sub_chunks = config.sub_chunks;
 
                         chunk.meshes = Vec::new();
+                        chunk.meshes_cached = false;
 
                         for sub_chunk in 0..sub_chunks {
-                            let opaque =
-                                Mesher::mesh_chunk(&chunk, false, sub_chunk, &config, &registry);
-                            let transparent =
-                                Mesher::mesh_chunk(&chunk, true, sub_chunk, &config, &registry);
+                            let opaque = Mesher::mesh_chunk(
+                                &chunk,
+                                GeometryType::Opaque,
+                                sub_chunk,
+                                &config,
+                                &registry,
+                            );
+                            let transparent = Mesher::mesh_chunk(
+                                &chunk,
+                                GeometryType::Cutout,
+                                sub_chunk,
+                                &config,
+                                &registry,
+                            );
+                            let translucent = Mesher::mesh_chunk(
+                                &chunk,
+                                GeometryType::Translucent,
+                                sub_chunk,
+                                &config,
+                                &registry,
+                            );
 
                             chunk.meshes.push(Meshes {
                                 opaque,
                                 transparent,
+                                translucent,
                                 sub_chunk: sub_chunk as i32,
                             });
 
                             chunk.is_dirty = false;
                         }
 
+                        chunk.dirty_levels.clear();
+                        profiler.record("mesher", started.elapsed());
+
                         chunk
                     })
                     .collect();
@@ -216,13 +348,16 @@ impl Chunks {
             let config = Arc::clone(&self.config);
             let registry = Arc::clone(&self.registry);
             let biomes = Arc::clone(&self.biomes);
+            let profiler = self.profiler.clone();
 
             self.pool.spawn(move || {
                 let chunks: Vec<Chunk> = chunks
                     .into_iter()
                     .map(|mut chunk| {
+                        let started = Instant::now();
                         Generator::generate_chunk(&mut chunk, &registry, &biomes, &config);
                         Generator::generate_chunk_height_map(&mut chunk, &registry, &config);
+                        profiler.record("generator", started.elapsed());
                         chunk
                     })
                     .collect();
@@ -230,16 +365,45 @@ impl Chunks {
             });
         }
 
-        if let Ok(chunks) = self.mesh_receiver.try_recv() {
-            chunks.into_iter().for_each(|c| {
-                self.add_chunk(c);
-            });
+        while let Ok(mut chunks) = self.mesh_receiver.try_recv() {
+            self.finished.append(&mut chunks);
         }
 
-        if let Ok(chunks) = self.gen_receiver.try_recv() {
-            chunks.into_iter().for_each(|c| {
-                self.add_chunk(c);
-            });
+        while let Ok(mut chunks) = self.gen_receiver.try_recv() {
+            self.finished.append(&mut chunks);
+        }
+
+        self.merge_finished();
+    }
+
+    /// Merge back the finished chunks nearest to any player, up to the tick budget
+    fn merge_finished(&mut self) {
+        if self.finished.is_empty() {
+            return;
+        }
+
+        let interests = &self.interests;
+        let distance = |coords: &Vec2<i32>| {
+            interests
+                .iter()
+                .map(|(center, _)| {
+                    let dx = coords.0 - center.0;
+                    let dz = coords.1 - center.1;
+                    dx * dx + dz * dz
+                })
+                .min()
+                .unwrap_or(0)
+        };
+
+        // furthest first, so the nearest ones are popped off the end
+        self.finished
+            .sort_by_cached_key(|chunk| std::cmp::Reverse(distance(&chunk.coords)));
+
+        for _ in 0..self.budget.merges() {
+            match self.finished.pop() {
+                Some(chunk) => self.add_chunk(chunk),
+                None => break,
+            }
         }
     }
 
@@ -271,29 +435,14 @@ impl Chunks {
         // if it's not urgent, then will be sent to other thread to mesh
         urgent: bool,
     ) -> Option<&Chunk> {
-        let chunk = self.get_chunk(coords);
-        let neighbors = self.neighbors(coords);
-
-        match chunk {
-            None => {
-                return None;
-            }
-            Some(chunk) => {
-                if chunk.needs_terrain
-                    || chunk.needs_decoration
-                    || neighbors.iter().any(|&c| c.is_none())
-                    || neighbors.iter().any(|&c| c.unwrap().needs_decoration)
-                {
-                    return None;
-                }
-                chunk
-            }
-        };
+        if !self.is_ready(coords) {
+            return None;
+        }
 
         if urgent {
             self.remesh_chunk(coords, remesh_level);
         } else {
-            let chunk = chunk.unwrap();
+            let chunk = self.get_chunk(coords).unwrap();
             if chunk.is_dirty {
                 let coords = chunk.coords.to_owned();
                 if !self.to_mesh.contains(&coords) && !self.meshing.contains(&coords) {
@@ -306,6 +455,49 @@ impl Chunks {
         self.get_chunk(coords)
     }
 
+    /// Whether a chunk and the chunks around it are generated and decorated, ready to mesh
+    fn is_ready(&self, coords: &Vec2<i32>) -> bool {
+        match self.get_chunk(coords) {
+            Some(chunk) if !chunk.needs_terrain && !chunk.needs_decoration => self
+                .neighbors(coords)
+                .iter()
+                .all(|&c| c.is_some_and(|c| !c.needs_decoration)),
+            _ => false,
+        }
+    }
+
+    /// Queue the chunks within a radius of 0,0 that aren't meshed yet to be meshed off-thread,
+    /// nearest first, so that the first players to join don't wait on them
+    ///
+    /// Returns how many chunks were queued.
+    pub fn warm(&mut self, radius: i16) -> usize {
+        let radius = radius as i32;
+        let mut warming = vec![];
+
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                let coords = Vec2(x, z);
+
+                if x * x + z * z > radius * radius
+                    || !self.is_ready(&coords)
+                    || !self.get_chunk(&coords).unwrap().is_dirty
+                    || self.to_mesh.contains(&coords)
+                    || self.meshing.contains(&coords)
+                {
+                    continue;
+                }
+
+                warming.push(coords);
+            }
+        }
+
+        warming.sort_by_key(|Vec2(x, z)| x * x + z * z);
+
+        let count = warming.len();
+        self.to_mesh.extend(warming);
+        count
+    }
+
     /// To preload chunks surrounding 0,0
     pub fn preload(&mut self, width: i16) {
         self.generate(&Vec2(0, 0), width, true);
@@ -326,14 +518,116 @@ impl Chunks {
         self.chunk_cache.clear();
     }
 
+    /// Remesh the invalidated chunks at their dirty levels, returning the ones remeshed
+    ///
+    /// However many edits landed on a chunk since the last tick, it's remeshed once. Chunks
+    /// whose neighbors aren't ready yet stay invalidated until they are.
+    pub fn remesh_invalidated(&mut self) -> Vec<(Vec2<i32>, MeshLevel)> {
+        let mut remeshed = vec![];
+
+        for coords in self.invalidated.drain().collect::<Vec<_>>() {
+            let levels = match self.get_chunk(&coords) {
+                Some(chunk) => chunk.dirty_levels.clone(),
+                None => continue,
+            };
+            let level = MeshLevel::Levels(levels);
+
+            if self.get(&coords, &level, true).is_some() {
+                remeshed.push((coords, level));
+            } else {
+                self.invalidated.insert(coords);
+            }
+        }
+
+        remeshed
+    }
+
+    /// Swap the registry for one reloaded, chunks generated from then on using it too
+    pub fn set_registry(&mut self, registry: Registry) {
+        self.builder = Arc::new(Builder::new(registry.to_owned(), Noise::new(LEVEL_SEED)));
+        self.mesh_salt = mesh_salt(&registry, &self.config);
+        self.registry = Arc::new(registry);
+
+        // the cached meshes were made with the old blocks, so the ones still fine are cached anew
+        self.chunks
+            .values_mut()
+            .for_each(|chunk| chunk.meshes_cached = false);
+    }
+
+    /// Invalidate every meshed chunk holding one of the blocks, returning how many there are
+    pub fn invalidate_blocks(&mut self, ids: &[u32]) -> usize {
+        if ids.is_empty() {
+            return 0;
+        }
+
+        let ids = ids.iter().copied().collect::<HashSet<_>>();
+        let sub_chunks = self.config.sub_chunks;
+        let mut invalidated = vec![];
+
+        for (coords, chunk) in self.chunks.iter_mut() {
+            let holds = chunk
+                .get_voxels()
+                .data
+                .iter()
+                .any(|&voxel| ids.contains(&Blocks::extract_id(voxel)));
+
+            if holds && !chunk.meshes.is_empty() {
+                chunk.dirty_levels.extend(0..sub_chunks);
+                chunk.is_dirty = true;
+                invalidated.push(coords.to_owned());
+            }
+        }
+
+        let count = invalidated.len();
+        self.invalidated.extend(invalidated);
+        count
+    }
+
     /// Save all chunks to their according JSON files
-    pub fn save(&self) {
-        // saving the chunks
-        self.chunks.values().for_each(|chunk| {
+    pub fn save(&mut self) {
+        self.save_unloaded();
+
+        let storage = match &self.storage {
+            Some(storage) => storage,
+            None => return,
+        };
+
+        // saving the chunks, the ones owned by other shards are theirs to save
+        let config = &self.config;
+        let salt = self.mesh_salt;
+        self.chunks.values_mut().for_each(|chunk| {
+            if !config.shards.is_local(&chunk.coords) {
+                return;
+            }
+
             if chunk.needs_saving {
-                chunk.save();
+                chunk.save(storage.as_ref());
             }
-        })
+
+            if config.cache_meshes {
+                save_meshes(chunk, storage.as_ref(), salt);
+            }
+        });
+        storage.flush();
+
+        // every journaled edit is now in a chunk file
+        self.journal.truncate();
+    }
+
+    /// Save the chunks unloaded since the last tick
+    pub fn save_unloaded(&mut self) {
+        if let Some(storage) = &self.storage {
+            let cache_meshes = self.config.cache_meshes;
+            let salt = self.mesh_salt;
+
+            self.unloaded.drain(..).for_each(|mut chunk| {
+                chunk.save(storage.as_ref());
+
+                if cache_meshes {
+                    save_meshes(&mut chunk, storage.as_ref(), salt);
+                }
+            });
+        }
     }
 
     /// Unload chunks when too many chunks are loaded.
@@ -365,9 +659,27 @@ impl Chunks {
                 for sub_chunk in 0..sub_chunks {
                     let chunk = self.get_chunk(coords).unwrap();
 
-                    let opaque = Mesher::mesh_chunk(chunk, false, sub_chunk, &config, &registry);
-                    let transparent =
-                        Mesher::mesh_chunk(chunk, true, sub_chunk, &config, &registry);
+                    let opaque = Mesher::mesh_chunk(
+                        chunk,
+                        GeometryType::Opaque,
+                        sub_chunk,
+                        &config,
+                        &registry,
+                    );
+                    let transparent = Mesher::mesh_chunk(
+                        chunk,
+                        GeometryType::Cutout,
+                        sub_chunk,
+                        &config,
+                        &registry,
+                    );
+                    let translucent = Mesher::mesh_chunk(
+                        chunk,
+                        GeometryType::Translucent,
+                        sub_chunk,
+                        &config,
+                        &registry,
+                    );
 
                     // borrow again in mutable form
                     let chunk = self.get_chunk_mut(coords).unwrap();
@@ -375,6 +687,7 @@ impl Chunks {
                     chunk.meshes.push(Meshes {
                         opaque,
                         transparent,
+                        translucent,
                         sub_chunk: sub_chunk as i32,
                     });
 
@@ -385,15 +698,34 @@ impl Chunks {
                 for &sub_chunk in ls {
                     let chunk = self.get_chunk_mut(coords).unwrap();
 
-                    let opaque = Mesher::mesh_chunk(chunk, false, sub_chunk, &config, &registry);
-                    let transparent =
-                        Mesher::mesh_chunk(chunk, true, sub_chunk, &config, &registry);
+                    let opaque = Mesher::mesh_chunk(
+                        chunk,
+                        GeometryType::Opaque,
+                        sub_chunk,
+                        &config,
+                        &registry,
+                    );
+                    let transparent = Mesher::mesh_chunk(
+                        chunk,
+                        GeometryType::Cutout,
+                        sub_chunk,
+                        &config,
+                        &registry,
+                    );
+                    let translucent = Mesher::mesh_chunk(
+                        chunk,
+                        GeometryType::Translucent,
+                        sub_chunk,
+                        &config,
+                        &registry,
+                    );
 
                     let chunk = self.get_chunk_mut(coords).unwrap();
 
                     chunk.meshes[sub_chunk as usize] = Meshes {
                         opaque,
                         transparent,
+                        translucent,
                         sub_chunk: sub_chunk as i32,
                     };
 
@@ -403,6 +735,11 @@ impl Chunks {
             _ => {}
         };
 
+        if let Some(chunk) = self.get_chunk_mut(coords) {
+            chunk.dirty_levels.clear();
+            chunk.meshes_cached = false;
+        }
+
         // debug!("Meshing took a total of {:?}", start.elapsed());
     }
 
@@ -437,7 +774,8 @@ impl Chunks {
 
                     if index.is_none() {
                         let mut new_chunk =
-                            Chunk::new(coords.to_owned(), &self.config, &self.chunk_folder);
+                            Chunk::new(coords.to_owned(), &self.config, self.storage.as_deref());
+                        new_chunk.fill_biomes(&self.biomes);
 
                         if let Some(updates) = self.update_queue.remove(&coords) {
                             for u in updates {
@@ -445,6 +783,22 @@ impl Chunks {
                             }
                         }
 
+                        // a chunk loaded as it was saved can take the meshes it had then
+                        if let Some(storage) = &self.storage {
+                            if self.config.cache_meshes
+                                && !new_chunk.needs_terrain
+                                && !new_chunk.needs_decoration
+                                && !new_chunk.needs_propagation
+                            {
+                                load_meshes(
+                                    &mut new_chunk,
+                                    storage.as_ref(),
+                                    self.mesh_salt,
+                                    self.config.sub_chunks,
+                                );
+                            }
+                        }
+
                         if new_chunk.needs_terrain {
                             if !self.generating.contains(&new_chunk.coords) {
                                 to_generate.push(new_chunk);
@@ -519,10 +873,10 @@ impl Chunks {
 
         for updates in to_decorate_updates.iter() {
             for u in updates {
-                let h = self.get_max_height(u.voxel.0, u.voxel.2) as i32;
+                let h = self.get_max_height(u.voxel.0, u.voxel.2);
                 self.set_voxel_by_voxel(u.voxel.0, u.voxel.1, u.voxel.2, u.id);
                 if u.voxel.1 > h && Generator::check_height(u.id, &self.registry) {
-                    self.set_max_height(u.voxel.0, u.voxel.2, u.voxel.1 as u32);
+                    self.set_max_height(u.voxel.0, u.voxel.2, u.voxel.1);
                 }
             }
         }
@@ -581,6 +935,57 @@ impl Chunks {
         self.get_chunk_mut(&coords)
     }
 
+    /// Get the block entity at a voxel coordinate
+    pub fn get_block_entity_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> Option<&BlockEntityData> {
+        self.get_chunk_by_voxel(vx, vy, vz)
+            .and_then(|chunk| chunk.get_block_entity(vx, vy, vz))
+    }
+
+    /// Attach a block entity to a voxel coordinate, returns false if the chunk isn't loaded
+    pub fn set_block_entity_by_voxel(
+        &mut self,
+        vx: i32,
+        vy: i32,
+        vz: i32,
+        entity: BlockEntityData,
+    ) -> bool {
+        if let Some(chunk) = self.get_chunk_by_voxel_mut(vx, vy, vz) {
+            chunk.set_block_entity(vx, vy, vz, entity.to_owned());
+            self.observers.notify(
+                Vec3(vx, vy, vz),
+                Change::BlockEntity(Some(entity.to_owned())),
+            );
+            self.journal.append(&JournalEntry::BlockEntity {
+                voxel: [vx, vy, vz],
+                entity: Some(entity),
+            });
+            return true;
+        }
+
+        false
+    }
+
+    /// Remove the block entity at a voxel coordinate, if any
+    pub fn remove_block_entity_by_voxel(
+        &mut self,
+        vx: i32,
+        vy: i32,
+        vz: i32,
+    ) -> Option<BlockEntityData> {
+        let removed = self
+            .get_chunk_by_voxel_mut(vx, vy, vz)
+            .and_then(|chunk| chunk.remove_block_entity(vx, vy, vz))?;
+
+        self.observers
+            .notify(Vec3(vx, vy, vz), Change::BlockEntity(None));
+        self.journal.append(&JournalEntry::BlockEntity {
+            voxel: [vx, vy, vz],
+            entity: None,
+        });
+
+        Some(removed)
+    }
+
     /// Get the voxel type at a voxel coordinate
     pub fn get_voxel_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> u32 {
         let chunk = self.get_chunk_by_voxel(vx, vy, vz);
@@ -606,18 +1011,14 @@ impl Chunks {
     /// 1. Sets the neighboring chunk's padding data if the coordinates are on a chunk edge.
     /// 2. Calculates the chunk's and the neighbors' dirty sub-chunk levels
     pub fn set_voxel_by_voxel(&mut self, vx: i32, vy: i32, vz: i32, id: u32) {
-        let max_height = self.config.max_height;
-        if vy as u32 >= max_height {
+        if !self.config.contains_height(vy) {
             return;
         }
 
-        let sub_chunks = self.config.sub_chunks;
         let chunk = self.get_chunk_by_voxel_mut(vx, vy, vz);
 
         if let Some(chunk) = chunk {
             chunk.set_voxel(vx, vy, vz, id);
-            chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-            chunk.is_dirty = true;
         } else {
             let updates = self
                 .update_queue
@@ -635,8 +1036,6 @@ impl Chunks {
 
             if let Some(n_chunk) = n_chunk {
                 n_chunk.set_voxel(vx, vy, vz, id);
-                n_chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-                n_chunk.is_dirty = true;
             } else {
                 let updates = self
                     .update_queue
@@ -647,7 +1046,10 @@ impl Chunks {
                     id,
                 });
             }
-        })
+        });
+
+        self.observers.notify(Vec3(vx, vy, vz), Change::Voxel(id));
+        self.invalidate_voxel(vx, vy, vz);
     }
 
     /// Get the voxel rotation at a voxel coordinate
@@ -673,20 +1075,16 @@ impl Chunks {
         vz: i32,
         rotation: &BlockRotation,
     ) {
-        let max_height = self.config.max_height;
-        if vy as u32 >= max_height {
+        if !self.config.contains_height(vy) {
             return;
         }
 
-        let sub_chunks = self.config.sub_chunks;
         let chunk = self.get_chunk_by_voxel_mut(vx, vy, vz);
 
         // TODO: update chunks data for unloaded chunks.
 
         if let Some(chunk) = chunk {
             chunk.set_voxel_rotation(vx, vy, vz, rotation);
-            chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-            chunk.is_dirty = true;
         }
 
         let neighbors = self.get_neighbor_chunk_coords(vx, vy, vz);
@@ -695,10 +1093,12 @@ impl Chunks {
 
             if let Some(n_chunk) = n_chunk {
                 n_chunk.set_voxel_rotation(vx, vy, vz, rotation);
-                n_chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-                n_chunk.is_dirty = true;
             }
-        })
+        });
+
+        self.observers
+            .notify(Vec3(vx, vy, vz), Change::Rotation(rotation.to_owned()));
+        self.invalidate_voxel(vx, vy, vz);
     }
 
     /// Get the voxel stage at a voxel coordinate
@@ -718,20 +1118,16 @@ impl Chunks {
     /// 1. Sets the neighboring chunk's padding data if the coordinates are on a chunk edge.
     /// 2. Calculates the chunk's and the neighbors' dirty sub-chunk levels
     pub fn set_voxel_stage_by_voxel(&mut self, vx: i32, vy: i32, vz: i32, stage: u32) {
-        let max_height = self.config.max_height;
-        if vy as u32 >= max_height {
+        if !self.config.contains_height(vy) {
             return;
         }
 
-        let sub_chunks = self.config.sub_chunks;
         let chunk = self.get_chunk_by_voxel_mut(vx, vy, vz);
 
         // TODO: update chunks data for unloaded chunks.
 
         if let Some(chunk) = chunk {
             chunk.set_voxel_stage(vx, vy, vz, stage);
-            chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-            chunk.is_dirty = true;
         }
 
         let neighbors = self.get_neighbor_chunk_coords(vx, vy, vz);
@@ -740,10 +1136,55 @@ impl Chunks {
 
             if let Some(n_chunk) = n_chunk {
                 n_chunk.set_voxel_stage(vx, vy, vz, stage);
-                n_chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-                n_chunk.is_dirty = true;
             }
-        })
+        });
+
+        self.observers
+            .notify(Vec3(vx, vy, vz), Change::Stage(stage));
+        self.invalidate_voxel(vx, vy, vz);
+    }
+
+    /// Get the rotation, stage and variant at a voxel coordinate
+    pub fn get_voxel_state_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> VoxelState {
+        let chunk = self.get_chunk_by_voxel(vx, vy, vz);
+        if let Some(chunk) = chunk {
+            chunk.get_voxel_state(vx, vy, vz)
+        } else {
+            panic!("State not obtainable.");
+        }
+    }
+
+    /// Set the rotation, stage and variant at a voxel coordinate
+    ///
+    /// Side-effects:
+    ///
+    /// 1. Sets the neighboring chunk's padding data if the coordinates are on a chunk edge.
+    /// 2. Calculates the chunk's and the neighbors' dirty sub-chunk levels
+    pub fn set_voxel_state_by_voxel(&mut self, vx: i32, vy: i32, vz: i32, state: &VoxelState) {
+        if !self.config.contains_height(vy) {
+            return;
+        }
+
+        let chunk = self.get_chunk_by_voxel_mut(vx, vy, vz);
+
+        // TODO: update chunks data for unloaded chunks.
+
+        if let Some(chunk) = chunk {
+            chunk.set_voxel_state(vx, vy, vz, state);
+        }
+
+        let neighbors = self.get_neighbor_chunk_coords(vx, vy, vz);
+        neighbors.iter().for_each(|c| {
+            let n_chunk = self.get_chunk_mut(c);
+
+            if let Some(n_chunk) = n_chunk {
+                n_chunk.set_voxel_state(vx, vy, vz, state);
+            }
+        });
+
+        self.observers
+            .notify(Vec3(vx, vy, vz), Change::State(state.to_owned()));
+        self.invalidate_voxel(vx, vy, vz);
     }
 
     /// Get the sunlight level at a voxel coordinate
@@ -763,29 +1204,24 @@ impl Chunks {
     /// 1. Sets the neighboring chunk's padding data if the coordinates are on a chunk edge.
     /// 2. Calculates the chunk's and the neighbors' dirty sub-chunk levels
     pub fn set_sunlight(&mut self, vx: i32, vy: i32, vz: i32, level: u32) {
-        let max_height = self.config.max_height;
-        if vy as u32 >= max_height {
+        if !self.config.contains_height(vy) {
             return;
         }
 
-        let sub_chunks = self.config.sub_chunks;
-
         let chunk = self
             .get_chunk_by_voxel_mut(vx, vy, vz)
             .expect("Chunk not found.");
 
         chunk.set_sunlight(vx, vy, vz, level);
-        chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-        chunk.is_dirty = true;
 
         let neighbors = self.get_neighbor_chunk_coords(vx, vy, vz);
         neighbors.iter().for_each(|c| {
             let n_chunk = self.get_chunk_mut(c).unwrap();
 
             n_chunk.set_sunlight(vx, vy, vz, level);
-            n_chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-            n_chunk.is_dirty = true;
-        })
+        });
+
+        self.invalidate_voxel(vx, vy, vz);
     }
 
     /// Get the torch light level by voxel coordinates of a specified color
@@ -805,29 +1241,24 @@ impl Chunks {
     /// 1. Sets the neighboring chunk's padding data if the coordinates are on a chunk edge.
     /// 2. Calculates the chunk's and the neighbors' dirty sub-chunk levels
     pub fn set_torch_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32, color: &LightColor) {
-        let max_height = self.config.max_height;
-        if vy as u32 >= max_height {
+        if !self.config.contains_height(vy) {
             return;
         }
 
-        let sub_chunks = self.config.sub_chunks;
-
         let chunk = self
             .get_chunk_by_voxel_mut(vx, vy, vz)
             .expect("Chunk not found.");
 
         chunk.set_torch_light(vx, vy, vz, level, color);
-        chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-        chunk.is_dirty = true;
 
         let neighbors = self.get_neighbor_chunk_coords(vx, vy, vz);
         neighbors.iter().for_each(|c| {
             let n_chunk = self.get_chunk_mut(c).unwrap();
 
             n_chunk.set_torch_light(vx, vy, vz, level, color);
-            n_chunk.calc_dirty_levels(vy, max_height, sub_chunks);
-            n_chunk.is_dirty = true;
-        })
+        });
+
+        self.invalidate_voxel(vx, vy, vz);
     }
 
     /// Get a block type from a voxel coordinate
@@ -841,17 +1272,17 @@ impl Chunks {
         self.registry.get_block_by_id(id)
     }
 
-    /// Get the max height at a voxel column coordinate
-    pub fn get_max_height(&self, vx: i32, vz: i32) -> u32 {
+    /// Get the max height at a voxel column coordinate, the bottom of the world if it's not loaded
+    pub fn get_max_height(&self, vx: i32, vz: i32) -> i32 {
         if let Some(chunk) = self.get_chunk_by_voxel(vx, 0, vz) {
             chunk.get_max_height(vx, vz)
         } else {
-            0
+            self.config.min_height
         }
     }
 
     /// Set the max height at a voxel column coordinate
-    pub fn set_max_height(&mut self, vx: i32, vz: i32, height: u32) {
+    pub fn set_max_height(&mut self, vx: i32, vz: i32, height: i32) {
         // this is reasonable because if a chunk DNE, and gets instantiated later on,
         // max height will be generated on instantiation too.
         if let Some(chunk) = self.get_chunk_by_voxel_mut(vx, 0, vz) {
@@ -869,10 +1300,9 @@ impl Chunks {
 
     /// Get whether a voxel is walkable
     pub fn get_walkable_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> bool {
-        let block = self
-            .registry
-            .get_block_by_id(self.get_voxel_by_voxel(vx, vy, vz));
-        !block.is_solid || block.is_plant
+        self.registry
+            .get_block_by_id(self.get_voxel_by_voxel(vx, vy, vz))
+            .is_walkable()
     }
 
     /// Get whether a voxel is solid
@@ -881,9 +1311,54 @@ impl Chunks {
     }
 
     /// Get whether a voxel is fluid
-    pub fn get_fluidity_by_voxel(&self, _vx: i32, _vy: i32, _vz: i32) -> bool {
-        // TODO: ADD FLUIDS
-        false
+    pub fn get_fluidity_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        self.registry.is_fluid(self.get_voxel_by_voxel(vx, vy, vz))
+    }
+
+    /// Get how the block at a voxel alters the movement of bodies
+    pub fn get_modifiers_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> BlockModifiers {
+        let id = self.get_voxel_by_voxel(vx, vy, vz);
+        let block = self.registry.get_block_by_id(id);
+
+        BlockModifiers {
+            climbable: block.is_climbable || self.registry.has_tag(id, CLIMBABLE),
+            speed: block.speed_multiplier,
+            friction: block.friction_multiplier,
+        }
+    }
+
+    /// Get whether a voxel is a rail
+    pub fn get_rail_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        self.registry
+            .get_block_by_id(self.get_voxel_by_voxel(vx, vy, vz))
+            .is_rail
+    }
+
+    /// Mark every chunk holding a voxel as dirty around its height, invalidating the meshed ones
+    ///
+    /// Besides its own chunk, a voxel on a chunk edge is in the padding of the neighbors it
+    /// borders, and their faces and ambient occlusion against it need remeshing too.
+    fn invalidate_voxel(&mut self, vx: i32, vy: i32, vz: i32) {
+        let max_height = self.config.max_height;
+        let sub_chunks = self.config.sub_chunks;
+
+        let mut holders = self.get_neighbor_chunk_coords(vx, vy, vz);
+        holders.insert(map_voxel_to_chunk(vx, vy, vz, self.config.chunk_size));
+
+        for coords in holders {
+            let meshed = match self.get_chunk_mut(&coords) {
+                Some(chunk) => {
+                    chunk.calc_dirty_levels(vy, max_height, sub_chunks);
+                    chunk.is_dirty = true;
+                    !chunk.meshes.is_empty()
+                }
+                None => continue,
+            };
+
+            if meshed {
+                self.invalidated.insert(coords);
+            }
+        }
     }
 
     /// Get neighboring chunks according to a voxel coordinate
@@ -953,7 +1428,25 @@ impl Chunks {
     /// Add a chunk instance to self
     ///
     /// Removes existing chunks first.
-    pub fn add_chunk(&mut self, chunk: Chunk) {
+    pub fn add_chunk(&mut self, mut chunk: Chunk) {
+        if !chunk.needs_terrain && !chunk.needs_decoration {
+            let edits = self.journal.take_pending(&chunk.coords);
+
+            if !edits.is_empty() {
+                self.replay(&mut chunk, edits);
+            }
+        }
+
+        // saved entities come alive, so the chunk has to be saved again once they move
+        if !chunk.entities.is_empty() {
+            chunk.needs_saving = true;
+            self.loaded_entities.append(&mut chunk.entities);
+        }
+
+        if !chunk.needs_terrain && !chunk.needs_decoration {
+            self.surface_changed.insert(chunk.coords.to_owned());
+        }
+
         self.meshing.remove(&chunk.coords);
         self.generating.remove(&chunk.coords);
 
@@ -965,19 +1458,94 @@ impl Chunks {
         self.unload_chunks();
     }
 
+    /// Take a chunk out of the world without saving it, such as to hand it off to another shard
+    pub fn release_chunk(&mut self, coords: &Vec2<i32>) -> Option<Chunk> {
+        if self.generating.contains(coords) || self.meshing.contains(coords) {
+            return None;
+        }
+
+        self.activities.retain(|c| c != coords);
+        self.to_mesh.retain(|c| c != coords);
+        self.chunk_cache.remove(coords);
+        self.invalidated.remove(coords);
+
+        self.chunks.remove(coords)
+    }
+
+    /// Adopt a chunk in its saved format, such as one handed off by another shard
+    pub fn adopt_chunk(&mut self, coords: &Vec2<i32>, data: &[u8]) {
+        let mut chunk = Chunk::new(coords.to_owned(), &self.config, None);
+        chunk.load_data(data);
+        chunk.fill_biomes(&self.biomes);
+
+        chunk.needs_saving = true;
+        chunk.is_dirty = true;
+
+        self.add_chunk(chunk);
+    }
+
+    /// Apply edits journaled before an unfinished save onto a chunk that just loaded
+    fn replay(&self, chunk: &mut Chunk, edits: Vec<JournalEntry>) {
+        for edit in edits {
+            match edit {
+                JournalEntry::Voxel {
+                    voxel: [vx, vy, vz],
+                    id,
+                    rotation,
+                    y_rotation,
+                } => {
+                    chunk.set_voxel(vx, vy, vz, id);
+
+                    let block = self.registry.get_block_by_id(id);
+                    if block.rotatable {
+                        let y_rotation = if block.y_rotatable { y_rotation } else { 0 };
+                        chunk.set_voxel_rotation(
+                            vx,
+                            vy,
+                            vz,
+                            &BlockRotation::encode(rotation, y_rotation),
+                        );
+                    }
+                }
+                JournalEntry::BlockEntity {
+                    voxel: [vx, vy, vz],
+                    entity,
+                } => match entity {
+                    Some(entity) => chunk.set_block_entity(vx, vy, vz, entity),
+                    None => {
+                        chunk.remove_block_entity(vx, vy, vz);
+                    }
+                },
+            }
+        }
+
+        Generator::generate_chunk_height_map(chunk, &self.registry, &self.config);
+
+        chunk.needs_propagation = true;
+        chunk.needs_saving = true;
+        chunk.edited = Some(true);
+        chunk.is_dirty = true;
+    }
+
     /// Update a voxel to a new type
     pub fn update(&mut self, vx: i32, vy: i32, vz: i32, id: u32, rotation: u32, y_rotation: u32) {
+        self.journal.append(&JournalEntry::Voxel {
+            voxel: [vx, vy, vz],
+            id,
+            rotation,
+            y_rotation,
+        });
+
         // TODO: fix this code (might have better way)
-        self.get_chunk_by_voxel_mut(vx, vy, vz)
-            .unwrap()
-            .needs_saving = true;
+        let chunk = self.get_chunk_by_voxel_mut(vx, vy, vz).unwrap();
+        chunk.needs_saving = true;
+        chunk.edited = Some(true);
         let needs_propagation = self
             .get_chunk_by_voxel(vx, vy, vz)
             .unwrap()
             .needs_propagation;
 
-        let max_height = self.config.max_height as i32;
-        let max_light_level = self.config.max_light_level;
+        let min_height = self.config.min_height;
 
         let height = self.get_max_height(vx, vz);
 
@@ -985,7 +1553,10 @@ impl Chunks {
         let current_type = self.get_block_by_voxel(vx, vy, vz).clone();
         let updated_type = self.get_block_by_id(id).clone();
 
-        let voxel = Vec3(vx, vy, vz);
+        // a replaced block takes its block entity with it
+        if self.get_voxel_by_voxel(vx, vy, vz) != id {
+            self.remove_block_entity_by_voxel(vx, vy, vz);
+        }
 
         // updating the new block
         self.set_voxel_by_voxel(vx, vy, vz, id);
@@ -1005,181 +1576,38 @@ impl Chunks {
             );
         }
 
+        if vy >= height {
+            let coords = self
+                .get_chunk_by_voxel(vx, vy, vz)
+                .unwrap()
+                .coords
+                .to_owned();
+            self.surface_changed.insert(coords);
+        }
+
         // updating the height map
         if self.registry.is_air(id) {
-            if vy == height as i32 {
+            if vy == height {
                 // on max height, should set max height to lower
-                for y in (0..vy).rev() {
-                    if y == 0
+                for y in (min_height..vy).rev() {
+                    if y == min_height
                         || Generator::check_height(
                             self.get_voxel_by_voxel(vx, y, vz),
                             &self.registry,
                         )
                     {
-                        self.set_max_height(vx, vz, y as u32);
+                        self.set_max_height(vx, vz, y);
                         break;
                     }
                 }
             }
-        } else if height < vy as u32 {
-            self.set_max_height(vx, vz, vy as u32);
+        } else if height < vy {
+            self.set_max_height(vx, vz, vy);
         }
 
-        const RED: LightColor = LightColor::Red;
-        const GREEN: LightColor = LightColor::Green;
-        const BLUE: LightColor = LightColor::Blue;
-        const NONE: LightColor = LightColor::None;
-
         // update light levels
         if !needs_propagation {
-            if current_type.is_light {
-                // remove leftover light
-                Lights::global_remove_light(self, vx, vy, vz, false, &RED);
-                Lights::global_remove_light(self, vx, vy, vz, false, &GREEN);
-                Lights::global_remove_light(self, vx, vy, vz, false, &BLUE);
-            } else if current_type.is_transparent && !updated_type.is_transparent {
-                // remove light if solid block is placed
-                [false, true].iter().for_each(|&is_sunlight| {
-                    if is_sunlight {
-                        if self.get_sunlight(vx, vy, vz) != 0 {
-                            Lights::global_remove_light(self, vx, vy, vz, is_sunlight, &NONE);
-                        }
-                    } else {
-                        if self.get_torch_light(vx, vy, vz, &RED) != 0 {
-                            Lights::global_remove_light(self, vx, vy, vz, is_sunlight, &RED);
-                        }
-                        if self.get_torch_light(vx, vy, vz, &GREEN) != 0 {
-                            Lights::global_remove_light(self, vx, vy, vz, is_sunlight, &GREEN);
-                        }
-                        if self.get_torch_light(vx, vy, vz, &BLUE) != 0 {
-                            Lights::global_remove_light(self, vx, vy, vz, is_sunlight, &BLUE);
-                        }
-                    };
-                });
-            }
-
-            if updated_type.is_light {
-                // placing a light
-
-                if updated_type.red_light_level > 0 {
-                    self.set_torch_light(vx, vy, vz, updated_type.red_light_level, &RED);
-
-                    Lights::global_flood_light(
-                        self,
-                        VecDeque::from(vec![LightNode {
-                            voxel: voxel.clone(),
-                            level: updated_type.red_light_level,
-                        }]),
-                        false,
-                        &RED,
-                    );
-                }
-
-                if updated_type.green_light_level > 0 {
-                    self.set_torch_light(vx, vy, vz, updated_type.green_light_level, &GREEN);
-
-                    Lights::global_flood_light(
-                        self,
-                        VecDeque::from(vec![LightNode {
-                            voxel: voxel.clone(),
-                            level: updated_type.green_light_level,
-                        }]),
-                        false,
-                        &GREEN,
-                    );
-                }
-
-                if updated_type.blue_light_level > 0 {
-                    self.set_torch_light(vx, vy, vz, updated_type.blue_light_level, &BLUE);
-
-                    Lights::global_flood_light(
-                        self,
-                        VecDeque::from(vec![LightNode {
-                            voxel,
-                            level: updated_type.blue_light_level,
-                        }]),
-                        false,
-                        &BLUE,
-                    );
-                }
-            } else if updated_type.is_transparent && !current_type.is_transparent {
-                // solid block removed
-                [false, true].iter().for_each(|&is_sunlight| {
-                    let mut queue = VecDeque::<LightNode>::new();
-                    let mut red_queue = VecDeque::<LightNode>::new();
-                    let mut green_queue = VecDeque::<LightNode>::new();
-                    let mut blue_queue = VecDeque::<LightNode>::new();
-
-                    if is_sunlight && vy == max_height - 1 {
-                        // propagate sunlight down
-                        self.set_sunlight(vx, vy, vz, max_light_level);
-                        queue.push_back(LightNode {
-                            voxel: voxel.clone(),
-                            level: max_light_level,
-                        })
-                    } else {
-                        for [ox, oy, oz] in VOXEL_NEIGHBORS.iter() {
-                            let nvy = vy + oy;
-
-                            if nvy < 0 || nvy >= max_height {
-                                return;
-                            }
-
-                            let nvx = vx + ox;
-                            let nvz = vz + oz;
-                            let n_voxel = Vec3(nvx, nvy, nvz);
-                            let &Block {
-                                is_light,
-                                is_transparent,
-                                ..
-                            } = self.get_block_by_voxel(nvx, nvy, nvz);
-
-                            // need propagation after solid block removed
-                            if is_sunlight {
-                                let level = self.get_sunlight(nvx, nvy, nvz);
-                                if level != 0 && is_transparent {
-                                    queue.push_back(LightNode {
-                                        voxel: n_voxel,
-                                        level,
-                                    })
-                                }
-                            } else {
-                                let red_level = self.get_torch_light(nvx, nvy, nvz, &RED);
-                                if red_level != 0 && (is_transparent || is_light) {
-                                    red_queue.push_back(LightNode {
-                                        voxel: n_voxel.clone(),
-                                        level: red_level,
-                                    })
-                                }
-
-                                let green_level = self.get_torch_light(nvx, nvy, nvz, &GREEN);
-                                if green_level != 0 && (is_transparent || is_light) {
-                                    green_queue.push_back(LightNode {
-                                        voxel: n_voxel.clone(),
-                                        level: green_level,
-                                    })
-                                }
-
-                                let blue_level = self.get_torch_light(nvx, nvy, nvz, &BLUE);
-                                if blue_level != 0 && (is_transparent || is_light) {
-                                    blue_queue.push_back(LightNode {
-                                        voxel: n_voxel,
-                                        level: blue_level,
-                                    })
-                                }
-                            }
-                        }
-                    }
-
-                    if is_sunlight {
-                        Lights::global_flood_light(self, queue, is_sunlight, &NONE);
-                    } else {
-                        Lights::global_flood_light(self, red_queue, is_sunlight, &RED);
-                        Lights::global_flood_light(self, green_queue, is_sunlight, &GREEN);
-                        Lights::global_flood_light(self, blue_queue, is_sunlight, &BLUE);
-                    }
-                })
-            }
+            Lights::update_light(self, vx, vy, vz, &current_type, &updated_type);
         }
     }
 
@@ -1201,11 +1629,11 @@ impl Chunks {
     ///
     /// 1. Spread sunlight from the very top of the chunk
     /// 2. Recognize the torch lights and flood-fill them as well
-    fn propagate_chunk(&mut self, coords: &Vec2<i32>) {
+    pub fn propagate_chunk(&mut self, coords: &Vec2<i32>) {
         let max_light_flood = self.config.max_light_level as usize;
 
         let space = Space::new(self, coords, max_light_flood);
-        let lights = Lights::calc_light(&space, &self.registry, &self.config);
+        let lights = lights::calc_light(&space, &self.registry, &self.config);
 
         let chunk = self.get_chunk_mut(coords).expect("Chunk not found");
 
@@ -1223,20 +1651,87 @@ impl Chunks {
         self.activities.push_back(coords.to_owned());
     }
 
+    /// Check if a chunk is within the view radius of any player
+    pub fn is_interested(&self, coords: &Vec2<i32>) -> bool {
+        self.interests.iter().any(|(center, radius)| {
+            let r = *radius as i32;
+            let dx = coords.0 - center.0;
+            let dz = coords.1 - center.1;
+
+            dx * dx + dz * dz <= r * r
+        })
+    }
+
     /// Unload chunks that are too old.
+    ///
+    /// Chunks that are still within a player's view radius are kept. Local chunks are queued in
+    /// `unloaded` to be saved.
     fn unload_chunks(&mut self) {
-        let diff = self.chunks.len() as i32 - self.config.max_loaded_chunks as i32;
-
-        if diff > 0 {
-            for _ in 0..diff {
-                if let Some(coords) = self.activities.pop_front() {
-                    if let Some(chunk) = self.chunks.remove(&coords) {
-                        if self.config.save {
-                            chunk.save();
-                        }
-                    }
+        let mut diff = self.chunks.len() as i32 - self.config.max_loaded_chunks as i32;
+        let mut kept = VecDeque::new();
+
+        while diff > 0 {
+            let coords = match self.activities.pop_front() {
+                Some(coords) => coords,
+                None => break,
+            };
+
+            if self.is_interested(&coords) {
+                kept.push_back(coords);
+                continue;
+            }
+
+            // saved on the next tick, with the entities standing in them
+            if let Some(chunk) = self.chunks.remove(&coords) {
+                if self.storage.is_some() && self.config.shards.is_local(&coords) {
+                    self.unloaded.push(chunk);
                 }
             }
+
+            diff -= 1;
+        }
+
+        // put the kept chunks back in their original order
+        while let Some(coords) = kept.pop_back() {
+            self.activities.push_front(coords);
         }
     }
 }
+
+impl LightSpace for Chunks {
+    fn min_height(&self) -> i32 {
+        self.config.min_height
+    }
+
+    fn top(&self) -> i32 {
+        self.config.top()
+    }
+
+    fn max_light_level(&self) -> u32 {
+        self.config.max_light_level
+    }
+
+    fn get_block_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> &Block {
+        Chunks::get_block_by_voxel(self, vx, vy, vz)
+    }
+
+    fn get_sunlight(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        Chunks::get_sunlight(self, vx, vy, vz)
+    }
+
+    fn set_sunlight(&mut self, vx: i32, vy: i32, vz: i32, level: u32) {
+        Chunks::set_sunlight(self, vx, vy, vz, level);
+    }
+
+    fn get_torch_light(&self, vx: i32, vy: i32, vz: i32, color: &LightColor) -> u32 {
+        Chunks::get_torch_light(self, vx, vy, vz, color)
+    }
+
+    fn set_torch_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32, color: &LightColor) {
+        Chunks::set_torch_light(self, vx, vy, vz, level, color);
+    }
+
+    fn light_changed(&mut self, vx: i32, vy: i32, vz: i32) {
+        self.mark_saving_from_voxel(vx, vy, vz);
+    }
+}
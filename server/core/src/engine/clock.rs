@@ -1,6 +1,35 @@
 #![allow(dead_code)]
 
-use std::time::SystemTime;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the unix epoch, used as server time on the wire
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards?")
+        .as_millis() as u64
+}
+
+/// Time of day when the sun starts and stops lighting the world, matching the client sky
+const SUNRISE: f32 = 600.0;
+const SUNSET: f32 = 1800.0;
+const SUNLIGHT_CHANGE_SPAN: f32 = 200.0;
+const MIN_SUNLIGHT: f32 = 0.1;
+
+/// Intensity of sunlight (0.1 to 1.0) at a time of day (0 to 2400)
+pub fn sunlight_intensity(time: f32) -> f32 {
+    let half_span = SUNLIGHT_CHANGE_SPAN / 2.0;
+
+    if time < SUNRISE - half_span || time > SUNSET + half_span {
+        MIN_SUNLIGHT
+    } else if time <= SUNRISE + half_span {
+        ((time - (SUNRISE - half_span)) / SUNLIGHT_CHANGE_SPAN).max(MIN_SUNLIGHT)
+    } else if time >= SUNSET - half_span {
+        (1.0 - (time - (SUNSET - half_span)) / SUNLIGHT_CHANGE_SPAN).max(MIN_SUNLIGHT)
+    } else {
+        1.0
+    }
+}
 
 pub struct Clock {
     pub time: f32,
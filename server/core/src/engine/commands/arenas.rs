@@ -0,0 +1,120 @@
+use log::warn;
+
+use crate::engine::arenas::{ArenaConfig, ArenaEvent, Arenas};
+use crate::engine::chunks::Chunks;
+use crate::engine::clock::Clock;
+use crate::engine::world::{MessagesQueue, World};
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+use crate::world_edit::{paste, Schematic};
+
+/// Handles `/arena join <name>` and `/arena leave`
+pub fn on_arena_command(world: &mut World, player_id: usize, args: &[&str]) -> messages::Message {
+    let (chat_type, key) = match args {
+        ["join", name] => match join_arena(world, player_id, name) {
+            Ok(_) => (ChatType::Info, "arena.joined"),
+            Err(key) => (ChatType::Error, key),
+        },
+        ["leave"] => {
+            if leave_arena(world, player_id) {
+                (ChatType::Info, "arena.left")
+            } else {
+                (ChatType::Error, "arena.missing")
+            }
+        }
+        _ => (ChatType::Error, "arena.usage"),
+    };
+
+    create_translated_message(MessageType::Message, chat_type, key, &[])
+}
+
+/// Define an arena, saving the current blocks of its region to reset it to after each round
+///
+/// Returns false if the name is taken or the region is too large to save.
+pub fn define_arena(world: &mut World, config: ArenaConfig) -> bool {
+    if let Err(e) = config.region().check_schematic() {
+        warn!("Unable to define arena \"{}\": {}", config.name, e);
+        return false;
+    }
+
+    let schematic = Schematic::from_chunks(&world.read_resource::<Chunks>(), &config.region());
+    world.write_resource::<Arenas>().define(config, schematic)
+}
+
+/// Add a player to the lobby of an arena, errors with a translation key
+pub fn join_arena(world: &mut World, player_id: usize, name: &str) -> Result<(), &'static str> {
+    let tick = world.read_resource::<Clock>().tick;
+    world.write_resource::<Arenas>().join(name, player_id, tick)
+}
+
+/// Remove a player from their arena, sending them back to its lobby
+pub fn leave_arena(world: &mut World, player_id: usize) -> bool {
+    let name = match world.write_resource::<Arenas>().leave(player_id) {
+        Some(name) => name,
+        None => return false,
+    };
+
+    let lobby = world
+        .read_resource::<Arenas>()
+        .get(&name)
+        .map(|arena| arena.config.lobby.to_owned());
+
+    if let Some(lobby) = lobby {
+        world.teleport(player_id, &lobby);
+    }
+
+    true
+}
+
+/// Act upon the arena lifecycle events of this tick
+///
+/// Players are moved into the arena when a round starts, and back to the lobby once the
+/// region is reset after it ends.
+pub fn tick_arenas(world: &mut World) {
+    let tick = world.read_resource::<Clock>().tick;
+    let events = world.write_resource::<Arenas>().tick(tick);
+
+    for event in events {
+        let (name, key, args) = match &event {
+            ArenaEvent::CountdownStarted { arena, seconds } => {
+                (arena, "arena.countdown", vec![seconds.to_string()])
+            }
+            ArenaEvent::CountdownCancelled { arena } => (arena, "arena.cancelled", vec![]),
+            ArenaEvent::RoundStarted { arena } => (arena, "arena.started", vec![]),
+            ArenaEvent::RoundEnded { arena } => (arena, "arena.ended", vec![]),
+        };
+
+        let (players, spawn, lobby, schematic, origin) =
+            match world.read_resource::<Arenas>().get(name) {
+                Some(arena) => (
+                    arena.players.to_owned(),
+                    arena.config.spawn.to_owned(),
+                    arena.config.lobby.to_owned(),
+                    arena.schematic.to_owned(),
+                    arena.config.region().min,
+                ),
+                None => continue,
+            };
+
+        match event {
+            ArenaEvent::RoundStarted { .. } => {
+                players.iter().for_each(|id| world.teleport(*id, &spawn));
+            }
+            ArenaEvent::RoundEnded { .. } => {
+                if let Err(e) = paste(world, &schematic, origin, 0) {
+                    warn!("Unable to reset arena \"{}\": {}", name, e);
+                }
+
+                players.iter().for_each(|id| world.teleport(*id, &lobby));
+                world.write_resource::<Arenas>().finish_reset(name, tick);
+            }
+            _ => {}
+        }
+
+        let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+        let message = create_translated_message(MessageType::Message, ChatType::Info, key, &args);
+
+        world
+            .write_resource::<MessagesQueue>()
+            .push((message, Some(players), None, None));
+    }
+}
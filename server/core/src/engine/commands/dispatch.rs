@@ -0,0 +1,151 @@
+use log::info;
+
+use crate::comp::effects::EffectType;
+use crate::engine::i18n::{Translations, DEFAULT_LOCALE};
+use crate::engine::players::Players;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+use super::operators::{is_operator, on_op_command, requires_operator};
+use super::{
+    arenas, fill, forms, functions, glide, holograms, kill, profile, record, restart, rollback,
+    teams, title, tp, CONSOLE_ID,
+};
+
+/// Run a command for a player, without its leading `/`, returns the feedback to send them
+pub fn run_command(world: &mut World, player_id: usize, command: &str) -> Vec<messages::Message> {
+    let operator = is_operator(world, player_id);
+    execute(world, player_id, operator, command)
+}
+
+/// Run a command with the console's permissions on behalf of a player, who `@s` and `~` refer to
+///
+/// For the commands of functions, which only operators and the world's own events start.
+pub fn run_command_for(
+    world: &mut World,
+    player_id: usize,
+    command: &str,
+) -> Vec<messages::Message> {
+    execute(world, player_id, true, command)
+}
+
+fn execute(
+    world: &mut World,
+    player_id: usize,
+    operator: bool,
+    command: &str,
+) -> Vec<messages::Message> {
+    let body = command.split_whitespace().collect::<Vec<_>>();
+
+    let mut msgs = vec![];
+
+    let create_msg = |chat_type: ChatType, key: &str| {
+        create_translated_message(MessageType::Message, chat_type, key, &[])
+    };
+
+    if body.is_empty() {
+        msgs.push(create_msg(ChatType::Error, "command.unknown"));
+    } else if requires_operator(&body) && !operator {
+        msgs.push(create_msg(ChatType::Error, "command.permission"));
+    } else {
+        match body[0] {
+            "op" => msgs.push(on_op_command(world, &body[1..], true)),
+            "deop" => msgs.push(on_op_command(world, &body[1..], false)),
+            "save" => {
+                world.save();
+                msgs.push(create_msg(ChatType::Info, "command.save"));
+            }
+            "undo" => {
+                if world.undo(player_id) {
+                    msgs.push(create_msg(ChatType::Info, "command.undo"));
+                } else {
+                    msgs.push(create_msg(ChatType::Error, "command.undo.empty"));
+                }
+            }
+            "redo" => {
+                if world.redo(player_id) {
+                    msgs.push(create_msg(ChatType::Info, "command.redo"));
+                } else {
+                    msgs.push(create_msg(ChatType::Error, "command.redo.empty"));
+                }
+            }
+            "effect" => {
+                let entity = world
+                    .read_resource::<Players>()
+                    .get(&player_id)
+                    .map(|player| player.entity);
+
+                // /effect <type> [seconds] [amplifier], or /effect clear
+                match (entity, body.get(1).copied()) {
+                    (Some(entity), Some("clear")) => {
+                        world.clear_effects(entity);
+                        msgs.push(create_msg(ChatType::Info, "command.effect.clear"));
+                    }
+                    (Some(entity), Some(name)) => match EffectType::from_name(name) {
+                        Some(kind) => {
+                            let duration = body
+                                .get(2)
+                                .and_then(|s| s.parse::<f32>().ok())
+                                .unwrap_or(30.0);
+                            let amplifier =
+                                body.get(3).and_then(|s| s.parse::<u32>().ok()).unwrap_or(0);
+
+                            world.add_effect(entity, kind, amplifier, duration);
+                            msgs.push(create_msg(ChatType::Info, "command.effect"));
+                        }
+                        None => {
+                            msgs.push(create_msg(ChatType::Error, "command.effect.unknown"));
+                        }
+                    },
+                    _ => msgs.push(create_msg(ChatType::Error, "command.unknown")),
+                }
+            }
+            "arena" => msgs.push(arenas::on_arena_command(world, player_id, &body[1..])),
+            "team" => msgs.push(teams::on_team_command(world, player_id, &body[1..])),
+            "fill" => msgs.push(fill::on_fill_command(world, player_id, &body[1..])),
+            "tp" => msgs.push(tp::on_tp_command(world, player_id, &body[1..])),
+            "kill" => msgs.push(kill::on_kill_command(world, player_id, &body[1..])),
+            "function" => msgs.push(functions::on_function_command(world, player_id, &body[1..])),
+            "schedule" => msgs.push(functions::on_schedule_command(world, player_id, &body[1..])),
+            "form" => msgs.extend(forms::on_form_command(world, player_id, &body[1..])),
+            "hologram" => msgs.push(holograms::on_hologram_command(world, player_id, &body[1..])),
+            "title" => msgs.push(title::on_title_command(world, player_id, &body[1..])),
+            "restart" => msgs.push(restart::on_restart_command(world, &body[1..])),
+            "record" => msgs.push(record::on_record_command(world, player_id, &body[1..])),
+            "glide" => msgs.push(glide::on_glide_command(world, player_id, &body[1..])),
+            "rollback" => msgs.push(rollback::on_rollback_command(world, player_id, &body[1..])),
+            "tps" => msgs.push(profile::on_tps_command(world)),
+            "profile" => msgs.append(&mut profile::on_profile_command(world, &body[1..])),
+            "reload" => msgs.append(&mut world.on_reload_command()),
+            "summon" => {
+                // /summon [type], such as Boat or Minecart
+                let etype = body.get(1).copied().unwrap_or("Test");
+
+                if world.test_entity(player_id, etype) {
+                    msgs.push(create_msg(ChatType::Info, "command.summon"));
+                } else {
+                    msgs.push(create_msg(ChatType::Error, "command.summon.unknown"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    msgs
+}
+
+/// Run a command as the console, returning its replies in the default locale
+pub fn run_console_command(world: &mut World, command: &str) -> Vec<String> {
+    let command = command.trim();
+    let command = command.strip_prefix('/').unwrap_or(command);
+
+    info!("Console ran \"/{}\" in \"{}\".", command, world.name);
+
+    let msgs = run_command(world, CONSOLE_ID, command);
+    let translations = world.read_resource::<Translations>();
+
+    msgs.iter()
+        .filter_map(|msg| translations.localize(msg, DEFAULT_LOCALE).message)
+        .map(|chat| chat.body)
+        .collect()
+}
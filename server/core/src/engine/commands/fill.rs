@@ -0,0 +1,75 @@
+use crate::engine::chunks::Chunks;
+use crate::engine::config::WorldConfig;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+use crate::world_edit::{fill, Region, MAX_FILL_VOLUME};
+
+use super::{parse_block, Position};
+
+/// Run a `/fill x1 y1 z1 x2 y2 z2 <block> [replace <block|#tag>]` command
+///
+/// Coordinates can be relative to the player with `~`.
+pub fn on_fill_command(world: &mut World, player_id: usize, args: &[&str]) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+        create_translated_message(MessageType::Message, chat_type, key, args)
+    };
+
+    let corners = match (
+        Position::parse(args),
+        Position::parse(args.get(3..).unwrap_or(&[])),
+    ) {
+        (Ok(from), Ok(to)) => (from, to),
+        _ => return create_msg(ChatType::Error, "command.fill.usage", &[]),
+    };
+
+    let (block, replace) = match &args[args.len().min(6)..] {
+        [block] => (*block, None),
+        [block, "replace", pattern] => (*block, Some(*pattern)),
+        _ => return create_msg(ChatType::Error, "command.fill.usage", &[]),
+    };
+
+    let origin = world.player_voxel(player_id).unwrap_or_default();
+    let (from, to) = (corners.0.resolve(&origin), corners.1.resolve(&origin));
+
+    let (id, replace) = {
+        let chunks = world.read_resource::<Chunks>();
+        let registry = &chunks.registry;
+        (
+            parse_block(block, registry).ok(),
+            replace.map(|pattern| registry.resolve_pattern(pattern)),
+        )
+    };
+
+    let (id, replace) = match (id, replace) {
+        (Some(id), None) => (id, None),
+        (Some(id), Some(Some(ids))) => (id, Some(ids)),
+        _ => return create_msg(ChatType::Error, "command.fill.unknown", &[]),
+    };
+
+    // only voxels within the world's height can be filled
+    let mut region = Region::new(from, to);
+    {
+        let config = world.read_resource::<WorldConfig>();
+        region.min.1 = region.min.1.max(config.min_height);
+        region.max.1 = region.max.1.min(config.top() - 1);
+    }
+
+    if region.min.1 > region.max.1 {
+        return create_msg(ChatType::Info, "command.fill", &["0"]);
+    }
+
+    if region
+        .volume()
+        .is_none_or(|volume| volume > MAX_FILL_VOLUME)
+    {
+        return create_msg(
+            ChatType::Error,
+            "command.fill.too_big",
+            &[&MAX_FILL_VOLUME.to_string()],
+        );
+    }
+
+    let count = fill(world, player_id, &region, id, replace.as_ref());
+
+    create_msg(ChatType::Info, "command.fill", &[&count.to_string()])
+}
@@ -0,0 +1,113 @@
+use crate::engine::events::{WorldEvent, WorldEvents};
+use crate::engine::forms::{Form, Forms};
+use crate::engine::players::Players;
+use crate::engine::world::World;
+use crate::network::models::{
+    create_message, create_translated_message, messages, ChatType, MessageComponents, MessageType,
+};
+
+use super::run_command;
+
+/// Handles `/form <name>`, showing a form from the world's forms.json
+pub fn on_form_command(
+    world: &mut World,
+    player_id: usize,
+    args: &[&str],
+) -> Option<messages::Message> {
+    let form = match args {
+        [name] => world.read_resource::<Forms>().get(name).cloned(),
+        _ => None,
+    };
+
+    match form {
+        Some(form) => {
+            show_form(world, player_id, form);
+            None
+        }
+        None => Some(create_translated_message(
+            MessageType::Message,
+            ChatType::Error,
+            "command.form.usage",
+            &[],
+        )),
+    }
+}
+
+/// Show a form to a player, replacing any open one, returns its id
+///
+/// Answers are checked against the form and emitted as `WorldEvent::FormSubmitted`, after
+/// running the command of the pressed button if any.
+pub fn show_form(world: &mut World, player_id: usize, form: Form) -> Option<u32> {
+    let id = {
+        let mut players = world.write_resource::<Players>();
+        let player = players.get_mut(&player_id)?;
+
+        player.form_id = player.form_id.wrapping_add(1);
+        player.form = Some((player.form_id, form.to_owned()));
+        player.form_id
+    };
+
+    let mut components = MessageComponents::default_for(MessageType::Form);
+    components.form = Some((id, form));
+
+    let message = create_message(components);
+    world.broadcast(&message, vec![player_id], vec![]);
+
+    Some(id)
+}
+
+/// Close the form a player has open, returns false if there's none
+pub fn close_form(world: &mut World, player_id: usize) -> bool {
+    let closed = match world.write_resource::<Players>().get_mut(&player_id) {
+        Some(player) => player.form.take().is_some(),
+        None => false,
+    };
+
+    if closed {
+        let message = create_message(MessageComponents::default_for(MessageType::FormClose));
+        world.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    closed
+}
+
+/// Handles a player's answer to their open form, ignoring answers that don't fit it
+pub fn on_form_response(world: &mut World, player_id: usize, msg: messages::Message) {
+    let answer = match msg.form_response {
+        Some(answer) => answer,
+        None => return,
+    };
+
+    let form = match world.write_resource::<Players>().get_mut(&player_id) {
+        Some(player) if matches!(&player.form, Some((id, _)) if *id == answer.id) => {
+            player.form.take().map(|(_, form)| form).unwrap()
+        }
+        _ => return,
+    };
+
+    let response = match form.validate(answer.button as usize, &answer.values) {
+        Some(response) => response,
+        None => return,
+    };
+
+    if let Some(command) = form.command(response.button) {
+        for msg in run_command(world, player_id, command) {
+            world.broadcast(&msg, vec![player_id], vec![]);
+        }
+    }
+
+    world
+        .write_resource::<WorldEvents>()
+        .push(WorldEvent::FormSubmitted {
+            player_id,
+            name: form.name,
+            response,
+        });
+}
+
+/// Handles a player closing their open form without answering
+pub fn on_form_close(world: &mut World, player_id: usize) {
+    if let Some(player) = world.write_resource::<Players>().get_mut(&player_id) {
+        player.form = None;
+    }
+}
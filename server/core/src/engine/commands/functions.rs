@@ -0,0 +1,60 @@
+use crate::engine::clock::Clock;
+use crate::engine::functions::{Functions, MAX_SCHEDULE_DELAY};
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// Run a `/function <name>` or `/function reload` command
+pub fn on_function_command(
+    world: &mut World,
+    player_id: usize,
+    args: &[&str],
+) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+        create_translated_message(MessageType::Message, chat_type, key, args)
+    };
+
+    match args {
+        ["reload"] => {
+            let count = world.write_resource::<Functions>().reload();
+            create_msg(
+                ChatType::Info,
+                "command.function.reload",
+                &[&count.to_string()],
+            )
+        }
+        [name] => match world.run_function(player_id, name) {
+            Some(count) => create_msg(ChatType::Info, "command.function", &[&count.to_string()]),
+            None => create_msg(ChatType::Error, "command.function.unknown", &[]),
+        },
+        _ => create_msg(ChatType::Error, "command.function.usage", &[]),
+    }
+}
+
+/// Run a `/schedule function <name> <ticks>` command
+pub fn on_schedule_command(
+    world: &mut World,
+    player_id: usize,
+    args: &[&str],
+) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+        create_translated_message(MessageType::Message, chat_type, key, args)
+    };
+
+    let (name, delay) = match args {
+        ["function", name, delay] => match delay.parse::<i32>() {
+            Ok(delay) => (*name, delay.clamp(1, MAX_SCHEDULE_DELAY)),
+            Err(_) => return create_msg(ChatType::Error, "command.schedule.usage", &[]),
+        },
+        _ => return create_msg(ChatType::Error, "command.schedule.usage", &[]),
+    };
+
+    let due = world.read_resource::<Clock>().tick.saturating_add(delay);
+    if world
+        .write_resource::<Functions>()
+        .schedule(name, player_id, due)
+    {
+        create_msg(ChatType::Info, "command.schedule", &[&delay.to_string()])
+    } else {
+        create_msg(ChatType::Error, "command.function.unknown", &[])
+    }
+}
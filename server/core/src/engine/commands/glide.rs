@@ -0,0 +1,57 @@
+use specs::WorldExt;
+
+use crate::comp::movement::{Gliding, Movement};
+use crate::comp::rigidbody::RigidBody;
+use crate::engine::config::WorldConfig;
+use crate::engine::players::Players;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// `/glide [target]`, starting or stopping the glide of a player in mid-air
+pub fn on_glide_command(world: &mut World, player_id: usize, args: &[&str]) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str| {
+        create_translated_message(MessageType::Message, chat_type, key, &[])
+    };
+
+    if !world.read_resource::<WorldConfig>().server_movement {
+        return create_msg(ChatType::Error, "command.glide.unavailable");
+    }
+
+    let entity = match args {
+        [] => match world.read_resource::<Players>().get(&player_id) {
+            Some(player) => player.entity,
+            None => return create_msg(ChatType::Error, "command.targets.none"),
+        },
+        [target] => match world.select_entities(player_id, target) {
+            Ok(entities) => match entities.first() {
+                Some(&entity) => entity,
+                None => return create_msg(ChatType::Error, "command.targets.none"),
+            },
+            Err(key) => return create_msg(ChatType::Error, key),
+        },
+        _ => return create_msg(ChatType::Error, "command.glide.usage"),
+    };
+
+    let airborne = match world.ecs.read_storage::<RigidBody>().get(entity) {
+        Some(body) => !body.in_fluid && body.at_rest_y() >= 0.0,
+        None => return create_msg(ChatType::Error, "command.targets.none"),
+    };
+
+    let mut movements = world.ecs.write_storage::<Movement>();
+    let movement = match movements.get_mut(entity) {
+        Some(movement) => movement,
+        None => return create_msg(ChatType::Error, "command.targets.none"),
+    };
+
+    if movement.clear() {
+        return create_msg(ChatType::Info, "command.glide.stopped");
+    }
+
+    // gliding starts in mid-air, the glide would end right away on the ground
+    if !airborne {
+        return create_msg(ChatType::Error, "command.glide.grounded");
+    }
+
+    movement.set(Box::new(Gliding));
+    create_msg(ChatType::Info, "command.glide")
+}
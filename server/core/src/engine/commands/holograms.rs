@@ -0,0 +1,148 @@
+use specs::WorldExt;
+
+use server_common::vec::Vec3;
+
+use crate::comp::rigidbody::RigidBody;
+use crate::engine::config::WorldConfig;
+use crate::engine::holograms::{Hologram, Holograms, HOLOGRAM_REACH};
+use crate::engine::players::Players;
+use crate::engine::world::{MessagesQueue, World};
+use crate::network::models::{
+    create_message, create_translated_message, messages, ChatType, MessageComponents, MessageType,
+};
+
+/// Run a `/hologram add <text>`, `/hologram set <text>` or `/hologram remove` command
+///
+/// Lines are separated by `|`. Holograms are added where the player stands, and the nearest
+/// one within reach is the one changed or removed.
+pub fn on_hologram_command(
+    world: &mut World,
+    player_id: usize,
+    args: &[&str],
+) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str| {
+        create_translated_message(MessageType::Message, chat_type, key, &[])
+    };
+
+    let position = match world
+        .read_resource::<Players>()
+        .get(&player_id)
+        .and_then(|player| {
+            world
+                .ecs
+                .read_storage::<RigidBody>()
+                .get(player.entity)
+                .map(|body| body.get_position())
+        }) {
+        Some(position) => position,
+        None => return create_msg(ChatType::Error, "command.unknown"),
+    };
+
+    let lines = args
+        .get(1..)
+        .unwrap_or(&[])
+        .join(" ")
+        .split('|')
+        .map(|line| line.trim().to_owned())
+        .collect::<Vec<_>>();
+
+    let reach = HOLOGRAM_REACH * world.read_resource::<WorldConfig>().dimension as f32;
+    let nearest = world
+        .read_resource::<Holograms>()
+        .nearest(&position, reach)
+        .map(|hologram| hologram.id.to_owned());
+
+    match (args.first().copied(), nearest) {
+        (Some("add"), _) if args.len() > 1 => {
+            spawn_hologram(world, position, lines);
+            create_msg(ChatType::Info, "command.hologram.added")
+        }
+        (Some("set"), Some(id)) if args.len() > 1 => {
+            update_hologram(world, &id, lines);
+            create_msg(ChatType::Info, "command.hologram.updated")
+        }
+        (Some("remove"), Some(id)) => {
+            remove_hologram(world, &id);
+            create_msg(ChatType::Info, "command.hologram.removed")
+        }
+        (Some("set"), None) | (Some("remove"), None) => {
+            create_msg(ChatType::Error, "command.hologram.missing")
+        }
+        _ => create_msg(ChatType::Error, "command.hologram.usage"),
+    }
+}
+
+/// Place a hologram showing lines of text at a world position, returns its id
+pub fn spawn_hologram(world: &mut World, position: Vec3<f32>, lines: Vec<String>) -> String {
+    let hologram = world
+        .write_resource::<Holograms>()
+        .spawn(position, lines)
+        .to_owned();
+    let id = hologram.id.to_owned();
+
+    broadcast_holograms(world, vec![hologram], vec![]);
+    id
+}
+
+/// Change the text of a hologram, false if there's no such hologram
+pub fn update_hologram(world: &mut World, id: &str, lines: Vec<String>) -> bool {
+    let hologram = world
+        .write_resource::<Holograms>()
+        .set_lines(id, lines)
+        .cloned();
+
+    match hologram {
+        Some(hologram) => {
+            broadcast_holograms(world, vec![hologram], vec![]);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Move a hologram to a world position, false if there's no such hologram
+pub fn move_hologram(world: &mut World, id: &str, position: Vec3<f32>) -> bool {
+    let hologram = world
+        .write_resource::<Holograms>()
+        .set_position(id, position)
+        .cloned();
+
+    match hologram {
+        Some(hologram) => {
+            broadcast_holograms(world, vec![hologram], vec![]);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Remove a hologram, false if there's no such hologram
+pub fn remove_hologram(world: &mut World, id: &str) -> bool {
+    if world.write_resource::<Holograms>().remove(id).is_none() {
+        return false;
+    }
+
+    let mut components = MessageComponents::default_for(MessageType::HologramRemove);
+    components.text = Some(id.to_owned());
+
+    world
+        .write_resource::<MessagesQueue>()
+        .push((create_message(components), None, None, None));
+    true
+}
+
+/// Send holograms to players, everyone if `include` is empty
+pub fn broadcast_holograms(world: &mut World, holograms: Vec<Hologram>, include: Vec<usize>) {
+    let mut components = MessageComponents::default_for(MessageType::Hologram);
+    components.holograms = Some(holograms);
+
+    let include = if include.is_empty() {
+        None
+    } else {
+        Some(include)
+    };
+
+    world
+        .write_resource::<MessagesQueue>()
+        .push((create_message(components), include, None, None));
+}
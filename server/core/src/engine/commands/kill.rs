@@ -0,0 +1,52 @@
+use specs::WorldExt;
+
+use crate::comp::health::Health;
+use crate::comp::metadata::Metadata;
+use crate::engine::events::{WorldEvent, WorldEvents};
+use crate::engine::players::Players;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// Run a `/kill [targets]` command, the targets defaulting to the player
+pub fn on_kill_command(world: &mut World, player_id: usize, args: &[&str]) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+        create_translated_message(MessageType::Message, chat_type, key, args)
+    };
+
+    let targets = match world.select_entities(player_id, args.first().copied().unwrap_or("@s")) {
+        Ok(targets) if targets.is_empty() => {
+            return create_msg(ChatType::Error, "command.targets.none", &[])
+        }
+        Ok(targets) => targets,
+        Err(key) => return create_msg(ChatType::Error, key, &[]),
+    };
+
+    for &entity in targets.iter() {
+        let victim = world
+            .read_resource::<Players>()
+            .iter()
+            .find(|(_, p)| p.entity == entity)
+            .map(|(id, _)| *id);
+
+        // dead players are left for respawning to deal with
+        if let Some(victim) = victim {
+            if let Some(health) = world.ecs.write_storage::<Health>().get_mut(entity) {
+                health.damage(health.current, 0.0);
+            }
+            if let Some(metadata) = world.ecs.write_storage::<Metadata>().get_mut(entity) {
+                metadata.set("health", "0");
+            }
+            world
+                .write_resource::<WorldEvents>()
+                .push(WorldEvent::PlayerDied { player_id: victim });
+        } else {
+            world.kill_entity(entity, None);
+        }
+    }
+
+    create_msg(
+        ChatType::Info,
+        "command.kill",
+        &[&targets.len().to_string()],
+    )
+}
@@ -0,0 +1,346 @@
+use rand::seq::SliceRandom;
+use specs::Entity;
+
+use server_common::vec::Vec3;
+
+use super::registry::Registry;
+
+#[cfg(feature = "server")]
+pub mod arenas;
+#[cfg(feature = "server")]
+mod dispatch;
+#[cfg(feature = "server")]
+pub mod fill;
+#[cfg(feature = "server")]
+pub mod forms;
+#[cfg(feature = "server")]
+pub mod functions;
+#[cfg(feature = "server")]
+pub mod glide;
+#[cfg(feature = "server")]
+pub mod holograms;
+#[cfg(feature = "server")]
+pub mod kill;
+#[cfg(feature = "server")]
+pub mod operators;
+#[cfg(feature = "server")]
+pub mod profile;
+#[cfg(feature = "server")]
+pub mod record;
+#[cfg(feature = "server")]
+pub mod restart;
+#[cfg(feature = "server")]
+pub mod rollback;
+#[cfg(feature = "server")]
+pub mod teams;
+#[cfg(feature = "server")]
+pub mod title;
+#[cfg(feature = "server")]
+pub mod tp;
+#[cfg(feature = "server")]
+pub mod trading;
+
+#[cfg(feature = "server")]
+pub use dispatch::{run_command, run_command_for, run_console_command};
+
+/// Player id commands from the console or the admin API run as, matching no player
+pub const CONSOLE_ID: usize = usize::MAX;
+
+/// Entity type players are matched as by `type=` filters
+pub const PLAYER_TYPE: &str = "player";
+
+/// Entities a selector starts from, before its filters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorKind {
+    /// `@a`, every player
+    All,
+    /// `@p`, the nearest player
+    Nearest,
+    /// `@r`, a random player
+    Random,
+    /// `@e`, every entity, players included
+    Entities,
+    /// `@s`, whoever ran the command
+    Itself,
+}
+
+/// An entity selector such as `@e[type=zombie,distance=..10,limit=3]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selector {
+    pub kind: SelectorKind,
+    /// Entity type, ignoring case
+    pub etype: Option<String>,
+    /// Player name, ignoring case
+    pub name: Option<String>,
+    /// Inclusive range of voxels away from whoever ran the command
+    pub distance: Option<(f32, f32)>,
+    /// Most entities selected, the nearest first
+    pub limit: Option<usize>,
+}
+
+/// Entities a command targets, picked by selector or a player's name
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Selector(Selector),
+    Name(String),
+}
+
+/// An entity that can be selected, positioned in voxels
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub entity: Entity,
+    pub position: Vec3<f32>,
+    /// `PLAYER_TYPE` for players
+    pub etype: String,
+    pub name: Option<String>,
+}
+
+impl Target {
+    /// Parse a target argument, errors with a translation key
+    pub fn parse(arg: &str) -> Result<Self, &'static str> {
+        if !arg.starts_with('@') {
+            return Ok(Target::Name(arg.to_owned()));
+        }
+
+        let (head, filters) = match arg.find('[') {
+            Some(index) if arg.ends_with(']') => (&arg[..index], &arg[index + 1..arg.len() - 1]),
+            Some(_) => return Err("command.selector.invalid"),
+            None => (arg, ""),
+        };
+
+        let kind = match head {
+            "@a" => SelectorKind::All,
+            "@p" => SelectorKind::Nearest,
+            "@r" => SelectorKind::Random,
+            "@e" => SelectorKind::Entities,
+            "@s" => SelectorKind::Itself,
+            _ => return Err("command.selector.invalid"),
+        };
+
+        let mut selector = Selector {
+            kind,
+            etype: None,
+            name: None,
+            distance: None,
+            limit: None,
+        };
+
+        for filter in filters.split(',').filter(|filter| !filter.is_empty()) {
+            let (key, value) = match filter.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err("command.selector.invalid"),
+            };
+
+            match key {
+                "type" => selector.etype = Some(value.to_owned()),
+                "name" => selector.name = Some(value.to_owned()),
+                "limit" => {
+                    selector.limit = Some(
+                        value
+                            .parse::<usize>()
+                            .map_err(|_| "command.selector.invalid")?,
+                    )
+                }
+                "distance" => selector.distance = Some(parse_range(value)?),
+                _ => return Err("command.selector.invalid"),
+            }
+        }
+
+        Ok(Target::Selector(selector))
+    }
+
+    /// Pick the targeted entities, the nearest to the sender first
+    ///
+    /// `sender` is whoever ran the command, `None` for the console.
+    pub fn select(&self, sender: Option<&Candidate>, candidates: &[Candidate]) -> Vec<Entity> {
+        let selector = match self {
+            Target::Name(name) => {
+                return candidates
+                    .iter()
+                    .filter(|c| c.etype == PLAYER_TYPE)
+                    .filter(|c| matches!(&c.name, Some(n) if n.eq_ignore_ascii_case(name)))
+                    .map(|c| c.entity)
+                    .take(1)
+                    .collect();
+            }
+            Target::Selector(selector) => selector,
+        };
+
+        let mut picked = match selector.kind {
+            SelectorKind::Itself => sender.into_iter().collect::<Vec<_>>(),
+            SelectorKind::Entities => candidates.iter().collect(),
+            _ => candidates
+                .iter()
+                .filter(|c| c.etype == PLAYER_TYPE)
+                .collect(),
+        };
+
+        let distance = |candidate: &Candidate| {
+            sender.map_or(0.0, |sender| {
+                let Vec3(dx, dy, dz) = candidate.position.sub(&sender.position);
+                (dx * dx + dy * dy + dz * dz).sqrt()
+            })
+        };
+
+        picked.retain(|c| {
+            selector
+                .etype
+                .as_ref()
+                .is_none_or(|etype| c.etype.eq_ignore_ascii_case(etype))
+                && selector
+                    .name
+                    .as_ref()
+                    .is_none_or(|name| matches!(&c.name, Some(n) if n.eq_ignore_ascii_case(name)))
+                && selector
+                    .distance
+                    .is_none_or(|(min, max)| (min..=max).contains(&distance(c)))
+        });
+
+        if selector.kind == SelectorKind::Random {
+            picked.shuffle(&mut rand::thread_rng());
+        } else {
+            picked.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+        }
+
+        let limit = match selector.kind {
+            SelectorKind::Nearest | SelectorKind::Random => selector.limit.unwrap_or(1),
+            _ => selector.limit.unwrap_or(usize::MAX),
+        };
+
+        picked.into_iter().take(limit).map(|c| c.entity).collect()
+    }
+}
+
+/// `a..b`, `..b`, `a..` or an exact `a`
+fn parse_range(value: &str) -> Result<(f32, f32), &'static str> {
+    let parse = |bound: &str, default: f32| match bound {
+        "" => Ok(default),
+        bound => bound.parse::<f32>().map_err(|_| "command.selector.invalid"),
+    };
+
+    match value.split_once("..") {
+        Some((min, max)) => Ok((parse(min, 0.0)?, parse(max, f32::MAX)?)),
+        None => {
+            let exact = parse(value, 0.0)?;
+            Ok((exact, exact))
+        }
+    }
+}
+
+/// A coordinate, either absolute or relative to whoever ran the command with `~`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coord {
+    Absolute(f32),
+    Relative(f32),
+}
+
+impl Coord {
+    /// Parse `12`, `~` or `~-3`, errors with a translation key
+    pub fn parse(arg: &str) -> Result<Self, &'static str> {
+        let invalid = |_| "command.position.invalid";
+
+        match arg.strip_prefix('~') {
+            Some("") => Ok(Coord::Relative(0.0)),
+            Some(offset) => offset.parse::<f32>().map(Coord::Relative).map_err(invalid),
+            None => arg.parse::<f32>().map(Coord::Absolute).map_err(invalid),
+        }
+    }
+
+    pub fn resolve(&self, origin: f32) -> f32 {
+        match self {
+            Coord::Absolute(value) => *value,
+            Coord::Relative(offset) => origin + offset,
+        }
+    }
+}
+
+/// A voxel position argument, three coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position(pub [Coord; 3]);
+
+impl Position {
+    /// Parse the first three arguments as a position, errors with a translation key
+    pub fn parse(args: &[&str]) -> Result<Self, &'static str> {
+        match args {
+            [x, y, z, ..] => Ok(Position([
+                Coord::parse(x)?,
+                Coord::parse(y)?,
+                Coord::parse(z)?,
+            ])),
+            _ => Err("command.position.invalid"),
+        }
+    }
+
+    /// Resolve to a voxel, relative coordinates starting from `origin`
+    pub fn resolve(&self, origin: &Vec3<i32>) -> Vec3<i32> {
+        let [x, y, z] = self.0;
+
+        Vec3(
+            x.resolve(origin.0 as f32).floor() as i32,
+            y.resolve(origin.1 as f32).floor() as i32,
+            z.resolve(origin.2 as f32).floor() as i32,
+        )
+    }
+}
+
+/// Parse a block argument, by name ignoring case or by id
+pub fn parse_block(arg: &str, registry: &Registry) -> Result<u32, &'static str> {
+    registry
+        .find_id_by_name(arg)
+        .or_else(|| arg.parse::<u32>().ok().filter(|&id| registry.has_type(id)))
+        .ok_or("command.block.unknown")
+}
+
+/// Parse an item argument, any block that can be held
+pub fn parse_item(arg: &str, registry: &Registry) -> Result<u32, &'static str> {
+    parse_block(arg, registry)
+        .ok()
+        .filter(|&id| {
+            let block = registry.get_block_by_id(id);
+            !block.is_empty && !block.is_fluid
+        })
+        .ok_or("command.item.unknown")
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::{Builder, World, WorldExt};
+
+    use super::*;
+
+    #[test]
+    fn selectors() {
+        let mut ecs = World::new();
+        let mut candidate = |etype: &str, name: Option<&str>, x: f32| Candidate {
+            entity: ecs.create_entity().build(),
+            position: Vec3(x, 0.0, 0.0),
+            etype: etype.to_owned(),
+            name: name.map(str::to_owned),
+        };
+
+        let me = candidate(PLAYER_TYPE, Some("Ian"), 0.0);
+        let far = candidate(PLAYER_TYPE, Some("Bob"), 20.0);
+        let zombie = candidate("Zombie", None, 5.0);
+        let candidates = vec![far.clone(), zombie.clone(), me.clone()];
+
+        let select = |arg: &str| Target::parse(arg).unwrap().select(Some(&me), &candidates);
+
+        assert_eq!(select("@p"), vec![me.entity]);
+        assert_eq!(select("@a"), vec![me.entity, far.entity]);
+        assert_eq!(select("@s"), vec![me.entity]);
+        assert_eq!(select("bob"), vec![far.entity]);
+        assert_eq!(select("@e[type=zombie]"), vec![zombie.entity]);
+        assert_eq!(select("@e[distance=..10]"), vec![me.entity, zombie.entity]);
+        assert_eq!(select("@e[distance=1..,limit=1]"), vec![zombie.entity]);
+        assert_eq!(select("@r[name=ian]"), vec![me.entity]);
+
+        assert!(Target::parse("@x").is_err());
+        assert!(Target::parse("@e[type=zombie").is_err());
+        assert!(Target::parse("@e[limit=a]").is_err());
+
+        let position = Position::parse(&["~", "~10", "-3"]).unwrap();
+        assert_eq!(position.resolve(&Vec3(1, 2, 3)), Vec3(1, 12, -3));
+        assert!(Position::parse(&["~", "a", "1"]).is_err());
+        assert!(Position::parse(&["~", "1"]).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use log::info;
+
+use crate::engine::players::Players;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+use super::CONSOLE_ID;
+
+/// Commands only operators and the console may run, as they affect the whole server, the world
+/// or other players
+pub const OPERATOR_COMMANDS: &[&str] = &[
+    "op", "deop", "restart", "rollback", "tp", "kill", "reload", "record", "save", "fill",
+    "summon", "effect", "title", "hologram", "arena", "form", "function", "schedule",
+];
+
+/// Subcommands only operators may run, of the commands open to every player
+pub const OPERATOR_SUBCOMMANDS: &[(&str, &str)] = &[
+    ("team", "remove"),
+    ("team", "color"),
+    ("team", "friendlyfire"),
+];
+
+/// Whether running a command, split on whitespace, takes an operator
+pub fn requires_operator(body: &[&str]) -> bool {
+    match body {
+        [command, ..] if OPERATOR_COMMANDS.contains(command) => true,
+        [command, subcommand, ..] => OPERATOR_SUBCOMMANDS.contains(&(command, subcommand)),
+        _ => false,
+    }
+}
+
+/// Whether a player may run operator commands, which the console always can
+pub fn is_operator(world: &World, player_id: usize) -> bool {
+    player_id == CONSOLE_ID
+        || world
+            .read_resource::<Players>()
+            .get(&player_id)
+            .is_some_and(|player| player.operator)
+}
+
+/// Handles `/op <player>` and `/deop <player>`, granting or revoking operator commands for the
+/// player's session
+///
+/// Names are picked by clients, so operators aren't remembered by name between sessions.
+pub fn on_op_command(world: &mut World, args: &[&str], operator: bool) -> messages::Message {
+    let name = match args {
+        [name] => *name,
+        _ => {
+            return create_translated_message(
+                MessageType::Message,
+                ChatType::Error,
+                "command.op.usage",
+                &[],
+            )
+        }
+    };
+
+    {
+        let mut players = world.write_resource::<Players>();
+        let player = players
+            .values_mut()
+            .find(|player| matches!(&player.name, Some(n) if n.eq_ignore_ascii_case(name)));
+
+        match player {
+            Some(player) => player.operator = operator,
+            None => {
+                return create_translated_message(
+                    MessageType::Message,
+                    ChatType::Error,
+                    "command.op.missing",
+                    &[],
+                )
+            }
+        }
+    }
+
+    info!(
+        "{} operator commands for \"{}\" in \"{}\".",
+        if operator { "Granted" } else { "Revoked" },
+        name,
+        world.name
+    );
+
+    let key = if operator {
+        "command.op"
+    } else {
+        "command.deop"
+    };
+    create_translated_message(MessageType::Message, ChatType::Info, key, &[name])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permissions() {
+        assert!(requires_operator(&["fill", "~", "~", "~"]));
+        assert!(requires_operator(&["function", "reload"]));
+        assert!(requires_operator(&["team", "remove", "red"]));
+        assert!(requires_operator(&["team", "friendlyfire", "red", "on"]));
+        assert!(!requires_operator(&["team", "join", "red"]));
+        assert!(!requires_operator(&["team"]));
+        assert!(!requires_operator(&["tps"]));
+    }
+}
@@ -0,0 +1,62 @@
+use crate::engine::clock::Clock;
+use crate::engine::config::WorldConfig;
+use crate::engine::profiler::Profiler;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// Run a `/tps` command, describing the recent ticks per second and tick time percentiles
+pub fn on_tps_command(world: &World) -> messages::Message {
+    let tick_rate = world.read_resource::<WorldConfig>().server_tick_rate;
+    let profiler = world.read_resource::<Profiler>();
+
+    let args = [
+        format!("{:.1}", profiler.tps(tick_rate)),
+        format!("{:.1}", profiler.tick_percentile(0.5)),
+        format!("{:.1}", profiler.tick_percentile(0.95)),
+        format!("{:.1}", profiler.tick_percentile(0.99)),
+    ];
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    create_translated_message(MessageType::Message, ChatType::Info, "command.tps", &args)
+}
+
+/// Run a `/profile start` or `/profile stop` command, the latter reporting every timing
+pub fn on_profile_command(world: &World, args: &[&str]) -> Vec<messages::Message> {
+    let tick = world.read_resource::<Clock>().tick;
+    let profiler = world.read_resource::<Profiler>();
+
+    let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+        create_translated_message(MessageType::Message, chat_type, key, args)
+    };
+
+    match args {
+        ["start"] => {
+            profiler.start(tick);
+            vec![create_msg(ChatType::Info, "command.profile.started", &[])]
+        }
+        ["stop"] => match profiler.stop(tick) {
+            Some(report) => {
+                let ticks = report.ticks.to_string();
+                let mut msgs = vec![create_msg(
+                    ChatType::Info,
+                    "command.profile.report",
+                    &[ticks.as_str()],
+                )];
+
+                for (name, timing) in report.timings.iter() {
+                    let per_tick = format!("{:.2}", report.per_tick(timing));
+                    let max = format!("{:.2}", timing.max.as_secs_f32() * 1000.0);
+                    msgs.push(create_msg(
+                        ChatType::Info,
+                        "command.profile.entry",
+                        &[*name, per_tick.as_str(), max.as_str()],
+                    ));
+                }
+
+                msgs
+            }
+            None => vec![create_msg(ChatType::Error, "command.profile.idle", &[])],
+        },
+        _ => vec![create_msg(ChatType::Error, "command.profile.usage", &[])],
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use log::{info, warn};
+
+use crate::engine::chunks::Chunks;
+use crate::engine::clock::Clock;
+use crate::engine::sweep_recorder::{
+    SweepRecorder, Written, DEFAULT_RECORDED_TICKS, TRACES_FOLDER,
+};
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// Run a `/record <target> [ticks]` command, recording the sweeps of an entity's body into a
+/// file of the world's traces, or `/record stop` to write it right away
+pub fn on_record_command(world: &mut World, player_id: usize, args: &[&str]) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+        create_translated_message(MessageType::Message, chat_type, key, args)
+    };
+
+    let (target, ticks) = match args {
+        ["stop"] => {
+            let stopped = world.write_resource::<SweepRecorder>().stop();
+
+            return match stopped {
+                Some((_, written)) => recording_message(written),
+                None => create_msg(ChatType::Error, "command.record.none", &[]),
+            };
+        }
+        [target] => (*target, DEFAULT_RECORDED_TICKS),
+        [target, ticks] => match ticks.parse::<u32>() {
+            Ok(ticks) if ticks > 0 => (*target, ticks),
+            _ => return create_msg(ChatType::Error, "command.record.usage", &[]),
+        },
+        _ => return create_msg(ChatType::Error, "command.record.usage", &[]),
+    };
+
+    let entity = match world.select_entities(player_id, target) {
+        Ok(entities) => match entities.first() {
+            Some(&entity) => entity,
+            None => return create_msg(ChatType::Error, "command.targets.none", &[]),
+        },
+        Err(key) => return create_msg(ChatType::Error, key, &[]),
+    };
+
+    let file = {
+        let chunks = world.read_resource::<Chunks>();
+        let folder = if chunks.config.save {
+            chunks.root_folder.join(TRACES_FOLDER)
+        } else {
+            PathBuf::from(TRACES_FOLDER)
+        };
+
+        folder.join(format!(
+            "sweeps-{}.json",
+            world.read_resource::<Clock>().tick
+        ))
+    };
+
+    world
+        .write_resource::<SweepRecorder>()
+        .start(entity, target, player_id, ticks, file);
+
+    create_msg(
+        ChatType::Info,
+        "command.record",
+        &[target, &ticks.to_string()],
+    )
+}
+
+/// Feedback on a recording written, or failing to be
+pub fn recording_message(written: Written) -> messages::Message {
+    match written {
+        Ok((file, count)) => {
+            info!("Wrote {} sweeps to {}", count, file.display());
+            create_translated_message(
+                MessageType::Message,
+                ChatType::Info,
+                "command.record.done",
+                &[&count.to_string(), &file.display().to_string()],
+            )
+        }
+        Err(error) => {
+            warn!("Unable to write the sweep recording: {}", error);
+            create_translated_message(
+                MessageType::Message,
+                ChatType::Error,
+                "command.record.failed",
+                &[&error],
+            )
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::engine::audit::parse_duration;
+use crate::engine::clock::unix_millis;
+use crate::engine::restart::RestartCountdown;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// Run a `/restart in <time>` or `/restart cancel` command, the time being such as 5m
+pub fn on_restart_command(world: &mut World, args: &[&str]) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str| {
+        create_translated_message(MessageType::Message, chat_type, key, &[])
+    };
+
+    match args {
+        ["in", delay] => match parse_duration(delay) {
+            Some(delay) => {
+                world
+                    .write_resource::<RestartCountdown>()
+                    .schedule(unix_millis(), delay);
+                create_msg(ChatType::Info, "command.restart")
+            }
+            None => create_msg(ChatType::Error, "command.restart.usage"),
+        },
+        ["cancel"] => {
+            if world.write_resource::<RestartCountdown>().cancel() {
+                let message = create_msg(ChatType::Server, "restart.cancelled");
+                world.broadcast(&message, vec![], vec![]);
+                create_msg(ChatType::Info, "command.restart.cancel")
+            } else {
+                create_msg(ChatType::Error, "command.restart.none")
+            }
+        }
+        _ => create_msg(ChatType::Error, "command.restart.usage"),
+    }
+}
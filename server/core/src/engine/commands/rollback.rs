@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crate::engine::audit::{parse_duration, AuditAction, AuditLog};
+use crate::engine::chunks::Chunks;
+use crate::engine::clock::unix_millis;
+use crate::engine::world::World;
+use crate::gen::blocks::BlockRotation;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// Handles `/rollback <player> <time>`, with times such as 30m
+pub fn on_rollback_command(
+    world: &mut World,
+    player_id: usize,
+    args: &[&str],
+) -> messages::Message {
+    match (args.first(), args.get(1).and_then(|t| parse_duration(t))) {
+        (Some(target), Some(duration)) => {
+            let since = unix_millis().saturating_sub(duration);
+            let count = rollback(world, player_id, target, since);
+
+            create_translated_message(
+                MessageType::Message,
+                ChatType::Info,
+                "command.rollback",
+                &[&count.to_string()],
+            )
+        }
+        _ => create_translated_message(
+            MessageType::Message,
+            ChatType::Error,
+            "command.rollback.usage",
+            &[],
+        ),
+    }
+}
+
+/// Revert the edits of a player logged since a time, returns how many voxels were reverted
+///
+/// Voxels edited since by someone else are left alone. The rollback is an edit of whoever
+/// ran it, so it can be undone.
+pub fn rollback(world: &mut World, player_id: usize, target: &str, since: u64) -> usize {
+    let records = {
+        let mut audit = world.write_resource::<AuditLog>();
+        audit.flush();
+        audit.read_since(since)
+    };
+
+    // the first edit of each voxel holds what it was before, the last what it should still be
+    let mut reverts: HashMap<[i32; 3], (u32, u32, u32, u32)> = HashMap::new();
+    let mut order = vec![];
+
+    for record in records.iter() {
+        if !record.player.eq_ignore_ascii_case(target) {
+            continue;
+        }
+
+        if let AuditAction::Edit {
+            voxel,
+            id,
+            previous,
+            previous_rotation,
+            previous_y_rotation,
+        } = &record.action
+        {
+            let revert = reverts.entry(*voxel).or_insert_with(|| {
+                order.push(*voxel);
+                (*previous, *previous_rotation, *previous_y_rotation, *id)
+            });
+            revert.3 = *id;
+        }
+    }
+
+    let reverts = {
+        let chunks = world.read_resource::<Chunks>();
+
+        order
+            .into_iter()
+            .rev()
+            .filter_map(|voxel| {
+                let [vx, vy, vz] = voxel;
+                let (previous, rotation, y_rotation, last) = reverts[&voxel];
+
+                if chunks.get_voxel_by_voxel(vx, vy, vz) == last {
+                    Some((voxel, previous, BlockRotation::encode(rotation, y_rotation)))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+
+    world
+        .batch_update_as(player_id, |transaction| {
+            for ([vx, vy, vz], id, rotation) in reverts.iter() {
+                transaction.set_rotated(*vx, *vy, *vz, *id, rotation);
+            }
+        })
+        .len()
+}
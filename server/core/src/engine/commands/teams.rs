@@ -0,0 +1,76 @@
+use crate::engine::players::Players;
+use crate::engine::teams::Teams;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// Run a `/team` command
+///
+/// `/team create <name> [color]`, `/team remove <name>`, `/team join <name>`, `/team leave`,
+/// `/team color <name> <color>` or `/team friendlyfire <name> <on|off>`.
+pub fn on_team_command(world: &mut World, player_id: usize, args: &[&str]) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str| {
+        create_translated_message(MessageType::Message, chat_type, key, &[])
+    };
+
+    let player_name = match world.read_resource::<Players>().get(&player_id) {
+        Some(player) => player.name.to_owned().unwrap_or_default(),
+        None => return create_msg(ChatType::Error, "command.unknown"),
+    };
+
+    let mut teams = world.write_resource::<Teams>();
+
+    let (chat_type, key, changed) = match args {
+        ["create", name] | ["create", name, _] => {
+            if teams.create(name, args.get(2).copied()) {
+                (ChatType::Info, "command.team.created", vec![])
+            } else {
+                (ChatType::Error, "command.team.exists", vec![])
+            }
+        }
+        ["remove", name] => match teams.remove(name) {
+            Some(team) => (
+                ChatType::Info,
+                "command.team.removed",
+                team.members.into_iter().collect(),
+            ),
+            None => (ChatType::Error, "command.team.missing", vec![]),
+        },
+        ["join", name] => {
+            if teams.join(name, &player_name) {
+                (ChatType::Info, "command.team.joined", vec![player_name])
+            } else {
+                (ChatType::Error, "command.team.missing", vec![])
+            }
+        }
+        ["leave"] => match teams.leave(&player_name) {
+            Some(_) => (ChatType::Info, "command.team.left", vec![player_name]),
+            None => (ChatType::Error, "command.team.missing", vec![]),
+        },
+        ["color", name, color] => match teams.get_mut(name) {
+            Some(team) => {
+                team.color = color.to_string();
+                let members = team.members.iter().cloned().collect();
+                (ChatType::Info, "command.team.updated", members)
+            }
+            None => (ChatType::Error, "command.team.missing", vec![]),
+        },
+        ["friendlyfire", name, toggle] if *toggle == "on" || *toggle == "off" => {
+            match teams.get_mut(name) {
+                Some(team) => {
+                    team.friendly_fire = *toggle == "on";
+                    (ChatType::Info, "command.team.updated", vec![])
+                }
+                None => (ChatType::Error, "command.team.missing", vec![]),
+            }
+        }
+        _ => return create_msg(ChatType::Error, "command.team.usage"),
+    };
+
+    drop(teams);
+
+    changed
+        .iter()
+        .for_each(|member| world.refresh_team_of(member));
+
+    create_msg(chat_type, key)
+}
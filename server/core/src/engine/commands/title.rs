@@ -0,0 +1,48 @@
+use crate::engine::hud::Title;
+use crate::engine::players::Players;
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+/// Run a `/title <targets> <title>[|<subtitle>]` or `/title <targets> clear` command
+pub fn on_title_command(world: &mut World, player_id: usize, args: &[&str]) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+        create_translated_message(MessageType::Message, chat_type, key, args)
+    };
+
+    if args.len() < 2 {
+        return create_msg(ChatType::Error, "command.title.usage", &[]);
+    }
+
+    let targets = match world.select_entities(player_id, args[0]) {
+        Ok(targets) => targets,
+        Err(key) => return create_msg(ChatType::Error, key, &[]),
+    };
+
+    let player_ids = world
+        .read_resource::<Players>()
+        .iter()
+        .filter(|(_, player)| targets.contains(&player.entity))
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+
+    if player_ids.is_empty() {
+        return create_msg(ChatType::Error, "command.targets.none", &[]);
+    }
+
+    let text = args[1..].join(" ");
+    let (title, subtitle) = text.split_once('|').unwrap_or((text.as_str(), ""));
+
+    for &id in player_ids.iter() {
+        if args[1..] == ["clear"] {
+            world.clear_title(id);
+        } else {
+            world.send_title(id, Title::new(title.trim(), subtitle.trim()));
+        }
+    }
+
+    create_msg(
+        ChatType::Info,
+        "command.title",
+        &[&player_ids.len().to_string()],
+    )
+}
@@ -0,0 +1,49 @@
+use crate::engine::world::World;
+use crate::network::models::{create_translated_message, messages, ChatType, MessageType};
+
+use super::Position;
+
+/// Run a `/tp [targets] <x y z|destination>` command, the targets defaulting to the player
+pub fn on_tp_command(world: &mut World, player_id: usize, args: &[&str]) -> messages::Message {
+    let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+        create_translated_message(MessageType::Message, chat_type, key, args)
+    };
+
+    let (targets, destination) = match args.len() {
+        1 | 3 => ("@s", args),
+        2 | 4 => (args[0], &args[1..]),
+        _ => return create_msg(ChatType::Error, "command.tp.usage", &[]),
+    };
+
+    let voxel = match destination {
+        [destination] => {
+            let destination = world
+                .select_entities(player_id, destination)
+                .map(|entities| entities.first().and_then(|&e| world.entity_voxel(e)));
+
+            match destination {
+                Ok(Some(voxel)) => voxel,
+                Ok(None) => return create_msg(ChatType::Error, "command.targets.none", &[]),
+                Err(key) => return create_msg(ChatType::Error, key, &[]),
+            }
+        }
+        _ => match Position::parse(destination) {
+            Ok(position) => position.resolve(&world.player_voxel(player_id).unwrap_or_default()),
+            Err(key) => return create_msg(ChatType::Error, key, &[]),
+        },
+    };
+
+    let targets = match world.select_entities(player_id, targets) {
+        Ok(targets) if targets.is_empty() => {
+            return create_msg(ChatType::Error, "command.targets.none", &[])
+        }
+        Ok(targets) => targets,
+        Err(key) => return create_msg(ChatType::Error, key, &[]),
+    };
+
+    for &entity in targets.iter() {
+        world.teleport_entity(entity, &voxel);
+    }
+
+    create_msg(ChatType::Info, "command.tp", &[&targets.len().to_string()])
+}
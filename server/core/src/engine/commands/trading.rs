@@ -0,0 +1,219 @@
+use specs::{Entity, WorldExt};
+
+use server_common::vec::Vec3;
+use server_utils::convert::map_world_to_voxel;
+
+use crate::comp::etype::EType;
+use crate::comp::rigidbody::RigidBody;
+use crate::comp::trader::Trader;
+use crate::engine::config::WorldConfig;
+use crate::engine::container::{SlotAction, CONTAINER_REACH};
+use crate::engine::players::{Player, Players};
+use crate::engine::trading::{click_trade, TradeLists, TradeWindow, TRADER_KIND};
+use crate::engine::world::World;
+use crate::network::models::{
+    create_message, messages, ContainerProtocol, MessageComponents, MessageType,
+};
+
+/// Handle a player trading with the entity whose id is the message's text
+pub fn on_trade(world: &mut World, player_id: usize, msg: messages::Message) {
+    if let Some(target) = world.find_entity(&msg.text) {
+        open_trade(world, player_id, target);
+    }
+}
+
+/// Open a trade window into an entity for a player
+///
+/// Returns false if the entity is out of reach or its type doesn't trade. Traders get the
+/// list of their type the first time, keeping their own stock from then on.
+pub fn open_trade(world: &mut World, player_id: usize, entity: Entity) -> bool {
+    let dimension = world.read_resource::<WorldConfig>().dimension as f32;
+
+    if !world.is_within_reach(player_id, entity, CONTAINER_REACH * dimension) {
+        return false;
+    }
+
+    if world.ecs.read_storage::<Trader>().get(entity).is_none() {
+        let offers = match world.ecs.read_storage::<EType>().get(entity) {
+            Some(etype) => world.read_resource::<TradeLists>().get(&etype.0).cloned(),
+            None => None,
+        };
+
+        let offers = match offers {
+            Some(offers) => offers,
+            None => return false,
+        };
+
+        world
+            .ecs
+            .write_storage::<Trader>()
+            .insert(entity, Trader { offers })
+            .expect("Unable to make an entity a trader.");
+    }
+
+    world.set_viewing(player_id, None);
+    close_trade(world, player_id);
+
+    if let Some(player) = world.write_resource::<Players>().get_mut(&player_id) {
+        player.container_id = player.container_id.wrapping_add(1);
+        player.trading = Some((entity, TradeWindow::new()));
+    }
+
+    send_trade(world, player_id);
+
+    true
+}
+
+/// Close a player's trade window, dropping the payment left in it at their feet
+pub fn close_trade(world: &mut World, player_id: usize) {
+    let (entity, mut window) = match world
+        .write_resource::<Players>()
+        .get_mut(&player_id)
+        .and_then(|player| {
+            player
+                .trading
+                .take()
+                .map(|trading| (player.entity, trading.1))
+        }) {
+        Some(trading) => trading,
+        None => return,
+    };
+
+    let payment = window.take_payment();
+    if payment.is_empty() {
+        return;
+    }
+
+    let position = match world.ecs.read_storage::<RigidBody>().get(entity) {
+        Some(body) => body.get_position(),
+        None => return,
+    };
+
+    world.drop_items(payment, &position);
+}
+
+/// Send the state of a player's trade window, closing it if the trader is gone
+fn send_trade(world: &mut World, player_id: usize) {
+    let (id, cursor, entity, window) = match world.read_resource::<Players>().get(&player_id) {
+        Some(Player {
+            container_id,
+            cursor,
+            trading: Some((entity, window)),
+            ..
+        }) => (*container_id, cursor.to_owned(), *entity, window.to_owned()),
+        _ => return,
+    };
+
+    let offers = world
+        .ecs
+        .read_storage::<Trader>()
+        .get(entity)
+        .map(|trader| trader.offers.to_owned());
+    let position = world
+        .ecs
+        .read_storage::<RigidBody>()
+        .get(entity)
+        .map(|body| body.get_position());
+
+    let (offers, Vec3(px, py, pz)) = match (offers, position) {
+        (Some(offers), Some(position)) => (offers, position),
+        _ => {
+            close_trade(world, player_id);
+            return;
+        }
+    };
+
+    let dimension = world.read_resource::<WorldConfig>().dimension;
+
+    let mut components = MessageComponents::default_for(MessageType::ContainerOpen);
+    components.container = Some(ContainerProtocol {
+        id,
+        kind: TRADER_KIND.to_owned(),
+        voxel: map_world_to_voxel(px, py, pz, dimension),
+        slots: window.slots(&offers),
+        cursor,
+        selected: window.selected,
+        offers,
+    });
+
+    let message = create_message(components);
+    world.broadcast(&message, vec![player_id], vec![]);
+}
+
+/// Handles a player choosing an offer in their trade window
+pub fn on_trade_select(world: &mut World, player_id: usize, msg: messages::Message) {
+    let click = match msg.click {
+        Some(click) => click,
+        None => return,
+    };
+
+    let entity = match world.read_resource::<Players>().get(&player_id) {
+        Some(Player {
+            container_id,
+            trading: Some((entity, _)),
+            ..
+        }) if *container_id == click.container => *entity,
+        _ => return,
+    };
+
+    let offers = match world.ecs.read_storage::<Trader>().get(entity) {
+        Some(trader) => trader.offers.to_owned(),
+        None => return,
+    };
+
+    if let Some(Player {
+        trading: Some((_, window)),
+        ..
+    }) = world.write_resource::<Players>().get_mut(&player_id)
+    {
+        window.select(click.slot as usize, &offers);
+    }
+
+    send_trade(world, player_id);
+}
+
+/// Handles a slot click in the player's trade window, validating trades against the payment
+/// and the trader's stock
+pub fn on_trade_click(world: &mut World, player_id: usize, click: messages::SlotClick) {
+    let action = match messages::slot_click::Action::from_i32(click.action) {
+        Some(messages::slot_click::Action::Pickup) => SlotAction::Pickup,
+        Some(messages::slot_click::Action::Split) => SlotAction::Split,
+        None => return,
+    };
+
+    let (entity, mut window, mut cursor) = match world.read_resource::<Players>().get(&player_id) {
+        Some(Player {
+            container_id,
+            cursor,
+            trading: Some((entity, window)),
+            ..
+        }) if *container_id == click.container => (*entity, window.to_owned(), cursor.to_owned()),
+        _ => return,
+    };
+
+    let traded = {
+        let mut traders = world.ecs.write_storage::<Trader>();
+        let trader = match traders.get_mut(entity) {
+            Some(trader) => trader,
+            None => return,
+        };
+
+        click_trade(
+            &mut window,
+            &mut trader.offers,
+            click.slot as usize,
+            action,
+            &mut cursor,
+        )
+    };
+
+    if traded {
+        if let Some(player) = world.write_resource::<Players>().get_mut(&player_id) {
+            player.cursor = cursor;
+            player.trading = Some((entity, window));
+        }
+    }
+
+    // invalid clicks resync the client too
+    send_trade(world, player_id);
+}
@@ -2,16 +2,309 @@ use hashbrown::HashMap;
 
 use std::fs::{self, File};
 
+use serde::{Deserialize, Serialize};
+
 use server_utils::json;
 
+#[cfg(feature = "server")]
+use crate::network::{bandwidth::BandwidthConfig, proxy::ProxyConfig, tls::TlsConfig};
+
+use server_common::vec::Vec3;
+
 use super::{
-    registry::Registry,
-    world::{WorldConfig, WorldMeta},
+    bridge::BridgeConfig, cron::TaskConfig, data_packs::DataPacks,
+    object_storage::ObjectStorageConfig, picking::EditRules, registry::Registry,
+    resource_pack::ResourcePack, shards::ShardConfig, storage::StorageKind,
 };
 
 pub struct Configs;
 
+/// Options of a world, its entry in `worlds.json` merged over the `shared` section
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldConfig {
+    pub chunk_size: usize,
+    pub dimension: usize,
+    pub max_height: u32,
+    /// Lowest voxel y, negative for deep worlds, the world spanning `max_height` voxels up from it
+    #[serde(default)]
+    pub min_height: i32,
+    pub max_light_level: u32,
+    pub save: bool,
+    pub chunk_root: String,
+    pub render_radius: usize,
+    pub max_loaded_chunks: usize,
+    pub sub_chunks: u32,
+    pub generation: String,
+    pub player_dimensions: Vec3<f32>,
+    pub player_head: f32,
+    pub max_per_thread: usize,
+    pub server_tick_rate: u64,
+
+    /// Clients send inputs instead of positions, and the server simulates their movement
+    #[serde(default)]
+    pub server_movement: bool,
+
+    /// Backend saved worlds are stored with, flat files by default
+    #[serde(default)]
+    pub storage: StorageKind,
+
+    /// Bucket an `s3` storage persists to
+    #[serde(default)]
+    pub object_storage: Option<ObjectStorageConfig>,
+    /// Chunk ranges owned by each shard when the world is split between processes
+    #[serde(default)]
+    pub shards: ShardConfig,
+    /// Shared by the servers of a network to sign the tokens players transfer between them with
+    #[serde(default)]
+    pub transfer_secret: Option<String>,
+    /// Seconds a dropped player is kept for its client to resume the session, 0 to disable
+    #[serde(default)]
+    pub resume_window: u64,
+    /// Entity snapshots sent to clients per second, 0 to send entities every tick
+    #[serde(default)]
+    pub snapshot_rate: u32,
+    /// Tell players which block the players near them are targeting
+    #[serde(default)]
+    pub broadcast_targets: bool,
+    /// Reach and line of sight block edits from players are checked for
+    #[serde(default)]
+    pub edits: EditRules,
+    /// Webhooks chat and player events are forwarded to, and the secret of the bot talking back
+    #[serde(default)]
+    pub bridge: BridgeConfig,
+    /// Repeating tasks, such as announcements or a nightly save
+    #[serde(default)]
+    pub tasks: Vec<TaskConfig>,
+    /// Seconds the world can go without ticking before the watchdog restarts the server, 0 to
+    /// turn the watchdog off
+    #[serde(default = "default_watchdog_timeout")]
+    pub watchdog_timeout: u64,
+    /// Whether clients may ask for debug overlays, which show light levels and entities through
+    /// walls
+    #[serde(default)]
+    pub debug_overlays: bool,
+    /// Save the meshes of chunks alongside them, so that a restart doesn't remesh the spawn area
+    /// before players can join
+    #[serde(default)]
+    pub cache_meshes: bool,
+    #[serde(default)]
+    pub physics: PhysicsConfig,
+    #[serde(default)]
+    pub parkour: ParkourConfig,
+}
+
+fn default_watchdog_timeout() -> u64 {
+    60
+}
+
+/// Movement constants of a world, sent to clients in `INIT` for them to predict movement with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PhysicsConfig {
+    /// Downwards acceleration, in blocks per second squared
+    pub gravity: f32,
+    /// Fastest speed bodies fall at, 0 for no limit
+    pub terminal_velocity: f32,
+    pub jump_impulse: f32,
+    /// Top speeds of players walking and sprinting, in blocks per second
+    pub walk_speed: f32,
+    pub sprint_speed: f32,
+    /// Multiplier of the friction between bodies and the ground
+    pub friction: f32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            gravity: 24.0,
+            terminal_velocity: 0.0,
+            jump_impulse: 8.0,
+            walk_speed: 6.0,
+            sprint_speed: 8.0,
+            friction: 1.0,
+        }
+    }
+}
+
+/// Moves players can make against walls in mid-air, for parkour game modes, all off by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ParkourConfig {
+    /// Fraction of their falling speed players sliding down a wall lose per second, 0 to turn
+    /// sliding off
+    pub wall_slide_friction: f32,
+    /// Speed of players jumping off a wall, 0 to turn wall jumps off
+    pub wall_jump_impulse: f32,
+    /// Whether falling players grab onto the top of walls they push against
+    pub ledge_grab: bool,
+}
+
+/// Shape of the chunks of a world, which clients size their own chunks and meshes by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dimensions {
+    /// Width of a chunk in voxels
+    pub chunk_size: usize,
+    /// Height of the world in voxels, such as 256 or 512 for taller builds
+    pub max_height: u32,
+    /// Lowest voxel y, such as -64 for deep underground layers
+    #[serde(default)]
+    pub min_height: i32,
+    /// Slices a chunk is meshed in, which have to split the height evenly
+    pub sub_chunks: u32,
+    /// Size of a voxel
+    pub dimension: usize,
+}
+
+impl Dimensions {
+    /// Check that chunks of these dimensions can be stored and meshed
+    pub fn validate(&self) -> Result<(), String> {
+        if self.chunk_size == 0 || self.max_height == 0 || self.dimension == 0 {
+            return Err("chunkSize, maxHeight and dimension have to be positive".to_owned());
+        }
+
+        if self.min_height > 0 {
+            return Err("minHeight can't be above 0".to_owned());
+        }
+
+        if self.sub_chunks == 0 || !self.max_height.is_multiple_of(self.sub_chunks) {
+            return Err(format!(
+                "maxHeight {} can't be split into {} sub chunks",
+                self.max_height, self.sub_chunks
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl WorldConfig {
+    /// One over the highest voxel y of the world
+    pub fn top(&self) -> i32 {
+        self.min_height + self.max_height as i32
+    }
+
+    /// Whether a voxel y is within the world's height
+    pub fn contains_height(&self, vy: i32) -> bool {
+        vy >= self.min_height && vy < self.top()
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        Dimensions {
+            chunk_size: self.chunk_size,
+            max_height: self.max_height,
+            min_height: self.min_height,
+            sub_chunks: self.sub_chunks,
+            dimension: self.dimension,
+        }
+    }
+}
+
+/// What a world is listed with, and the state it starts in
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldMeta {
+    pub name: String,
+    pub description: String,
+    pub preload: i16,
+    pub tick_speed: f32,
+    pub time: f32,
+
+    #[serde(default)]
+    pub packs: Vec<String>,
+}
+
+/// Server-wide options, the `server` section of `worlds.json`
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerConfig {
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    /// Certificate for the listeners serving `wss://`, requires the `tls` feature
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    /// Sockets to listen on, `localhost:4000` if none
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+    /// Seconds without hearing from a client before its connection is dropped
+    #[serde(default = "default_keepalive_timeout")]
+    pub keepalive_timeout: u64,
+    #[serde(default)]
+    pub bandwidth: BandwidthConfig,
+    /// Bearer token authorizing the admin API under `/admin/`, which is off without one
+    #[serde(default)]
+    pub admin_token: Option<String>,
+}
+
+#[cfg(feature = "server")]
+fn default_keepalive_timeout() -> u64 {
+    30
+}
+
+#[cfg(feature = "server")]
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            proxy: ProxyConfig::default(),
+            tls: None,
+            listeners: vec![],
+            keepalive_timeout: default_keepalive_timeout(),
+            bandwidth: BandwidthConfig::default(),
+            admin_token: None,
+        }
+    }
+}
+
+/// Address listened on when no listeners are configured
+#[cfg(feature = "server")]
+pub const DEFAULT_ADDRESS: &str = "localhost:4000";
+
+/// A socket the server listens on, such as `0.0.0.0:4000` or `[::]:4000`
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerConfig {
+    pub address: String,
+    /// Serve `wss://` with the server's certificate
+    #[serde(default)]
+    pub tls: bool,
+    /// Only serve the admin routes, such as chunk handoffs, which public listeners then don't
+    #[serde(default)]
+    pub admin: bool,
+}
+
+#[cfg(feature = "server")]
+impl ServerConfig {
+    /// Listeners to bind, a single one on `DEFAULT_ADDRESS` if none are configured
+    pub fn listeners(&self) -> Vec<ListenerConfig> {
+        if !self.listeners.is_empty() {
+            return self.listeners.to_owned();
+        }
+
+        vec![ListenerConfig {
+            address: DEFAULT_ADDRESS.to_owned(),
+            tls: self.tls.is_some(),
+            admin: false,
+        }]
+    }
+}
+
 impl Configs {
+    /// Load the server-wide options, defaults if there's no `server` section
+    #[cfg(feature = "server")]
+    pub fn load_server(path: &str) -> ServerConfig {
+        let worlds_json: serde_json::Value =
+            serde_json::from_reader(File::open(path).unwrap()).unwrap();
+
+        match worlds_json.get("server") {
+            Some(server) => serde_json::from_value(server.to_owned())
+                .unwrap_or_else(|e| panic!("Invalid server config: {}", e)),
+            None => ServerConfig::default(),
+        }
+    }
+
     pub fn load_worlds(path: &str) -> (HashMap<String, (WorldMeta, WorldConfig)>, Registry) {
         let worlds_json: serde_json::Value =
             serde_json::from_reader(File::open(path).unwrap()).unwrap();
@@ -33,6 +326,16 @@ impl Configs {
             let mut meta: WorldMeta = serde_json::from_value(world_json.clone()).unwrap();
             let config: WorldConfig = serde_json::from_value(world_json).unwrap();
 
+            if let Err(e) = config.dimensions().validate() {
+                panic!("Invalid dimensions of world \"{}\": {}", meta.name, e);
+            }
+
+            if config.save {
+                if let Err(e) = config.storage.validate(config.object_storage.as_ref()) {
+                    panic!("Invalid storage of world \"{}\": {}", meta.name, e);
+                }
+            }
+
             meta.packs = packs.clone();
 
             map.insert(meta.name.to_owned(), (meta, config));
@@ -42,8 +345,60 @@ impl Configs {
             panic!("No configs found!");
         }
 
-        let registry = Registry::new(packs, true);
+        // the server-wide data packs, layered over the metadata in order
+        let data_packs = match worlds_json.get("dataPacks") {
+            Some(names) => DataPacks::new(
+                serde_json::from_value(names.to_owned())
+                    .unwrap_or_else(|e| panic!("Invalid data packs: {}", e)),
+            ),
+            None => DataPacks::default(),
+        };
+
+        let mut registry = Registry::new(packs, data_packs, true);
+
+        // the server-wide resource pack, shared by all worlds
+        if let Some(name) = worlds_json["resourcePack"].as_str() {
+            registry
+                .apply_resource_pack(ResourcePack::load(name))
+                .unwrap_or_else(|e| panic!("{}", e));
+        }
 
         (map, registry)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimensions() {
+        let dimensions = Dimensions {
+            chunk_size: 16,
+            max_height: 512,
+            min_height: -64,
+            sub_chunks: 8,
+            dimension: 1,
+        };
+
+        assert!(dimensions.validate().is_ok());
+        assert!(Dimensions {
+            sub_chunks: 7,
+            ..dimensions
+        }
+        .validate()
+        .is_err());
+        assert!(Dimensions {
+            chunk_size: 0,
+            ..dimensions
+        }
+        .validate()
+        .is_err());
+        assert!(Dimensions {
+            min_height: 64,
+            ..dimensions
+        }
+        .validate()
+        .is_err());
+    }
+}
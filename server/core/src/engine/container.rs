@@ -0,0 +1,233 @@
+use super::{
+    block_entities::{BlockEntity, BlockEntityData, Chest, Furnace, MAX_STACK_SIZE},
+    items::ItemStack,
+    recipes::Recipes,
+};
+
+/// Slots of a chest opened for the first time
+pub const CHEST_SIZE: usize = 27;
+
+/// How far away in voxels players can open containers from
+pub const CONTAINER_REACH: f32 = 8.0;
+
+/// A block entity with item slots that players can move items in and out of
+pub trait Container: BlockEntity {
+    fn slots(&self) -> Vec<Option<ItemStack>>;
+
+    fn set_slot(&mut self, index: usize, stack: Option<ItemStack>);
+
+    /// Whether a player may put a stack into a slot
+    fn can_place(&self, _index: usize, _stack: &ItemStack, _recipes: &Recipes) -> bool {
+        true
+    }
+}
+
+impl Container for Chest {
+    fn slots(&self) -> Vec<Option<ItemStack>> {
+        let mut slots = self.slots.to_owned();
+        slots.resize(slots.len().max(CHEST_SIZE), None);
+        slots
+    }
+
+    fn set_slot(&mut self, index: usize, stack: Option<ItemStack>) {
+        if self.slots.len() <= index {
+            self.slots.resize(index + 1, None);
+        }
+
+        self.slots[index] = stack;
+    }
+}
+
+/// Furnace slots are the input, the fuel, then the output
+impl Container for Furnace {
+    fn slots(&self) -> Vec<Option<ItemStack>> {
+        vec![
+            self.input.to_owned(),
+            self.fuel.to_owned(),
+            self.output.to_owned(),
+        ]
+    }
+
+    fn set_slot(&mut self, index: usize, stack: Option<ItemStack>) {
+        match index {
+            0 => {
+                // a different input starts smelting over
+                if self.input.as_ref().map(|s| s.id) != stack.as_ref().map(|s| s.id) {
+                    self.progress = 0;
+                }
+
+                self.input = stack
+            }
+            1 => self.fuel = stack,
+            _ => self.output = stack,
+        }
+    }
+
+    fn can_place(&self, index: usize, stack: &ItemStack, recipes: &Recipes) -> bool {
+        match index {
+            0 => true,
+            1 => recipes.get_fuel(stack.id).is_some(),
+            _ => false,
+        }
+    }
+}
+
+/// Ways a player can click on a container slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotAction {
+    /// Take, place, merge or swap the whole stack
+    Pickup,
+    /// Take half of the slot, or place a single item held by the cursor
+    Split,
+}
+
+/// Move items between a slot and the cursor, returns false if the click isn't allowed
+pub fn click_slot(
+    slot: &mut Option<ItemStack>,
+    cursor: &mut Option<ItemStack>,
+    action: SlotAction,
+    can_place: bool,
+) -> bool {
+    match (slot.take(), cursor.take(), action) {
+        (None, None, _) => true,
+        // picking up
+        (Some(stack), None, SlotAction::Pickup) => {
+            *cursor = Some(stack);
+            true
+        }
+        (Some(mut stack), None, SlotAction::Split) => {
+            let taken = stack.count.div_ceil(2);
+            stack.count -= taken;
+
+            *cursor = Some(ItemStack::new(stack.id, taken));
+            *slot = if stack.count > 0 { Some(stack) } else { None };
+            true
+        }
+        // placing down
+        (current, Some(held), _) if !can_place => {
+            *slot = current;
+            *cursor = Some(held);
+            false
+        }
+        (None, Some(held), SlotAction::Pickup) => {
+            *slot = Some(held);
+            true
+        }
+        (None, Some(mut held), SlotAction::Split) => {
+            held.count -= 1;
+
+            *slot = Some(ItemStack::new(held.id, 1));
+            *cursor = if held.count > 0 { Some(held) } else { None };
+            true
+        }
+        // merging into the same kind of item
+        (Some(mut stack), Some(mut held), action) if stack.id == held.id => {
+            let space = MAX_STACK_SIZE.saturating_sub(stack.count);
+            let moved = match action {
+                SlotAction::Pickup => held.count.min(space),
+                SlotAction::Split => space.min(1),
+            };
+
+            stack.count += moved;
+            held.count -= moved;
+
+            *slot = Some(stack);
+            *cursor = if held.count > 0 { Some(held) } else { None };
+            true
+        }
+        (Some(stack), Some(held), SlotAction::Pickup) => {
+            *slot = Some(held);
+            *cursor = Some(stack);
+            true
+        }
+        (current, held, SlotAction::Split) => {
+            *slot = current;
+            *cursor = held;
+            false
+        }
+    }
+}
+
+/// Get the slots of a block entity, `None` if it isn't a container
+pub fn get_container_slots(data: &BlockEntityData) -> Option<Vec<Option<ItemStack>>> {
+    match data.kind.as_str() {
+        Chest::KIND => data.get::<Chest>().map(|chest| chest.slots()),
+        Furnace::KIND => data.get::<Furnace>().map(|furnace| furnace.slots()),
+        _ => None,
+    }
+}
+
+fn click_typed<T: Container>(
+    data: &BlockEntityData,
+    index: usize,
+    action: SlotAction,
+    cursor: &mut Option<ItemStack>,
+    recipes: &Recipes,
+) -> Option<BlockEntityData> {
+    let mut container = data.get::<T>()?;
+    let mut slot = container.slots().get(index)?.to_owned();
+    let mut held = cursor.to_owned();
+
+    let can_place = held
+        .as_ref()
+        .is_none_or(|stack| container.can_place(index, stack, recipes));
+
+    if !click_slot(&mut slot, &mut held, action, can_place) {
+        return None;
+    }
+
+    container.set_slot(index, slot);
+    *cursor = held;
+
+    Some(BlockEntityData::new(&container))
+}
+
+/// Apply a slot click to a container block entity
+///
+/// Returns the updated block entity, or `None` if the click is invalid and nothing changed.
+pub fn click_container(
+    data: &BlockEntityData,
+    index: usize,
+    action: SlotAction,
+    cursor: &mut Option<ItemStack>,
+    recipes: &Recipes,
+) -> Option<BlockEntityData> {
+    match data.kind.as_str() {
+        Chest::KIND => click_typed::<Chest>(data, index, action, cursor, recipes),
+        Furnace::KIND => click_typed::<Furnace>(data, index, action, cursor, recipes),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_clicks() {
+        let mut slot = Some(ItemStack::new(1, 5));
+        let mut cursor = None;
+
+        assert!(click_slot(&mut slot, &mut cursor, SlotAction::Split, true));
+        assert_eq!(slot, Some(ItemStack::new(1, 2)));
+        assert_eq!(cursor, Some(ItemStack::new(1, 3)));
+
+        assert!(click_slot(&mut slot, &mut cursor, SlotAction::Pickup, true));
+        assert_eq!(slot, Some(ItemStack::new(1, 5)));
+        assert_eq!(cursor, None);
+
+        let mut cursor = Some(ItemStack::new(2, 1));
+        assert!(!click_slot(
+            &mut slot,
+            &mut cursor,
+            SlotAction::Pickup,
+            false
+        ));
+        assert_eq!(slot, Some(ItemStack::new(1, 5)));
+        assert_eq!(cursor, Some(ItemStack::new(2, 1)));
+
+        assert!(click_slot(&mut slot, &mut cursor, SlotAction::Pickup, true));
+        assert_eq!(slot, Some(ItemStack::new(2, 1)));
+        assert_eq!(cursor, Some(ItemStack::new(1, 5)));
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+use log::warn;
+
+use serde::Deserialize;
+
+use super::storage::Storage;
+
+/// Next run times of the world's tasks are saved under this metadata key of the world's storage
+pub const CRON_FILE: &str = "cron.json";
+
+const MINUTE_MILLIS: u64 = 60 * 1000;
+const DAY_MILLIS: u64 = 24 * 60 * MINUTE_MILLIS;
+
+/// When a task runs, by the wall clock so that it keeps its times across restarts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskSchedule {
+    /// Every number of seconds
+    Every(u64),
+    /// Once a day at a minute of the day, in UTC
+    Daily(u32),
+}
+
+impl TaskSchedule {
+    /// Parse a time of day such as `04:00`, in UTC
+    pub fn daily(at: &str) -> Option<Self> {
+        let (hour, minute) = at.split_once(':')?;
+        let (hour, minute) = (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?);
+
+        if hour >= 24 || minute >= 60 {
+            return None;
+        }
+
+        Some(TaskSchedule::Daily(hour * 60 + minute))
+    }
+
+    /// The first run after a time, in milliseconds since the epoch
+    pub fn next(&self, after: u64) -> u64 {
+        match *self {
+            TaskSchedule::Every(seconds) => after + seconds.max(1) * 1000,
+            TaskSchedule::Daily(minute) => {
+                let run = after - after % DAY_MILLIS + minute as u64 * MINUTE_MILLIS;
+
+                if run > after {
+                    run
+                } else {
+                    run + DAY_MILLIS
+                }
+            }
+        }
+    }
+}
+
+/// What a task does when it runs
+#[derive(Clone)]
+pub enum TaskAction {
+    /// Commands run as the console on the tick thread, such as `save`
+    Commands(Vec<String>),
+    /// A message announced to everyone in the world
+    Announce(String),
+    /// Work run on a worker thread away from the world, such as uploading a backup
+    Job(Arc<dyn Fn() + Send + Sync>),
+}
+
+impl fmt::Debug for TaskAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskAction::Commands(commands) => f.debug_tuple("Commands").field(commands).finish(),
+            TaskAction::Announce(message) => f.debug_tuple("Announce").field(message).finish(),
+            TaskAction::Job(_) => f.write_str("Job"),
+        }
+    }
+}
+
+/// A task of a world as written in `worlds.json`, run `every` number of seconds or daily `at` a
+/// time such as `04:00` in UTC
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskConfig {
+    pub name: String,
+    #[serde(default)]
+    pub every: Option<u64>,
+    #[serde(default)]
+    pub at: Option<String>,
+    /// Commands ran as the console, their leading `/` being optional
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// Message announced before the commands run
+    #[serde(default)]
+    pub announce: Option<String>,
+}
+
+impl TaskConfig {
+    /// The schedule and actions of the task, none if it has no valid schedule
+    fn parse(&self) -> Option<(TaskSchedule, Vec<TaskAction>)> {
+        let schedule = match (self.every, &self.at) {
+            (Some(seconds), None) if seconds > 0 => TaskSchedule::Every(seconds),
+            (None, Some(at)) => TaskSchedule::daily(at)?,
+            _ => return None,
+        };
+
+        let mut actions = vec![];
+
+        if let Some(message) = &self.announce {
+            actions.push(TaskAction::Announce(message.to_owned()));
+        }
+
+        if !self.commands.is_empty() {
+            let commands = self
+                .commands
+                .iter()
+                .map(|command| command.strip_prefix('/').unwrap_or(command).to_owned())
+                .collect();
+            actions.push(TaskAction::Commands(commands));
+        }
+
+        Some((schedule, actions))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Task {
+    schedule: TaskSchedule,
+    actions: Vec<TaskAction>,
+    /// Milliseconds since the epoch
+    next_run: u64,
+}
+
+/// Resource of the repeating tasks of a world, by name
+///
+/// Next run times are saved, so a restart neither skips a nightly task nor runs it twice. Runs
+/// missed while the server was down happen once as soon as it's back up.
+#[derive(Debug, Default)]
+pub struct Cron {
+    tasks: BTreeMap<String, Task>,
+    /// Run times loaded for tasks, kept until they're registered
+    saved: HashMap<String, u64>,
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl Cron {
+    /// Register the tasks of the config, picking up the run times saved in `storage` if any
+    pub fn new(configs: &[TaskConfig], storage: Option<Arc<dyn Storage>>, now: u64) -> Self {
+        let saved = storage
+            .as_ref()
+            .and_then(|storage| storage.get_metadata(CRON_FILE))
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        let mut cron = Self {
+            tasks: BTreeMap::new(),
+            saved,
+            storage,
+        };
+
+        for config in configs {
+            match config.parse() {
+                Some((schedule, actions)) => cron.register(&config.name, schedule, actions, now),
+                None => warn!("Task \"{}\" needs either every or at.", config.name),
+            }
+        }
+
+        cron
+    }
+
+    /// Add a task, replacing the one of the same name
+    ///
+    /// A task registered again, such as by a plugin on every start, keeps its saved run time.
+    pub fn register(
+        &mut self,
+        name: &str,
+        schedule: TaskSchedule,
+        actions: Vec<TaskAction>,
+        now: u64,
+    ) {
+        let next_run = self
+            .saved
+            .remove(name)
+            .or_else(|| self.tasks.get(name).map(|task| task.next_run))
+            .unwrap_or_else(|| schedule.next(now));
+
+        self.tasks.insert(
+            name.to_owned(),
+            Task {
+                schedule,
+                actions,
+                next_run,
+            },
+        );
+    }
+
+    /// Remove a task, false if there's no such task
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.tasks.remove(name).is_some()
+    }
+
+    /// Names of the tasks along with their next run times, in milliseconds since the epoch
+    pub fn next_runs(&self) -> Vec<(&str, u64)> {
+        self.tasks
+            .iter()
+            .map(|(name, task)| (name.as_str(), task.next_run))
+            .collect()
+    }
+
+    /// Take the actions of the tasks due by a time, scheduling their next runs
+    pub fn take_due(&mut self, now: u64) -> Vec<(String, Vec<TaskAction>)> {
+        self.tasks
+            .iter_mut()
+            .filter(|(_, task)| task.next_run <= now)
+            .map(|(name, task)| {
+                task.next_run = task.schedule.next(now);
+                (name.to_owned(), task.actions.to_owned())
+            })
+            .collect()
+    }
+
+    /// Save the next run times, if the world is saved
+    pub fn save(&self) {
+        if let Some(storage) = &self.storage {
+            let next_runs = self
+                .tasks
+                .iter()
+                .map(|(name, task)| (name.to_owned(), task.next_run))
+                .collect::<BTreeMap<_, _>>();
+
+            let data = serde_json::to_vec(&next_runs).unwrap();
+            storage.put_metadata(CRON_FILE, &data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules() {
+        let four = TaskSchedule::daily("04:00").unwrap();
+        assert!(TaskSchedule::daily("24:00").is_none());
+
+        // 03:00 runs the same day, 05:00 the next
+        assert_eq!(four.next(3 * 60 * MINUTE_MILLIS), 4 * 60 * MINUTE_MILLIS);
+        assert_eq!(
+            four.next(5 * 60 * MINUTE_MILLIS),
+            DAY_MILLIS + 4 * 60 * MINUTE_MILLIS
+        );
+
+        let mut cron = Cron::default();
+        cron.register(
+            "announce",
+            TaskSchedule::Every(600),
+            vec![TaskAction::Announce("Vote!".into())],
+            0,
+        );
+
+        assert!(cron.take_due(599_999).is_empty());
+        assert_eq!(cron.take_due(600_000)[0].0, "announce");
+        assert_eq!(cron.next_runs(), vec![("announce", 1_200_000)]);
+
+        // registering again keeps the run time
+        cron.register("announce", TaskSchedule::Every(600), vec![], 5_000_000);
+        assert_eq!(cron.next_runs(), vec![("announce", 1_200_000)]);
+        assert!(cron.unregister("announce"));
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use serde_json::Value;
+
+/// Folder that data packs live in, each laid out like `assets/metadata`
+pub const DATA_PACKS_FOLDER: &str = "assets/datapacks";
+
+/// Folder of the data that packs are layered over
+pub const METADATA_FOLDER: &str = "assets/metadata";
+
+/// Keys of the entries set by packs, along with the pack that set them last
+type Origins = HashMap<String, String>;
+
+/// The data packs of the server, layered over `assets/metadata` in order
+///
+/// A pack holds any of `blocks.json`, `tags.json`, `recipes.json`, `loot.json`, `trades.json`
+/// and `structures.json`, along with the block, texture and structure files they point to. A
+/// later pack takes precedence, so that it only has to hold what it changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DataPacks {
+    names: Vec<String>,
+}
+
+impl DataPacks {
+    /// Panics with every pack that isn't in `assets/datapacks`, or that is listed twice
+    pub fn new(names: Vec<String>) -> Self {
+        let mut problems = vec![];
+
+        for (i, name) in names.iter().enumerate() {
+            if !Path::new(DATA_PACKS_FOLDER).join(name).is_dir() {
+                problems.push(format!("{} isn't in {}", name, DATA_PACKS_FOLDER));
+            } else if names[..i].contains(name) {
+                problems.push(format!("{} is listed twice", name));
+            }
+        }
+
+        if !problems.is_empty() {
+            panic!("Invalid data packs: {}.", problems.join(", "));
+        }
+
+        Self { names }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Path of a file in the last pack holding it, if any does
+    pub fn find(&self, file: &str) -> Option<PathBuf> {
+        self.names
+            .iter()
+            .rev()
+            .map(|name| Path::new(DATA_PACKS_FOLDER).join(name).join(file))
+            .find(|path| path.exists())
+    }
+
+    /// Path of a file in the last pack holding it, falling back to `assets/metadata`
+    pub fn path(&self, file: &str) -> PathBuf {
+        self.find(file)
+            .unwrap_or_else(|| Path::new(METADATA_FOLDER).join(file))
+    }
+
+    /// Read a JSON file with the ones of every pack layered over it, none if nothing holds it
+    ///
+    /// Packs setting the same entry differently are warned of, the later one winning.
+    pub fn read(&self, file: &str) -> Option<Value> {
+        let base = (None, Path::new(METADATA_FOLDER).join(file));
+        let layers = self.names.iter().map(|name| {
+            (
+                Some(name.as_str()),
+                Path::new(DATA_PACKS_FOLDER).join(name).join(file),
+            )
+        });
+
+        let mut merged: Option<Value> = None;
+        let mut origins = Origins::new();
+
+        for (pack, path) in std::iter::once(base).chain(layers) {
+            let value: Value = match File::open(&path) {
+                Ok(reader) => serde_json::from_reader(reader)
+                    .unwrap_or_else(|e| panic!("Unable to parse {}: {}", path.display(), e)),
+                Err(_) => continue,
+            };
+
+            let empty = match &value {
+                Value::Array(_) => Value::Array(vec![]),
+                _ => Value::Object(Default::default()),
+            };
+            let merged = merged.get_or_insert(empty);
+
+            match pack {
+                None => *merged = value,
+                Some(pack) => {
+                    for conflict in layer(merged, value, pack, &mut origins) {
+                        warn!("Data pack conflict in {}: {}", file, conflict);
+                    }
+                }
+            }
+        }
+
+        merged
+    }
+}
+
+/// Remember who set an entry, noting when another pack set it differently before
+fn note(
+    key: &str,
+    old: Option<&Value>,
+    new: &Value,
+    pack: &str,
+    origins: &mut Origins,
+    conflicts: &mut Vec<String>,
+) {
+    if let Some(earlier) = origins.get(key) {
+        if earlier != pack && old != Some(new) {
+            conflicts.push(format!(
+                "{} is set by both {} and {}, {} wins.",
+                key, earlier, pack, pack
+            ));
+        }
+    }
+
+    origins.insert(key.to_owned(), pack.to_owned());
+}
+
+/// Layer the file of a pack over the files merged before it
+///
+/// Entries of the same key replace each other, such as a block's loot table or a smelting fuel,
+/// one level into sections like `fuels` or `blocks`. Lists, such as the smelting recipes or the
+/// trades of an entity type, are extended instead. Returns the entries an earlier pack set
+/// differently.
+fn layer(merged: &mut Value, over: Value, pack: &str, origins: &mut Origins) -> Vec<String> {
+    let mut conflicts = vec![];
+
+    let (merged, over) = match (merged, over) {
+        (Value::Object(merged), Value::Object(over)) => (merged, over),
+        (Value::Array(list), Value::Array(items)) => {
+            list.extend(items);
+            return conflicts;
+        }
+        (merged, over) => {
+            *merged = over;
+            return conflicts;
+        }
+    };
+
+    for (key, value) in over {
+        match merged.get_mut(&key) {
+            Some(Value::Object(section)) if value.is_object() => {
+                if let Value::Object(entries) = value {
+                    for (entry, value) in entries {
+                        let path = format!("{}.{}", key, entry);
+                        note(
+                            &path,
+                            section.get(&entry),
+                            &value,
+                            pack,
+                            origins,
+                            &mut conflicts,
+                        );
+                        section.insert(entry, value);
+                    }
+                }
+            }
+            Some(Value::Array(list)) if value.is_array() => {
+                if let Value::Array(items) = value {
+                    list.extend(items);
+                }
+            }
+            _ => {
+                note(
+                    &key,
+                    merged.get(&key),
+                    &value,
+                    pack,
+                    origins,
+                    &mut conflicts,
+                );
+                merged.insert(key, value);
+            }
+        }
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn layering() {
+        let mut recipes = json!({
+            "smelting": [{ "input": "Sand", "output": "Glass" }],
+            "fuels": { "Coal": 1600, "#logs": 300 },
+        });
+        let mut origins = Origins::new();
+
+        let conflicts = layer(
+            &mut recipes,
+            json!({
+                "smelting": [{ "input": "Clay", "output": "Brick" }],
+                "fuels": { "Coal": 2000 },
+            }),
+            "faster",
+            &mut origins,
+        );
+        assert!(conflicts.is_empty());
+
+        // the same entry set again by another pack is reported, setting it the same isn't
+        let conflicts = layer(
+            &mut recipes,
+            json!({ "fuels": { "Coal": 800, "#logs": 300 } }),
+            "realistic",
+            &mut origins,
+        );
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].starts_with("fuels.Coal"));
+
+        assert_eq!(recipes["smelting"].as_array().unwrap().len(), 2);
+        assert_eq!(recipes["fuels"], json!({ "Coal": 800, "#logs": 300 }));
+
+        let mut structures = json!(["well.json"]);
+        layer(
+            &mut structures,
+            json!(["tower.json"]),
+            "faster",
+            &mut origins,
+        );
+        assert_eq!(structures, json!(["well.json", "tower.json"]));
+    }
+}
@@ -0,0 +1,159 @@
+use serde::Deserialize;
+
+use server_common::{
+    aabb::Aabb,
+    vec::{Vec2, Vec3},
+};
+
+use crate::gen::lights::LightColor;
+
+use super::chunks::Chunks;
+
+/// Ticks between the overlays sent to each client asking for them
+pub const DEBUG_INTERVAL: i32 = 10;
+
+/// Most voxels around a player that light levels are sent for, the cube being twice as wide
+pub const MAX_DEBUG_LIGHT_RADIUS: i32 = 8;
+
+/// Chunks around the player's that borders are sent for
+pub const DEBUG_CHUNK_RADIUS: i32 = 2;
+
+/// Distance within which the hit boxes and paths of entities are sent
+pub const DEBUG_ENTITY_RADIUS: f32 = 32.0;
+
+/// Overlays a client asked for in a `DEBUG` message
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DebugOptions {
+    pub chunks: bool,
+    /// Voxels around the player to send the light levels of, 0 for none
+    pub light: i32,
+    pub boxes: bool,
+    pub paths: bool,
+}
+
+impl DebugOptions {
+    /// Parse the options of a `DEBUG` message, none if it asks for nothing, turning overlays off
+    pub fn parse(json: &str) -> Option<Self> {
+        let mut options: Self = serde_json::from_str(json).ok()?;
+        options.light = options.light.clamp(0, MAX_DEBUG_LIGHT_RADIUS);
+
+        if options == Self::default() {
+            return None;
+        }
+
+        Some(options)
+    }
+}
+
+/// A chunk around the player, along with the work it's waiting on
+#[derive(Debug, Clone)]
+pub struct ChunkBorder {
+    pub coords: Vec2<i32>,
+    pub needs_propagation: bool,
+    pub is_dirty: bool,
+}
+
+/// Light levels of a voxel, torch light by color
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LightSample {
+    pub voxel: Vec3<i32>,
+    pub sunlight: u32,
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+}
+
+/// Hit box of a player or entity, by protocol id
+#[derive(Debug, Clone)]
+pub struct Hitbox {
+    pub id: String,
+    pub aabb: Aabb,
+}
+
+/// Nodes left of the path an entity walks along, by protocol id
+#[derive(Debug, Clone)]
+pub struct DebugPath {
+    pub id: String,
+    pub nodes: Vec<Vec3<i32>>,
+}
+
+/// Everything drawn for a client debugging the world around it
+#[derive(Debug, Clone, Default)]
+pub struct DebugOverlay {
+    pub chunks: Vec<ChunkBorder>,
+    pub lights: Vec<LightSample>,
+    pub boxes: Vec<Hitbox>,
+    pub paths: Vec<DebugPath>,
+}
+
+/// The loaded chunks within `DEBUG_CHUNK_RADIUS` of a chunk
+pub fn chunk_borders(chunks: &Chunks, center: &Vec2<i32>) -> Vec<ChunkBorder> {
+    let mut borders = vec![];
+
+    for x in -DEBUG_CHUNK_RADIUS..=DEBUG_CHUNK_RADIUS {
+        for z in -DEBUG_CHUNK_RADIUS..=DEBUG_CHUNK_RADIUS {
+            let coords = Vec2(center.0 + x, center.1 + z);
+
+            if let Some(chunk) = chunks.get_chunk(&coords) {
+                borders.push(ChunkBorder {
+                    coords,
+                    needs_propagation: chunk.needs_propagation,
+                    is_dirty: chunk.is_dirty,
+                });
+            }
+        }
+    }
+
+    borders
+}
+
+/// Light levels of the voxels within a radius, leaving out the opaque blocks that hold none
+pub fn light_samples(chunks: &Chunks, center: &Vec3<i32>, radius: i32) -> Vec<LightSample> {
+    let &Vec3(cx, cy, cz) = center;
+    let mut samples = vec![];
+
+    for vx in cx - radius..=cx + radius {
+        for vz in cz - radius..=cz + radius {
+            for vy in cy - radius..=cy + radius {
+                if !chunks.config.contains_height(vy)
+                    || chunks.get_chunk_by_voxel(vx, vy, vz).is_none()
+                {
+                    continue;
+                }
+
+                let block = chunks
+                    .registry
+                    .get_block_by_id(chunks.get_voxel_by_voxel(vx, vy, vz));
+                if block.is_block && !block.is_transparent {
+                    continue;
+                }
+
+                samples.push(LightSample {
+                    voxel: Vec3(vx, vy, vz),
+                    sunlight: chunks.get_sunlight(vx, vy, vz),
+                    red: chunks.get_torch_light(vx, vy, vz, &LightColor::Red),
+                    green: chunks.get_torch_light(vx, vy, vz, &LightColor::Green),
+                    blue: chunks.get_torch_light(vx, vy, vz, &LightColor::Blue),
+                });
+            }
+        }
+    }
+
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options() {
+        assert_eq!(DebugOptions::parse("{}"), None);
+        assert_eq!(DebugOptions::parse("not json"), None);
+
+        let options = DebugOptions::parse(r#"{"light": 100, "paths": true}"#).unwrap();
+        assert_eq!(options.light, MAX_DEBUG_LIGHT_RADIUS);
+        assert!(options.paths && !options.boxes);
+    }
+}
@@ -2,19 +2,25 @@ use std::{collections::HashMap, fs::File};
 
 use serde::{Deserialize, Serialize};
 use server_common::{aabb::Aabb, quaternion::Quaternion, vec::Vec3};
-use specs::{Builder, Entity as ECSEntity, World, WorldExt};
+use specs::{world::EntitiesRes, Builder, Entity as ECSEntity, EntityBuilder, World, WorldExt};
 
 use crate::comp::{
     brain::{Brain, BrainOptions},
     curr_chunk::CurrChunk,
     etype::EType,
+    history::History,
+    item_drop::ItemDrop,
     rigidbody::RigidBody,
     rotation::Rotation,
     target::{Target, TargetInner},
+    uuid::Uuid,
+    vehicle::{Vehicle, VehicleKind},
     view_radius::ViewRadius,
     walk_towards::WalkTowards,
 };
 
+use super::items::ItemStack;
+
 /// JSON format to store a rigid body configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -47,14 +53,21 @@ pub struct EntityPrototype {
     pub brain: String,
     pub brain_options: BrainOptions,
     pub rigidbody: RigidBodyProto,
+    /// Vehicles are moved by their own integrator instead of a brain
+    #[serde(default)]
+    pub vehicle: Option<VehicleKind>,
 }
 
 /// Entity type map
 pub type EntityPrototypes = HashMap<String, EntityPrototype>;
 
 /// Entities resource stored as a map
+///
+/// Also the registry of every live entity by UUID, which is how they're known in the protocol,
+/// saved chunks and plugins.
 pub struct Entities {
     prototypes: EntityPrototypes,
+    uuids: HashMap<String, ECSEntity>,
 }
 
 impl Default for Entities {
@@ -79,7 +92,57 @@ impl Entities {
             prototypes.insert(entity.etype.to_owned(), entity);
         }
 
-        Self { prototypes }
+        Self {
+            prototypes,
+            uuids: HashMap::new(),
+        }
+    }
+
+    /// Get the entity with a UUID, none if there's no such entity
+    ///
+    /// Entities deleted since the last tick may still be returned, check `is_alive`.
+    pub fn get(&self, uuid: &str) -> Option<ECSEntity> {
+        self.uuids.get(uuid).copied()
+    }
+
+    /// Register an entity by the UUID it has, giving it a new one if it has none
+    pub fn register(ecs: &mut World, entity: ECSEntity) -> ECSEntity {
+        let uuid = {
+            let mut uuids = ecs.write_storage::<Uuid>();
+
+            match uuids.get(entity) {
+                Some(uuid) => uuid.0.to_owned(),
+                None => {
+                    let uuid = Uuid::new();
+                    uuids
+                        .insert(entity, uuid.clone())
+                        .expect("Unable to give an entity a UUID.");
+                    uuid.0
+                }
+            }
+        };
+
+        ecs.write_resource::<Entities>().uuids.insert(uuid, entity);
+        entity
+    }
+
+    /// Change the UUID of an entity, such as to restore the one it was saved with
+    pub fn set_uuid(ecs: &mut World, entity: ECSEntity, uuid: &str) {
+        let old = ecs
+            .write_storage::<Uuid>()
+            .insert(entity, Uuid(uuid.to_owned()))
+            .expect("Unable to give an entity a UUID.");
+
+        let mut entities = ecs.write_resource::<Entities>();
+        if let Some(Uuid(old)) = old {
+            entities.uuids.remove(&old);
+        }
+        entities.uuids.insert(uuid.to_owned(), entity);
+    }
+
+    /// Forget the UUIDs of deleted entities
+    pub fn prune(&mut self, entities: &EntitiesRes) {
+        self.uuids.retain(|_, entity| entities.is_alive(*entity));
     }
 
     /// Clone all prototypes
@@ -100,6 +163,59 @@ impl Entities {
         position: &Vec3<f32>,
         rotation: &Quaternion,
     ) -> ECSEntity {
+        let observe = &prototype.observe;
+        let view_distance = &prototype.view_distance;
+        let brain_options = prototype.brain_options.to_owned();
+
+        let builder = Entities::create_body(ecs, prototype, etype, position, rotation);
+
+        let entity = if let Some(kind) = prototype.vehicle {
+            builder.with(Vehicle(kind)).build()
+        } else {
+            builder
+                .with(Target(if observe == "all" {
+                    TargetInner::ALL(None)
+                } else if observe == "player" {
+                    TargetInner::PLAYER(None)
+                } else {
+                    TargetInner::ENTITY(None)
+                }))
+                .with(ViewRadius::new(*view_distance))
+                .with(Brain::new(brain_options))
+                .with(WalkTowards(None, 100))
+                .build()
+        };
+
+        Entities::register(ecs, entity)
+    }
+
+    /// Spawn in a stack of items lying in the world, moved by physics alone
+    pub fn spawn_item(
+        ecs: &mut World,
+        prototype: &EntityPrototype,
+        stack: ItemStack,
+        position: &Vec3<f32>,
+    ) -> ECSEntity {
+        let entity = Entities::create_body(
+            ecs,
+            prototype,
+            &prototype.etype,
+            position,
+            &Quaternion(0.0, 0.0, 0.0, 0.0),
+        )
+        .with(ItemDrop(stack))
+        .build();
+
+        Entities::register(ecs, entity)
+    }
+
+    fn create_body<'a>(
+        ecs: &'a mut World,
+        prototype: &EntityPrototype,
+        etype: &str,
+        position: &Vec3<f32>,
+        rotation: &Quaternion,
+    ) -> EntityBuilder<'a> {
         let RigidBodyProto {
             aabb,
             head,
@@ -110,10 +226,6 @@ impl Entities {
             auto_step,
         } = &prototype.rigidbody;
 
-        let observe = &prototype.observe;
-        let view_distance = &prototype.view_distance;
-        let brain_options = prototype.brain_options.to_owned();
-
         ecs.create_entity()
             .with(EType::new(etype))
             .with(RigidBody::new(
@@ -127,16 +239,6 @@ impl Entities {
             ))
             .with(Rotation::from_quaternion(&rotation))
             .with(CurrChunk::new())
-            .with(Target(if observe == "all" {
-                TargetInner::ALL(None)
-            } else if observe == "player" {
-                TargetInner::PLAYER(None)
-            } else {
-                TargetInner::ENTITY(None)
-            }))
-            .with(ViewRadius::new(*view_distance))
-            .with(Brain::new(brain_options))
-            .with(WalkTowards(None, 100))
-            .build()
+            .with(History::new())
     }
 }
@@ -0,0 +1,46 @@
+use server_common::vec::Vec3;
+
+use super::forms::FormResponse;
+
+/// Gameplay events emitted by the world, consumed by systems such as spawning rules
+/// and advancements
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldEvent {
+    /// Light levels at a voxel changed, usually after a block update
+    LightChanged {
+        voxel: Vec3<i32>,
+        sunlight: u32,
+        torch_light: u32,
+    },
+    /// A player broke a block, `id` being what the block was
+    BlockBroken {
+        player_id: usize,
+        voxel: Vec3<i32>,
+        id: u32,
+    },
+    /// A player took items out of a crafting or smelting output
+    ItemCrafted {
+        player_id: usize,
+        id: u32,
+        count: u32,
+    },
+    /// A player joined the world
+    PlayerJoined { player_id: usize },
+    /// A player's health ran out
+    PlayerDied { player_id: usize },
+    /// A player answered a form, checked against it
+    FormSubmitted {
+        player_id: usize,
+        name: String,
+        response: FormResponse,
+    },
+    /// Emitted by plugins, such as to progress custom advancement triggers
+    Custom {
+        player_id: usize,
+        name: String,
+        amount: u32,
+    },
+}
+
+/// Resource of events emitted since the last tick, cleared after systems are dispatched
+pub type WorldEvents = Vec<WorldEvent>;
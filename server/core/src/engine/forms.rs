@@ -0,0 +1,223 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use log::warn;
+
+use serde::{Deserialize, Serialize};
+
+/// Forms commands can show are loaded from this file within the world folder
+pub const FORMS_FILE: &str = "forms.json";
+
+/// Most characters accepted in a text input
+pub const MAX_FORM_INPUT_LENGTH: usize = 256;
+
+/// Part of a form, shown in order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FormElement {
+    /// Answers the form when pressed, running its command as the player if any
+    Button {
+        label: String,
+        #[serde(default)]
+        command: Option<String>,
+    },
+    Input {
+        label: String,
+        #[serde(default)]
+        placeholder: String,
+        /// Text the input starts with
+        #[serde(default)]
+        value: String,
+    },
+    Dropdown {
+        label: String,
+        options: Vec<String>,
+        /// Index of the option chosen at first
+        #[serde(default)]
+        selected: usize,
+    },
+}
+
+/// A menu shown to a player, such as a kit selection or a list of warps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Form {
+    /// Name the form's answers are reported under
+    #[serde(default)]
+    pub name: String,
+    pub title: String,
+    pub elements: Vec<FormElement>,
+}
+
+/// What a player filled in an input or chose in a dropdown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormValue {
+    Text(String),
+    /// Index of the option chosen
+    Choice(usize),
+}
+
+/// A player's answer to a form, checked against it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormResponse {
+    /// Index of the element of the button pressed
+    pub button: usize,
+    /// Values of the inputs and dropdowns, in order
+    pub values: Vec<FormValue>,
+}
+
+impl Form {
+    pub fn new(name: &str, title: &str, elements: Vec<FormElement>) -> Self {
+        Self {
+            name: name.to_owned(),
+            title: title.to_owned(),
+            elements,
+        }
+    }
+
+    /// Check an answer from a client, none if it doesn't fit the form
+    ///
+    /// `button` is the index of the element pressed, and `values` holds the text of each input
+    /// and the index of the option of each dropdown, in order.
+    pub fn validate(&self, button: usize, values: &[String]) -> Option<FormResponse> {
+        if !matches!(self.elements.get(button), Some(FormElement::Button { .. })) {
+            return None;
+        }
+
+        let fields = self
+            .elements
+            .iter()
+            .filter(|element| !matches!(element, FormElement::Button { .. }))
+            .collect::<Vec<_>>();
+
+        if fields.len() != values.len() {
+            return None;
+        }
+
+        let values = fields
+            .iter()
+            .zip(values.iter())
+            .map(|(field, value)| match field {
+                FormElement::Input { .. } if value.chars().count() <= MAX_FORM_INPUT_LENGTH => {
+                    Some(FormValue::Text(value.to_owned()))
+                }
+                FormElement::Dropdown { options, .. } => value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&index| index < options.len())
+                    .map(FormValue::Choice),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(FormResponse { button, values })
+    }
+
+    /// Command run by pressing a button, if any
+    pub fn command(&self, button: usize) -> Option<&str> {
+        match self.elements.get(button) {
+            Some(FormElement::Button {
+                command: Some(command),
+                ..
+            }) => Some(command.strip_prefix('/').unwrap_or(command)),
+            _ => None,
+        }
+    }
+}
+
+/// Resource of the forms of a world by name, which `/form <name>` shows
+#[derive(Debug, Default)]
+pub struct Forms {
+    forms: HashMap<String, Form>,
+}
+
+impl Forms {
+    /// Load the forms saved in `folder`, if any
+    pub fn new(folder: Option<PathBuf>) -> Self {
+        let file = match folder {
+            Some(mut folder) => {
+                folder.push(FORMS_FILE);
+                folder
+            }
+            None => return Self::default(),
+        };
+
+        let bytes = match fs::read(&file) {
+            Ok(bytes) => bytes,
+            Err(_) => return Self::default(),
+        };
+
+        let forms = match serde_json::from_slice::<HashMap<String, Form>>(&bytes) {
+            Ok(forms) => forms,
+            Err(e) => {
+                warn!("Unable to parse the forms {:?}: {}", file, e);
+                return Self::default();
+            }
+        };
+
+        Self {
+            forms: forms
+                .into_iter()
+                .map(|(name, mut form)| {
+                    form.name = name.to_owned();
+                    (name, form)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Form> {
+        self.forms.get(name)
+    }
+
+    /// Add a form, replacing the one of the same name
+    pub fn set(&mut self, form: Form) {
+        self.forms.insert(form.name.to_owned(), form);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validation() {
+        let form = Form::new(
+            "warps",
+            "Warps",
+            vec![
+                FormElement::Input {
+                    label: "Note".into(),
+                    placeholder: String::new(),
+                    value: String::new(),
+                },
+                FormElement::Dropdown {
+                    label: "Warp".into(),
+                    options: vec!["Spawn".into(), "Shop".into()],
+                    selected: 0,
+                },
+                FormElement::Button {
+                    label: "Go".into(),
+                    command: Some("/function warp".into()),
+                },
+            ],
+        );
+
+        let values = |note: &str, warp: &str| vec![note.to_owned(), warp.to_owned()];
+
+        assert_eq!(
+            form.validate(2, &values("hi", "1")),
+            Some(FormResponse {
+                button: 2,
+                values: vec![FormValue::Text("hi".into()), FormValue::Choice(1)],
+            })
+        );
+        assert_eq!(form.command(2), Some("function warp"));
+
+        // not a button, option out of range, too long or missing values
+        assert!(form.validate(0, &values("hi", "1")).is_none());
+        assert!(form.validate(2, &values("hi", "2")).is_none());
+        assert!(form
+            .validate(2, &values(&"x".repeat(MAX_FORM_INPUT_LENGTH + 1), "0"))
+            .is_none());
+        assert!(form.validate(2, &["hi".to_owned()]).is_none());
+    }
+}
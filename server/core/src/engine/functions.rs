@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Functions are loaded from this folder within the world folder
+pub const FUNCTIONS_FOLDER: &str = "functions";
+
+/// Extension of the files in the functions folder, one command per line
+pub const FUNCTION_EXTENSION: &str = "mcfunction";
+
+/// Run for a player when they join
+pub const ON_JOIN: &str = "on_join";
+
+/// Run for a player when they die
+pub const ON_DEATH: &str = "on_death";
+
+/// How deep functions can run each other, stopping runaway recursion
+pub const MAX_FUNCTION_DEPTH: usize = 16;
+
+/// Furthest ahead a function can be scheduled, in ticks
+pub const MAX_SCHEDULE_DELAY: i32 = 1_000_000;
+
+/// A run of a function due at a tick, for the player who scheduled it
+#[derive(Debug, Clone)]
+struct Scheduled {
+    due: i32,
+    name: String,
+    player_id: usize,
+}
+
+/// Resource of the command scripts of a world, by name
+///
+/// A function named `arena/reset` is the file `functions/arena/reset.mcfunction` within the world
+/// folder. Its lines are commands, blank lines and those starting with `#` skipped.
+#[derive(Debug, Default)]
+pub struct Functions {
+    functions: HashMap<String, Vec<String>>,
+    scheduled: Vec<Scheduled>,
+    depth: usize,
+    folder: Option<PathBuf>,
+}
+
+impl Functions {
+    /// Load the functions within `folder`, if any
+    pub fn new(folder: Option<PathBuf>) -> Self {
+        let folder = folder.map(|mut folder| {
+            folder.push(FUNCTIONS_FOLDER);
+            folder
+        });
+
+        let mut functions = Self {
+            folder,
+            ..Default::default()
+        };
+        functions.reload();
+        functions
+    }
+
+    /// Load the functions again from their folder, returns how many there are
+    pub fn reload(&mut self) -> usize {
+        self.functions.clear();
+
+        if let Some(folder) = self.folder.to_owned() {
+            self.load_folder(&folder, "");
+        }
+
+        self.functions.len()
+    }
+
+    fn load_folder(&mut self, folder: &Path, prefix: &str) {
+        let entries = match fs::read_dir(folder) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let stem = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => format!("{}{}", prefix, stem),
+                None => continue,
+            };
+
+            if path.is_dir() {
+                self.load_folder(&path, &format!("{}/", stem));
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some(FUNCTION_EXTENSION) {
+                if let Ok(source) = fs::read_to_string(&path) {
+                    self.functions.insert(stem, Functions::parse(&source));
+                }
+            }
+        }
+    }
+
+    /// Parse a function's source into its commands, the leading `/` being optional
+    pub fn parse(source: &str) -> Vec<String> {
+        source
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.strip_prefix('/').unwrap_or(line).to_owned())
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Vec<String>> {
+        self.functions.get(name)
+    }
+
+    /// Add a function, replacing the one of the same name
+    pub fn set(&mut self, name: &str, commands: Vec<String>) {
+        self.functions.insert(name.to_owned(), commands);
+    }
+
+    /// Run a function at a future tick, returns false if there's no such function
+    pub fn schedule(&mut self, name: &str, player_id: usize, due: i32) -> bool {
+        if !self.functions.contains_key(name) {
+            return false;
+        }
+
+        self.scheduled.push(Scheduled {
+            due,
+            name: name.to_owned(),
+            player_id,
+        });
+
+        true
+    }
+
+    /// Take the functions due by a tick, with the players who scheduled them
+    pub fn take_due(&mut self, tick: i32) -> Vec<(String, usize)> {
+        let (due, pending) = self
+            .scheduled
+            .drain(..)
+            .partition::<Vec<_>, _>(|scheduled| scheduled.due <= tick);
+        self.scheduled = pending;
+
+        due.into_iter()
+            .map(
+                |Scheduled {
+                     name, player_id, ..
+                 }| (name, player_id),
+            )
+            .collect()
+    }
+
+    /// Start running a function, false if functions are already nested too deep
+    pub fn enter(&mut self) -> bool {
+        if self.depth >= MAX_FUNCTION_DEPTH {
+            return false;
+        }
+
+        self.depth += 1;
+        true
+    }
+
+    /// Finish running a function
+    pub fn exit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts() {
+        let commands = Functions::parse("# greet\n/effect speed 10\n\n  tp ~ ~10 ~  \n");
+        assert_eq!(commands, vec!["effect speed 10", "tp ~ ~10 ~"]);
+
+        let mut functions = Functions::default();
+        functions.set("greet", commands);
+
+        assert!(!functions.schedule("missing", 1, 10));
+        assert!(functions.schedule("greet", 1, 10));
+        assert!(functions.take_due(9).is_empty());
+        assert_eq!(functions.take_due(10), vec![("greet".to_owned(), 1)]);
+        assert!(functions.take_due(11).is_empty());
+
+        for _ in 0..MAX_FUNCTION_DEPTH {
+            assert!(functions.enter());
+        }
+        assert!(!functions.enter());
+        functions.exit();
+        assert!(functions.enter());
+    }
+}
@@ -0,0 +1,153 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use server_common::vec::Vec3;
+
+use crate::comp::uuid::Uuid;
+
+use super::storage::Storage;
+
+/// Holograms are saved under this metadata key of the world's storage
+pub const HOLOGRAMS_FILE: &str = "holograms.json";
+
+/// Most lines a hologram shows, extra ones being dropped
+pub const MAX_HOLOGRAM_LINES: usize = 8;
+
+/// Most characters per line of a hologram
+pub const MAX_HOLOGRAM_LINE_LENGTH: usize = 64;
+
+/// How far in voxels players can edit holograms from with commands
+pub const HOLOGRAM_REACH: f32 = 5.0;
+
+/// Lines of text floating at a position, such as a sign in a lobby
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hologram {
+    pub id: String,
+    /// World position of the top line
+    pub position: Vec3<f32>,
+    pub lines: Vec<String>,
+}
+
+/// Resource of the holograms of a world, by id
+///
+/// Holograms have no physics or brains, so they're kept apart from the ECS and only sent to
+/// clients as they change.
+#[derive(Debug, Default)]
+pub struct Holograms {
+    holograms: BTreeMap<String, Hologram>,
+    storage: Option<Arc<dyn Storage>>,
+}
+
+/// Keep up to `MAX_HOLOGRAM_LINES` lines, each cut to `MAX_HOLOGRAM_LINE_LENGTH` characters
+fn clamp_lines(lines: Vec<String>) -> Vec<String> {
+    lines
+        .into_iter()
+        .take(MAX_HOLOGRAM_LINES)
+        .map(|line| line.chars().take(MAX_HOLOGRAM_LINE_LENGTH).collect())
+        .collect()
+}
+
+impl Holograms {
+    /// Load the holograms saved in `storage`, if any
+    pub fn new(storage: Option<Arc<dyn Storage>>) -> Self {
+        let holograms = storage
+            .as_ref()
+            .and_then(|storage| storage.get_metadata(HOLOGRAMS_FILE))
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        Self { holograms, storage }
+    }
+
+    /// Place a hologram, returning it
+    pub fn spawn(&mut self, position: Vec3<f32>, lines: Vec<String>) -> &Hologram {
+        let id = Uuid::new().0;
+
+        self.holograms.insert(
+            id.to_owned(),
+            Hologram {
+                id: id.to_owned(),
+                position,
+                lines: clamp_lines(lines),
+            },
+        );
+
+        &self.holograms[&id]
+    }
+
+    /// Change the text of a hologram, none if there's no such hologram
+    pub fn set_lines(&mut self, id: &str, lines: Vec<String>) -> Option<&Hologram> {
+        let hologram = self.holograms.get_mut(id)?;
+        hologram.lines = clamp_lines(lines);
+        Some(hologram)
+    }
+
+    /// Move a hologram, none if there's no such hologram
+    pub fn set_position(&mut self, id: &str, position: Vec3<f32>) -> Option<&Hologram> {
+        let hologram = self.holograms.get_mut(id)?;
+        hologram.position = position;
+        Some(hologram)
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Hologram> {
+        self.holograms.remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Hologram> {
+        self.holograms.get(id)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Hologram> {
+        self.holograms.values()
+    }
+
+    /// The hologram nearest to a position within a distance, if any
+    pub fn nearest(&self, position: &Vec3<f32>, max_distance: f32) -> Option<&Hologram> {
+        let distance = |hologram: &Hologram| {
+            let Vec3(dx, dy, dz) = hologram.position.sub(position);
+            (dx * dx + dy * dy + dz * dz).sqrt()
+        };
+
+        self.holograms
+            .values()
+            .filter(|hologram| distance(hologram) <= max_distance)
+            .min_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap())
+    }
+
+    /// Save the holograms, if the world is saved
+    pub fn save(&self) {
+        if let Some(storage) = &self.storage {
+            let data = serde_json::to_vec(&self.holograms).unwrap();
+            storage.put_metadata(HOLOGRAMS_FILE, &data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holograms() {
+        let mut holograms = Holograms::default();
+
+        let long = "x".repeat(MAX_HOLOGRAM_LINE_LENGTH + 1);
+        let id = holograms
+            .spawn(Vec3(0.0, 0.0, 0.0), vec!["Shop".into(), long])
+            .id
+            .to_owned();
+        assert_eq!(
+            holograms.get(&id).unwrap().lines[1].len(),
+            MAX_HOLOGRAM_LINE_LENGTH
+        );
+
+        holograms.spawn(Vec3(10.0, 0.0, 0.0), vec![]);
+        assert_eq!(holograms.nearest(&Vec3(2.0, 0.0, 0.0), 5.0).unwrap().id, id);
+        assert!(holograms.nearest(&Vec3(5.0, 0.0, 0.0), 1.0).is_none());
+
+        assert!(holograms.set_lines(&id, vec!["Closed".into()]).is_some());
+        assert!(holograms.remove(&id).is_some());
+        assert!(holograms.set_lines(&id, vec![]).is_none());
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Color of a boss bar, sent to clients by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarColor {
+    Pink,
+    Blue,
+    Red,
+    Green,
+    Yellow,
+    Purple,
+    White,
+}
+
+impl BarColor {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BarColor::Pink => "pink",
+            BarColor::Blue => "blue",
+            BarColor::Red => "red",
+            BarColor::Green => "green",
+            BarColor::Yellow => "yellow",
+            BarColor::Purple => "purple",
+            BarColor::White => "white",
+        }
+    }
+
+    /// Parse a color by name, ignoring case
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "pink" => Some(BarColor::Pink),
+            "blue" => Some(BarColor::Blue),
+            "red" => Some(BarColor::Red),
+            "green" => Some(BarColor::Green),
+            "yellow" => Some(BarColor::Yellow),
+            "purple" => Some(BarColor::Purple),
+            "white" => Some(BarColor::White),
+            _ => None,
+        }
+    }
+}
+
+/// A bar along the top of the screen, such as a boss' health or a round's time left
+#[derive(Debug, Clone, PartialEq)]
+pub struct BossBar {
+    pub id: String,
+    pub title: String,
+    /// How full the bar is, from 0 to 1
+    pub progress: f32,
+    pub color: BarColor,
+}
+
+impl BossBar {
+    pub fn new(id: &str, title: &str, progress: f32, color: BarColor) -> Self {
+        Self {
+            id: id.to_owned(),
+            title: title.to_owned(),
+            progress: progress.clamp(0.0, 1.0),
+            color,
+        }
+    }
+}
+
+/// Milliseconds titles take to fade in, stay and fade out by default
+pub const TITLE_TIMES: (u32, u32, u32) = (500, 3500, 1000);
+
+/// Large text across the middle of the screen with smaller text below, such as to start a round
+///
+/// Clients fade titles in and out on their own, so they aren't tracked like boss bars.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Title {
+    pub title: String,
+    pub subtitle: String,
+    /// Milliseconds
+    pub fade_in: u32,
+    pub stay: u32,
+    pub fade_out: u32,
+}
+
+impl Title {
+    /// A title shown for the default times
+    pub fn new(title: &str, subtitle: &str) -> Self {
+        let (fade_in, stay, fade_out) = TITLE_TIMES;
+
+        Self {
+            title: title.to_owned(),
+            subtitle: subtitle.to_owned(),
+            fade_in,
+            stay,
+            fade_out,
+        }
+    }
+
+    pub fn with_times(mut self, fade_in: u32, stay: u32, fade_out: u32) -> Self {
+        self.fade_in = fade_in;
+        self.stay = stay;
+        self.fade_out = fade_out;
+        self
+    }
+}
+
+/// Something shown to a player that ran out of time, to be removed from their screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HudExpiry {
+    BossBar { player_id: usize, id: String },
+    ActionBar { player_id: usize },
+}
+
+#[derive(Debug, Default)]
+struct PlayerHud {
+    /// Boss bars by id, along with the tick each expires on if any
+    bars: BTreeMap<String, (BossBar, Option<i32>)>,
+    /// Tick the action bar text expires on, if it's showing
+    action_bar: Option<i32>,
+}
+
+/// Resource of the boss bars and action bar text shown to each player
+///
+/// Clients show whatever they're sent until told otherwise, so this keeps track of what's up
+/// to take it down as it expires.
+#[derive(Debug, Default)]
+pub struct Hud {
+    players: HashMap<usize, PlayerHud>,
+}
+
+impl Hud {
+    /// Show or change a boss bar, expiring on a tick or kept until removed
+    pub fn set_boss_bar(&mut self, player_id: usize, bar: BossBar, expires: Option<i32>) {
+        self.players
+            .entry(player_id)
+            .or_default()
+            .bars
+            .insert(bar.id.to_owned(), (bar, expires));
+    }
+
+    /// Remove a boss bar, false if the player isn't shown one of that id
+    pub fn remove_boss_bar(&mut self, player_id: usize, id: &str) -> bool {
+        self.players
+            .get_mut(&player_id)
+            .is_some_and(|hud| hud.bars.remove(id).is_some())
+    }
+
+    /// Boss bars shown to a player, by id
+    pub fn boss_bars(&self, player_id: usize) -> Vec<&BossBar> {
+        self.players.get(&player_id).map_or(vec![], |hud| {
+            hud.bars.values().map(|(bar, _)| bar).collect()
+        })
+    }
+
+    /// Keep track of action bar text shown to a player until a tick
+    pub fn set_action_bar(&mut self, player_id: usize, expires: i32) {
+        self.players.entry(player_id).or_default().action_bar = Some(expires);
+    }
+
+    /// Forget a player's action bar text, false if none was showing
+    pub fn clear_action_bar(&mut self, player_id: usize) -> bool {
+        self.players
+            .get_mut(&player_id)
+            .is_some_and(|hud| hud.action_bar.take().is_some())
+    }
+
+    /// Forget everything shown to a player, such as when they leave
+    pub fn remove_player(&mut self, player_id: usize) {
+        self.players.remove(&player_id);
+    }
+
+    /// Take what expired on or before a tick
+    pub fn take_expired(&mut self, tick: i32) -> Vec<HudExpiry> {
+        let mut expired = vec![];
+
+        for (player_id, hud) in self.players.iter_mut() {
+            hud.bars.retain(|id, (_, expires)| match expires {
+                Some(expires) if *expires <= tick => {
+                    expired.push(HudExpiry::BossBar {
+                        player_id: *player_id,
+                        id: id.to_owned(),
+                    });
+                    false
+                }
+                _ => true,
+            });
+
+            if matches!(hud.action_bar, Some(expires) if expires <= tick) {
+                hud.action_bar = None;
+                expired.push(HudExpiry::ActionBar {
+                    player_id: *player_id,
+                });
+            }
+        }
+
+        self.players
+            .retain(|_, hud| !hud.bars.is_empty() || hud.action_bar.is_some());
+
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expiry() {
+        let mut hud = Hud::default();
+
+        let bar = BossBar::new("round", "Round 1", 2.0, BarColor::Red);
+        assert_eq!(bar.progress, 1.0);
+
+        hud.set_boss_bar(1, bar, Some(10));
+        hud.set_boss_bar(
+            1,
+            BossBar::new("boss", "Dragon", 0.5, BarColor::Purple),
+            None,
+        );
+        hud.set_action_bar(2, 5);
+
+        assert!(hud.take_expired(4).is_empty());
+        assert_eq!(
+            hud.take_expired(5),
+            vec![HudExpiry::ActionBar { player_id: 2 }]
+        );
+        assert_eq!(
+            hud.take_expired(20),
+            vec![HudExpiry::BossBar {
+                player_id: 1,
+                id: "round".into()
+            }]
+        );
+        assert_eq!(hud.boss_bars(1).len(), 1);
+
+        assert!(!hud.clear_action_bar(2));
+        assert!(hud.remove_boss_bar(1, "boss"));
+        assert!(!hud.remove_boss_bar(1, "boss"));
+    }
+}
@@ -0,0 +1,117 @@
+use std::{collections::HashMap, fs};
+
+use crate::network::models::messages;
+
+/// Locale used when a player's locale or a key within it is missing
+pub const DEFAULT_LOCALE: &str = "en";
+
+const LANG_FOLDER: &str = "assets/metadata/lang";
+
+/// Bundled translations from `assets/metadata/lang/<locale>.json`, keyed by locale
+#[derive(Debug, Default)]
+pub struct Translations {
+    locales: HashMap<String, HashMap<String, String>>,
+}
+
+impl Translations {
+    /// Load every bundled translation file
+    pub fn new() -> Self {
+        let mut translations = Self::default();
+
+        let entries = match fs::read_dir(LANG_FOLDER) {
+            Ok(entries) => entries,
+            Err(_) => return translations,
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+
+            let locale = path.file_stem().unwrap().to_string_lossy().to_string();
+            let strings: HashMap<String, String> =
+                serde_json::from_reader(fs::File::open(&path).unwrap())
+                    .unwrap_or_else(|e| panic!("Unable to parse {:?}: {}", path, e));
+
+            translations.locales.insert(locale, strings);
+        }
+
+        translations
+    }
+
+    /// Whether a translation file exists for a locale
+    pub fn has_locale(&self, locale: &str) -> bool {
+        self.locales.contains_key(locale)
+    }
+
+    fn lookup(&self, locale: &str, key: &str) -> Option<&String> {
+        // "es-MX" falls back to "es", then to the default locale
+        let language = locale.split(['-', '_']).next().unwrap_or(locale);
+
+        [locale, language, DEFAULT_LOCALE]
+            .iter()
+            .find_map(|locale| self.locales.get(*locale).and_then(|s| s.get(key)))
+    }
+
+    /// Translate a key to a locale, replacing `{0}`, `{1}`... with args
+    ///
+    /// Missing keys are returned as is.
+    pub fn translate(&self, locale: &str, key: &str, args: &[String]) -> String {
+        let mut text = match self.lookup(locale, key) {
+            Some(text) => text.to_owned(),
+            None => return key.to_owned(),
+        };
+
+        for (i, arg) in args.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", i), arg);
+        }
+
+        text
+    }
+
+    /// Fill in the body of a translatable chat message for a locale
+    pub fn localize(&self, msg: &messages::Message, locale: &str) -> messages::Message {
+        let mut msg = msg.to_owned();
+
+        if let Some(chat) = msg.message.as_mut() {
+            if !chat.key.is_empty() {
+                chat.body = self.translate(locale, &chat.key, &chat.args);
+            }
+        }
+
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate() {
+        let mut translations = Translations::default();
+
+        let en = [("player.joined".to_owned(), "{0} joined the game".to_owned())];
+        let es = [("player.joined".to_owned(), "{0} se unió".to_owned())];
+        translations
+            .locales
+            .insert("en".to_owned(), en.iter().cloned().collect());
+        translations
+            .locales
+            .insert("es".to_owned(), es.iter().cloned().collect());
+
+        let args = ["Steve".to_owned()];
+        assert_eq!(
+            translations.translate("es-MX", "player.joined", &args),
+            "Steve se unió"
+        );
+        assert_eq!(
+            translations.translate("fr", "player.joined", &args),
+            "Steve joined the game"
+        );
+        assert_eq!(
+            translations.translate("es", "missing.key", &args),
+            "missing.key"
+        );
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// A stack of a single kind of item, identified by its block id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub id: u32,
+    pub count: u32,
+}
+
+impl ItemStack {
+    pub fn new(id: u32, count: u32) -> Self {
+        Self { id, count }
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use hashbrown::HashMap;
+
+use log::{error, info, warn};
+
+use serde::{Deserialize, Serialize};
+
+use server_common::vec::Vec2;
+use server_utils::convert::map_voxel_to_chunk;
+
+use crate::constants::DATA_PADDING;
+
+use super::block_entities::BlockEntityData;
+
+/// Edits are journaled into this file within the world folder until the chunks are saved
+pub const JOURNAL_FILE: &str = "journal.log";
+
+/// A single accepted edit, one JSON line in the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JournalEntry {
+    Voxel {
+        voxel: [i32; 3],
+        id: u32,
+        rotation: u32,
+        y_rotation: u32,
+    },
+    /// A block entity set, such as a chest's inventory changing, or removed if none
+    BlockEntity {
+        voxel: [i32; 3],
+        entity: Option<BlockEntityData>,
+    },
+}
+
+/// A line of the journal, leftover entries recording which chunks have yet to replay them
+#[derive(Serialize, Deserialize)]
+struct JournalLine {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chunks: Option<Vec<[i32; 2]>>,
+    #[serde(flatten)]
+    entry: JournalEntry,
+}
+
+/// Append-only write-ahead log of the edits made since the chunks were last saved
+///
+/// Entries left over on startup mean the last save didn't complete. They're replayed onto
+/// chunks as they load, and kept in the journal until the replayed chunks are saved.
+#[derive(Debug)]
+pub struct Journal {
+    path: Option<PathBuf>,
+    writer: Option<BufWriter<File>>,
+    chunk_size: usize,
+    /// Entries left over from before startup
+    leftover: Vec<JournalEntry>,
+    /// Indices into `leftover` each chunk still has to replay
+    pending: HashMap<Vec2<i32>, Vec<usize>>,
+}
+
+impl JournalEntry {
+    pub fn voxel(&self) -> [i32; 3] {
+        match self {
+            Self::Voxel { voxel, .. } | Self::BlockEntity { voxel, .. } => *voxel,
+        }
+    }
+
+    /// Coordinates of the chunks storing this edit, neighbors included for voxels in their padding
+    pub fn chunks(&self, chunk_size: usize) -> Vec<Vec2<i32>> {
+        let [vx, vy, vz] = self.voxel();
+        let owner = map_voxel_to_chunk(vx, vy, vz, chunk_size);
+
+        if let Self::BlockEntity { .. } = self {
+            return vec![owner];
+        }
+
+        let size = chunk_size as i32;
+        let padding = DATA_PADDING as i32;
+        let mut coords = vec![];
+
+        for dx in -1..=1 {
+            for dz in -1..=1 {
+                let Vec2(cx, cz) = Vec2(owner.0 + dx, owner.1 + dz);
+
+                if vx >= cx * size - padding
+                    && vx < (cx + 1) * size + padding
+                    && vz >= cz * size - padding
+                    && vz < (cz + 1) * size + padding
+                {
+                    coords.push(Vec2(cx, cz));
+                }
+            }
+        }
+
+        coords
+    }
+}
+
+impl Journal {
+    /// Open the journal within `folder`, if any, keeping the entries left over from an unfinished save
+    pub fn new(folder: Option<&Path>, chunk_size: usize) -> Self {
+        let mut journal = Self {
+            path: folder.map(|folder| folder.join(JOURNAL_FILE)),
+            writer: None,
+            chunk_size,
+            leftover: vec![],
+            pending: HashMap::new(),
+        };
+
+        let path = match &journal.path {
+            Some(path) => path.to_owned(),
+            None => return journal,
+        };
+
+        if let Ok(file) = File::open(&path) {
+            for line in BufReader::new(file).lines().map_while(Result::ok) {
+                // a torn last line is the edit that was being written when the server went down
+                match serde_json::from_str::<JournalLine>(&line) {
+                    Ok(JournalLine { chunks, entry }) => {
+                        let chunks = chunks.map(|chunks| {
+                            chunks.into_iter().map(|[cx, cz]| Vec2(cx, cz)).collect()
+                        });
+                        journal.add_leftover(entry, chunks);
+                    }
+                    Err(_) => warn!("Skipping an unreadable journal entry in {:?}.", path),
+                }
+            }
+        }
+
+        if !journal.leftover.is_empty() {
+            info!(
+                "Replaying {} edits journaled since the last save.",
+                journal.leftover.len()
+            );
+        }
+
+        // the leftover entries are rewritten, so that a torn line doesn't prefix new ones
+        journal.rewrite();
+        journal
+    }
+
+    /// Journal an accepted edit, synced to disk on the next `sync`
+    pub fn append(&mut self, entry: &JournalEntry) {
+        if let Some(writer) = &mut self.writer {
+            let line = serde_json::to_string(&JournalLine {
+                chunks: None,
+                entry: entry.to_owned(),
+            })
+            .unwrap();
+
+            if let Err(e) = writeln!(writer, "{}", line) {
+                self.stop(e);
+            }
+        }
+    }
+
+    /// Make sure every journaled edit reached the disk
+    pub fn sync(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            if let Err(e) = writer.flush().and_then(|_| writer.get_ref().sync_data()) {
+                self.stop(e);
+            }
+        }
+    }
+
+    /// Stop journaling after a failed write, such as on a full disk, until the next save
+    /// rewrites the journal
+    fn stop(&mut self, e: io::Error) {
+        error!(
+            "Unable to write to journal, edits aren't journaled until the next save: {}",
+            e
+        );
+        self.writer = None;
+    }
+
+    /// Take the leftover edits a chunk has to replay once loaded, in journal order
+    pub fn take_pending(&mut self, coords: &Vec2<i32>) -> Vec<JournalEntry> {
+        match self.pending.remove(coords) {
+            Some(indices) => indices
+                .into_iter()
+                .map(|index| self.leftover[index].to_owned())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Empty the journal once the chunks are saved, keeping the edits still waiting to be replayed
+    pub fn truncate(&mut self) {
+        let left = self
+            .remaining()
+            .into_iter()
+            .map(|(index, chunks)| (self.leftover[index].to_owned(), chunks))
+            .collect::<Vec<_>>();
+
+        self.leftover.clear();
+        self.pending.clear();
+        left.into_iter()
+            .for_each(|(entry, chunks)| self.add_leftover(entry, Some(chunks)));
+
+        self.rewrite();
+    }
+
+    /// Track a leftover entry, to be replayed by the chunks storing it unless given
+    fn add_leftover(&mut self, entry: JournalEntry, chunks: Option<Vec<Vec2<i32>>>) {
+        let index = self.leftover.len();
+        let chunks = chunks.unwrap_or_else(|| entry.chunks(self.chunk_size));
+
+        for coords in chunks {
+            self.pending.entry(coords).or_default().push(index);
+        }
+
+        self.leftover.push(entry);
+    }
+
+    /// Chunks yet to replay each leftover entry, in journal order
+    fn remaining(&self) -> BTreeMap<usize, Vec<Vec2<i32>>> {
+        let mut remaining: BTreeMap<usize, Vec<Vec2<i32>>> = BTreeMap::new();
+
+        for (coords, indices) in self.pending.iter() {
+            for index in indices {
+                remaining.entry(*index).or_default().push(coords.to_owned());
+            }
+        }
+
+        remaining
+    }
+
+    /// Replace the journal file with just the leftover entries, reopening it for appending
+    ///
+    /// If that fails, journaling stops until the next save, and the old journal is removed so
+    /// that its edits aren't replayed over chunks that have moved past them.
+    fn rewrite(&mut self) {
+        let path = match &self.path {
+            Some(path) => path.to_owned(),
+            None => return,
+        };
+
+        self.writer = None;
+
+        let temp = path.with_extension("log.tmp");
+        let mut data = String::new();
+        for (index, chunks) in self.remaining() {
+            let line = JournalLine {
+                chunks: Some(chunks.into_iter().map(|Vec2(cx, cz)| [cx, cz]).collect()),
+                entry: self.leftover[index].to_owned(),
+            };
+            data.push_str(&serde_json::to_string(&line).unwrap());
+            data.push('\n');
+        }
+
+        let reopened = fs::write(&temp, data)
+            .and_then(|_| fs::rename(&temp, &path))
+            .and_then(|_| OpenOptions::new().append(true).open(&path));
+
+        match reopened {
+            Ok(file) => self.writer = Some(BufWriter::new(file)),
+            Err(e) => {
+                fs::remove_file(&path).ok();
+                self.stop(e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay() {
+        let folder = std::env::temp_dir().join(format!("mine-journal-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+
+        let edge = JournalEntry::Voxel {
+            voxel: [0, 10, 5],
+            id: 1,
+            rotation: 0,
+            y_rotation: 0,
+        };
+        let chest = JournalEntry::BlockEntity {
+            voxel: [3, 10, 3],
+            entity: None,
+        };
+
+        let mut journal = Journal::new(Some(&folder), 16);
+        journal.append(&edge);
+        journal.append(&chest);
+        journal.sync();
+        drop(journal);
+
+        // the voxel sits in the padding of the chunk to its -x
+        let mut journal = Journal::new(Some(&folder), 16);
+        assert_eq!(
+            journal.take_pending(&Vec2(0, 0)),
+            vec![edge.to_owned(), chest]
+        );
+
+        journal.truncate();
+        drop(journal);
+
+        let mut journal = Journal::new(Some(&folder), 16);
+        assert!(journal.take_pending(&Vec2(0, 0)).is_empty());
+        assert_eq!(journal.take_pending(&Vec2(-1, 0)), vec![edge]);
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::Deserialize;
+
+use super::{items::ItemStack, registry::Registry};
+
+/// What the loot of a broken block or killed mob depends on
+#[derive(Debug, Clone, Default)]
+pub struct LootContext {
+    /// Tier of the block held as a tool, 0 for bare hands
+    pub tool_tier: u32,
+    /// Whether the player has the silk touch effect
+    pub silk_touch: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_one() -> u32 {
+    1
+}
+
+/// Condition a pool is only rolled under
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum LootCondition {
+    /// Tool of at least this tier
+    ToolTier { min: u32 },
+    /// Silk touch present, or absent if `value` is false
+    SilkTouch {
+        #[serde(default = "default_true")]
+        value: bool,
+    },
+    /// Random chance out of 1
+    Chance { chance: f64 },
+}
+
+impl LootCondition {
+    pub fn test<R: Rng>(&self, context: &LootContext, rng: &mut R) -> bool {
+        match self {
+            LootCondition::ToolTier { min } => context.tool_tier >= *min,
+            LootCondition::SilkTouch { value } => context.silk_touch == *value,
+            LootCondition::Chance { chance } => rng.gen_bool(chance.clamp(0.0, 1.0)),
+        }
+    }
+}
+
+/// One of the outcomes of a pool roll, `item` being `None` for nothing
+#[derive(Debug, Clone)]
+pub struct LootEntry {
+    pub item: Option<u32>,
+    pub weight: u32,
+    /// Inclusive range of the count dropped
+    pub count: (u32, u32),
+}
+
+/// Entries picked by weight, `rolls` times if every condition holds
+#[derive(Debug, Clone)]
+pub struct LootPool {
+    pub rolls: u32,
+    pub conditions: Vec<LootCondition>,
+    pub entries: Vec<LootEntry>,
+}
+
+/// Pools rolled one after the other, their drops adding up
+#[derive(Debug, Clone, Default)]
+pub struct LootTable {
+    pub pools: Vec<LootPool>,
+}
+
+impl LootTable {
+    /// Roll every pool, merging the drops of the same item
+    pub fn roll<R: Rng>(&self, context: &LootContext, rng: &mut R) -> Vec<ItemStack> {
+        let mut drops: Vec<ItemStack> = vec![];
+
+        for pool in self.pools.iter() {
+            if !pool.conditions.iter().all(|c| c.test(context, rng)) {
+                continue;
+            }
+
+            let total = pool.entries.iter().map(|e| e.weight).sum::<u32>();
+            if total == 0 {
+                continue;
+            }
+
+            for _ in 0..pool.rolls {
+                let mut pick = rng.gen_range(0..total);
+                let entry = pool
+                    .entries
+                    .iter()
+                    .find(|e| {
+                        if pick < e.weight {
+                            true
+                        } else {
+                            pick -= e.weight;
+                            false
+                        }
+                    })
+                    .unwrap();
+
+                let (min, max) = entry.count;
+                let count = rng.gen_range(min..=max.max(min));
+
+                if let (Some(id), true) = (entry.item, count > 0) {
+                    match drops.iter_mut().find(|stack| stack.id == id) {
+                        Some(stack) => stack.count += count,
+                        None => drops.push(ItemStack::new(id, count)),
+                    }
+                }
+            }
+        }
+
+        drops
+    }
+}
+
+#[derive(Deserialize)]
+struct LootEntryData {
+    /// Block name, missing for an entry dropping nothing
+    #[serde(default)]
+    item: Option<String>,
+    #[serde(default = "default_one")]
+    weight: u32,
+    /// Fixed count, or `[min, max]`
+    #[serde(default)]
+    count: Option<LootCount>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LootCount {
+    Fixed(u32),
+    Range([u32; 2]),
+}
+
+#[derive(Deserialize)]
+struct LootPoolData {
+    #[serde(default = "default_one")]
+    rolls: u32,
+    #[serde(default)]
+    conditions: Vec<LootCondition>,
+    entries: Vec<LootEntryData>,
+}
+
+#[derive(Deserialize)]
+struct LootTableData {
+    pools: Vec<LootPoolData>,
+}
+
+#[derive(Deserialize)]
+struct LootTablesData {
+    /// Keyed by block name or tag
+    #[serde(default)]
+    blocks: HashMap<String, LootTableData>,
+    /// Keyed by entity type
+    #[serde(default)]
+    entities: HashMap<String, LootTableData>,
+}
+
+/// Resource of what blocks and mobs drop, loaded from `assets/metadata/loot.json`
+///
+/// Blocks without a table drop themselves, mobs without one drop nothing.
+#[derive(Debug, Default)]
+pub struct LootTables {
+    blocks: HashMap<u32, LootTable>,
+    entities: HashMap<String, LootTable>,
+}
+
+impl LootTables {
+    /// Panics on a broken loot file, see `LootTables::load`
+    pub fn new(registry: &Registry) -> Self {
+        LootTables::load(registry).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Load the loot tables, resolving block names and tags through the registry
+    pub fn load(registry: &Registry) -> Result<Self, String> {
+        let data: LootTablesData = serde_json::from_value(
+            registry
+                .data_packs
+                .read("loot.json")
+                .ok_or("Unable to find loot.json")?,
+        )
+        .map_err(|e| format!("Unable to parse loot.json: {}", e))?;
+
+        let id = |name: &str| {
+            registry
+                .try_get_id_by_name(name)
+                .ok_or_else(|| format!("Unknown block in loot tables: {}", name))
+        };
+
+        let table = |data: &LootTableData| -> Result<LootTable, String> {
+            Ok(LootTable {
+                pools: data
+                    .pools
+                    .iter()
+                    .map(|pool| {
+                        Ok(LootPool {
+                            rolls: pool.rolls,
+                            conditions: pool.conditions.to_owned(),
+                            entries: pool
+                                .entries
+                                .iter()
+                                .map(|entry| {
+                                    Ok(LootEntry {
+                                        item: entry.item.as_deref().map(id).transpose()?,
+                                        weight: entry.weight,
+                                        count: match entry.count {
+                                            None => (1, 1),
+                                            Some(LootCount::Fixed(count)) => (count, count),
+                                            Some(LootCount::Range([min, max])) => (min, max),
+                                        },
+                                    })
+                                })
+                                .collect::<Result<_, String>>()?,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?,
+            })
+        };
+
+        // blocks named outright take precedence over the tags they're in
+        let mut block_tables = data.blocks.iter().collect::<Vec<_>>();
+        block_tables.sort_by_key(|&(pattern, _)| (!pattern.starts_with('#'), pattern));
+
+        let mut blocks = HashMap::new();
+        for (pattern, data) in block_tables {
+            let ids = match pattern.strip_prefix('#') {
+                Some(tag) => registry
+                    .tags
+                    .get(tag)
+                    .ok_or_else(|| format!("Unknown tag in loot tables: {}", pattern))?
+                    .iter()
+                    .copied()
+                    .collect(),
+                None => vec![id(pattern)?],
+            };
+
+            let table = table(data)?;
+            for id in ids {
+                blocks.insert(id, table.to_owned());
+            }
+        }
+
+        let entities = data
+            .entities
+            .iter()
+            .map(|(etype, data)| Ok((etype.to_owned(), table(data)?)))
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { blocks, entities })
+    }
+
+    /// Add a block's table, replacing its current one
+    pub fn set_block(&mut self, id: u32, table: LootTable) {
+        self.blocks.insert(id, table);
+    }
+
+    /// Add an entity type's table, replacing its current one
+    pub fn set_entity(&mut self, etype: &str, table: LootTable) {
+        self.entities.insert(etype.to_owned(), table);
+    }
+
+    /// Roll the drops of a broken block, itself if it has no table unless it's air or a fluid
+    pub fn roll_block<R: Rng>(
+        &self,
+        id: u32,
+        registry: &Registry,
+        context: &LootContext,
+        rng: &mut R,
+    ) -> Vec<ItemStack> {
+        match self.blocks.get(&id) {
+            Some(table) => table.roll(context, rng),
+            None => {
+                let block = registry.get_block_by_id(id);
+
+                if block.is_empty || block.is_fluid {
+                    vec![]
+                } else {
+                    vec![ItemStack::new(id, 1)]
+                }
+            }
+        }
+    }
+
+    /// Roll the drops of a killed mob
+    pub fn roll_entity<R: Rng>(
+        &self,
+        etype: &str,
+        context: &LootContext,
+        rng: &mut R,
+    ) -> Vec<ItemStack> {
+        self.entities
+            .get(etype)
+            .map(|table| table.roll(context, rng))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn pools() {
+        let entry = |item: Option<u32>, weight: u32| LootEntry {
+            item,
+            weight,
+            count: (1, 1),
+        };
+
+        // grass drops itself with silk touch or two dirt otherwise, plus a bonus for good tools
+        let table = LootTable {
+            pools: vec![
+                LootPool {
+                    rolls: 1,
+                    conditions: vec![LootCondition::SilkTouch { value: true }],
+                    entries: vec![entry(Some(2), 1)],
+                },
+                LootPool {
+                    rolls: 2,
+                    conditions: vec![LootCondition::SilkTouch { value: false }],
+                    entries: vec![entry(Some(1), 1)],
+                },
+                LootPool {
+                    rolls: 1,
+                    conditions: vec![LootCondition::ToolTier { min: 2 }],
+                    entries: vec![entry(Some(9), 1), entry(None, 0)],
+                },
+            ],
+        };
+
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let silk = LootContext {
+            tool_tier: 0,
+            silk_touch: true,
+        };
+        assert_eq!(table.roll(&silk, &mut rng), vec![ItemStack::new(2, 1)]);
+
+        let tool = LootContext {
+            tool_tier: 2,
+            silk_touch: false,
+        };
+        assert_eq!(
+            table.roll(&tool, &mut rng),
+            vec![ItemStack::new(1, 2), ItemStack::new(9, 1)]
+        );
+    }
+}
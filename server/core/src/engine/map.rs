@@ -0,0 +1,204 @@
+use std::{collections::HashMap, io::Cursor};
+
+use server_common::vec::Vec2;
+
+use super::{chunk::Chunk, registry::Registry};
+
+/// Most map tiles redrawn each tick, the rest waiting for the next ones
+pub const MAX_MAP_TILES_PER_TICK: usize = 8;
+
+/// Most map tiles a client can ask for at once
+pub const MAX_MAP_TILES_PER_REQUEST: usize = 256;
+
+/// Sides of a block's textures seen from above, in order of preference
+const TOP_SIDES: [&str; 3] = ["top", "all", "one"];
+
+/// Average color of each block's top texture, as seen from above
+#[derive(Debug, Default)]
+pub struct MapColors {
+    colors: HashMap<u32, [u8; 3]>,
+}
+
+impl MapColors {
+    /// Average the opaque pixels of each block's top texture in the atlas
+    pub fn new(registry: &Registry) -> Self {
+        let (width, height) = registry.atlas.dimensions();
+
+        let colors = registry
+            .blocks
+            .iter()
+            .filter(|(_, block)| !block.is_empty)
+            .filter_map(|(id, block)| {
+                let texture = TOP_SIDES
+                    .iter()
+                    .find_map(|side| block.textures.get(*side))
+                    .or_else(|| {
+                        let mut textures = block.textures.values().collect::<Vec<_>>();
+                        textures.sort();
+                        textures.first().copied()
+                    })?;
+                let uv = registry.ranges.get(texture)?;
+
+                let x0 = (uv.start_u * width as f32) as u32;
+                let x1 = ((uv.end_u * width as f32) as u32).min(width);
+                let y0 = ((1.0 - uv.start_v) * height as f32) as u32;
+                let y1 = (((1.0 - uv.end_v) * height as f32) as u32).min(height);
+
+                let (mut sum, mut count) = ([0u64; 3], 0u64);
+
+                for x in x0..x1 {
+                    for y in y0..y1 {
+                        let pixel = registry.atlas.get_pixel(x, y);
+
+                        if pixel[3] > 0 {
+                            (0..3).for_each(|i| sum[i] += pixel[i] as u64);
+                            count += 1;
+                        }
+                    }
+                }
+
+                if count == 0 {
+                    return None;
+                }
+
+                Some((
+                    *id,
+                    [
+                        (sum[0] / count) as u8,
+                        (sum[1] / count) as u8,
+                        (sum[2] / count) as u8,
+                    ],
+                ))
+            })
+            .collect();
+
+        Self { colors }
+    }
+
+    /// Color of a block seen from above, none for those that can't be seen such as air
+    pub fn get(&self, id: u32) -> Option<[u8; 3]> {
+        self.colors.get(&id).copied()
+    }
+}
+
+/// Colors of a chunk's voxel columns seen from above
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapTile {
+    pub coords: Vec2<i32>,
+    /// Side of the tile, the chunk size
+    pub size: usize,
+    /// RGB of each column, rows along x one z after the other
+    pub colors: Vec<u8>,
+}
+
+impl MapTile {
+    /// Draw a chunk from above, columns lit or shaded by how they rise over those north of them
+    pub fn render(chunk: &Chunk, colors: &MapColors) -> Self {
+        let size = chunk.size;
+        let mut tile = vec![0; size * size * 3];
+
+        let top = |vx: i32, vz: i32| {
+            let mut vy = chunk.get_max_height(vx, vz).min(chunk.max.1 - 1);
+
+            while vy >= chunk.min.1 {
+                if let Some(color) = colors.get(chunk.get_voxel(vx, vy, vz)) {
+                    return Some((vy, color));
+                }
+                vy -= 1;
+            }
+
+            None
+        };
+
+        for lz in 0..size {
+            for lx in 0..size {
+                let vx = chunk.min_inner.0 + lx as i32;
+                let vz = chunk.min_inner.2 + lz as i32;
+
+                let (height, color) = match top(vx, vz) {
+                    Some(top) => top,
+                    None => continue,
+                };
+
+                let shade = match top(vx, vz - 1) {
+                    Some((north, _)) if height > north => 1.15,
+                    Some((north, _)) if height < north => 0.85,
+                    _ => 1.0,
+                };
+
+                let index = (lz * size + lx) * 3;
+                for i in 0..3 {
+                    tile[index + i] = (color[i] as f32 * shade).min(255.0) as u8;
+                }
+            }
+        }
+
+        Self {
+            coords: chunk.coords.to_owned(),
+            size,
+            colors: tile,
+        }
+    }
+
+    /// Encode the tile as a PNG image, north up
+    pub fn to_png(&self) -> Vec<u8> {
+        let size = self.size as u32;
+        let image = image::RgbImage::from_raw(size, size, self.colors.to_owned())
+            .expect("Map tile doesn't match its size.");
+
+        let mut bytes = Cursor::new(vec![]);
+        image::DynamicImage::ImageRgb8(image)
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .expect("Unable to encode map tile.");
+
+        bytes.into_inner()
+    }
+}
+
+/// Resource of the map tiles of a world, drawn as chunks load and their surface changes
+///
+/// Tiles are kept once drawn, as a map of everywhere explored since the server started.
+#[derive(Debug, Default)]
+pub struct WorldMap {
+    pub colors: MapColors,
+    tiles: HashMap<Vec2<i32>, MapTile>,
+}
+
+impl WorldMap {
+    pub fn new(registry: &Registry) -> Self {
+        Self {
+            colors: MapColors::new(registry),
+            tiles: HashMap::new(),
+        }
+    }
+
+    /// Draw a chunk's tile again, returning it
+    pub fn redraw(&mut self, chunk: &Chunk) -> &MapTile {
+        let tile = MapTile::render(chunk, &self.colors);
+        self.tiles.insert(chunk.coords.to_owned(), tile);
+        &self.tiles[&chunk.coords]
+    }
+
+    pub fn get(&self, coords: &Vec2<i32>) -> Option<&MapTile> {
+        self.tiles.get(coords)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png() {
+        let tile = MapTile {
+            coords: Vec2(0, 0),
+            size: 2,
+            colors: vec![255, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0],
+        };
+
+        let png = image::load_from_memory(&tile.to_png()).unwrap().to_rgb8();
+        assert_eq!(png.dimensions(), (2, 2));
+        assert_eq!(png.get_pixel(1, 0).0, [0, 255, 0]);
+        assert_eq!(png.get_pixel(0, 1).0, [0, 0, 255]);
+    }
+}
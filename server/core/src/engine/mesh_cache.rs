@@ -0,0 +1,131 @@
+use std::{
+    borrow::Cow,
+    io::{Read, Write},
+};
+
+use libflate::zlib::{Decoder, Encoder};
+use serde::{Deserialize, Serialize};
+
+use server_utils::hash::{fnv1a, fnv1a_extend};
+
+use super::{
+    chunk::{Chunk, Meshes},
+    config::WorldConfig,
+    registry::Registry,
+    storage::Storage,
+};
+
+/// Version of the cached meshes, bumped as the mesher changes to throw the cached ones away
+pub const MESH_CACHE_VERSION: u32 = 1;
+
+/// Prototype for the meshes of a chunk saved alongside it
+#[derive(Serialize, Deserialize)]
+struct MeshFileData<'a> {
+    version: u32,
+    /// Hash of the voxels and lights the meshes were made of
+    hash: u64,
+    meshes: Cow<'a, [Meshes]>,
+}
+
+/// Hash of what meshes are made of besides the chunk, the blocks, where their textures are in
+/// the atlas and how chunks are split into sub-chunks
+pub fn mesh_salt(registry: &Registry, config: &WorldConfig) -> u64 {
+    let mut hash = fnv1a(&MESH_CACHE_VERSION.to_le_bytes());
+
+    let mut ids = registry.blocks.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+
+    // values hold their keys sorted, unlike the maps within blocks
+    for id in ids {
+        let block = serde_json::to_value(&registry.blocks[&id]).unwrap();
+        hash = fnv1a_extend(hash, &id.to_le_bytes());
+        hash = fnv1a_extend(hash, block.to_string().as_bytes());
+    }
+
+    let mut ranges = registry.ranges.iter().collect::<Vec<_>>();
+    ranges.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (texture, uv) in ranges {
+        hash = fnv1a_extend(hash, texture.as_bytes());
+        for value in &[uv.start_u, uv.end_u, uv.start_v, uv.end_v] {
+            hash = fnv1a_extend(hash, &value.to_bits().to_le_bytes());
+        }
+    }
+
+    for value in &[
+        config.chunk_size as u64,
+        config.max_height as u64,
+        config.sub_chunks as u64,
+    ] {
+        hash = fnv1a_extend(hash, &value.to_le_bytes());
+    }
+
+    hash
+}
+
+/// Hash of the voxels and lights of a chunk, padding included, along with the salt
+pub fn chunk_hash(chunk: &Chunk, salt: u64) -> u64 {
+    let hash = fnv1a(&salt.to_le_bytes());
+
+    chunk
+        .get_voxels()
+        .data
+        .iter()
+        .chain(chunk.get_lights().data.iter())
+        .fold(hash, |hash, value| fnv1a_extend(hash, &value.to_le_bytes()))
+}
+
+/// Load the meshes cached for a chunk, if they were made of the voxels and lights it holds now
+///
+/// Returns whether they were, the chunk being left to mesh otherwise.
+pub fn load_meshes(chunk: &mut Chunk, storage: &dyn Storage, salt: u64, sub_chunks: u32) -> bool {
+    let meshes = storage.get_meshes(&chunk.name).and_then(|data| {
+        let mut decoder = Decoder::new(&data[..]).ok()?;
+        let mut buf = Vec::new();
+        decoder.read_to_end(&mut buf).ok()?;
+
+        let data: MeshFileData = serde_json::from_slice(&buf).ok()?;
+
+        if data.version != MESH_CACHE_VERSION
+            || data.meshes.len() != sub_chunks as usize
+            || data.hash != chunk_hash(chunk, salt)
+        {
+            return None;
+        }
+
+        Some(data.meshes.into_owned())
+    });
+
+    match meshes {
+        Some(meshes) => {
+            chunk.meshes = meshes;
+            chunk.is_dirty = false;
+            chunk.dirty_levels.clear();
+            chunk.meshes_cached = true;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Save the meshes of a chunk alongside it, unless they're cached already or stale
+pub fn save_meshes(chunk: &mut Chunk, storage: &dyn Storage, salt: u64) {
+    if chunk.meshes_cached || chunk.is_dirty || chunk.needs_propagation || chunk.meshes.is_empty() {
+        return;
+    }
+
+    let data = MeshFileData {
+        version: MESH_CACHE_VERSION,
+        hash: chunk_hash(chunk, salt),
+        meshes: Cow::Borrowed(&chunk.meshes),
+    };
+
+    let mut encoder = Encoder::new(vec![]).unwrap();
+    encoder
+        .write_all(&serde_json::to_vec(&data).unwrap())
+        .unwrap();
+    let encoded = encoder.finish().into_result().unwrap();
+
+    storage.put_meshes(&chunk.name, &encoded);
+    chunk.meshes_cached = true;
+}
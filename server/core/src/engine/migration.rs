@@ -0,0 +1,156 @@
+use log::info;
+
+use serde_json::Value;
+
+use super::storage::Storage;
+
+/// Version of the on-disk chunk format written by this server
+///
+/// Chunk files saved before versioning have no `version` field and count as version 1.
+pub const CHUNK_FORMAT_VERSION: u32 = 3;
+
+/// A single step upgrading chunk data from `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut Value) -> Result<(), String>,
+}
+
+/// Every migration in order, one per format version bump
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "record the chunk format version",
+        apply: |_| Ok(()),
+    },
+    Migration {
+        from: 2,
+        description: "add block entities",
+        apply: |data| {
+            data["blockEntities"] = Value::Array(vec![]);
+            Ok(())
+        },
+    },
+];
+
+/// Get the format version of raw chunk data
+pub fn chunk_version(data: &Value) -> u32 {
+    data["version"].as_u64().map_or(1, |version| version as u32)
+}
+
+/// Upgrade raw chunk data to the current format in place
+///
+/// Returns whether any migration was applied.
+pub fn migrate_chunk(data: &mut Value) -> Result<bool, String> {
+    let mut version = chunk_version(data);
+
+    if version > CHUNK_FORMAT_VERSION {
+        return Err(format!(
+            "Chunk format version {} is newer than the supported version {}.",
+            version, CHUNK_FORMAT_VERSION
+        ));
+    }
+
+    if version == CHUNK_FORMAT_VERSION {
+        return Ok(false);
+    }
+
+    while version < CHUNK_FORMAT_VERSION {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|migration| migration.from == version)
+            .ok_or_else(|| format!("No migration found from chunk format {}.", version))?;
+
+        (migration.apply)(data)
+            .map_err(|e| format!("Migration \"{}\" failed: {}", migration.description, e))?;
+
+        version += 1;
+        data["version"] = Value::from(version);
+    }
+
+    Ok(true)
+}
+
+/// Upgrade every outdated chunk of a world, rewriting them one by one
+///
+/// Logs the progress roughly every tenth of the way. Returns the number of upgraded chunks.
+/// Chunks of a storage that can't list them are upgraded as they load instead.
+pub fn upgrade_chunks(world_name: &str, storage: &dyn Storage) -> usize {
+    let mut names = match storage.chunk_names() {
+        Some(names) => names,
+        None => return 0,
+    };
+    names.sort();
+
+    let total = names.len();
+    let step = (total / 10).max(1);
+    let mut upgraded = 0;
+
+    for (i, name) in names.iter().enumerate() {
+        let mut data: Value = storage
+            .get_chunk(name)
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_else(|| panic!("Couldn't read chunk: {}", name));
+
+        match migrate_chunk(&mut data) {
+            Ok(true) => {
+                storage.put_chunk(name, data.to_string().as_bytes());
+                upgraded += 1;
+            }
+            Ok(false) => {}
+            Err(e) => panic!("Unable to upgrade chunk {}: {}", name, e),
+        }
+
+        if upgraded > 0 && ((i + 1) % step == 0 || i + 1 == total) {
+            info!(
+                "Upgrading chunks of world \"{}\" to format {}: {}/{} checked, {} upgraded.",
+                world_name,
+                CHUNK_FORMAT_VERSION,
+                i + 1,
+                total,
+                upgraded
+            );
+        }
+    }
+
+    if upgraded > 0 {
+        storage.flush();
+    }
+
+    upgraded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate() {
+        let mut data = serde_json::json!({ "needsPropagation": false });
+        assert_eq!(chunk_version(&data), 1);
+
+        assert_eq!(migrate_chunk(&mut data), Ok(true));
+        assert_eq!(chunk_version(&data), CHUNK_FORMAT_VERSION);
+        assert_eq!(migrate_chunk(&mut data), Ok(false));
+
+        data["version"] = Value::from(CHUNK_FORMAT_VERSION + 1);
+        assert!(migrate_chunk(&mut data).is_err());
+    }
+
+    #[test]
+    fn upgrade() {
+        use super::super::storage::{open_storage, StorageKind};
+
+        let root = std::env::temp_dir().join(format!("mine-migration-{}", std::process::id()));
+        let storage = open_storage(StorageKind::Files, None, &root);
+
+        storage.put_chunk("0|0", br#"{ "needsPropagation": false }"#);
+        assert_eq!(upgrade_chunks("test", storage.as_ref()), 1);
+        assert_eq!(upgrade_chunks("test", storage.as_ref()), 0);
+
+        let data: Value = serde_json::from_slice(&storage.get_chunk("0|0").unwrap()).unwrap();
+        assert_eq!(chunk_version(&data), CHUNK_FORMAT_VERSION);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
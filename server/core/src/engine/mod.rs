@@ -1,12 +1,61 @@
+pub mod advancements;
+pub mod arenas;
 pub mod astar;
+pub mod audit;
+pub mod block_entities;
+pub mod bridge;
+pub mod budget;
 pub mod chunk;
 pub mod chunks;
 pub mod clock;
+pub mod commands;
 pub mod config;
+pub mod container;
+pub mod cron;
+pub mod data_packs;
+pub mod debug;
 pub mod entities;
+pub mod events;
+pub mod forms;
+pub mod functions;
+pub mod holograms;
+pub mod hud;
+pub mod i18n;
+pub mod items;
+pub mod journal;
 pub mod kdtree;
+pub mod loot;
+pub mod map;
+pub mod mesh_cache;
+pub mod migration;
+pub mod object_storage;
+pub mod observers;
 pub mod physics;
+pub mod picking;
+#[cfg(feature = "server")]
 pub mod players;
+pub mod pregen;
+pub mod profiler;
+pub mod recipes;
 pub mod registry;
+pub mod reload;
+pub mod resource_pack;
+pub mod restart;
+pub mod rng;
+pub mod scheduler;
+pub mod shards;
+pub mod skins;
+pub mod snapshots;
 pub mod space;
+pub mod storage;
+pub mod sweep_recorder;
+pub mod tags;
+pub mod teams;
+pub mod trading;
+pub mod transaction;
+pub mod transfer;
+pub mod trim;
+pub mod vehicles;
+pub mod verify;
+#[cfg(feature = "server")]
 pub mod world;
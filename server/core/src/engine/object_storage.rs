@@ -0,0 +1,426 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crossbeam_channel::{bounded, unbounded, RecvTimeoutError, Sender};
+
+use log::{error, warn};
+
+use serde::Deserialize;
+
+use super::storage::{FileStorage, Storage};
+
+/// How often the edits written to the local cache are uploaded
+pub const UPLOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Times an object is fetched before giving up on it, waiting a little longer after each failure
+const FETCH_ATTEMPTS: u32 = 3;
+const FETCH_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Connection details of an S3-compatible bucket, credentials are read from the environment
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectStorageConfig {
+    pub bucket: String,
+    pub region: String,
+    /// Custom endpoint of S3-compatible services such as MinIO, with path-style addressing
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Prepended to every key, so that worlds can share a bucket
+    #[serde(default)]
+    pub prefix: String,
+}
+
+/// A remote key-value store of objects, such as an S3 bucket
+pub trait ObjectStore: Debug + Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String>;
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), String>;
+    fn delete(&self, key: &str) -> Result<(), String>;
+    /// Keys of every object starting with a prefix, the prefix included
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String>;
+}
+
+/// Objects changed locally but not uploaded yet by key, none for the ones to delete
+type Dirty = Arc<Mutex<BTreeMap<String, Option<Vec<u8>>>>>;
+
+/// Storage backed by an object store, through a write-back cache in the world folder
+///
+/// Reads hit the local cache first, then the object store. Writes land in the cache right away
+/// and are uploaded in batches off-thread, every `UPLOAD_INTERVAL` or as soon as flushed.
+#[derive(Debug)]
+pub struct ObjectStorage {
+    cache: FileStorage,
+    store: Arc<dyn ObjectStore>,
+    dirty: Dirty,
+    /// Wakes the uploader, with a sender to signal once the batch is uploaded
+    uploads: Sender<Option<Sender<()>>>,
+}
+
+const CHUNKS_PREFIX: &str = "chunks/";
+const MESHES_PREFIX: &str = "meshes/";
+
+impl ObjectStorage {
+    pub fn new(store: Arc<dyn ObjectStore>, folder: &Path, interval: Duration) -> Self {
+        let dirty = Dirty::default();
+        let (uploads, requests) = unbounded::<Option<Sender<()>>>();
+
+        let uploader_store = Arc::clone(&store);
+        let uploader_dirty = Arc::clone(&dirty);
+
+        thread::spawn(move || loop {
+            let done = match requests.recv_timeout(interval) {
+                Ok(done) => done,
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            upload(uploader_store.as_ref(), &uploader_dirty);
+
+            if let Some(done) = done {
+                done.send(()).ok();
+            }
+        });
+
+        Self {
+            cache: FileStorage::new(folder),
+            store,
+            dirty,
+            uploads,
+        }
+    }
+
+    /// Read an object through the cache, retrying a few times if the store can't be reached
+    ///
+    /// An object the store still can't give back is logged and read as missing.
+    fn get(&self, key: &str, cached: Option<Vec<u8>>, cache: impl Fn(&[u8])) -> Option<Vec<u8>> {
+        if cached.is_some() {
+            return cached;
+        }
+
+        // removed, but not deleted from the store yet
+        if let Some(None) = self.dirty.lock().unwrap().get(key) {
+            return None;
+        }
+
+        for attempt in 1..=FETCH_ATTEMPTS {
+            match self.store.get(key) {
+                Ok(Some(data)) => {
+                    cache(&data);
+                    return Some(data);
+                }
+                Ok(None) => return None,
+                Err(e) if attempt < FETCH_ATTEMPTS => {
+                    warn!("Unable to fetch \"{}\", retrying: {}", key, e);
+                    thread::sleep(FETCH_BACKOFF * attempt);
+                }
+                Err(e) => error!("Unable to fetch \"{}\" from object storage: {}", key, e),
+            }
+        }
+
+        None
+    }
+
+    fn mark_dirty(&self, key: String, data: Option<&[u8]>) {
+        self.dirty
+            .lock()
+            .unwrap()
+            .insert(key, data.map(<[u8]>::to_vec));
+    }
+}
+
+/// Upload every dirty object, keeping the ones that failed for the next batch
+///
+/// Objects stay dirty until uploaded, so that reads meanwhile still see removals.
+fn upload(store: &dyn ObjectStore, dirty: &Dirty) {
+    let batch = dirty.lock().unwrap().clone();
+
+    for (key, data) in batch {
+        let uploaded = match &data {
+            Some(data) => store.put(&key, data),
+            None => store.delete(&key),
+        };
+
+        match uploaded {
+            Ok(_) => {
+                let mut dirty = dirty.lock().unwrap();
+
+                // a newer version may have been written meanwhile
+                if dirty.get(&key) == Some(&data) {
+                    dirty.remove(&key);
+                }
+            }
+            Err(e) => warn!("Unable to upload \"{}\", retrying later: {}", key, e),
+        }
+    }
+}
+
+impl Storage for ObjectStorage {
+    fn get_chunk(&self, name: &str) -> Option<Vec<u8>> {
+        self.get(
+            &format!("{}{}", CHUNKS_PREFIX, name),
+            self.cache.get_chunk(name),
+            |data| self.cache.put_chunk(name, data),
+        )
+    }
+
+    fn put_chunk(&self, name: &str, data: &[u8]) {
+        self.cache.put_chunk(name, data);
+        self.mark_dirty(format!("{}{}", CHUNKS_PREFIX, name), Some(data));
+    }
+
+    fn get_meshes(&self, name: &str) -> Option<Vec<u8>> {
+        self.get(
+            &format!("{}{}", MESHES_PREFIX, name),
+            self.cache.get_meshes(name),
+            |data| self.cache.put_meshes(name, data),
+        )
+    }
+
+    fn put_meshes(&self, name: &str, data: &[u8]) {
+        self.cache.put_meshes(name, data);
+        self.mark_dirty(format!("{}{}", MESHES_PREFIX, name), Some(data));
+    }
+
+    fn get_player(&self, name: &str) -> Option<Vec<u8>> {
+        self.get(
+            &format!("players/{}", name),
+            self.cache.get_player(name),
+            |data| self.cache.put_player(name, data),
+        )
+    }
+
+    fn put_player(&self, name: &str, data: &[u8]) {
+        self.cache.put_player(name, data);
+        self.mark_dirty(format!("players/{}", name), Some(data));
+    }
+
+    fn get_metadata(&self, key: &str) -> Option<Vec<u8>> {
+        self.get(
+            &format!("metadata/{}", key),
+            self.cache.get_metadata(key),
+            |data| self.cache.put_metadata(key, data),
+        )
+    }
+
+    fn put_metadata(&self, key: &str, data: &[u8]) {
+        self.cache.put_metadata(key, data);
+        self.mark_dirty(format!("metadata/{}", key), Some(data));
+    }
+
+    /// Upload the pending objects off-thread now, instead of at the next interval
+    fn flush(&self) {
+        self.uploads.send(None).ok();
+    }
+
+    /// Upload every pending object now, waiting for the batch to finish
+    fn sync(&self) {
+        let (done, wait) = bounded(1);
+
+        if self.uploads.send(Some(done)).is_ok() {
+            wait.recv().ok();
+        }
+    }
+
+    /// Names of the chunks in the store, along with the ones not uploaded yet
+    fn chunk_names(&self) -> Option<Vec<String>> {
+        let keys = match self.store.list(CHUNKS_PREFIX) {
+            Ok(keys) => keys,
+            Err(e) => {
+                error!("Unable to list the chunks in object storage: {}", e);
+                return None;
+            }
+        };
+
+        let mut names = keys
+            .iter()
+            .filter_map(|key| key.strip_prefix(CHUNKS_PREFIX))
+            .map(str::to_owned)
+            .collect::<BTreeSet<_>>();
+
+        for (key, data) in self.dirty.lock().unwrap().iter() {
+            if let Some(name) = key.strip_prefix(CHUNKS_PREFIX) {
+                if data.is_some() {
+                    names.insert(name.to_owned());
+                } else {
+                    names.remove(name);
+                }
+            }
+        }
+
+        Some(names.into_iter().collect())
+    }
+
+    fn remove_chunk(&self, name: &str) {
+        self.cache.remove_chunk(name);
+        self.mark_dirty(format!("{}{}", CHUNKS_PREFIX, name), None);
+        self.mark_dirty(format!("{}{}", MESHES_PREFIX, name), None);
+    }
+
+    /// Objects are deleted from the store as they're uploaded, only the cache has leftovers
+    fn compact(&self) {
+        self.cache.compact();
+    }
+}
+
+/// An S3 bucket, or any service speaking its protocol
+#[cfg(feature = "s3")]
+pub struct S3Store {
+    bucket: s3::bucket::Bucket,
+    prefix: String,
+}
+
+#[cfg(feature = "s3")]
+impl S3Store {
+    pub fn new(config: &ObjectStorageConfig) -> Self {
+        use s3::{bucket::Bucket, creds::Credentials, region::Region};
+
+        let credentials = Credentials::default().expect("Unable to find S3 credentials.");
+
+        let bucket = match &config.endpoint {
+            Some(endpoint) => Bucket::new_with_path_style(
+                &config.bucket,
+                Region::Custom {
+                    region: config.region.to_owned(),
+                    endpoint: endpoint.to_owned(),
+                },
+                credentials,
+            ),
+            None => Bucket::new(
+                &config.bucket,
+                config.region.parse().expect("Unknown S3 region."),
+                credentials,
+            ),
+        }
+        .expect("Unable to connect to S3 bucket.");
+
+        Self {
+            bucket,
+            prefix: config.prefix.to_owned(),
+        }
+    }
+}
+
+#[cfg(feature = "s3")]
+impl Debug for S3Store {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "S3Store({}/{})", self.bucket.name, self.prefix)
+    }
+}
+
+#[cfg(feature = "s3")]
+impl ObjectStore for S3Store {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        match self.bucket.get_object(format!("{}{}", self.prefix, key)) {
+            Ok((data, 200)) => Ok(Some(data)),
+            Ok((_, 404)) => Ok(None),
+            Ok((_, code)) => Err(format!("S3 responded with {}.", code)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), String> {
+        match self
+            .bucket
+            .put_object(format!("{}{}", self.prefix, key), data)
+        {
+            Ok((_, code)) if code < 300 => Ok(()),
+            Ok((_, code)) => Err(format!("S3 responded with {}.", code)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), String> {
+        match self.bucket.delete_object(format!("{}{}", self.prefix, key)) {
+            Ok((_, code)) if code < 300 || code == 404 => Ok(()),
+            Ok((_, code)) => Err(format!("S3 responded with {}.", code)),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let pages = self
+            .bucket
+            .list(format!("{}{}", self.prefix, prefix), None)
+            .map_err(|e| e.to_string())?;
+
+        Ok(pages
+            .into_iter()
+            .flat_map(|page| page.contents)
+            .filter_map(|object| object.key.strip_prefix(&self.prefix).map(str::to_owned))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct MemoryStore(Mutex<BTreeMap<String, Vec<u8>>>);
+
+    impl ObjectStore for MemoryStore {
+        fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn put(&self, key: &str, data: &[u8]) -> Result<(), String> {
+            self.0.lock().unwrap().insert(key.to_owned(), data.to_vec());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<(), String> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+
+        fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|key| key.starts_with(prefix))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[test]
+    fn write_back() {
+        let root = std::env::temp_dir().join(format!("mine-objects-{}", std::process::id()));
+        let store = Arc::new(MemoryStore::default());
+
+        let storage = ObjectStorage::new(store.clone(), &root.join("a"), Duration::from_secs(60));
+        storage.put_chunk("0|0", b"chunk");
+
+        // cached locally, uploaded once synced
+        assert!(store.get("chunks/0|0").unwrap().is_none());
+        assert_eq!(storage.chunk_names(), Some(vec!["0|0".to_owned()]));
+        storage.sync();
+        assert_eq!(store.get("chunks/0|0").unwrap(), Some(b"chunk".to_vec()));
+
+        // a fresh container without the cache reads it back from the store
+        let storage = ObjectStorage::new(store.clone(), &root.join("b"), Duration::from_secs(60));
+        assert_eq!(storage.get_chunk("0|0"), Some(b"chunk".to_vec()));
+        assert!(storage.get_chunk("1|0").is_none());
+
+        // removals hide the chunk and its meshes right away, and reach the store once synced
+        storage.put_meshes("0|0", b"meshes");
+        storage.sync();
+        storage.remove_chunk("0|0");
+        assert!(storage.get_chunk("0|0").is_none());
+        assert!(storage.get_meshes("0|0").is_none());
+        assert_eq!(storage.chunk_names(), Some(vec![]));
+        storage.sync();
+        assert!(store.list("").unwrap().is_empty());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
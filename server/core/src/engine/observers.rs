@@ -0,0 +1,159 @@
+use crossbeam_channel::{Sender, TrySendError};
+
+use server_common::{aabb::Aabb, vec::Vec3};
+
+use crate::gen::blocks::{BlockRotation, VoxelState};
+
+use super::block_entities::BlockEntityData;
+
+/// Id of a region observed, to stop observing it
+pub type ObserverId = u32;
+
+/// What changed about a voxel
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// Voxel type set, clearing its rotation and stage
+    Voxel(u32),
+    Rotation(BlockRotation),
+    Stage(u32),
+    State(VoxelState),
+    /// Block entity attached, none if it was removed
+    BlockEntity(Option<BlockEntityData>),
+}
+
+/// A change to a voxel within an observed region
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoxelChange {
+    pub voxel: Vec3<i32>,
+    pub change: Change,
+}
+
+#[derive(Debug)]
+struct Observer {
+    id: ObserverId,
+    /// Region in voxels, a voxel being in it if they overlap
+    region: Aabb,
+    sender: Sender<VoxelChange>,
+}
+
+impl Observer {
+    fn contains(&self, voxel: &Vec3<i32>) -> bool {
+        let &Vec3(vx, vy, vz) = voxel;
+        let Aabb { base, max, .. } = &self.region;
+
+        (vx as f32) < max.0
+            && (vx + 1) as f32 > base.0
+            && (vy as f32) < max.1
+            && (vy + 1) as f32 > base.1
+            && (vz as f32) < max.2
+            && (vz + 1) as f32 > base.2
+    }
+}
+
+/// The regions observed for voxel changes, each streaming the changes within it over a channel
+///
+/// Changes are sent as they're made, without blocking on a full channel. An observer is dropped
+/// once its receiver is, so that nothing has to unsubscribe from a region it's done with.
+#[derive(Debug, Default)]
+pub struct Observers {
+    next_id: ObserverId,
+    observers: Vec<Observer>,
+}
+
+impl Observers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stream the changes to the voxels overlapping a region over a channel
+    pub fn observe(&mut self, region: Aabb, sender: Sender<VoxelChange>) -> ObserverId {
+        self.next_id += 1;
+
+        self.observers.push(Observer {
+            id: self.next_id,
+            region,
+            sender,
+        });
+
+        self.next_id
+    }
+
+    /// Stop observing a region, false if it wasn't observed
+    pub fn unobserve(&mut self, id: ObserverId) -> bool {
+        let count = self.observers.len();
+        self.observers.retain(|observer| observer.id != id);
+        self.observers.len() != count
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// Send a change to the observers of the regions holding its voxel
+    pub fn notify(&mut self, voxel: Vec3<i32>, change: Change) {
+        if self.observers.is_empty() {
+            return;
+        }
+
+        let change = VoxelChange { voxel, change };
+
+        self.observers.retain(|observer| {
+            if !observer.contains(&change.voxel) {
+                return true;
+            }
+
+            // an observer falling behind misses changes rather than holding up the tick
+            !matches!(
+                observer.sender.try_send(change.to_owned()),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::{bounded, unbounded};
+
+    use super::*;
+
+    #[test]
+    fn regions() {
+        let mut observers = Observers::new();
+
+        let (sender, receiver) = unbounded();
+        let id = observers.observe(
+            Aabb::new(&Vec3(0.0, 0.0, 0.0), &Vec3(4.0, 4.0, 4.0)),
+            sender,
+        );
+
+        observers.notify(Vec3(1, 2, 3), Change::Voxel(5));
+        observers.notify(Vec3(4, 2, 3), Change::Stage(1));
+        observers.notify(Vec3(-1, 0, 0), Change::BlockEntity(None));
+
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![VoxelChange {
+                voxel: Vec3(1, 2, 3),
+                change: Change::Voxel(5),
+            }]
+        );
+
+        // a full channel misses changes, a dropped one stops being observed
+        let (full, _kept) = bounded(0);
+        observers.observe(Aabb::new(&Vec3(0.0, 0.0, 0.0), &Vec3(1.0, 1.0, 1.0)), full);
+        let (gone, dropped) = unbounded();
+        observers.observe(Aabb::new(&Vec3(0.0, 0.0, 0.0), &Vec3(1.0, 1.0, 1.0)), gone);
+        drop(dropped);
+
+        observers.notify(Vec3(0, 0, 0), Change::Voxel(1));
+        assert_eq!(observers.len(), 2);
+
+        assert!(observers.unobserve(id));
+        assert!(!observers.unobserve(id));
+    }
+}
@@ -1,13 +1,173 @@
 #![allow(dead_code)]
 
-use std::sync::{Arc, Mutex};
-
 use server_common::{aabb::Aabb, math::approx_equals, vec::Vec3};
-use server_utils::sweep::sweep;
 
 use crate::comp::rigidbody::RigidBody;
 
+use super::{
+    config::ParkourConfig,
+    sweep_recorder::{traced_sweep, SweepHit, SweepKind, Traces},
+};
+
 type TestFunction<'a> = &'a dyn Fn(i32, i32, i32) -> bool;
+type ModifierFunction<'a> = &'a dyn Fn(i32, i32, i32) -> BlockModifiers;
+
+/// Vertical speed of bodies climbing, or sliding down, climbable blocks
+pub const CLIMB_SPEED: f32 = 3.0;
+
+pub const SWIM_SPEED: f32 = 3.0;
+/// How quickly swimmers reach the swimming velocity, per second
+pub const SWIM_RESPONSIVENESS: f32 = 4.0;
+/// Fraction of gravity swimmers still sink by
+pub const SWIM_GRAVITY: f32 = 0.2;
+/// Upwards speed of swimmers climbing out onto a ledge
+pub const STEP_OUT_SPEED: f32 = 6.0;
+
+/// Fraction of gravity gliders fall by when flying level above the stall speed
+pub const GLIDE_GRAVITY: f32 = 0.1;
+/// Horizontal speed below which gliders lose their lift
+pub const GLIDE_STALL_SPEED: f32 = 4.0;
+/// How much of the falling speed of a diving glider turns into forward speed, per second
+pub const GLIDE_DIVE_RATE: f32 = 1.2;
+/// How much of the forward speed of a climbing glider turns into height, per second
+pub const GLIDE_CLIMB_RATE: f32 = 0.8;
+/// Fraction of speed gliders lose to drag, per second
+pub const GLIDE_DRAG: f32 = 0.05;
+/// How quickly gliders turn to face where they look, per second
+pub const GLIDE_RESPONSIVENESS: f32 = 3.0;
+
+/// Share of a wall jump's speed pushing away from the wall, the rest going up
+pub const WALL_JUMP_PUSH: f32 = 0.75;
+/// Distance below the top of a body that it grabs ledges at
+pub const LEDGE_REACH: f32 = 0.5;
+/// Upwards speed of bodies pulling themselves up onto a ledge
+pub const LEDGE_CLIMB_SPEED: f32 = 6.0;
+
+/// Longest distance a body is swept at once, longer moves being split into sub-steps so that
+/// fast bodies don't tunnel through thin walls on the precision lost over a long sweep
+pub const MAX_SWEEP_DISTANCE: f32 = 0.5;
+/// Most sub-steps a single move is split into
+pub const MAX_SUB_STEPS: u32 = 256;
+
+/// Movement input of a swimming body, angles are in radians
+#[derive(Debug, Clone, Default)]
+pub struct SwimInput {
+    /// Walking direction, `None` if no direction is held
+    pub heading: Option<f32>,
+    /// Looking up is positive
+    pub pitch: f32,
+    /// Whether the body is swimming straight up
+    pub up: bool,
+}
+
+/// Look direction of a gliding body, in radians
+#[derive(Debug, Clone, Default)]
+pub struct GlideInput {
+    pub yaw: f32,
+    /// Looking up is positive
+    pub pitch: f32,
+}
+
+/// Movement input of a body against a wall, see `Physics::wall_move`
+#[derive(Debug, Clone, Default)]
+pub struct WallInput {
+    /// Walking direction, `None` if no direction is held
+    pub heading: Option<f32>,
+    /// Whether jump was pressed this tick
+    pub jump: bool,
+    /// Whether jump is held
+    pub climb: bool,
+}
+
+/// What a body did against a wall
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallMove {
+    Slide,
+    Jump,
+    Hang,
+    Climb,
+}
+
+impl WallMove {
+    /// Pose peers are shown for the move, set as the `pose` metadata
+    pub fn pose(self) -> &'static str {
+        match self {
+            WallMove::Slide => "wallSliding",
+            WallMove::Jump => "wallJumping",
+            WallMove::Hang => "hanging",
+            WallMove::Climb => "climbing",
+        }
+    }
+}
+
+/// The voxel just past a body along an axis it's blocked along, at a height
+fn voxel_past(b: &RigidBody, axis: usize, dir: f32, vy: i32) -> Vec3<i32> {
+    let mut voxel = Vec3(
+        (b.aabb.base.0 + b.aabb.vec.0 / 2.0).floor() as i32,
+        vy,
+        (b.aabb.base.2 + b.aabb.vec.2 / 2.0).floor() as i32,
+    );
+    voxel[axis] = if dir > 0.0 {
+        (b.aabb.max[axis] - 0.001).floor() as i32 + 1
+    } else {
+        (b.aabb.base[axis] + 0.001).floor() as i32 - 1
+    };
+    voxel
+}
+
+/// How a block alters the movement of the bodies on or in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockModifiers {
+    pub climbable: bool,
+    pub speed: f32,
+    pub friction: f32,
+}
+
+impl Default for BlockModifiers {
+    fn default() -> Self {
+        Self {
+            climbable: false,
+            speed: 1.0,
+            friction: 1.0,
+        }
+    }
+}
+
+impl BlockModifiers {
+    /// Combine the modifiers of the blocks a body overlaps with and of the block beneath it
+    ///
+    /// Climbing and slowing come from any overlapped block, friction from the block beneath.
+    pub fn around(aabb: &Aabb, get_modifiers: ModifierFunction) -> Self {
+        let min = Vec3(
+            aabb.base.0.floor() as i32,
+            aabb.base.1.floor() as i32,
+            aabb.base.2.floor() as i32,
+        );
+        let max = Vec3(
+            aabb.max.0.ceil() as i32,
+            aabb.max.1.ceil() as i32,
+            aabb.max.2.ceil() as i32,
+        );
+
+        let mut combined = Self::default();
+
+        for vx in min.0..max.0 {
+            for vz in min.2..max.2 {
+                for vy in min.1..max.1 {
+                    let modifiers = get_modifiers(vx, vy, vz);
+                    combined.climbable |= modifiers.climbable;
+                    combined.speed = combined.speed.min(modifiers.speed);
+                }
+
+                let below = get_modifiers(vx, (aabb.base.1 - 0.01).floor() as i32, vz);
+                combined.speed = combined.speed.min(below.speed);
+                combined.friction = combined.friction.min(below.friction);
+            }
+        }
+
+        combined
+    }
+}
 
 /// Options to spawn in a body
 pub struct BodyOptions<'a> {
@@ -21,15 +181,33 @@ pub struct BodyOptions<'a> {
 }
 
 /// Options to add physics to body
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct PhysicsOptions {
     pub gravity: Vec3<f32>,
+    /// Fastest speed bodies fall at, 0 for no limit
+    pub terminal_velocity: f32,
+    /// Multiplier of the friction of every body
+    pub friction: f32,
     pub min_bounce_impulse: f32,
     pub air_drag: f32,
     pub fluid_drag: f32,
     pub fluid_density: f32,
 }
 
+impl Default for PhysicsOptions {
+    fn default() -> Self {
+        Self {
+            gravity: Vec3::default(),
+            terminal_velocity: 0.0,
+            friction: 1.0,
+            min_bounce_impulse: 0.0,
+            air_drag: 0.0,
+            fluid_drag: 0.0,
+            fluid_density: 0.0,
+        }
+    }
+}
+
 /// Resource for operating physics on bodies
 #[derive(Default)]
 pub struct Physics {
@@ -48,6 +226,32 @@ impl Physics {
         dt: f32,
         test_solid: TestFunction,
         test_fluid: TestFunction,
+        get_modifiers: ModifierFunction,
+    ) {
+        self.iterate(b, dt, test_solid, test_fluid, get_modifiers, None);
+    }
+
+    /// Process the physics on a certain body, tracing each of its sweeps
+    pub fn iterate_body_traced(
+        &self,
+        b: &mut RigidBody,
+        dt: f32,
+        test_solid: TestFunction,
+        test_fluid: TestFunction,
+        get_modifiers: ModifierFunction,
+        traces: &Traces,
+    ) {
+        self.iterate(b, dt, test_solid, test_fluid, get_modifiers, Some(traces));
+    }
+
+    fn iterate(
+        &self,
+        b: &mut RigidBody,
+        dt: f32,
+        test_solid: TestFunction,
+        test_fluid: TestFunction,
+        get_modifiers: ModifierFunction,
+        traces: Option<&Traces>,
     ) {
         let no_gravity = approx_equals(&0.0, &self.options.gravity.len().powi(2));
 
@@ -67,7 +271,7 @@ impl Physics {
 
         // skip bodies if static or no velocity/forces/impulses
         let local_no_grav = no_gravity || approx_equals(&b.gravity_multiplier, &0.0);
-        if self.body_asleep(b, &dt, &local_no_grav, &test_solid) {
+        if self.body_asleep(b, &dt, &local_no_grav, &test_solid, traces) {
             return;
         }
         b.sleep_frame_count -= 1;
@@ -75,6 +279,8 @@ impl Physics {
         // check if under water, if so apply buoyancy and drag forces
         self.apply_fluid_forces(b, &test_fluid);
 
+        let modifiers = BlockModifiers::around(&b.aabb, get_modifiers);
+
         // semi-implicit Euler integration
 
         // a = f/m + gravity * gravity_multiplier
@@ -90,10 +296,11 @@ impl Physics {
         b.velocity = b.velocity.add(&dv);
 
         // apply friction based on change in velocity this frame
-        if !approx_equals(&b.friction, &0.0) {
-            self.apply_friction_by_axis(0, b, &dv);
-            self.apply_friction_by_axis(1, b, &dv);
-            self.apply_friction_by_axis(2, b, &dv);
+        let friction = b.friction * modifiers.friction * self.options.friction;
+        if !approx_equals(&friction, &0.0) {
+            self.apply_friction_by_axis(0, b, &dv, friction);
+            self.apply_friction_by_axis(1, b, &dv, friction);
+            self.apply_friction_by_axis(2, b, &dv, friction);
         }
 
         // linear air or fluid friction - effectively v *= drag;
@@ -114,6 +321,24 @@ impl Physics {
         let mult = (1.0 - (drag * dt) / b.mass).max(0.0);
         b.velocity = b.velocity.scale(mult);
 
+        b.velocity.0 *= modifiers.speed;
+        b.velocity.2 *= modifiers.speed;
+
+        if self.options.terminal_velocity > 0.0 {
+            b.velocity.1 = b.velocity.1.max(-self.options.terminal_velocity);
+        }
+
+        // climb while pushing against a wall, otherwise slide down slowly
+        if modifiers.climbable {
+            let pushing =
+                !approx_equals(&b.resting[0], &0.0) || !approx_equals(&b.resting[2], &0.0);
+            b.velocity.1 = if pushing {
+                CLIMB_SPEED
+            } else {
+                b.velocity.1.max(-CLIMB_SPEED)
+            };
+        }
+
         // x1-x0 = v1*dt
         let dx = b.velocity.scale(dt);
 
@@ -129,12 +354,20 @@ impl Physics {
         };
 
         // sweeps aabb along dx and accounts for collisions
-        self.process_collisions(&mut b.aabb, &dx, &mut b.resting, &test_solid);
+        b.contacts.clear();
+        self.sweep_body(
+            &mut b.aabb,
+            &dx,
+            &mut b.resting,
+            &mut b.contacts,
+            &test_solid,
+            traces,
+        );
 
         // if autostep, and on ground, run collisions again with stepped up aabb
         if b.auto_step {
             let mut tmp_box = tmp_box.unwrap();
-            self.try_auto_stepping(b, &mut tmp_box, &dx, &test_solid);
+            self.try_auto_stepping(b, &mut tmp_box, &dx, &test_solid, traces);
         }
 
         let mut impacts = Vec3::default();
@@ -174,6 +407,164 @@ impl Physics {
         }
     }
 
+    /// Steer a body that's in fluid ahead of its next iteration
+    ///
+    /// Input maps to a velocity in 3D along the look direction, with gravity mostly cancelled
+    /// out. Swimmers pushing against a ledge at the surface get boosted up out of the fluid.
+    /// Returns false if the body isn't in fluid.
+    pub fn swim(
+        &self,
+        b: &mut RigidBody,
+        dt: f32,
+        input: &SwimInput,
+        test_solid: TestFunction,
+    ) -> bool {
+        if !b.in_fluid {
+            return false;
+        }
+
+        let mut target = match input.heading {
+            Some(heading) => Vec3(
+                heading.sin() * input.pitch.cos(),
+                input.pitch.sin(),
+                heading.cos() * input.pitch.cos(),
+            )
+            .scale(SWIM_SPEED),
+            None => Vec3::default(),
+        };
+        if input.up {
+            target.1 = SWIM_SPEED;
+        }
+
+        let blend = (SWIM_RESPONSIVENESS * dt).min(1.0);
+        b.velocity = b.velocity.add(&target.sub(&b.velocity).scale(blend));
+
+        // cancel out most of the gravity
+        let lift = -(1.0 - SWIM_GRAVITY) * b.mass * b.gravity_multiplier;
+        b.apply_force(&self.options.gravity.scale(lift));
+
+        // only partly submerged means the body is at the surface
+        if b.ratio_in_fluid < 1.0 && input.heading.is_some() {
+            let vy = b.aabb.base.1.floor() as i32;
+
+            for axis in [0, 2].iter().copied() {
+                let dir = b.resting[axis];
+                if approx_equals(&dir, &0.0) {
+                    continue;
+                }
+
+                let wall = voxel_past(b, axis, dir, vy);
+
+                if test_solid(wall.0, wall.1, wall.2) && !test_solid(wall.0, wall.1 + 1, wall.2) {
+                    b.velocity.1 = b.velocity.1.max(STEP_OUT_SPEED);
+                    break;
+                }
+            }
+        }
+
+        b.mark_active();
+
+        true
+    }
+
+    /// Steer a gliding body ahead of its next iteration
+    ///
+    /// Lift cancels most of the gravity while flying level, and fades as the glider pitches up or
+    /// down. Diving trades height for forward speed, climbing the other way around, and gliders
+    /// slower than `GLIDE_STALL_SPEED` stall and drop. Returns false once the body lands or
+    /// falls in fluid, ending the glide.
+    pub fn glide(&self, b: &mut RigidBody, dt: f32, input: &GlideInput) -> bool {
+        if b.in_fluid || b.at_rest_y() < 0.0 {
+            return false;
+        }
+
+        let GlideInput { yaw, pitch } = *input;
+        let level = pitch.cos().powi(2);
+        let mut speed = (b.velocity.0.powi(2) + b.velocity.2.powi(2)).sqrt();
+
+        if speed >= GLIDE_STALL_SPEED {
+            let lift = -(1.0 - GLIDE_GRAVITY) * level * b.mass * b.gravity_multiplier;
+            b.apply_force(&self.options.gravity.scale(lift));
+        }
+
+        if pitch < 0.0 && b.velocity.1 < 0.0 {
+            let dive = -b.velocity.1 * GLIDE_DIVE_RATE * (1.0 - level) * dt;
+            b.velocity.1 += dive;
+            speed += dive;
+        } else if pitch > 0.0 {
+            let climb = speed * pitch.sin() * GLIDE_CLIMB_RATE * dt;
+            b.velocity.1 += climb;
+            speed -= climb;
+        }
+
+        let target = Vec3(yaw.sin() * speed, b.velocity.1, yaw.cos() * speed);
+        let blend = (GLIDE_RESPONSIVENESS * dt).min(1.0);
+        b.velocity = b.velocity.add(&target.sub(&b.velocity).scale(blend));
+        b.velocity = b.velocity.scale((1.0 - GLIDE_DRAG * dt).max(0.0));
+
+        b.mark_active();
+
+        true
+    }
+
+    /// Move a body pushing against a wall in mid-air by the parkour moves the world allows
+    ///
+    /// Jumping pushes the body off the wall, falling next to the top of the wall grabs onto it
+    /// with jump pulling the body up, and otherwise falling slides down the wall slowed by
+    /// friction. Returns the move made, none if the body isn't against a wall.
+    pub fn wall_move(
+        &self,
+        b: &mut RigidBody,
+        dt: f32,
+        input: &WallInput,
+        options: &ParkourConfig,
+        test_solid: TestFunction,
+    ) -> Option<WallMove> {
+        if b.in_fluid || b.at_rest_y() < 0.0 {
+            return None;
+        }
+
+        let normal = b.wall_normal()?;
+        let heading = input.heading?;
+        if heading.sin() * normal.0 + heading.cos() * normal.2 >= 0.0 {
+            return None;
+        }
+
+        if input.jump && options.wall_jump_impulse > 0.0 {
+            let impulse = options.wall_jump_impulse;
+            b.velocity = normal.scale(impulse * WALL_JUMP_PUSH);
+            b.velocity.1 = impulse;
+            b.mark_active();
+            return Some(WallMove::Jump);
+        }
+
+        if options.ledge_grab && b.velocity.1 <= 0.0 {
+            let axis = if approx_equals(&normal.0, &0.0) { 2 } else { 0 };
+            let hands = (b.aabb.max.1 - LEDGE_REACH).floor() as i32;
+            let wall = voxel_past(b, axis, -normal[axis], hands);
+
+            if test_solid(wall.0, wall.1, wall.2) && !test_solid(wall.0, wall.1 + 1, wall.2) {
+                if input.climb {
+                    b.velocity.1 = LEDGE_CLIMB_SPEED;
+                    b.mark_active();
+                    return Some(WallMove::Climb);
+                }
+
+                b.velocity.1 = 0.0;
+                b.apply_force(&self.options.gravity.scale(-b.mass * b.gravity_multiplier));
+                return Some(WallMove::Hang);
+            }
+        }
+
+        if options.wall_slide_friction > 0.0 && b.velocity.1 < 0.0 {
+            b.velocity.1 *= (1.0 - options.wall_slide_friction * dt).max(0.0);
+            b.mark_active();
+            return Some(WallMove::Slide);
+        }
+
+        None
+    }
+
     fn apply_fluid_forces(&self, body: &mut RigidBody, test_fluid: TestFunction) {
         let aabb = &body.aabb;
         let cx = aabb.base[0].floor() as i32;
@@ -213,7 +604,13 @@ impl Physics {
         body.ratio_in_fluid = ratio_in_fluid;
     }
 
-    fn apply_friction_by_axis(&self, axis: usize, body: &mut RigidBody, dvel: &Vec3<f32>) {
+    fn apply_friction_by_axis(
+        &self,
+        axis: usize,
+        body: &mut RigidBody,
+        dvel: &Vec3<f32>,
+        friction: f32,
+    ) {
         // friction applies only if moving into a touched surface
         let rest_dir = body.resting[axis];
         let v_normal = dvel[axis];
@@ -238,7 +635,7 @@ impl Physics {
         //        dvF = dt * Ff / m
         //            = dt * (u * m * dvnormal / dt) / m
         //            = u * dvnormal
-        let dv_max = (body.friction * v_normal).abs();
+        let dv_max = (friction * v_normal).abs();
 
         // decrease lateral vel by dv_max (or clamp to zero)
         let scaler = if v_curr > dv_max {
@@ -251,37 +648,76 @@ impl Physics {
         body.velocity[(axis + 2) % 3] *= scaler;
     }
 
+    /// Sweep a body along its move, in sub-steps of up to `MAX_SWEEP_DISTANCE` if it's long
+    ///
+    /// An axis blocked in a sub-step stays blocked for the ones after, and the body rests
+    /// against everything it ran into along the way.
+    fn sweep_body(
+        &self,
+        aabb: &mut Aabb,
+        dx: &Vec3<f32>,
+        resting: &mut Vec3<f32>,
+        contacts: &mut Vec<Vec3<f32>>,
+        test_solid: TestFunction,
+        traces: Option<&Traces>,
+    ) {
+        let steps = (dx.len() / MAX_SWEEP_DISTANCE)
+            .ceil()
+            .clamp(1.0, MAX_SUB_STEPS as f32) as u32;
+
+        if steps == 1 {
+            let (_, hits) = self.process_collisions(aabb, dx, resting, test_solid, traces);
+            add_contacts(contacts, &hits);
+            return;
+        }
+
+        let mut step = dx.scale(1.0 / steps as f32);
+        let mut blocked = Vec3::<f32>::default();
+
+        for _ in 0..steps {
+            let (_, hits) = self.process_collisions(aabb, &step, resting, test_solid, traces);
+            add_contacts(contacts, &hits);
+
+            // every hit blocks its axis, not only the last one the body rests against, for a
+            // body stopped flush against a wall not to start the next sub-step past its face
+            for hit in &hits {
+                blocked[hit.axis] = hit.dir as f32;
+                step[hit.axis] = 0.0;
+            }
+
+            if approx_equals(&step.len(), &0.0) {
+                break;
+            }
+        }
+
+        *resting = blocked;
+    }
+
     fn process_collisions(
         &self,
         aabb: &mut Aabb,
         velocity: &Vec3<f32>,
         resting: &mut Vec3<f32>,
         test_solid: TestFunction,
-    ) -> f32 {
+        traces: Option<&Traces>,
+    ) -> (f32, Vec<SweepHit>) {
         resting.set(0.0, 0.0, 0.0);
 
-        // temporarily set axis to something impossible, just for safety
-        let d = Arc::new(Mutex::new((10, 0)));
-        let temp = d.clone();
-
-        let dist = sweep(
+        let (dist, hits) = traced_sweep(
+            SweepKind::Collide,
             test_solid,
             aabb,
             velocity,
-            &mut move |_, axis: usize, dir: i32, vec: &mut Vec3<f32>| {
-                *temp.lock().unwrap() = (axis, dir);
-                vec[axis] = 0.0;
-                false
-            },
             false,
+            traces,
         );
 
-        let (axis, dir) = *d.lock().unwrap();
-        if axis != 10 {
-            resting[axis] = dir as f32;
+        // the body rests against what it ran into last
+        if let Some(hit) = hits.last() {
+            resting[hit.axis] = hit.dir as f32;
         }
 
-        dist
+        (dist, hits)
     }
 
     fn try_auto_stepping(
@@ -290,6 +726,7 @@ impl Physics {
         old_aabb: &mut Aabb,
         dx: &Vec3<f32>,
         test_solid: TestFunction,
+        traces: Option<&Traces>,
     ) {
         // in the air
         if b.resting[1] >= 0.0 && !b.in_fluid {
@@ -314,36 +751,20 @@ impl Physics {
         let target_pos = old_aabb.base.add(&dx);
 
         // move towards the target until the first x/z collision
-        sweep(
-            test_solid,
-            old_aabb,
-            dx,
-            &mut move |_, axis, _, vec| {
-                if axis == 1 {
-                    vec[axis] = 0.0;
-                    return false;
-                }
-                true
-            },
-            false,
-        );
+        traced_sweep(SweepKind::Approach, test_solid, old_aabb, dx, false, traces);
 
         let y = b.aabb.base[1];
         let y_dist = (y + 1.001).floor() - y;
         let upvec = Vec3(0.0, y_dist, 0.0);
-        let collided = Arc::new(Mutex::new(false));
-        let temp = collided.clone();
-        sweep(
+        let (_, hits) = traced_sweep(
+            SweepKind::Probe,
             test_solid,
             old_aabb,
             &upvec,
-            &mut move |_, _, _, _| {
-                *temp.lock().unwrap() = true;
-                true
-            },
             false,
+            traces,
         );
-        if *collided.lock().unwrap() {
+        if !hits.is_empty() {
             return;
         }
 
@@ -351,7 +772,7 @@ impl Physics {
         let mut leftover = target_pos.sub(&old_aabb.base);
         leftover[1] = 0.0;
         let mut tmp_resting = Vec3::default();
-        self.process_collisions(old_aabb, &leftover, &mut tmp_resting, test_solid);
+        self.process_collisions(old_aabb, &leftover, &mut tmp_resting, test_solid, traces);
 
         // bail if no movement happened in the originally blocked direction
         if x_blocked && !approx_equals(&old_aabb.base[0], &target_pos[0]) {
@@ -374,6 +795,7 @@ impl Physics {
         dt: &f32,
         no_gravity: &bool,
         test_solid: TestFunction,
+        traces: Option<&Traces>,
     ) -> bool {
         if body.sleep_frame_count > 0 {
             return false;
@@ -390,21 +812,270 @@ impl Physics {
         let g_mult = 0.5 * dt * dt * body.gravity_multiplier;
         let sleep_vec = self.options.gravity.scale(g_mult);
 
-        let is_resting = Arc::new(Mutex::new(false));
-        let temp = is_resting.clone();
-
-        sweep(
+        let (_, hits) = traced_sweep(
+            SweepKind::Probe,
             test_solid,
             &mut body.aabb,
             &sleep_vec,
-            &mut move |_, _, _, _| {
-                *temp.lock().unwrap() = true;
-                true
-            },
             true,
+            traces,
+        );
+
+        !hits.is_empty()
+    }
+}
+
+/// Add the normals of the voxel faces a sweep ran into, once each
+fn add_contacts(contacts: &mut Vec<Vec3<f32>>, hits: &[SweepHit]) {
+    for hit in hits {
+        let mut normal = Vec3::default();
+        normal[hit.axis] = -hit.dir as f32;
+
+        if !contacts.contains(&normal) {
+            contacts.push(normal);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_modifiers() {
+        // ice at y = -1, a ladder at x = 1
+        let get_modifiers = |x: i32, y: i32, _: i32| BlockModifiers {
+            climbable: x == 1 && y >= 0,
+            speed: 1.0,
+            friction: if y == -1 { 0.1 } else { 1.0 },
+        };
+
+        let on_ice = Aabb::new(&Vec3(0.2, 0.0, 0.2), &Vec3(0.6, 1.8, 0.6));
+        let modifiers = BlockModifiers::around(&on_ice, &get_modifiers);
+        assert!(!modifiers.climbable);
+        assert_eq!(modifiers.friction, 0.1);
+
+        let on_ladder = Aabb::new(&Vec3(0.6, 0.5, 0.2), &Vec3(0.6, 1.8, 0.6));
+        assert!(BlockModifiers::around(&on_ladder, &get_modifiers).climbable);
+    }
+
+    #[test]
+    fn fast_bodies() {
+        let physics = Physics::new(PhysicsOptions::default());
+        let no_fluid = |_: i32, _: i32, _: i32| false;
+        let get_modifiers = |_: i32, _: i32, _: i32| BlockModifiers::default();
+
+        // walls a voxel thin at x = 40 and z = -30
+        let test_solid = |x: i32, _: i32, z: i32| x == 40 || z == -30;
+
+        for i in 0..200 {
+            let offset = i as f32 * 0.173;
+            let mut body = RigidBody::new(
+                Aabb::new(
+                    &Vec3(offset % 1.0, offset % 7.0, offset % 3.0),
+                    &Vec3(0.6, 1.8, 0.6),
+                ),
+                1.5,
+                1.0,
+                0.0,
+                0.0,
+                1.0,
+                false,
+            );
+
+            // thousands of blocks a second, some at an angle
+            let speed = 1000.0 + 40.0 * i as f32;
+            let angle = (i % 10) as f32 * 0.1;
+            body.velocity = Vec3(speed * angle.cos(), 0.0, -speed * angle.sin() * 0.5);
+
+            for _ in 0..10 {
+                physics.iterate_body(&mut body, 0.05, &test_solid, &no_fluid, &get_modifiers);
+            }
+
+            assert!(body.aabb.max.0 <= 40.0 + 1e-3, "body {} went through x", i);
+            assert!(
+                body.aabb.base.2 >= -29.0 - 1e-3,
+                "body {} went through z",
+                i
+            );
+        }
+    }
+
+    #[test]
+    fn glide() {
+        let physics = Physics::new(PhysicsOptions {
+            gravity: Vec3(0.0, -24.0, 0.0),
+            ..Default::default()
+        });
+        let test_solid = |_: i32, y: i32, _: i32| y < 0;
+        let no_fluid = |_: i32, _: i32, _: i32| false;
+        let get_modifiers = |_: i32, _: i32, _: i32| BlockModifiers::default();
+
+        let launch = |velocity: Vec3<f32>| {
+            let mut body = RigidBody::new(
+                Aabb::new(&Vec3(0.2, 60.0, 0.2), &Vec3(0.6, 1.8, 0.6)),
+                1.5,
+                1.0,
+                0.0,
+                0.0,
+                1.0,
+                false,
+            );
+            body.velocity = velocity;
+            body
+        };
+
+        let level = GlideInput::default();
+        let fly = |body: &mut RigidBody, input: &GlideInput, ticks: usize| {
+            for _ in 0..ticks {
+                if !physics.glide(body, 0.05, input) {
+                    return false;
+                }
+                physics.iterate_body(body, 0.05, &test_solid, &no_fluid, &get_modifiers);
+            }
+            true
+        };
+
+        // fast gliders fall far slower than stalled ones
+        let mut fast = launch(Vec3(0.0, 0.0, 10.0));
+        let mut stalled = launch(Vec3::default());
+        fly(&mut fast, &level, 20);
+        fly(&mut stalled, &level, 20);
+        assert!(fast.aabb.base.1 > stalled.aabb.base.1 + 5.0);
+        assert!(fast.aabb.base.2 > 5.0);
+
+        // diving gains speed
+        let mut diving = launch(Vec3(0.0, -10.0, 10.0));
+        let dive = GlideInput {
+            pitch: -0.8,
+            ..Default::default()
+        };
+        fly(&mut diving, &dive, 10);
+        assert!(diving.velocity.2 > 10.0);
+
+        // the glide ends on landing
+        assert!(!fly(&mut stalled, &level, 200));
+    }
+
+    #[test]
+    fn wall_moves() {
+        let physics = Physics::new(PhysicsOptions {
+            gravity: Vec3(0.0, -24.0, 0.0),
+            ..Default::default()
+        });
+        let options = ParkourConfig {
+            wall_slide_friction: 5.0,
+            wall_jump_impulse: 8.0,
+            ledge_grab: true,
+        };
+        let no_fluid = |_: i32, _: i32, _: i32| false;
+        let get_modifiers = |_: i32, _: i32, _: i32| BlockModifiers::default();
+
+        // a wall at x = 1, up to y = 9
+        let test_solid = |x: i32, y: i32, _: i32| x == 1 && y < 10;
+
+        // fall into the wall from a height
+        let against_wall = |y: f32| {
+            let mut body = RigidBody::new(
+                Aabb::new(&Vec3(0.3, y, 0.2), &Vec3(0.6, 1.8, 0.6)),
+                1.5,
+                1.0,
+                0.0,
+                0.0,
+                1.0,
+                false,
+            );
+            body.velocity = Vec3(4.0, -5.0, 0.0);
+            physics.iterate_body(&mut body, 0.05, &test_solid, &no_fluid, &get_modifiers);
+            body
+        };
+
+        let pushing = WallInput {
+            heading: Some(std::f32::consts::FRAC_PI_2),
+            ..Default::default()
+        };
+
+        let mut body = against_wall(3.0);
+        assert_eq!(body.wall_normal(), Some(Vec3(-1.0, 0.0, 0.0)));
+        let falling = body.velocity.1;
+        assert_eq!(
+            physics.wall_move(&mut body, 0.05, &pushing, &options, &test_solid),
+            Some(WallMove::Slide)
         );
+        assert!(body.velocity.1 > falling);
+
+        let jump = WallInput {
+            jump: true,
+            ..pushing.clone()
+        };
+        assert_eq!(
+            physics.wall_move(&mut body, 0.05, &jump, &options, &test_solid),
+            Some(WallMove::Jump)
+        );
+        assert!(body.velocity.0 < 0.0 && body.velocity.1 > 0.0);
+
+        // the top of the body reaches past the top of the wall
+        let mut body = against_wall(8.6);
+        assert_eq!(
+            physics.wall_move(&mut body, 0.05, &pushing, &options, &test_solid),
+            Some(WallMove::Hang)
+        );
+        assert_eq!(body.velocity.1, 0.0);
+
+        // nothing happens pushing away from the wall, or with the moves off
+        let away = WallInput {
+            heading: Some(-std::f32::consts::FRAC_PI_2),
+            ..Default::default()
+        };
+        assert_eq!(
+            physics.wall_move(&mut body, 0.05, &away, &options, &test_solid),
+            None
+        );
+        assert_eq!(
+            physics.wall_move(
+                &mut body,
+                0.05,
+                &pushing,
+                &ParkourConfig::default(),
+                &test_solid
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn swim() {
+        let physics = Physics::new(PhysicsOptions {
+            gravity: Vec3(0.0, -24.0, 0.0),
+            ..Default::default()
+        });
+
+        // a ledge at z = 1 with nothing on top
+        let test_solid = |_: i32, y: i32, z: i32| z == 1 && y == 0;
+        let mut body = RigidBody::new(
+            Aabb::new(&Vec3(0.2, 0.1, 0.2), &Vec3(0.6, 1.8, 0.6)),
+            1.5,
+            1.0,
+            1.0,
+            0.0,
+            1.0,
+            false,
+        );
+        let input = SwimInput {
+            heading: Some(0.0),
+            ..Default::default()
+        };
+
+        assert!(!physics.swim(&mut body, 0.1, &input, &test_solid));
+
+        body.in_fluid = true;
+        body.ratio_in_fluid = 0.5;
+        assert!(physics.swim(&mut body, 0.1, &input, &test_solid));
+        assert!(body.velocity.2 > 0.0);
+        assert!(body.forces.1 > 0.0);
 
-        let result = *is_resting.lock().unwrap();
-        result
+        body.resting[2] = 1.0;
+        physics.swim(&mut body, 0.1, &input, &test_solid);
+        assert_eq!(body.velocity.1, STEP_OUT_SPEED);
     }
 }
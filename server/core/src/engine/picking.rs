@@ -0,0 +1,216 @@
+use serde::Deserialize;
+
+use server_common::{math::approx_equals, types::GetVoxel, vec::Vec3};
+use server_utils::raycast;
+
+/// How far players can target blocks from, in blocks
+pub const PICK_REACH: f32 = 8.0;
+
+/// How far players in creative mode can edit blocks from by default, in blocks
+pub const CREATIVE_REACH: f32 = 16.0;
+
+/// A block a player is looking at, and the face the look ray enters it through
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockTarget {
+    pub voxel: Vec3<i32>,
+    /// Outward normal of the face, where a block placed against it would go
+    pub face: Vec3<i32>,
+}
+
+/// Cast a look ray through voxel space, returning the first pickable block within reach
+///
+/// The origin is in voxel units, such as a head position divided by the world's dimension.
+pub fn pick(
+    pickable: GetVoxel,
+    origin: &Vec3<f32>,
+    direction: &Vec3<f32>,
+    reach: f32,
+) -> Option<BlockTarget> {
+    if approx_equals(&direction.len(), &0.0) {
+        return None;
+    }
+
+    let mut hit_pos = Vec3::default();
+    let mut hit_norm = Vec3::default();
+
+    if !raycast::trace(
+        reach,
+        pickable,
+        &mut origin.to_owned(),
+        &mut direction.to_owned(),
+        &mut hit_pos,
+        &mut hit_norm,
+    ) {
+        return None;
+    }
+
+    // the hit lies on the face, step half a block inwards to land in the voxel
+    let inwards = |hit: f32, norm: i32| (hit - norm as f32 * 0.5).floor() as i32;
+
+    Some(BlockTarget {
+        voxel: Vec3(
+            inwards(hit_pos.0, hit_norm.0),
+            inwards(hit_pos.1, hit_norm.1),
+            inwards(hit_pos.2, hit_norm.2),
+        ),
+        face: hit_norm,
+    })
+}
+
+/// What block edits sent by players are checked against
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditRules {
+    /// How far from their head players can break and place blocks, in blocks
+    #[serde(default = "default_reach")]
+    pub reach: f32,
+    /// Reject edits hidden behind other blocks
+    #[serde(default = "default_line_of_sight")]
+    pub line_of_sight: bool,
+    /// Every player edits in creative mode
+    #[serde(default)]
+    pub creative: bool,
+    /// Names of the players editing in creative mode
+    #[serde(default)]
+    pub creative_players: Vec<String>,
+    /// Reach in creative mode, which skips the line of sight check
+    #[serde(default = "default_creative_reach")]
+    pub creative_reach: f32,
+}
+
+fn default_reach() -> f32 {
+    PICK_REACH
+}
+
+fn default_line_of_sight() -> bool {
+    true
+}
+
+fn default_creative_reach() -> f32 {
+    CREATIVE_REACH
+}
+
+impl Default for EditRules {
+    fn default() -> Self {
+        Self {
+            reach: default_reach(),
+            line_of_sight: default_line_of_sight(),
+            creative: false,
+            creative_players: vec![],
+            creative_reach: default_creative_reach(),
+        }
+    }
+}
+
+impl EditRules {
+    /// Whether a player is in creative mode
+    pub fn is_creative(&self, name: Option<&str>) -> bool {
+        self.creative
+            || name.is_some_and(|name| self.creative_players.iter().any(|player| player == name))
+    }
+
+    /// Whether a player with its head at `head`, in voxel units, can edit a voxel
+    pub fn allows(
+        &self,
+        solid: GetVoxel,
+        head: &Vec3<f32>,
+        voxel: &Vec3<i32>,
+        creative: bool,
+    ) -> bool {
+        let reach = if creative {
+            self.creative_reach
+        } else {
+            self.reach
+        };
+
+        // closest point of the voxel to the head
+        let closest = Vec3(
+            head.0.max(voxel.0 as f32).min(voxel.0 as f32 + 1.0),
+            head.1.max(voxel.1 as f32).min(voxel.1 as f32 + 1.0),
+            head.2.max(voxel.2 as f32).min(voxel.2 as f32 + 1.0),
+        );
+
+        if closest.sub(head).len() > reach {
+            return false;
+        }
+
+        if creative || !self.line_of_sight {
+            return true;
+        }
+
+        let center = Vec3(
+            voxel.0 as f32 + 0.5,
+            voxel.1 as f32 + 0.5,
+            voxel.2 as f32 + 0.5,
+        );
+        let direction = center.sub(head);
+
+        // only the voxel itself can be hit on the way in, which is at least half a block before
+        // its center
+        match pick(solid, head, &direction, (direction.len() - 0.5).max(0.0)) {
+            Some(hit) => hit.voxel == *voxel,
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets() {
+        // a floor at y = 0 and a wall at x = 3
+        let solid = |x: i32, y: i32, _z: i32| y == 0 || x == 3;
+
+        let down = pick(
+            &solid,
+            &Vec3(0.5, 2.5, 0.5),
+            &Vec3(0.0, -1.0, 0.0),
+            PICK_REACH,
+        );
+        assert_eq!(
+            down,
+            Some(BlockTarget {
+                voxel: Vec3(0, 0, 0),
+                face: Vec3(0, 1, 0),
+            })
+        );
+
+        let ahead = pick(
+            &solid,
+            &Vec3(0.5, 2.5, 0.5),
+            &Vec3(1.0, 0.0, 0.0),
+            PICK_REACH,
+        );
+        assert_eq!(
+            ahead,
+            Some(BlockTarget {
+                voxel: Vec3(3, 2, 0),
+                face: Vec3(-1, 0, 0),
+            })
+        );
+
+        assert_eq!(
+            pick(
+                &solid,
+                &Vec3(0.5, 2.5, 0.5),
+                &Vec3(-1.0, 0.0, 0.0),
+                PICK_REACH
+            ),
+            None
+        );
+
+        let rules = EditRules::default();
+        let head = Vec3(0.5, 2.5, 0.5);
+
+        assert!(rules.allows(&solid, &head, &Vec3(3, 2, 0), false));
+        assert!(rules.allows(&solid, &head, &Vec3(1, 1, 0), false));
+        // behind the wall, and out of reach
+        assert!(!rules.allows(&solid, &head, &Vec3(4, 2, 0), false));
+        assert!(!rules.allows(&solid, &head, &Vec3(-12, 1, 0), false));
+
+        assert!(!rules.is_creative(None));
+        assert!(rules.allows(&solid, &head, &Vec3(4, 2, 0), true));
+    }
+}
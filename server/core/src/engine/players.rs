@@ -1,17 +1,53 @@
 use std::{
-    collections::{HashMap, VecDeque},
-    usize,
+    collections::{HashMap, HashSet, VecDeque},
+    net::IpAddr,
+    time::Instant,
 };
 
 use actix::Recipient;
+use serde::{Deserialize, Serialize};
 use specs::Entity;
 
-use super::super::network::{message, models::messages};
+use super::{
+    debug::DebugOptions, forms::Form, i18n::Translations, items::ItemStack, teams::Teams,
+    trading::TradeWindow, transaction::EditHistory,
+};
+
+use super::super::network::{
+    message,
+    models::{messages, PlayerListProtocol},
+};
+
+use server_common::vec::{Vec2, Vec3};
 
-use server_common::vec::Vec2;
+use super::picking::BlockTarget;
 
 pub type PlayerUpdates = HashMap<usize, messages::Peer>;
 
+/// What's kept of a named player between sessions, in the players of the world's storage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerData {
+    pub position: [f32; 3],
+    pub rotation: [f32; 4],
+    pub health: f32,
+    /// URL of the skin uploaded last
+    #[serde(default)]
+    pub skin: Option<String>,
+}
+
+/// Most messages kept for a detached player, past which it's too far behind to resume
+pub const MAX_OUTBOX: usize = 4096;
+
+/// A player whose connection dropped, kept in the world in case the client resumes
+#[derive(Debug)]
+pub struct Detached {
+    /// When the player is removed unless resumed
+    pub deadline: Instant,
+    /// Messages sent to the player since, in order
+    pub outbox: VecDeque<messages::Message>,
+}
+
 /// Single unit of a player
 ///
 /// Stores the broker address to communicate with server
@@ -21,11 +57,120 @@ pub struct Player {
     pub name: Option<String>,
     pub addr: Recipient<message::Message>,
     pub requested_chunks: VecDeque<Vec2<i32>>,
+    /// Chunks that have been sent to and not yet unloaded by the client
+    pub sent_chunks: HashSet<Vec2<i32>>,
+    /// Name shown in the player list, falls back to `name`
+    pub display_name: Option<String>,
+    /// Player list sort order, lower goes first
+    pub list_order: i32,
+    /// Round-trip time in milliseconds
+    pub ping: u32,
+    /// Last processed client input sequence
+    pub last_sequence: u32,
+    /// Voxel edits that can be undone or redone
+    pub edits: EditHistory,
+    /// Latest client edit sequence applied, older edits arriving late are rejected
+    pub edit_sequence: u32,
+    /// Secret given on join that authorizes skin uploads over HTTP
+    pub skin_token: String,
+    /// Locale requested on join, used to translate server messages
+    pub locale: String,
+    /// Real address of the client, behind any trusted proxies
+    pub address: Option<IpAddr>,
+    /// Secret given on join that lets a briefly disconnected client resume the session
+    pub resume_token: String,
+    /// Set while the connection is gone and the session can still be resumed
+    pub detached: Option<Detached>,
+    /// Block the player was last seen targeting, when targets are broadcast
+    pub target: Option<BlockTarget>,
+    /// Voxel of the block entity whose UI the player has open
+    pub viewing: Option<Vec3<i32>>,
+    /// Id of the container window opened last, clicks on older windows are rejected
+    pub container_id: u32,
+    /// Stack picked up from a container, held by the cursor
+    pub cursor: Option<ItemStack>,
+    /// Trader entity the player has a window open into, instead of a block entity
+    pub trading: Option<(Entity, TradeWindow)>,
+    /// Form the player has open, along with its id, answers to others are rejected
+    pub form: Option<(u32, Form)>,
+    /// Id of the form shown last
+    pub form_id: u32,
+    /// Whether the client asked for map tiles, then sent those of its chunks as they change
+    pub minimap: bool,
+    /// Overlays the client asked for, sent to it every few ticks
+    pub debug: Option<DebugOptions>,
+    /// Whether the player may run operator commands, granted with `/op` from the console
+    pub operator: bool,
+    /// Tick the player last landed an attack, attacks within `ATTACK_COOLDOWN` of it are dropped
+    pub last_attack: Option<i32>,
+}
+
+impl Player {
+    /// Send a message to the client, buffering it while detached.
+    /// Returns false if the client is gone for good
+    pub fn send(&mut self, msg: messages::Message) -> bool {
+        if let Some(detached) = &mut self.detached {
+            if detached.outbox.len() >= MAX_OUTBOX {
+                // too many missed messages to catch up on, expire right away
+                detached.deadline = Instant::now();
+                detached.outbox.clear();
+            } else {
+                detached.outbox.push_back(msg);
+            }
+
+            return true;
+        }
+
+        self.addr.do_send(message::Message(msg)).is_ok()
+    }
+
+    /// Get the entry of this player in the player list
+    pub fn get_list_entry(&self, id: usize, teams: &Teams) -> PlayerListProtocol {
+        let name = self.name.to_owned().unwrap_or_default();
+        let team = teams.team_of(&name);
+
+        PlayerListProtocol {
+            id: id.to_string(),
+            display_name: self
+                .display_name
+                .to_owned()
+                .unwrap_or_else(|| name.to_owned()),
+            name,
+            ping: self.ping,
+            order: self.list_order,
+            removed: false,
+            team: team.map(|t| t.name.to_owned()).unwrap_or_default(),
+            color: team.map(|t| t.color.to_owned()).unwrap_or_default(),
+        }
+    }
 }
 
 /// Resource to store all server-side players in a HashMap
 pub type Players = HashMap<usize, Player>;
 
+pub trait PlayerListExt {
+    fn get_player_list(&self, teams: &Teams) -> Vec<PlayerListProtocol>;
+}
+
+impl PlayerListExt for Players {
+    /// Get the sorted list of all players that have joined with a name
+    fn get_player_list(&self, teams: &Teams) -> Vec<PlayerListProtocol> {
+        let mut list = self
+            .iter()
+            .filter(|(_, player)| player.name.is_some())
+            .map(|(id, player)| player.get_list_entry(*id, teams))
+            .collect::<Vec<_>>();
+
+        list.sort_by(|a, b| {
+            a.order
+                .cmp(&b.order)
+                .then_with(|| a.display_name.cmp(&b.display_name))
+        });
+
+        list
+    }
+}
+
 pub trait BroadcastExt {
     fn broadcast(
         &mut self,
@@ -33,12 +178,15 @@ pub trait BroadcastExt {
         include: Vec<usize>,
         exclude: Vec<usize>,
         sender: Option<usize>,
+        translations: &Translations,
     ) -> Vec<Player>;
 }
 
 impl BroadcastExt for Players {
     /// Broadcast a message to all players. Exclude will be used if include is empty.
     ///
+    /// Translatable messages are localized for each receiving player.
+    ///
     /// Returns a list of inactive/disconnected players
     fn broadcast(
         &mut self,
@@ -46,6 +194,7 @@ impl BroadcastExt for Players {
         include: Vec<usize>,
         exclude: Vec<usize>,
         sender: Option<usize>,
+        translations: &Translations,
     ) -> Vec<Player> {
         let mut resting_players = vec![];
 
@@ -57,27 +206,23 @@ impl BroadcastExt for Players {
 
         if !include.is_empty() {
             for id in include.iter() {
-                if let Some(player) = self.get(id) {
-                    if player
-                        .addr
-                        .do_send(message::Message(msg.to_owned()))
-                        .is_err()
-                    {
+                if let Some(player) = self.get_mut(id) {
+                    let localized = translations.localize(msg, &player.locale);
+
+                    if !player.send(localized) {
                         resting_players.push(*id);
                     }
                 }
             }
         } else {
-            for (id, player) in self.iter() {
+            for (id, player) in self.iter_mut() {
                 if exclude.contains(id) {
                     continue;
                 }
 
-                if player
-                    .addr
-                    .do_send(message::Message(msg.to_owned()))
-                    .is_err()
-                {
+                let localized = translations.localize(msg, &player.locale);
+
+                if !player.send(localized) {
                     resting_players.push(*id);
                 }
             }
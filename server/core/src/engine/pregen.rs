@@ -0,0 +1,159 @@
+use log::info;
+use rayon::ThreadPoolBuilder;
+use serde::{Deserialize, Serialize};
+
+use server_common::vec::Vec2;
+
+use super::{chunks::Chunks, config::WorldConfig, registry::Registry};
+
+/// Metadata key of how far an interrupted pregeneration got
+const PROGRESS_KEY: &str = "pregen";
+
+/// Chunks decorated around one before it's lit
+///
+/// Trees reach a chunk over and its light floods in from the chunks next to it, so a chunk is
+/// only final once every chunk two over is decorated.
+const DECORATE_REACH: i16 = 3;
+
+/// Rows of chunks kept loaded behind the one being lit, whose edges the next rows still touch
+const ROWS_KEPT: i32 = DECORATE_REACH as i32 * 2;
+
+/// The last row of chunks saved by a pregeneration of a radius
+#[derive(Serialize, Deserialize)]
+struct Progress {
+    radius: i32,
+    row: i32,
+}
+
+/// Number of chunks within a radius of the origin
+pub fn count_chunks(radius: i32) -> usize {
+    (-radius..=radius)
+        .map(|z| row_width(radius, z) as usize * 2 + 1)
+        .sum()
+}
+
+/// How far a row of the circle of chunks reaches on either side
+fn row_width(radius: i32, z: i32) -> i32 {
+    ((radius * radius - z * z) as f32).sqrt() as i32
+}
+
+/// Generate, light and save every chunk within a radius of the origin, a row at a time
+///
+/// Pregenerating again resumes after the last row saved, so an interrupted run loses a row at
+/// most. `progress` is called after each row with the chunks done and the total. Returns the
+/// number of chunks newly saved.
+pub fn pregenerate(
+    world_name: &str,
+    mut config: WorldConfig,
+    registry: Registry,
+    radius: i32,
+    threads: Option<usize>,
+    progress: &mut dyn FnMut(usize, usize),
+) -> usize {
+    // rows behind are released by hand instead, only once they're saved
+    config.max_loaded_chunks = i32::MAX as usize;
+
+    let mut chunks = Chunks::new(world_name, config, registry);
+    let storage = chunks
+        .storage
+        .clone()
+        .unwrap_or_else(|| panic!("World \"{}\" isn't saved to pregenerate.", world_name));
+
+    let mut pool = ThreadPoolBuilder::new();
+    if let Some(threads) = threads {
+        pool = pool.num_threads(threads);
+    }
+    let pool = pool.build().unwrap();
+
+    let start = storage
+        .get_metadata(PROGRESS_KEY)
+        .and_then(|data| serde_json::from_slice::<Progress>(&data).ok())
+        .filter(|progress| progress.radius == radius)
+        .map_or(-radius, |progress| progress.row + 1);
+
+    if start > -radius {
+        info!(
+            "Resuming pregeneration of \"{}\" at row {}.",
+            world_name, start
+        );
+    }
+
+    let total = count_chunks(radius);
+    let mut done = (-radius..start)
+        .map(|z| row_width(radius, z) as usize * 2 + 1)
+        .sum();
+    let mut saved = 0;
+
+    for z in start..=radius {
+        let width = row_width(radius, z);
+
+        // generating spreads across the pool, decorating and lighting stay in order
+        pool.install(|| {
+            for x in -width..=width {
+                chunks.generate(&Vec2(x, z), DECORATE_REACH, true);
+            }
+        });
+
+        for x in -width..=width {
+            let coords = Vec2(x, z);
+
+            if !chunks.config.shards.is_local(&coords) {
+                continue;
+            }
+
+            let chunk = chunks.raw(&coords).unwrap();
+            if !chunk.needs_propagation && !chunk.needs_saving {
+                continue;
+            }
+
+            if chunk.needs_propagation {
+                chunks.propagate_chunk(&coords);
+            }
+
+            chunks.raw(&coords).unwrap().save(storage.as_ref());
+            saved += 1;
+        }
+
+        storage.put_metadata(
+            PROGRESS_KEY,
+            &serde_json::to_vec(&Progress { radius, row: z }).unwrap(),
+        );
+        storage.flush();
+
+        let behind = chunks
+            .all()
+            .iter()
+            .map(|chunk| chunk.coords.to_owned())
+            .filter(|coords| coords.1 < z - ROWS_KEPT)
+            .collect::<Vec<_>>();
+        for coords in behind {
+            chunks.release_chunk(&coords);
+        }
+
+        done += width as usize * 2 + 1;
+        progress(done, total);
+    }
+
+    storage.sync();
+
+    saved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        assert_eq!(count_chunks(0), 1);
+        assert_eq!(count_chunks(1), 5);
+        assert_eq!(count_chunks(2), 13);
+
+        let radius = 10;
+        let within = (-radius..=radius)
+            .flat_map(|x| (-radius..=radius).map(move |z| (x, z)))
+            .filter(|(x, z)| x * x + z * z <= radius * radius)
+            .count();
+        assert_eq!(count_chunks(radius), within);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    cmp::Reverse,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use hashbrown::HashMap;
+
+/// Number of recent ticks kept for the tick time percentiles
+pub const TICK_WINDOW: usize = 1200;
+
+/// Aggregated timings of a single system or tick phase
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timing {
+    pub count: u32,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+/// Timings gathered between starting and stopping the profiler
+#[derive(Debug, Clone)]
+pub struct ProfileReport {
+    pub ticks: i32,
+    /// Most expensive first
+    pub timings: Vec<(&'static str, Timing)>,
+}
+
+#[derive(Debug)]
+struct ProfileData {
+    started: i32,
+    timings: HashMap<&'static str, Timing>,
+    ticks: VecDeque<Duration>,
+}
+
+/// Resource keeping the recent tick durations and, while started, per-system timings
+///
+/// Clones share their data, so work done off-thread such as lighting can be timed too.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    active: Arc<AtomicBool>,
+    data: Arc<Mutex<ProfileData>>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            active: Arc::new(AtomicBool::new(false)),
+            data: Arc::new(Mutex::new(ProfileData {
+                started: 0,
+                timings: HashMap::new(),
+                ticks: VecDeque::with_capacity(TICK_WINDOW),
+            })),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Start gathering timings from a world tick, discarding any previous ones
+    pub fn start(&self, tick: i32) {
+        let mut data = self.data.lock().unwrap();
+        data.started = tick;
+        data.timings.clear();
+
+        self.active.store(true, Ordering::Relaxed);
+    }
+
+    /// Stop gathering timings, returns none if the profiler wasn't started
+    pub fn stop(&self, tick: i32) -> Option<ProfileReport> {
+        if !self.active.swap(false, Ordering::Relaxed) {
+            return None;
+        }
+
+        let mut data = self.data.lock().unwrap();
+
+        let mut timings = data.timings.drain().collect::<Vec<_>>();
+        timings.sort_by_key(|(_, timing)| Reverse(timing.total));
+
+        Some(ProfileReport {
+            ticks: (tick - data.started).max(1),
+            timings,
+        })
+    }
+
+    /// Add a timing of a system or tick phase, ignored unless started
+    pub fn record(&self, name: &'static str, duration: Duration) {
+        if !self.is_active() {
+            return;
+        }
+
+        let mut data = self.data.lock().unwrap();
+        let timing = data.timings.entry(name).or_default();
+
+        timing.count += 1;
+        timing.total += duration;
+        timing.max = timing.max.max(duration);
+    }
+
+    /// Add the duration of a whole world tick
+    pub fn record_tick(&self, duration: Duration) {
+        let mut data = self.data.lock().unwrap();
+
+        if data.ticks.len() == TICK_WINDOW {
+            data.ticks.pop_front();
+        }
+        data.ticks.push_back(duration);
+    }
+
+    /// Get a percentile of the recent tick durations, in milliseconds
+    pub fn tick_percentile(&self, percentile: f32) -> f32 {
+        let data = self.data.lock().unwrap();
+
+        if data.ticks.is_empty() {
+            return 0.0;
+        }
+
+        let mut ticks = data.ticks.iter().copied().collect::<Vec<_>>();
+        ticks.sort();
+
+        let index = ((ticks.len() - 1) as f32 * percentile.clamp(0.0, 1.0)).round() as usize;
+        ticks[index].as_secs_f32() * 1000.0
+    }
+
+    /// Ticks per second achieved recently, capped by the tick rate in milliseconds
+    pub fn tps(&self, tick_rate: u64) -> f32 {
+        let data = self.data.lock().unwrap();
+
+        if data.ticks.is_empty() {
+            return 0.0;
+        }
+
+        let average =
+            data.ticks.iter().sum::<Duration>().as_secs_f32() * 1000.0 / data.ticks.len() as f32;
+
+        1000.0 / average.max(tick_rate.max(1) as f32)
+    }
+}
+
+impl ProfileReport {
+    /// Milliseconds per tick spent on a timing on average
+    pub fn per_tick(&self, timing: &Timing) -> f32 {
+        timing.total.as_secs_f32() * 1000.0 / self.ticks as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile() {
+        let profiler = Profiler::new();
+
+        for millis in 1..=100 {
+            profiler.record_tick(Duration::from_millis(millis));
+        }
+        assert!((profiler.tick_percentile(0.5) - 51.0).abs() < 1e-3);
+        assert!((profiler.tick_percentile(1.0) - 100.0).abs() < 1e-3);
+        assert!((profiler.tps(16) - 1000.0 / 50.5).abs() < 1e-3);
+
+        // timings are only gathered once started
+        profiler.record("physics", Duration::from_millis(5));
+        assert!(profiler.stop(10).is_none());
+
+        profiler.start(10);
+        profiler.record("physics", Duration::from_millis(2));
+        profiler.record("physics", Duration::from_millis(4));
+        profiler.record("lighting", Duration::from_millis(1));
+
+        let report = profiler.stop(12).unwrap();
+        assert_eq!(report.ticks, 2);
+        assert_eq!(report.timings[0].0, "physics");
+        assert_eq!(report.timings[0].1.count, 2);
+        assert_eq!(report.timings[0].1.max, Duration::from_millis(4));
+        assert!((report.per_tick(&report.timings[0].1) - 3.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use super::registry::Registry;
+
+/// Recipe turning one input item into an output when smelted
+#[derive(Debug, Clone)]
+pub struct SmeltingRecipe {
+    pub input: u32,
+    pub output: u32,
+    pub count: u32,
+    /// Ticks it takes to smelt a single input
+    pub time: u32,
+}
+
+#[derive(Deserialize)]
+struct SmeltingRecipeData {
+    /// Block name, or tag such as `#logs`
+    input: String,
+    output: String,
+    count: u32,
+    time: u32,
+}
+
+#[derive(Deserialize)]
+struct RecipesData {
+    #[serde(default)]
+    smelting: Vec<SmeltingRecipeData>,
+    /// Ticks a single item burns for, keyed by block name or tag
+    #[serde(default)]
+    fuels: HashMap<String, u32>,
+}
+
+/// Resource of all recipes, loaded from `assets/metadata/recipes.json`
+#[derive(Debug, Default)]
+pub struct Recipes {
+    smelting: HashMap<u32, SmeltingRecipe>,
+    fuels: HashMap<u32, u32>,
+}
+
+impl Recipes {
+    /// Panics on a broken recipes file, see `Recipes::load`
+    pub fn new(registry: &Registry) -> Self {
+        Recipes::load(registry).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Load the recipes, resolving block names through the registry
+    pub fn load(registry: &Registry) -> Result<Self, String> {
+        let mut data: RecipesData = serde_json::from_value(
+            registry
+                .data_packs
+                .read("recipes.json")
+                .ok_or("Unable to find recipes.json")?,
+        )
+        .map_err(|e| format!("Unable to parse recipes.json: {}", e))?;
+
+        let id = |name: &str| {
+            registry
+                .try_get_id_by_name(name)
+                .ok_or_else(|| format!("Unknown block in recipes: {}", name))
+        };
+
+        // inputs may be tags, standing for each of their blocks
+        let ids = |pattern: &str| -> Result<Vec<u32>, String> {
+            match pattern.strip_prefix('#') {
+                Some(tag) => {
+                    let mut ids = registry
+                        .tags
+                        .get(tag)
+                        .ok_or_else(|| format!("Unknown tag in recipes: {}", pattern))?
+                        .iter()
+                        .copied()
+                        .collect::<Vec<_>>();
+                    ids.sort_unstable();
+                    Ok(ids)
+                }
+                None => Ok(vec![id(pattern)?]),
+            }
+        };
+
+        // blocks named outright take precedence over the tags they're in
+        data.smelting
+            .sort_by_key(|recipe| !recipe.input.starts_with('#'));
+        let mut fuels_data = data.fuels.iter().collect::<Vec<_>>();
+        fuels_data.sort_by_key(|&(name, _)| (!name.starts_with('#'), name));
+
+        let mut smelting = HashMap::new();
+        for recipe in data.smelting.iter() {
+            let output = id(&recipe.output)?;
+
+            for input in ids(&recipe.input)? {
+                smelting.insert(
+                    input,
+                    SmeltingRecipe {
+                        input,
+                        output,
+                        count: recipe.count,
+                        time: recipe.time,
+                    },
+                );
+            }
+        }
+
+        let mut fuels = HashMap::new();
+        for (pattern, &time) in fuels_data {
+            for id in ids(pattern)? {
+                fuels.insert(id, time);
+            }
+        }
+
+        Ok(Self { smelting, fuels })
+    }
+
+    /// Register a smelting recipe, replacing the one of the same input
+    pub fn add_smelting(&mut self, recipe: SmeltingRecipe) {
+        self.smelting.insert(recipe.input, recipe);
+    }
+
+    /// Register an item as fuel burning for `time` ticks
+    pub fn add_fuel(&mut self, id: u32, time: u32) {
+        self.fuels.insert(id, time);
+    }
+
+    /// Get the smelting recipe for an input item
+    pub fn get_smelting(&self, input: u32) -> Option<&SmeltingRecipe> {
+        self.smelting.get(&input)
+    }
+
+    /// Get how many ticks an item burns for as fuel, `None` if it isn't a fuel
+    pub fn get_fuel(&self, id: u32) -> Option<u32> {
+        self.fuels.get(&id).copied()
+    }
+}
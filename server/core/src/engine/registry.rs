@@ -1,13 +1,16 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::path::Path;
 
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use server_common::types::{Block, TypeMap, UV};
 use server_utils::json;
 
+use super::{data_packs::DataPacks, resource_pack::ResourcePack, tags::Tags};
+
 pub type Ranges = HashMap<String, UV>;
 pub type Blocks = HashMap<u32, Block>;
 
@@ -26,29 +29,47 @@ pub struct Registry {
     pub blocks: Blocks,
     pub uv_side_count: u32,
     pub uv_texture_size: u32,
+    pub resource_pack: Option<ResourcePack>,
+    pub tags: Tags,
+    /// Packs the blocks, tags, recipes, loot, trades and structures are layered from
+    pub data_packs: DataPacks,
 
     name_map: HashMap<String, u32>,
 }
 
 impl Registry {
-    pub fn new(mut packs: Vec<String>, write: bool) -> Self {
+    /// Panics on a broken pack or block file, see `Registry::load`
+    pub fn new(packs: Vec<String>, data_packs: DataPacks, write: bool) -> Self {
+        Registry::load(packs, data_packs, write).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Load the texture packs, failing on the first broken pack or block file
+    pub fn load(
+        mut packs: Vec<String>,
+        data_packs: DataPacks,
+        write: bool,
+    ) -> Result<Self, String> {
         if packs.is_empty() {
-            panic!("No texture packs found.");
+            return Err("No texture packs found.".to_owned());
         }
 
-        let registry: Self = Registry::load_pack(&packs.remove(0), write);
+        let registry: Self = Registry::load_pack(&packs.remove(0), &data_packs, write)?;
 
-        packs.into_iter().for_each(|name| {
-            Registry::load_pack(&name, write);
-        });
+        for name in packs {
+            Registry::load_pack(&name, &data_packs, write)?;
+        }
 
-        registry
+        Ok(registry)
     }
 
-    /// Load a texture pack
-    pub fn load_pack(pack_name: &str, write: bool) -> Self {
-        let blocks_json: HashMap<String, String> =
-            serde_json::from_reader(File::open("assets/metadata/blocks.json").unwrap()).unwrap();
+    /// Load a texture pack, along with the blocks of the data packs layered over it
+    pub fn load_pack(pack_name: &str, data_packs: &DataPacks, write: bool) -> Result<Self, String> {
+        let blocks_json: HashMap<String, String> = serde_json::from_value(
+            data_packs
+                .read("blocks.json")
+                .ok_or("Unable to find blocks.json")?,
+        )
+        .map_err(|e| format!("Unable to parse blocks.json: {}", e))?;
 
         let mut base_cache: HashMap<String, serde_json::Value> = HashMap::new();
         let mut texture_map: HashMap<String, image::DynamicImage> = HashMap::new();
@@ -56,59 +77,80 @@ impl Registry {
         let mut name_map = HashMap::new();
 
         let mut blocks: Blocks = HashMap::new();
+        let mut duplicates = vec![];
 
-        let pack: PackDetails = serde_json::from_reader(
-            File::open(format!("assets/textures/packs/{}/pack.json", pack_name)).unwrap(),
-        )
-        .unwrap();
+        let pack: PackDetails = read_json(Path::new(&format!(
+            "assets/textures/packs/{}/pack.json",
+            pack_name
+        )))?;
 
         for (id, block_file) in blocks_json.iter() {
-            let path = format!("./assets/metadata/blocks/{}", block_file);
-            let mut block_json: serde_json::Value =
-                serde_json::from_reader(File::open(path).unwrap()).unwrap();
-
-            let base = &block_json["base"];
-
-            let base = match base {
-                serde_json::Value::String(base_str) => {
-                    Some(base_cache.entry(base_str.to_owned()).or_insert_with(|| {
-                        serde_json::from_reader(
-                            File::open(format!("./assets/metadata/blocks/{}", base_str).as_str())
-                                .unwrap(),
-                        )
-                        .unwrap()
-                    }))
-                }
-                _ => None,
+            let path = data_packs.path(&format!("blocks/{}", block_file));
+            let mut block_json: serde_json::Value = read_json(&path)?;
+
+            let base_str = block_json["base"]
+                .as_str()
+                .ok_or_else(|| format!("Block file without a base: {}", path.display()))?;
+
+            if !base_cache.contains_key(base_str) {
+                let base = read_json(&data_packs.path(&format!("blocks/{}", base_str)))?;
+                base_cache.insert(base_str.to_owned(), base);
             }
-            .unwrap();
+            let base = base_cache[base_str].to_owned();
 
-            json::merge(&mut block_json, base, false);
+            json::merge(&mut block_json, &base, false);
 
             let textures = &block_json["textures"];
             let mut textures_hash = HashMap::new();
 
             if !serde_json::Value::is_null(textures) {
-                for (side, img_src) in textures.as_object().unwrap().iter() {
-                    let img_src_str = img_src.as_str().unwrap();
+                let textures = textures
+                    .as_object()
+                    .ok_or_else(|| format!("Invalid textures in {}", path.display()))?;
+
+                for (side, img_src) in textures.iter() {
+                    let img_src_str = img_src
+                        .as_str()
+                        .ok_or_else(|| format!("Invalid texture in {}", path.display()))?;
 
                     let image = if img_src_str.ends_with(".png") {
-                        let path =
-                            format!("assets/textures/packs/{}/blocks/{}", pack_name, img_src_str);
-                        image::open(&path).unwrap_or_else(|_| panic!("Texture not found: {}", path))
+                        // blocks added by data packs bring their own textures
+                        let path = Path::new("assets/textures/packs")
+                            .join(pack_name)
+                            .join("blocks")
+                            .join(img_src_str);
+                        let path = if path.exists() {
+                            path
+                        } else {
+                            data_packs
+                                .find(&format!("textures/{}", img_src_str))
+                                .unwrap_or(path)
+                        };
+                        image::open(&path)
+                            .map_err(|_| format!("Texture not found: {}", path.display()))?
                     } else {
                         // texture data
-                        let texture_data: serde_json::Value = serde_json::from_reader(
-                            File::open(format!("assets/textures/procedural/{}", img_src_str))
-                                .unwrap(),
-                        )
-                        .unwrap();
-
-                        let color_vec = texture_data["color"].as_array().unwrap().as_slice();
-
-                        let color_r = (color_vec[0].as_f64().unwrap() * 255.0) as u8;
-                        let color_g = (color_vec[1].as_f64().unwrap() * 255.0) as u8;
-                        let color_b = (color_vec[2].as_f64().unwrap() * 255.0) as u8;
+                        let texture_path =
+                            Path::new("assets/textures/procedural").join(img_src_str);
+                        let texture_data: serde_json::Value = read_json(&texture_path)?;
+
+                        let color = texture_data["color"]
+                            .as_array()
+                            .filter(|color| color.len() >= 3)
+                            .and_then(|color| {
+                                color
+                                    .iter()
+                                    .take(3)
+                                    .map(|c| c.as_f64())
+                                    .collect::<Option<Vec<_>>>()
+                            })
+                            .ok_or_else(|| {
+                                format!("Invalid color in {}", texture_path.display())
+                            })?;
+
+                        let color_r = (color[0] * 255.0) as u8;
+                        let color_g = (color[1] * 255.0) as u8;
+                        let color_b = (color[2] * 255.0) as u8;
 
                         let imgbuf = image::ImageBuffer::from_pixel(
                             16,
@@ -124,13 +166,28 @@ impl Registry {
                 }
             }
 
-            let mut new_block: Block = serde_json::from_value(block_json).unwrap();
+            let mut new_block: Block = serde_json::from_value(block_json)
+                .map_err(|e| format!("Unable to parse {}: {}", path.display(), e))?;
             new_block.textures = textures_hash;
-            let id = id.parse::<u32>().unwrap();
-            name_map.insert(new_block.name.clone(), id);
+            // declaring the light it emits is all it takes to be a light source
+            new_block.normalize_emission();
+            let id = id
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid block id in blocks.json: {}", id))?;
+            if let Some(other) = name_map.insert(new_block.name.clone(), id) {
+                duplicates.push(format!("{} is both {} and {}", new_block.name, other, id));
+            }
             blocks.insert(id, new_block);
         }
 
+        if !duplicates.is_empty() {
+            duplicates.sort();
+            return Err(format!(
+                "Block names have to be unique: {}.",
+                duplicates.join(", ")
+            ));
+        }
+
         // OBTAINED TEXTURE MAP
         let map_size = texture_map.len() as f32;
         let mut shifts = 1;
@@ -203,17 +260,42 @@ impl Registry {
                     "assets/textures/generated/{}-atlas.png",
                     pack_name
                 ))
-                .unwrap();
+                .map_err(|e| format!("Unable to write the atlas of {}: {}", pack_name, e))?;
         }
 
-        Self {
+        let tags_json: HashMap<String, Vec<String>> = serde_json::from_value(
+            data_packs
+                .read("tags.json")
+                .ok_or("Unable to find tags.json")?,
+        )
+        .map_err(|e| format!("Unable to parse tags.json: {}", e))?;
+        let tags = Tags::resolve(&tags_json, &name_map)?;
+
+        Ok(Self {
             atlas,
             ranges,
             blocks,
             uv_texture_size: texture_dim,
             uv_side_count: count_per_side,
+            resource_pack: None,
+            tags,
+            data_packs: data_packs.to_owned(),
             name_map,
+        })
+    }
+
+    /// Use a server-declared resource pack, pointing block entries to its assets
+    pub fn apply_resource_pack(&mut self, pack: ResourcePack) -> Result<(), String> {
+        for (name, sounds) in pack.block_sounds()? {
+            let id = self
+                .try_get_id_by_name(&name)
+                .ok_or_else(|| format!("Unknown block in resource pack: {}", name))?;
+            self.blocks.get_mut(&id).unwrap().sounds = sounds;
         }
+
+        self.resource_pack = Some(pack);
+
+        Ok(())
     }
 
     /// Get block transparency by id
@@ -320,6 +402,45 @@ impl Registry {
             .unwrap_or_else(|| panic!("Type name not found: {}", name))
     }
 
+    /// Get block id by name, if such block exists
+    pub fn try_get_id_by_name(&self, name: &str) -> Option<u32> {
+        self.name_map.get(name).copied()
+    }
+
+    /// Get block id by name, ignoring case and taking underscores for spaces as typed in commands
+    pub fn find_id_by_name(&self, name: &str) -> Option<u32> {
+        self.try_get_id_by_name(name).or_else(|| {
+            let name = name.replace('_', " ");
+            self.name_map
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(&name))
+                .map(|(_, &id)| id)
+        })
+    }
+
+    /// Check if a block is in a tag
+    pub fn has_tag(&self, id: u32, tag: &str) -> bool {
+        self.tags.contains(tag, id)
+    }
+
+    /// Get the ids a block pattern matches, either a block name or a tag such as `#logs`
+    pub fn resolve_pattern(&self, pattern: &str) -> Option<HashSet<u32>> {
+        match pattern.strip_prefix('#') {
+            Some(tag) => self.tags.get(tag).cloned(),
+            None => self
+                .find_id_by_name(pattern)
+                .map(|id| std::iter::once(id).collect()),
+        }
+    }
+
+    /// Check if a block matches a pattern, either its name or a tag it's in
+    pub fn matches_pattern(&self, id: u32, pattern: &str) -> bool {
+        match pattern.strip_prefix('#') {
+            Some(tag) => self.has_tag(id, tag),
+            None => self.find_id_by_name(pattern) == Some(id),
+        }
+    }
+
     /// Get UV map by block
     pub fn get_uv_map(&self, block: &Block) -> HashMap<String, &UV> {
         let mut uv_map = HashMap::new();
@@ -395,3 +516,9 @@ fn fix_texture_bleeding(
         end_v + offset,
     )
 }
+
+/// Read a JSON file of the packs, naming the file on failure
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let file = File::open(path).map_err(|_| format!("File not found: {}", path.display()))?;
+    serde_json::from_reader(file).map_err(|e| format!("Unable to parse {}: {}", path.display(), e))
+}
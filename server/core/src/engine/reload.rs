@@ -0,0 +1,208 @@
+use server_common::types::Block;
+
+use super::{
+    data_packs::DataPacks,
+    loot::LootTables,
+    recipes::Recipes,
+    registry::{Blocks, Ranges, Registry},
+    resource_pack::ResourcePack,
+    trading::TradeLists,
+};
+
+/// The data files of a world loaded again, ready to replace the running ones
+pub struct Reloaded {
+    pub registry: Registry,
+    pub recipes: Recipes,
+    pub loot: LootTables,
+    pub trades: TradeLists,
+}
+
+/// Load the blocks, textures, recipes, loot tables and trades again from their files and packs
+///
+/// Fails on the first broken file, leaving the running world as it is.
+pub fn reload_data(
+    packs: Vec<String>,
+    data_packs: DataPacks,
+    resource_pack: Option<ResourcePack>,
+) -> Result<Reloaded, String> {
+    let mut registry = Registry::load(packs, data_packs, true)?;
+
+    if let Some(pack) = resource_pack {
+        registry.apply_resource_pack(pack)?;
+    }
+
+    Ok(Reloaded {
+        recipes: Recipes::load(&registry)?,
+        loot: LootTables::load(&registry)?,
+        trades: TradeLists::load(&registry)?,
+        registry,
+    })
+}
+
+/// How the blocks reloaded differ from the running ones
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RegistryDiff {
+    pub added: Vec<u32>,
+    /// Blocks that look different, such as new textures, whose chunks are remeshed
+    pub visual: Vec<u32>,
+    /// Blocks that only behave differently, such as being climbable now
+    pub behavior: Vec<u32>,
+    /// Why the reloaded blocks can't replace the running ones, such as removed blocks the
+    /// chunks still hold
+    pub conflicts: Vec<String>,
+}
+
+/// Whether a block is drawn the same in both registries
+fn looks_same(old: (&Block, &Ranges), new: (&Block, &Ranges)) -> bool {
+    let ((old, old_ranges), (new, new_ranges)) = (old, new);
+
+    let uv = |block: &Block, ranges: &Ranges| {
+        let mut uvs = block
+            .textures
+            .iter()
+            .map(|(side, texture)| {
+                let uv = ranges.get(texture);
+                (
+                    side.to_owned(),
+                    uv.map(|uv| [uv.start_u, uv.end_u, uv.start_v, uv.end_v]),
+                )
+            })
+            .collect::<Vec<_>>();
+        uvs.sort_by(|a, b| a.0.cmp(&b.0));
+        uvs
+    };
+
+    old.is_block == new.is_block
+        && old.is_empty == new.is_empty
+        && old.is_fluid == new.is_fluid
+        && old.is_plant == new.is_plant
+        && old.is_transparent == new.is_transparent
+        && old.transparent_standalone == new.transparent_standalone
+        && old.rotatable == new.rotatable
+        && old.y_rotatable == new.y_rotatable
+        && old.textures == new.textures
+        && uv(old, old_ranges) == uv(new, new_ranges)
+}
+
+impl RegistryDiff {
+    pub fn new(old: &Registry, new: &Registry) -> Self {
+        Self::between((&old.blocks, &old.ranges), (&new.blocks, &new.ranges))
+    }
+
+    /// Compare the blocks and texture ranges of two registries, block ids in order
+    pub fn between(old: (&Blocks, &Ranges), new: (&Blocks, &Ranges)) -> Self {
+        let ((old_blocks, old_ranges), (new_blocks, new_ranges)) = (old, new);
+        let mut diff = Self::default();
+
+        let mut ids = old_blocks
+            .keys()
+            .chain(new_blocks.keys())
+            .collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.dedup();
+
+        for &id in ids {
+            match (old_blocks.get(&id), new_blocks.get(&id)) {
+                (Some(old), None) => diff.conflicts.push(format!(
+                    "Block {} ({}) was removed, chunks may still hold it.",
+                    old.name, id
+                )),
+                (None, Some(_)) => diff.added.push(id),
+                (Some(old), Some(new)) if old.name != new.name => diff.conflicts.push(format!(
+                    "Block {} was renamed from {} to {}, ids have to stay the same.",
+                    id, old.name, new.name
+                )),
+                (Some(old), Some(new)) => {
+                    if !looks_same((old, old_ranges), (new, new_ranges)) {
+                        diff.visual.push(id);
+                    } else if serde_json::to_value(old).ok() != serde_json::to_value(new).ok() {
+                        diff.behavior.push(id);
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_safe(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use server_common::types::UV;
+
+    use super::*;
+
+    fn block(name: &str, texture: &str, is_climbable: bool) -> Block {
+        let mut block: Block = serde_json::from_value(serde_json::json!({
+            "name": name,
+            "rotatable": false,
+            "yRotatable": false,
+            "isBlock": true,
+            "isEmpty": false,
+            "isFluid": false,
+            "isPlant": false,
+            "isSolid": true,
+            "isTransparent": false,
+            "isPlantable": false,
+            "transparentStandalone": false,
+            "isClimbable": is_climbable,
+        }))
+        .unwrap();
+        block.textures.insert("all".into(), texture.into());
+        block
+    }
+
+    #[test]
+    fn diff() {
+        let uv = |start_u: f32| UV {
+            start_u,
+            end_u: start_u + 0.5,
+            start_v: 1.0,
+            end_v: 0.5,
+        };
+        let ranges = vec![
+            ("dirt.png".to_owned(), uv(0.0)),
+            ("stone.png".to_owned(), uv(0.5)),
+        ]
+        .into_iter()
+        .collect::<Ranges>();
+
+        let old = vec![
+            (1, block("Dirt", "dirt.png", false)),
+            (2, block("Stone", "stone.png", false)),
+            (3, block("Ladder", "dirt.png", false)),
+        ]
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+
+        let mut new = old.clone();
+        new.insert(2, block("Stone", "dirt.png", false));
+        new.insert(3, block("Ladder", "dirt.png", true));
+        new.insert(4, block("Glass", "stone.png", false));
+
+        let diff = RegistryDiff::between((&old, &ranges), (&new, &ranges));
+        assert_eq!(diff.added, vec![4]);
+        assert_eq!(diff.visual, vec![2]);
+        assert_eq!(diff.behavior, vec![3]);
+        assert!(diff.is_safe());
+
+        // the textures moving around the atlas is a visual change too
+        let mut moved = ranges.clone();
+        moved.insert("dirt.png".into(), uv(0.25));
+        let diff = RegistryDiff::between((&old, &ranges), (&old, &moved));
+        assert_eq!(diff.visual, vec![1, 3]);
+
+        new.remove(&1);
+        new.insert(4, block("Sand", "stone.png", false));
+        let diff = RegistryDiff::between((&new, &ranges), (&old, &ranges));
+        assert_eq!(diff.conflicts.len(), 1);
+        assert!(!diff.is_safe());
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    path::Path,
+};
+
+use server_utils::hash::fnv1a;
+
+/// Folder that resource packs live in, served over HTTP under `/resources/`
+pub const RESOURCE_PACKS_FOLDER: &str = "assets/resources";
+
+/// Assets of a block that a resource pack provides, keyed by block name in the pack's
+/// `blocks.json`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockAssets {
+    #[serde(default)]
+    sounds: HashMap<String, String>,
+}
+
+/// A single file of a resource pack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackFile {
+    pub path: String,
+    pub hash: String,
+    pub size: u64,
+}
+
+/// A server-declared pack of textures, block definitions and sounds, which clients download
+/// and cache by hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcePack {
+    pub name: String,
+    /// Hash of every file's path and content, changes whenever anything in the pack changes
+    pub hash: String,
+    /// Where the pack's files are served from
+    pub url: String,
+    pub files: Vec<PackFile>,
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PackFile>) {
+    let mut entries = fs::read_dir(dir)
+        .unwrap_or_else(|_| panic!("Resource pack folder not found: {:?}", dir))
+        .flatten()
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_files(root, &path, files);
+            continue;
+        }
+
+        let data = fs::read(&path).unwrap();
+        let relative = path.strip_prefix(root).unwrap();
+
+        files.push(PackFile {
+            path: relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            hash: format!("{:016x}", fnv1a(&data)),
+            size: data.len() as u64,
+        });
+    }
+}
+
+impl ResourcePack {
+    /// Hash every file of the pack in `assets/resources/<name>`
+    pub fn load(name: &str) -> Self {
+        let root = Path::new(RESOURCE_PACKS_FOLDER).join(name);
+
+        let mut files = vec![];
+        collect_files(&root, &root, &mut files);
+
+        let listing = files
+            .iter()
+            .map(|file| format!("{}:{}", file.path, file.hash))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Self {
+            name: name.to_owned(),
+            hash: format!("{:016x}", fnv1a(listing.as_bytes())),
+            url: format!("/resources/{}/", name),
+            files,
+        }
+    }
+
+    /// Whether the pack contains a file, by its path within the pack
+    pub fn has_file(&self, path: &str) -> bool {
+        self.files.iter().any(|file| file.path == path)
+    }
+
+    /// Block sounds declared in the pack's `blocks.json`, keyed by block name
+    ///
+    /// Fails if a sound is missing from the pack's `sounds` folder.
+    pub fn block_sounds(&self) -> Result<HashMap<String, HashMap<String, String>>, String> {
+        let path = Path::new(RESOURCE_PACKS_FOLDER)
+            .join(&self.name)
+            .join("blocks.json");
+
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+
+        let file =
+            File::open(&path).map_err(|e| format!("Unable to open {}: {}", path.display(), e))?;
+        let blocks: HashMap<String, BlockAssets> = serde_json::from_reader(file)
+            .map_err(|e| format!("Unable to parse {}: {}", path.display(), e))?;
+
+        blocks
+            .into_iter()
+            .map(|(name, assets)| {
+                if let Some(sound) = assets
+                    .sounds
+                    .values()
+                    .find(|sound| !self.has_file(&format!("sounds/{}", sound)))
+                {
+                    return Err(format!("Sound not found in resource pack: {}", sound));
+                }
+
+                Ok((name, assets.sounds))
+            })
+            .collect()
+    }
+
+    /// What gets advertised to clients on join, without the file listing
+    pub fn summary_json(&self) -> String {
+        format!(
+            r#"{{"name": "{}", "hash": "{}", "url": "{}"}}"#,
+            self.name, self.hash, self.url
+        )
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicI32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use log::error;
+
+use super::{clock::unix_millis, profiler::Profiler};
+
+/// Exit code of a restart asked for with `/restart`, for the supervisor to start the server again
+pub const RESTART_EXIT_CODE: i32 = 75;
+
+/// Exit code of a restart the watchdog forced, after a world stopped ticking
+pub const WATCHDOG_EXIT_CODE: i32 = 76;
+
+/// Seconds left at which players are warned of a restart
+const RESTART_WARNINGS: [u64; 11] = [600, 300, 120, 60, 30, 10, 5, 4, 3, 2, 1];
+
+/// How often the watchdog checks on the worlds
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where a world's loop is at, shared with the watchdog watching it from another thread
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat {
+    /// Milliseconds since the epoch of the last tick started, 0 before the first one
+    last: Arc<AtomicU64>,
+    tick: Arc<AtomicI32>,
+    /// Part of the tick being run
+    phase: Arc<Mutex<&'static str>>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a tick as started
+    pub fn beat(&self, tick: i32) {
+        self.last.store(unix_millis(), Ordering::Relaxed);
+        self.tick.store(tick, Ordering::Relaxed);
+        self.enter("tick");
+    }
+
+    /// Mark the part of the tick being run, for the diagnostics of a hang
+    pub fn enter(&self, phase: &'static str) {
+        *self.phase.lock().unwrap() = phase;
+    }
+
+    /// Milliseconds since the last tick started, 0 until the world starts ticking
+    pub fn since(&self, now: u64) -> u64 {
+        match self.last.load(Ordering::Relaxed) {
+            0 => 0,
+            last => now.saturating_sub(last),
+        }
+    }
+}
+
+/// A world the watchdog watches
+#[derive(Debug, Clone)]
+pub struct Watched {
+    pub name: String,
+    pub heartbeat: Heartbeat,
+    pub profiler: Profiler,
+    /// Where the report of a hang is written, the world folder if it's saved
+    pub folder: Option<PathBuf>,
+    /// Seconds the world can go without ticking, 0 if it isn't watched
+    pub timeout: u64,
+}
+
+impl Watched {
+    /// Describe where the world's loop is stuck
+    pub fn diagnostics(&self, now: u64) -> String {
+        format!(
+            "World \"{}\" hasn't ticked for {}s, stuck on tick {} in \"{}\". \
+             Recent ticks took p50 {:.1}ms, p99 {:.1}ms.",
+            self.name,
+            self.heartbeat.since(now) / 1000,
+            self.heartbeat.tick.load(Ordering::Relaxed),
+            *self.heartbeat.phase.lock().unwrap(),
+            self.profiler.tick_percentile(0.5),
+            self.profiler.tick_percentile(0.99),
+        )
+    }
+}
+
+/// Watch the loops of the worlds from a thread of its own, exiting with `WATCHDOG_EXIT_CODE` once
+/// one of them goes over its timeout without ticking
+///
+/// The world can't be saved while its loop is stuck holding it, but the edits journaled up to
+/// the last tick are replayed when the server starts again.
+pub fn spawn_watchdog(worlds: Vec<Watched>) {
+    let worlds = worlds
+        .into_iter()
+        .filter(|world| world.timeout > 0)
+        .collect::<Vec<_>>();
+
+    if worlds.is_empty() {
+        return;
+    }
+
+    thread::spawn(move || loop {
+        thread::sleep(WATCHDOG_INTERVAL);
+
+        let now = unix_millis();
+
+        let hung = match worlds
+            .iter()
+            .find(|world| world.heartbeat.since(now) >= world.timeout * 1000)
+        {
+            Some(hung) => hung,
+            None => continue,
+        };
+
+        let report = hung.diagnostics(now);
+        error!("Watchdog: {} Restarting.", report);
+
+        if let Some(folder) = &hung.folder {
+            let _ = fs::write(folder.join(format!("hang-{}.txt", now)), &report);
+        }
+
+        std::process::exit(WATCHDOG_EXIT_CODE);
+    });
+}
+
+/// Step of a restart countdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Countdown {
+    /// Warn players of the seconds left
+    Warn(u64),
+    Restart,
+}
+
+/// Resource of a restart scheduled with `/restart`, warning players as it nears
+#[derive(Debug, Default)]
+pub struct RestartCountdown {
+    /// Milliseconds since the epoch
+    due: Option<u64>,
+    /// Seconds left at the last warning, none before the first one
+    warned: Option<u64>,
+    requested: bool,
+}
+
+impl RestartCountdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restart after a number of milliseconds, replacing the restart scheduled before if any
+    pub fn schedule(&mut self, now: u64, delay: u64) {
+        self.due = Some(now.saturating_add(delay));
+        self.warned = None;
+    }
+
+    /// Call off the scheduled restart, false if none was
+    pub fn cancel(&mut self) -> bool {
+        self.warned = None;
+        self.due.take().is_some()
+    }
+
+    /// Whether the countdown ran out, for the server to save the worlds and exit
+    pub fn is_requested(&self) -> bool {
+        self.requested
+    }
+
+    /// The step of the countdown due by a time, if any
+    pub fn tick(&mut self, now: u64) -> Option<Countdown> {
+        let due = self.due?;
+
+        if now >= due {
+            self.due = None;
+            self.requested = true;
+            return Some(Countdown::Restart);
+        }
+
+        let left = (due - now).div_ceil(1000);
+
+        // the first warning says how long there's left, the next ones fall on the marks
+        let warning = match self.warned {
+            None => Some(left),
+            Some(warned) => RESTART_WARNINGS
+                .iter()
+                .rev()
+                .find(|&&mark| mark >= left && mark < warned)
+                .copied(),
+        }?;
+
+        self.warned = Some(warning);
+        Some(Countdown::Warn(warning))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countdown() {
+        let mut countdown = RestartCountdown::new();
+        assert_eq!(countdown.tick(0), None);
+
+        countdown.schedule(0, 90_000);
+        assert_eq!(countdown.tick(0), Some(Countdown::Warn(90)));
+        assert_eq!(countdown.tick(1_000), None);
+        assert_eq!(countdown.tick(30_000), Some(Countdown::Warn(60)));
+        assert_eq!(countdown.tick(30_500), None);
+
+        // a late tick skips to the mark closest to what's left
+        assert_eq!(countdown.tick(86_500), Some(Countdown::Warn(4)));
+        assert!(!countdown.is_requested());
+        assert_eq!(countdown.tick(90_000), Some(Countdown::Restart));
+        assert!(countdown.is_requested());
+
+        countdown.schedule(0, 10_000);
+        assert!(countdown.cancel());
+        assert_eq!(countdown.tick(10_000), None);
+
+        countdown.schedule(1, u64::MAX);
+        assert!(countdown.tick(u64::MAX - 1).is_some());
+        assert!(countdown.cancel());
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use server_common::vec::Vec3;
+use server_utils::hash::{fnv1a, fnv1a_extend};
+
+/// Parts of the world drawing randomness from streams of their own, so that one drawing more
+/// doesn't change what the others get
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RngDomain {
+    Terrain,
+    Structures,
+    Loot,
+    MobAi,
+}
+
+impl RngDomain {
+    /// Tag mixed into the world seed to derive the stream of the domain
+    pub fn tag(self) -> &'static str {
+        match self {
+            RngDomain::Terrain => "terrain",
+            RngDomain::Structures => "structures",
+            RngDomain::Loot => "loot",
+            RngDomain::MobAi => "mob_ai",
+        }
+    }
+}
+
+/// Spread the bits of a value, the finalizer of splitmix64
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+/// Seed of a domain's stream, derived from the world seed and the domain's tag
+///
+/// FNV-1a is used over the std hashers, whose output isn't promised to stay the same between
+/// Rust releases.
+pub fn derive_seed(seed: u64, tag: &str) -> u64 {
+    let hash = fnv1a_extend(fnv1a(&seed.to_le_bytes()), tag.as_bytes());
+
+    mix(hash)
+}
+
+/// A stream of a domain for a voxel, drawing the same for the same voxel whatever came before
+pub fn seeded_at(seed: u64, domain: RngDomain, voxel: &Vec3<i32>) -> StdRng {
+    let &Vec3(vx, vy, vz) = voxel;
+
+    let mut hash = derive_seed(seed, domain.tag());
+    for &coord in &[vx, vy, vz] {
+        hash = mix(hash ^ coord as i64 as u64);
+    }
+
+    StdRng::seed_from_u64(hash)
+}
+
+/// Resource handing out the deterministic streams of randomness of a world, one per domain
+///
+/// Every stream is derived from the world seed, so a world run again with the same seed, such as
+/// in a replay or a test, draws the same.
+pub struct RngService {
+    seed: u64,
+    streams: HashMap<RngDomain, StdRng>,
+}
+
+impl RngService {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The running stream of a domain, started from its derived seed on first use
+    pub fn stream(&mut self, domain: RngDomain) -> &mut StdRng {
+        let seed = self.seed;
+        self.streams
+            .entry(domain)
+            .or_insert_with(|| StdRng::seed_from_u64(derive_seed(seed, domain.tag())))
+    }
+
+    /// A stream of a domain for a voxel, apart from the running stream of the domain
+    pub fn at(&self, domain: RngDomain, voxel: &Vec3<i32>) -> StdRng {
+        seeded_at(self.seed, domain, voxel)
+    }
+
+    /// Start every stream over from its seed
+    pub fn reset(&mut self) {
+        self.streams.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn streams() {
+        let draw = |rngs: &mut RngService, domain| rngs.stream(domain).gen::<u64>();
+
+        let mut rngs = RngService::new(1021);
+        let loot = draw(&mut rngs, RngDomain::Loot);
+
+        // drawing from another domain doesn't change what the loot stream draws next
+        let mut other = RngService::new(1021);
+        assert_eq!(draw(&mut other, RngDomain::Loot), loot);
+        for _ in 0..10 {
+            draw(&mut rngs, RngDomain::MobAi);
+        }
+        assert_eq!(
+            draw(&mut rngs, RngDomain::Loot),
+            draw(&mut other, RngDomain::Loot)
+        );
+
+        assert_ne!(
+            derive_seed(1021, RngDomain::Loot.tag()),
+            derive_seed(1021, RngDomain::MobAi.tag())
+        );
+        assert_ne!(derive_seed(1021, "loot"), derive_seed(1022, "loot"));
+
+        let voxel = Vec3(3, -4, 5);
+        assert_eq!(
+            rngs.at(RngDomain::Structures, &voxel).gen::<u64>(),
+            other.at(RngDomain::Structures, &voxel).gen::<u64>()
+        );
+        assert_ne!(
+            rngs.at(RngDomain::Structures, &voxel).gen::<u64>(),
+            rngs.at(RngDomain::Structures, &Vec3(3, -4, 6)).gen::<u64>()
+        );
+
+        rngs.reset();
+        assert_eq!(draw(&mut rngs, RngDomain::Loot), loot);
+    }
+}
@@ -0,0 +1,153 @@
+use std::{collections::BTreeMap, hash::Hash};
+
+use hashbrown::{HashMap, HashSet};
+
+use server_common::vec::Vec3;
+
+/// Resource scheduling voxels to be ticked on a later world tick, such as active furnaces
+#[derive(Debug, Default)]
+pub struct BlockTicks {
+    scheduled: BTreeMap<i32, Vec<Vec3<i32>>>,
+    pending: HashSet<Vec3<i32>>,
+}
+
+impl BlockTicks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedule a voxel to be ticked on a world tick, ignored if it's already scheduled
+    pub fn schedule(&mut self, voxel: Vec3<i32>, tick: i32) {
+        if self.pending.insert(voxel.to_owned()) {
+            self.scheduled.entry(tick).or_default().push(voxel);
+        }
+    }
+
+    /// Take the voxels due on or before a world tick, in scheduled order
+    pub fn take_due(&mut self, tick: i32) -> Vec<Vec3<i32>> {
+        let later = self.scheduled.split_off(&(tick + 1));
+        let due = std::mem::replace(&mut self.scheduled, later);
+
+        let due = due.into_values().flatten().collect::<Vec<_>>();
+        due.iter().for_each(|voxel| {
+            self.pending.remove(voxel);
+        });
+
+        due
+    }
+
+    /// Get the voxels matching a filter along with their due ticks, leaving them scheduled
+    pub fn peek_where<F: Fn(&Vec3<i32>) -> bool>(&self, filter: F) -> Vec<(Vec3<i32>, i32)> {
+        self.scheduled
+            .iter()
+            .flat_map(|(tick, voxels)| {
+                voxels
+                    .iter()
+                    .filter(|voxel| filter(voxel))
+                    .map(move |voxel| (voxel.to_owned(), *tick))
+            })
+            .collect()
+    }
+
+    /// Take the voxels matching a filter along with their due ticks, such as those of a chunk
+    pub fn take_where<F: Fn(&Vec3<i32>) -> bool>(&mut self, filter: F) -> Vec<(Vec3<i32>, i32)> {
+        let mut taken = vec![];
+
+        for (tick, voxels) in self.scheduled.iter_mut() {
+            voxels.retain(|voxel| {
+                if !filter(voxel) {
+                    return true;
+                }
+
+                taken.push((voxel.to_owned(), *tick));
+                false
+            });
+        }
+
+        self.scheduled.retain(|_, voxels| !voxels.is_empty());
+        taken.iter().for_each(|(voxel, _)| {
+            self.pending.remove(voxel);
+        });
+
+        taken
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Named timers going off on a world tick, rescheduling a timer replaces it
+#[derive(Debug)]
+pub struct Timers<K> {
+    due: HashMap<K, i32>,
+}
+
+impl<K: Clone + Eq + Hash + Ord> Timers<K> {
+    pub fn new() -> Self {
+        Self {
+            due: HashMap::new(),
+        }
+    }
+
+    pub fn schedule(&mut self, key: K, tick: i32) {
+        self.due.insert(key, tick);
+    }
+
+    /// Cancel a timer, returns false if it wasn't running
+    pub fn cancel(&mut self, key: &K) -> bool {
+        self.due.remove(key).is_some()
+    }
+
+    /// Ticks left until a timer goes off
+    pub fn remaining(&self, key: &K, tick: i32) -> Option<i32> {
+        self.due.get(key).map(|due| (due - tick).max(0))
+    }
+
+    /// Take the timers due on or before a world tick, earliest first
+    pub fn take_due(&mut self, tick: i32) -> Vec<K> {
+        let mut due = self
+            .due
+            .iter()
+            .filter(|(_, due)| **due <= tick)
+            .map(|(key, due)| (*due, key.to_owned()))
+            .collect::<Vec<_>>();
+        due.sort();
+
+        due.into_iter()
+            .map(|(_, key)| {
+                self.due.remove(&key);
+                key
+            })
+            .collect()
+    }
+}
+
+impl<K: Clone + Eq + Hash + Ord> Default for Timers<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_due() {
+        let mut ticks = BlockTicks::new();
+
+        ticks.schedule(Vec3(0, 0, 0), 5);
+        ticks.schedule(Vec3(1, 0, 0), 3);
+        ticks.schedule(Vec3(0, 0, 0), 4);
+
+        assert_eq!(ticks.len(), 2);
+        assert!(ticks.take_due(2).is_empty());
+        assert_eq!(ticks.take_due(5), vec![Vec3(1, 0, 0), Vec3(0, 0, 0)]);
+        assert!(ticks.is_empty());
+    }
+}
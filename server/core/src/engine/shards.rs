@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use specs::Entity;
+
+use std::collections::HashMap;
+
+use server_common::vec::Vec2;
+use server_utils::secret::constant_time_eq;
+
+use super::{items::ItemStack, trading::TradeOffer};
+
+/// A rectangle of chunks owned by a shard, bounds inclusive
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardRange {
+    /// Shard owning the chunks
+    pub id: String,
+    /// Address players are routed to, such as `game-2.example.com:4000`
+    pub address: String,
+    pub min: [i32; 2],
+    pub max: [i32; 2],
+}
+
+/// How a world's chunks are split between server processes
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardConfig {
+    /// Shard this process runs as, none to own the whole world
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Owners of chunk ranges, chunks outside every range belong to the first shard listed
+    #[serde(default)]
+    pub ranges: Vec<ShardRange>,
+    /// Shared by the shards of a world to authorize chunk handoffs
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// Live state of an entity within a chunk, handed off to another shard or saved with the chunk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityHandoff {
+    /// Missing from entities saved before they had UUIDs, which get new ones
+    #[serde(default)]
+    pub uuid: Option<String>,
+    pub etype: String,
+    pub position: [f32; 3],
+    pub rotation: [f32; 4],
+    #[serde(default)]
+    pub health: Option<f32>,
+    #[serde(default)]
+    pub metadata: Vec<(String, String)>,
+    /// Stack of an item drop
+    #[serde(default)]
+    pub item: Option<ItemStack>,
+    /// Stock of a trader that has been traded with
+    #[serde(default)]
+    pub trades: Option<Vec<TradeOffer>>,
+}
+
+/// Everything live about a chunk, sent to the shard taking it over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkHandoff {
+    pub coords: [i32; 2],
+    /// The chunk in its saved format
+    pub data: String,
+    /// Scheduled block ticks as voxels and their delay in ticks, relative so clocks can differ
+    pub ticks: Vec<([i32; 3], i32)>,
+    pub entities: Vec<EntityHandoff>,
+}
+
+/// Resource of the chunks handed off and kept until the shard taking them over accepts them,
+/// along with the entities that went with each
+pub type PendingHandoffs = HashMap<Vec2<i32>, Vec<Entity>>;
+
+impl ShardConfig {
+    /// Whether the world is split between shards at all
+    pub fn is_sharded(&self) -> bool {
+        self.id.is_some() && !self.ranges.is_empty()
+    }
+
+    /// Get the range owning a chunk, none if the world isn't sharded
+    pub fn owner(&self, coords: &Vec2<i32>) -> Option<&ShardRange> {
+        if !self.is_sharded() {
+            return None;
+        }
+
+        let Vec2(cx, cz) = *coords;
+
+        self.ranges
+            .iter()
+            .find(|range| {
+                cx >= range.min[0] && cx <= range.max[0] && cz >= range.min[1] && cz <= range.max[1]
+            })
+            .or_else(|| self.ranges.first())
+    }
+
+    /// Whether this process owns a chunk, always true if the world isn't sharded
+    pub fn is_local(&self, coords: &Vec2<i32>) -> bool {
+        match self.owner(coords) {
+            Some(range) => self.id.as_ref() == Some(&range.id),
+            None => true,
+        }
+    }
+
+    /// Address to route a connection to if another shard owns the chunk
+    pub fn route(&self, coords: &Vec2<i32>) -> Option<&str> {
+        if self.is_local(coords) {
+            return None;
+        }
+
+        self.owner(coords).map(|range| range.address.as_str())
+    }
+
+    /// Check the secret a handoff request came with
+    pub fn authorize(&self, secret: Option<&str>) -> bool {
+        match &self.secret {
+            Some(expected) => secret
+                .is_some_and(|secret| constant_time_eq(secret.as_bytes(), expected.as_bytes())),
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ownership() {
+        let range = |id: &str, min, max| ShardRange {
+            id: id.to_owned(),
+            address: format!("{}:4000", id),
+            min,
+            max,
+        };
+
+        let mut config = ShardConfig {
+            id: Some("west".to_owned()),
+            ranges: vec![
+                range("west", [-64, -64], [-1, 63]),
+                range("east", [0, -64], [63, 63]),
+            ],
+            secret: Some("hunter2".to_owned()),
+        };
+
+        assert!(config.is_local(&Vec2(-1, 0)));
+        assert_eq!(config.route(&Vec2(0, 0)), Some("east:4000"));
+        // outside every range falls back to the first shard
+        assert!(config.is_local(&Vec2(100, 100)));
+
+        assert!(config.authorize(Some("hunter2")));
+        assert!(!config.authorize(None));
+
+        config.ranges.clear();
+        assert!(config.is_local(&Vec2(0, 0)));
+        assert!(config.route(&Vec2(0, 0)).is_none());
+    }
+}
@@ -0,0 +1,94 @@
+use image::{io::Reader, ImageFormat};
+
+use std::{fs, io::Cursor, path::Path};
+
+use server_utils::hash::fnv1a;
+
+use super::clock::unix_millis;
+
+/// Folder that uploaded skins are stored in and served from under `/skins/`
+pub const SKINS_FOLDER: &str = "data/skins";
+
+/// Largest skin upload accepted in bytes
+pub const MAX_SKIN_SIZE: usize = 64 * 1024;
+
+/// Allowed skin dimensions, the modern and the legacy layout
+const SKIN_DIMENSIONS: [(u32, u32); 2] = [(64, 64), (64, 32)];
+
+const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Make sure an upload is a PNG skin of a supported size
+pub fn validate_skin(data: &[u8]) -> Result<(), String> {
+    if data.len() > MAX_SKIN_SIZE {
+        return Err(format!("Skin is larger than {} bytes.", MAX_SKIN_SIZE));
+    }
+
+    if !data.starts_with(PNG_SIGNATURE) {
+        return Err("Skin is not a PNG.".to_owned());
+    }
+
+    // the header is enough to reject sizes that would take a lot of memory to decode
+    let dimensions = Reader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .ok_or_else(|| "Skin could not be decoded.".to_owned())?;
+
+    if !SKIN_DIMENSIONS.contains(&dimensions) {
+        return Err("Skin must be 64x64 or 64x32.".to_owned());
+    }
+
+    image::load_from_memory_with_format(data, ImageFormat::Png)
+        .map_err(|_| "Skin could not be decoded.".to_owned())?;
+
+    Ok(())
+}
+
+/// File name of a world or player, the characters safe in a file name followed by a hash of
+/// the whole name, so that names differing only in the characters left out don't collide
+pub fn file_key(name: &str) -> String {
+    let safe = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect::<String>();
+
+    format!("{}-{:016x}", safe, fnv1a(name.as_bytes()))
+}
+
+/// Validate and store a player's skin, returning the URL it's served at
+///
+/// The URL changes on every upload so clients don't keep a cached older skin.
+pub fn save_skin(world_name: &str, player_name: &str, data: &[u8]) -> Result<String, String> {
+    validate_skin(data)?;
+
+    if player_name.is_empty() {
+        return Err("Player name is empty.".to_owned());
+    }
+
+    let world_name = file_key(world_name);
+    let player_name = file_key(player_name);
+
+    let folder = Path::new(SKINS_FOLDER).join(&world_name);
+    fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+    fs::write(folder.join(format!("{}.png", player_name)), data).map_err(|e| e.to_string())?;
+
+    Ok(format!(
+        "/skins/{}/{}.png?v={}",
+        world_name,
+        player_name,
+        unix_millis()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_keys() {
+        assert_ne!(file_key("a.b"), file_key("ab"));
+        assert_ne!(file_key("名前"), file_key("なまえ"));
+        assert_eq!(file_key("ian"), file_key("ian"));
+        assert!(file_key("../ian").starts_with("ian-"));
+    }
+}
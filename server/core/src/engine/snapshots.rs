@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+/// Resource pacing entity snapshots, which clients interpolate between
+///
+/// Entities are sent at `rate` snapshots per second instead of every tick, each snapshot
+/// stamped with the server time it was taken at.
+#[derive(Debug, Default)]
+pub struct Snapshots {
+    interval: Option<Duration>,
+    last: Option<Instant>,
+}
+
+impl Snapshots {
+    /// Take `rate` snapshots per second, 0 for one every tick
+    pub fn new(rate: u32) -> Self {
+        Self {
+            interval: if rate == 0 {
+                None
+            } else {
+                Some(Duration::from_secs(1) / rate)
+            },
+            last: None,
+        }
+    }
+
+    /// Whether a snapshot is due this tick, starting the next interval if so
+    pub fn due(&mut self, now: Instant) -> bool {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return true,
+        };
+
+        match self.last {
+            Some(last) if now.duration_since(last) < interval => false,
+            Some(last) => {
+                // stay on schedule rather than drifting by however late this tick is
+                let behind = now.duration_since(last) - interval;
+                self.last = Some(now - behind.min(interval));
+                true
+            }
+            None => {
+                self.last = Some(now);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pacing() {
+        let start = Instant::now();
+
+        let mut every_tick = Snapshots::new(0);
+        assert!(every_tick.due(start));
+        assert!(every_tick.due(start));
+
+        let mut snapshots = Snapshots::new(10);
+        let sent = (0..60)
+            .filter(|i| snapshots.due(start + Duration::from_millis(1000 * i / 60)))
+            .count();
+
+        assert_eq!(sent, 10);
+    }
+}
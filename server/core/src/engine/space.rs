@@ -1,6 +1,6 @@
 use hashbrown::HashMap;
 
-use super::{chunks::Chunks, world::WorldConfig};
+use super::{chunks::Chunks, config::WorldConfig};
 
 use server_common::{
     ndarray::Ndarray,
@@ -10,6 +10,8 @@ use server_utils::convert::{map_voxel_to_chunk, map_voxel_to_chunk_local};
 
 /// A data access model that samples a 3D space of voxels in the world.
 /// Used for reference in other threads.
+///
+/// Heights and `y` coordinates are counted up from the bottom of the world, as chunks store them.
 pub struct Space {
     pub width: usize,
     pub shape: Vec<usize>,
@@ -21,21 +23,18 @@ pub struct Space {
 }
 
 impl Space {
-    pub fn new(chunks: &Chunks, Vec2(cx, cz): &Vec2<i32>, margin: usize) -> Self {
-        assert!(margin > 0, "Margin of 0 on Space is wasteful");
-
+    pub fn new(chunks: &Chunks, coords: &Vec2<i32>, margin: usize) -> Self {
         let WorldConfig {
             chunk_size,
             max_height,
             ..
         } = *chunks.config;
 
-        let width = chunk_size + margin * 2;
+        let Vec2(cx, cz) = *coords;
 
         let mut voxels = HashMap::new();
         let mut height_maps = HashMap::new();
 
-        let chunk_size = chunks.config.chunk_size;
         let extended = (margin as f32 / chunk_size as f32).ceil() as i32;
 
         for x in -extended..(extended + 1) {
@@ -49,12 +48,36 @@ impl Space {
             }
         }
 
+        Space::from_raw(
+            coords,
+            margin,
+            chunk_size,
+            max_height as usize,
+            voxels,
+            height_maps,
+        )
+    }
+
+    /// A space around a chunk from voxel data and height maps of the chunks around it,
+    /// for callers with no `Chunks` of their own
+    pub fn from_raw(
+        Vec2(cx, cz): &Vec2<i32>,
+        margin: usize,
+        chunk_size: usize,
+        max_height: usize,
+        voxels: HashMap<Vec2<i32>, Ndarray<u32>>,
+        height_maps: HashMap<Vec2<i32>, Ndarray<u32>>,
+    ) -> Self {
+        assert!(margin > 0, "Margin of 0 on Space is wasteful");
+
+        let width = chunk_size + margin * 2;
+
         let cs = chunk_size as i32;
         let m = margin as i32;
         // i'm not sure why it needs a +1 here, but it does.
         let min = Vec3(cx * cs - m + 1, 0, cz * cs - m + 1);
 
-        let shape = vec![width, max_height as usize, width];
+        let shape = vec![width, max_height, width];
 
         Self {
             width,
@@ -79,7 +102,7 @@ impl Space {
         }
     }
 
-    /// Access the max height by voxel column within the space
+    /// Access the max height by voxel column within the space, up from the bottom of the world
     ///
     /// `x,y,z` in terms of voxels
     pub fn get_max_height(&self, vx: i32, vz: i32) -> u32 {
@@ -0,0 +1,416 @@
+use std::{
+    fmt::Debug,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(feature = "sled")]
+use log::info;
+
+use serde::Deserialize;
+
+use super::object_storage::{ObjectStorage, ObjectStorageConfig, UPLOAD_INTERVAL};
+
+/// Where a world persists its chunks, players and metadata
+///
+/// Keys are plain names, such as a chunk's name or `world.json` for metadata.
+pub trait Storage: Debug + Send + Sync {
+    fn get_chunk(&self, name: &str) -> Option<Vec<u8>>;
+    fn put_chunk(&self, name: &str, data: &[u8]);
+
+    fn get_player(&self, name: &str) -> Option<Vec<u8>>;
+    fn put_player(&self, name: &str, data: &[u8]);
+
+    fn get_metadata(&self, key: &str) -> Option<Vec<u8>>;
+    fn put_metadata(&self, key: &str, data: &[u8]);
+
+    /// Meshes cached for a chunk, none if the backend doesn't keep them
+    fn get_meshes(&self, _name: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Cache the meshes of a chunk, where the backend keeps them
+    fn put_meshes(&self, _name: &str, _data: &[u8]) {}
+
+    /// Make sure every put has reached the disk, without waiting on remote backends
+    fn flush(&self) {}
+
+    /// Flush, then wait for remote backends to hold every put too, before the process exits
+    fn sync(&self) {
+        self.flush();
+    }
+
+    /// Names of every stored chunk, none if the backend can't list them
+    fn chunk_names(&self) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Delete a stored chunk along with its meshes, to be generated anew if it's ever loaded again
+    fn remove_chunk(&self, _name: &str) {}
+
+    /// Reclaim the space of removed chunks, where the backend doesn't on its own
+    fn compact(&self) {}
+}
+
+/// Storage backend selected in a world's config
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StorageKind {
+    /// A JSON file per chunk and player within the world folder, easy to inspect and back up
+    #[default]
+    Files,
+    /// A single embedded database within the world folder, requires the `sled` feature
+    Sled,
+    /// An S3-compatible bucket cached in the world folder, requires the `s3` feature
+    S3,
+}
+
+impl StorageKind {
+    /// Check that this server was built with the backend, and that it's configured
+    pub fn validate(&self, object_storage: Option<&ObjectStorageConfig>) -> Result<(), String> {
+        match self {
+            StorageKind::Sled if !cfg!(feature = "sled") => {
+                Err("sled storage requires the server to be built with `sled`".to_owned())
+            }
+            StorageKind::S3 if !cfg!(feature = "s3") => {
+                Err("s3 storage requires the server to be built with `s3`".to_owned())
+            }
+            StorageKind::S3 if object_storage.is_none() => {
+                Err("s3 storage requires an `objectStorage` config".to_owned())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Open the storage of a world within its folder, of a kind that passed validation
+pub fn open_storage(
+    kind: StorageKind,
+    object_storage: Option<&ObjectStorageConfig>,
+    folder: &Path,
+) -> Arc<dyn Storage> {
+    match kind {
+        StorageKind::Files => Arc::new(FileStorage::new(folder)),
+        #[cfg(feature = "sled")]
+        StorageKind::Sled => Arc::new(SledStorage::new(folder)),
+        #[cfg(not(feature = "sled"))]
+        StorageKind::Sled => panic!("Sled storage requires the server to be built with `sled`."),
+        StorageKind::S3 => {
+            let config = object_storage.expect("S3 storage requires an `objectStorage` config.");
+            Arc::new(ObjectStorage::new(
+                open_s3(config),
+                &folder.join(OBJECT_CACHE_FOLDER),
+                UPLOAD_INTERVAL,
+            ))
+        }
+    }
+}
+
+#[cfg(feature = "s3")]
+fn open_s3(config: &ObjectStorageConfig) -> Arc<dyn super::object_storage::ObjectStore> {
+    Arc::new(super::object_storage::S3Store::new(config))
+}
+
+#[cfg(not(feature = "s3"))]
+fn open_s3(_: &ObjectStorageConfig) -> Arc<dyn super::object_storage::ObjectStore> {
+    panic!("S3 storage requires the server to be built with `s3`.")
+}
+
+/// Folder within the world folder caching the objects of an object storage
+pub const OBJECT_CACHE_FOLDER: &str = "cache";
+
+/// Folder within the world folder holding a file per chunk
+pub const CHUNKS_FOLDER: &str = "chunks";
+
+/// Folder within the world folder holding the cached meshes of chunks, a file per chunk
+pub const MESHES_FOLDER: &str = "meshes";
+
+/// Folder within the world folder holding a file per player
+pub const PLAYERS_FOLDER: &str = "players";
+
+/// Flat files within the world folder, metadata at its root
+#[derive(Debug)]
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(root: &Path) -> Self {
+        fs::create_dir_all(root.join(CHUNKS_FOLDER)).expect("Unable to create chunks directory...");
+        fs::create_dir_all(root.join(PLAYERS_FOLDER)).expect("Unable to create players directory.");
+        fs::create_dir_all(root.join(MESHES_FOLDER)).expect("Unable to create meshes directory.");
+
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    pub fn chunk_folder(&self) -> PathBuf {
+        self.root.join(CHUNKS_FOLDER)
+    }
+
+    fn read(&self, path: PathBuf) -> Option<Vec<u8>> {
+        fs::read(path).ok()
+    }
+
+    /// Written to a temporary file first, so that a crash mid-write leaves the old file intact
+    fn write(&self, path: PathBuf, data: &[u8]) {
+        let temp = path.with_extension("tmp");
+
+        let mut file = File::create(&temp).expect("Unable to create file.");
+        file.write_all(data).expect("Unable to write to file.");
+        file.sync_data().expect("Unable to write to file.");
+
+        fs::rename(&temp, &path).expect("Unable to write to file.");
+    }
+}
+
+impl Storage for FileStorage {
+    fn get_chunk(&self, name: &str) -> Option<Vec<u8>> {
+        self.read(self.root.join(CHUNKS_FOLDER).join(format!("{}.json", name)))
+    }
+
+    fn put_chunk(&self, name: &str, data: &[u8]) {
+        self.write(
+            self.root.join(CHUNKS_FOLDER).join(format!("{}.json", name)),
+            data,
+        );
+    }
+
+    fn get_meshes(&self, name: &str) -> Option<Vec<u8>> {
+        self.read(
+            self.root
+                .join(MESHES_FOLDER)
+                .join(format!("{}.meshes", name)),
+        )
+    }
+
+    fn put_meshes(&self, name: &str, data: &[u8]) {
+        self.write(
+            self.root
+                .join(MESHES_FOLDER)
+                .join(format!("{}.meshes", name)),
+            data,
+        );
+    }
+
+    fn get_player(&self, name: &str) -> Option<Vec<u8>> {
+        self.read(
+            self.root
+                .join(PLAYERS_FOLDER)
+                .join(format!("{}.json", name)),
+        )
+    }
+
+    fn put_player(&self, name: &str, data: &[u8]) {
+        self.write(
+            self.root
+                .join(PLAYERS_FOLDER)
+                .join(format!("{}.json", name)),
+            data,
+        );
+    }
+
+    fn get_metadata(&self, key: &str) -> Option<Vec<u8>> {
+        self.read(self.root.join(key))
+    }
+
+    /// Keys with a slash are files within folders of the world folder, such as the schematics of
+    /// arenas
+    fn put_metadata(&self, key: &str, data: &[u8]) {
+        let path = self.root.join(key);
+
+        if let Some(folder) = path.parent() {
+            fs::create_dir_all(folder).expect("Unable to create metadata directory.");
+        }
+
+        self.write(path, data);
+    }
+
+    fn chunk_names(&self) -> Option<Vec<String>> {
+        let entries = fs::read_dir(self.chunk_folder()).ok()?;
+
+        Some(
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter_map(|file| file.strip_suffix(".json").map(str::to_owned))
+                .collect(),
+        )
+    }
+
+    fn remove_chunk(&self, name: &str) {
+        let _ = fs::remove_file(self.chunk_folder().join(format!("{}.json", name)));
+        let _ = fs::remove_file(
+            self.root
+                .join(MESHES_FOLDER)
+                .join(format!("{}.meshes", name)),
+        );
+    }
+
+    /// Files free their space once removed, only the writes a crash left half done remain
+    fn compact(&self) {
+        for folder in [CHUNKS_FOLDER, PLAYERS_FOLDER, MESHES_FOLDER].iter() {
+            if let Ok(entries) = fs::read_dir(self.root.join(folder)) {
+                for entry in entries.flatten() {
+                    if entry.path().extension().is_some_and(|ext| ext == "tmp") {
+                        let _ = fs::remove_file(entry.path());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Database file within the world folder used by the sled storage
+#[cfg(feature = "sled")]
+pub const SLED_FOLDER: &str = "world.sled";
+
+/// An embedded sled database within the world folder, a tree per kind of data
+#[cfg(feature = "sled")]
+#[derive(Debug)]
+pub struct SledStorage {
+    db: sled::Db,
+    chunks: sled::Tree,
+    meshes: sled::Tree,
+    players: sled::Tree,
+    metadata: sled::Tree,
+}
+
+#[cfg(feature = "sled")]
+impl SledStorage {
+    pub fn new(root: &Path) -> Self {
+        let path = root.join(SLED_FOLDER);
+        let db = sled::open(&path).expect("Unable to open sled database.");
+
+        info!("Opened sled database at {:?}.", path);
+
+        Self {
+            chunks: db.open_tree("chunks").unwrap(),
+            meshes: db.open_tree("meshes").unwrap(),
+            players: db.open_tree("players").unwrap(),
+            metadata: db.open_tree("metadata").unwrap(),
+            db,
+        }
+    }
+
+    fn get(tree: &sled::Tree, key: &str) -> Option<Vec<u8>> {
+        tree.get(key)
+            .expect("Unable to read from sled database.")
+            .map(|value| value.to_vec())
+    }
+
+    fn put(tree: &sled::Tree, key: &str, data: &[u8]) {
+        tree.insert(key, data)
+            .expect("Unable to write to sled database.");
+    }
+}
+
+#[cfg(feature = "sled")]
+impl Storage for SledStorage {
+    fn get_chunk(&self, name: &str) -> Option<Vec<u8>> {
+        Self::get(&self.chunks, name)
+    }
+
+    fn put_chunk(&self, name: &str, data: &[u8]) {
+        Self::put(&self.chunks, name, data);
+    }
+
+    fn get_meshes(&self, name: &str) -> Option<Vec<u8>> {
+        Self::get(&self.meshes, name)
+    }
+
+    fn put_meshes(&self, name: &str, data: &[u8]) {
+        Self::put(&self.meshes, name, data);
+    }
+
+    fn get_player(&self, name: &str) -> Option<Vec<u8>> {
+        Self::get(&self.players, name)
+    }
+
+    fn put_player(&self, name: &str, data: &[u8]) {
+        Self::put(&self.players, name, data);
+    }
+
+    fn get_metadata(&self, key: &str) -> Option<Vec<u8>> {
+        Self::get(&self.metadata, key)
+    }
+
+    fn put_metadata(&self, key: &str, data: &[u8]) {
+        Self::put(&self.metadata, key, data);
+    }
+
+    fn flush(&self) {
+        self.db.flush().expect("Unable to flush sled database.");
+    }
+
+    fn chunk_names(&self) -> Option<Vec<String>> {
+        Some(
+            self.chunks
+                .iter()
+                .keys()
+                .flatten()
+                .map(|key| String::from_utf8_lossy(&key).into_owned())
+                .collect(),
+        )
+    }
+
+    fn remove_chunk(&self, name: &str) {
+        self.chunks
+            .remove(name)
+            .expect("Unable to write to sled database.");
+        self.meshes
+            .remove(name)
+            .expect("Unable to write to sled database.");
+    }
+
+    /// Sled reuses the segments of removed chunks by itself once they're flushed
+    fn compact(&self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files() {
+        let root = std::env::temp_dir().join(format!("mine-storage-{}", std::process::id()));
+        let storage = open_storage(StorageKind::Files, None, &root);
+
+        assert!(storage.get_chunk("0|0").is_none());
+
+        storage.put_chunk("0|0", b"chunk");
+        storage.put_player("steve", b"player");
+        storage.put_metadata("world.json", b"{}");
+
+        assert_eq!(storage.get_chunk("0|0"), Some(b"chunk".to_vec()));
+        assert_eq!(storage.get_player("steve"), Some(b"player".to_vec()));
+        assert_eq!(storage.get_metadata("world.json"), Some(b"{}".to_vec()));
+
+        storage.put_meshes("0|0", b"meshes");
+        assert_eq!(storage.get_meshes("0|0"), Some(b"meshes".to_vec()));
+
+        // cached meshes aren't chunks, and go with theirs
+        assert_eq!(storage.chunk_names(), Some(vec!["0|0".to_owned()]));
+        storage.remove_chunk("0|0");
+        assert!(storage.get_chunk("0|0").is_none());
+        assert!(storage.get_meshes("0|0").is_none());
+        assert_eq!(storage.chunk_names(), Some(vec![]));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn kinds() {
+        assert!(StorageKind::Files.validate(None).is_ok());
+        assert_eq!(
+            StorageKind::Sled.validate(None).is_ok(),
+            cfg!(feature = "sled")
+        );
+        assert!(StorageKind::S3.validate(None).is_err());
+    }
+}
@@ -0,0 +1,401 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use specs::Entity;
+
+use server_common::{aabb::Aabb, math::approx_equals, vec::Vec3};
+use server_utils::sweep::sweep;
+
+/// Ticks a recording runs for unless asked otherwise
+pub const DEFAULT_RECORDED_TICKS: u32 = 200;
+
+/// Most ticks a recording runs for
+pub const MAX_RECORDED_TICKS: u32 = 1200;
+
+/// Largest a recording is written as, the sweeps past it being dropped
+pub const MAX_RECORDING_BYTES: usize = 8 * 1024 * 1024;
+
+/// Most recordings kept in the traces folder, the oldest being removed past it
+pub const MAX_RECORDINGS: usize = 16;
+
+/// Folder recordings are written to, within the world folder if the world is saved
+pub const TRACES_FOLDER: &str = "traces";
+
+/// Sweeps traced while iterating a body, filled in by the physics
+pub type Traces = RefCell<Vec<SweepTrace>>;
+
+/// File a recording was written to with its number of sweeps, or why it wasn't
+pub type Written = Result<(PathBuf, usize), String>;
+
+/// How the physics responds to a sweep running into a voxel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SweepKind {
+    /// Stop along the axis hit and keep going along the others, moving a body
+    Collide,
+    /// Keep going only past floors and ceilings, moving an auto-stepping body up to a wall
+    Approach,
+    /// Stop at the first hit, checking whether a body rests on something or has room above
+    Probe,
+}
+
+impl SweepKind {
+    /// Respond to a hit along an axis, true to stop the sweep
+    pub fn respond(self, axis: usize, vec: &mut Vec3<f32>) -> bool {
+        match self {
+            SweepKind::Collide => {
+                vec[axis] = 0.0;
+                false
+            }
+            SweepKind::Approach if axis == 1 => {
+                vec[axis] = 0.0;
+                false
+            }
+            SweepKind::Approach | SweepKind::Probe => true,
+        }
+    }
+}
+
+/// A voxel a sweep ran into
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SweepHit {
+    pub axis: usize,
+    pub dir: i32,
+    /// Distance swept before the hit
+    pub t: f32,
+}
+
+/// Everything a sweep was given and came up with, enough to run it again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepTrace {
+    pub tick: i32,
+    pub kind: SweepKind,
+    /// Corners of the box swept
+    pub base: [f32; 3],
+    pub max: [f32; 3],
+    pub dir: [f32; 3],
+    pub no_translate: bool,
+    /// Solid voxels the sweep tested, any other voxel being empty
+    pub solids: Vec<[i32; 3]>,
+    pub hits: Vec<SweepHit>,
+    /// How far the box was moved
+    pub translation: [f32; 3],
+    pub distance: f32,
+}
+
+/// Sweep a box as the physics does, tracing the sweep if `traces` is given
+///
+/// Returns the distance swept along with the voxels run into, in order.
+pub fn traced_sweep(
+    kind: SweepKind,
+    test_solid: &dyn Fn(i32, i32, i32) -> bool,
+    aabb: &mut Aabb,
+    dir: &Vec3<f32>,
+    no_translate: bool,
+    traces: Option<&Traces>,
+) -> (f32, Vec<SweepHit>) {
+    let hits = Arc::new(Mutex::new(vec![]));
+    let recorded = hits.clone();
+
+    let solids = RefCell::new(HashSet::new());
+    let test = |vx: i32, vy: i32, vz: i32| {
+        let solid = test_solid(vx, vy, vz);
+        if solid && traces.is_some() {
+            solids.borrow_mut().insert([vx, vy, vz]);
+        }
+        solid
+    };
+
+    let before = aabb.clone();
+    let distance = sweep(
+        &test,
+        aabb,
+        dir,
+        &mut move |t, axis, dir, vec| {
+            recorded.lock().unwrap().push(SweepHit { axis, dir, t });
+            kind.respond(axis, vec)
+        },
+        no_translate,
+    );
+
+    let hits = hits.lock().unwrap().to_owned();
+
+    if let Some(traces) = traces {
+        let mut solids = solids.into_inner().into_iter().collect::<Vec<_>>();
+        solids.sort_unstable();
+
+        let moved = aabb.base.sub(&before.base);
+        traces.borrow_mut().push(SweepTrace {
+            tick: 0,
+            kind,
+            base: [before.base.0, before.base.1, before.base.2],
+            max: [before.max.0, before.max.1, before.max.2],
+            dir: [dir.0, dir.1, dir.2],
+            no_translate,
+            solids,
+            hits: hits.to_owned(),
+            translation: [moved.0, moved.1, moved.2],
+            distance,
+        });
+    }
+
+    (distance, hits)
+}
+
+/// Run a traced sweep again with the current code, from the same box among the same voxels
+pub fn replay(trace: &SweepTrace) -> SweepTrace {
+    let solids = trace.solids.iter().copied().collect::<HashSet<_>>();
+    let test_solid = |vx: i32, vy: i32, vz: i32| solids.contains(&[vx, vy, vz]);
+
+    let [bx, by, bz] = trace.base;
+    let [mx, my, mz] = trace.max;
+    let mut aabb = Aabb::new(&Vec3(bx, by, bz), &Vec3(mx - bx, my - by, mz - bz));
+
+    let [dx, dy, dz] = trace.dir;
+    let traces = Traces::default();
+    traced_sweep(
+        trace.kind,
+        &test_solid,
+        &mut aabb,
+        &Vec3(dx, dy, dz),
+        trace.no_translate,
+        Some(&traces),
+    );
+
+    let mut replayed = traces.into_inner().remove(0);
+    replayed.tick = trace.tick;
+    replayed
+}
+
+/// How a replayed sweep differs from its trace, none if it came out the same
+pub fn compare(trace: &SweepTrace, replayed: &SweepTrace) -> Option<String> {
+    let same = |a: &[f32], b: &[f32]| a.iter().zip(b).all(|(a, b)| approx_equals(a, b));
+
+    let same_hits = trace.hits.len() == replayed.hits.len()
+        && trace
+            .hits
+            .iter()
+            .zip(&replayed.hits)
+            .all(|(a, b)| a.axis == b.axis && a.dir == b.dir && approx_equals(&a.t, &b.t));
+
+    if same_hits
+        && same(&trace.translation, &replayed.translation)
+        && approx_equals(&trace.distance, &replayed.distance)
+    {
+        return None;
+    }
+
+    Some(format!(
+        "Sweep of tick {} moved {:?} hitting {:?}, now moves {:?} hitting {:?}.",
+        trace.tick, trace.translation, trace.hits, replayed.translation, replayed.hits
+    ))
+}
+
+/// A file of the sweeps of an entity over some ticks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepRecording {
+    /// Name or id of the entity recorded
+    pub entity: String,
+    pub sweeps: Vec<SweepTrace>,
+}
+
+/// Replay the sweeps of a recording, returning how those that changed differ
+pub fn replay_file(path: &Path) -> Result<Vec<String>, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    let recording: SweepRecording = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+
+    Ok(recording
+        .sweeps
+        .iter()
+        .filter_map(|trace| compare(trace, &replay(trace)))
+        .collect())
+}
+
+struct Recording {
+    entity: Entity,
+    name: String,
+    /// Player who asked for the recording, told when it's written
+    requester: usize,
+    ticks_left: u32,
+    sweeps: Vec<SweepTrace>,
+    /// Size of the sweeps once written
+    bytes: usize,
+    file: PathBuf,
+}
+
+/// Resource recording the sweeps of a single entity, written to a file after a number of ticks
+#[derive(Default)]
+pub struct SweepRecorder {
+    recording: Option<Recording>,
+}
+
+impl SweepRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an entity's sweeps for some ticks into a file, replacing the running recording
+    pub fn start(
+        &mut self,
+        entity: Entity,
+        name: &str,
+        requester: usize,
+        ticks: u32,
+        file: PathBuf,
+    ) {
+        self.recording = Some(Recording {
+            entity,
+            name: name.to_owned(),
+            requester,
+            ticks_left: ticks.clamp(1, MAX_RECORDED_TICKS),
+            sweeps: vec![],
+            bytes: 0,
+            file,
+        });
+    }
+
+    /// Entity being recorded
+    pub fn target(&self) -> Option<Entity> {
+        self.recording.as_ref().map(|recording| recording.entity)
+    }
+
+    /// Add the sweeps an iteration of the recorded body traced
+    ///
+    /// Once the recording would grow past `MAX_RECORDING_BYTES`, it's written on the next tick.
+    pub fn record(&mut self, tick: i32, traces: Vec<SweepTrace>) {
+        if let Some(recording) = &mut self.recording {
+            for trace in traces {
+                let trace = SweepTrace { tick, ..trace };
+                // one more byte for the comma separating sweeps
+                let bytes = serde_json::to_vec(&trace).unwrap().len() + 1;
+
+                if recording.bytes + bytes > MAX_RECORDING_BYTES {
+                    recording.ticks_left = 1;
+                    break;
+                }
+
+                recording.bytes += bytes;
+                recording.sweeps.push(trace);
+            }
+        }
+    }
+
+    /// Count a tick off, writing the recording once it's done
+    ///
+    /// Returns who asked for it, and the file with the number of sweeps written or why it wasn't.
+    pub fn tick(&mut self) -> Option<(usize, Written)> {
+        let recording = self.recording.as_mut()?;
+        recording.ticks_left = recording.ticks_left.saturating_sub(1);
+
+        if recording.ticks_left > 0 {
+            return None;
+        }
+
+        self.stop()
+    }
+
+    /// Write the recording before it's done, none if nothing was being recorded
+    pub fn stop(&mut self) -> Option<(usize, Written)> {
+        let Recording {
+            name,
+            requester,
+            sweeps,
+            file,
+            ..
+        } = self.recording.take()?;
+
+        let count = sweeps.len();
+        let recording = SweepRecording {
+            entity: name,
+            sweeps,
+        };
+
+        let written = file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&file, serde_json::to_string(&recording).unwrap()))
+            .map(|_| (file, count))
+            .map_err(|e| e.to_string());
+
+        if let Ok((file, _)) = &written {
+            if let Some(folder) = file.parent() {
+                prune_recordings(folder);
+            }
+        }
+
+        Some((requester, written))
+    }
+}
+
+/// Remove the oldest recordings of a folder, keeping `MAX_RECORDINGS`
+fn prune_recordings(folder: &Path) {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    let mut recordings = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("sweeps-") && name.ends_with(".json")
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect::<Vec<_>>();
+
+    if recordings.len() <= MAX_RECORDINGS {
+        return;
+    }
+
+    recordings.sort();
+
+    let excess = recordings.len() - MAX_RECORDINGS;
+    for (_, path) in recordings.into_iter().take(excess) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays() {
+        // a floor at y = 0, and a wall at x = 2
+        let test_solid = |vx: i32, vy: i32, _: i32| vy == 0 || vx == 2;
+        let mut aabb = Aabb::new(&Vec3(0.2, 1.5, 0.2), &Vec3(0.6, 1.8, 0.6));
+
+        let traces = Traces::default();
+        traced_sweep(
+            SweepKind::Collide,
+            &test_solid,
+            &mut aabb,
+            &Vec3(3.0, -2.0, 0.0),
+            false,
+            Some(&traces),
+        );
+
+        let trace = traces.into_inner().remove(0);
+        assert_eq!(trace.hits.len(), 2);
+        assert!(approx_equals(&aabb.base.1, &1.0));
+        assert!(approx_equals(&aabb.max.0, &2.0));
+
+        // the trace survives being written, and runs the same again
+        let json = serde_json::to_string(&trace).unwrap();
+        let trace: SweepTrace = serde_json::from_str(&json).unwrap();
+        assert_eq!(compare(&trace, &replay(&trace)), None);
+
+        // a change in the physics shows up as a difference
+        let mut changed = trace.clone();
+        changed.translation[1] = 0.0;
+        assert!(compare(&changed, &replay(&changed)).is_some());
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::{HashMap, HashSet};
+
+/// Blocks bodies can climb, on top of the ones declaring `isClimbable`
+pub const CLIMBABLE: &str = "climbable";
+
+/// Named groups of blocks, loaded from `assets/metadata/tags.json`
+///
+/// A tag lists block names and other tags prefixed with `#`, such as
+/// `"mineable/pickaxe": ["#stone", "Ice"]`, resolved into the ids of every block it holds.
+#[derive(Debug, Clone, Default)]
+pub struct Tags {
+    tags: HashMap<String, HashSet<u32>>,
+}
+
+impl Tags {
+    /// Resolve tag definitions against the block names
+    ///
+    /// Fails on unknown blocks or tags, and on tags that include themselves.
+    pub fn resolve(
+        definitions: &HashMap<String, Vec<String>>,
+        name_map: &HashMap<String, u32>,
+    ) -> Result<Self, String> {
+        let mut tags = HashMap::new();
+
+        for name in definitions.keys() {
+            Tags::resolve_tag(name, definitions, name_map, &mut tags, &mut vec![])?;
+        }
+
+        Ok(Self { tags })
+    }
+
+    fn resolve_tag(
+        name: &str,
+        definitions: &HashMap<String, Vec<String>>,
+        name_map: &HashMap<String, u32>,
+        tags: &mut HashMap<String, HashSet<u32>>,
+        visiting: &mut Vec<String>,
+    ) -> Result<HashSet<u32>, String> {
+        if let Some(ids) = tags.get(name) {
+            return Ok(ids.to_owned());
+        }
+
+        if visiting.iter().any(|visited| visited == name) {
+            return Err(format!(
+                "Tag #{} includes itself through {}",
+                name,
+                visiting.join(", #")
+            ));
+        }
+
+        let entries = definitions
+            .get(name)
+            .ok_or_else(|| format!("Unknown tag: #{}", name))?;
+
+        visiting.push(name.to_owned());
+
+        let mut ids = HashSet::new();
+        for entry in entries {
+            match entry.strip_prefix('#') {
+                Some(tag) => ids.extend(Tags::resolve_tag(
+                    tag,
+                    definitions,
+                    name_map,
+                    tags,
+                    visiting,
+                )?),
+                None => {
+                    let &id = name_map
+                        .get(entry)
+                        .ok_or_else(|| format!("Unknown block in tag #{}: {}", name, entry))?;
+                    ids.insert(id);
+                }
+            }
+        }
+
+        visiting.pop();
+        tags.insert(name.to_owned(), ids.to_owned());
+
+        Ok(ids)
+    }
+
+    /// Whether a block is in a tag, false for unknown tags
+    pub fn contains(&self, tag: &str, id: u32) -> bool {
+        self.tags.get(tag).is_some_and(|ids| ids.contains(&id))
+    }
+
+    /// Ids of the blocks in a tag
+    pub fn get(&self, tag: &str) -> Option<&HashSet<u32>> {
+        self.tags.get(tag)
+    }
+
+    /// Names of the tags a block is in
+    pub fn of(&self, id: u32) -> Vec<&str> {
+        let mut names = self
+            .tags
+            .iter()
+            .filter(|(_, ids)| ids.contains(&id))
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        let name_map = [("Stone", 3), ("Stone Bricks", 30), ("Ice", 7)]
+            .iter()
+            .map(|(name, id)| (name.to_string(), *id))
+            .collect();
+
+        let mut definitions = HashMap::new();
+        definitions.insert(
+            "stone".to_owned(),
+            vec!["Stone".to_owned(), "Stone Bricks".to_owned()],
+        );
+        definitions.insert(
+            "mineable/pickaxe".to_owned(),
+            vec!["#stone".to_owned(), "Ice".to_owned()],
+        );
+
+        let tags = Tags::resolve(&definitions, &name_map).unwrap();
+
+        assert!(tags.contains("mineable/pickaxe", 30));
+        assert!(tags.contains("mineable/pickaxe", 7));
+        assert!(!tags.contains("stone", 7));
+        assert!(!tags.contains("logs", 3));
+        assert_eq!(tags.of(3), vec!["mineable/pickaxe", "stone"]);
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::storage::Storage;
+
+/// Teams are saved under this metadata key of the world's storage
+pub const TEAMS_FILE: &str = "teams.json";
+
+/// Color of teams created without one
+pub const DEFAULT_TEAM_COLOR: &str = "#ffffff";
+
+/// A group of players sharing a name color, identified by player names
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Team {
+    pub name: String,
+    pub color: String,
+    /// Whether members can hurt each other
+    pub friendly_fire: bool,
+    pub members: HashSet<String>,
+}
+
+/// Resource of all teams of a world
+#[derive(Debug, Default)]
+pub struct Teams {
+    teams: BTreeMap<String, Team>,
+    storage: Option<Arc<dyn Storage>>,
+}
+
+impl Teams {
+    /// Load the teams saved in `storage`, if any
+    pub fn new(storage: Option<Arc<dyn Storage>>) -> Self {
+        let teams = storage
+            .as_ref()
+            .and_then(|storage| storage.get_metadata(TEAMS_FILE))
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+
+        Self { teams, storage }
+    }
+
+    /// Create a new team, returns false if the name is taken
+    pub fn create(&mut self, name: &str, color: Option<&str>) -> bool {
+        if self.teams.contains_key(name) {
+            return false;
+        }
+
+        self.teams.insert(
+            name.to_owned(),
+            Team {
+                name: name.to_owned(),
+                color: color.unwrap_or(DEFAULT_TEAM_COLOR).to_owned(),
+                friendly_fire: false,
+                members: HashSet::new(),
+            },
+        );
+
+        true
+    }
+
+    /// Remove a team, returning it with its former members
+    pub fn remove(&mut self, name: &str) -> Option<Team> {
+        self.teams.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Team> {
+        self.teams.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Team> {
+        self.teams.get_mut(name)
+    }
+
+    pub fn all(&self) -> impl Iterator<Item = &Team> {
+        self.teams.values()
+    }
+
+    /// Add a player to a team, leaving any previous team, returns false if there is no such team
+    pub fn join(&mut self, name: &str, player: &str) -> bool {
+        if !self.teams.contains_key(name) {
+            return false;
+        }
+
+        self.leave(player);
+        self.teams
+            .get_mut(name)
+            .unwrap()
+            .members
+            .insert(player.to_owned());
+
+        true
+    }
+
+    /// Remove a player from their team, returning the team's name
+    pub fn leave(&mut self, player: &str) -> Option<String> {
+        let team = self
+            .teams
+            .values_mut()
+            .find(|t| t.members.contains(player))?;
+        team.members.remove(player);
+
+        Some(team.name.to_owned())
+    }
+
+    /// Get the team of a player
+    pub fn team_of(&self, player: &str) -> Option<&Team> {
+        self.teams.values().find(|t| t.members.contains(player))
+    }
+
+    /// Check if one player is allowed to hurt another
+    pub fn can_hurt(&self, attacker: &str, target: &str) -> bool {
+        match (self.team_of(attacker), self.team_of(target)) {
+            (Some(a), Some(b)) if a.name == b.name => a.friendly_fire,
+            _ => true,
+        }
+    }
+
+    /// Save the teams into the world's storage
+    pub fn save(&self) {
+        if let Some(storage) = &self.storage {
+            let data = serde_json::to_vec(&self.teams).unwrap();
+            storage.put_metadata(TEAMS_FILE, &data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn friendly_fire() {
+        let mut teams = Teams::default();
+
+        assert!(teams.create("red", Some("#ff0000")));
+        assert!(!teams.create("red", None));
+        assert!(teams.create("blue", None));
+
+        teams.join("red", "steve");
+        teams.join("red", "alex");
+        assert!(!teams.can_hurt("steve", "alex"));
+
+        teams.get_mut("red").unwrap().friendly_fire = true;
+        assert!(teams.can_hurt("steve", "alex"));
+
+        teams.join("blue", "alex");
+        assert_eq!(teams.team_of("alex").unwrap().name, "blue");
+        assert!(!teams.get("red").unwrap().members.contains("alex"));
+        assert!(teams.can_hurt("steve", "notch"));
+    }
+}
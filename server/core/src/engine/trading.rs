@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    block_entities::MAX_STACK_SIZE,
+    container::{click_slot, SlotAction},
+    items::ItemStack,
+    registry::Registry,
+};
+
+/// Kind of container trade windows are sent to clients as
+pub const TRADER_KIND: &str = "trader";
+
+/// Slots of a trade window holding the payment, followed by the output slot
+pub const PAYMENT_SLOTS: usize = 2;
+
+/// Items a trader gives in exchange for others, until out of stock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeOffer {
+    /// Paid in the payment slots, in order
+    pub inputs: Vec<ItemStack>,
+    pub output: ItemStack,
+    /// Trades allowed before the offer is out of stock
+    pub max_uses: u32,
+    #[serde(default)]
+    pub uses: u32,
+}
+
+impl TradeOffer {
+    pub fn in_stock(&self) -> bool {
+        self.uses < self.max_uses
+    }
+
+    /// Whether the payment slots hold at least the inputs
+    pub fn is_paid(&self, payment: &[Option<ItemStack>]) -> bool {
+        self.inputs
+            .iter()
+            .zip(payment.iter())
+            .all(|(input, slot)| match slot {
+                Some(stack) => stack.id == input.id && stack.count >= input.count,
+                None => false,
+            })
+            && payment.len() >= self.inputs.len()
+    }
+}
+
+#[derive(Deserialize)]
+struct TradeItemData {
+    item: String,
+    #[serde(default = "default_count")]
+    count: u32,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TradeOfferData {
+    inputs: Vec<TradeItemData>,
+    output: TradeItemData,
+    max_uses: u32,
+}
+
+/// Resource of what each type of entity offers to trade, loaded from `assets/metadata/trades.json`
+///
+/// Traders get a copy of the list of their type the first time they're traded with, keeping
+/// their own stock from then on.
+#[derive(Debug, Default)]
+pub struct TradeLists {
+    lists: HashMap<String, Vec<TradeOffer>>,
+}
+
+impl TradeLists {
+    /// Panics on a broken trades file, see `TradeLists::load`
+    pub fn new(registry: &Registry) -> Self {
+        TradeLists::load(registry).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Load the trade lists, resolving block names through the registry
+    pub fn load(registry: &Registry) -> Result<Self, String> {
+        let data: HashMap<String, Vec<TradeOfferData>> = serde_json::from_value(
+            registry
+                .data_packs
+                .read("trades.json")
+                .ok_or("Unable to find trades.json")?,
+        )
+        .map_err(|e| format!("Unable to parse trades.json: {}", e))?;
+
+        let stack = |data: &TradeItemData| -> Result<ItemStack, String> {
+            let id = registry
+                .try_get_id_by_name(&data.item)
+                .ok_or_else(|| format!("Unknown block in trades: {}", data.item))?;
+
+            Ok(ItemStack::new(id, data.count.clamp(1, MAX_STACK_SIZE)))
+        };
+
+        let lists = data
+            .iter()
+            .map(|(etype, offers)| {
+                let offers = offers
+                    .iter()
+                    .map(|offer| {
+                        if offer.inputs.is_empty() || offer.inputs.len() > PAYMENT_SLOTS {
+                            return Err(format!(
+                                "Trades of {} take 1 to {} inputs.",
+                                etype, PAYMENT_SLOTS
+                            ));
+                        }
+
+                        Ok(TradeOffer {
+                            inputs: offer.inputs.iter().map(stack).collect::<Result<_, _>>()?,
+                            output: stack(&offer.output)?,
+                            max_uses: offer.max_uses,
+                            uses: 0,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+
+                Ok((etype.to_owned(), offers))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { lists })
+    }
+
+    /// Set the trade list of an entity type, for traders spawned from now on
+    pub fn set(&mut self, etype: &str, offers: Vec<TradeOffer>) {
+        self.lists.insert(etype.to_owned(), offers);
+    }
+
+    /// Get the trade list of an entity type, `None` if it doesn't trade
+    pub fn get(&self, etype: &str) -> Option<&Vec<TradeOffer>> {
+        self.lists.get(etype)
+    }
+}
+
+/// A player's open window into a trader, with the items put up as payment
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeWindow {
+    pub payment: Vec<Option<ItemStack>>,
+    /// Index of the offer chosen
+    pub selected: Option<usize>,
+}
+
+impl Default for TradeWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TradeWindow {
+    pub fn new() -> Self {
+        Self {
+            payment: vec![None; PAYMENT_SLOTS],
+            selected: None,
+        }
+    }
+
+    /// Choose an offer to trade, false if there's no such offer
+    pub fn select(&mut self, index: usize, offers: &[TradeOffer]) -> bool {
+        if index >= offers.len() {
+            return false;
+        }
+
+        self.selected = Some(index);
+        true
+    }
+
+    /// Output of the chosen offer, if it's paid for and in stock
+    pub fn output(&self, offers: &[TradeOffer]) -> Option<ItemStack> {
+        self.selected
+            .and_then(|index| offers.get(index))
+            .filter(|offer| offer.in_stock() && offer.is_paid(&self.payment))
+            .map(|offer| offer.output.to_owned())
+    }
+
+    /// Payment slots followed by the output slot
+    pub fn slots(&self, offers: &[TradeOffer]) -> Vec<Option<ItemStack>> {
+        let mut slots = self.payment.to_owned();
+        slots.push(self.output(offers));
+        slots
+    }
+
+    /// Empty the payment slots, to give the items back when the window closes
+    pub fn take_payment(&mut self) -> Vec<ItemStack> {
+        self.payment.iter_mut().filter_map(Option::take).collect()
+    }
+}
+
+/// Apply a slot click to a trade window, returns false if the click isn't allowed
+///
+/// The payment slots work like any other slot. Clicking the output takes what it holds into the
+/// cursor whole, consuming the payment and a use of the offer.
+pub fn click_trade(
+    window: &mut TradeWindow,
+    offers: &mut [TradeOffer],
+    index: usize,
+    action: SlotAction,
+    cursor: &mut Option<ItemStack>,
+) -> bool {
+    if index < PAYMENT_SLOTS {
+        return click_slot(&mut window.payment[index], cursor, action, true);
+    }
+
+    if index > PAYMENT_SLOTS {
+        return false;
+    }
+
+    let output = match window.output(offers) {
+        Some(output) => output,
+        None => return false,
+    };
+
+    match cursor {
+        None => *cursor = Some(output),
+        Some(held) if held.id == output.id && held.count + output.count <= MAX_STACK_SIZE => {
+            held.count += output.count
+        }
+        _ => return false,
+    }
+
+    let offer = &mut offers[window.selected.unwrap()];
+    offer.uses += 1;
+
+    for (slot, input) in window.payment.iter_mut().zip(offer.inputs.iter()) {
+        if let Some(stack) = slot {
+            stack.count -= input.count;
+
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trades() {
+        let mut offers = vec![TradeOffer {
+            inputs: vec![ItemStack::new(1, 4)],
+            output: ItemStack::new(2, 1),
+            max_uses: 1,
+            uses: 0,
+        }];
+
+        let mut window = TradeWindow::new();
+        let mut cursor = Some(ItemStack::new(1, 6));
+
+        assert!(!window.select(1, &offers));
+        assert!(window.select(0, &offers));
+        assert_eq!(window.output(&offers), None);
+
+        // pay, then take the output with an empty cursor
+        assert!(click_trade(
+            &mut window,
+            &mut offers,
+            0,
+            SlotAction::Pickup,
+            &mut cursor
+        ));
+        assert_eq!(window.output(&offers), Some(ItemStack::new(2, 1)));
+        assert!(click_trade(
+            &mut window,
+            &mut offers,
+            2,
+            SlotAction::Pickup,
+            &mut cursor
+        ));
+        assert_eq!(cursor, Some(ItemStack::new(2, 1)));
+        assert_eq!(window.payment[0], Some(ItemStack::new(1, 2)));
+
+        // out of stock
+        window.payment[0] = Some(ItemStack::new(1, 4));
+        assert_eq!(window.output(&offers), None);
+        assert!(!click_trade(
+            &mut window,
+            &mut offers,
+            2,
+            SlotAction::Pickup,
+            &mut cursor
+        ));
+        assert_eq!(window.take_payment(), vec![ItemStack::new(1, 4)]);
+    }
+}
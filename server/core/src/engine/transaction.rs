@@ -0,0 +1,188 @@
+use hashbrown::{HashMap, HashSet};
+
+use std::collections::VecDeque;
+
+use server_common::vec::Vec3;
+
+use crate::{gen::blocks::BlockRotation, network::models::messages};
+
+/// Voxel edits collected by `World::batch_update`, applied together
+///
+/// Setting the same voxel twice keeps the last edit.
+#[derive(Debug, Default)]
+pub struct VoxelTransaction {
+    updates: Vec<messages::Update>,
+}
+
+impl VoxelTransaction {
+    /// Set a voxel to a block type
+    pub fn set(&mut self, vx: i32, vy: i32, vz: i32, id: u32) {
+        self.set_rotated(vx, vy, vz, id, &BlockRotation::PY(0));
+    }
+
+    /// Set a voxel to a block type with rotation, ignored for non-rotatable blocks
+    pub fn set_rotated(&mut self, vx: i32, vy: i32, vz: i32, id: u32, rotation: &BlockRotation) {
+        let (rotation, y_rotation) = BlockRotation::decode(rotation);
+
+        self.updates.push(messages::Update {
+            vx,
+            vy,
+            vz,
+            r#type: id,
+            rotation,
+            y_rotation,
+            sequence: 0,
+        });
+    }
+
+    /// Number of queued edits
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    /// Whether no edits are queued
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    /// Consume the transaction into the updates to apply, last edit per voxel winning
+    pub fn into_updates(self) -> Vec<messages::Update> {
+        let mut seen = HashSet::new();
+
+        // the world pops updates from the back, so keep the earliest edit there
+        self.updates
+            .into_iter()
+            .rev()
+            .filter(|update| seen.insert((update.vx, update.vy, update.vz)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .collect()
+    }
+}
+
+/// Most edits kept per player to undo
+pub const MAX_EDIT_HISTORY: usize = 32;
+
+/// A reversible set of voxel edits
+#[derive(Debug, Clone, Default)]
+pub struct Edit {
+    /// Updates in the order they were applied
+    pub applied: Vec<messages::Update>,
+    /// What each applied voxel was before its update
+    pub previous: Vec<messages::Update>,
+}
+
+impl Edit {
+    /// Updates that revert this edit, ordered so the last applied voxel is reverted first
+    pub fn undo_updates(&self) -> Vec<messages::Update> {
+        self.previous.to_owned()
+    }
+
+    /// Updates that apply this edit again in its original order
+    pub fn redo_updates(&self) -> Vec<messages::Update> {
+        self.applied.iter().rev().cloned().collect()
+    }
+}
+
+/// Bounded undo and redo stacks of a player's edits
+#[derive(Debug, Default)]
+pub struct EditHistory {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl EditHistory {
+    /// Record a new edit, which discards everything that could be redone
+    pub fn record(&mut self, edit: Edit) {
+        if edit.applied.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push_back(edit);
+
+        if self.undo.len() > MAX_EDIT_HISTORY {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Take the latest edit to undo, moving it onto the redo stack
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop_back()?;
+        self.redo.push(edit.clone());
+        Some(edit)
+    }
+
+    /// Take the latest undone edit to redo, moving it back onto the undo stack
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.undo.push_back(edit.clone());
+        Some(edit)
+    }
+}
+
+/// Resource of the voxels players edited this tick, so that the first edit of a voxel wins
+#[derive(Debug, Default)]
+pub struct TickEdits(HashMap<Vec3<i32>, usize>);
+
+impl TickEdits {
+    /// Claim a voxel for a player's edit, false if another player edited it this tick
+    pub fn claim(&mut self, voxel: Vec3<i32>, player_id: usize) -> bool {
+        *self.0.entry(voxel).or_insert(player_id) == player_id
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_history() {
+        let mut transaction = VoxelTransaction::default();
+        transaction.set(0, 0, 0, 1);
+        transaction.set(1, 0, 0, 1);
+        transaction.set(0, 0, 0, 2);
+
+        let applied = transaction.into_updates();
+        assert_eq!(applied.len(), 2);
+        assert_eq!(applied.iter().find(|u| u.vx == 0).unwrap().r#type, 2);
+
+        let mut history = EditHistory::default();
+        for _ in 0..MAX_EDIT_HISTORY + 1 {
+            history.record(Edit {
+                applied: applied.clone(),
+                previous: vec![],
+            });
+        }
+        assert_eq!(history.undo.len(), MAX_EDIT_HISTORY);
+
+        assert!(history.undo().is_some());
+        assert!(history.redo().is_some());
+        assert!(history.redo().is_none());
+
+        history.undo();
+        history.record(Edit {
+            applied,
+            previous: vec![],
+        });
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn tick_edits() {
+        let mut edits = TickEdits::default();
+
+        assert!(edits.claim(Vec3(0, 0, 0), 1));
+        assert!(edits.claim(Vec3(0, 0, 0), 1));
+        assert!(!edits.claim(Vec3(0, 0, 0), 2));
+        assert!(edits.claim(Vec3(1, 0, 0), 2));
+
+        edits.clear();
+        assert!(edits.claim(Vec3(0, 0, 0), 2));
+    }
+}
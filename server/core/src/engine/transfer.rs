@@ -0,0 +1,146 @@
+use hashbrown::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+use server_utils::secret::constant_time_eq;
+
+/// How long a transfer token can be redeemed for, in milliseconds
+pub const TRANSFER_TOKEN_TTL: u64 = 30_000;
+
+/// What a transfer token vouches for, the player's name in the target world
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferClaims {
+    pub name: Option<String>,
+    pub world: String,
+    /// Unix time in milliseconds the token stops being valid at
+    pub expires: u64,
+}
+
+/// Resource issuing and redeeming the tokens moving players between servers
+///
+/// Tokens are signed with a secret shared by the servers of a network, and can only be
+/// redeemed once within `TRANSFER_TOKEN_TTL`.
+#[derive(Debug, Default)]
+pub struct Transfers {
+    secret: Option<String>,
+    /// Tokens already redeemed and when they expire, so that they can't be replayed
+    redeemed: HashMap<String, u64>,
+}
+
+impl Transfers {
+    pub fn new(secret: Option<String>) -> Self {
+        Self {
+            secret,
+            redeemed: HashMap::new(),
+        }
+    }
+
+    /// Issue a token for a player to join `world` on another server, none without a secret
+    pub fn issue(&self, name: Option<String>, world: &str, now: u64) -> Option<String> {
+        let secret = self.secret.as_ref()?;
+
+        let claims = TransferClaims {
+            name,
+            world: world.to_owned(),
+            expires: now + TRANSFER_TOKEN_TTL,
+        };
+        let payload = base64::encode(serde_json::to_string(&claims).unwrap());
+        let signature = hex(&hmac(secret.as_bytes(), payload.as_bytes()));
+
+        Some(format!("{}.{}", payload, signature))
+    }
+
+    /// Redeem a token presented when joining `world`, returning what it vouches for
+    pub fn redeem(&mut self, token: &str, world: &str, now: u64) -> Result<TransferClaims, String> {
+        let secret = self
+            .secret
+            .as_ref()
+            .ok_or_else(|| "Transfers aren't enabled.".to_owned())?;
+
+        self.redeemed.retain(|_, expires| *expires > now);
+
+        let (payload, signature) = match token.split_once('.') {
+            Some(parts) => parts,
+            None => return Err("Malformed transfer token.".to_owned()),
+        };
+
+        let expected = hex(&hmac(secret.as_bytes(), payload.as_bytes()));
+        if !constant_time_eq(expected.as_bytes(), signature.as_bytes()) {
+            return Err("Invalid transfer token.".to_owned());
+        }
+
+        let claims = base64::decode(payload)
+            .ok()
+            .and_then(|json| serde_json::from_slice::<TransferClaims>(&json).ok())
+            .ok_or_else(|| "Malformed transfer token.".to_owned())?;
+
+        if claims.expires <= now {
+            return Err("Expired transfer token.".to_owned());
+        }
+
+        if claims.world != world {
+            return Err("Transfer token is for another world.".to_owned());
+        }
+
+        if self.redeemed.contains_key(token) {
+            return Err("Transfer token was already used.".to_owned());
+        }
+
+        self.redeemed.insert(token.to_owned(), claims.expires);
+
+        Ok(claims)
+    }
+}
+
+/// HMAC-SHA256 of a message
+fn hmac(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redeem() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hex(&hmac(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        let lobby = Transfers::new(Some("secret".to_owned()));
+        let mut game = Transfers::new(Some("secret".to_owned()));
+
+        let token = lobby
+            .issue(Some("steve".to_owned()), "survival", 1000)
+            .unwrap();
+
+        assert!(game.redeem(&token, "creative", 2000).is_err());
+        assert_eq!(
+            game.redeem(&token, "survival", 2000).unwrap().name,
+            Some("steve".to_owned())
+        );
+        // single use
+        assert!(game.redeem(&token, "survival", 2000).is_err());
+
+        let token = lobby.issue(None, "survival", 1000).unwrap();
+        assert!(game
+            .redeem(&token, "survival", 1000 + TRANSFER_TOKEN_TTL)
+            .is_err());
+
+        let mut other = Transfers::new(Some("other".to_owned()));
+        let token = lobby.issue(None, "survival", 1000).unwrap();
+        assert!(other.redeem(&token, "survival", 2000).is_err());
+    }
+}
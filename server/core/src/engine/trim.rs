@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use super::{
+    config::WorldConfig,
+    storage::{open_storage, Storage},
+};
+
+use server_common::vec::Vec2;
+use server_utils::convert::parse_chunk_name;
+
+/// Which stored chunks to trim, any matching either rule unless players edited them
+///
+/// Chunks saved before edits were tracked are always kept, as they may hold builds.
+#[derive(Debug, Default)]
+pub struct TrimRules {
+    /// Chunks farther than this from the origin, in chunks
+    pub radius: Option<i32>,
+    /// Chunks last saved before this unix time in milliseconds
+    pub before: Option<u64>,
+}
+
+impl TrimRules {
+    /// Whether a chunk of what's known about it is to be removed
+    pub fn matches(&self, coords: &Vec2<i32>, edited: Option<bool>, saved_at: Option<u64>) -> bool {
+        if edited != Some(false) {
+            return false;
+        }
+
+        let outside = self
+            .radius
+            .is_some_and(|r| coords.0 * coords.0 + coords.1 * coords.1 > r * r);
+        let untouched = match (self.before, saved_at) {
+            (Some(before), Some(saved_at)) => saved_at < before,
+            _ => false,
+        };
+
+        outside || untouched
+    }
+}
+
+/// Chunks a trim removed and kept
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TrimReport {
+    pub removed: usize,
+    pub kept: usize,
+}
+
+/// Remove the stored chunks matching the rules then compact the storage
+///
+/// Meant for stopped servers, a running one would save the chunks it has loaded right back.
+/// Panics if the storage can't list its chunks.
+pub fn trim(storage: &dyn Storage, rules: &TrimRules) -> TrimReport {
+    let names = storage
+        .chunk_names()
+        .expect("This storage can't list its chunks to trim them.");
+
+    let mut report = TrimReport::default();
+
+    for name in names {
+        let data = storage
+            .get_chunk(&name)
+            .and_then(|data| serde_json::from_slice::<serde_json::Value>(&data).ok());
+
+        // unreadable chunks are for `clean_chunks` to deal with
+        let remove = data.is_some_and(|data| {
+            rules.matches(
+                &parse_chunk_name(&name),
+                data["edited"].as_bool(),
+                data["savedAt"].as_u64(),
+            )
+        });
+
+        if remove {
+            storage.remove_chunk(&name);
+            report.removed += 1;
+        } else {
+            report.kept += 1;
+        }
+    }
+
+    storage.compact();
+    storage.sync();
+
+    report
+}
+
+/// Trim the storage of a saved world, `None` if the world isn't saved
+pub fn trim_world(world_name: &str, config: &WorldConfig, rules: &TrimRules) -> Option<TrimReport> {
+    if !config.save {
+        return None;
+    }
+
+    let mut root_folder = PathBuf::from(&config.chunk_root);
+    root_folder.push(world_name);
+
+    let storage = open_storage(config.storage, config.object_storage.as_ref(), &root_folder);
+
+    Some(trim(storage.as_ref(), rules))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules() {
+        let rules = TrimRules {
+            radius: Some(10),
+            before: Some(1000),
+        };
+
+        // far away or stale
+        assert!(rules.matches(&Vec2(20, 0), Some(false), Some(2000)));
+        assert!(rules.matches(&Vec2(0, 0), Some(false), Some(500)));
+
+        // near and recent, edited, or saved before edits were tracked
+        assert!(!rules.matches(&Vec2(0, 0), Some(false), Some(2000)));
+        assert!(!rules.matches(&Vec2(20, 0), Some(true), Some(500)));
+        assert!(!rules.matches(&Vec2(20, 0), None, None));
+        assert!(!rules.matches(&Vec2(0, 0), Some(false), None));
+
+        assert!(!TrimRules::default().matches(&Vec2(20, 0), Some(false), Some(0)));
+    }
+}
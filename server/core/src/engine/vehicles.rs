@@ -0,0 +1,203 @@
+use crate::comp::rigidbody::RigidBody;
+
+type TestFunction<'a> = &'a dyn Fn(i32, i32, i32) -> bool;
+
+pub const BOAT_MAX_SPEED: f32 = 8.0;
+pub const BOAT_ACCELERATION: f32 = 6.0;
+/// Fraction of the horizontal speed lost per second on fluids
+pub const BOAT_DRAG: f32 = 0.8;
+/// Fraction of the boat's height sunk below the fluid surface
+pub const BOAT_DRAFT: f32 = 0.3;
+/// How quickly boats settle onto the surface, per second
+const BOAT_BUOYANCY: f32 = 6.0;
+/// Deepest boats are lifted back up from, in voxels
+const BOAT_MAX_DEPTH: i32 = 64;
+
+pub const MINECART_MAX_SPEED: f32 = 12.0;
+pub const MINECART_ACCELERATION: f32 = 4.0;
+/// Speed gained per second rolling down a rail slope
+pub const MINECART_SLOPE_ACCELERATION: f32 = 9.0;
+/// Fraction of the speed lost per second on rails
+pub const MINECART_FRICTION: f32 = 0.2;
+
+fn voxel_of(body: &RigidBody, dimension: f32) -> (i32, i32, i32) {
+    let position = body.get_position();
+
+    // nudge up so that bodies resting on a voxel aren't counted as inside the one below
+    (
+        (position.0 / dimension).floor() as i32,
+        ((position.1 + 0.01 * dimension) / dimension).floor() as i32,
+        (position.2 / dimension).floor() as i32,
+    )
+}
+
+/// Float a boat on the surface of the fluid it's in, pushed along a heading by its rider
+///
+/// Returns false if the boat isn't on a fluid, leaving it to plain physics.
+pub fn float_boat(
+    body: &mut RigidBody,
+    dt: f32,
+    dimension: f32,
+    thrust: Option<f32>,
+    test_fluid: TestFunction,
+) -> bool {
+    let (vx, _, vz) = voxel_of(body, dimension);
+    let position = body.get_position();
+
+    // the voxel holding the bottom of the boat
+    let vy = ((position.1 - 0.01 * dimension) / dimension).floor() as i32;
+
+    if !test_fluid(vx, vy, vz) {
+        body.gravity_multiplier = 1.0;
+        return false;
+    }
+
+    let mut top = vy;
+    while top - vy < BOAT_MAX_DEPTH && test_fluid(vx, top + 1, vz) {
+        top += 1;
+    }
+
+    let surface = (top + 1) as f32 * dimension;
+    let target = surface - body.aabb.vec.1 * BOAT_DRAFT;
+
+    body.gravity_multiplier = 0.0;
+    body.velocity.1 = (target - position.1) * BOAT_BUOYANCY;
+
+    let drag = (1.0 - BOAT_DRAG * dt).max(0.0);
+    body.velocity.0 *= drag;
+    body.velocity.2 *= drag;
+
+    if let Some(heading) = thrust {
+        body.velocity.0 += heading.sin() * BOAT_ACCELERATION * dt;
+        body.velocity.2 += heading.cos() * BOAT_ACCELERATION * dt;
+    }
+
+    let speed = body.velocity.0.hypot(body.velocity.2);
+    if speed > BOAT_MAX_SPEED {
+        body.velocity.0 *= BOAT_MAX_SPEED / speed;
+        body.velocity.2 *= BOAT_MAX_SPEED / speed;
+    }
+
+    body.mark_active();
+
+    true
+}
+
+/// Keep a minecart on the rails it's on, rolling down slopes and pushed by its rider
+///
+/// Rails run along the x or z axis, following the neighboring rails. Returns false if the
+/// minecart isn't on rails, leaving it to plain physics.
+pub fn ride_rails(
+    body: &mut RigidBody,
+    dt: f32,
+    dimension: f32,
+    thrust: Option<f32>,
+    is_rail: TestFunction,
+) -> bool {
+    let (vx, vy, vz) = voxel_of(body, dimension);
+
+    if !is_rail(vx, vy, vz) {
+        return false;
+    }
+
+    // a neighbor connects whether it's level, a step up or a step down
+    let connects = |dx: i32, dz: i32| (-1..=1).any(|dy| is_rail(vx + dx, vy + dy, vz + dz));
+    let along_x = match (
+        connects(1, 0) || connects(-1, 0),
+        connects(0, 1) || connects(0, -1),
+    ) {
+        (true, false) => true,
+        (false, true) => false,
+        _ => body.velocity.0.abs() >= body.velocity.2.abs(),
+    };
+
+    let (dx, dz) = if along_x { (1, 0) } else { (0, 1) };
+    let mut speed = if along_x {
+        body.velocity.0
+    } else {
+        body.velocity.2
+    };
+
+    if let Some(heading) = thrust {
+        let push = if along_x {
+            heading.sin()
+        } else {
+            heading.cos()
+        };
+        speed += push * MINECART_ACCELERATION * dt;
+    }
+
+    // rails going down towards +axis, or up towards -axis, slope towards +axis
+    let slope = is_rail(vx + dx, vy - 1, vz + dz) as i32 - is_rail(vx - dx, vy - 1, vz - dz) as i32
+        + is_rail(vx - dx, vy + 1, vz - dz) as i32
+        - is_rail(vx + dx, vy + 1, vz + dz) as i32;
+    speed += slope.signum() as f32 * MINECART_SLOPE_ACCELERATION * dt;
+
+    speed *= (1.0 - MINECART_FRICTION * dt).max(0.0);
+    speed = speed.clamp(-MINECART_MAX_SPEED, MINECART_MAX_SPEED);
+
+    // stay centered on the rails
+    let mut position = body.get_position();
+    if along_x {
+        position.2 = (vz as f32 + 0.5) * dimension;
+        body.velocity.0 = speed;
+        body.velocity.2 = 0.0;
+    } else {
+        position.0 = (vx as f32 + 0.5) * dimension;
+        body.velocity.0 = 0.0;
+        body.velocity.2 = speed;
+    }
+    body.set_position(&position);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use server_common::{aabb::Aabb, vec::Vec3};
+
+    use super::*;
+
+    fn body_at(x: f32, y: f32, z: f32) -> RigidBody {
+        let mut body = RigidBody::new(
+            Aabb::new(&Vec3(0.0, 0.0, 0.0), &Vec3(0.8, 0.5, 0.8)),
+            0.4,
+            1.0,
+            1.0,
+            0.0,
+            1.0,
+            true,
+        );
+        body.set_position(&Vec3(x, y, z));
+        body
+    }
+
+    #[test]
+    fn vehicles() {
+        // water from y = 0 to y = 3
+        let water = |_: i32, y: i32, _: i32| (0..3).contains(&y);
+        let mut boat = body_at(0.5, 1.0, 0.5);
+
+        assert!(float_boat(&mut boat, 0.1, 1.0, None, &water));
+        assert!(boat.velocity.1 > 0.0);
+        assert_eq!(boat.gravity_multiplier, 0.0);
+
+        // rails along x, going down towards +x
+        let rails = |x: i32, y: i32, z: i32| z == 0 && y == -x;
+        let mut cart = body_at(0.5, 0.0, 0.7);
+        cart.velocity = Vec3(0.0, 0.0, 3.0);
+
+        assert!(ride_rails(&mut cart, 0.1, 1.0, None, &rails));
+        assert!(cart.velocity.0 > 0.0);
+        assert_eq!(cart.velocity.2, 0.0);
+        assert!((cart.get_position().2 - 0.5).abs() < 1e-5);
+
+        assert!(!ride_rails(
+            &mut body_at(0.5, 0.0, 3.5),
+            0.1,
+            1.0,
+            None,
+            &rails
+        ));
+    }
+}
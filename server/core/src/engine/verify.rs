@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+
+use super::super::gen::{blocks::Blocks, generator::Generator, lights::Lights};
+
+use super::{
+    chunk::Chunk,
+    config::WorldConfig,
+    registry::Registry,
+    storage::{open_storage, Storage},
+};
+
+use server_utils::convert::parse_chunk_name;
+
+/// Metadata key prefix that chunks beyond repair are moved under
+pub const QUARANTINE_PREFIX: &str = "quarantined-";
+
+/// What verifying does about the chunks it finds wrong, only reporting them by default
+#[derive(Debug, Default)]
+pub struct VerifyOptions {
+    /// Replace unknown ids with air, and rebuild bad light and height data
+    pub fix: bool,
+    /// Move the chunks that can't be loaded out of the way, for them to be generated anew
+    pub quarantine: bool,
+}
+
+/// Problems in the data of a chunk that loads, all of them repairable
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ChunkIssues {
+    /// Voxels of ids the registry doesn't have
+    pub unknown_ids: usize,
+    /// Lights over the max level, or with bits set beyond the light channels
+    pub bad_lights: usize,
+    /// Column heights above the world
+    pub bad_heights: usize,
+}
+
+impl ChunkIssues {
+    pub fn is_empty(&self) -> bool {
+        self == &ChunkIssues::default()
+    }
+}
+
+/// Chunks verified by outcome
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub healthy: usize,
+    /// Chunks with issues, repaired or not
+    pub damaged: usize,
+    pub repaired: usize,
+    /// Chunks that couldn't be loaded at all
+    pub corrupt: usize,
+    pub quarantined: usize,
+}
+
+/// Whether a light value holds levels within the max in each channel and nothing else
+pub fn is_valid_light(light: u32, max_light_level: u32) -> bool {
+    light >> 16 == 0
+        && Lights::extract_sunlight(light) <= max_light_level
+        && Lights::extract_red_light(light) <= max_light_level
+        && Lights::extract_green_light(light) <= max_light_level
+        && Lights::extract_blue_light(light) <= max_light_level
+}
+
+/// Find what's wrong with the data of a loaded chunk
+pub fn inspect(chunk: &Chunk, registry: &Registry, config: &WorldConfig) -> ChunkIssues {
+    ChunkIssues {
+        unknown_ids: chunk
+            .get_voxels()
+            .data
+            .iter()
+            .filter(|&&voxel| !registry.has_type(Blocks::extract_id(voxel)))
+            .count(),
+        bad_lights: chunk
+            .get_lights()
+            .data
+            .iter()
+            .filter(|&&light| !is_valid_light(light, config.max_light_level))
+            .count(),
+        bad_heights: chunk
+            .get_height_map()
+            .data
+            .iter()
+            .filter(|&&height| height >= config.max_height)
+            .count(),
+    }
+}
+
+/// Fix the issues found in a chunk, its lights to be propagated again once it's loaded
+pub fn repair(chunk: &mut Chunk, issues: &ChunkIssues, registry: &Registry, config: &WorldConfig) {
+    if issues.unknown_ids > 0 {
+        let &air = registry.get_id_by_name("Air");
+
+        let mut voxels = chunk.get_voxels().to_owned();
+        voxels.map_in_place(|&voxel| {
+            if registry.has_type(Blocks::extract_id(voxel)) {
+                voxel
+            } else {
+                air
+            }
+        });
+        chunk.set_voxels(voxels);
+    }
+
+    if issues.unknown_ids > 0 || issues.bad_heights > 0 {
+        Generator::generate_chunk_height_map(chunk, registry, config);
+    }
+
+    if issues.unknown_ids > 0 || issues.bad_lights > 0 || issues.bad_heights > 0 {
+        let mut lights = chunk.get_lights().to_owned();
+        lights.map_in_place(|_| 0);
+        chunk.set_lights(lights);
+        chunk.needs_propagation = true;
+    }
+}
+
+/// Check every chunk of a storage, repairing or quarantining the bad ones as asked
+///
+/// `log` is called with the name of each bad chunk and what's wrong with it. Meant for stopped
+/// servers, a running one would save the chunks it has loaded right back.
+pub fn verify(
+    storage: &dyn Storage,
+    config: &WorldConfig,
+    registry: &Registry,
+    options: &VerifyOptions,
+    log: &mut dyn FnMut(&str, &str),
+) -> VerifyReport {
+    let names = storage
+        .chunk_names()
+        .expect("This storage can't list its chunks to verify them.");
+
+    let mut report = VerifyReport::default();
+
+    for name in names {
+        let data = match storage.get_chunk(&name) {
+            Some(data) => data,
+            None => continue,
+        };
+
+        let mut chunk = Chunk::new(parse_chunk_name(&name), config, None);
+
+        if let Err(e) = chunk.try_load_data(&data) {
+            log(&name, &e);
+            report.corrupt += 1;
+
+            if options.quarantine {
+                storage.put_metadata(&format!("{}{}.json", QUARANTINE_PREFIX, name), &data);
+                storage.remove_chunk(&name);
+                report.quarantined += 1;
+            }
+
+            continue;
+        }
+
+        let issues = inspect(&chunk, registry, config);
+
+        if issues.is_empty() {
+            report.healthy += 1;
+            continue;
+        }
+
+        log(&name, &format!("{:?}", issues));
+        report.damaged += 1;
+
+        if options.fix {
+            repair(&mut chunk, &issues, registry, config);
+            chunk.save(storage);
+            report.repaired += 1;
+        }
+    }
+
+    storage.sync();
+
+    report
+}
+
+/// Verify the storage of a saved world, `None` if the world isn't saved
+pub fn verify_world(
+    world_name: &str,
+    config: &WorldConfig,
+    registry: &Registry,
+    options: &VerifyOptions,
+    log: &mut dyn FnMut(&str, &str),
+) -> Option<VerifyReport> {
+    if !config.save {
+        return None;
+    }
+
+    let mut root_folder = PathBuf::from(&config.chunk_root);
+    root_folder.push(world_name);
+
+    let storage = open_storage(config.storage, config.object_storage.as_ref(), &root_folder);
+
+    Some(verify(storage.as_ref(), config, registry, options, log))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lights() {
+        let light = Lights::insert_sunlight(Lights::insert_red_light(0, 12), 15);
+
+        assert!(is_valid_light(light, 15));
+        assert!(!is_valid_light(light, 14));
+        assert!(!is_valid_light(light | 1 << 16, 15));
+        assert!(ChunkIssues::default().is_empty());
+    }
+}
@@ -1,50 +1,71 @@
 #![allow(dead_code)]
 
 use actix::Recipient;
-use log::info;
+use crossbeam_channel::Sender;
+use log::{info, warn};
 
 use ansi_term::Colour::Yellow;
 
+use rand::{distributions::Alphanumeric, Rng};
+
 use specs::shred::{Fetch, FetchMut, Resource};
 
-use std::io::Write;
-use std::time::Instant;
-use std::{collections::VecDeque, fs::File};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
 
-use specs::{Builder, DispatcherBuilder, World as ECSWorld, WorldExt};
+use specs::{Builder, DispatcherBuilder, Entity, Join, World as ECSWorld, WorldExt};
 
 use serde::{Deserialize, Serialize};
 
 use server_common::quaternion::Quaternion;
 
-use crate::comp::brain::Brain;
+use crate::comp::attributes::{AttributeKind, AttributeModifier, Attributes};
+use crate::comp::brain::{Brain, BrainOptions};
+use crate::comp::controls::Controls;
 use crate::comp::curr_chunk::CurrChunk;
+use crate::comp::effects::{EffectType, Effects};
 use crate::comp::etype::EType;
-use crate::comp::id::Id;
+use crate::comp::health::{
+    Health, ATTACK_COOLDOWN, ATTACK_DAMAGE, ATTACK_REACH, PLAYER_MAX_HEALTH,
+};
+use crate::comp::history::History;
+use crate::comp::id::{protocol_id, Id};
+use crate::comp::item_drop::ItemDrop;
+use crate::comp::metadata::Metadata;
+use crate::comp::mount::{Mounted, MOUNT_REACH};
+use crate::comp::movement::Movement;
 use crate::comp::name::Name;
 use crate::comp::rotation::Rotation;
 use crate::comp::target::Target;
+use crate::comp::trader::Trader;
+use crate::comp::uuid::Uuid;
+use crate::comp::vehicle::Vehicle;
 use crate::comp::view_radius::ViewRadius;
 use crate::comp::walk_towards::WalkTowards;
+use crate::gen::{blocks::BlockRotation, lights::LightColor};
 use crate::network::models::{create_of_type, ChatType};
 use crate::sys::{
-    BroadcastSystem, ChunkingSystem, EntitiesSystem, GenerationSystem, MeshingSystem,
-    ObserveSystem, PathFindSystem, PeersSystem, SearchSystem, WalkTowardsSystem,
+    AdvancementsSystem, AttributesSystem, BlockTickSystem, BroadcastSystem, ChunkingSystem,
+    EffectsSystem, EntitiesSystem, GenerationSystem, HistorySystem, MeshingSystem, MountSystem,
+    MovementSystem, ObserveSystem, PathFindSystem, PeersSystem, RemeshSystem, SearchSystem,
+    TimedExt, UnloadingSystem, VehiclesSystem, WalkTowardsSystem,
 };
 use crate::{
     comp::rigidbody::RigidBody,
-    network::message::{JoinResult, Message},
+    network::message::{JoinResult, Message, PlayerSummary, WorldMetrics},
 };
 
 use super::entities::Entities;
 use super::kdtree::KdTree;
 use super::{
     super::{
-        constants::WORLD_DATA_FILE,
+        constants::{LEVEL_SEED, WORLD_DATA_FILE},
         engine::chunks::MeshLevel,
         network::models::{
-            create_chat_message, create_message, messages, ChunkProtocol, MessageComponents,
-            MessageType,
+            create_chat_message, create_message, create_translated_message, messages,
+            BlockEntityProtocol, ContainerProtocol, MessageComponents, MessageType, MountProtocol,
+            TransferProtocol,
         },
         sys::PhysicsSystem,
     },
@@ -56,11 +77,51 @@ use server_common::{
     aabb::Aabb,
     vec::{Vec2, Vec3},
 };
-
+use server_utils::convert::{map_voxel_to_chunk, map_world_to_voxel};
+use server_utils::secret::constant_time_eq;
+
+use super::advancements::Advancements;
+use super::arenas::Arenas;
+use super::audit::{AuditAction, AuditLog};
+use super::block_entities::{BlockEntity, BlockEntityData, Furnace};
+use super::bridge::{self, Bridge, BridgeEvent, MAX_BRIDGE_MESSAGE_LENGTH};
 use super::chunks::Chunks;
-use super::clock::Clock;
-use super::players::{BroadcastExt, PlayerUpdates, Players};
+use super::clock::{sunlight_intensity, unix_millis, Clock};
+use super::commands::{self, Candidate, Target as Selector, PLAYER_TYPE};
+use super::config::{WorldConfig, WorldMeta};
+use super::container::{click_container, get_container_slots, SlotAction, CONTAINER_REACH};
+use super::cron::{Cron, TaskAction, TaskSchedule};
+use super::debug::{
+    chunk_borders, light_samples, DebugOptions, DebugOverlay, DebugPath, Hitbox,
+    DEBUG_ENTITY_RADIUS, DEBUG_INTERVAL,
+};
+use super::events::{WorldEvent, WorldEvents};
+use super::forms::Forms;
+use super::functions::{Functions, ON_DEATH, ON_JOIN};
+use super::holograms::Holograms;
+use super::hud::{BossBar, Hud, HudExpiry, Title};
+use super::i18n::{Translations, DEFAULT_LOCALE};
+use super::items::ItemStack;
+use super::loot::{LootContext, LootTables};
+use super::map::{MapColors, MapTile, WorldMap, MAX_MAP_TILES_PER_REQUEST, MAX_MAP_TILES_PER_TICK};
+use super::observers::{ObserverId, VoxelChange};
+use super::picking::{pick, BlockTarget, PICK_REACH};
+use super::players::{BroadcastExt, Detached, PlayerData, PlayerUpdates, Players};
+use super::profiler::Profiler;
+use super::recipes::Recipes;
 use super::registry::Registry;
+use super::reload::{reload_data, RegistryDiff, Reloaded};
+use super::restart::{Countdown, Heartbeat, RestartCountdown, Watched};
+use super::rng::{RngDomain, RngService};
+use super::scheduler::BlockTicks;
+use super::shards::{ChunkHandoff, EntityHandoff, PendingHandoffs};
+use super::skins::{file_key, save_skin};
+use super::snapshots::Snapshots;
+use super::sweep_recorder::SweepRecorder;
+use super::teams::Teams;
+use super::trading::TradeLists;
+use super::transaction::{Edit, EditHistory, TickEdits, VoxelTransaction};
+use super::transfer::{TransferClaims, Transfers};
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -69,37 +130,6 @@ struct WorldData {
     tick_speed: f32,
 }
 
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct WorldConfig {
-    pub chunk_size: usize,
-    pub dimension: usize,
-    pub max_height: u32,
-    pub max_light_level: u32,
-    pub save: bool,
-    pub chunk_root: String,
-    pub render_radius: usize,
-    pub max_loaded_chunks: usize,
-    pub sub_chunks: u32,
-    pub generation: String,
-    pub player_dimensions: Vec3<f32>,
-    pub player_head: f32,
-    pub max_per_thread: usize,
-    pub server_tick_rate: u64,
-}
-
-#[derive(Deserialize, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct WorldMeta {
-    pub name: String,
-    pub description: String,
-    pub preload: i16,
-    pub tick_speed: f32,
-    pub time: f32,
-
-    #[serde(default)]
-    pub packs: Vec<String>,
-}
 /// A single voxel-based and ECS-based world
 pub struct World {
     pub ecs: ECSWorld,
@@ -134,11 +164,23 @@ impl World {
         let mut ecs = ECSWorld::new();
 
         // ECS Components
+        ecs.register::<Attributes>();
         ecs.register::<Brain>();
+        ecs.register::<Controls>();
+        ecs.register::<Effects>();
+        ecs.register::<Health>();
         ecs.register::<CurrChunk>();
         ecs.register::<EType>();
+        ecs.register::<History>();
         ecs.register::<Id>();
+        ecs.register::<ItemDrop>();
+        ecs.register::<Metadata>();
+        ecs.register::<Mounted>();
+        ecs.register::<Movement>();
+        ecs.register::<Vehicle>();
         ecs.register::<Target>();
+        ecs.register::<Trader>();
+        ecs.register::<Uuid>();
         ecs.register::<Name>();
         ecs.register::<RigidBody>();
         ecs.register::<Rotation>();
@@ -147,15 +189,60 @@ impl World {
 
         // ECS Resources
         ecs.insert(name.to_owned());
-        ecs.insert(Chunks::new(&name, config.clone(), registry));
+        ecs.insert(Recipes::new(&registry));
+        ecs.insert(LootTables::new(&registry));
+        ecs.insert(TradeLists::new(&registry));
+        ecs.insert(BlockTicks::new());
+        ecs.insert(PendingHandoffs::new());
+        ecs.insert(Transfers::new(config.transfer_secret.to_owned()));
+        ecs.insert(Snapshots::new(config.snapshot_rate));
+        ecs.insert(TickEdits::default());
+        ecs.insert(WorldMap::new(&registry));
+        let chunks = Chunks::new(&name, config.clone(), registry);
+        ecs.insert(Advancements::new(chunks.storage.clone()));
+        ecs.insert(Teams::new(chunks.storage.clone()));
+        ecs.insert(AuditLog::new(if config.save {
+            Some(chunks.root_folder.to_owned())
+        } else {
+            None
+        }));
+        ecs.insert(Forms::new(if config.save {
+            Some(chunks.root_folder.to_owned())
+        } else {
+            None
+        }));
+        ecs.insert(Hud::default());
+        ecs.insert(Bridge::new());
+        ecs.insert(Heartbeat::new());
+        ecs.insert(RestartCountdown::new());
+        ecs.insert(SweepRecorder::new());
+        ecs.insert(RngService::new(LEVEL_SEED as u64));
+        ecs.insert(Cron::new(
+            &config.tasks,
+            chunks.storage.clone(),
+            unix_millis(),
+        ));
+        ecs.insert(Holograms::new(chunks.storage.clone()));
+        ecs.insert(Functions::new(if config.save {
+            Some(chunks.root_folder.to_owned())
+        } else {
+            None
+        }));
+        ecs.insert(Arenas::new(chunks.storage.clone(), config.server_tick_rate));
+        ecs.insert(chunks.profiler.clone());
+        ecs.insert(chunks);
         ecs.insert(Clock::new(time, tick_speed));
         ecs.insert(KdTree::new());
         ecs.insert(Players::new());
         ecs.insert(PlayerUpdates::new());
         ecs.insert(MessagesQueue::new());
+        ecs.insert(WorldEvents::new());
+        ecs.insert(Translations::new());
         ecs.insert(Entities::new());
         ecs.insert(Physics::new(PhysicsOptions {
-            gravity: Vec3(0.0, -24.0, 0.0),
+            gravity: Vec3(0.0, -config.physics.gravity, 0.0),
+            terminal_velocity: config.physics.terminal_velocity,
+            friction: config.physics.friction,
             min_bounce_impulse: 0.1,
             air_drag: 0.1,
             fluid_drag: 0.4,
@@ -218,13 +305,32 @@ impl World {
             name,
             duration
         );
+
+        // the spawn area is meshed off-thread as the world starts ticking, instead of as the
+        // first players ask for it
+        if chunks.config.cache_meshes {
+            let warming = chunks.warm(preload);
+
+            if warming > 0 {
+                info!(
+                    "Warming the meshes of {} chunks for world \"{}\".",
+                    warming, name
+                );
+            }
+        }
     }
 
     /// Add a new player, signaled from the server
+    ///
+    /// `view_radius` is the radius in chunks requested by the client, clamped by
+    /// the world's render radius. `locale` falls back to the default locale.
     pub fn add_player(
         &mut self,
         id: Option<usize>,
         player_name: Option<String>,
+        view_radius: Option<i16>,
+        locale: Option<String>,
+        address: Option<IpAddr>,
         player_addr: Recipient<Message>,
     ) -> JoinResult {
         let mut id = id.unwrap_or_else(rand::random::<usize>);
@@ -234,12 +340,23 @@ impl World {
 
         let time = clock.time;
         let tick_speed = clock.tick_speed;
-        let spawn = [0, chunks.get_max_height(0, 0) as i32, 0];
+        let spawn = [0, chunks.get_max_height(0, 0), 0];
         let passables = chunks.registry.get_passable_solids();
+        let resource_pack = chunks.registry.resource_pack.to_owned();
+        let biomes = chunks.biomes.registry_json();
+        let dimensions = chunks.config.dimensions();
+        let physics = chunks.config.physics;
 
         drop(clock);
         drop(chunks);
 
+        // named players pick up where they left off
+        let saved = player_name.as_ref().and_then(|name| self.load_player(name));
+        let spawn = saved.as_ref().map_or(spawn, |data| {
+            let [px, py, pz] = data.position;
+            [px.floor() as i32, py.floor() as i32, pz.floor() as i32]
+        });
+
         let players = self.read_resource::<Players>();
 
         loop {
@@ -254,8 +371,14 @@ impl World {
 
         let config = self.read_resource::<WorldConfig>();
         let dimension = config.player_dimensions.clone();
-        let render_radius = config.render_radius as i16;
+        let chunk_size = config.chunk_size;
+        let max_radius = config.render_radius as i16;
+        let render_radius = view_radius.map_or(max_radius, |r| {
+            r.max(1).saturating_mul(chunk_size as i16).min(max_radius)
+        });
         let head = config.player_head;
+        // bodies only fall when the server simulates the movement
+        let gravity_multiplier = if config.server_movement { 1.0 } else { 0.0 };
 
         drop(config);
 
@@ -273,13 +396,54 @@ impl World {
                 1.0,
                 1.0,
                 0.0,
-                0.0,
+                gravity_multiplier,
                 false,
             ))
             .with(Rotation::new(0.0, 0.0, 0.0, 0.0))
             .with(CurrChunk::new())
             .with(ViewRadius::new(render_radius))
+            .with(Metadata::new())
+            .with(History::new())
+            .with(Controls::new())
+            .with(Movement::new())
+            .with(Effects::new())
+            .with(Health::new(PLAYER_MAX_HEALTH))
+            .with(Brain::new(BrainOptions {
+                max_speed: physics.walk_speed,
+                sprint_speed: physics.sprint_speed,
+                jump_impulse: physics.jump_impulse,
+                ..Default::default()
+            }))
+            .with(
+                Attributes::new()
+                    .with_base(AttributeKind::MaxHealth, PLAYER_MAX_HEALTH)
+                    .with_base(AttributeKind::MovementSpeed, physics.walk_speed)
+                    .with_base(AttributeKind::SprintSpeed, physics.sprint_speed)
+                    .with_base(AttributeKind::JumpImpulse, physics.jump_impulse)
+                    .with_base(AttributeKind::AttackDamage, ATTACK_DAMAGE),
+            )
             .build();
+        let entity = Entities::register(self.ecs_mut(), entity);
+
+        if let Some(data) = saved {
+            self.restore_player(entity, data);
+        }
+
+        let render_radius = ViewRadius::new(render_radius).in_chunks(chunk_size);
+
+        let skin_token = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(24)
+            .map(char::from)
+            .collect::<String>();
+
+        let resume_token = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(24)
+            .map(char::from)
+            .collect::<String>();
+
+        let locale = locale.unwrap_or_else(|| DEFAULT_LOCALE.to_owned());
 
         let mut players = self.write_resource::<Players>();
 
@@ -288,9 +452,52 @@ impl World {
             name: player_name,
             addr: player_addr,
             requested_chunks: VecDeque::default(),
+            sent_chunks: HashSet::default(),
+            display_name: None,
+            list_order: 0,
+            ping: 0,
+            last_sequence: 0,
+            edits: EditHistory::default(),
+            skin_token: skin_token.to_owned(),
+            locale: locale.to_owned(),
+            address,
+            resume_token: resume_token.to_owned(),
+            detached: None,
+            target: None,
+            edit_sequence: 0,
+            viewing: None,
+            container_id: 0,
+            cursor: None,
+            trading: None,
+            form: None,
+            form_id: 0,
+            minimap: false,
+            debug: None,
+            operator: false,
+            last_attack: None,
         };
 
         players.insert(id, new_player);
+        drop(players);
+
+        self.write_resource::<WorldEvents>()
+            .push(WorldEvent::PlayerJoined { player_id: id });
+
+        let holograms = self
+            .read_resource::<Holograms>()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        if !holograms.is_empty() {
+            commands::holograms::broadcast_holograms(self, holograms, vec![id]);
+        }
+
+        if let Some(address) = address {
+            info!(
+                "Player {} connected to \"{}\" from {}.",
+                id, self.name, address
+            );
+        }
 
         JoinResult {
             id,
@@ -298,9 +505,178 @@ impl World {
             tick_speed,
             spawn,
             passables,
+            render_radius,
+            skin_token,
+            locale,
+            resource_pack,
+            biomes,
+            resume_token,
+            resumed: false,
+            dimensions,
+            physics,
+            replay: vec![],
+        }
+    }
+
+    /// Keep a player whose connection dropped for the world's resume window, buffering what's
+    /// sent to it meanwhile. Returns false if sessions can't be resumed
+    pub fn detach_player(&mut self, player_id: &usize) -> bool {
+        let window = self.read_resource::<WorldConfig>().resume_window;
+
+        if window == 0 {
+            return false;
+        }
+
+        match self.write_resource::<Players>().get_mut(player_id) {
+            Some(player) => {
+                player.detached = Some(Detached {
+                    deadline: Instant::now() + Duration::from_secs(window),
+                    outbox: VecDeque::new(),
+                });
+            }
+            None => return false,
+        }
+
+        info!(
+            "Player {} of \"{}\" disconnected, resumable for {}s.",
+            player_id, self.name, window
+        );
+
+        true
+    }
+
+    /// Reattach a new connection to a detached player, handing back the messages it missed to
+    /// send after the init message.
+    /// Returns none if there's no detached player with that token
+    pub fn resume_player(
+        &mut self,
+        token: &str,
+        player_addr: Recipient<Message>,
+    ) -> Option<JoinResult> {
+        let mut players = self.write_resource::<Players>();
+
+        let (id, player) = players.iter_mut().find(|(_, player)| {
+            player.detached.is_some()
+                && constant_time_eq(player.resume_token.as_bytes(), token.as_bytes())
+        })?;
+        let id = *id;
+
+        let detached = player.detached.take().unwrap();
+        if detached.deadline <= Instant::now() {
+            player.detached = Some(detached);
+            return None;
+        }
+
+        // the outbox is replayed once the client knows it resumed, for it to keep its chunks
+        player.addr = player_addr;
+
+        let entity = player.entity;
+        let skin_token = player.skin_token.to_owned();
+        let locale = player.locale.to_owned();
+        let resume_token = player.resume_token.to_owned();
+
+        drop(players);
+
+        let clock = self.read_resource::<Clock>();
+        let chunks = self.read_resource::<Chunks>();
+        let config = self.read_resource::<WorldConfig>();
+
+        let Vec3(px, py, pz) = self
+            .ecs
+            .read_storage::<RigidBody>()
+            .get(entity)
+            .map(|body| body.get_position())
+            .unwrap_or_default();
+        let render_radius = self
+            .ecs
+            .read_storage::<ViewRadius>()
+            .get(entity)
+            .map(|radius| radius.in_chunks(config.chunk_size))
+            .unwrap_or(config.render_radius as i16);
+
+        info!("Player {} resumed their session in \"{}\".", id, self.name);
+
+        Some(JoinResult {
+            id,
+            time: clock.time,
+            tick_speed: clock.tick_speed,
+            spawn: [px.floor() as i32, py.floor() as i32, pz.floor() as i32],
+            passables: chunks.registry.get_passable_solids(),
+            render_radius,
+            skin_token,
+            locale,
+            resource_pack: chunks.registry.resource_pack.to_owned(),
+            biomes: chunks.biomes.registry_json(),
+            resume_token,
+            resumed: true,
+            dimensions: config.dimensions(),
+            physics: config.physics,
+            replay: detached.outbox.into(),
+        })
+    }
+
+    /// Remove the detached players that didn't resume in time
+    fn expire_detached(&mut self) {
+        let now = Instant::now();
+        let expired = self
+            .read_resource::<Players>()
+            .iter()
+            .filter(|(_, player)| {
+                player
+                    .detached
+                    .as_ref()
+                    .is_some_and(|detached| detached.deadline <= now)
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for id in expired {
+            self.remove_player(&id);
         }
     }
 
+    /// Move a player to `world` on another server, handing them a token to join it with.
+    /// Returns false if there's no such player or transfers aren't enabled
+    pub fn transfer_player(
+        &mut self,
+        player_id: usize,
+        host: &str,
+        port: u32,
+        world: &str,
+    ) -> bool {
+        let name = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => player.name.to_owned(),
+            None => return false,
+        };
+
+        let token = match self
+            .read_resource::<Transfers>()
+            .issue(name, world, unix_millis())
+        {
+            Some(token) => token,
+            None => return false,
+        };
+
+        let mut components = MessageComponents::default_for(MessageType::Transfer);
+        components.transfer = Some(TransferProtocol {
+            host: host.to_owned(),
+            port,
+            token,
+        });
+
+        self.broadcast(&create_message(components), vec![player_id], vec![]);
+
+        true
+    }
+
+    /// Redeem the token a player transferred from another server joined with
+    pub fn redeem_transfer(&mut self, token: &str) -> Result<TransferClaims, String> {
+        let name = self.name.to_owned();
+
+        self.write_resource::<Transfers>()
+            .redeem(token, &name, unix_millis())
+    }
+
     /// Remove a player, signaled from the server
     pub fn remove_player(&mut self, player_id: &usize) {
         let name = self.name.to_owned();
@@ -316,17 +692,37 @@ impl World {
         let player = player.unwrap();
         drop(players);
 
+        if player.name.is_some() {
+            let mut entry = player.get_list_entry(*player_id, &self.read_resource::<Teams>());
+            entry.removed = true;
+
+            let mut components = MessageComponents::default_for(MessageType::Players);
+            components.players = Some(vec![entry]);
+            message_queue.push(create_message(components));
+        }
+
+        self.write_resource::<Arenas>().leave(*player_id);
+        self.write_resource::<Hud>().remove_player(*player_id);
+
+        if let Some(name) = &player.name {
+            self.save_player(name, player.entity);
+        }
+
         let player_name = player.name.unwrap_or_else(|| "Somebody".to_owned());
 
+        self.bridge_event(BridgeEvent::Leave {
+            player: player_name.to_owned(),
+        });
+
         self.ecs_mut()
             .delete_entity(player.entity)
             .expect("Error removing player entity...");
 
-        let mut new_message = create_chat_message(
+        let mut new_message = create_translated_message(
             MessageType::Leave,
             ChatType::Info,
-            "",
-            format!("{} left the game", player_name).as_str(),
+            "player.left",
+            &[&player_name],
         );
         new_message.text = player_id.to_string();
 
@@ -341,243 +737,2384 @@ impl World {
         })
     }
 
-    /// Broadcast a message instantly
-    ///
-    /// Suggested against, use message_queue instead.
-    pub fn broadcast(&mut self, msg: &messages::Message, include: Vec<usize>, exclude: Vec<usize>) {
-        self.write_resource::<Players>()
-            .broadcast(msg, include, exclude, None);
-    }
+    /// What a named player was at when they last left, none if they're new or the world isn't saved
+    fn load_player(&self, name: &str) -> Option<PlayerData> {
+        let storage = self.read_resource::<Chunks>().storage.clone()?;
 
-    /// Adds a message to the message queue
-    ///
-    /// Message will be sent per tick, in a lazy fashion.
-    pub fn broadcast_lazy(
-        &mut self,
-        msg: &messages::Message,
-        include: Vec<usize>,
-        exclude: Vec<usize>,
-        from: usize,
-    ) {
-        self.write_resource::<MessagesQueue>().push((
-            msg.to_owned(),
-            Some(include),
-            Some(exclude),
-            Some(from),
-        ))
+        storage
+            .get_player(&file_key(name))
+            .and_then(|data| serde_json::from_slice(&data).ok())
     }
 
-    /// Handles server-side chunk request
-    pub fn on_chunk_request(&mut self, player_id: usize, msg: messages::Message) {
-        let mut players = self.write_resource::<Players>();
+    /// Put a player back where they were, as they were
+    fn restore_player(&mut self, entity: Entity, data: PlayerData) {
+        let PlayerData {
+            position: [px, py, pz],
+            rotation: [qx, qy, qz, qw],
+            health,
+            skin,
+        } = data;
+
+        if let Some(body) = self.ecs.write_storage::<RigidBody>().get_mut(entity) {
+            body.set_position(&Vec3(px, py, pz));
+        }
 
-        let json = msg.parse_json().unwrap();
+        if let Some(rotation) = self.ecs.write_storage::<Rotation>().get_mut(entity) {
+            *rotation = Rotation::new(qx, qy, qz, qw);
+        }
 
-        let cx = json["x"].as_i64().unwrap() as i32;
-        let cz = json["z"].as_i64().unwrap() as i32;
+        if let Some(current) = self.ecs.write_storage::<Health>().get_mut(entity) {
+            current.current = health.max(0.0).min(current.max);
+        }
 
-        if let Some(player) = players.get_mut(&player_id) {
-            player.requested_chunks.push_back(Vec2(cx, cz));
+        if let Some(skin) = skin {
+            if let Some(metadata) = self.ecs.write_storage::<Metadata>().get_mut(entity) {
+                metadata.set("skin", &skin);
+            }
         }
     }
 
-    /// Handles server-side config change
-    pub fn on_config(&mut self, player_id: usize, msg: messages::Message) {
-        let mut clock = self.write_resource::<Clock>();
-
-        let json = msg.parse_json().unwrap();
+    /// Keep what a named player is at for their next session, if the world is saved
+    fn save_player(&self, name: &str, entity: Entity) {
+        let storage = match &self.read_resource::<Chunks>().storage {
+            Some(storage) => storage.clone(),
+            None => return,
+        };
 
-        let time = json["time"].as_f64();
-        let tick_speed = json["tickSpeed"].as_f64();
+        let Vec3(px, py, pz) = match self.ecs.read_storage::<RigidBody>().get(entity) {
+            Some(body) => body.get_position(),
+            None => return,
+        };
+        let Quaternion(qx, qy, qz, qw) = self
+            .ecs
+            .read_storage::<Rotation>()
+            .get(entity)
+            .map(|rotation| rotation.0.to_owned())
+            .unwrap_or_default();
+        let health = self
+            .ecs
+            .read_storage::<Health>()
+            .get(entity)
+            .map_or(PLAYER_MAX_HEALTH, |health| health.current);
+        let skin = self
+            .ecs
+            .read_storage::<Metadata>()
+            .get(entity)
+            .and_then(|metadata| metadata.data.get("skin").cloned());
+
+        let data = PlayerData {
+            position: [px, py, pz],
+            rotation: [qx, qy, qz, qw],
+            health,
+            skin,
+        };
 
-        if let Some(time) = time {
-            clock.time = time as f32;
-        }
+        storage.put_player(&file_key(name), &serde_json::to_vec(&data).unwrap());
+    }
 
-        if let Some(tick_speed) = tick_speed {
-            clock.tick_speed = tick_speed as f32;
+    /// Set the name shown in the player list, `None` to fall back to the player name
+    pub fn set_display_name(&mut self, player_id: usize, display_name: Option<String>) {
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.display_name = display_name;
         }
 
-        // damn?
-        drop(clock);
+        self.broadcast_list_entry(player_id);
+    }
 
-        let mut new_message = create_of_type(MessageType::Config);
-        new_message.json = json.to_string();
+    /// Set the sort order of a player in the player list, lower goes first
+    pub fn set_list_order(&mut self, player_id: usize, order: i32) {
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.list_order = order;
+        }
 
-        self.broadcast_lazy(&new_message, vec![], vec![], player_id);
+        self.broadcast_list_entry(player_id);
     }
 
-    /// Handles server-side voxel updates
-    ///
-    /// Remesh chunks based on which sub-chunks are changed according to internal
-    /// chunk caching system.
-    pub fn on_update(&mut self, _player_id: usize, msg: messages::Message) {
-        let mut chunks = self.write_resource::<Chunks>();
+    /// Queue the player list entry of a player to be sent to everyone
+    fn broadcast_list_entry(&mut self, player_id: usize) {
+        let players = self.read_resource::<Players>();
+        let teams = self.read_resource::<Teams>();
 
-        let &air = chunks.registry.get_id_by_name("Air");
+        let entry = match players.get(&player_id) {
+            Some(player) if player.name.is_some() => player.get_list_entry(player_id, &teams),
+            _ => return,
+        };
 
-        let mut updates = msg.updates;
-        let mut results = vec![];
+        drop(players);
+        drop(teams);
 
-        while !updates.is_empty() {
-            let update = updates.pop().unwrap();
+        let mut components = MessageComponents::default_for(MessageType::Players);
+        components.players = Some(vec![entry]);
 
-            let vx = update.vx;
-            let vy = update.vy;
-            let vz = update.vz;
-            let id = update.r#type;
-            let rotation = update.rotation;
-            let y_rotation = update.y_rotation;
+        self.write_resource::<MessagesQueue>()
+            .push((create_message(components), None, None, None));
+    }
 
-            if vy < 0 || vy >= chunks.config.max_height as i32 || !chunks.registry.has_type(id) {
-                continue;
+    /// Store a skin uploaded by a player and share its URL through the peer metadata
+    pub fn set_skin(
+        &mut self,
+        player_id: usize,
+        token: &str,
+        data: &[u8],
+    ) -> Result<String, String> {
+        let (entity, name) = {
+            let players = self.read_resource::<Players>();
+            let player = players
+                .get(&player_id)
+                .ok_or_else(|| "Player not found.".to_owned())?;
+
+            if !constant_time_eq(player.skin_token.as_bytes(), token.as_bytes()) {
+                return Err("Invalid skin token.".to_owned());
             }
 
-            let chunk = chunks.get_chunk_by_voxel(vx, vy, vz).unwrap();
-            if chunk.needs_propagation {
-                continue;
-            }
+            (
+                player.entity,
+                player.name.clone().unwrap_or_else(|| player_id.to_string()),
+            )
+        };
 
-            let current_id = chunks.get_voxel_by_voxel(vx, vy, vz);
-            if chunks.registry.is_air(current_id) && chunks.registry.is_air(id) {
-                continue;
-            }
+        let url = save_skin(&self.name, &name, data)?;
 
-            chunks.start_caching();
-            chunks.update(vx, vy, vz, id, rotation, y_rotation);
-            chunks.stop_caching();
+        if let Some(metadata) = self.ecs.write_storage::<Metadata>().get_mut(entity) {
+            metadata.set("skin", &url);
+        }
 
-            let neighbor_chunks = chunks.get_neighbor_chunk_coords(vx, vy, vz);
-            neighbor_chunks.into_iter().for_each(|c| {
-                chunks.chunk_cache.insert(c);
-            });
+        Ok(url)
+    }
 
-            if chunks
-                .registry
-                .is_plant(chunks.get_voxel_by_voxel(vx, vy + 1, vz))
-            {
-                updates.push(messages::Update {
-                    vx,
-                    vy: vy + 1,
-                    vz,
-                    r#type: air,
-                    rotation: 0,
-                    y_rotation: 0,
-                });
-            }
+    /// Get the typed block entity at a voxel coordinate
+    pub fn get_block_entity<T: BlockEntity>(&self, vx: i32, vy: i32, vz: i32) -> Option<T> {
+        self.read_resource::<Chunks>()
+            .get_block_entity_by_voxel(vx, vy, vz)
+            .and_then(|data| data.get::<T>())
+    }
 
-            results.push(update);
+    /// Attach a block entity to a voxel coordinate and sync it to all players
+    ///
+    /// Returns false if the chunk isn't loaded.
+    pub fn set_block_entity<T: BlockEntity>(
+        &mut self,
+        vx: i32,
+        vy: i32,
+        vz: i32,
+        entity: &T,
+    ) -> bool {
+        let data = BlockEntityData::new(entity);
+        let protocol = data.to_protocol(&Vec3(vx, vy, vz));
+
+        if !self
+            .write_resource::<Chunks>()
+            .set_block_entity_by_voxel(vx, vy, vz, data)
+        {
+            return false;
         }
 
-        let cache = chunks.chunk_cache.clone();
-        chunks.clear_cache();
+        self.broadcast_block_entity(protocol);
 
-        drop(chunks);
+        if T::TICKS {
+            self.schedule_block_tick(vx, vy, vz, 1);
+        }
 
-        let mut chunk_mesh_protocols = vec![];
+        true
+    }
 
-        cache.iter().for_each(|coords| {
-            let mut chunks = self.write_resource::<Chunks>();
+    /// Schedule the block entity at a voxel coordinate to be ticked in `delay` ticks
+    pub fn schedule_block_tick(&mut self, vx: i32, vy: i32, vz: i32, delay: i32) {
+        let tick = self.read_resource::<Clock>().tick + delay.max(1);
+        self.write_resource::<BlockTicks>()
+            .schedule(Vec3(vx, vy, vz), tick);
+    }
 
-            let levels = chunks.raw(&coords).unwrap().dirty_levels.clone();
-            let mesh_level = MeshLevel::Levels(levels);
+    /// Set the block entity whose UI a player has open, receiving its every change
+    pub fn set_viewing(&mut self, player_id: usize, voxel: Option<Vec3<i32>>) {
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.viewing = voxel;
+        }
+    }
 
-            let chunk = chunks.get(&coords, &mesh_level, true).unwrap();
-            chunk_mesh_protocols.push(chunk.get_protocol(true, false, false, mesh_level));
+    /// Remove the block entity at a voxel coordinate and sync the removal to all players
+    pub fn remove_block_entity(&mut self, vx: i32, vy: i32, vz: i32) -> Option<BlockEntityData> {
+        let removed = self
+            .write_resource::<Chunks>()
+            .remove_block_entity_by_voxel(vx, vy, vz)?;
 
-            drop(chunks);
+        self.broadcast_block_entity(BlockEntityProtocol {
+            voxel: Vec3(vx, vy, vz),
+            kind: removed.kind.to_owned(),
+            data: String::new(),
         });
 
-        let chunks = self.read_resource::<Chunks>();
-
-        // First send the message, so borrow checker doesn't freak out
-        let mut components = MessageComponents::default_for(MessageType::Update);
-        let mut chunk_protocols: Vec<ChunkProtocol> = cache
-            .iter()
-            .map(|coords| {
-                chunks
-                    .get_chunk(coords)
-                    .unwrap()
-                    .get_protocol(false, false, true, MeshLevel::None)
-            })
-            .collect();
-        chunk_protocols.append(&mut chunk_mesh_protocols);
-        components.chunks = Some(chunk_protocols);
-        let mut new_message = create_message(components);
-        new_message.updates = results;
+        Some(removed)
+    }
 
-        drop(chunks);
+    fn broadcast_block_entity(&mut self, protocol: BlockEntityProtocol) {
+        let mut components = MessageComponents::default_for(MessageType::BlockEntity);
+        components.block_entities = Some(vec![protocol]);
 
-        self.broadcast(&new_message, vec![], vec![]);
+        let message = create_message(components);
+        self.broadcast(&message, vec![], vec![]);
     }
 
-    /// Adds the player update to the resource `PlayerUpdate`, handled later in an ECS system.
-    pub fn on_peer(&mut self, player_id: usize, msg: messages::Message) {
-        let mut player_updates = self.write_resource::<PlayerUpdates>();
-        player_updates.insert(player_id, msg.peers[0].clone());
+    /// Get the sunlight level at a voxel coordinate
+    pub fn get_sunlight(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.read_resource::<Chunks>().get_sunlight(vx, vy, vz)
     }
 
-    /// Handles an incoming chat message, broadcasts response lazily
-    pub fn on_chat_message(&mut self, player_id: usize, msg: messages::Message) {
-        if let Some(message) = msg.message.clone() {
-            let sender: String = message.sender;
-            let body: String = message.body;
-
-            info!("{}: {}", sender, body);
+    /// Get the brightest torch light level of any color at a voxel coordinate
+    pub fn get_torch_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        let chunks = self.read_resource::<Chunks>();
 
-            if body.starts_with('/') {
-                let body = body
-                    .strip_prefix('/')
-                    .unwrap()
-                    .split_whitespace()
-                    .collect::<Vec<_>>();
+        [LightColor::Red, LightColor::Green, LightColor::Blue]
+            .iter()
+            .map(|color| chunks.get_torch_light(vx, vy, vz, color))
+            .max()
+            .unwrap_or(0)
+    }
 
-                let mut msgs = vec![];
+    /// Get the effective light level at a voxel coordinate for a time of day (0 to 2400)
+    ///
+    /// Sunlight is dimmed by the time of day the same way clients shade it, and the brighter
+    /// of sunlight and torch light wins.
+    pub fn get_combined_light(&self, vx: i32, vy: i32, vz: i32, time: f32) -> u32 {
+        let sunlight = self.get_sunlight(vx, vy, vz) as f32 * sunlight_intensity(time);
+        (sunlight.round() as u32).max(self.get_torch_light(vx, vy, vz))
+    }
 
-                let create_msg = |chat_type: ChatType, body: &str| {
-                    create_chat_message(MessageType::Message, chat_type, "", body)
-                };
+    /// Emit the light levels at a voxel coordinate as a `WorldEvent::LightChanged`
+    fn emit_light_changed(&mut self, vx: i32, vy: i32, vz: i32) {
+        let sunlight = self.get_sunlight(vx, vy, vz);
+        let torch_light = self.get_torch_light(vx, vy, vz);
 
-                if body.is_empty() {
-                    msgs.push(create_msg(ChatType::Error, "Unknown command."));
-                } else {
-                    match body[0] {
-                        "save" => {
-                            self.save();
-                            msgs.push(create_msg(ChatType::Info, "World has been saved."));
-                        }
-                        "summon" => {
-                            self.test_entity(player_id);
-                            msgs.push(create_msg(ChatType::Info, "Summoned a test entity."));
-                        }
-                        _ => {}
-                    }
-                }
+        self.write_resource::<WorldEvents>()
+            .push(WorldEvent::LightChanged {
+                voxel: Vec3(vx, vy, vz),
+                sunlight,
+                torch_light,
+            });
+    }
 
-                msgs.into_iter().for_each(|msg| {
-                    self.broadcast_lazy(&msg, vec![], vec![], player_id);
-                });
-            } else {
-                self.broadcast_lazy(&msg, vec![], vec![], player_id);
+    /// Give an entity a status effect for `duration` seconds
+    ///
+    /// Keeps the stronger, or else the longer, of the effects of the same kind.
+    pub fn add_effect(&mut self, entity: Entity, kind: EffectType, amplifier: u32, duration: f32) {
+        // effects modify the attributes, which entities spawned with a brain alone start from
+        let mut attributes = self.ecs.write_storage::<Attributes>();
+        if !attributes.contains(entity) {
+            if let Some(brain) = self.ecs.read_storage::<Brain>().get(entity) {
+                let _ = attributes.insert(entity, Attributes::of_brain(&brain.options));
             }
         }
-    }
+        drop(attributes);
 
-    /// TEST:
-    ///
-    /// Used to test entity spawning
-    pub fn test_entity(&mut self, player_id: usize) {
-        let players = self.read_resource::<Players>();
-        let player = players.get(&player_id);
+        let mut effects = self.ecs.write_storage::<Effects>();
 
-        if player.is_none() {
-            return;
+        if let Ok(entry) = effects.entry(entity) {
+            entry
+                .or_insert_with(Effects::new)
+                .add(kind, amplifier, duration);
         }
+    }
 
-        let player = player.unwrap();
+    /// Resolved value of an entity's attribute, none if the entity doesn't have it
+    pub fn attribute(&self, entity: Entity, kind: AttributeKind) -> Option<f32> {
+        let attributes = self.ecs.read_storage::<Attributes>();
+        let attributes = attributes.get(entity)?;
 
-        let bodies = self.ecs().read_component::<RigidBody>();
-        let body = bodies.get(player.entity).unwrap();
+        // resolved every tick, so the ones changed since are worked out on the spot
+        attributes.value(kind).or_else(|| attributes.compute(kind))
+    }
+
+    /// Replace the attribute modifiers of a source on an entity, such as a plugin's or an armor
+    /// piece's, removing them if there are none
+    ///
+    /// Returns false if the entity has no attributes.
+    pub fn set_attribute_modifiers(
+        &mut self,
+        entity: Entity,
+        source: &str,
+        modifiers: Vec<AttributeModifier>,
+    ) -> bool {
+        match self.ecs.write_storage::<Attributes>().get_mut(entity) {
+            Some(attributes) => {
+                attributes.set_modifiers(source, modifiers);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a status effect from an entity
+    pub fn remove_effect(&mut self, entity: Entity, kind: EffectType) {
+        if let Some(effects) = self.ecs.write_storage::<Effects>().get_mut(entity) {
+            effects.remove(kind);
+        }
+    }
+
+    /// Remove all status effects from an entity
+    pub fn clear_effects(&mut self, entity: Entity) {
+        if let Some(effects) = self.ecs.write_storage::<Effects>().get_mut(entity) {
+            effects.clear();
+        }
+    }
+
+    /// Get what the loot a player gets depends on, its held tool and effects
+    pub fn get_loot_context(&self, player_id: usize) -> LootContext {
+        let entity = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => player.entity,
+            None => return LootContext::default(),
+        };
+
+        let chunks = self.read_resource::<Chunks>();
+        let registry = &chunks.registry;
+        let tool_tier = self
+            .ecs
+            .read_storage::<Metadata>()
+            .get(entity)
+            .and_then(|metadata| metadata.data.get("held").cloned())
+            .and_then(|held| {
+                held.parse::<u32>()
+                    .ok()
+                    .filter(|&id| registry.has_type(id))
+                    .or_else(|| registry.try_get_id_by_name(&held))
+            })
+            .map_or(0, |id| registry.get_block_by_id(id).tool_tier);
+
+        let silk_touch = self
+            .ecs
+            .read_storage::<Effects>()
+            .get(entity)
+            .is_some_and(|effects| effects.get(EffectType::SilkTouch).is_some());
+
+        LootContext {
+            tool_tier,
+            silk_touch,
+        }
+    }
+
+    /// Spawn a stack of items lying at a world position for each of the drops
+    pub fn drop_items(&mut self, drops: Vec<ItemStack>, position: &Vec3<f32>) {
+        let prototype = match self.read_resource::<Entities>().get_prototype("Item") {
+            Some(prototype) => prototype.clone(),
+            None => return,
+        };
+
+        for stack in drops {
+            Entities::spawn_item(self.ecs_mut(), &prototype, stack, position);
+        }
+    }
+
+    /// Remove a mob, dropping its loot as rolled for the player who killed it
+    pub fn kill_entity(&mut self, entity: Entity, killer: Option<usize>) {
+        let etype = match self.ecs.read_storage::<EType>().get(entity) {
+            Some(etype) => etype.0.to_owned(),
+            None => return,
+        };
+        let position = match self.ecs.read_storage::<RigidBody>().get(entity) {
+            Some(body) => body.get_position(),
+            None => return,
+        };
+
+        let context = killer.map_or_else(LootContext::default, |id| self.get_loot_context(id));
+        let drops = self.read_resource::<LootTables>().roll_entity(
+            &etype,
+            &context,
+            self.ecs
+                .write_resource::<RngService>()
+                .stream(RngDomain::Loot),
+        );
+
+        self.ecs
+            .delete_entity(entity)
+            .expect("Unable to remove a killed entity.");
+
+        self.drop_items(drops, &position);
+    }
+
+    /// Get where an entity was as seen by a player, rewound by the player's round-trip time
+    pub fn get_rewound_position(&self, player_id: usize, entity: Entity) -> Option<Vec3<f32>> {
+        let rtt = self.read_resource::<Players>().get(&player_id)?.ping as u64;
+        let histories = self.ecs.read_storage::<History>();
+
+        histories
+            .get(entity)?
+            .position_at(unix_millis().saturating_sub(rtt))
+    }
+
+    /// Check if an entity is within `reach` of a player's head, as seen by the player
+    ///
+    /// Used to validate interactions such as attacks and projectile hits.
+    pub fn is_within_reach(&self, player_id: usize, entity: Entity, reach: f32) -> bool {
+        let bodies = self.ecs.read_storage::<RigidBody>();
+        let players = self.read_resource::<Players>();
+
+        let (player, target) = match (
+            players.get(&player_id).and_then(|p| bodies.get(p.entity)),
+            bodies.get(entity),
+        ) {
+            (Some(player), Some(target)) => (player, target),
+            _ => return false,
+        };
+
+        let rewound = match self.get_rewound_position(player_id, entity) {
+            Some(rewound) => rewound,
+            None => target.get_position(),
+        };
+
+        // move the target's hit box back in time, then find its closest point to the head
+        let mut aabb = target.aabb.clone();
+        aabb.translate(&rewound.sub(&target.get_position()));
+
+        let head = player.get_head_position();
+        let closest = Vec3(
+            head.0.max(aabb.base.0).min(aabb.max.0),
+            head.1.max(aabb.base.1).min(aabb.max.1),
+            head.2.max(aabb.base.2).min(aabb.max.2),
+        );
+
+        closest.sub(&head).len() <= reach
+    }
+
+    /// Get the block a player is looking at within reach, and the face it's looking at
+    pub fn pick_block(&self, player_id: usize) -> Option<BlockTarget> {
+        let entity = self.read_resource::<Players>().get(&player_id)?.entity;
+
+        let bodies = self.ecs.read_storage::<RigidBody>();
+        let rotations = self.ecs.read_storage::<Rotation>();
+        let chunks = self.read_resource::<Chunks>();
+
+        let head = bodies.get(entity)?.get_head_position();
+        let direction = rotations.get(entity)?.0.forward();
+        let dimension = chunks.config.dimension as f32;
+
+        let pickable = |vx: i32, vy: i32, vz: i32| {
+            let id = chunks.get_voxel_by_voxel(vx, vy, vz);
+            id != 0 && !chunks.registry.is_fluid(id)
+        };
+
+        pick(
+            &pickable,
+            &Vec3(head.0 / dimension, head.1 / dimension, head.2 / dimension),
+            &direction,
+            PICK_REACH,
+        )
+    }
+
+    /// Tell the players within view of each player what it started targeting, so that clients
+    /// can draw the selection boxes of others
+    fn broadcast_targets(&mut self) {
+        if !self.read_resource::<WorldConfig>().broadcast_targets {
+            return;
+        }
+
+        let ids = self
+            .read_resource::<Players>()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            let target = self.pick_block(id);
+
+            let mut players = self.write_resource::<Players>();
+            let player = match players.get_mut(&id) {
+                Some(player) if player.target != target => player,
+                _ => continue,
+            };
+            player.target = target.clone();
+            let entity = player.entity;
+            drop(players);
+
+            let nearby = self.get_players_in_view(entity, id);
+            if nearby.is_empty() {
+                continue;
+            }
+
+            let data = match &target {
+                Some(BlockTarget {
+                    voxel: Vec3(vx, vy, vz),
+                    face: Vec3(fx, fy, fz),
+                }) => format!(
+                    "{{\"id\": \"{}\", \"voxel\": [{}, {}, {}], \"face\": [{}, {}, {}]}}",
+                    id, vx, vy, vz, fx, fy, fz
+                ),
+                None => format!("{{\"id\": \"{}\", \"voxel\": null}}", id),
+            };
+
+            let mut components = MessageComponents::default_for(MessageType::Target);
+            components.json = Some(data);
+
+            self.broadcast(&create_message(components), nearby, vec![]);
+        }
+    }
+
+    /// Get the other players that have an entity within their view radius
+    fn get_players_in_view(&self, entity: Entity, except: usize) -> Vec<usize> {
+        let bodies = self.ecs.read_storage::<RigidBody>();
+        let radiuses = self.ecs.read_storage::<ViewRadius>();
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+
+        let position = match bodies.get(entity) {
+            Some(body) => body.get_position(),
+            None => return vec![],
+        };
+
+        self.read_resource::<Players>()
+            .iter()
+            .filter(|(id, _)| **id != except)
+            .filter(
+                |(_, player)| match (bodies.get(player.entity), radiuses.get(player.entity)) {
+                    (Some(body), Some(radius)) => {
+                        body.get_position().sub(&position).len() <= radius.0 as f32 * dimension
+                    }
+                    _ => false,
+                },
+            )
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Move a player on top of a voxel
+    pub fn teleport(&mut self, player_id: usize, voxel: &Vec3<i32>) {
+        let entity = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => player.entity,
+            None => return,
+        };
+
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+
+        if let Some(body) = self.ecs.write_storage::<RigidBody>().get_mut(entity) {
+            body.set_position(&Vec3(
+                (voxel.0 as f32 + 0.5) * dimension,
+                (voxel.1 as f32 + 1.0) * dimension,
+                (voxel.2 as f32 + 0.5) * dimension,
+            ));
+        }
+
+        let mut new_message = create_of_type(MessageType::Teleport);
+        new_message.json = format!("{{\"voxel\": [{}, {}, {}]}}", voxel.0, voxel.1, voxel.2);
+
+        self.broadcast_lazy(&new_message, vec![player_id], vec![], player_id);
+    }
+
+    /// Make an entity ride another, kept at `offset` from the mount's position
+    ///
+    /// Returns false if the rider is the mount, or is ridden by it directly or not.
+    pub fn mount(&mut self, rider: Entity, mount: Entity, offset: Vec3<f32>) -> bool {
+        let mut mounts = self.ecs.write_storage::<Mounted>();
+
+        // walk down the mounts below to prevent riding loops
+        let mut below = Some(mount);
+        while let Some(entity) = below {
+            if entity == rider {
+                return false;
+            }
+
+            below = mounts.get(entity).map(|mounted| mounted.mount);
+        }
+
+        if mounts
+            .insert(rider, Mounted::new(mount, offset.to_owned()))
+            .is_err()
+        {
+            return false;
+        }
+
+        drop(mounts);
+
+        self.broadcast_mount(MessageType::Mount, rider, Some(mount), offset);
+
+        true
+    }
+
+    /// Detach an entity from the entity it rides, returns false if it wasn't riding
+    pub fn dismount(&mut self, rider: Entity) -> bool {
+        match self.ecs.write_storage::<Mounted>().remove(rider) {
+            Some(_) => {}
+            None => return false,
+        }
+
+        self.broadcast_mount(MessageType::Dismount, rider, None, Vec3::default());
+
+        true
+    }
+
+    fn broadcast_mount(
+        &mut self,
+        message_type: MessageType,
+        rider: Entity,
+        mount: Option<Entity>,
+        offset: Vec3<f32>,
+    ) {
+        let ids = self.ecs.read_storage::<Id>();
+        let uuids = self.ecs.read_storage::<Uuid>();
+
+        let mut components = MessageComponents::default_for(message_type);
+        components.mount = Some(MountProtocol {
+            rider: protocol_id(rider, ids.get(rider), uuids.get(rider)),
+            mount: mount
+                .map(|mount| protocol_id(mount, ids.get(mount), uuids.get(mount)))
+                .unwrap_or_default(),
+            offset,
+        });
+
+        drop(ids);
+        drop(uuids);
+
+        self.write_resource::<MessagesQueue>()
+            .push((create_message(components), None, None, None));
+    }
+
+    /// Find an entity by its id in the protocol, the player id of players or the UUID of others
+    pub fn find_entity(&self, id: &str) -> Option<Entity> {
+        if let Ok(player_id) = id.parse::<usize>() {
+            return self
+                .read_resource::<Players>()
+                .get(&player_id)
+                .map(|player| player.entity);
+        }
+
+        self.read_resource::<Entities>()
+            .get(id)
+            .filter(|entity| self.ecs.is_alive(*entity))
+    }
+
+    /// Handle a player mounting the entity whose id is the message's text
+    pub fn on_mount(&mut self, player_id: usize, msg: messages::Message) {
+        let mount = match self.find_entity(&msg.text) {
+            Some(mount) => mount,
+            None => return,
+        };
+
+        let rider = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => player.entity,
+            None => return,
+        };
+
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+
+        if !self.ecs.is_alive(mount)
+            || !self.is_within_reach(player_id, mount, MOUNT_REACH * dimension)
+        {
+            return;
+        }
+
+        // sit on top of the mount
+        let offset = match self.ecs.read_storage::<RigidBody>().get(mount) {
+            Some(body) => Vec3(0.0, body.aabb.vec.1, 0.0),
+            None => return,
+        };
+
+        self.mount(rider, mount, offset);
+    }
+
+    /// Handle a player getting off what they ride
+    pub fn on_dismount(&mut self, player_id: usize) {
+        let rider = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => player.entity,
+            None => return,
+        };
+
+        self.dismount(rider);
+    }
+
+    /// Have a player attack an entity, returns whether the attack landed
+    ///
+    /// Targets out of reach, the player themselves, and teammates without friendly fire, are left
+    /// unharmed, as are all targets until `ATTACK_COOLDOWN` ticks after the player's last hit.
+    pub fn attack(&mut self, player_id: usize, target: Entity, damage: f32) -> bool {
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+        let tick = self.read_resource::<Clock>().tick;
+
+        if !self.is_within_reach(player_id, target, ATTACK_REACH * dimension) {
+            return false;
+        }
+
+        {
+            let players = self.read_resource::<Players>();
+            let teams = self.read_resource::<Teams>();
+
+            let player = match players.get(&player_id) {
+                Some(player) => player,
+                None => return false,
+            };
+            let cooling_down = player
+                .last_attack
+                .is_some_and(|last| (0..ATTACK_COOLDOWN).contains(&tick.saturating_sub(last)));
+            if player.entity == target || cooling_down {
+                return false;
+            }
+
+            let attacker = players.get(&player_id).and_then(|p| p.name.to_owned());
+            let victim = players
+                .values()
+                .find(|p| p.entity == target)
+                .and_then(|p| p.name.to_owned());
+
+            if let (Some(attacker), Some(victim)) = (attacker, victim) {
+                if !teams.can_hurt(&attacker, &victim) {
+                    return false;
+                }
+            }
+        }
+
+        let mut healths = self.ecs.write_storage::<Health>();
+        let health = match healths.get_mut(target) {
+            Some(health) => health,
+            None => return false,
+        };
+
+        health.damage(damage, 0.0);
+
+        let current = health.current;
+        let dead = health.is_dead();
+        drop(healths);
+
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.last_attack = Some(tick);
+        }
+
+        if let Some(metadata) = self.ecs.write_storage::<Metadata>().get_mut(target) {
+            metadata.set("health", &current.to_string());
+        }
+
+        // dead players are left for respawning to deal with
+        let victim = self
+            .read_resource::<Players>()
+            .iter()
+            .find(|(_, p)| p.entity == target)
+            .map(|(id, _)| *id);
+        match (dead, victim) {
+            (true, Some(victim)) => self
+                .write_resource::<WorldEvents>()
+                .push(WorldEvent::PlayerDied { player_id: victim }),
+            (true, None) => self.kill_entity(target, Some(player_id)),
+            _ => {}
+        }
+
+        true
+    }
+
+    /// Handle a player attacking the entity whose id is the message's text
+    pub fn on_attack(&mut self, player_id: usize, msg: messages::Message) {
+        if let Some(target) = self.find_entity(&msg.text) {
+            let damage = self
+                .read_resource::<Players>()
+                .get(&player_id)
+                .and_then(|player| self.attribute(player.entity, AttributeKind::AttackDamage))
+                .unwrap_or(ATTACK_DAMAGE);
+
+            self.attack(player_id, target, damage);
+        }
+    }
+
+    /// Broadcast a message instantly
+    ///
+    /// Suggested against, use message_queue instead.
+    pub fn broadcast(&mut self, msg: &messages::Message, include: Vec<usize>, exclude: Vec<usize>) {
+        let translations = self.read_resource::<Translations>();
+
+        self.ecs
+            .write_resource::<Players>()
+            .broadcast(msg, include, exclude, None, &translations);
+    }
+
+    /// Adds a message to the message queue
+    ///
+    /// Message will be sent per tick, in a lazy fashion.
+    pub fn broadcast_lazy(
+        &mut self,
+        msg: &messages::Message,
+        include: Vec<usize>,
+        exclude: Vec<usize>,
+        from: usize,
+    ) {
+        self.write_resource::<MessagesQueue>().push((
+            msg.to_owned(),
+            Some(include),
+            Some(exclude),
+            Some(from),
+        ))
+    }
+
+    /// Handles server-side chunk request
+    pub fn on_chunk_request(&mut self, player_id: usize, msg: messages::Message) {
+        let chunk_size = self.read_resource::<WorldConfig>().chunk_size;
+        let mut players = self.ecs.write_resource::<Players>();
+
+        let json = msg.parse_json().unwrap();
+
+        let cx = json["x"].as_i64().unwrap() as i32;
+        let cz = json["z"].as_i64().unwrap() as i32;
+        let coords = Vec2(cx, cz);
+
+        if let Some(player) = players.get_mut(&player_id) {
+            let curr_chunks = self.ecs.read_storage::<CurrChunk>();
+            let radiuses = self.ecs.read_storage::<ViewRadius>();
+
+            // ignore chunks outside of the player's negotiated view radius
+            if let (
+                Some(CurrChunk {
+                    val: Some(center), ..
+                }),
+                Some(radius),
+            ) = (curr_chunks.get(player.entity), radiuses.get(player.entity))
+            {
+                if !radius.loosely_covers_chunk(center, &coords, chunk_size) {
+                    return;
+                }
+            }
+
+            player.requested_chunks.push_back(coords);
+        }
+    }
+
+    /// Handles a pong echoing the server time of a ping
+    ///
+    /// Updates the player's round-trip time and replies with the current server
+    /// time, so the client can sync its clock.
+    pub fn on_pong(&mut self, player_id: usize, msg: messages::Message) {
+        let json = msg.parse_json().unwrap();
+
+        let sent = match json["time"].as_u64() {
+            Some(sent) => sent,
+            None => return,
+        };
+
+        let now = unix_millis();
+        let rtt = now.saturating_sub(sent) as u32;
+
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.ping = rtt;
+        } else {
+            return;
+        }
+
+        let time = self.read_resource::<Clock>().time;
+
+        let mut new_message = create_of_type(MessageType::Time);
+        new_message.json = format!(
+            "{{\"serverTime\": {}, \"rtt\": {}, \"time\": {}}}",
+            now, rtt, time
+        );
+
+        self.broadcast_lazy(&new_message, vec![player_id], vec![], player_id);
+        self.broadcast_list_entry(player_id);
+    }
+
+    /// Handles server-side config change
+    pub fn on_config(&mut self, player_id: usize, msg: messages::Message) {
+        let mut clock = self.write_resource::<Clock>();
+
+        let json = msg.parse_json().unwrap();
+
+        let time = json["time"].as_f64();
+        let tick_speed = json["tickSpeed"].as_f64();
+
+        if let Some(time) = time {
+            clock.time = time as f32;
+        }
+
+        if let Some(tick_speed) = tick_speed {
+            clock.tick_speed = tick_speed as f32;
+        }
+
+        // damn?
+        drop(clock);
+
+        let mut new_message = create_of_type(MessageType::Config);
+        new_message.json = json.to_string();
+
+        self.broadcast_lazy(&new_message, vec![], vec![], player_id);
+    }
+
+    /// Handles server-side voxel updates
+    ///
+    /// Remesh chunks based on which sub-chunks are changed according to internal
+    /// chunk caching system.
+    pub fn on_update(&mut self, player_id: usize, msg: messages::Message) {
+        let mut allowed = vec![];
+        let mut rejected = vec![];
+
+        for update in msg.updates {
+            if self.can_edit(player_id, &Vec3(update.vx, update.vy, update.vz))
+                && self.claim_edit(player_id, &update)
+            {
+                allowed.push(update);
+            } else {
+                rejected.push(update);
+            }
+        }
+
+        let edit = self.apply_updates(allowed.to_owned());
+
+        // edits the world skipped, such as ones out of bounds, are rejected too
+        let (accepted, skipped): (Vec<_>, Vec<_>) = allowed.into_iter().partition(|update| {
+            edit.applied.iter().any(|applied| {
+                (applied.vx, applied.vy, applied.vz) == (update.vx, update.vy, update.vz)
+            })
+        });
+        rejected.extend(skipped);
+
+        self.acknowledge_edits(player_id, &accepted, &rejected);
+
+        let air = *self
+            .read_resource::<Chunks>()
+            .registry
+            .get_id_by_name("Air");
+        let broken = edit
+            .applied
+            .iter()
+            .zip(edit.previous.iter())
+            .filter(|(applied, previous)| applied.r#type == air && previous.r#type != air)
+            .map(|(_, previous)| WorldEvent::BlockBroken {
+                player_id,
+                voxel: Vec3(previous.vx, previous.vy, previous.vz),
+                id: previous.r#type,
+            })
+            .collect::<Vec<_>>();
+
+        if !broken.is_empty() {
+            let context = self.get_loot_context(player_id);
+            let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+
+            for event in broken.iter() {
+                if let WorldEvent::BlockBroken { voxel, id, .. } = event {
+                    let drops = self.read_resource::<LootTables>().roll_block(
+                        *id,
+                        &self.read_resource::<Chunks>().registry,
+                        &context,
+                        self.ecs
+                            .write_resource::<RngService>()
+                            .stream(RngDomain::Loot),
+                    );
+                    let position = Vec3(
+                        (voxel.0 as f32 + 0.5) * dimension,
+                        (voxel.1 as f32 + 0.5) * dimension,
+                        (voxel.2 as f32 + 0.5) * dimension,
+                    );
+
+                    self.drop_items(drops, &position);
+                }
+            }
+        }
+
+        self.write_resource::<WorldEvents>().extend(broken);
+
+        self.record_edit(player_id, edit);
+    }
+
+    /// Check that a voxel is within a player's reach and in sight, as set by the world's edit rules
+    pub fn can_edit(&self, player_id: usize, voxel: &Vec3<i32>) -> bool {
+        let players = self.read_resource::<Players>();
+        let player = match players.get(&player_id) {
+            Some(player) => player,
+            None => return false,
+        };
+
+        let bodies = self.ecs.read_storage::<RigidBody>();
+        let head = match bodies.get(player.entity) {
+            Some(body) => body.get_head_position(),
+            None => return false,
+        };
+
+        let chunks = self.read_resource::<Chunks>();
+        let config = self.read_resource::<WorldConfig>();
+        let rules = &config.edits;
+        let dimension = chunks.config.dimension as f32;
+
+        let solid = |vx: i32, vy: i32, vz: i32| !chunks.get_walkable_by_voxel(vx, vy, vz);
+
+        rules.allows(
+            &solid,
+            &Vec3(head.0 / dimension, head.1 / dimension, head.2 / dimension),
+            voxel,
+            rules.is_creative(player.name.as_deref()),
+        )
+    }
+
+    /// Order a player's edit against the others, false if it's older than one already applied or
+    /// another player edited the voxel first this tick
+    fn claim_edit(&mut self, player_id: usize, update: &messages::Update) -> bool {
+        if update.sequence != 0 {
+            let mut players = self.write_resource::<Players>();
+
+            if let Some(player) = players.get_mut(&player_id) {
+                if update.sequence < player.edit_sequence {
+                    return false;
+                }
+
+                player.edit_sequence = update.sequence;
+            }
+        }
+
+        self.write_resource::<TickEdits>()
+            .claim(Vec3(update.vx, update.vy, update.vz), player_id)
+    }
+
+    /// Tell a player which of its edits were accepted, and what the voxels of the rejected ones
+    /// really are so that its client undoes what it predicted
+    fn acknowledge_edits(
+        &mut self,
+        player_id: usize,
+        accepted: &[messages::Update],
+        rejected: &[messages::Update],
+    ) {
+        let acks = accepted
+            .iter()
+            .map(|update| (update, true))
+            .chain(rejected.iter().map(|update| (update, false)))
+            .filter(|(update, _)| update.sequence != 0)
+            .map(|(update, accepted)| messages::EditAck {
+                sequence: update.sequence,
+                accepted,
+            })
+            .collect::<Vec<_>>();
+
+        if acks.is_empty() && rejected.is_empty() {
+            return;
+        }
+
+        let chunks = self.read_resource::<Chunks>();
+        let chunk_size = chunks.config.chunk_size;
+
+        let updates = rejected
+            .iter()
+            .map(|&messages::Update { vx, vy, vz, .. }| {
+                let (rotation, y_rotation) =
+                    BlockRotation::decode(&chunks.get_voxel_rotation_by_voxel(vx, vy, vz));
+
+                messages::Update {
+                    vx,
+                    vy,
+                    vz,
+                    r#type: chunks.get_voxel_by_voxel(vx, vy, vz),
+                    rotation,
+                    y_rotation,
+                    sequence: 0,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let coords = rejected
+            .iter()
+            .map(|update| map_voxel_to_chunk(update.vx, update.vy, update.vz, chunk_size))
+            .collect::<HashSet<_>>();
+
+        let mut components = MessageComponents::default_for(MessageType::EditAck);
+        components.chunks = Some(
+            coords
+                .iter()
+                .filter_map(|coords| chunks.get_chunk(coords))
+                .map(|chunk| chunk.get_protocol(true, false, false, MeshLevel::All))
+                .collect(),
+        );
+
+        let mut message = create_message(components);
+        message.updates = updates;
+        message.acks = acks;
+
+        drop(chunks);
+
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Stream the changes to the voxels and block entities in a region over a channel, in voxels
+    ///
+    /// The region is observed until it's unobserved or the receiver is dropped, instead of the
+    /// chunks being polled for changes.
+    ///
+    /// ```ignore
+    /// let (sender, receiver) = crossbeam_channel::unbounded();
+    /// world.observe_region(Aabb::new(&Vec3(0.0, 70.0, 0.0), &Vec3(1.0, 1.0, 1.0)), sender);
+    /// ```
+    pub fn observe_region(&mut self, region: Aabb, sender: Sender<VoxelChange>) -> ObserverId {
+        self.ecs
+            .write_resource::<Chunks>()
+            .observers
+            .observe(region, sender)
+    }
+
+    /// Stop observing a region, false if it wasn't observed
+    pub fn unobserve_region(&mut self, id: ObserverId) -> bool {
+        self.ecs.write_resource::<Chunks>().observers.unobserve(id)
+    }
+
+    /// Apply several voxel edits at once
+    ///
+    /// Edits are collected in a `VoxelTransaction`, then the touched chunks are remeshed once
+    /// and a single update is broadcasted, instead of one per edit. Returns the applied updates.
+    ///
+    /// ```ignore
+    /// world.batch_update(|tx| {
+    ///     tx.set(0, 80, 0, stone);
+    ///     tx.set(0, 81, 0, stone);
+    /// });
+    /// ```
+    pub fn batch_update<F: FnOnce(&mut VoxelTransaction)>(
+        &mut self,
+        build: F,
+    ) -> Vec<messages::Update> {
+        let mut transaction = VoxelTransaction::default();
+        build(&mut transaction);
+
+        if transaction.is_empty() {
+            return vec![];
+        }
+
+        self.apply_updates(transaction.into_updates()).applied
+    }
+
+    /// Same as `batch_update`, but the edit can be undone by the player
+    pub fn batch_update_as<F: FnOnce(&mut VoxelTransaction)>(
+        &mut self,
+        player_id: usize,
+        build: F,
+    ) -> Vec<messages::Update> {
+        let mut transaction = VoxelTransaction::default();
+        build(&mut transaction);
+
+        let edit = self.apply_updates(transaction.into_updates());
+        let applied = edit.applied.clone();
+        self.record_edit(player_id, edit);
+
+        applied
+    }
+
+    /// Revert the latest edit of a player, returning whether there was one to undo
+    pub fn undo(&mut self, player_id: usize) -> bool {
+        let edit = match self.write_resource::<Players>().get_mut(&player_id) {
+            Some(player) => player.edits.undo(),
+            None => None,
+        };
+
+        match edit {
+            Some(edit) => {
+                self.apply_updates(edit.undo_updates());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply the latest undone edit of a player again, returning whether there was one to redo
+    pub fn redo(&mut self, player_id: usize) -> bool {
+        let edit = match self.write_resource::<Players>().get_mut(&player_id) {
+            Some(player) => player.edits.redo(),
+            None => None,
+        };
+
+        match edit {
+            Some(edit) => {
+                self.apply_updates(edit.redo_updates());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keep an edit in the player's history so it can be undone, and in the audit log
+    fn record_edit(&mut self, player_id: usize, edit: Edit) {
+        let name = self.audit_name(player_id);
+        let time = unix_millis();
+
+        {
+            let mut audit = self.write_resource::<AuditLog>();
+            for (applied, previous) in edit.applied.iter().zip(edit.previous.iter()) {
+                audit.record(
+                    time,
+                    &name,
+                    AuditAction::Edit {
+                        voxel: [applied.vx, applied.vy, applied.vz],
+                        id: applied.r#type,
+                        previous: previous.r#type,
+                        previous_rotation: previous.rotation,
+                        previous_y_rotation: previous.y_rotation,
+                    },
+                );
+            }
+        }
+
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.edits.record(edit);
+        }
+    }
+
+    /// Name a player is audited as, their id if they have none
+    fn audit_name(&self, player_id: usize) -> String {
+        self.read_resource::<Players>()
+            .get(&player_id)
+            .and_then(|player| player.name.to_owned())
+            .unwrap_or_else(|| player_id.to_string())
+    }
+
+    /// Apply voxel updates and broadcast them, the affected chunks are remeshed by `RemeshSystem`
+    ///
+    /// Returns the applied updates along with what the voxels were before.
+    fn apply_updates(&mut self, updates: Vec<messages::Update>) -> Edit {
+        let mut chunks = self.write_resource::<Chunks>();
+
+        let &air = chunks.registry.get_id_by_name("Air");
+
+        let mut updates = updates;
+        let mut results = vec![];
+        let mut previous = vec![];
+
+        while !updates.is_empty() {
+            let update = updates.pop().unwrap();
+
+            let vx = update.vx;
+            let vy = update.vy;
+            let vz = update.vz;
+            let id = update.r#type;
+            let rotation = update.rotation;
+            let y_rotation = update.y_rotation;
+
+            if !chunks.config.contains_height(vy) || !chunks.registry.has_type(id) {
+                continue;
+            }
+
+            let chunk = match chunks.get_chunk_by_voxel(vx, vy, vz) {
+                Some(chunk) => chunk,
+                None => continue,
+            };
+
+            if chunk.needs_propagation {
+                continue;
+            }
+
+            let current_id = chunks.get_voxel_by_voxel(vx, vy, vz);
+            if chunks.registry.is_air(current_id) && chunks.registry.is_air(id) {
+                continue;
+            }
+
+            let (current_rotation, current_y_rotation) =
+                BlockRotation::decode(&chunks.get_voxel_rotation_by_voxel(vx, vy, vz));
+            previous.push(messages::Update {
+                vx,
+                vy,
+                vz,
+                r#type: current_id,
+                rotation: current_rotation,
+                y_rotation: current_y_rotation,
+                sequence: 0,
+            });
+
+            chunks.update(vx, vy, vz, id, rotation, y_rotation);
+
+            if chunks
+                .registry
+                .is_plant(chunks.get_voxel_by_voxel(vx, vy + 1, vz))
+            {
+                updates.push(messages::Update {
+                    vx,
+                    vy: vy + 1,
+                    vz,
+                    r#type: air,
+                    rotation: 0,
+                    y_rotation: 0,
+                    sequence: 0,
+                });
+            }
+
+            // edit sequences only mean something to the player that sent them
+            results.push(messages::Update {
+                sequence: 0,
+                ..update
+            });
+        }
+
+        drop(chunks);
+
+        if results.is_empty() {
+            return Edit::default();
+        }
+
+        // the chunks are remeshed at the end of the tick, along with any others edited in it
+        let mut new_message = create_message(MessageComponents::default_for(MessageType::Update));
+        new_message.updates = results.clone();
+
+        // the updated voxel and the one above it are what spawning and growth care about
+        new_message.updates.iter().for_each(|update| {
+            self.emit_light_changed(update.vx, update.vy, update.vz);
+            self.emit_light_changed(update.vx, update.vy + 1, update.vz);
+        });
+
+        self.broadcast(&new_message, vec![], vec![]);
+
+        Edit {
+            applied: results,
+            previous,
+        }
+    }
+
+    /// Adds the player update to the resource `PlayerUpdate`, handled later in an ECS system.
+    pub fn on_peer(&mut self, player_id: usize, msg: messages::Message) {
+        let peer = match msg.peers.into_iter().next() {
+            Some(peer) => peer,
+            None => return,
+        };
+
+        // selectors and chunk lookups measure from the position, which can't be NaN or infinite
+        let transform = [
+            peer.px, peer.py, peer.pz, peer.qx, peer.qy, peer.qz, peer.qw,
+        ];
+        if !transform.iter().all(|value| value.is_finite()) {
+            return;
+        }
+
+        let mut player_updates = self.write_resource::<PlayerUpdates>();
+        player_updates.insert(player_id, peer);
+    }
+
+    /// Handles movement inputs, only used with server-authoritative movement
+    pub fn on_input(&mut self, player_id: usize, msg: messages::Message) {
+        if !self.read_resource::<WorldConfig>().server_movement {
+            return;
+        }
+
+        let input = match msg.input {
+            Some(input) => input,
+            None => return,
+        };
+
+        let entity = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => player.entity,
+            None => return,
+        };
+
+        let mut controls = self.ecs.write_storage::<Controls>();
+
+        if let Some(controls) = controls.get_mut(entity) {
+            // inputs may arrive out of order, keep the latest
+            if input.sequence < controls.sequence {
+                return;
+            }
+
+            let messages::Input {
+                sequence,
+                forward,
+                backward,
+                left,
+                right,
+                jump,
+                sprint,
+                yaw,
+                pitch,
+            } = input;
+
+            *controls = Controls {
+                sequence,
+                forward,
+                backward,
+                left,
+                right,
+                jump,
+                sprint,
+                yaw,
+                pitch,
+            };
+        }
+    }
+
+    /// Handles a request to open the container at a voxel
+    pub fn on_container_open(&mut self, player_id: usize, msg: messages::Message) {
+        if let Some(container) = msg.container {
+            self.open_container(player_id, &Vec3(container.vx, container.vy, container.vz));
+        }
+    }
+
+    /// Open the container block entity at a voxel for a player
+    ///
+    /// Returns false if there is no container within the player's reach.
+    pub fn open_container(&mut self, player_id: usize, voxel: &Vec3<i32>) -> bool {
+        let entity = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => player.entity,
+            None => return false,
+        };
+
+        let head = match self.ecs.read_storage::<RigidBody>().get(entity) {
+            Some(body) => body.get_head_position(),
+            None => return false,
+        };
+
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+        let center = Vec3(
+            (voxel.0 as f32 + 0.5) * dimension,
+            (voxel.1 as f32 + 0.5) * dimension,
+            (voxel.2 as f32 + 0.5) * dimension,
+        );
+
+        if head.sub(&center).len() > CONTAINER_REACH * dimension {
+            return false;
+        }
+
+        let data = match self
+            .read_resource::<Chunks>()
+            .get_block_entity_by_voxel(voxel.0, voxel.1, voxel.2)
+        {
+            Some(data) if get_container_slots(data).is_some() => data.to_owned(),
+            _ => return false,
+        };
+
+        commands::trading::close_trade(self, player_id);
+
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.container_id = player.container_id.wrapping_add(1);
+            player.viewing = Some(voxel.to_owned());
+        }
+
+        self.send_container(player_id, voxel, &data);
+
+        true
+    }
+
+    /// Send the state of a container to a player, under the id of their open window
+    fn send_container(&mut self, player_id: usize, voxel: &Vec3<i32>, data: &BlockEntityData) {
+        let slots = match get_container_slots(data) {
+            Some(slots) => slots,
+            None => return,
+        };
+
+        let (id, cursor) = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) => (player.container_id, player.cursor.to_owned()),
+            None => return,
+        };
+
+        let mut components = MessageComponents::default_for(MessageType::ContainerOpen);
+        components.container = Some(ContainerProtocol {
+            id,
+            kind: data.kind.to_owned(),
+            voxel: voxel.to_owned(),
+            slots,
+            cursor,
+            offers: vec![],
+            selected: None,
+        });
+
+        let message = create_message(components);
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Handles a slot click in the player's open container, the server decides where items go
+    ///
+    /// Invalid clicks are answered with the current container state to resync the client.
+    pub fn on_container_click(&mut self, player_id: usize, msg: messages::Message) {
+        let click = match msg.click {
+            Some(click) => click,
+            None => return,
+        };
+
+        let trading = self
+            .read_resource::<Players>()
+            .get(&player_id)
+            .is_some_and(|player| player.trading.is_some());
+        if trading {
+            commands::trading::on_trade_click(self, player_id, click);
+            return;
+        }
+
+        let (voxel, mut cursor) = match self.read_resource::<Players>().get(&player_id) {
+            Some(player) if player.container_id == click.container => match &player.viewing {
+                Some(voxel) => (voxel.to_owned(), player.cursor.to_owned()),
+                None => return,
+            },
+            _ => return,
+        };
+
+        let Vec3(vx, vy, vz) = voxel;
+
+        let data = self
+            .read_resource::<Chunks>()
+            .get_block_entity_by_voxel(vx, vy, vz)
+            .cloned();
+
+        let data = match data {
+            Some(data) => data,
+            None => {
+                self.set_viewing(player_id, None);
+                return;
+            }
+        };
+
+        let action = match messages::slot_click::Action::from_i32(click.action) {
+            Some(messages::slot_click::Action::Pickup) => SlotAction::Pickup,
+            Some(messages::slot_click::Action::Split) => SlotAction::Split,
+            None => return,
+        };
+
+        let taken_output = match (data.get::<Furnace>(), click.slot) {
+            (Some(furnace), 2) => furnace.output,
+            _ => None,
+        };
+
+        let updated = click_container(
+            &data,
+            click.slot as usize,
+            action,
+            &mut cursor,
+            &self.read_resource::<Recipes>(),
+        );
+
+        let updated = match updated {
+            Some(updated) => updated,
+            None => {
+                self.send_container(player_id, &voxel, &data);
+                return;
+            }
+        };
+
+        self.write_resource::<Chunks>()
+            .set_block_entity_by_voxel(vx, vy, vz, updated.to_owned());
+
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.cursor = cursor;
+        }
+
+        if let Some(output) = taken_output {
+            let left = updated
+                .get::<Furnace>()
+                .and_then(|furnace| furnace.output)
+                .map_or(0, |stack| stack.count);
+
+            if output.count > left {
+                self.write_resource::<WorldEvents>()
+                    .push(WorldEvent::ItemCrafted {
+                        player_id,
+                        id: output.id,
+                        count: output.count - left,
+                    });
+            }
+        }
+
+        // new items might be ready to smelt
+        if updated.kind == Furnace::KIND {
+            self.schedule_block_tick(vx, vy, vz, 1);
+        }
+
+        // everyone with the container open sees the change
+        let viewers = self
+            .read_resource::<Players>()
+            .iter()
+            .filter(|(_, player)| player.viewing.as_ref() == Some(&voxel))
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        viewers.into_iter().for_each(|viewer| {
+            self.send_container(viewer, &voxel, &updated);
+        });
+    }
+
+    /// Handles the player closing their open container
+    pub fn on_container_close(&mut self, player_id: usize) {
+        self.set_viewing(player_id, None);
+        commands::trading::close_trade(self, player_id);
+    }
+
+    /// Handles an incoming chat message, broadcasts response lazily
+    pub fn on_chat_message(&mut self, player_id: usize, msg: messages::Message) {
+        if let Some(message) = msg.message.clone() {
+            let sender: String = message.sender;
+            let body: String = message.body;
+
+            info!("{}: {}", sender, body);
+
+            let name = self.audit_name(player_id);
+            let action = match body.strip_prefix('/') {
+                Some(command) => AuditAction::Command {
+                    body: command.to_owned(),
+                },
+                None => AuditAction::Chat {
+                    body: body.to_owned(),
+                },
+            };
+            self.write_resource::<AuditLog>()
+                .record(unix_millis(), &name, action);
+
+            if let Some(command) = body.strip_prefix('/') {
+                let msgs = commands::run_command(self, player_id, command);
+
+                msgs.into_iter().for_each(|msg| {
+                    self.broadcast_lazy(&msg, vec![], vec![], player_id);
+                });
+            } else {
+                self.bridge_event(BridgeEvent::Chat {
+                    player: name,
+                    message: body,
+                });
+                self.broadcast_lazy(&msg, vec![], vec![], player_id);
+            }
+        }
+    }
+
+    /// Players of the world as listed by the admin API
+    pub fn player_summaries(&self) -> Vec<PlayerSummary> {
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+        let bodies = self.ecs.read_storage::<RigidBody>();
+
+        let mut summaries = self
+            .read_resource::<Players>()
+            .iter()
+            .map(|(id, player)| PlayerSummary {
+                id: *id,
+                name: player.name.to_owned(),
+                ping: player.ping,
+                position: bodies.get(player.entity).map(|body| {
+                    let Vec3(px, py, pz) = body.get_position();
+                    [px / dimension, py / dimension, pz / dimension]
+                }),
+                address: player.address,
+                detached: player.detached.is_some(),
+            })
+            .collect::<Vec<_>>();
+
+        summaries.sort_by_key(|summary| summary.id);
+        summaries
+    }
+
+    /// Health of the world as reported by the admin API
+    pub fn metrics(&self) -> WorldMetrics {
+        let tick_rate = self.read_resource::<WorldConfig>().server_tick_rate;
+        let profiler = self.read_resource::<Profiler>();
+
+        WorldMetrics {
+            tps: profiler.tps(tick_rate),
+            tick_p50: profiler.tick_percentile(0.5),
+            tick_p95: profiler.tick_percentile(0.95),
+            tick_p99: profiler.tick_percentile(0.99),
+            players: self.read_resource::<Players>().len(),
+            chunks: self.read_resource::<Chunks>().len(),
+            entities: (&self.ecs.read_storage::<EType>()).join().count(),
+        }
+    }
+
+    /// Run a function's commands for a player, discarding their feedback
+    ///
+    /// The commands run with the console's permissions, as only operators and the world's events
+    /// start functions, while `@s` stays the player. Returns how many commands ran, none if
+    /// there's no such function or functions are nested too deep.
+    pub fn run_function(&mut self, player_id: usize, name: &str) -> Option<usize> {
+        let commands = self.read_resource::<Functions>().get(name)?.to_owned();
+
+        if !self.write_resource::<Functions>().enter() {
+            warn!("Function \"{}\" is nested too deep, stopping.", name);
+            return None;
+        }
+
+        for command in commands.iter() {
+            commands::run_command_for(self, player_id, command);
+        }
+
+        self.write_resource::<Functions>().exit();
+
+        Some(commands.len())
+    }
+
+    /// Run the functions due this tick, and the handlers of the player events emitted
+    fn tick_functions(&mut self) {
+        let tick = self.read_resource::<Clock>().tick;
+        let due = self.write_resource::<Functions>().take_due(tick);
+
+        let handlers = self
+            .read_resource::<WorldEvents>()
+            .iter()
+            .filter_map(|event| match event {
+                WorldEvent::PlayerJoined { player_id } => Some((ON_JOIN.to_owned(), *player_id)),
+                WorldEvent::PlayerDied { player_id } => Some((ON_DEATH.to_owned(), *player_id)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for (name, player_id) in due.into_iter().chain(handlers) {
+            self.run_function(player_id, &name);
+        }
+    }
+
+    /// Add a repeating task, replacing the one of the same name, such as for a plugin's backups
+    pub fn register_task(&mut self, name: &str, schedule: TaskSchedule, actions: Vec<TaskAction>) {
+        self.write_resource::<Cron>()
+            .register(name, schedule, actions, unix_millis());
+    }
+
+    /// Remove a repeating task, false if there's no such task
+    pub fn unregister_task(&mut self, name: &str) -> bool {
+        self.write_resource::<Cron>().unregister(name)
+    }
+
+    /// Run the tasks due by now, saving their next run times if any ran
+    fn tick_cron(&mut self) {
+        let due = self.write_resource::<Cron>().take_due(unix_millis());
+
+        if due.is_empty() {
+            return;
+        }
+
+        for (name, actions) in due {
+            info!("Running task \"{}\" in \"{}\".", name, self.name);
+
+            for action in actions {
+                match action {
+                    TaskAction::Commands(commands) => commands.iter().for_each(|command| {
+                        commands::run_console_command(self, command);
+                    }),
+                    TaskAction::Announce(text) => {
+                        let message =
+                            create_chat_message(MessageType::Message, ChatType::Server, "", &text);
+                        self.broadcast(&message, vec![], vec![]);
+                    }
+                    TaskAction::Job(job) => rayon::spawn(move || job()),
+                }
+            }
+        }
+
+        self.read_resource::<Cron>().save();
+    }
+
+    /// Show or change a boss bar of a player, for a number of ticks or until removed
+    pub fn show_boss_bar(&mut self, player_id: usize, bar: BossBar, duration: Option<i32>) {
+        let tick = self.read_resource::<Clock>().tick;
+        self.write_resource::<Hud>().set_boss_bar(
+            player_id,
+            bar.to_owned(),
+            duration.map(|duration| tick + duration),
+        );
+
+        let mut components = MessageComponents::default_for(MessageType::BossBar);
+        components.boss_bar = Some(bar);
+
+        let message = create_message(components);
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Remove a boss bar of a player, false if they aren't shown one of that id
+    pub fn remove_boss_bar(&mut self, player_id: usize, id: &str) -> bool {
+        if !self.write_resource::<Hud>().remove_boss_bar(player_id, id) {
+            return false;
+        }
+
+        self.send_hud_text(player_id, MessageType::BossBarRemove, id);
+        true
+    }
+
+    /// Show text above a player's hotbar for a number of ticks, replacing any shown already
+    pub fn show_action_bar(&mut self, player_id: usize, text: &str, duration: i32) {
+        let tick = self.read_resource::<Clock>().tick;
+        self.write_resource::<Hud>()
+            .set_action_bar(player_id, tick + duration);
+
+        self.send_hud_text(player_id, MessageType::ActionBar, text);
+    }
+
+    /// Clear a player's action bar text before it expires
+    pub fn clear_action_bar(&mut self, player_id: usize) {
+        if self.write_resource::<Hud>().clear_action_bar(player_id) {
+            self.send_hud_text(player_id, MessageType::ActionBar, "");
+        }
+    }
+
+    /// Show a title to a player, replacing any showing
+    pub fn send_title(&mut self, player_id: usize, title: Title) {
+        let mut components = MessageComponents::default_for(MessageType::Title);
+        components.title = Some(title);
+
+        let message = create_message(components);
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Clear the title showing to a player right away
+    pub fn clear_title(&mut self, player_id: usize) {
+        let message = create_message(MessageComponents::default_for(MessageType::TitleClear));
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Write the sweep recording once it's done, telling whoever asked for it
+    fn tick_recorder(&mut self) {
+        let done = self.write_resource::<SweepRecorder>().tick();
+
+        if let Some((requester, written)) = done {
+            let message = commands::record::recording_message(written);
+            self.broadcast(&message, vec![requester], vec![]);
+        }
+    }
+
+    /// Warn players of the scheduled restart as it nears
+    fn tick_restart(&mut self) {
+        let step = self
+            .write_resource::<RestartCountdown>()
+            .tick(unix_millis());
+
+        let message = match step {
+            Some(Countdown::Warn(seconds)) if seconds >= 60 && seconds % 60 == 0 => {
+                create_translated_message(
+                    MessageType::Message,
+                    ChatType::Server,
+                    "restart.minutes",
+                    &[&(seconds / 60).to_string()],
+                )
+            }
+            Some(Countdown::Warn(seconds)) => create_translated_message(
+                MessageType::Message,
+                ChatType::Server,
+                "restart.seconds",
+                &[&seconds.to_string()],
+            ),
+            Some(Countdown::Restart) => create_translated_message(
+                MessageType::Message,
+                ChatType::Server,
+                "restart.now",
+                &[],
+            ),
+            None => return,
+        };
+
+        self.broadcast(&message, vec![], vec![]);
+    }
+
+    /// Whether the restart scheduled with `/restart` is due, for the server to save and exit
+    pub fn wants_restart(&self) -> bool {
+        self.read_resource::<RestartCountdown>().is_requested()
+    }
+
+    /// What the watchdog watches the world's loop by
+    pub fn watched(&self) -> Watched {
+        let chunks = self.read_resource::<Chunks>();
+
+        Watched {
+            name: self.name.to_owned(),
+            heartbeat: (*self.read_resource::<Heartbeat>()).clone(),
+            profiler: (*self.read_resource::<Profiler>()).clone(),
+            folder: if chunks.config.save {
+                Some(chunks.root_folder.to_owned())
+            } else {
+                None
+            },
+            timeout: chunks.config.watchdog_timeout,
+        }
+    }
+
+    /// Send a player a HUD message holding only text, such as an id or action bar text
+    fn send_hud_text(&mut self, player_id: usize, message_type: MessageType, text: &str) {
+        let mut components = MessageComponents::default_for(message_type);
+        components.text = Some(text.to_owned());
+
+        let message = create_message(components);
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Draw the map tiles of chunks whose surface changed, sending them to minimap clients
+    fn tick_map(&mut self) {
+        let tiles = {
+            let mut chunks = self.ecs.write_resource::<Chunks>();
+            let mut map = self.ecs.write_resource::<WorldMap>();
+
+            let changed = chunks
+                .surface_changed
+                .iter()
+                .take(MAX_MAP_TILES_PER_TICK)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            changed
+                .into_iter()
+                .filter_map(|coords| {
+                    chunks.surface_changed.remove(&coords);
+                    chunks
+                        .get_chunk(&coords)
+                        .map(|chunk| map.redraw(chunk).to_owned())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        if tiles.is_empty() {
+            return;
+        }
+
+        let players = self
+            .read_resource::<Players>()
+            .iter()
+            .filter(|(_, player)| player.minimap)
+            .map(|(id, player)| {
+                let tiles = tiles
+                    .iter()
+                    .filter(|tile| player.sent_chunks.contains(&tile.coords))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (*id, tiles)
+            })
+            .filter(|(_, tiles)| !tiles.is_empty())
+            .collect::<Vec<_>>();
+
+        for (player_id, tiles) in players {
+            self.send_map_tiles(player_id, tiles);
+        }
+    }
+
+    fn send_map_tiles(&mut self, player_id: usize, tiles: Vec<MapTile>) {
+        let mut components = MessageComponents::default_for(MessageType::Map);
+        components.map_tiles = Some(tiles);
+
+        let message = create_message(components);
+        self.broadcast(&message, vec![player_id], vec![]);
+    }
+
+    /// Handles a client asking for map tiles, answered with those drawn so far
+    pub fn on_map(&mut self, player_id: usize, msg: messages::Message) {
+        match self.write_resource::<Players>().get_mut(&player_id) {
+            Some(player) => player.minimap = true,
+            None => return,
+        }
+
+        let tiles = {
+            let map = self.read_resource::<WorldMap>();
+
+            msg.map_tiles
+                .iter()
+                .take(MAX_MAP_TILES_PER_REQUEST)
+                .filter_map(|tile| map.get(&Vec2(tile.cx, tile.cz)).cloned())
+                .collect::<Vec<_>>()
+        };
+
+        if !tiles.is_empty() {
+            self.send_map_tiles(player_id, tiles);
+        }
+    }
+
+    /// Handles a client asking for debug overlays, or to stop them with options asking for nothing
+    pub fn on_debug(&mut self, player_id: usize, msg: messages::Message) {
+        let options = DebugOptions::parse(&msg.json);
+
+        if options.is_some() && !self.read_resource::<WorldConfig>().debug_overlays {
+            let message = create_translated_message(
+                MessageType::Message,
+                ChatType::Error,
+                "debug.disabled",
+                &[],
+            );
+            self.broadcast(&message, vec![player_id], vec![]);
+            return;
+        }
+
+        if let Some(player) = self.write_resource::<Players>().get_mut(&player_id) {
+            player.debug = options;
+        }
+    }
+
+    /// Send the overlays of the clients debugging the world, every `DEBUG_INTERVAL` ticks
+    fn tick_debug(&mut self) {
+        if self.read_resource::<Clock>().tick % DEBUG_INTERVAL != 0 {
+            return;
+        }
+
+        let debugging = self
+            .read_resource::<Players>()
+            .iter()
+            .filter_map(|(id, player)| Some((*id, player.entity, player.debug.to_owned()?)))
+            .collect::<Vec<_>>();
+
+        if debugging.is_empty() {
+            return;
+        }
+
+        let overlays = {
+            let chunks = self.read_resource::<Chunks>();
+            let entities = self.ecs.entities();
+            let bodies = self.ecs.read_storage::<RigidBody>();
+            let ids = self.ecs.read_storage::<Id>();
+            let uuids = self.ecs.read_storage::<Uuid>();
+            let walks = self.ecs.read_storage::<WalkTowards>();
+
+            let dimension = chunks.config.dimension;
+            let chunk_size = chunks.config.chunk_size;
+
+            debugging
+                .into_iter()
+                .filter_map(|(player_id, entity, options)| {
+                    let position = bodies.get(entity)?.get_position();
+                    let voxel = map_world_to_voxel(position.0, position.1, position.2, dimension);
+
+                    let mut overlay = DebugOverlay::default();
+
+                    if options.chunks {
+                        let coords = map_voxel_to_chunk(voxel.0, voxel.1, voxel.2, chunk_size);
+                        overlay.chunks = chunk_borders(&chunks, &coords);
+                    }
+
+                    if options.light > 0 {
+                        overlay.lights = light_samples(&chunks, &voxel, options.light);
+                    }
+
+                    if options.boxes || options.paths {
+                        for (other, body, id, uuid, walk) in (
+                            &entities,
+                            &bodies,
+                            ids.maybe(),
+                            uuids.maybe(),
+                            walks.maybe(),
+                        )
+                            .join()
+                        {
+                            if body.get_position().sub(&position).len() > DEBUG_ENTITY_RADIUS {
+                                continue;
+                            }
+
+                            let id = protocol_id(other, id, uuid);
+
+                            if options.boxes {
+                                overlay.boxes.push(Hitbox {
+                                    id: id.to_owned(),
+                                    aabb: body.aabb.clone(),
+                                });
+                            }
+
+                            if let (true, Some(WalkTowards(Some(path), next))) =
+                                (options.paths, walk)
+                            {
+                                overlay.paths.push(DebugPath {
+                                    id,
+                                    nodes: path.iter().skip(*next).cloned().collect(),
+                                });
+                            }
+                        }
+                    }
+
+                    Some((player_id, overlay))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for (player_id, overlay) in overlays {
+            let mut components = MessageComponents::default_for(MessageType::Debug);
+            components.debug = Some(overlay);
+
+            let message = create_message(components);
+            self.broadcast(&message, vec![player_id], vec![]);
+        }
+    }
+
+    /// A map tile drawn so far as a PNG image, such as for a web map
+    pub fn map_tile_png(&self, coords: &Vec2<i32>) -> Option<Vec<u8>> {
+        self.read_resource::<WorldMap>()
+            .get(coords)
+            .map(MapTile::to_png)
+    }
+
+    /// Queue an event to be posted to the webhooks of the world's bridge, if it has any
+    fn bridge_event(&mut self, event: BridgeEvent) {
+        let line = {
+            let config = self.read_resource::<WorldConfig>();
+
+            if config.bridge.webhooks.is_empty() {
+                return;
+            }
+
+            event.format(&config.bridge.templates, &self.name)
+        };
+
+        self.write_resource::<Bridge>().push(line);
+    }
+
+    /// Post the joins and deaths of this tick along with the chat queued to the bridge's webhooks
+    fn tick_bridge(&mut self) {
+        let events = self
+            .read_resource::<WorldEvents>()
+            .iter()
+            .filter_map(|event| match event {
+                WorldEvent::PlayerJoined { player_id } => Some(BridgeEvent::Join {
+                    player: self.audit_name(*player_id),
+                }),
+                WorldEvent::PlayerDied { player_id } => Some(BridgeEvent::Death {
+                    player: self.audit_name(*player_id),
+                }),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        for event in events {
+            self.bridge_event(event);
+        }
+
+        let posts = self.write_resource::<Bridge>().take(Instant::now());
+
+        if !posts.is_empty() {
+            let webhooks = self
+                .read_resource::<WorldConfig>()
+                .bridge
+                .webhooks
+                .to_owned();
+            posts.iter().for_each(|post| bridge::post(&webhooks, post));
+        }
+    }
+
+    /// Show a message the bridge's bot took from outside of the game in chat
+    pub fn on_bridge_message(&mut self, author: &str, message: &str) {
+        let clamp = |text: &str| {
+            text.lines()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .chars()
+                .take(MAX_BRIDGE_MESSAGE_LENGTH)
+                .collect::<String>()
+        };
+
+        let body = bridge::fill(
+            &self
+                .read_resource::<WorldConfig>()
+                .bridge
+                .templates
+                .incoming,
+            &clamp(author),
+            &clamp(message),
+            &self.name,
+        );
+
+        info!("{}", body);
+
+        let message = create_chat_message(MessageType::Message, ChatType::Info, "", &body);
+        self.broadcast(&message, vec![], vec![]);
+    }
+
+    /// Take down the boss bars and action bar text that ran out of time
+    fn tick_hud(&mut self) {
+        let tick = self.read_resource::<Clock>().tick;
+        let expired = self.write_resource::<Hud>().take_expired(tick);
+
+        for expiry in expired {
+            match expiry {
+                HudExpiry::BossBar { player_id, id } => {
+                    self.send_hud_text(player_id, MessageType::BossBarRemove, &id)
+                }
+                HudExpiry::ActionBar { player_id } => {
+                    self.send_hud_text(player_id, MessageType::ActionBar, "")
+                }
+            }
+        }
+    }
+
+    /// Entities commands can target, positioned in voxels
+    fn command_candidates(&self) -> Vec<Candidate> {
+        let dimension = self.read_resource::<WorldConfig>().dimension;
+
+        let entities = self.ecs.entities();
+        let bodies = self.ecs.read_storage::<RigidBody>();
+        let etypes = self.ecs.read_storage::<EType>();
+        let ids = self.ecs.read_storage::<Id>();
+        let names = self.ecs.read_storage::<Name>();
+
+        (
+            &*entities,
+            &bodies,
+            etypes.maybe(),
+            ids.maybe(),
+            names.maybe(),
+        )
+            .join()
+            .filter_map(|(entity, body, etype, id, name)| {
+                let etype = match (etype, id) {
+                    (Some(etype), _) => etype.0.to_owned(),
+                    (None, Some(_)) => PLAYER_TYPE.to_owned(),
+                    (None, None) => return None,
+                };
+
+                let Vec3(px, py, pz) = body.get_position();
+                let Vec3(vx, vy, vz) = map_world_to_voxel(px, py, pz, dimension);
+
+                Some(Candidate {
+                    entity,
+                    position: Vec3(vx as f32, vy as f32, vz as f32),
+                    etype,
+                    name: name.and_then(|name| name.0.to_owned()),
+                })
+            })
+            .collect()
+    }
+
+    /// Resolve a command's target argument, a selector or player name, errors with a
+    /// translation key
+    pub fn select_entities(
+        &self,
+        player_id: usize,
+        arg: &str,
+    ) -> Result<Vec<Entity>, &'static str> {
+        let target = Selector::parse(arg)?;
+
+        let candidates = self.command_candidates();
+        let sender = self
+            .read_resource::<Players>()
+            .get(&player_id)
+            .map(|player| player.entity)
+            .and_then(|entity| candidates.iter().find(|c| c.entity == entity));
+
+        Ok(target.select(sender, &candidates))
+    }
+
+    /// Voxel an entity is in
+    pub fn entity_voxel(&self, entity: Entity) -> Option<Vec3<i32>> {
+        let dimension = self.read_resource::<WorldConfig>().dimension;
+        let Vec3(px, py, pz) = self
+            .ecs
+            .read_storage::<RigidBody>()
+            .get(entity)?
+            .get_position();
+
+        Some(map_world_to_voxel(px, py, pz, dimension))
+    }
+
+    /// Voxel a player is in
+    pub fn player_voxel(&self, player_id: usize) -> Option<Vec3<i32>> {
+        let entity = self.read_resource::<Players>().get(&player_id)?.entity;
+        self.entity_voxel(entity)
+    }
+
+    /// Move an entity to stand in a voxel, telling players they moved
+    pub fn teleport_entity(&mut self, entity: Entity, voxel: &Vec3<i32>) {
+        let player_id = self
+            .read_resource::<Players>()
+            .iter()
+            .find(|(_, player)| player.entity == entity)
+            .map(|(id, _)| *id);
+
+        // players are moved on top of the voxel below
+        if let Some(player_id) = player_id {
+            self.teleport(player_id, &Vec3(voxel.0, voxel.1 - 1, voxel.2));
+            return;
+        }
+
+        let dimension = self.read_resource::<WorldConfig>().dimension as f32;
+
+        if let Some(body) = self.ecs.write_storage::<RigidBody>().get_mut(entity) {
+            body.set_position(&Vec3(
+                (voxel.0 as f32 + 0.5) * dimension,
+                voxel.1 as f32 * dimension,
+                (voxel.2 as f32 + 0.5) * dimension,
+            ));
+            body.velocity = Vec3::default();
+        }
+    }
+
+    /// Load the blocks, recipes, loot tables, trades and functions again, applying them live
+    ///
+    /// Chunks holding blocks that look different are remeshed, and clients are told to fetch
+    /// the blocks and atlas again. Nothing changes if the files don't load or the blocks can't
+    /// replace the running ones, the reasons why being returned instead.
+    pub fn reload(&mut self) -> Result<(RegistryDiff, usize), Vec<String>> {
+        let packs = self.read_resource::<WorldMeta>().packs.to_owned();
+        let old = self.read_resource::<Chunks>().registry.clone();
+
+        let Reloaded {
+            registry,
+            recipes,
+            loot,
+            trades,
+        } = reload_data(
+            packs,
+            old.data_packs.to_owned(),
+            old.resource_pack.to_owned(),
+        )
+        .map_err(|error| vec![error])?;
+
+        let diff = RegistryDiff::new(&old, &registry);
+        if !diff.is_safe() {
+            return Err(diff.conflicts);
+        }
+
+        *self.write_resource::<Recipes>() = recipes;
+        *self.write_resource::<LootTables>() = loot;
+        *self.write_resource::<TradeLists>() = trades;
+        self.write_resource::<WorldMap>().colors = MapColors::new(&registry);
+        self.write_resource::<Functions>().reload();
+
+        let remeshed = {
+            let mut chunks = self.write_resource::<Chunks>();
+            chunks.set_registry(registry);
+            chunks.invalidate_blocks(&diff.visual)
+        };
+
+        self.broadcast(&create_of_type(MessageType::Reload), vec![], vec![]);
+
+        info!(
+            "Reloaded the data of \"{}\": {} blocks added, {} changed, {} chunks to remesh.",
+            self.name,
+            diff.added.len(),
+            diff.visual.len() + diff.behavior.len(),
+            remeshed
+        );
+
+        Ok((diff, remeshed))
+    }
+
+    /// Run a `/reload` command, reporting what changed or why nothing could
+    pub fn on_reload_command(&mut self) -> Vec<messages::Message> {
+        let create_msg = |chat_type: ChatType, key: &str, args: &[&str]| {
+            create_translated_message(MessageType::Message, chat_type, key, args)
+        };
+
+        match self.reload() {
+            Ok((diff, remeshed)) => {
+                let args = [
+                    diff.added.len().to_string(),
+                    (diff.visual.len() + diff.behavior.len()).to_string(),
+                    remeshed.to_string(),
+                ];
+                let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+                vec![create_msg(ChatType::Info, "command.reload", &args)]
+            }
+            Err(conflicts) => {
+                let mut msgs = vec![create_msg(ChatType::Error, "command.reload.failed", &[])];
+
+                for conflict in conflicts.iter() {
+                    msgs.push(create_msg(
+                        ChatType::Error,
+                        "command.reload.conflict",
+                        &[conflict],
+                    ));
+                }
+
+                msgs
+            }
+        }
+    }
+
+    /// Update the team name and color a player is shown with
+    pub fn refresh_team_of(&mut self, player_name: &str) {
+        let found = self
+            .read_resource::<Players>()
+            .iter()
+            .find(|(_, player)| player.name.as_deref() == Some(player_name))
+            .map(|(id, player)| (*id, player.entity));
+
+        let (player_id, entity) = match found {
+            Some(found) => found,
+            None => return,
+        };
+
+        let (team, color) = match self.read_resource::<Teams>().team_of(player_name) {
+            Some(team) => (team.name.to_owned(), team.color.to_owned()),
+            None => (String::new(), String::new()),
+        };
+
+        if let Some(metadata) = self.ecs.write_storage::<Metadata>().get_mut(entity) {
+            metadata.set("team", &team);
+            metadata.set("teamColor", &color);
+        }
+
+        self.broadcast_list_entry(player_id);
+    }
+
+    /// TEST:
+    ///
+    /// Used to test entity spawning, returns false if there's no such entity type
+    pub fn test_entity(&mut self, player_id: usize, etype: &str) -> bool {
+        let players = self.read_resource::<Players>();
+        let player = players.get(&player_id);
+
+        if player.is_none() {
+            return false;
+        }
+
+        let player = player.unwrap();
+
+        let bodies = self.ecs().read_component::<RigidBody>();
+        let body = bodies.get(player.entity).unwrap();
 
         let pos = body.get_head_position();
 
@@ -585,33 +3122,305 @@ impl World {
         drop(players);
 
         let entities = self.read_resource::<Entities>();
-        let prototype = entities
-            .get_prototype("Test")
-            .unwrap_or_else(|| panic!("Prototype not found: Test"))
-            .clone();
+        let prototype = match entities.get_prototype(etype) {
+            Some(prototype) => prototype.clone(),
+            None => return false,
+        };
 
         drop(entities);
 
         Entities::spawn_entity(
             self.ecs_mut(),
             &prototype,
-            "Test",
+            etype,
             &Vec3(pos.0, pos.1, pos.2),
             &Quaternion(0.0, 0.0, 0.0, 0.0),
         );
+
+        true
     }
 
-    /// Sync configurations to the world's JSON file
-    pub fn sync_config(&mut self) {
-        let chunks = self.read_resource::<Chunks>();
+    /// Capture the state of the entities standing in chunks, by the chunk they're in
+    ///
+    /// Players aren't captured, having no entity type.
+    fn snapshot_entities(&self) -> HashMap<Vec2<i32>, Vec<(Entity, EntityHandoff)>> {
+        let (chunk_size, dimension) = {
+            let config = self.read_resource::<WorldConfig>();
+            (config.chunk_size, config.dimension)
+        };
 
-        let mut path = chunks.root_folder.clone();
-        path.push(WORLD_DATA_FILE);
+        let ecs_entities = self.ecs.entities();
+        let etypes = self.ecs.read_storage::<EType>();
+        let bodies = self.ecs.read_storage::<RigidBody>();
+        let rotations = self.ecs.read_storage::<Rotation>();
+        let healths = self.ecs.read_storage::<Health>();
+        let metadatas = self.ecs.read_storage::<Metadata>();
+        let items = self.ecs.read_storage::<ItemDrop>();
+        let traders = self.ecs.read_storage::<Trader>();
+        let uuids = self.ecs.read_storage::<Uuid>();
+
+        let mut snapshots: HashMap<Vec2<i32>, Vec<(Entity, EntityHandoff)>> = HashMap::new();
+
+        for (entity, etype, body) in (&*ecs_entities, &etypes, &bodies).join() {
+            let Vec3(px, py, pz) = body.get_position();
+            let Vec3(vx, vy, vz) = map_world_to_voxel(px, py, pz, dimension);
+
+            let Quaternion(qx, qy, qz, qw) = rotations
+                .get(entity)
+                .map(|rotation| rotation.0.to_owned())
+                .unwrap_or_default();
+
+            let snapshot = EntityHandoff {
+                uuid: uuids.get(entity).map(|uuid| uuid.0.to_owned()),
+                etype: etype.0.to_owned(),
+                position: [px, py, pz],
+                rotation: [qx, qy, qz, qw],
+                health: healths.get(entity).map(|health| health.current),
+                metadata: metadatas
+                    .get(entity)
+                    .map(|metadata| {
+                        metadata
+                            .data
+                            .iter()
+                            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                item: items.get(entity).map(|item| item.0.to_owned()),
+                trades: traders.get(entity).map(|trader| trader.offers.to_owned()),
+            };
 
-        drop(chunks);
+            snapshots
+                .entry(map_voxel_to_chunk(vx, vy, vz, chunk_size))
+                .or_default()
+                .push((entity, snapshot));
+        }
+
+        snapshots
+    }
+
+    /// Spawn an entity saved with a chunk or handed off with one, none if its type is unknown
+    fn spawn_saved_entity(&mut self, saved: EntityHandoff) -> Option<Entity> {
+        let EntityHandoff {
+            uuid,
+            etype,
+            position: [px, py, pz],
+            rotation: [qx, qy, qz, qw],
+            health,
+            metadata,
+            item,
+            trades,
+        } = saved;
+
+        let prototype = match self.read_resource::<Entities>().get_prototype(&etype) {
+            Some(prototype) => prototype.clone(),
+            None => {
+                warn!("Dropping saved entity of unknown type \"{}\".", etype);
+                return None;
+            }
+        };
+
+        let position = Vec3(px, py, pz);
+        let entity = match item {
+            Some(stack) => Entities::spawn_item(self.ecs_mut(), &prototype, stack, &position),
+            None => Entities::spawn_entity(
+                self.ecs_mut(),
+                &prototype,
+                &etype,
+                &position,
+                &Quaternion(qx, qy, qz, qw),
+            ),
+        };
+
+        if let Some(uuid) = uuid {
+            Entities::set_uuid(self.ecs_mut(), entity, &uuid);
+        }
+
+        if let Some(current) = health {
+            let mut healths = self.ecs.write_storage::<Health>();
+            if let Some(health) = healths.get_mut(entity) {
+                health.current = current;
+            }
+        }
+
+        if !metadata.is_empty() {
+            let mut metadatas = self.ecs.write_storage::<Metadata>();
+            if let Some(data) = metadatas.get_mut(entity) {
+                data.extend(metadata.into_iter().collect());
+            }
+        }
+
+        if let Some(offers) = trades {
+            self.ecs
+                .write_storage::<Trader>()
+                .insert(entity, Trader { offers })
+                .expect("Unable to make an entity a trader.");
+        }
+
+        Some(entity)
+    }
+
+    /// Save the chunks unloaded this tick with the entities standing in them, which are removed,
+    /// and spawn the entities of the chunks loaded
+    fn persist_chunk_entities(&mut self) {
+        let unloaded = std::mem::take(&mut self.write_resource::<Chunks>().unloaded);
+
+        if !unloaded.is_empty() {
+            let mut snapshots = self.snapshot_entities();
+            let mut removed = vec![];
+
+            let mut chunks = self.write_resource::<Chunks>();
+            for mut chunk in unloaded {
+                let (entities, saved): (Vec<_>, Vec<_>) = snapshots
+                    .remove(&chunk.coords)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .unzip();
+
+                chunk.entities = saved;
+                removed.extend(entities);
+                chunks.unloaded.push(chunk);
+            }
+            chunks.save_unloaded();
+            drop(chunks);
+
+            self.ecs
+                .delete_entities(&removed)
+                .expect("Unable to remove the entities of unloaded chunks.");
+        }
+
+        let loaded = std::mem::take(&mut self.write_resource::<Chunks>().loaded_entities);
+        for saved in loaded {
+            self.spawn_saved_entity(saved);
+        }
+    }
+
+    /// Hand a chunk off to another shard, along with its scheduled block ticks and entities, none
+    /// if it isn't loaded or still being worked on off-thread
+    ///
+    /// The chunk stays in this world until `confirm_handoff` once the other shard accepted it, so
+    /// that a failed handoff loses nothing.
+    pub fn handoff_chunk(&mut self, coords: &Vec2<i32>) -> Option<ChunkHandoff> {
+        let mut chunk = {
+            let chunks = self.read_resource::<Chunks>();
+            if chunks.generating.contains(coords) || chunks.meshing.contains(coords) {
+                return None;
+            }
+
+            chunks.get_chunk(coords)?.to_owned()
+        };
+
+        let chunk_size = self.read_resource::<WorldConfig>().chunk_size;
+        let tick = self.read_resource::<Clock>().tick;
+
+        let ticks = self
+            .read_resource::<BlockTicks>()
+            .peek_where(|Vec3(vx, vy, vz)| map_voxel_to_chunk(*vx, *vy, *vz, chunk_size) == *coords)
+            .into_iter()
+            .map(|(Vec3(vx, vy, vz), due)| ([vx, vy, vz], due - tick))
+            .collect();
+
+        let (handed, entities): (Vec<_>, Vec<_>) = self
+            .snapshot_entities()
+            .remove(coords)
+            .unwrap_or_default()
+            .into_iter()
+            .unzip();
+
+        self.write_resource::<PendingHandoffs>()
+            .insert(coords.to_owned(), handed);
+
+        // the entities travel apart from the chunk's data
+        chunk.entities.clear();
+
+        Some(ChunkHandoff {
+            coords: [coords.0, coords.1],
+            data: chunk.to_data(),
+            ticks,
+            entities,
+        })
+    }
+
+    /// Let go of a chunk handed off with `handoff_chunk` now that the other shard accepted it,
+    /// removing it along with its block ticks and the entities that went with it
+    pub fn confirm_handoff(&mut self, coords: &Vec2<i32>) -> Result<(), String> {
+        if !self.read_resource::<PendingHandoffs>().contains_key(coords) {
+            return Err("Chunk isn't being handed off.".to_owned());
+        }
+
+        {
+            let mut chunks = self.write_resource::<Chunks>();
+            if chunks.generating.contains(coords) || chunks.meshing.contains(coords) {
+                return Err("Chunk is still being worked on.".to_owned());
+            }
+
+            // the chunk may have been unloaded since, which its handoff already covers
+            chunks.release_chunk(coords);
+        }
+
+        let handed = self
+            .write_resource::<PendingHandoffs>()
+            .remove(coords)
+            .unwrap_or_default();
+
+        let chunk_size = self.read_resource::<WorldConfig>().chunk_size;
+        self.write_resource::<BlockTicks>()
+            .take_where(|Vec3(vx, vy, vz)| {
+                map_voxel_to_chunk(*vx, *vy, *vz, chunk_size) == *coords
+            });
+
+        let released = handed
+            .into_iter()
+            .filter(|entity| self.ecs.is_alive(*entity))
+            .collect::<Vec<_>>();
+
+        self.ecs
+            .delete_entities(&released)
+            .expect("Unable to remove handed off entities.");
+
+        Ok(())
+    }
+
+    /// Take over a chunk handed off by another shard, resuming its block ticks and entities
+    pub fn accept_handoff(&mut self, handoff: ChunkHandoff) {
+        let ChunkHandoff {
+            coords: [cx, cz],
+            data,
+            ticks,
+            entities,
+        } = handoff;
+
+        self.write_resource::<Chunks>()
+            .adopt_chunk(&Vec2(cx, cz), data.as_bytes());
+
+        for ([vx, vy, vz], delay) in ticks {
+            self.schedule_block_tick(vx, vy, vz, delay);
+        }
+
+        for entity in entities {
+            self.spawn_saved_entity(entity);
+        }
+    }
+
+    /// Address of the shard players joining this world should be routed to, none if it's this one
+    pub fn route_connection(&self) -> Option<String> {
+        // players spawn above the origin
+        self.read_resource::<WorldConfig>()
+            .shards
+            .route(&Vec2(0, 0))
+            .map(|address| address.to_owned())
+    }
 
-        if let Ok(file) = File::open(path) {
-            let WorldData { time, tick_speed } = serde_json::from_reader(file).unwrap();
+    /// Sync configurations to the world's JSON file
+    pub fn sync_config(&mut self) {
+        let data = self
+            .read_resource::<Chunks>()
+            .storage
+            .as_ref()
+            .and_then(|storage| storage.get_metadata(WORLD_DATA_FILE));
+
+        if let Some(data) = data {
+            let WorldData { time, tick_speed } = serde_json::from_slice(&data).unwrap();
             let mut clock = self.write_resource::<Clock>();
 
             clock.set_time(time);
@@ -622,37 +3431,84 @@ impl World {
     /// Saves the world. Things done:
     ///
     /// 1. Saves the world configs (`time`, `tick_speed`, ...etc)
-    /// 2. Save all chunks within `chunks` to their corresponding JSON files
-    pub fn save(&self) {
-        let chunks = self.read_resource::<Chunks>();
-        let clock = self.read_resource::<Clock>();
+    /// 2. Save all chunks within `chunks` to their corresponding JSON files, along with the
+    ///    entities standing in them
+    /// 3. Saves where the named players are at, for their next session
+    pub fn save(&mut self) {
+        if !self.read_resource::<Chunks>().config.save {
+            return;
+        }
 
-        if chunks.config.save {
-            // saving world data
-            let mut root = chunks.root_folder.clone();
-            root.push(WORLD_DATA_FILE);
+        let snapshots = self.snapshot_entities();
+
+        // saving the named players, flushed along with the chunks
+        let named = self
+            .read_resource::<Players>()
+            .values()
+            .filter_map(|player| Some((player.name.to_owned()?, player.entity)))
+            .collect::<Vec<_>>();
+        for (name, entity) in named {
+            self.save_player(&name, entity);
+        }
 
-            let mut file = File::create(root.into_os_string().into_string().unwrap())
-                .expect("Could not create world config.");
+        // saving world data
+        let data = {
+            let clock = self.read_resource::<Clock>();
 
-            let data = WorldData {
+            WorldData {
                 time: clock.time,
                 tick_speed: clock.tick_speed,
-            };
+            }
+        };
+
+        {
+            let mut chunks = self.write_resource::<Chunks>();
 
             let j = serde_json::to_string(&data).unwrap();
 
-            file.write_all(j.as_bytes())
-                .expect("Unable to save world data");
+            if let Some(storage) = &chunks.storage {
+                storage.put_metadata(WORLD_DATA_FILE, j.as_bytes());
+            }
+
+            // saving chunks, holding their entities only while they're saved
+            let mut populated = vec![];
+            for (coords, snapshots) in snapshots {
+                if let Some(chunk) = chunks.get_chunk_mut(&coords) {
+                    chunk.entities = snapshots
+                        .into_iter()
+                        .map(|(_, snapshot)| snapshot)
+                        .collect();
+                    chunk.needs_saving = true;
+                    populated.push(coords);
+                }
+            }
 
-            // saving chunks
             chunks.save();
 
-            // info!(
-            //     "Saving data for world \"{}\" took {:?}.",
-            //     self.name,
-            //     start.elapsed()
-            // );
+            for coords in populated {
+                if let Some(chunk) = chunks.get_chunk_mut(&coords) {
+                    chunk.entities.clear();
+                }
+            }
+        }
+
+        self.read_resource::<Advancements>().save();
+        self.read_resource::<Teams>().save();
+        self.read_resource::<Holograms>().save();
+        self.read_resource::<Arenas>().save();
+        self.read_resource::<Cron>().save();
+
+        // info!(
+        //     "Saving data for world \"{}\" took {:?}.",
+        //     self.name,
+        //     start.elapsed()
+        // );
+    }
+
+    /// Wait for the storage to hold everything saved, before the process exits
+    pub fn sync_storage(&self) {
+        if let Some(storage) = &self.read_resource::<Chunks>().storage {
+            storage.sync();
         }
     }
 
@@ -666,33 +3522,101 @@ impl World {
     pub fn tick(&mut self) {
         // TODO: make dispatchers
 
+        let started = Instant::now();
+
         // handle game tick
         self.write_resource::<Clock>().tick();
 
+        let heartbeat = (*self.read_resource::<Heartbeat>()).clone();
+        heartbeat.beat(self.read_resource::<Clock>().tick);
+
+        self.expire_detached();
+
+        let profiler = (*self.read_resource::<Profiler>()).clone();
+
         // handle chunk generation
+        heartbeat.enter("chunks");
+        let phase = Instant::now();
         self.write_resource::<Chunks>().tick();
+        profiler.record("chunks", phase.elapsed());
 
         let mut dispatcher = DispatcherBuilder::new()
-            .with(PhysicsSystem, "physics", &[])
-            .with(PeersSystem, "peers", &["physics"])
-            .with(ChunkingSystem, "chunking", &["peers"])
-            .with(GenerationSystem, "generation", &["chunking"])
-            .with(MeshingSystem, "meshing", &["generation"])
-            .with(SearchSystem, "search", &["peers"])
-            .with(ObserveSystem, "observe", &["search"])
-            .with(EntitiesSystem, "entities", &["chunking"])
-            .with(PathFindSystem, "pathfind", &["observe"])
-            .with(BroadcastSystem, "broadcast", &["peers"])
-            .with(WalkTowardsSystem, "walk_towards", &["pathfind"])
+            .with_timed(EffectsSystem, "effects", &[], &profiler)
+            .with_timed(AttributesSystem, "attributes", &["effects"], &profiler)
+            .with_timed(MovementSystem, "movement", &["attributes"], &profiler)
+            .with_timed(VehiclesSystem, "vehicles", &["movement"], &profiler)
+            .with_timed(PhysicsSystem, "physics", &["vehicles"], &profiler)
+            .with_timed(MountSystem, "mount", &["physics"], &profiler)
+            .with_timed(PeersSystem, "peers", &["mount"], &profiler)
+            .with_timed(HistorySystem, "history", &["peers"], &profiler)
+            .with_timed(ChunkingSystem, "chunking", &["peers"], &profiler)
+            .with_timed(GenerationSystem, "generation", &["chunking"], &profiler)
+            .with_timed(MeshingSystem, "meshing", &["generation"], &profiler)
+            .with_timed(UnloadingSystem, "unloading", &["meshing"], &profiler)
+            .with_timed(SearchSystem, "search", &["peers"], &profiler)
+            .with_timed(ObserveSystem, "observe", &["search"], &profiler)
+            .with_timed(EntitiesSystem, "entities", &["chunking"], &profiler)
+            .with_timed(BlockTickSystem, "block_tick", &["chunking"], &profiler)
+            .with_timed(AdvancementsSystem, "advancements", &["peers"], &profiler)
+            .with_timed(PathFindSystem, "pathfind", &["observe"], &profiler)
+            .with_timed(
+                RemeshSystem,
+                "remesh",
+                &["meshing", "block_tick"],
+                &profiler,
+            )
+            .with_timed(
+                BroadcastSystem,
+                "broadcast",
+                &["peers", "remesh"],
+                &profiler,
+            )
+            .with_timed(WalkTowardsSystem, "walk_towards", &["pathfind"], &profiler)
             .build();
 
+        heartbeat.enter("systems");
         dispatcher.dispatch(&self.ecs);
 
+        heartbeat.enter("maintain");
+        let phase = Instant::now();
         self.ecs.maintain();
+        self.ecs
+            .write_resource::<Entities>()
+            .prune(&self.ecs.entities());
+        profiler.record("maintain", phase.elapsed());
+
+        self.persist_chunk_entities();
+
+        self.broadcast_targets();
+
+        heartbeat.enter("arenas");
+        let phase = Instant::now();
+        commands::arenas::tick_arenas(self);
+        profiler.record("arenas", phase.elapsed());
+
+        heartbeat.enter("functions");
+        self.tick_functions();
+        self.tick_cron();
+        self.tick_restart();
+        self.tick_recorder();
+        self.tick_hud();
+        self.tick_map();
+        self.tick_debug();
+        self.tick_bridge();
+        self.write_resource::<AuditLog>().flush();
+
+        // events only live for the tick they were dispatched in
+        self.write_resource::<WorldEvents>().clear();
+        self.write_resource::<TickEdits>().clear();
 
         // saving the chunks
         if self.read_resource::<Clock>().tick % 8000 == 0 {
+            heartbeat.enter("save");
             self.save()
         }
+
+        let elapsed = started.elapsed();
+        profiler.record_tick(elapsed);
+        self.write_resource::<Chunks>().budget.record(elapsed);
     }
 }
@@ -0,0 +1,487 @@
+//! C ABI over the server's collision, picking, meshing and lighting
+//!
+//! Native engines link the library built with `cargo rustc -p server_core --features ffi
+//! --crate-type cdylib` and include the header the build writes to `include/minejs.h`. Every
+//! buffer is allocated by the caller; only the context holding a world's config and block
+//! registry is allocated here, and freed with `mjs_context_free`.
+//!
+//! Chunk buffers are laid out as the server keeps them, padded by `DATA_PADDING` voxels on
+//! either horizontal side, and indexed `[x, y, z]` with `z` varying fastest.
+
+use std::{
+    ffi::{c_void, CStr},
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+    sync::{Arc, Mutex},
+};
+
+use hashbrown::HashMap;
+
+use server_common::{
+    aabb::Aabb,
+    chunk::ChunkData,
+    ndarray::{ndarray, Ndarray},
+    vec::{Vec2, Vec3},
+};
+use server_utils::sweep::sweep;
+
+use crate::{
+    constants::DATA_PADDING,
+    engine::{
+        chunk::Chunk, config::WorldConfig, data_packs::DataPacks, picking, registry::Registry,
+        space::Space,
+    },
+    gen::{
+        lights,
+        mesher::{GeometryType, Mesher},
+    },
+};
+
+/// Whether bodies collide with a voxel, answered by the caller with its own voxel data
+pub type MjsIsSolid = extern "C" fn(user: *mut c_void, vx: i32, vy: i32, vz: i32) -> bool;
+
+/// A box by its minimum corner and size, in voxel units
+#[repr(C)]
+pub struct MjsBox {
+    pub base: [f32; 3],
+    pub size: [f32; 3],
+}
+
+/// A caller-allocated float buffer, `len` set to what was or would have been written
+#[repr(C)]
+pub struct MjsFloats {
+    pub data: *mut f32,
+    pub capacity: usize,
+    pub len: usize,
+}
+
+/// A caller-allocated integer buffer, `len` set to what was or would have been written
+#[repr(C)]
+pub struct MjsInts {
+    pub data: *mut i32,
+    pub capacity: usize,
+    pub len: usize,
+}
+
+/// Buffers a chunk is meshed into, the same attributes the server sends clients
+#[repr(C)]
+pub struct MjsMesh {
+    pub positions: MjsFloats,
+    pub indices: MjsInts,
+    pub uvs: MjsFloats,
+    pub aos: MjsInts,
+    pub lights: MjsInts,
+    pub centroids: MjsFloats,
+}
+
+pub const MJS_GEOMETRY_OPAQUE: u32 = 0;
+pub const MJS_GEOMETRY_CUTOUT: u32 = 1;
+pub const MJS_GEOMETRY_TRANSLUCENT: u32 = 2;
+
+pub const MJS_MESHED: i32 = 1;
+pub const MJS_EMPTY: i32 = 0;
+pub const MJS_BUFFER_TOO_SMALL: i32 = -1;
+pub const MJS_INVALID_ARGUMENT: i32 = -2;
+
+/// The world config and block registry meshing and lighting run with
+pub struct MjsContext {
+    config: WorldConfig,
+    registry: Registry,
+}
+
+impl MjsContext {
+    fn chunk_shape(&self) -> Vec<usize> {
+        ChunkData::shape(self.config.chunk_size, self.config.max_height as usize)
+    }
+
+    fn height_map_shape(&self) -> Vec<usize> {
+        let width = self.config.chunk_size + DATA_PADDING * 2;
+        vec![width, width]
+    }
+}
+
+/// Copy caller data of the length of `shape` into an ndarray
+unsafe fn read_ndarray(data: *const u32, shape: Vec<usize>) -> Ndarray<u32> {
+    let mut array = ndarray(shape, 0);
+    let len = array.data.len();
+    array.data.copy_from_slice(slice::from_raw_parts(data, len));
+    array
+}
+
+/// Copy into a caller buffer if it's large enough, recording the length needed either way
+unsafe fn write<T: Copy>(data: *mut T, capacity: usize, len: &mut usize, values: &[T]) -> bool {
+    *len = values.len();
+
+    if values.len() > capacity {
+        return false;
+    }
+
+    ptr::copy_nonoverlapping(values.as_ptr(), data, values.len());
+    true
+}
+
+/// Create a context from a texture pack name and a world config as JSON, null on failure
+///
+/// The config holds the fields of a world in `worlds.json`, defaults merged in.
+///
+/// # Safety
+///
+/// Both strings must be valid and nul-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn mjs_context_new(
+    pack: *const c_char,
+    config: *const c_char,
+) -> *mut MjsContext {
+    if pack.is_null() || config.is_null() {
+        return ptr::null_mut();
+    }
+
+    let pack = CStr::from_ptr(pack).to_string_lossy().into_owned();
+    let config = match serde_json::from_slice::<WorldConfig>(CStr::from_ptr(config).to_bytes()) {
+        Ok(config) => config,
+        Err(_) => return ptr::null_mut(),
+    };
+
+    // a missing pack panics, which must not unwind into the caller
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+        Registry::new(vec![pack], DataPacks::default(), false)
+    })) {
+        Ok(registry) => Box::into_raw(Box::new(MjsContext { config, registry })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Free a context created by `mjs_context_new`
+///
+/// # Safety
+///
+/// `context` must come from `mjs_context_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn mjs_context_free(context: *mut MjsContext) {
+    if !context.is_null() {
+        drop(Box::from_raw(context));
+    }
+}
+
+/// Number of voxels in a padded chunk, the length of voxel and light buffers
+///
+/// # Safety
+///
+/// `context` must be a live context.
+#[no_mangle]
+pub unsafe extern "C" fn mjs_chunk_len(context: *const MjsContext) -> usize {
+    (*context).chunk_shape().iter().product()
+}
+
+/// Number of columns in a padded chunk, the length of height map buffers
+///
+/// # Safety
+///
+/// `context` must be a live context.
+#[no_mangle]
+pub unsafe extern "C" fn mjs_height_map_len(context: *const MjsContext) -> usize {
+    (*context).height_map_shape().iter().product()
+}
+
+/// Move a box by `d` until it collides, returning how far it moved
+///
+/// `resting` receives the direction the box came to rest against on each axis, -1 or 1, or
+/// 0 where it moved freely. It may be null.
+///
+/// # Safety
+///
+/// `body` must be valid, and `resting` null or valid for three integers.
+#[no_mangle]
+pub unsafe extern "C" fn mjs_sweep(
+    is_solid: MjsIsSolid,
+    user: *mut c_void,
+    body: *mut MjsBox,
+    dx: f32,
+    dy: f32,
+    dz: f32,
+    resting: *mut i32,
+) -> f32 {
+    let solid = |vx: i32, vy: i32, vz: i32| is_solid(user, vx, vy, vz);
+
+    let MjsBox { base, size } = &mut *body;
+    let mut aabb = Aabb::new(
+        &Vec3(base[0], base[1], base[2]),
+        &Vec3(size[0], size[1], size[2]),
+    );
+
+    let axes = Arc::new(Mutex::new([0; 3]));
+    let temp = axes.clone();
+
+    let distance = sweep(
+        &solid,
+        &mut aabb,
+        &Vec3(dx, dy, dz),
+        &mut move |_, axis: usize, dir: i32, vec: &mut Vec3<f32>| {
+            temp.lock().unwrap()[axis] = dir;
+            vec[axis] = 0.0;
+            false
+        },
+        false,
+    );
+
+    *base = [aabb.base.0, aabb.base.1, aabb.base.2];
+
+    if !resting.is_null() {
+        let axes = axes.lock().unwrap();
+        ptr::copy_nonoverlapping(axes.as_ptr(), resting, 3);
+    }
+
+    distance
+}
+
+/// Cast a ray for the first solid voxel within `max_distance`, as the server picks blocks
+///
+/// On a hit, `voxel` receives the voxel and `face` the outward normal of the face entered.
+///
+/// # Safety
+///
+/// `origin` and `direction` must be valid for three floats, `voxel` and `face` for three
+/// integers.
+#[no_mangle]
+pub unsafe extern "C" fn mjs_raycast(
+    is_solid: MjsIsSolid,
+    user: *mut c_void,
+    origin: *const f32,
+    direction: *const f32,
+    max_distance: f32,
+    voxel: *mut i32,
+    face: *mut i32,
+) -> bool {
+    let solid = |vx: i32, vy: i32, vz: i32| is_solid(user, vx, vy, vz);
+
+    let origin = slice::from_raw_parts(origin, 3);
+    let direction = slice::from_raw_parts(direction, 3);
+
+    match picking::pick(
+        &solid,
+        &Vec3(origin[0], origin[1], origin[2]),
+        &Vec3(direction[0], direction[1], direction[2]),
+        max_distance,
+    ) {
+        Some(target) => {
+            ptr::copy_nonoverlapping(
+                [target.voxel.0, target.voxel.1, target.voxel.2].as_ptr(),
+                voxel,
+                3,
+            );
+            ptr::copy_nonoverlapping(
+                [target.face.0, target.face.1, target.face.2].as_ptr(),
+                face,
+                3,
+            );
+            true
+        }
+        None => false,
+    }
+}
+
+/// Mesh one geometry type of a sub-chunk into caller buffers
+///
+/// Returns `MJS_MESHED`, `MJS_EMPTY` if there's nothing to draw, or `MJS_BUFFER_TOO_SMALL`
+/// with every `len` set to the size needed, to grow the buffers and call again.
+///
+/// # Safety
+///
+/// `voxels` and `lights` must hold `mjs_chunk_len` values, and every buffer of `mesh` must be
+/// valid for its capacity.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn mjs_mesh_chunk(
+    context: *const MjsContext,
+    cx: i32,
+    cz: i32,
+    voxels: *const u32,
+    lights: *const u32,
+    geometry: u32,
+    sub_chunk: u32,
+    mesh: *mut MjsMesh,
+) -> i32 {
+    let context = &*context;
+
+    let geometry_type = match geometry {
+        MJS_GEOMETRY_OPAQUE => GeometryType::Opaque,
+        MJS_GEOMETRY_CUTOUT => GeometryType::Cutout,
+        MJS_GEOMETRY_TRANSLUCENT => GeometryType::Translucent,
+        _ => return MJS_INVALID_ARGUMENT,
+    };
+
+    if sub_chunk >= context.config.sub_chunks {
+        return MJS_INVALID_ARGUMENT;
+    }
+
+    let mut chunk = Chunk::new(Vec2(cx, cz), &context.config, None);
+    chunk.set_voxels(read_ndarray(voxels, context.chunk_shape()));
+    chunk.set_lights(read_ndarray(lights, context.chunk_shape()));
+
+    let meshed = match Mesher::mesh_chunk(
+        &chunk,
+        geometry_type,
+        sub_chunk,
+        &context.config,
+        &context.registry,
+    ) {
+        Some(meshed) => meshed,
+        None => return MJS_EMPTY,
+    };
+
+    let MjsMesh {
+        positions,
+        indices,
+        uvs,
+        aos,
+        lights,
+        centroids,
+    } = &mut *mesh;
+
+    // write every buffer, so all lengths are known when one is too small
+    let written = [
+        write(
+            positions.data,
+            positions.capacity,
+            &mut positions.len,
+            &meshed.positions,
+        ),
+        write(
+            indices.data,
+            indices.capacity,
+            &mut indices.len,
+            &meshed.indices,
+        ),
+        write(uvs.data, uvs.capacity, &mut uvs.len, &meshed.uvs),
+        write(aos.data, aos.capacity, &mut aos.len, &meshed.aos),
+        write(
+            lights.data,
+            lights.capacity,
+            &mut lights.len,
+            &meshed.lights,
+        ),
+        write(
+            centroids.data,
+            centroids.capacity,
+            &mut centroids.len,
+            &meshed.centroids,
+        ),
+    ];
+
+    if written.iter().all(|&written| written) {
+        MJS_MESHED
+    } else {
+        MJS_BUFFER_TOO_SMALL
+    }
+}
+
+/// Propagate sunlight and torch light through a chunk and its neighbors into `lights`
+///
+/// `voxels` and `height_maps` hold nine pointers each, to the chunks at offsets `x` and `z`
+/// from `-1` to `1` at index `(x + 1) * 3 + (z + 1)`, null for chunks that aren't loaded. Light
+/// reaches no further than the neighbors, as the server assumes light levels below the chunk
+/// size.
+///
+/// # Safety
+///
+/// The non-null pointers of `voxels` must hold `mjs_chunk_len` values, those of `height_maps`
+/// `mjs_height_map_len` values, and `lights` must be valid for `mjs_chunk_len` values.
+#[no_mangle]
+pub unsafe extern "C" fn mjs_propagate_light(
+    context: *const MjsContext,
+    cx: i32,
+    cz: i32,
+    voxels: *const *const u32,
+    height_maps: *const *const u32,
+    lights: *mut u32,
+) {
+    let context = &*context;
+
+    let voxels = slice::from_raw_parts(voxels, 9);
+    let height_maps = slice::from_raw_parts(height_maps, 9);
+
+    let mut voxel_map = HashMap::new();
+    let mut height_map_map = HashMap::new();
+
+    for x in -1..=1 {
+        for z in -1..=1 {
+            let index = ((x + 1) * 3 + (z + 1)) as usize;
+
+            if voxels[index].is_null() || height_maps[index].is_null() {
+                continue;
+            }
+
+            let coords = Vec2(cx + x, cz + z);
+            voxel_map.insert(
+                coords.clone(),
+                read_ndarray(voxels[index], context.chunk_shape()),
+            );
+            height_map_map.insert(
+                coords,
+                read_ndarray(height_maps[index], context.height_map_shape()),
+            );
+        }
+    }
+
+    let space = Space::from_raw(
+        &Vec2(cx, cz),
+        context.config.max_light_level as usize,
+        context.config.chunk_size,
+        context.config.max_height as usize,
+        voxel_map,
+        height_map_map,
+    );
+
+    let propagated = lights::calc_light(&space, &context.registry, &context.config);
+    ptr::copy_nonoverlapping(propagated.data.as_ptr(), lights, propagated.data.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern "C" fn floor(_: *mut c_void, _: i32, vy: i32, _: i32) -> bool {
+        vy < 0
+    }
+
+    #[test]
+    fn sweep_and_raycast() {
+        let mut body = MjsBox {
+            base: [0.5, 2.0, 0.5],
+            size: [0.5, 1.0, 0.5],
+        };
+        let mut resting = [0; 3];
+
+        unsafe {
+            mjs_sweep(
+                floor,
+                ptr::null_mut(),
+                &mut body,
+                0.0,
+                -5.0,
+                0.0,
+                resting.as_mut_ptr(),
+            );
+        }
+        assert!(body.base[1].abs() < 1e-4);
+        assert_eq!(resting, [0, -1, 0]);
+
+        let mut voxel = [0; 3];
+        let mut face = [0; 3];
+        let hit = unsafe {
+            mjs_raycast(
+                floor,
+                ptr::null_mut(),
+                [0.5, 2.5, 0.5].as_ptr(),
+                [0.0, -1.0, 0.0].as_ptr(),
+                8.0,
+                voxel.as_mut_ptr(),
+                face.as_mut_ptr(),
+            )
+        };
+        assert!(hit);
+        assert_eq!(voxel, [0, -1, 0]);
+        assert_eq!(face, [0, 1, 0]);
+    }
+}
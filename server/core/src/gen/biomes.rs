@@ -7,7 +7,7 @@ use std::fs::File;
 use kdtree::distance::squared_euclidean;
 use kdtree::KdTree;
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use server_common::{
     math::smooth_interpolation,
@@ -56,9 +56,42 @@ pub struct BlocksData {
     pub cover: String,
 }
 
+/// How clients render a biome, tinting and fogging columns by their biome id
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BiomeVisuals {
+    pub grass_color: String,
+    pub foliage_color: String,
+    pub water_color: String,
+    pub fog_color: String,
+    pub sky_color: String,
+    /// Fraction of the render distance fog starts at
+    pub fog_near: f32,
+    /// Looping ambient sound played within the biome
+    pub ambience: Option<String>,
+}
+
+impl Default for BiomeVisuals {
+    fn default() -> Self {
+        Self {
+            grass_color: "#79c05a".to_owned(),
+            foliage_color: "#59ae30".to_owned(),
+            water_color: "#3f76e4".to_owned(),
+            fog_color: "#c0d8ff".to_owned(),
+            sky_color: "#78a7ff".to_owned(),
+            fog_near: 0.6,
+            ambience: None,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Biome {
+    /// Index in the biome registry, the river comes after every other biome
+    #[serde(skip)]
+    pub id: u32,
+
     pub name: String,
 
     pub presets: Vec<Vec<f64>>,
@@ -66,6 +99,9 @@ pub struct Biome {
     pub blocks: BlocksData,
 
     pub config: BiomeConfig,
+
+    #[serde(default)]
+    pub visuals: BiomeVisuals,
 }
 
 #[derive(Debug)]
@@ -95,9 +131,16 @@ impl Default for Biomes {
 impl Biomes {
     /// https://www.desmos.com/calculator/vjrxi1kyh7
     pub fn new() -> Self {
-        let biome_configs: BiomeConfigs =
+        let mut biome_configs: BiomeConfigs =
             serde_json::from_reader(File::open("assets/metadata/biomes.json").unwrap()).unwrap();
 
+        biome_configs
+            .biomes
+            .iter_mut()
+            .enumerate()
+            .for_each(|(id, biome)| biome.id = id as u32);
+        biome_configs.river.id = biome_configs.biomes.len() as u32;
+
         let BiomeConfigs {
             temperature_scale,
             temperature_seed,
@@ -173,8 +216,8 @@ impl Biomes {
             .collect()
     }
 
-    /// Get the interpolated height of X nearest biomes
-    pub fn get_biome(&self, vx: i32, vz: i32) -> Biome {
+    /// Sample the weighted biomes of a voxel column
+    fn sample(&self, vx: i32, vz: i32) -> Vec<(f64, &Biome)> {
         let vx = vx as f64;
         let vz = vz as f64;
 
@@ -190,6 +233,37 @@ impl Biomes {
             panic!("No biomes found.");
         }
 
+        biomes
+    }
+
+    /// Get the registry id of the biome a voxel column is in
+    pub fn get_biome_id(&self, vx: i32, vz: i32) -> u32 {
+        self.sample(vx, vz)[0].1.id
+    }
+
+    /// Every biome in id order, as JSON for clients to look up biome ids in
+    pub fn registry_json(&self) -> String {
+        let registry = self
+            .configs
+            .biomes
+            .iter()
+            .chain(std::iter::once(&self.configs.river))
+            .map(|biome| {
+                serde_json::json!({
+                    "id": biome.id,
+                    "name": biome.name,
+                    "visuals": biome.visuals,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_string(&registry).unwrap()
+    }
+
+    /// Get the interpolated height of X nearest biomes
+    pub fn get_biome(&self, vx: i32, vz: i32) -> Biome {
+        let biomes = self.sample(vx, vz);
+
         // https://www.gstatic.com/education/formulas2/355397047/en/weighted_average_formula.svg
 
         // let mut scale_numerator = 0.0;
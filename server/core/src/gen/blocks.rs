@@ -1,12 +1,6 @@
 use std::f32;
 
-/// Base class to extract voxel data from a single u32
-///
-/// Bit lineup as such (from right to left):
-/// - `1 - 16 bits`: ID (0x0000FFFF)
-/// - `17 - 20 bit`: rotation (0x000F0000)
-/// - `21 - 24 bit`: y rotation (0x00F00000)
-/// - `25 - 32 bit`: stage (0x0F000000)
+use server_common::chunk;
 
 const PY_ROTATION: u32 = 0;
 const NY_ROTATION: u32 = 1;
@@ -27,11 +21,12 @@ const Y_315_ROTATION: u32 = 7;
 const ROTATION_MASK: u32 = 0xFFF0FFFF;
 const Y_ROTATION_MASK: u32 = 0xFF0FFFFF;
 const STAGE_MASK: u32 = 0xF0FFFFFF;
+const VARIANT_MASK: u32 = 0x0FFFFFFF;
 
 /// 6 possible rotations: (px, nx, py, ny, pz, nz)
 ///
 /// Default rotation is PY
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum BlockRotation {
     PX(u32),
     NX(u32),
@@ -205,19 +200,45 @@ impl BlockRotation {
     }
 }
 
+/// Everything stored in a voxel besides its id
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct VoxelState {
+    pub rotation: BlockRotation,
+    pub stage: u32,
+    pub variant: u32,
+}
+
+impl Default for VoxelState {
+    fn default() -> Self {
+        Self {
+            rotation: BlockRotation::PY(0),
+            stage: 0,
+            variant: 0,
+        }
+    }
+}
+
+/// Base class to extract voxel data from a single u32
+///
+/// Bit lineup as such (from right to left):
+/// - `1 - 16 bits`: ID (0x0000FFFF)
+/// - `17 - 20 bit`: rotation (0x000F0000)
+/// - `21 - 24 bit`: y rotation (0x00F00000)
+/// - `25 - 28 bit`: stage (0x0F000000)
+/// - `29 - 32 bit`: variant (0xF0000000)
 pub struct Blocks;
 
 impl Blocks {
     /// Extract the bits in voxel that stores the voxel id
     #[inline]
     pub fn extract_id(voxel: u32) -> u32 {
-        voxel & 0xFFFF
+        chunk::extract_id(voxel)
     }
 
     /// Insert a voxel id into voxel value
     #[inline]
     pub fn insert_id(voxel: u32, id: u32) -> u32 {
-        (voxel & 0xFFFF0000) | (id & 0xFFFF)
+        chunk::insert_id(voxel, id)
     }
 
     /// Extract the bits in voxel that stores the voxel rotation
@@ -251,6 +272,40 @@ impl Blocks {
 
         (voxel & STAGE_MASK) | (stage << 24)
     }
+
+    /// Extract the bits in voxel that stores the variant value, such as the wood type
+    #[inline]
+    pub fn extract_variant(voxel: u32) -> u32 {
+        (voxel >> 28) & 0xF
+    }
+
+    /// Insert a voxel variant into voxel value
+    ///
+    /// Panics if variant overflows max (15)
+    #[inline]
+    pub fn insert_variant(voxel: u32, variant: u32) -> u32 {
+        assert!(variant <= 15, "Maximum variant is 15");
+
+        (voxel & VARIANT_MASK) | (variant << 28)
+    }
+
+    /// Extract the rotation, stage and variant of voxel
+    #[inline]
+    pub fn extract_state(voxel: u32) -> VoxelState {
+        VoxelState {
+            rotation: Blocks::extract_rotation(voxel),
+            stage: Blocks::extract_stage(voxel),
+            variant: Blocks::extract_variant(voxel),
+        }
+    }
+
+    /// Insert the rotation, stage and variant into voxel value, keeping the id
+    #[inline]
+    pub fn insert_state(voxel: u32, state: &VoxelState) -> u32 {
+        let value = Blocks::insert_rotation(voxel, &state.rotation);
+        let value = Blocks::insert_stage(value, state.stage);
+        Blocks::insert_variant(value, state.variant)
+    }
 }
 
 #[cfg(test)]
@@ -324,6 +379,25 @@ mod tests {
         assert_eq!(Blocks::extract_id(voxel), id);
     }
 
+    #[test]
+    fn state() {
+        let mut voxel = Blocks::insert_id(0, 13);
+        assert_eq!(Blocks::extract_state(voxel), VoxelState::default());
+
+        let state = VoxelState {
+            rotation: BlockRotation::NZ(270),
+            stage: 7,
+            variant: 15,
+        };
+
+        voxel = Blocks::insert_state(voxel, &state);
+        assert_eq!(Blocks::extract_state(voxel), state);
+        assert_eq!(Blocks::extract_id(voxel), 13);
+
+        voxel = Blocks::insert_stage(voxel, 2);
+        assert_eq!(Blocks::extract_variant(voxel), 15);
+    }
+
     // #[test]
     // #[should_panic(expected = "Maximum stage is 15")]
     // fn stage_max_exceeded() {
@@ -2,7 +2,10 @@ use std::f32::consts::PI;
 
 use super::super::engine::{chunk::Chunk, registry::Registry};
 
-use super::biomes::{BiomeConfig, Biomes};
+use super::{
+    biomes::{BiomeConfig, Biomes},
+    structures::Structures,
+};
 
 use server_common::{noise::Noise, vec::Vec3};
 
@@ -18,11 +21,16 @@ pub struct VoxelUpdate {
 pub struct Builder {
     noise: Noise,
     registry: Registry,
+    structures: Structures,
 }
 
 impl Builder {
     pub fn new(registry: Registry, noise: Noise) -> Self {
-        Self { noise, registry }
+        Self {
+            noise,
+            structures: Structures::new(&registry.data_packs),
+            registry,
+        }
     }
 
     /// Helper function to draw a circle of blocks horizontally
@@ -59,7 +67,7 @@ impl Builder {
 
         for vx in min.0..max.0 {
             for vz in min.2..max.2 {
-                let vy = chunk.get_max_height(vx, vz) as i32;
+                let vy = chunk.get_max_height(vx, vz);
 
                 let BiomeConfig { plant_scale, .. } = biomes.get_biome(vx, vz).config;
 
@@ -130,7 +138,7 @@ impl Builder {
 
         for vx in min.0..max.0 {
             for vz in min.2..max.2 {
-                let vy = chunk.get_max_height(vx, vz) as i32;
+                let vy = chunk.get_max_height(vx, vz);
                 let BiomeConfig { tree_scale, .. } = biomes.get_biome(vx, vz).config;
 
                 if self
@@ -249,7 +257,7 @@ impl Builder {
 
         for vx in min.0..max.0 {
             for vz in min.2..max.2 {
-                let vy = chunk.get_max_height(vx, vz) as i32;
+                let vy = chunk.get_max_height(vx, vz);
                 if self.noise.central_perlin(vx as f64, vz as f64, 0.02) {
                     locations.push(Vec3(vx, vy, vz));
                 }
@@ -283,7 +291,7 @@ impl Builder {
 
         for vx in min.0..max.0 {
             for vz in min.2..max.2 {
-                let vy = chunk.get_max_height(vx, vz) as i32;
+                let vy = chunk.get_max_height(vx, vz);
                 if self.noise.central_perlin(vx as f64, vz as f64, 0.008) {
                     locations.push(Vec3(vx, vy + 1, vz));
                 }
@@ -326,6 +334,53 @@ impl Builder {
         updates
     }
 
+    /// Place data-driven structure templates down on their sampled locations
+    fn generate_structures(&self, chunk: &Chunk, biomes: &Biomes) -> Vec<VoxelUpdate> {
+        let mut updates = Vec::new();
+
+        if self
+            .structures
+            .templates
+            .values()
+            .all(|t| t.frequency <= 0.0)
+        {
+            return updates;
+        }
+
+        let Chunk { min, max, .. } = chunk;
+
+        for vx in min.0..max.0 {
+            for vz in min.2..max.2 {
+                let biome = biomes.get_biome(vx, vz);
+
+                for template in self.structures.naturals(&biome.name) {
+                    if self
+                        .noise
+                        .central_perlin(vx as f64, vz as f64, template.frequency)
+                    {
+                        let vy = chunk.get_max_height(vx, vz);
+
+                        if let Some(ground) = &template.ground {
+                            let below = chunk.get_voxel(vx, vy, vz);
+                            if !self.registry.matches_pattern(below, ground) {
+                                continue;
+                            }
+                        }
+
+                        let vy = vy + 1;
+                        updates.append(&mut self.structures.place(
+                            &template.name,
+                            Vec3(vx, vy, vz),
+                            &self.registry,
+                        ));
+                    }
+                }
+            }
+        }
+
+        updates
+    }
+
     /// Returns a list of voxel updates from chunk
     pub fn build(&self, chunk: &Chunk, biomes: &Biomes) -> Vec<VoxelUpdate> {
         let mut updates = Vec::new();
@@ -334,6 +389,7 @@ impl Builder {
         // updates.append(&mut self.generate_stone_structure(chunk));
         // updates.append(&mut self.generate_plants(chunk, biomes));
         // updates.append(&mut self.generate_trees(chunk, biomes));
+        updates.append(&mut self.generate_structures(chunk, biomes));
 
         updates
     }
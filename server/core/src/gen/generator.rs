@@ -1,6 +1,6 @@
 use super::super::{
     constants::LEVEL_SEED,
-    engine::{chunk::Chunk, registry::Registry, world::WorldConfig},
+    engine::{chunk::Chunk, config::WorldConfig, registry::Registry},
     gen::builder::VoxelUpdate,
 };
 
@@ -80,8 +80,8 @@ impl Generator {
                 let mut pairs = vec![];
                 for i in 0..config.sub_chunks as i32 {
                     pairs.push((
-                        Vec3(start_x, unit * i, start_z),
-                        Vec3(end_x, unit * (i + 1), end_z),
+                        Vec3(start_x, start_y + unit * i, start_z),
+                        Vec3(end_x, start_y + unit * (i + 1), end_z),
                     ));
                 }
 
@@ -106,6 +106,15 @@ impl Generator {
                                         continue;
                                     }
 
+                                    // deep worlds are solid stone below where the terrain starts
+                                    if vy < 0 {
+                                        updates.push(VoxelUpdate {
+                                            voxel: Vec3(vx, vy, vz),
+                                            id: stone,
+                                        });
+                                        continue;
+                                    }
+
                                     let vy_ = vy;
                                     let vy = vy - height_offset;
 
@@ -257,18 +266,17 @@ impl Generator {
     ///
     /// Note: the chunk should already be initialized with voxel data
     pub fn generate_chunk_height_map(chunk: &mut Chunk, registry: &Registry, config: &WorldConfig) {
-        let max_height = config.max_height;
         let min = chunk.min.to_owned();
         let max = chunk.max.to_owned();
 
         for vx in min.0..max.0 {
             for vz in min.2..max.2 {
-                for vy in (0..max_height as i32).rev() {
+                for vy in (min.1..max.1).rev() {
                     let id = chunk.get_voxel(vx, vy, vz);
 
                     // TODO: CHECK FROM REGISTRY &&&&& PLANTS
-                    if vy == 0 || (Generator::check_height(id, registry)) {
-                        chunk.set_max_height(vx, vz, vy as u32);
+                    if vy == min.1 || (Generator::check_height(id, registry)) {
+                        chunk.set_max_height(vx, vz, vy);
                         break;
                     }
                 }
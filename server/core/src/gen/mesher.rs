@@ -6,8 +6,8 @@ use super::super::{
     constants::{BlockFace, CornerData, CornerSimplified, PlantFace, BLOCK_FACES, PLANT_FACES},
     engine::{
         chunk::Chunk,
+        config::WorldConfig,
         registry::{get_texture_type, Registry},
-        world::WorldConfig,
     },
     gen::lights::Lights,
 };
@@ -19,6 +19,26 @@ use server_common::{
 
 pub struct Mesher;
 
+/// Geometry buckets a chunk is meshed into
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeometryType {
+    /// Fully solid blocks
+    Opaque,
+    /// Alpha-tested blocks, such as leaves and plants
+    Cutout,
+    /// Alpha-blended blocks, such as water and glass, that clients need to depth-sort
+    Translucent,
+}
+
+/// Push the centroid of the last quad added to `positions`
+fn push_centroid(positions: &[f32], centroids: &mut Vec<f32>) {
+    let quad = &positions[positions.len() - 12..];
+
+    for axis in 0..3 {
+        centroids.push((quad[axis] + quad[axis + 3] + quad[axis + 6] + quad[axis + 9]) / 4.0);
+    }
+}
+
 fn get_block_by_voxel<'a>(
     vx: i32,
     vy: i32,
@@ -33,7 +53,7 @@ impl Mesher {
     /// Meshing a chunk. Poorly written. Needs refactor.
     pub fn mesh_chunk(
         chunk: &Chunk,
-        transparent: bool,
+        geometry_type: GeometryType,
         sub_chunk: u32,
         config: &WorldConfig,
         registry: &Registry,
@@ -59,8 +79,11 @@ impl Mesher {
         let mut green_lights = Vec::<i32>::new();
         let mut blue_lights = Vec::<i32>::new();
         let mut sunlights = Vec::<i32>::new();
+        let mut centroids = Vec::<f32>::new();
 
-        let &Vec3(start_x, _, start_z) = min_inner;
+        let needs_centroids = geometry_type == GeometryType::Translucent;
+
+        let &Vec3(start_x, start_y, start_z) = min_inner;
         let &Vec3(end_x, _, end_z) = max_inner;
 
         let vertex_ao = |side1: bool, side2: bool, corner: bool| -> i32 {
@@ -77,12 +100,13 @@ impl Mesher {
 
         let plant_shrink = 0.6;
 
-        let sub_chunk_unit = max_height / sub_chunks;
+        let sub_chunk_unit = (max_height / sub_chunks) as i32;
+        let sub_chunk = sub_chunk as i32;
 
         for vx in start_x..end_x {
             for vz in start_z..end_z {
                 for vy in
-                    (sub_chunk * sub_chunk_unit) as i32..((sub_chunk + 1) * sub_chunk_unit) as i32
+                    start_y + sub_chunk * sub_chunk_unit..start_y + (sub_chunk + 1) * sub_chunk_unit
                 {
                     let voxel_id = chunk.get_voxel(vx, vy, vz);
                     let rotation = chunk.get_voxel_rotation(vx, vy, vz);
@@ -93,17 +117,19 @@ impl Mesher {
                         is_block,
                         is_plant,
                         is_fluid,
+                        transparent_standalone,
                         ..
                     } = registry.get_block_by_id(voxel_id);
 
-                    // TODO: simplify this logic
-                    if (is_solid || is_plant)
-                        && (if transparent {
-                            is_transparent
-                        } else {
-                            !is_transparent
-                        })
-                    {
+                    let block_geometry = if !is_transparent {
+                        GeometryType::Opaque
+                    } else if is_plant || transparent_standalone {
+                        GeometryType::Cutout
+                    } else {
+                        GeometryType::Translucent
+                    };
+
+                    if (is_solid || is_plant) && block_geometry == geometry_type {
                         let texture = registry.get_texture_by_id(voxel_id);
                         let texture_type = get_texture_type(texture);
                         let uv_map = registry.get_uv_by_id(voxel_id);
@@ -152,6 +178,10 @@ impl Mesher {
                                     aos.push(3);
                                 }
 
+                                if needs_centroids {
+                                    push_centroid(&positions, &mut centroids);
+                                }
+
                                 indices.push(ndx);
                                 indices.push(ndx + 1);
                                 indices.push(ndx + 2);
@@ -192,7 +222,7 @@ impl Mesher {
 
                                 if ((n_block_type.is_transparent && !n_block_type.is_fluid)
                                     || (n_block_type.is_fluid && !is_fluid))
-                                    && (!transparent
+                                    && (geometry_type == GeometryType::Opaque
                                         || n_block_type.is_empty
                                         || neighbor_id != voxel_id
                                         || (n_block_type.transparent_standalone
@@ -488,6 +518,10 @@ impl Mesher {
                                         );
                                     }
 
+                                    if needs_centroids {
+                                        push_centroid(&positions, &mut centroids);
+                                    }
+
                                     let a_rt = four_red_lights[0];
                                     let b_rt = four_red_lights[1];
                                     let c_rt = four_red_lights[2];
@@ -595,6 +629,7 @@ impl Mesher {
             uvs,
             aos,
             lights,
+            centroids,
         })
     }
 }
@@ -4,3 +4,5 @@ pub mod builder;
 pub mod generator;
 pub mod lights;
 pub mod mesher;
+pub mod snapshot;
+pub mod structures;
@@ -0,0 +1,156 @@
+use std::{collections::BTreeMap, fs::File};
+
+use crate::{
+    constants::LEVEL_SEED,
+    engine::{
+        chunk::Chunk,
+        config::{Configs, WorldConfig},
+        registry::Registry,
+    },
+};
+
+use super::{biomes::Biomes, builder::Builder, generator::Generator};
+
+use server_common::{noise::Noise, vec::Vec2};
+use server_utils::hash::{fnv1a_extend, FNV_OFFSET};
+
+/// Committed hashes of the snapshot chunks, keyed by `<world>/<cx>,<cz>`
+pub const GOLDEN_FILE: &str = "assets/metadata/golden.json";
+
+/// Chunks to snapshot, spread out to cover several biomes
+pub const SNAPSHOT_CHUNKS: [Vec2<i32>; 6] = [
+    Vec2(0, 0),
+    Vec2(1, -1),
+    Vec2(-3, 5),
+    Vec2(8, 8),
+    Vec2(-16, -4),
+    Vec2(32, -27),
+];
+
+pub type Snapshots = BTreeMap<String, String>;
+
+/// Hash the voxels and the height map of a chunk, paddings included
+pub fn hash_chunk(chunk: &Chunk) -> u64 {
+    let mut hash = FNV_OFFSET;
+
+    for vx in chunk.min.0..chunk.max.0 {
+        for vz in chunk.min.2..chunk.max.2 {
+            hash = fnv1a_extend(hash, &chunk.get_max_height(vx, vz).to_le_bytes());
+
+            for vy in chunk.min.1..chunk.max.1 {
+                hash = fnv1a_extend(hash, &chunk.get_raw_voxel(vx, vy, vz).to_le_bytes());
+            }
+        }
+    }
+
+    hash
+}
+
+/// Generate a chunk from scratch the way `Chunks` does, decorations within the chunk included
+pub fn generate_chunk(
+    coords: &Vec2<i32>,
+    config: &WorldConfig,
+    registry: &Registry,
+    biomes: &Biomes,
+    builder: &Builder,
+) -> Chunk {
+    // without storage, a saved chunk is never read
+    let mut chunk = Chunk::new(coords.to_owned(), config, None);
+
+    Generator::generate_chunk(&mut chunk, registry, biomes, config);
+    Generator::generate_chunk_height_map(&mut chunk, registry, config);
+
+    for update in builder.build(&chunk, biomes) {
+        let (min, max, voxel) = (&chunk.min, &chunk.max, &update.voxel);
+
+        if voxel.0 >= min.0
+            && voxel.0 < max.0
+            && voxel.1 >= min.1
+            && voxel.1 < max.1
+            && voxel.2 >= min.2
+            && voxel.2 < max.2
+        {
+            chunk.set_voxel(voxel.0, voxel.1, voxel.2, update.id);
+        }
+    }
+
+    chunk
+}
+
+/// Hash the snapshot chunks of every world in a worlds file
+pub fn snapshot_worlds(path: &str) -> Snapshots {
+    let (configs, registry) = Configs::load_worlds(path);
+
+    let biomes = Biomes::new();
+    let builder = Builder::new(registry.to_owned(), Noise::new(LEVEL_SEED));
+
+    let mut snapshots = Snapshots::new();
+
+    for (name, (_, config)) in configs.iter() {
+        for coords in SNAPSHOT_CHUNKS.iter() {
+            let chunk = generate_chunk(coords, config, &registry, &biomes, &builder);
+
+            snapshots.insert(
+                format!("{}/{},{}", name, coords.0, coords.1),
+                format!("{:016x}", hash_chunk(&chunk)),
+            );
+        }
+    }
+
+    snapshots
+}
+
+/// Read the committed golden hashes, empty if none have been generated yet
+pub fn load_golden() -> Snapshots {
+    match File::open(GOLDEN_FILE) {
+        Ok(file) => serde_json::from_reader(file).expect("Unable to parse golden hashes."),
+        Err(_) => Snapshots::new(),
+    }
+}
+
+/// Overwrite the golden hashes, only to be done for intended terrain changes
+pub fn save_golden(snapshots: &Snapshots) {
+    let file = File::create(GOLDEN_FILE).expect("Unable to write golden hashes.");
+    serde_json::to_writer_pretty(file, snapshots).unwrap();
+}
+
+/// List the chunks whose hashes differ from the golden ones
+///
+/// Chunks without a golden hash are skipped.
+pub fn diff(golden: &Snapshots, current: &Snapshots) -> Vec<String> {
+    current
+        .iter()
+        .filter(|(key, hash)| golden.get(*key).is_some_and(|golden| golden != *hash))
+        .map(|(key, hash)| format!("{}: expected {}, got {}", key, golden[key], hash))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden_chunks() {
+        // assets are resolved from the repository root
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../..")).unwrap();
+
+        let current = snapshot_worlds("assets/metadata/worlds.json");
+        let mismatches = diff(&load_golden(), &current);
+
+        assert!(
+            mismatches.is_empty(),
+            "Generated terrain changed, run `cargo run --bin golden` if intended:\n{}",
+            mismatches.join("\n")
+        );
+    }
+
+    #[test]
+    fn deterministic() {
+        std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../..")).unwrap();
+
+        assert_eq!(
+            snapshot_worlds("assets/metadata/worlds.json"),
+            snapshot_worlds("assets/metadata/worlds.json")
+        );
+    }
+}
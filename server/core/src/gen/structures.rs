@@ -0,0 +1,291 @@
+use std::{collections::HashMap, fs::File};
+
+use log::info;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use serde::Deserialize;
+
+use server_common::vec::Vec3;
+
+use super::{
+    super::{
+        constants::LEVEL_SEED,
+        engine::{
+            data_packs::DataPacks,
+            registry::Registry,
+            rng::{seeded_at, RngDomain},
+        },
+    },
+    builder::VoxelUpdate,
+};
+
+/// Default limit of how many times connections can expand into more templates
+const DEFAULT_MAX_DEPTH: usize = 4;
+
+/// A jigsaw-like point where another template from a pool can attach
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StructureConnection {
+    pub name: String,
+    pub position: [i32; 3],
+    /// Horizontal direction the connection points towards, one of `px`, `nx`, `pz` or `nz`
+    pub facing: String,
+    /// Names of templates that can attach here, none means nothing attaches
+    #[serde(default)]
+    pub pool: Vec<String>,
+}
+
+/// Replace a block of the palette with one of the options, picked once per placement
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PaletteSwap {
+    pub block: String,
+    pub options: Vec<String>,
+}
+
+/// A box of the template that's only placed by chance
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OptionalSection {
+    pub min: [i32; 3],
+    pub max: [i32; 3],
+    pub chance: f64,
+}
+
+/// A data-driven structure, loaded from `assets/metadata/structures`
+///
+/// `layers` go from bottom to top, each layer is a list of rows along z, and each character
+/// of a row is a voxel along x, looked up in `palette`. Spaces or characters missing from the
+/// palette leave the world untouched.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct StructureTemplate {
+    pub name: String,
+    pub palette: HashMap<String, String>,
+    pub layers: Vec<Vec<String>>,
+    /// Named points within the template, `origin` is placed on the sampled location
+    #[serde(default)]
+    pub anchors: HashMap<String, [i32; 3]>,
+    #[serde(default)]
+    pub connections: Vec<StructureConnection>,
+    #[serde(default)]
+    pub swaps: Vec<PaletteSwap>,
+    #[serde(default)]
+    pub optional: Vec<OptionalSection>,
+    /// Noise scale of natural placement, 0 only places the template by connections
+    #[serde(default)]
+    pub frequency: f64,
+    /// Biomes the template is naturally placed in, empty means all
+    #[serde(default)]
+    pub biomes: Vec<String>,
+    /// Block or tag such as `#dirt` the template is naturally placed on, any if missing
+    #[serde(default)]
+    pub ground: Option<String>,
+    #[serde(default)]
+    pub max_depth: Option<usize>,
+}
+
+fn facing_to_dir(facing: &str) -> Option<(i32, i32)> {
+    match facing {
+        "px" => Some((1, 0)),
+        "nx" => Some((-1, 0)),
+        "pz" => Some((0, 1)),
+        "nz" => Some((0, -1)),
+        _ => None,
+    }
+}
+
+fn opposite_facing(facing: &str) -> &str {
+    match facing {
+        "px" => "nx",
+        "nx" => "px",
+        "pz" => "nz",
+        "nz" => "pz",
+        _ => "",
+    }
+}
+
+impl StructureTemplate {
+    /// Local position placed on the sampled location
+    pub fn origin(&self) -> [i32; 3] {
+        self.anchors.get("origin").cloned().unwrap_or([0, 0, 0])
+    }
+
+    /// Voxel updates of this template with its minimum corner at `corner`
+    fn voxels(&self, corner: Vec3<i32>, registry: &Registry, rng: &mut StdRng) -> Vec<VoxelUpdate> {
+        let swaps = self
+            .swaps
+            .iter()
+            .filter_map(|swap| {
+                swap.options
+                    .choose(rng)
+                    .map(|option| (swap.block.to_owned(), option.to_owned()))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let skipped = self
+            .optional
+            .iter()
+            .filter(|section| !rng.gen_bool(section.chance.clamp(0.0, 1.0)))
+            .collect::<Vec<_>>();
+
+        let mut updates = vec![];
+
+        for (y, layer) in self.layers.iter().enumerate() {
+            for (z, row) in layer.iter().enumerate() {
+                for (x, symbol) in row.chars().enumerate() {
+                    let local = [x as i32, y as i32, z as i32];
+
+                    if skipped.iter().any(|section| {
+                        (0..3).all(|i| local[i] >= section.min[i] && local[i] <= section.max[i])
+                    }) {
+                        continue;
+                    }
+
+                    let block = match self.palette.get(&symbol.to_string()) {
+                        Some(block) => swaps.get(block).unwrap_or(block),
+                        None => continue,
+                    };
+
+                    if let Some(id) = registry.try_get_id_by_name(block) {
+                        updates.push(VoxelUpdate {
+                            voxel: Vec3(
+                                corner.0 + local[0],
+                                corner.1 + local[1],
+                                corner.2 + local[2],
+                            ),
+                            id,
+                        });
+                    }
+                }
+            }
+        }
+
+        updates
+    }
+}
+
+/// Every structure template, keyed by name
+#[derive(Debug, Default)]
+pub struct Structures {
+    pub templates: HashMap<String, StructureTemplate>,
+}
+
+impl Structures {
+    /// Load the templates listed in `structures.json`, if any, those of later data packs
+    /// replacing the ones of the same name
+    pub fn new(data_packs: &DataPacks) -> Self {
+        let mut structures = Self::default();
+
+        let files: Vec<String> = match data_packs.read("structures.json") {
+            Some(files) => serde_json::from_value(files).expect("Unable to parse structures.json"),
+            None => return structures,
+        };
+
+        for file in files {
+            let path = data_packs.path(&format!("structures/{}", file));
+            let template: StructureTemplate = serde_json::from_reader(
+                File::open(&path).unwrap_or_else(|_| panic!("Structure not found: {}", file)),
+            )
+            .unwrap_or_else(|e| panic!("Unable to parse structure {}: {}", file, e));
+
+            structures.register(template);
+        }
+
+        info!("Loaded {} structure templates", structures.templates.len());
+
+        structures
+    }
+
+    /// Add a template, replacing any with the same name
+    pub fn register(&mut self, template: StructureTemplate) {
+        self.templates.insert(template.name.to_owned(), template);
+    }
+
+    /// Templates that are naturally placed in a biome
+    pub fn naturals<'a>(&'a self, biome: &'a str) -> impl Iterator<Item = &'a StructureTemplate> {
+        self.templates.values().filter(move |template| {
+            template.frequency > 0.0
+                && (template.biomes.is_empty() || template.biomes.iter().any(|b| b == biome))
+        })
+    }
+
+    /// Place a template with its `origin` anchor at location, expanding its connections
+    ///
+    /// The same location always results in the same randomization.
+    pub fn place(&self, name: &str, location: Vec3<i32>, registry: &Registry) -> Vec<VoxelUpdate> {
+        let mut rng = seeded_at(LEVEL_SEED as u64, RngDomain::Structures, &location);
+
+        let template = match self.templates.get(name) {
+            Some(template) => template,
+            None => return vec![],
+        };
+
+        let [ox, oy, oz] = template.origin();
+        let corner = Vec3(location.0 - ox, location.1 - oy, location.2 - oz);
+        let max_depth = template.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+
+        let mut updates = vec![];
+        self.expand(
+            template,
+            corner,
+            max_depth,
+            registry,
+            &mut rng,
+            &mut updates,
+        );
+        updates
+    }
+
+    fn expand(
+        &self,
+        template: &StructureTemplate,
+        corner: Vec3<i32>,
+        depth: usize,
+        registry: &Registry,
+        rng: &mut StdRng,
+        updates: &mut Vec<VoxelUpdate>,
+    ) {
+        updates.append(&mut template.voxels(corner.clone(), registry, rng));
+
+        if depth == 0 {
+            return;
+        }
+
+        for connection in template.connections.iter() {
+            let (dx, dz) = match facing_to_dir(&connection.facing) {
+                Some(dir) => dir,
+                None => continue,
+            };
+
+            let target = match connection
+                .pool
+                .choose(rng)
+                .and_then(|n| self.templates.get(n))
+            {
+                Some(target) => target,
+                None => continue,
+            };
+
+            // attach by a connection facing back towards this one
+            let facing = opposite_facing(&connection.facing);
+            let candidates = target
+                .connections
+                .iter()
+                .filter(|c| c.facing == facing)
+                .collect::<Vec<_>>();
+
+            if let Some(other) = candidates.choose(rng) {
+                let [px, py, pz] = connection.position;
+                let [cx, cy, cz] = other.position;
+
+                let child = Vec3(
+                    corner.0 + px + dx - cx,
+                    corner.1 + py - cy,
+                    corner.2 + pz + dz - cz,
+                );
+
+                self.expand(target, child, depth - 1, registry, rng, updates);
+            }
+        }
+    }
+}
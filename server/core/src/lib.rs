@@ -1,6 +1,31 @@
+//! Voxel engine of mine.js, and the server built on it
+//!
+//! Without the default `server` feature this is a plain library with no actix or WebSocket
+//! dependencies, for projects that want the algorithms alone:
+//!
+//! - `common` and `utils`: `Ndarray`, `Aabb`, vectors, chunk data and its lighting, `sweep` and
+//!   `raycast`
+//! - `engine::chunk`, `engine::chunks` and `engine::space`: chunk storage and sampling
+//! - `gen`: terrain generation, `gen::mesher::Mesher` and `gen::lights::calc_light`
+//! - `engine::registry` and `engine::config`: blocks, texture packs and world options
+//! - `network::models`: the protocol messages, generated from `protocol/messages.proto`
+//!
+//! The `server` feature adds the ECS world loop in `engine::world` and `sys`, the WebSocket
+//! server in `network` with `network::app::serve` to run it, and the maintenance tools in
+//! `cli`. The `main` binary is a thin wrapper setting up logging and calling into those.
+
+pub use server_common as common;
+pub use server_utils as utils;
+
+#[cfg(feature = "server")]
+pub mod cli;
 pub mod comp;
 pub mod constants;
 pub mod engine;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod gen;
 pub mod network;
+#[cfg(feature = "server")]
 pub mod sys;
+pub mod world_edit;
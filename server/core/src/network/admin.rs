@@ -0,0 +1,378 @@
+use actix::prelude::*;
+use actix_web::{
+    get, post,
+    web::{self, Query},
+    Error, HttpRequest, HttpResponse, Result,
+};
+use actix_web_actors::ws;
+
+use rand::{distributions::Alphanumeric, Rng};
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use server_utils::secret::constant_time_eq;
+
+use crate::engine::config::ServerConfig;
+
+use super::{message, routes, server::WsServer};
+
+/// Lines of the server log kept for admins connecting later
+pub const MAX_CONSOLE_LINES: usize = 1000;
+
+/// How often console streams check the log for new lines
+const CONSOLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long a console ticket can be redeemed for
+const CONSOLE_TICKET_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default)]
+struct ConsoleLines {
+    /// Lines ever logged, the sequence number of the next one
+    next: u64,
+    lines: VecDeque<String>,
+}
+
+/// Recent lines of the server log, shared by the logger and the admin console streams
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleLog {
+    inner: Arc<Mutex<ConsoleLines>>,
+}
+
+impl ConsoleLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a line, forgetting the oldest past `MAX_CONSOLE_LINES`
+    pub fn push(&self, line: String) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.lines.len() >= MAX_CONSOLE_LINES {
+            inner.lines.pop_front();
+        }
+
+        inner.lines.push_back(line);
+        inner.next += 1;
+    }
+
+    /// Lines from a sequence number on, along with the sequence number to read from next
+    pub fn since(&self, sequence: u64) -> (Vec<String>, u64) {
+        let inner = self.inner.lock().unwrap();
+        let first = inner.next - inner.lines.len() as u64;
+
+        let lines = inner
+            .lines
+            .iter()
+            .skip(sequence.saturating_sub(first) as usize)
+            .cloned()
+            .collect();
+
+        (lines, inner.next)
+    }
+}
+
+/// Single-use tickets opening the console stream of a world
+///
+/// Browsers can't set headers on WebSockets, so they trade the admin token for a ticket first
+/// instead of putting the token in the URL, where it would end up in logs and histories.
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleTickets {
+    inner: Arc<Mutex<HashMap<String, (String, Instant)>>>,
+}
+
+impl ConsoleTickets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue a ticket for the console of a world
+    pub fn issue(&self, world_name: &str) -> String {
+        let ticket = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(24)
+            .map(char::from)
+            .collect::<String>();
+
+        let mut tickets = self.inner.lock().unwrap();
+        tickets.retain(|_, (_, issued)| issued.elapsed() < CONSOLE_TICKET_TTL);
+        tickets.insert(ticket.to_owned(), (world_name.to_owned(), Instant::now()));
+
+        ticket
+    }
+
+    /// Redeem a ticket, returning the world it was issued for if it's still valid
+    pub fn redeem(&self, ticket: &str) -> Option<String> {
+        self.inner
+            .lock()
+            .unwrap()
+            .remove(ticket)
+            .filter(|(_, issued)| issued.elapsed() < CONSOLE_TICKET_TTL)
+            .map(|(world_name, _)| world_name)
+    }
+}
+
+/// Whether a request carries the admin token as a bearer token
+fn is_authorized(req: &HttpRequest, config: &ServerConfig) -> bool {
+    let expected = match &config.admin_token {
+        Some(token) if !token.is_empty() => token,
+        _ => return false,
+    };
+
+    let token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "));
+
+    token.is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes()))
+}
+
+/// Check a request is authorized and names a world, answering it otherwise
+fn admin_world(
+    req: &HttpRequest,
+    params: &HashMap<String, String>,
+    config: &ServerConfig,
+) -> Result<String, HttpResponse> {
+    if !is_authorized(req, config) {
+        return Err(HttpResponse::Unauthorized().finish());
+    }
+
+    match params.get("world") {
+        Some(world_name) => Ok(world_name.to_owned()),
+        None => Err(HttpResponse::BadRequest().body("Missing world.")),
+    }
+}
+
+/// Route to list the players of a world
+#[get("/admin/players")]
+pub async fn players(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    config: web::Data<ServerConfig>,
+) -> Result<HttpResponse> {
+    let world_name = match admin_world(&req, &params, &config) {
+        Ok(world_name) => world_name,
+        Err(response) => return Ok(response),
+    };
+
+    let players = WsServer::from_registry()
+        .send(message::ListPlayers(world_name))
+        .await
+        .unwrap();
+
+    match players {
+        Some(players) => Ok(HttpResponse::Ok().json(players)),
+        None => Ok(HttpResponse::NotFound().body("World not found.")),
+    }
+}
+
+/// Route to get the tick times and counts of a world
+#[get("/admin/metrics")]
+pub async fn metrics(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    config: web::Data<ServerConfig>,
+) -> Result<HttpResponse> {
+    let world_name = match admin_world(&req, &params, &config) {
+        Ok(world_name) => world_name,
+        Err(response) => return Ok(response),
+    };
+
+    let metrics = WsServer::from_registry()
+        .send(message::GetMetrics(world_name))
+        .await
+        .unwrap();
+
+    match metrics {
+        Some(metrics) => Ok(HttpResponse::Ok().json(metrics)),
+        None => Ok(HttpResponse::NotFound().body("World not found.")),
+    }
+}
+
+/// Route to run the command in the body as the console, answered with its replies
+#[post("/admin/command")]
+pub async fn command(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    config: web::Data<ServerConfig>,
+    body: String,
+) -> Result<HttpResponse> {
+    let world_name = match admin_world(&req, &params, &config) {
+        Ok(world_name) => world_name,
+        Err(response) => return Ok(response),
+    };
+
+    let replies = WsServer::from_registry()
+        .send(message::RunCommand {
+            world_name,
+            command: body,
+        })
+        .await
+        .unwrap();
+
+    match replies {
+        Some(replies) => Ok(HttpResponse::Ok().json(replies)),
+        None => Ok(HttpResponse::NotFound().body("World not found.")),
+    }
+}
+
+/// Route to get a map tile, the same as `/map`
+#[get("/admin/map")]
+pub async fn map(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    config: web::Data<ServerConfig>,
+) -> Result<HttpResponse> {
+    if let Err(response) = admin_world(&req, &params, &config) {
+        return Ok(response);
+    }
+
+    routes::map_tile(&params).await
+}
+
+/// Route trading the admin token for a ticket to open the console stream of a world with
+#[post("/admin/console/ticket")]
+pub async fn console_ticket(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    config: web::Data<ServerConfig>,
+    tickets: web::Data<ConsoleTickets>,
+) -> Result<HttpResponse> {
+    let world_name = match admin_world(&req, &params, &config) {
+        Ok(world_name) => world_name,
+        Err(response) => return Ok(response),
+    };
+
+    let ticket = tickets.issue(&world_name);
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "ticket": ticket })))
+}
+
+/// Route streaming the server log over a WebSocket, text sent back being run as commands
+///
+/// Opened with the admin token as a bearer token, or with the `ticket` parameter from
+/// `/admin/console/ticket` by browsers.
+pub async fn console(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    stream: web::Payload,
+    config: web::Data<ServerConfig>,
+    log: web::Data<ConsoleLog>,
+    tickets: web::Data<ConsoleTickets>,
+) -> Result<HttpResponse, Error> {
+    let ticket = params
+        .get("ticket")
+        .and_then(|ticket| tickets.redeem(ticket));
+
+    let world_name = match ticket {
+        Some(world_name) => world_name,
+        None => match admin_world(&req, &params, &config) {
+            Ok(world_name) => world_name,
+            Err(response) => return Ok(response),
+        },
+    };
+
+    let session = ConsoleSession {
+        world_name,
+        log: log.get_ref().clone(),
+        next: 0,
+    };
+
+    ws::start(session, &req, stream)
+}
+
+/// An admin's console stream, starting with the lines kept in the log
+pub struct ConsoleSession {
+    world_name: String,
+    log: ConsoleLog,
+    /// Sequence number of the next line to send
+    next: u64,
+}
+
+impl Actor for ConsoleSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(CONSOLE_POLL_INTERVAL, |act, ctx| {
+            let (lines, next) = act.log.since(act.next);
+            act.next = next;
+
+            lines.into_iter().for_each(|line| ctx.text(line));
+        });
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for ConsoleSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(_) => {
+                ctx.stop();
+                return;
+            }
+        };
+
+        match msg {
+            ws::Message::Text(text) => {
+                WsServer::from_registry()
+                    .send(message::RunCommand {
+                        world_name: self.world_name.to_owned(),
+                        command: text.to_string(),
+                    })
+                    .into_actor(self)
+                    .then(|replies, _, ctx| {
+                        match replies {
+                            Ok(Some(replies)) => replies.into_iter().for_each(|r| ctx.text(r)),
+                            _ => ctx.text("World not found."),
+                        }
+
+                        fut::ready(())
+                    })
+                    .wait(ctx);
+            }
+            ws::Message::Ping(bytes) => ctx.pong(&bytes),
+            ws::Message::Close(reason) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn console_tickets() {
+        let tickets = ConsoleTickets::new();
+        let ticket = tickets.issue("world");
+
+        assert_eq!(tickets.redeem("unknown"), None);
+        assert_eq!(tickets.redeem(&ticket), Some("world".to_owned()));
+        // tickets only open one stream
+        assert_eq!(tickets.redeem(&ticket), None);
+    }
+
+    #[test]
+    fn console_log() {
+        let log = ConsoleLog::new();
+
+        for i in 0..MAX_CONSOLE_LINES + 2 {
+            log.push(i.to_string());
+        }
+
+        // the two oldest lines are gone
+        let (lines, next) = log.since(0);
+        assert_eq!(lines.len(), MAX_CONSOLE_LINES);
+        assert_eq!(lines[0], "2");
+        assert_eq!(next, MAX_CONSOLE_LINES as u64 + 2);
+
+        log.push("new".to_owned());
+        assert_eq!(log.since(next), (vec!["new".to_owned()], next + 1));
+    }
+}
@@ -0,0 +1,158 @@
+use log::info;
+
+use actix::SystemService;
+use actix_cors::Cors;
+use actix_files as fs;
+use actix_web::{web, App, HttpServer};
+
+use std::net::ToSocketAddrs;
+
+use crate::engine::{config::Configs, resource_pack::RESOURCE_PACKS_FOLDER, skins::SKINS_FOLDER};
+
+#[cfg(feature = "tls")]
+use super::tls;
+use super::{
+    admin::{self, ConsoleLog, ConsoleTickets},
+    message,
+    proxy::ClientAddrs,
+    routes,
+    server::WsServer,
+};
+
+/// Routes players and browsers use
+pub fn public_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/", web::get().to(routes::index))
+        .route("/biomes", web::get().to(routes::index))
+        .service(routes::worlds)
+        .service(routes::world)
+        .service(routes::time)
+        .service(routes::skin)
+        .service(routes::shard)
+        .service(routes::map)
+        .service(web::resource("/ws/").to(routes::ws_route))
+        .service(fs::Files::new("/atlas/", "assets/textures/generated/").show_files_listing())
+        .service(fs::Files::new("/procedural/", "assets/textures/procedural").show_files_listing())
+        .service(fs::Files::new("/packs/", "assets/textures/packs/").show_files_listing())
+        .service(fs::Files::new("/models/", "assets/models/objects/").show_files_listing())
+        .service(fs::Files::new("/skins/", SKINS_FOLDER))
+        .service(fs::Files::new("/resources/", RESOURCE_PACKS_FOLDER))
+        .service(fs::Files::new("/", "public/").show_files_listing());
+}
+
+/// Routes other servers and operators use
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(routes::release_handoff)
+        .service(routes::confirm_handoff)
+        .service(
+            web::resource("/handoff/accept")
+                .app_data(web::JsonConfig::default().limit(routes::MAX_HANDOFF_SIZE))
+                .route(web::post().to(routes::accept_handoff)),
+        )
+        .service(routes::bridge)
+        .service(admin::players)
+        .service(admin::metrics)
+        .service(admin::command)
+        .service(admin::map)
+        .service(admin::console_ticket)
+        .service(web::resource("/admin/console").to(admin::console));
+}
+
+/// Bind a server to each of the listeners, serving the ones asking for it over TLS
+macro_rules! bind_listeners {
+    ($srv:expr, $listeners:expr, $tls:expr) => {{
+        let mut srv = $srv;
+
+        for listener in $listeners {
+            srv = match (listener.tls, $tls) {
+                #[cfg(feature = "tls")]
+                (true, Some(tls_config)) => {
+                    srv.bind_rustls(&listener.address, tls::load_rustls(tls_config))?
+                }
+                #[cfg(not(feature = "tls"))]
+                (true, Some(_)) => panic!("TLS requires the server to be built with `tls`."),
+                (true, None) => panic!("A TLS listener needs `server.tls` to be configured."),
+                (false, _) => srv.bind(&listener.address)?,
+            };
+
+            info!(
+                "🚀  MineJS {}running on {}://{}",
+                if listener.admin { "admin " } else { "" },
+                if listener.tls { "https" } else { "http" },
+                &listener.address
+            );
+        }
+
+        srv
+    }};
+}
+
+/// Serve the worlds of `assets/metadata/worlds.json` on the configured listeners
+///
+/// Streams the log lines pushed to `console` to the admin console, returning once the servers
+/// stop.
+pub async fn serve(console: ConsoleLog) -> std::io::Result<()> {
+    std::fs::create_dir_all(SKINS_FOLDER)?;
+
+    let config = Configs::load_server("assets/metadata/worlds.json");
+    let addrs = ClientAddrs::new(&config.proxy);
+
+    let (admin, public): (Vec<_>, Vec<_>) = config
+        .listeners()
+        .into_iter()
+        .partition(|listener| listener.admin);
+
+    // the admin routes are served publicly unless listeners are dedicated to them
+    let has_admin = !admin.is_empty();
+
+    if let (Some(proxy_protocol), Some(listener)) = (&config.proxy.proxy_protocol, public.first()) {
+        let upstream = listener.address.to_socket_addrs()?.next().unwrap();
+        addrs.spawn_relay(proxy_protocol, upstream)?;
+    }
+
+    let mut servers = vec![];
+    let server_config = config.clone();
+    let public_console = console.clone();
+    let tickets = ConsoleTickets::new();
+    let public_tickets = tickets.clone();
+
+    if !public.is_empty() {
+        let srv = HttpServer::new(move || {
+            let app = App::new()
+                .wrap(Cors::permissive())
+                .data(addrs.clone())
+                .data(server_config.clone())
+                .data(public_console.clone())
+                .data(public_tickets.clone());
+
+            if has_admin {
+                app.configure(public_routes)
+            } else {
+                app.configure(admin_routes).configure(public_routes)
+            }
+        });
+
+        servers.push(bind_listeners!(srv, &public, config.tls.as_ref()).run());
+    }
+
+    if has_admin {
+        let admin_config = config.clone();
+        let srv = HttpServer::new(move || {
+            App::new()
+                .data(admin_config.clone())
+                .data(console.clone())
+                .data(tickets.clone())
+                .configure(admin_routes)
+        });
+
+        servers.push(bind_listeners!(srv, &admin, config.tls.as_ref()).run());
+    }
+
+    // Wake up the sever
+    WsServer::from_registry().do_send(message::Noop);
+
+    for server in servers {
+        server.await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use super::models::messages::{self, message::Type as MessageType};
+
+/// How long the measured send rate is averaged over
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Per-connection limits on what the server sends
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthConfig {
+    /// Bytes per second sent to each client before low-priority messages are held back,
+    /// 0 for no limit
+    #[serde(default)]
+    pub limit: u64,
+}
+
+/// How much a message matters to a client staying consistent with the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Cosmetics, dropped when over budget
+    Low,
+    /// Peers and entities, deferred when over budget
+    Normal,
+    /// Chunks, block updates and everything else, always sent
+    Critical,
+}
+
+/// What to do with a message given the connection's budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Send,
+    Defer,
+    Drop,
+}
+
+/// Get the priority of an outbound message
+pub fn priority_of(message: &messages::Message) -> Priority {
+    match MessageType::from_i32(message.r#type) {
+        Some(MessageType::Time) | Some(MessageType::Advancement) | Some(MessageType::Target) => {
+            Priority::Low
+        }
+        Some(MessageType::Peer)
+        | Some(MessageType::Entity)
+        | Some(MessageType::Players)
+        | Some(MessageType::Message)
+        | Some(MessageType::Ping) => Priority::Normal,
+        _ => Priority::Critical,
+    }
+}
+
+/// Bytes sent over a connection, and a token bucket throttling it to the configured limit
+#[derive(Debug, Default)]
+pub struct Bandwidth {
+    limit: u64,
+    /// Bytes that can still be sent right away, negative after a critical burst
+    budget: i64,
+    refilled: Option<Instant>,
+    /// Bytes sent since `window` started, and the rate measured over the last window
+    window: Option<Instant>,
+    window_bytes: u64,
+    rate: u64,
+    pub total: u64,
+}
+
+impl Bandwidth {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            budget: limit as i64,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the connection is throttled at all
+    pub fn is_limited(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// Bytes per second sent over the last second
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Decide whether a message of some priority goes out now
+    pub fn admit(&mut self, priority: Priority) -> Verdict {
+        self.refill();
+
+        if !self.is_limited() || self.budget > 0 || priority == Priority::Critical {
+            return Verdict::Send;
+        }
+
+        match priority {
+            Priority::Low => Verdict::Drop,
+            _ => Verdict::Defer,
+        }
+    }
+
+    /// Account for bytes written to the connection
+    pub fn record(&mut self, bytes: usize) {
+        let now = Instant::now();
+        let window = self.window.get_or_insert(now);
+
+        if now.duration_since(*window) >= RATE_WINDOW {
+            self.rate = self.window_bytes * 1000 / now.duration_since(*window).as_millis() as u64;
+            self.window_bytes = 0;
+            *window = now;
+        }
+
+        self.window_bytes += bytes as u64;
+        self.total += bytes as u64;
+
+        if self.is_limited() {
+            self.budget -= bytes as i64;
+        }
+    }
+
+    fn refill(&mut self) {
+        if !self.is_limited() {
+            return;
+        }
+
+        let now = Instant::now();
+        let refilled = self.refilled.get_or_insert(now);
+        let elapsed = now.duration_since(*refilled).as_millis() as u64;
+        let earned = self.limit * elapsed / 1000;
+
+        // only move the clock on by whole bytes earned, so frequent calls don't lose any
+        if earned > 0 {
+            self.budget = (self.budget + earned as i64).min(self.limit as i64);
+            *refilled = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttle() {
+        let mut unlimited = Bandwidth::new(0);
+        unlimited.record(1 << 20);
+        assert_eq!(unlimited.admit(Priority::Low), Verdict::Send);
+
+        let mut bandwidth = Bandwidth::new(1000);
+        assert_eq!(bandwidth.admit(Priority::Low), Verdict::Send);
+
+        bandwidth.record(1500);
+        assert_eq!(bandwidth.admit(Priority::Low), Verdict::Drop);
+        assert_eq!(bandwidth.admit(Priority::Normal), Verdict::Defer);
+        assert_eq!(bandwidth.admit(Priority::Critical), Verdict::Send);
+        assert_eq!(bandwidth.total, 1500);
+
+        let of_type = |r#type: MessageType| messages::Message {
+            r#type: r#type as i32,
+            ..Default::default()
+        };
+
+        assert_eq!(priority_of(&of_type(MessageType::Time)), Priority::Low);
+        assert_eq!(
+            priority_of(&of_type(MessageType::Update)),
+            Priority::Critical
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::{thread, time::Duration};
+
+use actix::prelude::*;
+
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+
+use super::{
+    message::{self, JoinResult, JoinWorld, LeaveWorld, PlayerMessage},
+    models::messages,
+    server::WsServer,
+};
+
+/// Entry point for running the server without any sockets
+pub struct Server;
+
+impl Server {
+    /// Run the worlds of `worlds.json` on a background thread and join one of them in-process
+    ///
+    /// Messages go over channels instead of a WebSocket, so a native single-player client gets
+    /// the full world, physics and generation without a network stack. The server stops when
+    /// the returned client is dropped.
+    pub fn run_embedded(world_name: &str, player_name: Option<String>) -> EmbeddedClient {
+        let (setup_sender, setup_receiver) = bounded(1);
+        let world_name = world_name.to_owned();
+
+        thread::spawn(move || {
+            let mut runner = System::new("minejs-embedded");
+
+            let (sender, receiver) = unbounded();
+            let session = EmbeddedSession { sender }.start();
+            let server = WsServer::from_registry();
+
+            let join = runner
+                .block_on(server.send(JoinWorld {
+                    world_name: world_name.to_owned(),
+                    player_name,
+                    view_radius: None,
+                    locale: None,
+                    transfer: None,
+                    address: None,
+                    resume: None,
+                    player_addr: session.recipient(),
+                }))
+                .expect("Embedded server stopped before joining.");
+
+            setup_sender
+                .send((System::current(), server, join, receiver, world_name))
+                .unwrap();
+
+            runner.run().expect("Embedded server crashed.");
+        });
+
+        let (system, server, join, receiver, world_name) = setup_receiver
+            .recv()
+            .expect("Embedded server failed to start.");
+
+        EmbeddedClient {
+            join,
+            world_name,
+            system,
+            server,
+            receiver,
+        }
+    }
+}
+
+/// Session of an embedded client, forwarding what the world sends it onto a channel
+struct EmbeddedSession {
+    sender: Sender<messages::Message>,
+}
+
+impl Actor for EmbeddedSession {
+    type Context = Context<Self>;
+}
+
+impl Handler<message::Message> for EmbeddedSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: message::Message, ctx: &mut Self::Context) {
+        // the client is gone, which the world finds out on its next send
+        if self.sender.send(msg.0).is_err() {
+            ctx.stop();
+        }
+    }
+}
+
+/// A player joined to an in-process server
+pub struct EmbeddedClient {
+    /// What a WebSocket client gets in its init message
+    pub join: JoinResult,
+    world_name: String,
+    system: System,
+    server: Addr<WsServer>,
+    receiver: Receiver<messages::Message>,
+}
+
+impl EmbeddedClient {
+    /// Send a message to the world, as a WebSocket client would
+    pub fn send(&self, message: messages::Message) {
+        self.server.do_send(PlayerMessage {
+            player_id: self.join.id,
+            world_name: self.world_name.to_owned(),
+            raw: message,
+        });
+    }
+
+    /// Take the next message from the world, if any arrived
+    pub fn try_recv(&self) -> Option<messages::Message> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Wait up to `timeout` for the next message from the world
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<messages::Message> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+}
+
+impl Drop for EmbeddedClient {
+    fn drop(&mut self) {
+        self.server.do_send(LeaveWorld {
+            world_name: self.world_name.to_owned(),
+            player_id: self.join.id,
+            resumable: false,
+        });
+
+        self.system.stop();
+    }
+}
@@ -0,0 +1,264 @@
+use std::error::Error;
+
+use super::models::{decode_message, encode_message, messages};
+
+/// Magic bytes at the start of a raw geometry blob
+pub const RAW_GEOMETRY_MAGIC: &[u8; 4] = b"MJSG";
+
+/// Wire formats a connection can negotiate at handshake with the `format` query
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WireFormat {
+    /// Compact binary encoding, the default
+    #[default]
+    Protobuf,
+    /// Self-describing encoding sent as text frames, meant for debugging with
+    /// browser devtools or `websocat`
+    Json,
+}
+
+impl WireFormat {
+    /// Parse a wire format from its handshake name
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "protobuf" => Some(WireFormat::Protobuf),
+            "json" => Some(WireFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Encode message into a buffer of this format
+    pub fn encode(&self, message: &messages::Message) -> Vec<u8> {
+        match self {
+            WireFormat::Protobuf => encode_message(message),
+            WireFormat::Json => serde_json::to_vec(message).unwrap(),
+        }
+    }
+
+    /// Decode a buffer of this format into message
+    pub fn decode(&self, buf: &[u8]) -> Result<messages::Message, Box<dyn Error>> {
+        match self {
+            WireFormat::Protobuf => Ok(decode_message(buf)?),
+            WireFormat::Json => Ok(serde_json::from_slice(buf)?),
+        }
+    }
+}
+
+fn write_f32s(blob: &mut Vec<u8>, values: &[f32]) {
+    values
+        .iter()
+        .for_each(|v| blob.extend_from_slice(&v.to_le_bytes()));
+}
+
+fn write_i32s(blob: &mut Vec<u8>, values: &[i32]) {
+    values
+        .iter()
+        .for_each(|v| blob.extend_from_slice(&v.to_le_bytes()));
+}
+
+/// Pack a geometry's arrays into its `raw` blob so clients can hand the buffer
+/// straight to typed arrays without a decode pass.
+///
+/// The blob starts with `RAW_GEOMETRY_MAGIC` followed by the u32 lengths of positions,
+/// uvs, aos, indices, lights and centroids, then each array in that order. Every
+/// section is 4-byte aligned.
+pub fn pack_geometry(geometry: &mut messages::Geometry) {
+    let messages::Geometry {
+        positions,
+        uvs,
+        aos,
+        indices,
+        lights,
+        centroids,
+        raw,
+    } = geometry;
+
+    let lengths = [
+        positions.len(),
+        uvs.len(),
+        aos.len(),
+        indices.len(),
+        lights.len(),
+        centroids.len(),
+    ];
+
+    let mut blob = Vec::with_capacity(4 + lengths.len() * 4 + lengths.iter().sum::<usize>() * 4);
+    blob.extend_from_slice(RAW_GEOMETRY_MAGIC);
+    lengths
+        .iter()
+        .for_each(|len| blob.extend_from_slice(&(*len as u32).to_le_bytes()));
+
+    write_f32s(&mut blob, positions);
+    write_f32s(&mut blob, uvs);
+    write_i32s(&mut blob, aos);
+    write_i32s(&mut blob, indices);
+    write_i32s(&mut blob, lights);
+    write_f32s(&mut blob, centroids);
+
+    positions.clear();
+    uvs.clear();
+    aos.clear();
+    indices.clear();
+    lights.clear();
+    centroids.clear();
+
+    *raw = blob;
+}
+
+/// Pack every chunk geometry of a message into raw blobs
+pub fn pack_chunk_geometries(message: &mut messages::Message) {
+    message
+        .chunks
+        .iter_mut()
+        .flat_map(|chunk| chunk.meshes.iter_mut())
+        .flat_map(|mesh| {
+            vec![
+                mesh.opaque.as_mut(),
+                mesh.transparent.as_mut(),
+                mesh.translucent.as_mut(),
+            ]
+        })
+        .flatten()
+        .for_each(pack_geometry);
+}
+
+/// Width of the cells packed positions are relative to, in world units
+pub const TRANSFORM_CELL_SIZE: f32 = 256.0;
+
+/// Largest value of the three smallest components of a unit quaternion
+const MAX_QUATERNION_COMPONENT: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Pack a position into its cell and 16-bit offsets within it
+pub fn pack_position(px: f32, py: f32, pz: f32) -> messages::PackedTransform {
+    let mut packed = messages::PackedTransform::default();
+    let mut local = 0;
+
+    for (axis, (value, cell)) in [
+        (px, &mut packed.cx),
+        (py, &mut packed.cy),
+        (pz, &mut packed.cz),
+    ]
+    .iter_mut()
+    .enumerate()
+    {
+        let origin = (*value / TRANSFORM_CELL_SIZE).floor();
+        let offset = ((*value - origin * TRANSFORM_CELL_SIZE) * 256.0).round() as u64;
+
+        **cell = origin as i32;
+        local |= offset.min(u16::MAX as u64) << (axis * 16);
+    }
+
+    packed.local = local;
+    packed
+}
+
+/// Get the position a packed transform holds
+pub fn unpack_position(packed: &messages::PackedTransform) -> [f32; 3] {
+    let offset = |axis: u64| ((packed.local >> (axis * 16)) & 0xffff) as f32 / 256.0;
+
+    [
+        packed.cx as f32 * TRANSFORM_CELL_SIZE + offset(0),
+        packed.cy as f32 * TRANSFORM_CELL_SIZE + offset(1),
+        packed.cz as f32 * TRANSFORM_CELL_SIZE + offset(2),
+    ]
+}
+
+/// Smallest-three compression of a unit quaternion into 32 bits
+///
+/// The largest component is dropped, as it can be recomputed from the others, and its sign
+/// flipped positive by negating the whole quaternion, which is the same rotation.
+pub fn pack_quaternion(q: [f32; 4]) -> u32 {
+    let largest = (0..4)
+        .max_by(|&a, &b| q[a].abs().partial_cmp(&q[b].abs()).unwrap())
+        .unwrap();
+    let sign = if q[largest] < 0.0 { -1.0 } else { 1.0 };
+
+    (0..4)
+        .filter(|&i| i != largest)
+        .fold(largest as u32, |packed, i| {
+            let normalized = (q[i] * sign / MAX_QUATERNION_COMPONENT).clamp(-1.0, 1.0);
+            (packed << 10) | ((normalized * 0.5 + 0.5) * 1023.0).round() as u32
+        })
+}
+
+/// Recover a unit quaternion from its smallest-three compression
+pub fn unpack_quaternion(packed: u32) -> [f32; 4] {
+    let largest = (packed >> 30) as usize;
+    let mut q = [0.0; 4];
+
+    let mut shift = 30;
+    for (i, component) in q.iter_mut().enumerate() {
+        if i == largest {
+            continue;
+        }
+
+        shift -= 10;
+        let bits = (packed >> shift) & 0x3ff;
+        *component = (bits as f32 / 1023.0 * 2.0 - 1.0) * MAX_QUATERNION_COMPONENT;
+    }
+
+    let rest = q.iter().map(|c| c * c).sum::<f32>();
+    q[largest] = (1.0 - rest).max(0.0).sqrt();
+
+    q
+}
+
+/// Replace the positions and orientations of a message's peers and entities with their
+/// packed transforms
+pub fn pack_transforms(message: &mut messages::Message) {
+    for peer in message.peers.iter_mut() {
+        let mut packed = pack_position(peer.px, peer.py, peer.pz);
+        packed.rotation = pack_quaternion([peer.qx, peer.qy, peer.qz, peer.qw]);
+        peer.packed = Some(packed);
+
+        peer.px = 0.0;
+        peer.py = 0.0;
+        peer.pz = 0.0;
+        peer.qx = 0.0;
+        peer.qy = 0.0;
+        peer.qz = 0.0;
+        peer.qw = 0.0;
+    }
+
+    for entity in message.entities.iter_mut() {
+        entity.packed = Some(pack_position(entity.px, entity.py, entity.pz));
+
+        entity.px = 0.0;
+        entity.py = 0.0;
+        entity.pz = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms() {
+        let packed = pack_position(-12.3, 70.25, 300.5);
+        assert_eq!((packed.cx, packed.cy, packed.cz), (-1, 0, 1));
+
+        let [x, y, z] = unpack_position(&packed);
+        assert!((x + 12.3).abs() < 0.01);
+        assert!((y - 70.25).abs() < 0.01);
+        assert!((z - 300.5).abs() < 0.01);
+
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        for q in [
+            [0.0, 0.0, 0.0, 1.0],
+            [0.0, -half, 0.0, half],
+            [0.5, -0.5, 0.5, -0.5],
+        ]
+        .iter()
+        {
+            let unpacked = unpack_quaternion(pack_quaternion(*q));
+
+            // q and -q are the same rotation
+            let dot = q
+                .iter()
+                .zip(unpacked.iter())
+                .map(|(a, b)| a * b)
+                .sum::<f32>();
+            assert!(dot.abs() > 0.999, "{:?} unpacked as {:?}", q, unpacked);
+        }
+    }
+}
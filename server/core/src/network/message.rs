@@ -1,8 +1,15 @@
 use serde::{Deserialize, Serialize};
 
+use std::net::IpAddr;
+
 use actix::prelude::*;
 
-use crate::engine::entities::EntityPrototypes;
+use crate::engine::{
+    config::{Dimensions, PhysicsConfig},
+    entities::EntityPrototypes,
+    resource_pack::ResourcePack,
+    shards::ChunkHandoff,
+};
 
 use super::super::engine::registry::{Blocks, Ranges};
 
@@ -23,6 +30,22 @@ pub struct JoinResult {
     pub tick_speed: f32,
     pub spawn: [i32; 3],
     pub passables: Vec<u32>,
+    pub render_radius: i16,
+    pub skin_token: String,
+    pub locale: String,
+    pub resource_pack: Option<ResourcePack>,
+    /// Biome registry as JSON, indexed by the biome ids of chunks
+    pub biomes: String,
+    /// Secret to resume the session with after a brief disconnect
+    pub resume_token: String,
+    /// Whether an existing session was resumed, the client then keeps its chunks
+    pub resumed: bool,
+    /// Shape of the world's chunks, for the client to size its own by
+    pub dimensions: Dimensions,
+    /// Movement constants of the world, for the client to predict movement with
+    pub physics: PhysicsConfig,
+    /// Messages the resumed session missed, to send in order after the init message
+    pub replay: Vec<models::messages::Message>,
 }
 
 #[derive(Clone, Message)]
@@ -30,6 +53,14 @@ pub struct JoinResult {
 pub struct JoinWorld {
     pub world_name: String,
     pub player_name: Option<String>,
+    pub view_radius: Option<i16>,
+    pub locale: Option<String>,
+    /// Token handed out by the server the player transferred from, if any
+    pub transfer: Option<String>,
+    /// Real address of the client, behind any trusted proxies
+    pub address: Option<IpAddr>,
+    /// Token of a detached session to resume, if any
+    pub resume: Option<String>,
     pub player_addr: Recipient<Message>,
 }
 
@@ -38,6 +69,8 @@ pub struct JoinWorld {
 pub struct LeaveWorld {
     pub world_name: String,
     pub player_id: usize,
+    /// Whether the connection dropped rather than closed, so the session can be resumed
+    pub resumable: bool,
 }
 
 /* -------------------------------------------------------------------------- */
@@ -54,6 +87,35 @@ pub struct PlayerMessage {
     pub raw: models::messages::Message,
 }
 
+/// Skin uploaded over HTTP, authorized by the token the player received on join
+#[derive(Clone, Message)]
+#[rtype(result = "Result<String, String>")]
+pub struct SetSkin {
+    pub world_name: String,
+    pub player_id: usize,
+    pub token: String,
+    pub data: Vec<u8>,
+}
+
+/// Map tile of a world's chunk as a PNG image, none if it hasn't been drawn
+#[derive(Clone, Message)]
+#[rtype(result = "Option<Vec<u8>>")]
+pub struct GetMapTile {
+    pub world_name: String,
+    pub coords: [i32; 2],
+}
+
+/// Move a player to a world on another server, false if transfers aren't enabled
+#[derive(Clone, Message)]
+#[rtype(result = "bool")]
+pub struct TransferPlayer {
+    pub world_name: String,
+    pub player_id: usize,
+    pub host: String,
+    pub port: u32,
+    pub target_world: String,
+}
+
 #[derive(Clone, Message)]
 #[rtype(result = "()")]
 pub struct Noop;
@@ -72,6 +134,8 @@ pub struct SimpleWorldData {
     pub generation: String,
     pub description: String,
     pub players: usize,
+    /// Average round-trip time of all players in milliseconds
+    pub ping: u32,
 }
 
 #[derive(MessageResponse, Deserialize, Serialize, Debug)]
@@ -80,6 +144,7 @@ pub struct FullWorldData {
     pub chunk_size: usize,
     pub dimension: usize,
     pub max_height: u32,
+    pub min_height: i32,
     pub max_light_level: u32,
     pub time: f32,
     pub name: String,
@@ -93,12 +158,99 @@ pub struct FullWorldData {
     pub uv_side_count: u32,
     pub uv_texture_size: u32,
     pub packs: Vec<String>,
+    pub resource_pack: Option<ResourcePack>,
 }
 
 #[derive(Clone, Message)]
 #[rtype(result = "Vec<SimpleWorldData>")]
 pub struct ListWorlds;
 
+/* -------------------------------------------------------------------------- */
+/*                                Admin Messages                              */
+/* -------------------------------------------------------------------------- */
+/// A player as listed by the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerSummary {
+    pub id: usize,
+    pub name: Option<String>,
+    pub ping: u32,
+    pub position: Option<[f32; 3]>,
+    pub address: Option<IpAddr>,
+    pub detached: bool,
+}
+
+/// Health of a world as reported by the admin API, tick times in milliseconds
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorldMetrics {
+    pub tps: f32,
+    pub tick_p50: f32,
+    pub tick_p95: f32,
+    pub tick_p99: f32,
+    pub players: usize,
+    pub chunks: usize,
+    pub entities: usize,
+}
+
+/// Players of a world, none if there's no such world
+#[derive(Clone, Message)]
+#[rtype(result = "Option<Vec<PlayerSummary>>")]
+pub struct ListPlayers(pub String);
+
+#[derive(Clone, Message)]
+#[rtype(result = "Option<WorldMetrics>")]
+pub struct GetMetrics(pub String);
+
+/// Run a command in a world as the console, answered with what it replied
+#[derive(Clone, Message)]
+#[rtype(result = "Option<Vec<String>>")]
+pub struct RunCommand {
+    pub world_name: String,
+    pub command: String,
+}
+
+/// A message from a bridge's bot to show in a world's chat, authorized by the bridge's secret
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct BridgeMessage {
+    pub world_name: String,
+    pub secret: Option<String>,
+    pub author: String,
+    pub message: String,
+}
+
+/// Address of the shard a connection to a world should go to, none if it's this process
+#[derive(Clone, Message)]
+#[rtype(result = "Option<String>")]
+pub struct RouteConnection(pub String);
+
+/* -------------------------------------------------------------------------- */
+/*                              Sharding Messages                             */
+/* -------------------------------------------------------------------------- */
+#[derive(Clone, Message)]
+#[rtype(result = "Result<ChunkHandoff, String>")]
+pub struct HandoffChunk {
+    pub world_name: String,
+    pub secret: Option<String>,
+    pub coords: [i32; 2],
+}
+
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct ConfirmHandoff {
+    pub world_name: String,
+    pub secret: Option<String>,
+    pub coords: [i32; 2],
+}
+
+#[derive(Clone, Message)]
+#[rtype(result = "Result<(), String>")]
+pub struct AcceptHandoff {
+    pub world_name: String,
+    pub secret: Option<String>,
+    pub handoff: ChunkHandoff,
+}
+
 #[derive(Clone, Message)]
 #[rtype(result = "FullWorldData")]
 pub struct GetWorld(pub String);
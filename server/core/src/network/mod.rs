@@ -1,5 +1,22 @@
+#[cfg(feature = "server")]
+pub mod admin;
+#[cfg(feature = "server")]
+pub mod app;
+pub mod bandwidth;
+#[cfg(feature = "server")]
+pub mod embedded;
+pub mod format;
+#[cfg(feature = "server")]
 pub mod message;
 pub mod models;
+#[cfg(feature = "server")]
+pub mod proxy;
+pub mod queue;
+#[cfg(feature = "server")]
 pub mod routes;
+#[cfg(feature = "server")]
 pub mod server;
+#[cfg(feature = "server")]
 pub mod session;
+#[cfg(feature = "server")]
+pub mod tls;
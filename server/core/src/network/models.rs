@@ -1,10 +1,32 @@
 use prost::Message;
 
-use std::io::Cursor;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
 
-use super::super::engine::chunk::Meshes;
+use super::super::{
+    comp::effects::Effect,
+    engine::{
+        advancements::Advancement,
+        chunk::Meshes,
+        debug::DebugOverlay,
+        forms::{Form, FormElement},
+        holograms::Hologram,
+        hud::{BossBar, Title},
+        items::ItemStack,
+        map::MapTile,
+        trading::TradeOffer,
+    },
+};
 
-use server_common::{ndarray::Ndarray, vec::Vec3};
+use server_common::{
+    aabb::Aabb,
+    ndarray::Ndarray,
+    types::MeshType,
+    vec::{Vec2, Vec3},
+};
 
 /// Protobuf format for chunks
 #[derive(Debug)]
@@ -14,6 +36,8 @@ pub struct ChunkProtocol {
     pub meshes: Option<Vec<Meshes>>,
     pub voxels: Option<Ndarray<u32>>,
     pub lights: Option<Ndarray<u32>>,
+    pub biomes: Option<Ndarray<u32>>,
+    pub block_entities: Option<Vec<BlockEntityProtocol>>,
 }
 
 /// Protobuf format for peer updates
@@ -28,6 +52,8 @@ pub struct PeerProtocol {
     pub qy: f32,
     pub qz: f32,
     pub qw: f32,
+    pub metadata: HashMap<String, String>,
+    pub sequence: u32,
 }
 
 /// Protobuf format for entity updates
@@ -40,6 +66,37 @@ pub struct EntityProtocol {
     pub pz: f32,
     pub heading: Option<Vec3<f32>>,
     pub look_at: Option<Vec3<f32>>,
+    /// Id of the entity ridden, the position is then relative to it
+    pub mount: Option<String>,
+}
+
+/// Protobuf format for an entity mounting or dismounting another
+#[derive(Debug, Clone)]
+pub struct MountProtocol {
+    pub rider: String,
+    pub mount: String,
+    pub offset: Vec3<f32>,
+}
+
+/// Protobuf format for moving a player to another server
+#[derive(Debug, Clone)]
+pub struct TransferProtocol {
+    pub host: String,
+    pub port: u32,
+    pub token: String,
+}
+
+/// Protobuf format for player list entries
+#[derive(Debug, Clone)]
+pub struct PlayerListProtocol {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+    pub ping: u32,
+    pub order: i32,
+    pub removed: bool,
+    pub team: String,
+    pub color: String,
 }
 
 /// Protobuf format for voxel updates
@@ -53,12 +110,58 @@ pub struct UpdateProtocol {
     pub y_rotation: u32,
 }
 
+/// Protobuf format for block entities, `data` is JSON and empty on removal
+#[derive(Debug, Clone)]
+pub struct BlockEntityProtocol {
+    pub voxel: Vec3<i32>,
+    pub kind: String,
+    pub data: String,
+}
+
+impl BlockEntityProtocol {
+    fn to_message(&self) -> messages::BlockEntity {
+        let Vec3(vx, vy, vz) = self.voxel;
+
+        messages::BlockEntity {
+            vx,
+            vy,
+            vz,
+            kind: self.kind.to_owned(),
+            data: self.data.to_owned(),
+        }
+    }
+}
+
+/// Protobuf format for an opened container
+#[derive(Debug, Clone)]
+pub struct ContainerProtocol {
+    pub id: u32,
+    pub kind: String,
+    pub voxel: Vec3<i32>,
+    pub slots: Vec<Option<ItemStack>>,
+    pub cursor: Option<ItemStack>,
+    /// Offers of a trader, empty for other containers
+    pub offers: Vec<TradeOffer>,
+    pub selected: Option<usize>,
+}
+
+fn create_slot(stack: &Option<ItemStack>) -> messages::Slot {
+    stack
+        .as_ref()
+        .map_or_else(Default::default, |stack| messages::Slot {
+            id: stack.id,
+            count: stack.count,
+        })
+}
+
 /// Protobuf format for chat messages
 #[derive(Debug)]
 pub struct ChatProtocol {
     pub r#type: messages::chat_message::Type,
     pub sender: String,
     pub body: String,
+    pub key: String,
+    pub args: Vec<String>,
 }
 
 /// Protobuf format for an entire message
@@ -72,6 +175,21 @@ pub struct MessageComponents {
     pub entities: Option<Vec<EntityProtocol>>,
     pub chunks: Option<Vec<ChunkProtocol>>,
     pub updates: Option<Vec<UpdateProtocol>>,
+    pub unloads: Option<Vec<Vec2<i32>>>,
+    pub players: Option<Vec<PlayerListProtocol>>,
+    pub block_entities: Option<Vec<BlockEntityProtocol>>,
+    pub container: Option<ContainerProtocol>,
+    pub effects: Option<Vec<Effect>>,
+    pub advancement: Option<Advancement>,
+    pub mount: Option<MountProtocol>,
+    pub transfer: Option<TransferProtocol>,
+    pub holograms: Option<Vec<Hologram>>,
+    /// Form to show, along with its id
+    pub form: Option<(u32, Form)>,
+    pub boss_bar: Option<BossBar>,
+    pub title: Option<Title>,
+    pub map_tiles: Option<Vec<MapTile>>,
+    pub debug: Option<DebugOverlay>,
 }
 
 impl MessageComponents {
@@ -85,6 +203,20 @@ impl MessageComponents {
             entities: None,
             chunks: None,
             updates: None,
+            unloads: None,
+            players: None,
+            block_entities: None,
+            container: None,
+            effects: None,
+            advancement: None,
+            mount: None,
+            transfer: None,
+            holograms: None,
+            form: None,
+            boss_bar: None,
+            title: None,
+            map_tiles: None,
+            debug: None,
         }
     }
 }
@@ -106,6 +238,19 @@ pub fn create_of_type(r#type: messages::message::Type) -> messages::Message {
     create_message(MessageComponents::default_for(r#type))
 }
 
+/// Convert mesh data into protobuf geometry
+fn create_geometry(mesh: &MeshType) -> messages::Geometry {
+    messages::Geometry {
+        aos: mesh.aos.to_owned(),
+        indices: mesh.indices.to_owned(),
+        positions: mesh.positions.to_owned(),
+        lights: mesh.lights.to_owned(),
+        uvs: mesh.uvs.to_owned(),
+        centroids: mesh.centroids.to_owned(),
+        raw: vec![],
+    }
+}
+
 /// Create a message with protobuf components
 pub fn create_message(components: MessageComponents) -> messages::Message {
     let mut message = messages::Message {
@@ -126,6 +271,8 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
             r#type: chat_message.r#type as i32,
             body: chat_message.body,
             sender: chat_message.sender,
+            key: chat_message.key,
+            args: chat_message.args,
         });
     }
 
@@ -142,6 +289,9 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
                 qy: peer.qy,
                 qz: peer.qz,
                 qw: peer.qw,
+                metadata: peer.metadata,
+                sequence: peer.sequence,
+                packed: None,
             })
             .collect()
     }
@@ -165,6 +315,8 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
                 } else {
                     vec![]
                 },
+                mount: entity.mount.unwrap_or_default(),
+                packed: None,
             })
             .collect()
     }
@@ -176,27 +328,11 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
                 meshes: if let Some(meshes) = chunk.meshes {
                     meshes
                         .iter()
-                        .map(|mesh| {
-                            let opaque = mesh.opaque.as_ref();
-                            let transparent = mesh.transparent.as_ref();
-
-                            messages::Mesh {
-                                sub_chunk: mesh.sub_chunk,
-                                opaque: opaque.map(|opaque| messages::Geometry {
-                                    aos: opaque.aos.to_owned(),
-                                    indices: opaque.indices.to_owned(),
-                                    positions: opaque.positions.to_owned(),
-                                    lights: opaque.lights.to_owned(),
-                                    uvs: opaque.uvs.to_owned(),
-                                }),
-                                transparent: transparent.map(|transparent| messages::Geometry {
-                                    aos: transparent.aos.to_owned(),
-                                    indices: transparent.indices.to_owned(),
-                                    positions: transparent.positions.to_owned(),
-                                    lights: transparent.lights.to_owned(),
-                                    uvs: transparent.uvs.to_owned(),
-                                }),
-                            }
+                        .map(|mesh| messages::Mesh {
+                            sub_chunk: mesh.sub_chunk,
+                            opaque: mesh.opaque.as_ref().map(create_geometry),
+                            transparent: mesh.transparent.as_ref().map(create_geometry),
+                            translucent: mesh.translucent.as_ref().map(create_geometry),
                         })
                         .collect()
                 } else {
@@ -212,6 +348,16 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
                 } else {
                     Vec::<u32>::new()
                 },
+                biomes: if let Some(b) = chunk.biomes {
+                    b.data
+                } else {
+                    Vec::<u32>::new()
+                },
+                block_entities: if let Some(b) = chunk.block_entities {
+                    b.iter().map(BlockEntityProtocol::to_message).collect()
+                } else {
+                    vec![]
+                },
                 x: chunk.x,
                 z: chunk.z,
             })
@@ -228,6 +374,247 @@ pub fn create_message(components: MessageComponents) -> messages::Message {
                 vz: update.vz,
                 rotation: update.rotation,
                 y_rotation: update.y_rotation,
+                sequence: 0,
+            })
+            .collect()
+    }
+
+    if let Some(holograms) = components.holograms {
+        message.holograms = holograms
+            .into_iter()
+            .map(|hologram| {
+                let Vec3(px, py, pz) = hologram.position;
+
+                messages::Hologram {
+                    id: hologram.id,
+                    px,
+                    py,
+                    pz,
+                    lines: hologram.lines,
+                }
+            })
+            .collect()
+    }
+
+    if let Some((id, form)) = components.form {
+        use messages::form::{element::Type as ElementType, Element};
+
+        message.form = Some(messages::Form {
+            id,
+            title: form.title,
+            elements: form
+                .elements
+                .into_iter()
+                .map(|element| match element {
+                    FormElement::Button { label, .. } => Element {
+                        r#type: ElementType::Button as i32,
+                        label,
+                        ..Default::default()
+                    },
+                    FormElement::Input {
+                        label,
+                        placeholder,
+                        value,
+                    } => Element {
+                        r#type: ElementType::Input as i32,
+                        label,
+                        placeholder,
+                        value,
+                        ..Default::default()
+                    },
+                    FormElement::Dropdown {
+                        label,
+                        options,
+                        selected,
+                    } => Element {
+                        r#type: ElementType::Dropdown as i32,
+                        label,
+                        options,
+                        selected: selected as u32,
+                        ..Default::default()
+                    },
+                })
+                .collect(),
+        });
+    }
+
+    if let Some(bar) = components.boss_bar {
+        message.boss_bar = Some(messages::BossBar {
+            id: bar.id,
+            title: bar.title,
+            progress: bar.progress,
+            color: bar.color.name().to_owned(),
+        });
+    }
+
+    if let Some(title) = components.title {
+        message.title = Some(messages::Title {
+            title: title.title,
+            subtitle: title.subtitle,
+            fade_in: title.fade_in,
+            stay: title.stay,
+            fade_out: title.fade_out,
+        });
+    }
+
+    if let Some(tiles) = components.map_tiles {
+        message.map_tiles = tiles
+            .into_iter()
+            .map(|tile| messages::MapTile {
+                cx: tile.coords.0,
+                cz: tile.coords.1,
+                size: tile.size as u32,
+                colors: tile.colors,
+            })
+            .collect()
+    }
+
+    if let Some(overlay) = components.debug {
+        message.debug = Some(messages::DebugOverlay {
+            chunks: overlay
+                .chunks
+                .into_iter()
+                .map(|border| messages::debug_overlay::ChunkBorder {
+                    cx: border.coords.0,
+                    cz: border.coords.1,
+                    needs_propagation: border.needs_propagation,
+                    dirty: border.is_dirty,
+                })
+                .collect(),
+            lights: overlay
+                .lights
+                .into_iter()
+                .map(|light| messages::debug_overlay::Light {
+                    vx: light.voxel.0,
+                    vy: light.voxel.1,
+                    vz: light.voxel.2,
+                    sunlight: light.sunlight,
+                    red: light.red,
+                    green: light.green,
+                    blue: light.blue,
+                })
+                .collect(),
+            boxes: overlay
+                .boxes
+                .into_iter()
+                .map(|hitbox| {
+                    let Aabb { base, max, .. } = hitbox.aabb;
+                    messages::debug_overlay::Hitbox {
+                        id: hitbox.id,
+                        min: vec![base.0, base.1, base.2],
+                        max: vec![max.0, max.1, max.2],
+                    }
+                })
+                .collect(),
+            paths: overlay
+                .paths
+                .into_iter()
+                .map(|path| messages::debug_overlay::Path {
+                    id: path.id,
+                    nodes: path
+                        .nodes
+                        .iter()
+                        .flat_map(|node| vec![node.0, node.1, node.2])
+                        .collect(),
+                })
+                .collect(),
+        });
+    }
+
+    if let Some(block_entities) = components.block_entities {
+        message.block_entities = block_entities
+            .iter()
+            .map(BlockEntityProtocol::to_message)
+            .collect()
+    }
+
+    if let Some(container) = components.container {
+        let Vec3(vx, vy, vz) = container.voxel;
+
+        message.container = Some(messages::Container {
+            id: container.id,
+            kind: container.kind,
+            vx,
+            vy,
+            vz,
+            slots: container.slots.iter().map(create_slot).collect(),
+            cursor: Some(create_slot(&container.cursor)),
+            offers: container
+                .offers
+                .iter()
+                .map(|offer| messages::TradeOffer {
+                    inputs: offer
+                        .inputs
+                        .iter()
+                        .map(|input| create_slot(&Some(input.to_owned())))
+                        .collect(),
+                    output: Some(create_slot(&Some(offer.output.to_owned()))),
+                    uses: offer.uses,
+                    max_uses: offer.max_uses,
+                })
+                .collect(),
+            selected: container.selected.map_or(-1, |index| index as i32),
+        });
+    }
+
+    if let Some(effects) = components.effects {
+        message.effects = effects
+            .into_iter()
+            .map(|effect| messages::Effect {
+                r#type: effect.kind.name().to_owned(),
+                amplifier: effect.amplifier,
+                remaining: effect.remaining,
+            })
+            .collect()
+    }
+
+    if let Some(advancement) = components.advancement {
+        message.advancement = Some(messages::Advancement {
+            id: advancement.id,
+            title: advancement.title,
+            description: advancement.description,
+        });
+    }
+
+    if let Some(mount) = components.mount {
+        let Vec3(ox, oy, oz) = mount.offset;
+
+        message.mount = Some(messages::Mount {
+            rider: mount.rider,
+            mount: mount.mount,
+            ox,
+            oy,
+            oz,
+        });
+    }
+
+    if let Some(transfer) = components.transfer {
+        message.transfer = Some(messages::Transfer {
+            host: transfer.host,
+            port: transfer.port,
+            token: transfer.token,
+        });
+    }
+
+    if let Some(unloads) = components.unloads {
+        message.unloads = unloads
+            .into_iter()
+            .map(|Vec2(x, z)| messages::UnloadChunk { x, z })
+            .collect()
+    }
+
+    if let Some(players) = components.players {
+        message.players = players
+            .into_iter()
+            .map(|player| messages::PlayerInfo {
+                id: player.id,
+                name: player.name,
+                display_name: player.display_name,
+                ping: player.ping,
+                order: player.order,
+                removed: player.removed,
+                team: player.team,
+                color: player.color,
             })
             .collect()
     }
@@ -248,6 +635,28 @@ pub fn create_chat_message(
         r#type: chat_type,
         sender: sender.to_owned(),
         body: body.to_owned(),
+        key: String::new(),
+        args: vec![],
+    });
+
+    create_message(components)
+}
+
+/// Create a broadcast-able message from a translation key, localized for each receiver
+pub fn create_translated_message(
+    message_type: messages::message::Type,
+    chat_type: messages::chat_message::Type,
+    key: &str,
+    args: &[&str],
+) -> messages::Message {
+    let mut components = MessageComponents::default_for(message_type);
+
+    components.message = Some(ChatProtocol {
+        r#type: chat_type,
+        sender: String::new(),
+        body: key.to_owned(),
+        key: key.to_owned(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
     });
 
     create_message(components)
@@ -255,16 +664,245 @@ pub fn create_chat_message(
 
 /// Encode message into protobuf buffer
 pub fn encode_message(message: &messages::Message) -> Vec<u8> {
-    let mut buf = Vec::new();
-    buf.reserve(message.encoded_len());
+    let mut buf = Vec::with_capacity(message.encoded_len());
     message.encode(&mut buf).unwrap();
     buf
 }
 
 /// Decode protobuf buffer into message
 pub fn decode_message(buf: &[u8]) -> Result<messages::Message, prost::DecodeError> {
-    messages::Message::decode(&mut Cursor::new(buf))
+    messages::Message::decode(buf)
+}
+
+/// Encoded messages larger than this are split into fragments
+pub const MAX_FRAGMENT_SIZE: usize = 64 * 1024;
+
+/// Maximum amount of fragments a single message can be split into
+const MAX_FRAGMENTS: u32 = 1024;
+
+/// Most fragmented messages a connection can be sending at once
+const MAX_PENDING_MESSAGES: usize = 16;
+
+/// Most bytes of fragments a connection can have buffered across its pending messages
+const MAX_PENDING_BYTES: usize = 16 * 1024 * 1024;
+
+/// How long a fragmented message can take to arrive before its fragments are dropped
+const FRAGMENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+static FRAGMENT_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Encode message into protobuf buffers, splitting it into `FRAGMENT` messages
+/// if the encoded message is larger than `max_size`
+pub fn encode_fragments(message: &messages::Message, max_size: usize) -> Vec<Vec<u8>> {
+    let encoded = encode_message(message);
+
+    if encoded.len() <= max_size {
+        return vec![encoded];
+    }
+
+    let id = FRAGMENT_ID.fetch_add(1, Ordering::Relaxed);
+    let total = encoded.len().div_ceil(max_size) as u32;
+
+    encoded
+        .chunks(max_size)
+        .enumerate()
+        .map(|(index, data)| {
+            let mut fragment = create_of_type(MessageType::Fragment);
+            fragment.fragment = Some(messages::Fragment {
+                id,
+                index: index as u32,
+                total,
+                data: data.to_vec(),
+            });
+
+            encode_message(&fragment)
+        })
+        .collect()
+}
+
+/// A fragmented message still arriving
+#[derive(Debug)]
+struct PendingMessage {
+    parts: Vec<Option<Vec<u8>>>,
+    started: Instant,
+}
+
+/// Reassembles `FRAGMENT` messages back into the original messages
+///
+/// A connection can only have so many messages and bytes pending, and messages that take too
+/// long to arrive are dropped, so that fragments never completed don't pile up.
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    pending: HashMap<u32, PendingMessage>,
+    /// Bytes of fragments buffered across the pending messages
+    buffered: usize,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode protobuf buffer into message
+    ///
+    /// Returns `None` while a fragmented message is still incomplete.
+    pub fn decode(&mut self, buf: &[u8]) -> Result<Option<messages::Message>, prost::DecodeError> {
+        self.decode_at(buf, Instant::now())
+    }
+
+    fn decode_at(
+        &mut self,
+        buf: &[u8],
+        now: Instant,
+    ) -> Result<Option<messages::Message>, prost::DecodeError> {
+        let mut message = decode_message(buf)?;
+
+        if message.r#type() != MessageType::Fragment {
+            return Ok(Some(message));
+        }
+
+        let fragment = match message.fragment.take() {
+            Some(fragment) => fragment,
+            None => return Err(prost::DecodeError::new("missing fragment")),
+        };
+
+        if fragment.total == 0
+            || fragment.total > MAX_FRAGMENTS
+            || fragment.index >= fragment.total
+            || fragment.data.len() > MAX_FRAGMENT_SIZE
+        {
+            return Err(prost::DecodeError::new("invalid fragment"));
+        }
+
+        self.expire(now);
+
+        if !self.pending.contains_key(&fragment.id) && self.pending.len() >= MAX_PENDING_MESSAGES {
+            return Err(prost::DecodeError::new("too many fragmented messages"));
+        }
+
+        let pending = self
+            .pending
+            .entry(fragment.id)
+            .or_insert_with(|| PendingMessage {
+                parts: vec![None; fragment.total as usize],
+                started: now,
+            });
+
+        if pending.parts.len() != fragment.total as usize {
+            return Err(prost::DecodeError::new("mismatched fragment total"));
+        }
+
+        let part = &mut pending.parts[fragment.index as usize];
+        let replaced = part.as_ref().map_or(0, Vec::len);
+
+        if self.buffered - replaced + fragment.data.len() > MAX_PENDING_BYTES {
+            return Err(prost::DecodeError::new("too many fragment bytes"));
+        }
+
+        self.buffered = self.buffered - replaced + fragment.data.len();
+        *part = Some(fragment.data);
+
+        if pending.parts.iter().any(Option::is_none) {
+            return Ok(None);
+        }
+
+        let buf = self
+            .pending
+            .remove(&fragment.id)
+            .unwrap()
+            .parts
+            .into_iter()
+            .flatten()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        self.buffered -= buf.len();
+
+        decode_message(&buf).map(Some)
+    }
+
+    /// Drop the messages that took too long to arrive
+    fn expire(&mut self, now: Instant) {
+        let buffered = &mut self.buffered;
+
+        self.pending.retain(|_, pending| {
+            if now.duration_since(pending.started) < FRAGMENT_TIMEOUT {
+                return true;
+            }
+
+            *buffered -= pending.parts.iter().flatten().map(Vec::len).sum::<usize>();
+            false
+        });
+    }
 }
 
 pub type ChatType = messages::chat_message::Type;
 pub type MessageType = messages::message::Type;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fragments_reassemble() {
+        let mut message = create_of_type(MessageType::Info);
+        message.text = "x".repeat(1000);
+
+        let fragments = encode_fragments(&message, 128);
+        assert!(fragments.len() > 1);
+
+        let mut reassembler = Reassembler::new();
+        // fragments may arrive in any order
+        for fragment in fragments[1..].iter() {
+            assert!(reassembler.decode(fragment).unwrap().is_none());
+        }
+
+        let decoded = reassembler.decode(&fragments[0]).unwrap().unwrap();
+        assert_eq!(decoded, message);
+        assert!(reassembler.pending.is_empty());
+
+        // small messages pass through untouched
+        let small = create_of_type(MessageType::Info);
+        assert_eq!(encode_fragments(&small, 128).len(), 1);
+        assert_eq!(
+            reassembler.decode(&encode_message(&small)).unwrap(),
+            Some(small)
+        );
+    }
+
+    #[test]
+    fn fragments_bounded() {
+        let mut message = create_of_type(MessageType::Info);
+        message.text = "x".repeat(1000);
+
+        let start = Instant::now();
+        let mut reassembler = Reassembler::new();
+
+        // messages never completed count against the connection until they expire
+        for _ in 0..MAX_PENDING_MESSAGES {
+            let fragments = encode_fragments(&message, 128);
+            assert!(reassembler
+                .decode_at(&fragments[0], start)
+                .unwrap()
+                .is_none());
+        }
+
+        let fragments = encode_fragments(&message, 128);
+        assert!(reassembler.decode_at(&fragments[0], start).is_err());
+        assert!(reassembler.buffered > 0);
+
+        let later = start + FRAGMENT_TIMEOUT;
+        assert!(reassembler
+            .decode_at(&fragments[0], later)
+            .unwrap()
+            .is_none());
+        assert_eq!(reassembler.pending.len(), 1);
+        assert_eq!(reassembler.buffered, 128);
+
+        for fragment in fragments[1..].iter() {
+            reassembler.decode_at(fragment, later).unwrap();
+        }
+        assert!(reassembler.pending.is_empty());
+        assert_eq!(reassembler.buffered, 0);
+    }
+}
@@ -0,0 +1,343 @@
+use std::{
+    io::{self, Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use actix_web::HttpRequest;
+
+use hashbrown::HashMap;
+
+use log::{info, warn};
+
+use serde::Deserialize;
+
+/// Signature starting every PROXY protocol v2 header
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\x00\r\nQUIT\n";
+
+/// Longest a PROXY protocol v1 header can be, including the trailing CRLF
+const V1_MAX_LENGTH: usize = 107;
+
+/// How long a relayed connection has to send its PROXY protocol header
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Most connections relayed at once, past which new ones are dropped
+const MAX_RELAYED_CONNECTIONS: usize = 1024;
+
+/// How the real addresses of clients behind a load balancer are found
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    /// Peers trusted to report client addresses in `X-Forwarded-For`
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+    /// Address accepting connections prefixed with a HAProxy PROXY protocol header, from the
+    /// trusted proxies only
+    #[serde(default)]
+    pub proxy_protocol: Option<String>,
+}
+
+/// Result of parsing the start of a connection for a PROXY protocol header
+#[derive(Debug, PartialEq)]
+pub enum ProxyHeader {
+    /// More bytes are needed to tell
+    Incomplete,
+    /// Header length in bytes, and the client's address unless the connection is local
+    Complete(usize, Option<SocketAddr>),
+    Invalid,
+}
+
+/// Parse a PROXY protocol v1 or v2 header at the start of a connection
+pub fn parse_proxy_header(buf: &[u8]) -> ProxyHeader {
+    if buf.len() < V2_SIGNATURE.len() {
+        return if V2_SIGNATURE.starts_with(buf) || b"PROXY ".starts_with(&buf[..buf.len().min(6)]) {
+            ProxyHeader::Incomplete
+        } else {
+            ProxyHeader::Invalid
+        };
+    }
+
+    if buf.starts_with(V2_SIGNATURE) {
+        parse_v2(buf)
+    } else if buf.starts_with(b"PROXY ") {
+        parse_v1(buf)
+    } else {
+        ProxyHeader::Invalid
+    }
+}
+
+/// `PROXY TCP4 192.168.0.1 192.168.0.11 56324 443\r\n`
+fn parse_v1(buf: &[u8]) -> ProxyHeader {
+    let end = match buf.windows(2).position(|w| w == b"\r\n") {
+        Some(end) => end,
+        None if buf.len() < V1_MAX_LENGTH => return ProxyHeader::Incomplete,
+        None => return ProxyHeader::Invalid,
+    };
+
+    let line = match std::str::from_utf8(&buf[..end]) {
+        Ok(line) => line,
+        Err(_) => return ProxyHeader::Invalid,
+    };
+    let parts = line.split(' ').collect::<Vec<_>>();
+
+    match parts.as_slice() {
+        ["PROXY", "UNKNOWN", ..] => ProxyHeader::Complete(end + 2, None),
+        ["PROXY", "TCP4", source, _, port, _] | ["PROXY", "TCP6", source, _, port, _] => {
+            match (source.parse::<IpAddr>(), port.parse::<u16>()) {
+                (Ok(ip), Ok(port)) => {
+                    ProxyHeader::Complete(end + 2, Some(SocketAddr::new(ip, port)))
+                }
+                _ => ProxyHeader::Invalid,
+            }
+        }
+        _ => ProxyHeader::Invalid,
+    }
+}
+
+fn parse_v2(buf: &[u8]) -> ProxyHeader {
+    if buf.len() < 16 {
+        return ProxyHeader::Incomplete;
+    }
+
+    let version_command = buf[12];
+    let family = buf[13];
+    let length = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+
+    if version_command >> 4 != 2 {
+        return ProxyHeader::Invalid;
+    }
+
+    if buf.len() < 16 + length {
+        return ProxyHeader::Incomplete;
+    }
+
+    let total = 16 + length;
+    let body = &buf[16..total];
+
+    // LOCAL connections, such as health checks, carry no address
+    if version_command & 0x0f == 0 {
+        return ProxyHeader::Complete(total, None);
+    }
+
+    match family >> 4 {
+        // AF_INET: source, destination, source port, destination port
+        1 if body.len() >= 12 => {
+            let ip = Ipv4Addr::new(body[0], body[1], body[2], body[3]);
+            let port = u16::from_be_bytes([body[8], body[9]]);
+            ProxyHeader::Complete(total, Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        2 if body.len() >= 36 => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(&body[..16]);
+            let port = u16::from_be_bytes([body[32], body[33]]);
+            ProxyHeader::Complete(
+                total,
+                Some(SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port)),
+            )
+        }
+        _ => ProxyHeader::Complete(total, None),
+    }
+}
+
+/// Resolves the real address of clients, shared with the HTTP server as app data
+#[derive(Debug, Clone, Default)]
+pub struct ClientAddrs {
+    trusted: Vec<IpAddr>,
+    /// Client addresses of relayed PROXY protocol connections, by the relay's local address
+    relayed: Arc<Mutex<HashMap<SocketAddr, IpAddr>>>,
+}
+
+impl ClientAddrs {
+    pub fn new(config: &ProxyConfig) -> Self {
+        Self {
+            trusted: config.trusted_proxies.to_owned(),
+            relayed: Arc::default(),
+        }
+    }
+
+    /// Get the real address of the client behind a request
+    ///
+    /// Relayed PROXY protocol connections use the address in their header. Requests from trusted
+    /// proxies use the last address in `X-Forwarded-For` that isn't a trusted proxy itself.
+    pub fn resolve(&self, req: &HttpRequest) -> Option<IpAddr> {
+        let peer = req.peer_addr()?;
+
+        if let Some(ip) = self.relayed.lock().unwrap().get(&peer) {
+            return Some(*ip);
+        }
+
+        if !self.trusted.contains(&peer.ip()) {
+            return Some(peer.ip());
+        }
+
+        let forwarded = req
+            .headers()
+            .get_all("x-forwarded-for")
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+            .collect::<Vec<_>>();
+
+        forwarded
+            .into_iter()
+            .rev()
+            .find(|ip| !self.trusted.contains(ip))
+            .or_else(|| Some(peer.ip()))
+    }
+
+    /// Accept PROXY protocol connections on `address`, relaying them to the server at `upstream`
+    ///
+    /// Only the trusted proxies can connect, as anyone else could claim any address in a header.
+    pub fn spawn_relay(&self, address: &str, upstream: SocketAddr) -> io::Result<()> {
+        if self.trusted.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the PROXY protocol needs the proxies sending it listed in trustedProxies",
+            ));
+        }
+
+        let listener = TcpListener::bind(address)?;
+        let trusted = self.trusted.to_owned();
+        let relayed = self.relayed.clone();
+        let open = Arc::new(AtomicUsize::new(0));
+
+        info!("Accepting PROXY protocol connections on {}.", address);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                match stream.peer_addr() {
+                    Ok(peer) if trusted.contains(&peer.ip()) => {}
+                    Ok(peer) => {
+                        warn!("Refused a PROXY protocol connection from {}.", peer);
+                        continue;
+                    }
+                    Err(_) => continue,
+                }
+
+                if open.fetch_add(1, Ordering::SeqCst) >= MAX_RELAYED_CONNECTIONS {
+                    open.fetch_sub(1, Ordering::SeqCst);
+                    warn!(
+                        "Dropped a PROXY protocol connection, {} are relayed already.",
+                        MAX_RELAYED_CONNECTIONS
+                    );
+                    continue;
+                }
+
+                let relayed = relayed.clone();
+                let open = open.clone();
+
+                thread::spawn(move || {
+                    if let Err(e) = relay(stream, upstream, &relayed) {
+                        warn!("Dropped a PROXY protocol connection: {}", e);
+                    }
+
+                    open.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Read the header off a connection, then pipe it to the server in both directions
+fn relay(
+    mut client: TcpStream,
+    upstream: SocketAddr,
+    relayed: &Mutex<HashMap<SocketAddr, IpAddr>>,
+) -> io::Result<()> {
+    let mut buf = vec![];
+    let mut chunk = [0; 256];
+
+    // connections trickling in their header don't get to hold a thread for long
+    client.set_read_timeout(Some(HEADER_TIMEOUT))?;
+
+    let (length, source) = loop {
+        let read = client.read(&mut chunk)?;
+        if read == 0 {
+            return Ok(());
+        }
+        buf.extend_from_slice(&chunk[..read]);
+
+        match parse_proxy_header(&buf) {
+            ProxyHeader::Incomplete => continue,
+            ProxyHeader::Complete(length, source) => break (length, source),
+            ProxyHeader::Invalid => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "missing PROXY protocol header",
+                ))
+            }
+        }
+    };
+
+    client.set_read_timeout(None)?;
+
+    let mut server = TcpStream::connect(upstream)?;
+    let local = server.local_addr()?;
+
+    if let Some(source) = source {
+        relayed.lock().unwrap().insert(local, source.ip());
+    }
+
+    server.write_all(&buf[length..])?;
+
+    let mut client_read = client.try_clone()?;
+    let mut server_write = server.try_clone()?;
+    let upload = thread::spawn(move || {
+        io::copy(&mut client_read, &mut server_write).ok();
+        server_write.shutdown(Shutdown::Write).ok();
+    });
+
+    io::copy(&mut server, &mut client).ok();
+    client.shutdown(Shutdown::Write).ok();
+    upload.join().ok();
+
+    relayed.lock().unwrap().remove(&local);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers() {
+        let v1 = b"PROXY TCP4 203.0.113.7 10.0.0.1 56324 4000\r\nGET /ws/ HTTP/1.1";
+        assert_eq!(
+            parse_proxy_header(v1),
+            ProxyHeader::Complete(44, Some("203.0.113.7:56324".parse().unwrap()))
+        );
+        assert_eq!(
+            parse_proxy_header(b"PROXY TCP4 203.0"),
+            ProxyHeader::Incomplete
+        );
+        assert_eq!(
+            parse_proxy_header(b"GET /ws/ HTTP/1.1"),
+            ProxyHeader::Invalid
+        );
+
+        let mut v2 = V2_SIGNATURE.to_vec();
+        v2.extend_from_slice(&[0x21, 0x11, 0, 12]);
+        v2.extend_from_slice(&[203, 0, 113, 7, 10, 0, 0, 1, 0xdc, 0x04, 0x0f, 0xa0]);
+        assert_eq!(
+            parse_proxy_header(&v2),
+            ProxyHeader::Complete(28, Some("203.0.113.7:56324".parse().unwrap()))
+        );
+        assert_eq!(parse_proxy_header(&v2[..20]), ProxyHeader::Incomplete);
+    }
+
+    #[test]
+    fn untrusted_relay() {
+        // with no proxy trusted, anyone could claim any address
+        let addrs = ClientAddrs::new(&ProxyConfig::default());
+        let upstream = "127.0.0.1:4000".parse().unwrap();
+        assert!(addrs.spawn_relay("127.0.0.1:0", upstream).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+
+use super::{
+    bandwidth::{priority_of, Priority},
+    models::messages,
+};
+
+/// Times a lane can be passed over for higher ones before it's served anyway
+const STARVATION_LIMIT: u32 = 8;
+
+/// Most messages a lane holds, the oldest are dropped past it
+const MAX_LANE_LENGTH: usize = 1024;
+
+const LANES: [Priority; 3] = [Priority::Critical, Priority::Normal, Priority::Low];
+
+/// Messages waiting to be sent to a client, served by priority
+///
+/// Messages of one priority keep their order. Lower lanes still get a turn every
+/// `STARVATION_LIMIT` messages, so a busy critical lane can't hold peers back forever.
+#[derive(Debug, Default)]
+pub struct OutboundQueue {
+    lanes: [VecDeque<messages::Message>; 3],
+    /// Times each lane was passed over since it was last served
+    passed: [u32; 3],
+}
+
+fn lane(priority: Priority) -> usize {
+    LANES.iter().position(|p| *p == priority).unwrap()
+}
+
+impl OutboundQueue {
+    pub fn is_empty(&self) -> bool {
+        self.lanes.iter().all(VecDeque::is_empty)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lanes.iter().map(VecDeque::len).sum()
+    }
+
+    /// Queue a message behind the others of its priority
+    pub fn push(&mut self, message: messages::Message) {
+        let lane = &mut self.lanes[lane(priority_of(&message))];
+
+        if lane.len() >= MAX_LANE_LENGTH {
+            lane.pop_front();
+        }
+
+        lane.push_back(message);
+    }
+
+    /// Put a popped message back at the head of its lane
+    pub fn unpop(&mut self, priority: Priority, message: messages::Message) {
+        self.lanes[lane(priority)].push_front(message);
+    }
+
+    /// Take the next message of a priority only
+    pub fn pop_lane(&mut self, priority: Priority) -> Option<messages::Message> {
+        self.lanes[lane(priority)].pop_front()
+    }
+
+    /// Take the next message to send, highest priority first unless a lower lane is starving
+    pub fn pop(&mut self) -> Option<(Priority, messages::Message)> {
+        let waiting = (0..LANES.len())
+            .filter(|&i| !self.lanes[i].is_empty())
+            .collect::<Vec<_>>();
+
+        let chosen = waiting
+            .iter()
+            .copied()
+            .find(|&i| self.passed[i] >= STARVATION_LIMIT)
+            .or_else(|| waiting.first().copied())?;
+
+        for i in waiting.into_iter().filter(|&i| i != chosen) {
+            self.passed[i] += 1;
+        }
+        self.passed[chosen] = 0;
+
+        self.lanes[chosen]
+            .pop_front()
+            .map(|message| (LANES[chosen], message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::models::messages::message::Type as MessageType;
+    use super::*;
+
+    #[test]
+    fn lanes() {
+        let of_type = |r#type: MessageType, text: &str| messages::Message {
+            r#type: r#type as i32,
+            text: text.to_owned(),
+            ..Default::default()
+        };
+
+        let mut queue = OutboundQueue::default();
+        queue.push(of_type(MessageType::Peer, "peer"));
+        queue.push(of_type(MessageType::Time, "time"));
+        for i in 0..10 {
+            queue.push(of_type(MessageType::Update, &i.to_string()));
+        }
+        assert_eq!(queue.len(), 12);
+
+        let order = std::iter::from_fn(|| queue.pop())
+            .map(|(_, message)| message.text)
+            .collect::<Vec<_>>();
+
+        // block updates first, in order, until the peer update has waited long enough
+        assert_eq!(
+            order,
+            vec!["0", "1", "2", "3", "4", "5", "6", "7", "peer", "time", "8", "9"]
+        );
+        assert!(queue.is_empty());
+    }
+}
@@ -2,23 +2,34 @@ use actix::SystemService;
 use actix_files as fs;
 use actix_web::{
     get,
+    http::StatusCode,
+    post,
     web::{self, Query},
     Error, HttpRequest, HttpResponse, Result,
 };
 use actix_web_actors::ws;
 
+use serde::Deserialize;
+
 use std::{
     collections::HashMap,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use super::{message, server::WsServer, session};
+use crate::engine::{config::ServerConfig, shards::ChunkHandoff, skins::MAX_SKIN_SIZE};
+
+use super::{
+    bandwidth::Bandwidth, format::WireFormat, message, proxy::ClientAddrs, server::WsServer,
+    session,
+};
 
 /// Main websocket route
 pub async fn ws_route(
     req: HttpRequest,
     params: Query<HashMap<String, String>>,
     stream: web::Payload,
+    addrs: web::Data<ClientAddrs>,
+    config: web::Data<ServerConfig>,
 ) -> Result<HttpResponse, Error> {
     let world_query = params.get("world");
 
@@ -33,8 +44,69 @@ pub async fn ws_route(
         }
     };
 
+    // WebSocket upgrades can't follow redirects, so clients look the shard up with `/shard`
+    // first, and connections that didn't are refused with where to go
+    let route = WsServer::from_registry()
+        .send(message::RouteConnection(world_name.to_owned()))
+        .await
+        .unwrap();
+
+    if let Some(address) = route {
+        return Ok(HttpResponse::build(StatusCode::MISDIRECTED_REQUEST).json(address));
+    }
+
+    // requested view radius in chunks, clamped by the world
+    let view_radius = params
+        .get("radius")
+        .and_then(|radius| radius.parse::<i16>().ok());
+
+    let format = params
+        .get("format")
+        .and_then(|format| WireFormat::parse(format))
+        .unwrap_or_default();
+
+    let raw_geometry = params
+        .get("geometry")
+        .map(|geometry| geometry == "raw")
+        .unwrap_or(false);
+
+    let packed_transforms = params
+        .get("transforms")
+        .map(|transforms| transforms == "packed")
+        .unwrap_or(false);
+
+    // locale for server messages, such as "en" or "es-MX"
+    let locale = params
+        .get("locale")
+        .filter(|locale| {
+            locale.len() <= 16
+                && locale
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(|locale| locale.to_owned());
+
+    // token from the server the player transferred from
+    let transfer = params.get("transfer").map(|token| token.to_owned());
+
+    // token of a session dropped moments ago, to pick it back up
+    let resume = params.get("resume").map(|token| token.to_owned());
+
+    // the client's own address, even behind a load balancer
+    let address = addrs.resolve(&req);
+
     let player = session::WsSession {
         world_name,
+        view_radius,
+        format,
+        raw_geometry,
+        packed_transforms,
+        locale,
+        transfer,
+        address,
+        resume,
+        timeout: Duration::from_secs(config.keepalive_timeout),
+        bandwidth: Bandwidth::new(config.bandwidth.limit),
         ..Default::default()
     };
 
@@ -88,3 +160,241 @@ pub async fn time(params: Query<HashMap<String, String>>) -> Result<HttpResponse
             .as_millis()
     )))
 }
+
+/// Route to upload a player's skin, a 64x64 or 64x32 PNG body authorized by the
+/// `token` the player received on join
+#[post("/skin")]
+pub async fn skin(
+    params: Query<HashMap<String, String>>,
+    body: web::Bytes,
+) -> Result<HttpResponse> {
+    let player_id = params.get("id").and_then(|id| id.parse::<usize>().ok());
+
+    let (world_name, player_id, token) = match (params.get("world"), player_id, params.get("token"))
+    {
+        (Some(world_name), Some(player_id), Some(token)) => {
+            (world_name.to_owned(), player_id, token.to_owned())
+        }
+        _ => return Ok(HttpResponse::BadRequest().body("Missing world, id or token.")),
+    };
+
+    if body.len() > MAX_SKIN_SIZE {
+        return Ok(HttpResponse::PayloadTooLarge().finish());
+    }
+
+    let result = WsServer::from_registry()
+        .send(message::SetSkin {
+            world_name,
+            player_id,
+            token,
+            data: body.to_vec(),
+        })
+        .await
+        .unwrap();
+
+    match result {
+        Ok(url) => Ok(HttpResponse::Ok().json(url)),
+        Err(error) => Ok(HttpResponse::BadRequest().body(error)),
+    }
+}
+
+/// Route to get the map tile of a chunk as a PNG image, drawn from above
+#[get("/map")]
+pub async fn map(params: Query<HashMap<String, String>>) -> Result<HttpResponse> {
+    map_tile(&params).await
+}
+
+/// Answer with the PNG map tile a request's `world`, `cx` and `cz` point to
+pub async fn map_tile(params: &HashMap<String, String>) -> Result<HttpResponse> {
+    let coords = (
+        params.get("cx").and_then(|cx| cx.parse::<i32>().ok()),
+        params.get("cz").and_then(|cz| cz.parse::<i32>().ok()),
+    );
+
+    let (world_name, coords) = match (params.get("world"), coords) {
+        (Some(world_name), (Some(cx), Some(cz))) => (world_name.to_owned(), [cx, cz]),
+        _ => return Ok(HttpResponse::BadRequest().body("Missing world, cx or cz.")),
+    };
+
+    let tile = WsServer::from_registry()
+        .send(message::GetMapTile { world_name, coords })
+        .await
+        .unwrap();
+
+    match tile {
+        Some(png) => Ok(HttpResponse::Ok().content_type("image/png").body(png)),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
+
+/// A message posted by a bridge's bot
+#[derive(Debug, Deserialize)]
+pub struct BridgePost {
+    pub author: String,
+    pub message: String,
+}
+
+/// Header holding the bridge's `secret` on a bot's messages, kept out of the query so that it
+/// doesn't end up in access logs
+pub const BRIDGE_SECRET_HEADER: &str = "X-Bridge-Secret";
+
+/// Route for a bridge's bot to post a message into a world's chat, authorized by the bridge's
+/// `secret`
+#[post("/bridge")]
+pub async fn bridge(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    post: web::Json<BridgePost>,
+) -> Result<HttpResponse> {
+    let world_name = match params.get("world") {
+        Some(world_name) => world_name.to_owned(),
+        None => return Ok(HttpResponse::BadRequest().body("Missing world.")),
+    };
+
+    let BridgePost { author, message } = post.into_inner();
+
+    let result = WsServer::from_registry()
+        .send(message::BridgeMessage {
+            world_name,
+            secret: req
+                .headers()
+                .get(BRIDGE_SECRET_HEADER)
+                .and_then(|header| header.to_str().ok())
+                .map(str::to_owned),
+            author,
+            message,
+        })
+        .await
+        .unwrap();
+
+    match result {
+        Ok(()) => Ok(HttpResponse::Ok().finish()),
+        Err(error) => Ok(HttpResponse::BadRequest().body(error)),
+    }
+}
+
+/// Route to get the address of the shard owning a world's spawn, null if it's this server
+#[get("/shard")]
+pub async fn shard(params: Query<HashMap<String, String>>) -> Result<HttpResponse> {
+    let world_name = match params.get("world") {
+        Some(world_name) => world_name.to_owned(),
+        None => return Ok(HttpResponse::BadRequest().body("Missing world.")),
+    };
+
+    let route = WsServer::from_registry()
+        .send(message::RouteConnection(world_name))
+        .await
+        .unwrap();
+
+    Ok(HttpResponse::Ok().json(route))
+}
+
+/// Header holding the world's shard `secret` on handoff requests, kept out of the query so that
+/// it doesn't end up in access logs
+pub const SHARD_SECRET_HEADER: &str = "X-Shard-Secret";
+
+/// The shard secret a request came with, if any
+fn shard_secret(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(SHARD_SECRET_HEADER)
+        .and_then(|header| header.to_str().ok())
+        .map(str::to_owned)
+}
+
+/// Route for a shard to take a chunk over, answered with the chunk along with its block ticks and
+/// entities, authorized by the world's shard `secret`
+///
+/// The chunk is kept until `/handoff/confirm` once the shard accepted it.
+#[post("/handoff/release")]
+pub async fn release_handoff(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+) -> Result<HttpResponse> {
+    let coords = (
+        params.get("cx").and_then(|cx| cx.parse::<i32>().ok()),
+        params.get("cz").and_then(|cz| cz.parse::<i32>().ok()),
+    );
+
+    let (world_name, coords) = match (params.get("world"), coords) {
+        (Some(world_name), (Some(cx), Some(cz))) => (world_name.to_owned(), [cx, cz]),
+        _ => return Ok(HttpResponse::BadRequest().body("Missing world, cx or cz.")),
+    };
+
+    let result = WsServer::from_registry()
+        .send(message::HandoffChunk {
+            world_name,
+            secret: shard_secret(&req),
+            coords,
+        })
+        .await
+        .unwrap();
+
+    match result {
+        Ok(released) => Ok(HttpResponse::Ok().json(released)),
+        Err(error) => Ok(HttpResponse::BadRequest().body(error)),
+    }
+}
+
+/// Route to let go of a chunk released with `/handoff/release` once the shard taking it over
+/// accepted it, authorized by the world's shard `secret`
+#[post("/handoff/confirm")]
+pub async fn confirm_handoff(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+) -> Result<HttpResponse> {
+    let coords = (
+        params.get("cx").and_then(|cx| cx.parse::<i32>().ok()),
+        params.get("cz").and_then(|cz| cz.parse::<i32>().ok()),
+    );
+
+    let (world_name, coords) = match (params.get("world"), coords) {
+        (Some(world_name), (Some(cx), Some(cz))) => (world_name.to_owned(), [cx, cz]),
+        _ => return Ok(HttpResponse::BadRequest().body("Missing world, cx or cz.")),
+    };
+
+    let result = WsServer::from_registry()
+        .send(message::ConfirmHandoff {
+            world_name,
+            secret: shard_secret(&req),
+            coords,
+        })
+        .await
+        .unwrap();
+
+    match result {
+        Ok(()) => Ok(HttpResponse::Ok().finish()),
+        Err(error) => Ok(HttpResponse::BadRequest().body(error)),
+    }
+}
+
+/// Largest chunk handoff accepted, in bytes, fitting a chunk of the tallest worlds whose voxels
+/// and lights don't compress at all, along with its entities
+pub const MAX_HANDOFF_SIZE: usize = 32 * 1024 * 1024;
+
+/// Route to accept a chunk handed off by another shard, authorized by the world's shard `secret`
+///
+/// Served at `/handoff/accept` with a JSON limit of `MAX_HANDOFF_SIZE`.
+pub async fn accept_handoff(
+    req: HttpRequest,
+    params: Query<HashMap<String, String>>,
+    chunk: web::Json<ChunkHandoff>,
+) -> Result<HttpResponse> {
+    let world_name = match params.get("world") {
+        Some(world_name) => world_name.to_owned(),
+        None => return Ok(HttpResponse::BadRequest().body("Missing world.")),
+    };
+
+    let result = WsServer::from_registry()
+        .send(message::AcceptHandoff {
+            world_name,
+            secret: shard_secret(&req),
+            handoff: chunk.into_inner(),
+        })
+        .await
+        .unwrap();
+
+    match result {
+        Ok(()) => Ok(HttpResponse::Ok().finish()),
+        Err(error) => Ok(HttpResponse::BadRequest().body(error)),
+    }
+}
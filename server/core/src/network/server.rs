@@ -2,23 +2,32 @@ use actix::prelude::*;
 use actix_broker::BrokerSubscribe;
 
 use hashbrown::HashMap;
+use log::{info, warn};
 use std::time::Duration;
 
-use crate::engine::config::Configs;
+use server_common::vec::Vec2;
+
+use crate::engine::commands;
+use crate::engine::config::{Configs, WorldConfig, WorldMeta};
 use crate::engine::entities::Entities;
-use crate::engine::world::{WorldConfig, WorldMeta};
+use crate::engine::restart::{spawn_watchdog, RESTART_EXIT_CODE};
+use crate::engine::shards::ChunkHandoff;
 
 use super::super::engine::{chunks::Chunks, clock::Clock, players::Players, world::World};
 
 use super::message::{
-    FullWorldData, GetWorld, JoinWorld, LeaveWorld, ListWorldNames, ListWorlds, Noop,
-    PlayerMessage, SimpleWorldData,
+    AcceptHandoff, BridgeMessage, ConfirmHandoff, FullWorldData, GetMapTile, GetMetrics, GetWorld,
+    HandoffChunk, JoinWorld, LeaveWorld, ListPlayers, ListWorldNames, ListWorlds, Noop,
+    PlayerMessage, PlayerSummary, RouteConnection, RunCommand, SetSkin, SimpleWorldData,
+    TransferPlayer, WorldMetrics,
 };
 use super::models::{messages, messages::message::Type as MessageType};
 
 #[derive(Default)]
 pub struct WsServer {
     worlds: HashMap<String, World>,
+    /// The worlds were saved for a restart, and the process is about to exit
+    restarting: bool,
 }
 
 impl WsServer {
@@ -44,18 +53,42 @@ impl WsServer {
             processes.push((world.name.to_owned(), tick_rate));
         }
 
+        spawn_watchdog(self.worlds.values().map(World::watched).collect());
+
         let mut intervals = vec![];
 
         processes.into_iter().for_each(|(name, tick_rate)| {
             intervals.push(
-                ctx.run_interval(Duration::from_millis(tick_rate), move |act, _ctx| {
-                    act.worlds.get_mut(&name).unwrap().tick();
+                ctx.run_interval(Duration::from_millis(tick_rate), move |act, ctx| {
+                    let world = act.worlds.get_mut(&name).unwrap();
+                    world.tick();
+
+                    if world.wants_restart() && !act.restarting {
+                        act.restart(ctx);
+                    }
                 }),
             );
         });
 
         intervals
     }
+
+    /// Save every world and exit with `RESTART_EXIT_CODE`, giving the sessions a moment to send
+    /// out the last messages
+    fn restart(&mut self, ctx: &mut Context<Self>) {
+        self.restarting = true;
+
+        for world in self.worlds.values_mut() {
+            world.save();
+            world.sync_storage();
+        }
+
+        info!("Worlds saved, restarting.");
+
+        ctx.run_later(Duration::from_secs(1), |_, _| {
+            std::process::exit(RESTART_EXIT_CODE)
+        });
+    }
 }
 
 impl Actor for WsServer {
@@ -75,11 +108,33 @@ impl Handler<JoinWorld> for WsServer {
         let JoinWorld {
             world_name,
             player_name,
+            view_radius,
+            locale,
+            transfer,
+            address,
+            resume,
             player_addr,
         } = msg;
 
         let world = self.worlds.get_mut(&world_name).expect("World not found!");
-        let result = world.add_player(None, player_name, player_addr);
+
+        if let Some(token) = resume {
+            if let Some(result) = world.resume_player(&token, player_addr.clone()) {
+                return MessageResult(result);
+            }
+        }
+
+        // transferred players keep the name the other server vouched for
+        let player_name = match transfer.map(|token| world.redeem_transfer(&token)) {
+            Some(Ok(claims)) => claims.name.or(player_name),
+            Some(Err(error)) => {
+                warn!("Rejected a transfer into \"{}\": {}", world_name, error);
+                player_name
+            }
+            None => player_name,
+        };
+
+        let result = world.add_player(None, player_name, view_radius, locale, address, player_addr);
 
         MessageResult(result)
     }
@@ -90,6 +145,10 @@ impl Handler<LeaveWorld> for WsServer {
 
     fn handle(&mut self, msg: LeaveWorld, _ctx: &mut Self::Context) {
         if let Some(world) = self.worlds.get_mut(&msg.world_name) {
+            if msg.resumable && world.detach_player(&msg.player_id) {
+                return;
+            }
+
             world.remove_player(&msg.player_id);
         }
     }
@@ -103,6 +162,64 @@ impl Handler<ListWorldNames> for WsServer {
     }
 }
 
+impl Handler<SetSkin> for WsServer {
+    type Result = Result<String, String>;
+
+    fn handle(&mut self, msg: SetSkin, _ctx: &mut Self::Context) -> Self::Result {
+        let SetSkin {
+            world_name,
+            player_id,
+            token,
+            data,
+        } = msg;
+
+        match self.worlds.get_mut(&world_name) {
+            Some(world) => world.set_skin(player_id, &token, &data),
+            None => Err("World not found.".to_owned()),
+        }
+    }
+}
+
+impl Handler<GetMapTile> for WsServer {
+    type Result = Option<Vec<u8>>;
+
+    fn handle(&mut self, msg: GetMapTile, _ctx: &mut Self::Context) -> Self::Result {
+        let [cx, cz] = msg.coords;
+
+        self.worlds
+            .get(&msg.world_name)?
+            .map_tile_png(&Vec2(cx, cz))
+    }
+}
+
+impl Handler<ListPlayers> for WsServer {
+    type Result = Option<Vec<PlayerSummary>>;
+
+    fn handle(&mut self, msg: ListPlayers, _ctx: &mut Self::Context) -> Self::Result {
+        self.worlds
+            .get(&msg.0)
+            .map(|world| world.player_summaries())
+    }
+}
+
+impl Handler<GetMetrics> for WsServer {
+    type Result = Option<WorldMetrics>;
+
+    fn handle(&mut self, msg: GetMetrics, _ctx: &mut Self::Context) -> Self::Result {
+        self.worlds.get(&msg.0).map(|world| world.metrics())
+    }
+}
+
+impl Handler<RunCommand> for WsServer {
+    type Result = Option<Vec<String>>;
+
+    fn handle(&mut self, msg: RunCommand, _ctx: &mut Self::Context) -> Self::Result {
+        self.worlds
+            .get_mut(&msg.world_name)
+            .map(|world| commands::run_console_command(world, &msg.command))
+    }
+}
+
 impl Handler<PlayerMessage> for WsServer {
     type Result = ();
 
@@ -122,11 +239,44 @@ impl Handler<PlayerMessage> for WsServer {
             MessageType::Update => world.on_update(player_id, raw),
             MessageType::Peer => world.on_peer(player_id, raw),
             MessageType::Message => world.on_chat_message(player_id, raw),
+            MessageType::Pong => world.on_pong(player_id, raw),
+            MessageType::Input => world.on_input(player_id, raw),
+            MessageType::ContainerOpen => world.on_container_open(player_id, raw),
+            MessageType::ContainerClick => world.on_container_click(player_id, raw),
+            MessageType::ContainerClose => world.on_container_close(player_id),
+            MessageType::Attack => world.on_attack(player_id, raw),
+            MessageType::Trade => commands::trading::on_trade(world, player_id, raw),
+            MessageType::TradeSelect => commands::trading::on_trade_select(world, player_id, raw),
+            MessageType::Mount => world.on_mount(player_id, raw),
+            MessageType::Dismount => world.on_dismount(player_id),
+            MessageType::FormResponse => commands::forms::on_form_response(world, player_id, raw),
+            MessageType::FormClose => commands::forms::on_form_close(world, player_id),
+            MessageType::Map => world.on_map(player_id, raw),
+            MessageType::Debug => world.on_debug(player_id, raw),
             _ => {}
         }
     }
 }
 
+impl Handler<TransferPlayer> for WsServer {
+    type Result = bool;
+
+    fn handle(&mut self, msg: TransferPlayer, _ctx: &mut Self::Context) -> Self::Result {
+        let TransferPlayer {
+            world_name,
+            player_id,
+            host,
+            port,
+            target_world,
+        } = msg;
+
+        match self.worlds.get_mut(&world_name) {
+            Some(world) => world.transfer_player(player_id, &host, port, &target_world),
+            None => false,
+        }
+    }
+}
+
 impl Handler<Noop> for WsServer {
     type Result = ();
 
@@ -150,6 +300,11 @@ impl Handler<ListWorlds> for WsServer {
                 generation: chunks.config.generation.to_owned(),
                 description: world.description.to_owned(),
                 players: players.len(),
+                ping: if players.is_empty() {
+                    0
+                } else {
+                    players.values().map(|p| p.ping).sum::<u32>() / players.len() as u32
+                },
             });
         });
 
@@ -177,6 +332,7 @@ impl Handler<GetWorld> for WsServer {
             chunk_size: config.chunk_size,
             dimension: config.dimension,
             max_height: config.max_height,
+            min_height: config.min_height,
             max_light_level: config.max_light_level,
             name: world.name.to_owned(),
             render_radius: config.render_radius,
@@ -190,10 +346,134 @@ impl Handler<GetWorld> for WsServer {
             uv_side_count: registry.uv_side_count,
             uv_texture_size: registry.uv_texture_size,
             packs: meta.packs.to_owned(),
+            resource_pack: registry.resource_pack.to_owned(),
         })
     }
 }
 
+impl Handler<RouteConnection> for WsServer {
+    type Result = Option<String>;
+
+    fn handle(&mut self, msg: RouteConnection, _ctx: &mut Self::Context) -> Self::Result {
+        self.worlds
+            .get(&msg.0)
+            .and_then(|world| world.route_connection())
+    }
+}
+
+impl Handler<HandoffChunk> for WsServer {
+    type Result = Result<ChunkHandoff, String>;
+
+    fn handle(&mut self, msg: HandoffChunk, _ctx: &mut Self::Context) -> Self::Result {
+        let HandoffChunk {
+            world_name,
+            secret,
+            coords: [cx, cz],
+        } = msg;
+
+        let world = self
+            .worlds
+            .get_mut(&world_name)
+            .ok_or_else(|| "World not found.".to_owned())?;
+
+        if !world
+            .read_resource::<WorldConfig>()
+            .shards
+            .authorize(secret.as_deref())
+        {
+            return Err("Unauthorized.".to_owned());
+        }
+
+        world
+            .handoff_chunk(&Vec2(cx, cz))
+            .ok_or_else(|| "Chunk isn't loaded or is still generating.".to_owned())
+    }
+}
+
+impl Handler<ConfirmHandoff> for WsServer {
+    type Result = Result<(), String>;
+
+    fn handle(&mut self, msg: ConfirmHandoff, _ctx: &mut Self::Context) -> Self::Result {
+        let ConfirmHandoff {
+            world_name,
+            secret,
+            coords: [cx, cz],
+        } = msg;
+
+        let world = self
+            .worlds
+            .get_mut(&world_name)
+            .ok_or_else(|| "World not found.".to_owned())?;
+
+        if !world
+            .read_resource::<WorldConfig>()
+            .shards
+            .authorize(secret.as_deref())
+        {
+            return Err("Unauthorized.".to_owned());
+        }
+
+        world.confirm_handoff(&Vec2(cx, cz))
+    }
+}
+
+impl Handler<AcceptHandoff> for WsServer {
+    type Result = Result<(), String>;
+
+    fn handle(&mut self, msg: AcceptHandoff, _ctx: &mut Self::Context) -> Self::Result {
+        let AcceptHandoff {
+            world_name,
+            secret,
+            handoff,
+        } = msg;
+
+        let world = self
+            .worlds
+            .get_mut(&world_name)
+            .ok_or_else(|| "World not found.".to_owned())?;
+
+        if !world
+            .read_resource::<WorldConfig>()
+            .shards
+            .authorize(secret.as_deref())
+        {
+            return Err("Unauthorized.".to_owned());
+        }
+
+        world.accept_handoff(handoff);
+        Ok(())
+    }
+}
+
+impl Handler<BridgeMessage> for WsServer {
+    type Result = Result<(), String>;
+
+    fn handle(&mut self, msg: BridgeMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let BridgeMessage {
+            world_name,
+            secret,
+            author,
+            message,
+        } = msg;
+
+        let world = self
+            .worlds
+            .get_mut(&world_name)
+            .ok_or_else(|| "World not found.".to_owned())?;
+
+        if !world
+            .read_resource::<WorldConfig>()
+            .bridge
+            .authorize(secret.as_deref())
+        {
+            return Err("Unauthorized.".to_owned());
+        }
+
+        world.on_bridge_message(&author, &message);
+        Ok(())
+    }
+}
+
 impl SystemService for WsServer {
     fn service_started(&mut self, ctx: &mut Context<Self>) {
         self.load_worlds();
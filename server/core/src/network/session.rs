@@ -1,18 +1,35 @@
 use libflate::zlib::Encoder;
 
 use std::io::Write;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
 
 use actix::prelude::*;
 use actix_broker::BrokerIssue;
 use actix_web_actors::ws;
 
-use super::super::network::models::{create_of_type, encode_message, messages};
+use log::{debug, info};
+
+use super::super::{
+    engine::clock::unix_millis,
+    network::{
+        bandwidth::{Bandwidth, Priority, Verdict},
+        format::{pack_chunk_geometries, pack_transforms, WireFormat},
+        models::{create_of_type, encode_fragments, messages, Reassembler, MAX_FRAGMENT_SIZE},
+        queue::OutboundQueue,
+    },
+};
 
 use super::message::{self, PlayerMessage};
 use super::message::{JoinWorld, LeaveWorld};
-use super::models;
 use super::server::WsServer;
 
+/// How often the server measures a connection's round-trip time
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often messages held back by a connection's bandwidth limit are retried
+const THROTTLE_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug, Default)]
 pub struct WsSession {
     // unique sessions id
@@ -21,6 +38,36 @@ pub struct WsSession {
     pub world_name: String,
     // name in world
     pub name: Option<String>,
+    // requested view radius in chunks
+    pub view_radius: Option<i16>,
+    // negotiated wire format
+    pub format: WireFormat,
+    // whether chunk geometries are sent as raw little-endian blobs
+    pub raw_geometry: bool,
+    // whether peer and entity transforms are sent compressed
+    pub packed_transforms: bool,
+    // requested locale for server messages
+    pub locale: Option<String>,
+    // token from the server the player transferred from
+    pub transfer: Option<String>,
+    // real address of the client, behind any trusted proxies
+    pub address: Option<IpAddr>,
+    // incoming fragmented messages
+    pub fragments: Reassembler,
+    // how long the client can go silent before being dropped
+    pub timeout: Duration,
+    // last time anything was heard from the client
+    pub heartbeat: Option<Instant>,
+    // token of a detached session to resume
+    pub resume: Option<String>,
+    // whether the client closed the connection, rather than it dropping
+    pub closed: bool,
+    // bytes sent to the client and its throttle
+    pub bandwidth: Bandwidth,
+    // messages waiting to be sent, by priority
+    pub queue: OutboundQueue,
+    // whether sending the queue is already scheduled
+    pub flushing: bool,
 }
 
 impl WsSession {
@@ -31,6 +78,7 @@ impl WsSession {
         let leave_msg = LeaveWorld {
             world_name: self.world_name.clone(),
             player_id: self.id,
+            resumable: false,
         };
 
         // issue_sync comes from having the `BrokerIssue` trait in scope
@@ -39,6 +87,11 @@ impl WsSession {
         let join_msg = JoinWorld {
             world_name: world_name.to_owned(),
             player_name: self.name.clone(),
+            view_radius: self.view_radius,
+            locale: self.locale.clone(),
+            transfer: self.transfer.clone(),
+            address: self.address,
+            resume: self.resume.clone(),
             player_addr: ctx.address().recipient(),
         };
 
@@ -61,7 +114,16 @@ impl WsSession {
                         "time": {},
                         "tickSpeed": {},
                         "spawn": [{}, {}, {}],
-                        "passables": {}
+                        "passables": [{}],
+                        "renderRadius": {},
+                        "skinToken": "{}",
+                        "locale": "{}",
+                        "resourcePack": {},
+                        "biomes": {},
+                        "resumeToken": "{}",
+                        "resumed": {},
+                        "dimensions": {},
+                        "physics": {}
                     }}
                     "#,
                         result.id,
@@ -70,14 +132,30 @@ impl WsSession {
                         result.spawn[0],
                         result.spawn[1],
                         result.spawn[2],
-                        format!("[{}]", passables)
+                        passables,
+                        result.render_radius,
+                        result.skin_token,
+                        result.locale,
+                        result
+                            .resource_pack
+                            .as_ref()
+                            .map(|pack| pack.summary_json())
+                            .unwrap_or_else(|| "null".to_owned()),
+                        result.biomes,
+                        result.resume_token,
+                        result.resumed,
+                        serde_json::to_string(&result.dimensions).unwrap(),
+                        serde_json::to_string(&result.physics).unwrap()
                     );
 
                     let mut message = create_of_type(messages::message::Type::Init);
                     message.json = data;
-                    let encoded = encode_message(&message);
 
-                    ctx.binary(encoded);
+                    act.send(&message, ctx);
+
+                    for missed in result.replay {
+                        act.deliver(missed, ctx);
+                    }
                 }
 
                 fut::ready(())
@@ -85,6 +163,136 @@ impl WsSession {
             .wait(ctx);
     }
 
+    /// Periodically send the server time, which the client echoes back in a pong, dropping
+    /// clients that vanished without closing their connection
+    fn start_ping(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(PING_INTERVAL, |act, ctx| {
+            let heartbeat = act.heartbeat.get_or_insert_with(Instant::now);
+
+            if !act.timeout.is_zero() && heartbeat.elapsed() > act.timeout {
+                info!(
+                    "Dropping player {} of \"{}\", silent for {:?}.",
+                    act.id,
+                    act.world_name,
+                    heartbeat.elapsed()
+                );
+
+                // leaving the world on stop cleans the player up
+                ctx.stop();
+                return;
+            }
+
+            ctx.ping(b"");
+
+            let mut message = create_of_type(messages::message::Type::Ping);
+            message.json = format!("{{\"time\": {}}}", unix_millis());
+
+            act.send(&message, ctx);
+        });
+    }
+
+    /// Send what was held back by the bandwidth limit as budget frees up
+    fn start_throttle(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        if !self.bandwidth.is_limited() {
+            return;
+        }
+
+        ctx.run_interval(THROTTLE_INTERVAL, |act, ctx| act.flush(ctx));
+    }
+
+    /// Queue a message from the world, packed the way the client asked for
+    fn deliver(&mut self, mut msg: messages::Message, ctx: &mut ws::WebsocketContext<Self>) {
+        if self.raw_geometry {
+            pack_chunk_geometries(&mut msg);
+        }
+
+        if self.packed_transforms {
+            pack_transforms(&mut msg);
+        }
+
+        self.enqueue(msg, ctx);
+    }
+
+    /// Close a connection that sent something it can't decode
+    fn reject(&mut self, error: &dyn std::fmt::Display, ctx: &mut ws::WebsocketContext<Self>) {
+        info!(
+            "Dropping player {} of \"{}\", sent an undecodable message: {}",
+            self.id, self.world_name, error
+        );
+
+        self.closed = true;
+        ctx.close(Some(ws::CloseReason {
+            code: ws::CloseCode::Protocol,
+            description: Some(error.to_string()),
+        }));
+        ctx.stop();
+    }
+
+    /// Queue a message, sending the queue once the messages arriving together are all in
+    fn enqueue(&mut self, message: messages::Message, ctx: &mut ws::WebsocketContext<Self>) {
+        self.queue.push(message);
+
+        if !self.flushing {
+            self.flushing = true;
+
+            ctx.run_later(Duration::from_millis(0), |act, ctx| {
+                act.flushing = false;
+                act.flush(ctx);
+            });
+        }
+    }
+
+    /// Send queued messages by priority for as long as the bandwidth limit allows
+    fn flush(&mut self, ctx: &mut ws::WebsocketContext<Self>) {
+        while let Some((priority, message)) = self.queue.pop() {
+            match self.bandwidth.admit(priority) {
+                Verdict::Send => self.send(&message, ctx),
+                Verdict::Defer => {
+                    debug!(
+                        "Throttling player {} of \"{}\" at {} B/s, {} messages queued.",
+                        self.id,
+                        self.world_name,
+                        self.bandwidth.rate(),
+                        self.queue.len() + 1
+                    );
+
+                    self.queue.unpop(priority, message);
+
+                    // world-consistency messages still go out over budget
+                    while let Some(message) = self.queue.pop_lane(Priority::Critical) {
+                        self.send(&message, ctx);
+                    }
+
+                    break;
+                }
+                Verdict::Drop => {}
+            }
+        }
+    }
+
+    /// Send a message to the client in the negotiated wire format
+    fn send(&mut self, message: &messages::Message, ctx: &mut ws::WebsocketContext<Self>) {
+        if self.format == WireFormat::Json {
+            let encoded = self.format.encode(message);
+            self.bandwidth.record(encoded.len());
+            ctx.text(String::from_utf8(encoded).unwrap());
+            return;
+        }
+
+        for encoded in encode_fragments(message, MAX_FRAGMENT_SIZE) {
+            if encoded.len() > 1024 {
+                let mut encoder = Encoder::new(Vec::new()).unwrap();
+                encoder.write_all(encoded.as_slice()).unwrap();
+                let encoded = encoder.finish().into_result().unwrap();
+                self.bandwidth.record(encoded.len());
+                ctx.binary(encoded);
+            } else {
+                self.bandwidth.record(encoded.len());
+                ctx.binary(encoded);
+            }
+        }
+    }
+
     fn on_request(&mut self, message: messages::Message) {
         WsServer::from_registry().do_send(PlayerMessage {
             player_id: self.id,
@@ -99,12 +307,15 @@ impl Actor for WsSession {
 
     fn started(&mut self, ctx: &mut Self::Context) {
         self.join_world(ctx);
+        self.start_ping(ctx);
+        self.start_throttle(ctx);
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
         WsServer::from_registry().do_send(LeaveWorld {
             world_name: self.world_name.clone(),
             player_id: self.id,
+            resumable: !self.closed,
         });
     }
 }
@@ -114,16 +325,7 @@ impl Handler<message::Message> for WsSession {
 
     fn handle(&mut self, msg: message::Message, ctx: &mut Self::Context) {
         let message::Message(msg) = msg;
-        let encoded = encode_message(&msg);
-
-        if encoded.len() > 1024 {
-            let mut encoder = Encoder::new(Vec::new()).unwrap();
-            encoder.write_all(encoded.as_slice()).unwrap();
-            let encoded = encoder.finish().into_result().unwrap();
-            ctx.binary(encoded);
-        } else {
-            ctx.binary(encoded);
-        }
+        self.deliver(msg, ctx);
     }
 }
 
@@ -137,12 +339,23 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
             Ok(msg) => msg,
         };
 
+        self.heartbeat = Some(Instant::now());
+
         match msg {
-            ws::Message::Binary(bytes) => {
-                let message = models::decode_message(&bytes.to_vec()).unwrap();
-                self.on_request(message);
+            ws::Message::Binary(bytes) => match self.fragments.decode(&bytes) {
+                Ok(Some(message)) => self.on_request(message),
+                Ok(None) => {}
+                Err(error) => self.reject(&error, ctx),
+            },
+            ws::Message::Text(text) if self.format == WireFormat::Json => {
+                match self.format.decode(text.as_bytes()) {
+                    Ok(message) => self.on_request(message),
+                    Err(error) => self.reject(&error, ctx),
+                }
             }
+            ws::Message::Ping(bytes) => ctx.pong(&bytes),
             ws::Message::Close(reason) => {
+                self.closed = true;
                 ctx.close(reason);
                 ctx.stop();
             }
@@ -0,0 +1,49 @@
+use serde::Deserialize;
+
+/// Certificate and key served for `wss://` connections, PEM files
+///
+/// Renewed certificates, such as ones kept up to date by an ACME client like certbot, are
+/// picked up on restart.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TlsConfig {
+    /// Certificate chain, leaf first
+    pub cert: String,
+    /// PKCS#8 or RSA private key
+    pub key: String,
+}
+
+/// Build the rustls config of a listener from the certificate and key files
+#[cfg(feature = "tls")]
+pub fn load_rustls(config: &TlsConfig) -> rustls::ServerConfig {
+    use rustls::{
+        internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys},
+        NoClientAuth, ServerConfig,
+    };
+    use std::{fs::File, io::BufReader};
+
+    let open = |path: &str| {
+        BufReader::new(
+            File::open(path).unwrap_or_else(|e| panic!("Unable to open \"{}\": {}", path, e)),
+        )
+    };
+
+    let chain = certs(&mut open(&config.cert))
+        .unwrap_or_else(|_| panic!("Invalid certificate in \"{}\".", config.cert));
+
+    let mut keys = pkcs8_private_keys(&mut open(&config.key)).unwrap_or_default();
+    if keys.is_empty() {
+        keys = rsa_private_keys(&mut open(&config.key)).unwrap_or_default();
+    }
+    let key = keys
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| panic!("No private key found in \"{}\".", config.key));
+
+    let mut server_config = ServerConfig::new(NoClientAuth::new());
+    server_config
+        .set_single_cert(chain, key)
+        .expect("Certificate doesn't match its private key.");
+
+    server_config
+}
@@ -0,0 +1,129 @@
+use specs::{ReadExpect, ReadStorage, System, WriteExpect};
+
+use crate::{
+    comp::rigidbody::RigidBody,
+    engine::{
+        advancements::{Advancement, Advancements, Trigger},
+        chunks::Chunks,
+        clock::Clock,
+        events::{WorldEvent, WorldEvents},
+        players::Players,
+        world::MessagesQueue,
+    },
+    network::models::{create_message, MessageComponents, MessageType},
+};
+
+/// Ticks between checks of the players' locations
+const LOCATION_CHECK_INTERVAL: i32 = 20;
+
+pub struct AdvancementsSystem;
+
+impl<'a> System<'a> for AdvancementsSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Clock>,
+        ReadExpect<'a, Chunks>,
+        ReadExpect<'a, WorldEvents>,
+        ReadExpect<'a, Players>,
+        WriteExpect<'a, Advancements>,
+        WriteExpect<'a, MessagesQueue>,
+        ReadStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (clock, chunks, events, players, mut advancements, mut messages, bodies) = data;
+
+        let registry = &chunks.registry;
+        let mut completed: Vec<(usize, Advancement)> = vec![];
+
+        let name_of = |player_id: &usize| players.get(player_id).and_then(|p| p.name.to_owned());
+
+        for event in events.iter() {
+            let (player_id, done) = match event {
+                WorldEvent::BlockBroken { player_id, id, .. } => match name_of(player_id) {
+                    Some(name) => (
+                        player_id,
+                        advancements.progress(&name, 1, |trigger| match trigger {
+                            Trigger::BreakBlock { block, .. } => {
+                                Advancements::is_block(registry, block, *id)
+                            }
+                            _ => false,
+                        }),
+                    ),
+                    None => continue,
+                },
+                WorldEvent::ItemCrafted {
+                    player_id,
+                    id,
+                    count,
+                } => match name_of(player_id) {
+                    Some(name) => (
+                        player_id,
+                        advancements.progress(&name, *count, |trigger| match trigger {
+                            Trigger::CraftItem { item, .. } => {
+                                Advancements::is_block(registry, item, *id)
+                            }
+                            _ => false,
+                        }),
+                    ),
+                    None => continue,
+                },
+                WorldEvent::Custom {
+                    player_id,
+                    name: event_name,
+                    amount,
+                } => match name_of(player_id) {
+                    Some(name) => (
+                        player_id,
+                        advancements.progress(&name, *amount, |trigger| match trigger {
+                            Trigger::Custom { name, .. } => name == event_name,
+                            _ => false,
+                        }),
+                    ),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            completed.extend(done.into_iter().map(|a| (*player_id, a)));
+        }
+
+        if clock.tick % LOCATION_CHECK_INTERVAL == 0 {
+            let dimension = chunks.config.dimension as f32;
+
+            for (player_id, player) in players.iter() {
+                let (name, body) = match (&player.name, bodies.get(player.entity)) {
+                    (Some(name), Some(body)) => (name, body),
+                    _ => continue,
+                };
+
+                let position = body.get_position().scale(1.0 / dimension);
+
+                let done = advancements.progress(name, 1, |trigger| match trigger {
+                    Trigger::ReachLocation {
+                        x,
+                        z,
+                        radius,
+                        outside,
+                    } => {
+                        let (dx, dz) = (position.0 - x, position.2 - z);
+                        let inside = dx * dx + dz * dz <= radius * radius;
+                        inside != *outside
+                    }
+                    _ => false,
+                });
+
+                completed.extend(done.into_iter().map(|a| (*player_id, a)));
+            }
+        }
+
+        // completion toasts
+        for (player_id, advancement) in completed {
+            let mut components = MessageComponents::default_for(MessageType::Advancement);
+            components.advancement = Some(advancement);
+
+            let message = create_message(components);
+            messages.push((message, Some(vec![player_id]), None, None));
+        }
+    }
+}
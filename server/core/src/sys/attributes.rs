@@ -0,0 +1,61 @@
+use specs::{System, WriteStorage};
+
+use crate::comp::{
+    attributes::{AttributeKind, Attributes},
+    brain::Brain,
+    health::Health,
+    metadata::Metadata,
+};
+
+/// Resolve the attributes whose bases or modifiers changed, feeding them to the brain and health
+pub struct AttributesSystem;
+
+impl<'a> System<'a> for AttributesSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, Attributes>,
+        WriteStorage<'a, Brain>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Metadata>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        use specs::Join;
+
+        let (mut attributes, mut brains, mut healths, mut metadatas) = data;
+
+        for (attributes, mut brain, mut health, mut metadata) in (
+            &mut attributes,
+            (&mut brains).maybe(),
+            (&mut healths).maybe(),
+            (&mut metadatas).maybe(),
+        )
+            .join()
+        {
+            for (kind, value) in attributes.resolve() {
+                match (kind, brain.as_mut(), health.as_mut()) {
+                    (AttributeKind::MovementSpeed, Some(brain), _) => {
+                        brain.options.max_speed = value
+                    }
+                    (AttributeKind::SprintSpeed, Some(brain), _) => {
+                        brain.options.sprint_speed = value
+                    }
+                    (AttributeKind::JumpImpulse, Some(brain), _) => {
+                        brain.options.jump_impulse = value
+                    }
+                    (AttributeKind::MaxHealth, _, Some(health)) => {
+                        health.max = value;
+                        health.current = health.current.min(value);
+
+                        if let Some(metadata) = metadata.as_mut() {
+                            metadata.set("maxHealth", &value.to_string());
+                            metadata.set("health", &health.current.to_string());
+                        }
+                    }
+                    // attack damage is looked up by the attacks themselves
+                    _ => {}
+                }
+            }
+        }
+    }
+}
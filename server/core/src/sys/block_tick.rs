@@ -0,0 +1,84 @@
+use specs::{ReadExpect, System, WriteExpect};
+
+use server_common::vec::Vec3;
+
+use crate::{
+    engine::{
+        block_entities::{BlockEntity, BlockEntityData, Furnace},
+        chunks::Chunks,
+        clock::Clock,
+        players::Players,
+        recipes::Recipes,
+        scheduler::BlockTicks,
+        world::MessagesQueue,
+    },
+    network::models::{create_message, MessageComponents, MessageType},
+};
+
+/// Ticks to wait before retrying a voxel whose chunk isn't loaded
+const UNLOADED_RETRY_TICKS: i32 = 20;
+
+pub struct BlockTickSystem;
+
+impl<'a> System<'a> for BlockTickSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Clock>,
+        ReadExpect<'a, Recipes>,
+        ReadExpect<'a, Players>,
+        WriteExpect<'a, Chunks>,
+        WriteExpect<'a, BlockTicks>,
+        WriteExpect<'a, MessagesQueue>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (clock, recipes, players, mut chunks, mut ticks, mut messages) = data;
+
+        for voxel in ticks.take_due(clock.tick) {
+            let Vec3(vx, vy, vz) = voxel;
+
+            if chunks.get_chunk_by_voxel(vx, vy, vz).is_none() {
+                ticks.schedule(voxel, clock.tick + UNLOADED_RETRY_TICKS);
+                continue;
+            }
+
+            let entity = match chunks.get_block_entity_by_voxel(vx, vy, vz) {
+                Some(entity) => entity,
+                None => continue,
+            };
+
+            let (data, keep_ticking) = match entity.kind.as_str() {
+                Furnace::KIND => {
+                    let mut furnace = match entity.get::<Furnace>() {
+                        Some(furnace) => furnace,
+                        None => continue,
+                    };
+
+                    let keep_ticking = furnace.tick(&recipes);
+                    (BlockEntityData::new(&furnace), keep_ticking)
+                }
+                _ => continue,
+            };
+
+            if keep_ticking {
+                ticks.schedule(voxel.to_owned(), clock.tick + 1);
+            }
+
+            // only players with the block entity's UI open need every step of the progress
+            let viewers = players
+                .iter()
+                .filter(|(_, player)| player.viewing.as_ref() == Some(&voxel))
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+
+            if !viewers.is_empty() {
+                let mut components = MessageComponents::default_for(MessageType::BlockEntity);
+                components.block_entities = Some(vec![data.to_protocol(&voxel)]);
+
+                messages.push((create_message(components), Some(viewers), None, None));
+            }
+
+            chunks.set_block_entity_by_voxel(vx, vy, vz, data);
+        }
+    }
+}
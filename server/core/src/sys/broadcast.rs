@@ -1,6 +1,7 @@
-use specs::{Entities, System, WriteExpect};
+use specs::{Entities, ReadExpect, System, WriteExpect};
 
 use crate::engine::{
+    i18n::Translations,
     players::{BroadcastExt, Players},
     world::MessagesQueue,
 };
@@ -10,12 +11,13 @@ pub struct BroadcastSystem;
 impl<'a> System<'a> for BroadcastSystem {
     type SystemData = (
         Entities<'a>,
+        ReadExpect<'a, Translations>,
         WriteExpect<'a, MessagesQueue>,
         WriteExpect<'a, Players>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut messages, mut players) = data;
+        let (entities, translations, mut messages, mut players) = data;
 
         for (msg, include, exclude, sender) in messages.iter() {
             // TODO: add spam detection?
@@ -37,6 +39,7 @@ impl<'a> System<'a> for BroadcastSystem {
                 include.to_owned(),
                 exclude.to_owned(),
                 sender.to_owned(),
+                &translations,
             );
 
             inactives.into_iter().for_each(|player| {
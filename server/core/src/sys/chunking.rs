@@ -6,7 +6,7 @@ use server_common::vec::Vec3;
 
 use crate::{
     comp::{curr_chunk::CurrChunk, rigidbody::RigidBody},
-    engine::world::WorldConfig,
+    engine::config::WorldConfig,
 };
 
 pub struct ChunkingSystem;
@@ -0,0 +1,92 @@
+use specs::{ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
+
+use crate::{
+    comp::{
+        attributes::Attributes,
+        effects::{Effects, EFFECTS_SOURCE},
+        health::Health,
+        id::Id,
+        metadata::Metadata,
+    },
+    engine::{clock::Clock, world::MessagesQueue},
+    network::models::{create_message, MessageComponents, MessageType},
+};
+
+/// Poison never takes the last health point
+const POISON_MIN_HEALTH: f32 = 1.0;
+
+pub struct EffectsSystem;
+
+impl<'a> System<'a> for EffectsSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Clock>,
+        WriteExpect<'a, MessagesQueue>,
+        ReadStorage<'a, Id>,
+        WriteStorage<'a, Effects>,
+        WriteStorage<'a, Attributes>,
+        WriteStorage<'a, Health>,
+        WriteStorage<'a, Metadata>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        use specs::Join;
+
+        let (clock, mut messages, ids, mut effects, mut attributes, mut healths, mut metadatas) =
+            data;
+
+        let delta = clock.delta;
+
+        for (id, effects, attributes, health, mut metadata) in (
+            &ids,
+            &mut effects,
+            (&mut attributes).maybe(),
+            (&mut healths).maybe(),
+            (&mut metadatas).maybe(),
+        )
+            .join()
+        {
+            if effects.active.is_empty() && !effects.is_dirty() {
+                continue;
+            }
+
+            let damage = effects.tick(delta);
+
+            if let Some(health) = health {
+                if damage > 0.0 {
+                    health.damage(damage, POISON_MIN_HEALTH);
+
+                    if let Some(metadata) = metadata.as_mut() {
+                        metadata.set("health", &health.current.to_string());
+                    }
+                }
+            }
+
+            if !effects.take_dirty() {
+                continue;
+            }
+
+            // the attributes system applies the modifiers to the brain and health
+            if let Some(attributes) = attributes {
+                attributes.set_modifiers(EFFECTS_SOURCE, effects.modifiers());
+            }
+
+            if let Some(metadata) = metadata {
+                let names = effects
+                    .active
+                    .iter()
+                    .map(|e| e.kind.name())
+                    .collect::<Vec<_>>();
+                metadata.set("effects", &names.join(","));
+            }
+
+            // clients simulating their own movement apply the modifiers themselves
+            let mut components = MessageComponents::default_for(MessageType::Effects);
+            components.effects = Some(effects.active.to_owned());
+            components.text = Some(id.0.to_string());
+
+            let message = create_message(components);
+            messages.push((message, Some(vec![id.0]), None, None));
+        }
+    }
+}
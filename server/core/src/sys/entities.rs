@@ -1,14 +1,23 @@
 use server_utils::convert::map_world_to_voxel;
 use specs::{Entities, ReadExpect, ReadStorage, System, WriteExpect};
 
+use std::time::Instant;
+
 use server_common::vec::Vec3;
 
 use crate::{
     comp::{
-        curr_chunk::CurrChunk, etype::EType, rigidbody::RigidBody, target::Target,
+        curr_chunk::CurrChunk,
+        etype::EType,
+        id::{protocol_id, Id},
+        mount::Mounted,
+        rigidbody::RigidBody,
+        target::Target,
+        uuid::Uuid,
+        view_radius::ViewRadius,
         walk_towards::WalkTowards,
     },
-    engine::world::{MessagesQueue, WorldConfig},
+    engine::{clock::unix_millis, config::WorldConfig, snapshots::Snapshots, world::MessagesQueue},
     network::models::{create_message, EntityProtocol, MessageComponents, MessageType},
 };
 
@@ -20,47 +29,71 @@ impl<'a> System<'a> for EntitiesSystem {
         Entities<'a>,
         ReadExpect<'a, WorldConfig>,
         WriteExpect<'a, MessagesQueue>,
+        WriteExpect<'a, Snapshots>,
         ReadStorage<'a, EType>,
+        ReadStorage<'a, Id>,
+        ReadStorage<'a, Uuid>,
+        ReadStorage<'a, ViewRadius>,
         ReadStorage<'a, RigidBody>,
         ReadStorage<'a, CurrChunk>,
         ReadStorage<'a, Target>,
         ReadStorage<'a, WalkTowards>,
+        ReadStorage<'a, Mounted>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         use specs::Join;
 
-        let (entities, configs, mut messages, types, bodies, curr_chunks, targets, walk_towards) =
-            data;
+        let (
+            entities,
+            configs,
+            mut messages,
+            mut snapshots,
+            types,
+            ids,
+            uuids,
+            radiuses,
+            bodies,
+            curr_chunks,
+            targets,
+            walk_towards,
+            mounts,
+        ) = data;
+
+        if !snapshots.due(Instant::now()) {
+            return;
+        }
 
         let dimension = configs.dimension;
 
         let mut entity_updates = vec![];
 
+        // vehicles have neither targets nor paths
         for (ent, etype, body, _curr_chunk, target, walk_toward) in (
             &*entities,
             &types,
             &bodies,
             &curr_chunks,
-            &targets,
-            &walk_towards,
+            targets.maybe(),
+            walk_towards.maybe(),
         )
             .join()
         {
             let Vec3(px, py, pz) = body.get_position();
 
-            let look_target = target.get_position();
-            let mut heading: Option<Vec3<f32>> = walk_toward.0.as_ref().map(|nodes| {
-                let mut node = Vec3::<f32>::from(if nodes.len() > 1 {
-                    &nodes[1]
-                } else {
-                    &nodes[0]
+            let look_target = target.and_then(|target| target.get_position());
+            let mut heading: Option<Vec3<f32>> =
+                walk_toward.and_then(|w| w.0.as_ref()).map(|nodes| {
+                    let mut node = Vec3::<f32>::from(if nodes.len() > 1 {
+                        &nodes[1]
+                    } else {
+                        &nodes[0]
+                    });
+                    node.0 += 0.5;
+                    node.1 += body.head;
+                    node.2 += 0.5;
+                    node
                 });
-                node.0 += 0.5;
-                node.1 += body.head;
-                node.2 += 0.5;
-                node
-            });
 
             if let Some(h) = &heading {
                 let h_pos = map_world_to_voxel(h.0, h.1, h.2, dimension);
@@ -70,23 +103,62 @@ impl<'a> System<'a> for EntitiesSystem {
                 }
             }
 
-            entity_updates.push(EntityProtocol {
-                id: ent.id().to_string(),
-                r#type: etype.0.to_owned(),
-                look_at: look_target,
-                heading,
-                px,
-                py,
-                pz,
-            })
+            // riders are synced as offsets from their mounts
+            let (Vec3(ox, oy, oz), mount) = match mounts.get(ent) {
+                Some(mounted) => (
+                    mounted.offset.to_owned(),
+                    Some(protocol_id(
+                        mounted.mount,
+                        ids.get(mounted.mount),
+                        uuids.get(mounted.mount),
+                    )),
+                ),
+                None => (Vec3(px, py, pz), None),
+            };
+
+            entity_updates.push((
+                Vec3(px, py, pz),
+                EntityProtocol {
+                    id: protocol_id(ent, None, uuids.get(ent)),
+                    r#type: etype.0.to_owned(),
+                    look_at: look_target,
+                    heading,
+                    px: ox,
+                    py: oy,
+                    pz: oz,
+                    mount,
+                },
+            ))
+        }
+
+        if entity_updates.is_empty() {
+            return;
         }
 
-        if !entity_updates.is_empty() {
+        // stamped so clients can interpolate between snapshots
+        let snapshot = format!("{{\"time\": {}}}", unix_millis());
+
+        // only send entities within each player's view radius
+        for (id, body, radius) in (&ids, &bodies, &radiuses).join() {
+            let position = body.get_position();
+            let max_dist = radius.0 as f32 * dimension as f32;
+
+            let nearby = entity_updates
+                .iter()
+                .filter(|(p, _)| p.sub(&position).len() <= max_dist)
+                .map(|(_, e)| e.to_owned())
+                .collect::<Vec<_>>();
+
+            if nearby.is_empty() {
+                continue;
+            }
+
             let mut components = MessageComponents::default_for(MessageType::Entity);
-            components.entities = Some(entity_updates);
+            components.entities = Some(nearby);
+            components.json = Some(snapshot.to_owned());
 
             let msg = create_message(components);
-            messages.push((msg, None, None, None));
+            messages.push((msg, Some(vec![id.0]), None, None));
         }
     }
 }
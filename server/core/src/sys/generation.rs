@@ -22,10 +22,14 @@ impl<'a> System<'a> for GenerationSystem {
         let (mut chunks, ids, radiuses, mut curr_chunks) = data;
         let chunk_size = chunks.config.chunk_size;
 
+        chunks.interests.clear();
+
         for (radius, curr_chunk, _) in (&radiuses, &mut curr_chunks, &ids).join() {
             if let Some(coords) = &curr_chunk.val {
+                let r = radius.in_chunks(chunk_size);
+                chunks.interests.push((coords.to_owned(), r));
+
                 if curr_chunk.changed {
-                    let r = (radius.0 as f32 / chunk_size as f32).ceil() as i16;
                     chunks.generate(coords, r, false);
                     curr_chunk.changed = false;
                 }
@@ -0,0 +1,23 @@
+use specs::{ReadStorage, System, WriteStorage};
+
+use crate::{
+    comp::{history::History, rigidbody::RigidBody},
+    engine::clock::unix_millis,
+};
+
+pub struct HistorySystem;
+
+impl<'a> System<'a> for HistorySystem {
+    type SystemData = (ReadStorage<'a, RigidBody>, WriteStorage<'a, History>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        use specs::Join;
+
+        let (bodies, mut histories) = data;
+        let now = unix_millis();
+
+        for (body, history) in (&bodies, &mut histories).join() {
+            history.record(now, body.get_position());
+        }
+    }
+}
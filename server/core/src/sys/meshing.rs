@@ -51,6 +51,10 @@ impl<'a> System<'a> for MeshingSystem {
                         let new_message = create_message(component);
                         messages.push((new_message, Some(vec![player_id]), None, None));
                     }
+
+                    if let Some(player) = players.get_mut(&player_id) {
+                        player.sent_chunks.insert(coords);
+                    }
                 } else {
                     players
                         .get_mut(&player_id)
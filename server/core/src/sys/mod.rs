@@ -1,23 +1,45 @@
+mod advancements;
+mod attributes;
+mod block_tick;
 mod broadcast;
 mod chunking;
+mod effects;
 mod entities;
 mod generation;
+mod history;
 mod meshing;
+mod mount;
+mod movement;
 mod observe;
 mod pathfind;
 mod peers;
 mod physics;
+mod remesh;
 mod search;
+mod timed;
+mod unloading;
+mod vehicles;
 mod walk_towards;
 
+pub use advancements::AdvancementsSystem;
+pub use attributes::AttributesSystem;
+pub use block_tick::BlockTickSystem;
 pub use broadcast::BroadcastSystem;
 pub use chunking::ChunkingSystem;
+pub use effects::EffectsSystem;
 pub use entities::EntitiesSystem;
 pub use generation::GenerationSystem;
+pub use history::HistorySystem;
 pub use meshing::MeshingSystem;
+pub use mount::MountSystem;
+pub use movement::MovementSystem;
 pub use observe::ObserveSystem;
 pub use pathfind::PathFindSystem;
 pub use peers::PeersSystem;
 pub use physics::PhysicsSystem;
+pub use remesh::RemeshSystem;
 pub use search::SearchSystem;
+pub use timed::{Timed, TimedExt};
+pub use unloading::UnloadingSystem;
+pub use vehicles::VehiclesSystem;
 pub use walk_towards::WalkTowardsSystem;
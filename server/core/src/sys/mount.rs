@@ -0,0 +1,67 @@
+use specs::{Entities, ReadStorage, System, WriteExpect, WriteStorage};
+
+use server_common::vec::Vec3;
+
+use crate::{
+    comp::{
+        id::{protocol_id, Id},
+        mount::Mounted,
+        rigidbody::RigidBody,
+        uuid::Uuid,
+    },
+    engine::world::MessagesQueue,
+    network::models::{create_message, MessageComponents, MessageType, MountProtocol},
+};
+
+/// Keeps riders at their offset from their mounts, dismounting those whose mount is gone
+pub struct MountSystem;
+
+impl<'a> System<'a> for MountSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, MessagesQueue>,
+        ReadStorage<'a, Id>,
+        ReadStorage<'a, Uuid>,
+        WriteStorage<'a, Mounted>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        use specs::Join;
+
+        let (entities, mut messages, ids, uuids, mut mounts, mut bodies) = data;
+
+        let mut positions = vec![];
+        let mut dismounted = vec![];
+
+        for (rider, mounted) in (&*entities, &mounts).join() {
+            match bodies.get(mounted.mount) {
+                Some(mount) if entities.is_alive(mounted.mount) => {
+                    let position = mount.get_position().add(&mounted.offset);
+                    positions.push((rider, position));
+                }
+                _ => dismounted.push(rider),
+            }
+        }
+
+        for (rider, position) in positions {
+            if let Some(body) = bodies.get_mut(rider) {
+                body.set_position(&position);
+                body.velocity = Vec3::default();
+            }
+        }
+
+        for rider in dismounted {
+            mounts.remove(rider);
+
+            let mut components = MessageComponents::default_for(MessageType::Dismount);
+            components.mount = Some(MountProtocol {
+                rider: protocol_id(rider, ids.get(rider), uuids.get(rider)),
+                mount: String::new(),
+                offset: Vec3::default(),
+            });
+
+            messages.push((create_message(components), None, None, None));
+        }
+    }
+}
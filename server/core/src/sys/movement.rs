@@ -0,0 +1,165 @@
+use server_common::{quaternion::Quaternion, vec::Vec3};
+use specs::{ReadExpect, ReadStorage, System, WriteStorage};
+
+use crate::{
+    comp::{
+        brain::Brain,
+        controls::Controls,
+        metadata::Metadata,
+        movement::{Gliding, Movement},
+        rigidbody::RigidBody,
+        rotation::Rotation,
+    },
+    engine::{
+        chunks::Chunks,
+        clock::Clock,
+        config::WorldConfig,
+        physics::{Physics, SwimInput, WallInput, WallMove},
+        registry::Registry,
+    },
+};
+
+/// Tag of the items that players holding them can glide with, jumping in mid-air
+pub const GLIDERS_TAG: &str = "gliders";
+
+/// Whether a player holds an item they can glide with, going by their `held` metadata
+fn holds_glider(metadata: &Metadata, registry: &Registry) -> bool {
+    metadata
+        .data
+        .get("held")
+        .and_then(|held| {
+            held.parse::<u32>()
+                .ok()
+                .filter(|&id| registry.has_type(id))
+                .or_else(|| registry.try_get_id_by_name(held))
+        })
+        .is_some_and(|id| registry.has_tag(id, GLIDERS_TAG))
+}
+
+pub struct MovementSystem;
+
+impl<'a> System<'a> for MovementSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Clock>,
+        ReadExpect<'a, WorldConfig>,
+        ReadExpect<'a, Physics>,
+        ReadExpect<'a, Chunks>,
+        ReadStorage<'a, Controls>,
+        WriteStorage<'a, RigidBody>,
+        WriteStorage<'a, Rotation>,
+        WriteStorage<'a, Brain>,
+        WriteStorage<'a, Movement>,
+        WriteStorage<'a, Metadata>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        use specs::Join;
+
+        let (
+            clock,
+            config,
+            physics,
+            chunks,
+            controls,
+            mut bodies,
+            mut rotations,
+            mut brains,
+            mut movements,
+            mut metadatas,
+        ) = data;
+
+        if !config.server_movement {
+            return;
+        }
+
+        let delta = clock.delta;
+
+        let test_solid = |x: i32, y: i32, z: i32| !chunks.get_walkable_by_voxel(x, y, z);
+
+        for (controls, body, rotation, brain, movement, metadata) in (
+            &controls,
+            &mut bodies,
+            &mut rotations,
+            &mut brains,
+            &mut movements,
+            &mut metadatas,
+        )
+            .join()
+        {
+            rotation.0 = Quaternion::from_yaw_pitch(controls.yaw, controls.pitch);
+
+            // jumping again in mid-air while holding a glider starts gliding
+            let airborne = !body.in_fluid && body.at_rest_y() >= 0.0 && body.velocity.1 < 0.0;
+            if controls.jump
+                && !brain.state.jumping
+                && airborne
+                && holds_glider(metadata, &chunks.registry)
+            {
+                movement.set(Box::new(Gliding));
+            }
+
+            if movement.operate(&physics, body, controls, clock.delta_secs()) {
+                metadata.set("pose", movement.pose());
+
+                brain.stop();
+                if controls.jump {
+                    brain.jump();
+                } else {
+                    brain.stop_jumping();
+                }
+                continue;
+            }
+
+            // swimmers move in 3D instead of walking
+            let swim = SwimInput {
+                heading: controls.heading(),
+                pitch: controls.pitch,
+                up: controls.jump,
+            };
+            if physics.swim(body, clock.delta_secs(), &swim, &test_solid) {
+                metadata.set("pose", movement.pose());
+                continue;
+            }
+
+            let wall = WallInput {
+                heading: controls.heading(),
+                jump: controls.jump && !brain.state.jumping,
+                climb: controls.jump,
+            };
+            let wall_move = physics.wall_move(
+                body,
+                clock.delta_secs(),
+                &wall,
+                &config.parkour,
+                &test_solid,
+            );
+            metadata.set("pose", wall_move.map_or(movement.pose(), WallMove::pose));
+
+            brain.sprint(controls.sprint);
+
+            if controls.jump {
+                brain.jump();
+            } else {
+                brain.stop_jumping();
+            }
+
+            // brains walk towards a target, so aim one unit ahead along the heading
+            let heading = controls.heading();
+            let target = match heading {
+                Some(heading) => {
+                    brain.walk();
+                    body.get_position()
+                        .add(&Vec3(heading.sin(), 0.0, heading.cos()))
+                }
+                None => {
+                    brain.stop();
+                    body.get_position()
+                        .add(&Vec3(controls.yaw.sin(), 0.0, controls.yaw.cos()))
+                }
+            };
+
+            brain.operate(&target, body, delta);
+        }
+    }
+}
@@ -10,15 +10,19 @@ use specs::{ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 use server_common::{quaternion::Quaternion, vec::Vec3};
 
 use crate::{
-    comp::{id::Id, name::Name, rigidbody::RigidBody, rotation::Rotation},
+    comp::{
+        controls::Controls, id::Id, metadata::Metadata, name::Name, rigidbody::RigidBody,
+        rotation::Rotation, view_radius::ViewRadius,
+    },
     engine::{
         chunks::Chunks,
-        players::{PlayerUpdates, Players},
+        players::{PlayerListExt, PlayerUpdates, Players},
+        teams::Teams,
         world::MessagesQueue,
     },
     network::models::{
-        create_chat_message, create_message, create_of_type, messages, ChatType, MessageComponents,
-        MessageType, PeerProtocol,
+        create_message, create_of_type, create_translated_message, messages, ChatType,
+        MessageComponents, MessageType, PeerProtocol,
     },
 };
 
@@ -29,13 +33,17 @@ impl<'a> System<'a> for PeersSystem {
     type SystemData = (
         ReadExpect<'a, String>,
         ReadExpect<'a, Chunks>,
+        ReadExpect<'a, Teams>,
         WriteExpect<'a, PlayerUpdates>,
         WriteExpect<'a, MessagesQueue>,
         WriteExpect<'a, Players>,
         ReadStorage<'a, Id>,
+        ReadStorage<'a, ViewRadius>,
+        ReadStorage<'a, Controls>,
         WriteStorage<'a, Name>,
         WriteStorage<'a, RigidBody>,
         WriteStorage<'a, Rotation>,
+        WriteStorage<'a, Metadata>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -44,18 +52,33 @@ impl<'a> System<'a> for PeersSystem {
         let (
             world_name,
             chunks,
+            teams,
             mut updates,
             mut messages,
             mut players,
             ids,
+            radiuses,
+            controls,
             mut names,
             mut bodies,
             mut rotations,
+            mut metadatas,
         ) = data;
 
+        let server_movement = chunks.config.server_movement;
+
         let mut peers_update = HashMap::new();
+        let mut joined = vec![];
 
-        for (id, name, body, rotation) in (&ids, &mut names, &mut bodies, &mut rotations).join() {
+        for (id, name, body, rotation, metadata) in (
+            &ids,
+            &mut names,
+            &mut bodies,
+            &mut rotations,
+            &mut metadatas,
+        )
+            .join()
+        {
             if let Some(update) = updates.remove(&id.0) {
                 let messages::Peer {
                     id: peer_id,
@@ -67,9 +90,23 @@ impl<'a> System<'a> for PeersSystem {
                     qy,
                     qz,
                     qw,
+                    metadata: mut new_metadata,
+                    sequence,
                     ..
                 } = update;
 
+                // the pose follows the movement mode, which only the server switches
+                new_metadata.remove("pose");
+                metadata.extend(new_metadata);
+
+                // peers render the name of a joining player in its team's color
+                if name.0.is_none() {
+                    if let Some(team) = teams.team_of(&new_name) {
+                        metadata.set("team", &team.name);
+                        metadata.set("teamColor", &team.color);
+                    }
+                }
+
                 peers_update.insert(
                     id.0,
                     PeerProtocol {
@@ -82,28 +119,36 @@ impl<'a> System<'a> for PeersSystem {
                         qy,
                         qz,
                         qw,
+                        metadata: metadata.take_dirty(),
+                        sequence: 0,
                     },
                 );
 
                 if name.0.is_none() {
+                    joined.push(id.0);
+
                     let message =
                         format!("{} joined the world {}", new_name, world_name.to_string());
 
                     info!("{}", Yellow.bold().paint(message));
 
-                    let new_message = create_chat_message(
+                    let new_message = create_translated_message(
                         MessageType::Message,
                         ChatType::Info,
-                        "",
-                        format!("{} joined the game", new_name).as_str(),
+                        "player.joined",
+                        &[&new_name],
                     );
 
                     messages.push((new_message, None, None, Some(id.0.to_owned())));
                 }
 
                 name.0 = Some(new_name.clone());
-                body.set_head_position(&Vec3(px, py, pz));
-                rotation.0 = Quaternion(qx, qy, qz, qw);
+
+                // the server owns the positions in server-authoritative movement
+                if !server_movement {
+                    body.set_head_position(&Vec3(px, py, pz));
+                    rotation.0 = Quaternion(qx, qy, qz, qw);
+                }
 
                 let voxel = map_world_to_voxel(px, py, pz, chunks.config.dimension);
                 let biome = chunks.biomes.get_biome(voxel.0, voxel.2);
@@ -112,15 +157,171 @@ impl<'a> System<'a> for PeersSystem {
                 messages.push((new_message, Some(vec![id.0]), None, None));
 
                 if let Some(player) = players.get_mut(&id.0) {
-                    player.name = Some(new_name);
+                    player.name = Some(new_name.to_owned());
+                    player.last_sequence = sequence;
+                }
+
+                // acknowledge the processed input along with the authoritative state
+                if !server_movement {
+                    let Vec3(px, py, pz) = body.get_head_position();
+                    let Quaternion(qx, qy, qz, qw) = rotation.0.clone();
+
+                    let mut components = MessageComponents::default_for(MessageType::Ack);
+                    components.peers = Some(vec![PeerProtocol {
+                        id: id.0.to_string(),
+                        name: new_name,
+                        px,
+                        py,
+                        pz,
+                        qx,
+                        qy,
+                        qz,
+                        qw,
+                        metadata: Default::default(),
+                        sequence,
+                    }]);
+
+                    let message = create_message(components);
+                    messages.push((message, Some(vec![id.0]), None, None));
                 }
+            } else if metadata.is_dirty() {
+                // metadata changed on the server side, such as the held item
+                let Vec3(px, py, pz) = body.get_head_position();
+                let Quaternion(qx, qy, qz, qw) = rotation.0.clone();
+
+                peers_update.insert(
+                    id.0,
+                    PeerProtocol {
+                        id: id.0.to_string(),
+                        name: name.0.clone().unwrap_or_default(),
+                        px,
+                        py,
+                        pz,
+                        qx,
+                        qy,
+                        qz,
+                        qw,
+                        metadata: metadata.take_dirty(),
+                        sequence: 0,
+                    },
+                );
+            }
+        }
+
+        // broadcast the simulated states, acknowledging the last processed inputs
+        if server_movement {
+            for (id, name, body, rotation, controls) in
+                (&ids, &names, &bodies, &rotations, &controls).join()
+            {
+                let name = match &name.0 {
+                    Some(name) => name.to_owned(),
+                    None => continue,
+                };
+
+                let Vec3(px, py, pz) = body.get_head_position();
+                let Quaternion(qx, qy, qz, qw) = rotation.0.clone();
+
+                let state = PeerProtocol {
+                    id: id.0.to_string(),
+                    name,
+                    px,
+                    py,
+                    pz,
+                    qx,
+                    qy,
+                    qz,
+                    qw,
+                    metadata: Default::default(),
+                    sequence: controls.sequence,
+                };
+
+                let mut components = MessageComponents::default_for(MessageType::Ack);
+                components.peers = Some(vec![state.clone()]);
+
+                let message = create_message(components);
+                messages.push((message, Some(vec![id.0]), None, None));
+
+                // keep the metadata diff of this tick, if any
+                let entry = peers_update.entry(id.0).or_insert_with(|| state.clone());
+                let metadata = std::mem::take(&mut entry.metadata);
+                *entry = PeerProtocol { metadata, ..state };
             }
         }
 
-        for id in ids.join() {
+        // keep everyone's player list in sync, regardless of view radius
+        for joined_id in joined.iter() {
+            if let Some(player) = players.get(joined_id) {
+                let mut components = MessageComponents::default_for(MessageType::Players);
+                components.players = Some(vec![player.get_list_entry(*joined_id, &teams)]);
+
+                let message = create_message(components);
+                messages.push((message, None, Some(vec![*joined_id]), None));
+            }
+
+            let mut components = MessageComponents::default_for(MessageType::Players);
+            components.players = Some(players.get_player_list(&teams));
+
+            let message = create_message(components);
+            messages.push((message, Some(vec![*joined_id]), None, None));
+        }
+
+        let dimension = chunks.config.dimension as f32;
+
+        // newly joined players receive the full metadata of everyone around
+        for (id, body, radius) in (&ids, &bodies, &radiuses).join() {
+            if !joined.contains(&id.0) {
+                continue;
+            }
+
+            let position = body.get_head_position();
+            let max_dist = radius.0 as f32 * dimension;
+
+            let peers = (&ids, &names, &bodies, &rotations, &metadatas)
+                .join()
+                .filter(|(other, name, other_body, ..)| {
+                    other.0 != id.0
+                        && name.0.is_some()
+                        && other_body.get_head_position().sub(&position).len() <= max_dist
+                })
+                .map(|(other, name, other_body, rotation, metadata)| {
+                    let Vec3(px, py, pz) = other_body.get_head_position();
+                    let Quaternion(qx, qy, qz, qw) = rotation.0.clone();
+
+                    PeerProtocol {
+                        id: other.0.to_string(),
+                        name: name.0.clone().unwrap_or_default(),
+                        px,
+                        py,
+                        pz,
+                        qx,
+                        qy,
+                        qz,
+                        qw,
+                        metadata: metadata.data.clone(),
+                        sequence: 0,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            if !peers.is_empty() {
+                let mut components = MessageComponents::default_for(MessageType::Peer);
+                components.peers = Some(peers);
+
+                let message = create_message(components);
+                messages.push((message, Some(vec![id.0]), None, None));
+            }
+        }
+
+        for (id, body, radius) in (&ids, &bodies, &radiuses).join() {
+            let position = body.get_head_position();
+            let max_dist = radius.0 as f32 * dimension;
+
+            // only send peers within the player's view radius
             let updates = peers_update
                 .iter()
-                .filter(|(&i, ..)| i != id.0)
+                .filter(|(&i, peer)| {
+                    i != id.0 && Vec3(peer.px, peer.py, peer.pz).sub(&position).len() <= max_dist
+                })
                 .collect::<HashMap<_, _>>()
                 .values()
                 .map(|p| p.to_owned().to_owned())
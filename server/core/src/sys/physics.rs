@@ -1,30 +1,65 @@
-use specs::{ReadExpect, System, WriteStorage};
+use specs::{Entities, ReadExpect, ReadStorage, System, WriteExpect, WriteStorage};
 
-use crate::{comp::rigidbody::RigidBody, engine::physics::Physics};
+use crate::{
+    comp::{mount::Mounted, rigidbody::RigidBody},
+    engine::{
+        physics::Physics,
+        sweep_recorder::{SweepRecorder, Traces},
+    },
+};
 
 use super::super::engine::{chunks::Chunks, clock::Clock};
 
 pub struct PhysicsSystem;
 
 impl<'a> System<'a> for PhysicsSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         ReadExpect<'a, Physics>,
         ReadExpect<'a, Clock>,
         ReadExpect<'a, Chunks>,
+        WriteExpect<'a, SweepRecorder>,
+        Entities<'a>,
+        ReadStorage<'a, Mounted>,
         WriteStorage<'a, RigidBody>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         use specs::Join;
 
-        let (core, clock, chunks, mut bodies) = data;
+        let (core, clock, chunks, mut recorder, entities, mounts, mut bodies) = data;
 
         let test_solid =
             |x: i32, y: i32, z: i32| -> bool { !chunks.get_walkable_by_voxel(x, y, z) };
-        let test_fluid = |_, _, _| false;
+        let test_fluid = |x: i32, y: i32, z: i32| -> bool { chunks.get_fluidity_by_voxel(x, y, z) };
 
-        for body in (&mut bodies).join() {
-            core.iterate_body(body, clock.delta_secs(), &test_solid, &test_fluid);
+        // riders move along with their mounts instead
+        let get_modifiers = |x: i32, y: i32, z: i32| chunks.get_modifiers_by_voxel(x, y, z);
+
+        let recorded = recorder.target();
+
+        for (entity, body, _) in (&entities, &mut bodies, !&mounts).join() {
+            if recorded == Some(entity) {
+                let traces = Traces::default();
+                core.iterate_body_traced(
+                    body,
+                    clock.delta_secs(),
+                    &test_solid,
+                    &test_fluid,
+                    &get_modifiers,
+                    &traces,
+                );
+                recorder.record(clock.tick, traces.into_inner());
+                continue;
+            }
+
+            core.iterate_body(
+                body,
+                clock.delta_secs(),
+                &test_solid,
+                &test_fluid,
+                &get_modifiers,
+            );
         }
     }
 }
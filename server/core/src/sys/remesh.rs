@@ -0,0 +1,44 @@
+use specs::{System, WriteExpect};
+
+use crate::{
+    engine::{
+        chunks::{Chunks, MeshLevel},
+        world::MessagesQueue,
+    },
+    network::models::{create_message, MessageComponents, MessageType},
+};
+
+/// Remeshes the chunks edits made stale during the tick, sending them out in one update
+pub struct RemeshSystem;
+
+impl<'a> System<'a> for RemeshSystem {
+    type SystemData = (WriteExpect<'a, Chunks>, WriteExpect<'a, MessagesQueue>);
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut chunks, mut messages) = data;
+
+        if chunks.invalidated.is_empty() {
+            return;
+        }
+
+        let remeshed = chunks.remesh_invalidated();
+
+        if remeshed.is_empty() {
+            return;
+        }
+
+        let mut chunk_protocols = vec![];
+
+        for (coords, level) in remeshed {
+            let chunk = chunks.get_chunk(&coords).unwrap();
+
+            chunk_protocols.push(chunk.get_protocol(false, false, true, MeshLevel::None));
+            chunk_protocols.push(chunk.get_protocol(true, false, false, level));
+        }
+
+        let mut components = MessageComponents::default_for(MessageType::Update);
+        components.chunks = Some(chunk_protocols);
+
+        messages.push((create_message(components), None, None, None));
+    }
+}
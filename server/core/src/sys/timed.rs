@@ -0,0 +1,65 @@
+use std::time::Instant;
+
+use specs::{DispatcherBuilder, System};
+
+use crate::engine::profiler::Profiler;
+
+/// Wraps a system to record how long it runs into the profiler while profiling
+pub struct Timed<S> {
+    name: &'static str,
+    system: S,
+    profiler: Profiler,
+}
+
+impl<S> Timed<S> {
+    pub fn new(name: &'static str, system: S, profiler: &Profiler) -> Self {
+        Self {
+            name,
+            system,
+            profiler: profiler.clone(),
+        }
+    }
+}
+
+impl<'a, S: System<'a>> System<'a> for Timed<S> {
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        if !self.profiler.is_active() {
+            self.system.run(data);
+            return;
+        }
+
+        let started = Instant::now();
+        self.system.run(data);
+        self.profiler.record(self.name, started.elapsed());
+    }
+}
+
+/// Adds systems to a dispatcher, timed by the profiler under their name
+pub trait TimedExt {
+    fn with_timed<S>(
+        self,
+        system: S,
+        name: &'static str,
+        dependencies: &[&str],
+        profiler: &Profiler,
+    ) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static;
+}
+
+impl<'a, 'b> TimedExt for DispatcherBuilder<'a, 'b> {
+    fn with_timed<S>(
+        self,
+        system: S,
+        name: &'static str,
+        dependencies: &[&str],
+        profiler: &Profiler,
+    ) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        self.with(Timed::new(name, system, profiler), name, dependencies)
+    }
+}
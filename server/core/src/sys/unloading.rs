@@ -0,0 +1,64 @@
+use specs::{ReadExpect, ReadStorage, System, WriteExpect};
+
+use crate::{
+    comp::{curr_chunk::CurrChunk, view_radius::ViewRadius},
+    engine::{config::WorldConfig, players::Players, world::MessagesQueue},
+    network::models::{create_message, MessageComponents, MessageType},
+};
+
+pub struct UnloadingSystem;
+
+impl<'a> System<'a> for UnloadingSystem {
+    type SystemData = (
+        ReadExpect<'a, WorldConfig>,
+        WriteExpect<'a, Players>,
+        WriteExpect<'a, MessagesQueue>,
+        ReadStorage<'a, CurrChunk>,
+        ReadStorage<'a, ViewRadius>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (config, mut players, mut messages, curr_chunks, radiuses) = data;
+        let chunk_size = config.chunk_size;
+
+        players.iter_mut().for_each(|(id, player)| {
+            let center = match curr_chunks.get(player.entity) {
+                Some(CurrChunk {
+                    val: Some(center), ..
+                }) => center,
+                _ => return,
+            };
+
+            let radius = match radiuses.get(player.entity) {
+                Some(radius) => radius,
+                None => return,
+            };
+
+            // chunks that left the player's interest area
+            let unloads = player
+                .sent_chunks
+                .iter()
+                .filter(|coords| !radius.loosely_covers_chunk(center, coords, chunk_size))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            if unloads.is_empty() {
+                return;
+            }
+
+            unloads.iter().for_each(|coords| {
+                player.sent_chunks.remove(coords);
+            });
+
+            player
+                .requested_chunks
+                .retain(|coords| radius.loosely_covers_chunk(center, coords, chunk_size));
+
+            let mut components = MessageComponents::default_for(MessageType::Unload);
+            components.unloads = Some(unloads);
+
+            let new_message = create_message(components);
+            messages.push((new_message, Some(vec![id.to_owned()]), None, None));
+        });
+    }
+}
@@ -0,0 +1,62 @@
+use specs::{Entities, Entity, ReadExpect, ReadStorage, System, WriteStorage};
+
+use hashbrown::HashMap;
+
+use crate::{
+    comp::{
+        controls::Controls,
+        mount::Mounted,
+        rigidbody::RigidBody,
+        vehicle::{Vehicle, VehicleKind},
+    },
+    engine::{
+        chunks::Chunks,
+        clock::Clock,
+        config::WorldConfig,
+        vehicles::{float_boat, ride_rails},
+    },
+};
+
+/// Runs the boat and minecart integrators, steered by their riders, ahead of the physics sweep
+pub struct VehiclesSystem;
+
+impl<'a> System<'a> for VehiclesSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Clock>,
+        ReadExpect<'a, Chunks>,
+        ReadExpect<'a, WorldConfig>,
+        ReadStorage<'a, Vehicle>,
+        ReadStorage<'a, Mounted>,
+        ReadStorage<'a, Controls>,
+        WriteStorage<'a, RigidBody>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        use specs::Join;
+
+        let (entities, clock, chunks, config, vehicles, mounts, controls, mut bodies) = data;
+
+        let dt = clock.delta_secs();
+        let dimension = config.dimension as f32;
+
+        // riders steer whatever they ride towards where they're heading
+        let thrusts: HashMap<Entity, f32> = (&mounts, &controls)
+            .join()
+            .filter_map(|(mounted, controls)| Some((mounted.mount, controls.heading()?)))
+            .collect();
+
+        let test_fluid = |x: i32, y: i32, z: i32| chunks.get_fluidity_by_voxel(x, y, z);
+        let is_rail = |x: i32, y: i32, z: i32| chunks.get_rail_by_voxel(x, y, z);
+
+        for (entity, vehicle, body) in (&*entities, &vehicles, &mut bodies).join() {
+            let thrust = thrusts.get(&entity).copied();
+
+            match vehicle.0 {
+                VehicleKind::Boat => float_boat(body, dt, dimension, thrust, &test_fluid),
+                VehicleKind::Minecart => ride_rails(body, dt, dimension, thrust, &is_rail),
+            };
+        }
+    }
+}
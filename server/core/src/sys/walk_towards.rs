@@ -4,7 +4,7 @@ use specs::{ReadExpect, ReadStorage, System, WriteStorage};
 
 use crate::{
     comp::{brain::Brain, rigidbody::RigidBody, walk_towards::WalkTowards},
-    engine::{clock::Clock, world::WorldConfig},
+    engine::{clock::Clock, config::WorldConfig},
 };
 
 pub struct WalkTowardsSystem;
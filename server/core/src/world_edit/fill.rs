@@ -0,0 +1,50 @@
+use std::collections::HashSet;
+
+use crate::engine::{chunks::Chunks, world::World};
+
+use super::schematic::Region;
+
+/// Most voxels a single fill may set
+pub const MAX_FILL_VOLUME: usize = 32_768;
+
+/// Set every voxel of a region to a block, or only the ones of `replace` if given
+///
+/// Done as the player so that it can be undone. Returns the number of voxels updated.
+pub fn fill(
+    world: &mut World,
+    player_id: usize,
+    region: &Region,
+    id: u32,
+    replace: Option<&HashSet<u32>>,
+) -> usize {
+    let targets = {
+        let chunks = world.read_resource::<Chunks>();
+        let mut targets = vec![];
+
+        for vx in region.min.0..=region.max.0 {
+            for vy in region.min.1..=region.max.1 {
+                if !chunks.config.contains_height(vy) {
+                    continue;
+                }
+
+                for vz in region.min.2..=region.max.2 {
+                    let current = chunks.get_voxel_by_voxel(vx, vy, vz);
+
+                    if current != id && replace.is_none_or(|ids| ids.contains(&current)) {
+                        targets.push((vx, vy, vz));
+                    }
+                }
+            }
+        }
+
+        targets
+    };
+
+    world
+        .batch_update_as(player_id, |tx| {
+            for (vx, vy, vz) in targets {
+                tx.set(vx, vy, vz, id);
+            }
+        })
+        .len()
+}
@@ -0,0 +1,10 @@
+#[cfg(feature = "server")]
+mod fill;
+mod nbt;
+mod schematic;
+
+#[cfg(feature = "server")]
+pub use fill::{fill, MAX_FILL_VOLUME};
+pub use schematic::{load_schematic, PaletteEntry, Region, Schematic, MAX_SCHEMATIC_VOLUME};
+#[cfg(feature = "server")]
+pub use schematic::{paste, paste_schematic, save_schematic};
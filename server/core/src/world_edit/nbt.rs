@@ -0,0 +1,214 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+/// Deepest nesting of lists and compounds before giving up on a file
+const MAX_DEPTH: usize = 512;
+
+/// A named binary tag, Minecraft's format used by Sponge schematics
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(u8, Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    /// The id of this tag's type
+    pub fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(..) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    /// Get a child of a compound tag by name
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(children) => children
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, tag)| tag),
+            _ => None,
+        }
+    }
+
+    /// Get the value of any integer tag
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(v) => Some(*v as i64),
+            Tag::Short(v) => Some(*v as i64),
+            Tag::Int(v) => Some(*v as i64),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+/// Read a named root tag, returning its name and the tag
+pub fn read_root<R: Read>(reader: &mut R) -> io::Result<(String, Tag)> {
+    let id = reader.read_u8()?;
+    if id != 10 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Root tag is not a compound.",
+        ));
+    }
+
+    let name = read_string(reader)?;
+    let tag = read_payload(reader, id, 0)?;
+
+    Ok((name, tag))
+}
+
+/// Write a named root tag
+pub fn write_root<W: Write>(writer: &mut W, name: &str, tag: &Tag) -> io::Result<()> {
+    writer.write_u8(tag.id())?;
+    write_string(writer, name)?;
+    write_payload(writer, tag)
+}
+
+fn read_string<R: Read>(reader: &mut R) -> io::Result<String> {
+    let len = reader.read_u16::<BigEndian>()? as usize;
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes)?;
+
+    String::from_utf8(bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> io::Result<()> {
+    writer.write_u16::<BigEndian>(value.len() as u16)?;
+    writer.write_all(value.as_bytes())
+}
+
+fn read_len<R: Read>(reader: &mut R) -> io::Result<usize> {
+    let len = reader.read_i32::<BigEndian>()?;
+    if len < 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "Negative length."));
+    }
+
+    Ok(len as usize)
+}
+
+fn read_payload<R: Read>(reader: &mut R, id: u8, depth: usize) -> io::Result<Tag> {
+    if depth > MAX_DEPTH {
+        return Err(Error::new(ErrorKind::InvalidData, "Tags nested too deep."));
+    }
+
+    Ok(match id {
+        1 => Tag::Byte(reader.read_i8()?),
+        2 => Tag::Short(reader.read_i16::<BigEndian>()?),
+        3 => Tag::Int(reader.read_i32::<BigEndian>()?),
+        4 => Tag::Long(reader.read_i64::<BigEndian>()?),
+        5 => Tag::Float(reader.read_f32::<BigEndian>()?),
+        6 => Tag::Double(reader.read_f64::<BigEndian>()?),
+        7 => {
+            let len = read_len(reader)?;
+            let mut bytes = vec![0; len];
+            reader.read_i8_into(&mut bytes)?;
+            Tag::ByteArray(bytes)
+        }
+        8 => Tag::String(read_string(reader)?),
+        9 => {
+            let child_id = reader.read_u8()?;
+            let len = read_len(reader)?;
+            let children = (0..len)
+                .map(|_| read_payload(reader, child_id, depth + 1))
+                .collect::<io::Result<Vec<_>>>()?;
+            Tag::List(child_id, children)
+        }
+        10 => {
+            let mut children = vec![];
+            loop {
+                let child_id = reader.read_u8()?;
+                if child_id == 0 {
+                    break;
+                }
+
+                let name = read_string(reader)?;
+                children.push((name, read_payload(reader, child_id, depth + 1)?));
+            }
+            Tag::Compound(children)
+        }
+        11 => {
+            let len = read_len(reader)?;
+            let mut values = vec![0; len];
+            reader.read_i32_into::<BigEndian>(&mut values)?;
+            Tag::IntArray(values)
+        }
+        12 => {
+            let len = read_len(reader)?;
+            let mut values = vec![0; len];
+            reader.read_i64_into::<BigEndian>(&mut values)?;
+            Tag::LongArray(values)
+        }
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unknown tag id: {}", id),
+            ))
+        }
+    })
+}
+
+fn write_payload<W: Write>(writer: &mut W, tag: &Tag) -> io::Result<()> {
+    match tag {
+        Tag::Byte(v) => writer.write_i8(*v),
+        Tag::Short(v) => writer.write_i16::<BigEndian>(*v),
+        Tag::Int(v) => writer.write_i32::<BigEndian>(*v),
+        Tag::Long(v) => writer.write_i64::<BigEndian>(*v),
+        Tag::Float(v) => writer.write_f32::<BigEndian>(*v),
+        Tag::Double(v) => writer.write_f64::<BigEndian>(*v),
+        Tag::ByteArray(values) => {
+            writer.write_i32::<BigEndian>(values.len() as i32)?;
+            values.iter().try_for_each(|v| writer.write_i8(*v))
+        }
+        Tag::String(value) => write_string(writer, value),
+        Tag::List(child_id, children) => {
+            writer.write_u8(*child_id)?;
+            writer.write_i32::<BigEndian>(children.len() as i32)?;
+            children
+                .iter()
+                .try_for_each(|child| write_payload(writer, child))
+        }
+        Tag::Compound(children) => {
+            for (name, child) in children {
+                writer.write_u8(child.id())?;
+                write_string(writer, name)?;
+                write_payload(writer, child)?;
+            }
+            writer.write_u8(0)
+        }
+        Tag::IntArray(values) => {
+            writer.write_i32::<BigEndian>(values.len() as i32)?;
+            values
+                .iter()
+                .try_for_each(|v| writer.write_i32::<BigEndian>(*v))
+        }
+        Tag::LongArray(values) => {
+            writer.write_i32::<BigEndian>(values.len() as i32)?;
+            values
+                .iter()
+                .try_for_each(|v| writer.write_i64::<BigEndian>(*v))
+        }
+    }
+}
@@ -0,0 +1,638 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use libflate::{gzip, zlib};
+#[cfg(feature = "server")]
+use log::info;
+
+use std::{
+    fs,
+    io::{self, Cursor, Error, ErrorKind, Read, Write},
+    path::Path,
+};
+
+use server_common::vec::Vec3;
+
+#[cfg(feature = "server")]
+use crate::engine::world::World;
+use crate::{engine::chunks::Chunks, gen::blocks::BlockRotation};
+
+use super::nbt::{self, Tag};
+
+const NATIVE_MAGIC: &[u8; 4] = b"MJSC";
+const NATIVE_VERSION: u8 = 1;
+
+const SPONGE_VERSION: i32 = 2;
+const SPONGE_DATA_VERSION: i32 = 2586;
+const SPONGE_NAMESPACE: &str = "minecraft:";
+
+/// Most blocks a schematic copies out of a world, 256 cubed
+pub const MAX_SCHEMATIC_VOLUME: usize = 1 << 24;
+
+/// Inclusive box of voxel coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub min: Vec3<i32>,
+    pub max: Vec3<i32>,
+}
+
+impl Region {
+    /// Create a region spanning two corners in any order
+    pub fn new(a: Vec3<i32>, b: Vec3<i32>) -> Self {
+        Self {
+            min: Vec3(a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+            max: Vec3(a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+        }
+    }
+
+    /// Width, height and length of the region in voxels
+    pub fn size(&self) -> (usize, usize, usize) {
+        // spans of corners far apart don't fit an i32
+        let span = |min: i32, max: i32| (max as i64 - min as i64 + 1) as usize;
+
+        (
+            span(self.min.0, self.max.0),
+            span(self.min.1, self.max.1),
+            span(self.min.2, self.max.2),
+        )
+    }
+
+    /// Number of voxels in the region, none if it doesn't fit a usize
+    pub fn volume(&self) -> Option<usize> {
+        let (width, height, length) = self.size();
+        width.checked_mul(height)?.checked_mul(length)
+    }
+
+    /// Make sure a schematic can be copied out of the region, its sides fitting the schematic
+    /// formats and its volume within `MAX_SCHEMATIC_VOLUME`
+    pub fn check_schematic(&self) -> io::Result<()> {
+        let (width, height, length) = self.size();
+        let max_side = u16::MAX as usize;
+
+        if width > max_side || height > max_side || length > max_side {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Region is too large for a schematic.",
+            ));
+        }
+
+        match self.volume() {
+            Some(volume) if volume <= MAX_SCHEMATIC_VOLUME => Ok(()),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Region is too large for a schematic.",
+            )),
+        }
+    }
+}
+
+/// A block in a schematic palette, stored by name so that schematics can move between
+/// servers with different block ids
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteEntry {
+    pub name: String,
+    pub rotation: BlockRotation,
+}
+
+/// A box of blocks copied out of a world
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schematic {
+    pub width: usize,
+    pub height: usize,
+    pub length: usize,
+    pub palette: Vec<PaletteEntry>,
+    /// Palette indices ordered by x, then z, then y, same as Sponge schematics
+    pub blocks: Vec<usize>,
+}
+
+impl Schematic {
+    /// Copy the blocks within a region, unloaded chunks are copied as air
+    ///
+    /// The region should pass `Region::check_schematic`.
+    pub fn from_chunks(chunks: &Chunks, region: &Region) -> Self {
+        let (width, height, length) = region.size();
+
+        let mut palette: Vec<PaletteEntry> = vec![];
+        let mut blocks = Vec::with_capacity(width * height * length);
+
+        for y in 0..height as i32 {
+            for z in 0..length as i32 {
+                for x in 0..width as i32 {
+                    let Vec3(vx, vy, vz) =
+                        Vec3(region.min.0 + x, region.min.1 + y, region.min.2 + z);
+
+                    let id = chunks.get_voxel_by_voxel(vx, vy, vz);
+                    let rotation = if chunks.get_chunk_by_voxel(vx, vy, vz).is_some() {
+                        chunks.get_voxel_rotation_by_voxel(vx, vy, vz)
+                    } else {
+                        BlockRotation::PY(0)
+                    };
+
+                    let entry = PaletteEntry {
+                        name: chunks.registry.get_block_by_id(id).name.to_owned(),
+                        rotation,
+                    };
+
+                    let index = match palette.iter().position(|e| *e == entry) {
+                        Some(index) => index,
+                        None => {
+                            palette.push(entry);
+                            palette.len() - 1
+                        }
+                    };
+
+                    blocks.push(index);
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            length,
+            palette,
+            blocks,
+        }
+    }
+
+    /// Make sure the block data matches the dimensions and palette
+    fn validate(self) -> io::Result<Self> {
+        if self.blocks.len() != self.width * self.height * self.length {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Block data does not match schematic size.",
+            ));
+        }
+
+        if self.blocks.iter().any(|&index| index >= self.palette.len()) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Block data refers outside of palette.",
+            ));
+        }
+
+        Ok(self)
+    }
+
+    /// Encode into the native compact format
+    ///
+    /// `MJSC`, a version byte, then a zlib-compressed body of little-endian u16 sizes,
+    /// the palette of names and rotations, and a u16 palette index per block.
+    pub fn to_native(&self) -> io::Result<Vec<u8>> {
+        let mut body = vec![];
+
+        body.write_u16::<LittleEndian>(self.width as u16)?;
+        body.write_u16::<LittleEndian>(self.height as u16)?;
+        body.write_u16::<LittleEndian>(self.length as u16)?;
+
+        body.write_u16::<LittleEndian>(self.palette.len() as u16)?;
+        for PaletteEntry { name, rotation } in self.palette.iter() {
+            let (rotation, y_rotation) = BlockRotation::decode(rotation);
+
+            body.write_u16::<LittleEndian>(name.len() as u16)?;
+            body.write_all(name.as_bytes())?;
+            body.write_u8(rotation as u8)?;
+            body.write_u8(y_rotation as u8)?;
+        }
+
+        for &index in self.blocks.iter() {
+            body.write_u16::<LittleEndian>(index as u16)?;
+        }
+
+        let mut encoder = zlib::Encoder::new(Vec::new())?;
+        encoder.write_all(&body)?;
+        let body = encoder.finish().into_result()?;
+
+        let mut buf = NATIVE_MAGIC.to_vec();
+        buf.push(NATIVE_VERSION);
+        buf.extend(body);
+
+        Ok(buf)
+    }
+
+    /// Decode from the native compact format
+    pub fn from_native(buf: &[u8]) -> io::Result<Self> {
+        if buf.len() < 5 || &buf[0..4] != NATIVE_MAGIC || buf[4] != NATIVE_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Not a native schematic.",
+            ));
+        }
+
+        let mut body = vec![];
+        zlib::Decoder::new(&buf[5..])?.read_to_end(&mut body)?;
+        let mut reader = Cursor::new(body);
+
+        let width = reader.read_u16::<LittleEndian>()? as usize;
+        let height = reader.read_u16::<LittleEndian>()? as usize;
+        let length = reader.read_u16::<LittleEndian>()? as usize;
+
+        let palette_len = reader.read_u16::<LittleEndian>()? as usize;
+        let mut palette = Vec::with_capacity(palette_len);
+        for _ in 0..palette_len {
+            let len = reader.read_u16::<LittleEndian>()? as usize;
+            let mut name = vec![0; len];
+            reader.read_exact(&mut name)?;
+            let name =
+                String::from_utf8(name).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+            let rotation = reader.read_u8()? as u32;
+            let y_rotation = reader.read_u8()? as u32;
+            if rotation > 5 || y_rotation > 7 {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Unknown block rotation.",
+                ));
+            }
+
+            palette.push(PaletteEntry {
+                name,
+                rotation: BlockRotation::encode(rotation, y_rotation),
+            });
+        }
+
+        let mut blocks = Vec::with_capacity(width * height * length);
+        for _ in 0..width * height * length {
+            blocks.push(reader.read_u16::<LittleEndian>()? as usize);
+        }
+
+        Self {
+            width,
+            height,
+            length,
+            palette,
+            blocks,
+        }
+        .validate()
+    }
+
+    /// Encode into a gzipped Sponge schematic (version 2)
+    ///
+    /// Block rotations are not kept, as they have no direct Minecraft counterpart.
+    pub fn to_sponge(&self) -> io::Result<Vec<u8>> {
+        let mut names: Vec<String> = vec![];
+        let mut remap = Vec::with_capacity(self.palette.len());
+
+        for entry in self.palette.iter() {
+            let name = to_sponge_name(&entry.name);
+            let index = match names.iter().position(|n| *n == name) {
+                Some(index) => index,
+                None => {
+                    names.push(name);
+                    names.len() - 1
+                }
+            };
+            remap.push(index as u32);
+        }
+
+        let mut block_data = vec![];
+        self.blocks
+            .iter()
+            .for_each(|&index| write_varint(&mut block_data, remap[index]));
+
+        let palette = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.to_owned(), Tag::Int(index as i32)))
+            .collect();
+
+        let root = Tag::Compound(vec![
+            ("Version".to_owned(), Tag::Int(SPONGE_VERSION)),
+            ("DataVersion".to_owned(), Tag::Int(SPONGE_DATA_VERSION)),
+            ("Width".to_owned(), Tag::Short(self.width as u16 as i16)),
+            ("Height".to_owned(), Tag::Short(self.height as u16 as i16)),
+            ("Length".to_owned(), Tag::Short(self.length as u16 as i16)),
+            ("Offset".to_owned(), Tag::IntArray(vec![0, 0, 0])),
+            ("PaletteMax".to_owned(), Tag::Int(names.len() as i32)),
+            ("Palette".to_owned(), Tag::Compound(palette)),
+            ("BlockData".to_owned(), Tag::ByteArray(block_data)),
+            ("BlockEntities".to_owned(), Tag::List(10, vec![])),
+        ]);
+
+        let mut encoder = gzip::Encoder::new(Vec::new())?;
+        nbt::write_root(&mut encoder, "Schematic", &root)?;
+
+        encoder.finish().into_result()
+    }
+
+    /// Decode from a gzipped Sponge schematic
+    pub fn from_sponge(buf: &[u8]) -> io::Result<Self> {
+        let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_owned());
+
+        let mut decoder = gzip::Decoder::new(buf)?;
+        let (_, root) = nbt::read_root(&mut decoder)?;
+
+        let size = |name: &str| {
+            root.get(name)
+                .and_then(|tag| tag.as_i64())
+                .map(|v| v as u16 as usize)
+                .ok_or_else(|| invalid("Missing schematic size."))
+        };
+
+        let width = size("Width")?;
+        let height = size("Height")?;
+        let length = size("Length")?;
+
+        let mut palette = vec![];
+        match root.get("Palette") {
+            Some(Tag::Compound(entries)) => {
+                for (key, index) in entries.iter() {
+                    let index = index
+                        .as_i64()
+                        .filter(|&i| i >= 0 && (i as usize) < u16::MAX as usize)
+                        .ok_or_else(|| invalid("Invalid palette index."))?
+                        as usize;
+
+                    if palette.len() <= index {
+                        palette.resize(
+                            index + 1,
+                            PaletteEntry {
+                                name: "Air".to_owned(),
+                                rotation: BlockRotation::PY(0),
+                            },
+                        );
+                    }
+
+                    palette[index].name = from_sponge_name(key);
+                }
+            }
+            _ => return Err(invalid("Missing palette.")),
+        }
+
+        let blocks = match root.get("BlockData") {
+            Some(Tag::ByteArray(data)) => read_varints(data)?,
+            _ => return Err(invalid("Missing block data.")),
+        };
+
+        Self {
+            width,
+            height,
+            length,
+            palette,
+            blocks,
+        }
+        .validate()
+    }
+}
+
+/// "Oak Log" becomes "minecraft:oak_log"
+fn to_sponge_name(name: &str) -> String {
+    format!(
+        "{}{}",
+        SPONGE_NAMESPACE,
+        name.to_lowercase().replace(' ', "_")
+    )
+}
+
+/// "minecraft:oak_log[axis=y]" becomes "Oak Log"
+fn from_sponge_name(key: &str) -> String {
+    let name = key.split('[').next().unwrap_or_default();
+    let name = name.rsplit(':').next().unwrap_or_default();
+
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn write_varint(buf: &mut Vec<i8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buf.push(byte as i8);
+            break;
+        }
+
+        buf.push((byte | 0x80) as i8);
+    }
+}
+
+fn read_varints(data: &[i8]) -> io::Result<Vec<usize>> {
+    let mut values = vec![];
+    let mut value = 0usize;
+    let mut shift = 0;
+
+    for &byte in data.iter() {
+        let byte = byte as u8;
+        value |= ((byte & 0x7F) as usize) << shift;
+
+        if byte & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+            shift = 0;
+        } else {
+            shift += 7;
+            if shift > 28 {
+                return Err(Error::new(ErrorKind::InvalidData, "Varint too long."));
+            }
+        }
+    }
+
+    Ok(values)
+}
+
+/// Turn a block rotation a number of quarter turns around the y axis
+fn rotate_block(rotation: &BlockRotation, turns: u32) -> BlockRotation {
+    let mut rotation = rotation.clone();
+
+    for _ in 0..turns {
+        rotation = match rotation {
+            BlockRotation::PY(y) => BlockRotation::PY((y + 90) % 360),
+            BlockRotation::NY(y) => BlockRotation::NY((y + 90) % 360),
+            BlockRotation::PX(y) => BlockRotation::PZ(y),
+            BlockRotation::PZ(y) => BlockRotation::NX(y),
+            BlockRotation::NX(y) => BlockRotation::NZ(y),
+            BlockRotation::NZ(y) => BlockRotation::PX(y),
+        };
+    }
+
+    rotation
+}
+
+/// Read a schematic file, Sponge if it ends with `.schem` and native otherwise
+pub fn load_schematic(path: &Path) -> io::Result<Schematic> {
+    let buf = fs::read(path)?;
+
+    if path.extension().is_some_and(|ext| ext == "schem") {
+        Schematic::from_sponge(&buf)
+    } else {
+        Schematic::from_native(&buf)
+    }
+}
+
+/// Save the blocks within a region to a schematic file, Sponge if it ends with `.schem`
+/// and native otherwise
+#[cfg(feature = "server")]
+pub fn save_schematic(world: &World, region: &Region, path: &Path) -> io::Result<()> {
+    region.check_schematic()?;
+
+    let schematic = Schematic::from_chunks(&world.read_resource::<Chunks>(), region);
+
+    let buf = if path.extension().is_some_and(|ext| ext == "schem") {
+        schematic.to_sponge()?
+    } else {
+        schematic.to_native()?
+    };
+
+    fs::write(path, buf)
+}
+
+/// Paste a schematic file with its minimum corner at origin, turned by rotation degrees
+/// (0, 90, 180 or 270) around the y axis
+///
+/// Blocks unknown to this server are skipped. Returns the number of voxels updated.
+#[cfg(feature = "server")]
+pub fn paste_schematic(
+    world: &mut World,
+    path: &Path,
+    origin: Vec3<i32>,
+    rotation: u32,
+) -> io::Result<usize> {
+    paste(world, &load_schematic(path)?, origin, rotation)
+}
+
+/// Paste a schematic already in memory, see `paste_schematic`
+#[cfg(feature = "server")]
+pub fn paste(
+    world: &mut World,
+    schematic: &Schematic,
+    origin: Vec3<i32>,
+    rotation: u32,
+) -> io::Result<usize> {
+    if !rotation.is_multiple_of(90) || rotation >= 360 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Rotation must be 0, 90, 180 or 270.",
+        ));
+    }
+
+    let turns = rotation / 90;
+
+    let (ids, skipped) = {
+        let chunks = world.read_resource::<Chunks>();
+        let ids = schematic
+            .palette
+            .iter()
+            .map(|entry| chunks.registry.try_get_id_by_name(&entry.name))
+            .collect::<Vec<_>>();
+        let skipped = schematic
+            .palette
+            .iter()
+            .zip(ids.iter())
+            .filter(|(_, id)| id.is_none())
+            .map(|(entry, _)| entry.name.to_owned())
+            .collect::<Vec<_>>();
+
+        (ids, skipped)
+    };
+
+    if !skipped.is_empty() {
+        info!("Skipping unknown schematic blocks: {}", skipped.join(", "));
+    }
+
+    let Schematic {
+        width,
+        length,
+        palette,
+        blocks,
+        ..
+    } = schematic;
+    let (width, length) = (*width, *length);
+
+    let updates = world.batch_update(|tx| {
+        for (i, &index) in blocks.iter().enumerate() {
+            let id = match ids[index] {
+                Some(id) => id,
+                None => continue,
+            };
+
+            let x = (i % width) as i32;
+            let z = ((i / width) % length) as i32;
+            let y = (i / (width * length)) as i32;
+
+            let (x, z) = match turns {
+                1 => (-z, x),
+                2 => (-x, -z),
+                3 => (z, -x),
+                _ => (x, z),
+            };
+
+            tx.set_rotated(
+                origin.0 + x,
+                origin.1 + y,
+                origin.2 + z,
+                id,
+                &rotate_block(&palette[index].rotation, turns),
+            );
+        }
+    });
+
+    Ok(updates.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Schematic {
+        Schematic {
+            width: 2,
+            height: 1,
+            length: 2,
+            palette: vec![
+                PaletteEntry {
+                    name: "Air".to_owned(),
+                    rotation: BlockRotation::PY(0),
+                },
+                PaletteEntry {
+                    name: "Oak Log".to_owned(),
+                    rotation: BlockRotation::PX(0),
+                },
+            ],
+            blocks: vec![0, 1, 1, 0],
+        }
+    }
+
+    #[test]
+    fn native_round_trip() {
+        let schematic = sample();
+        let decoded = Schematic::from_native(&schematic.to_native().unwrap()).unwrap();
+        assert_eq!(decoded, schematic);
+    }
+
+    #[test]
+    fn sponge_round_trip() {
+        let schematic = sample();
+        let decoded = Schematic::from_sponge(&schematic.to_sponge().unwrap()).unwrap();
+
+        // rotations are dropped in sponge schematics
+        assert_eq!(decoded.blocks, schematic.blocks);
+        assert_eq!(decoded.palette[1].name, "Oak Log");
+        assert_eq!(decoded.palette[1].rotation, BlockRotation::PY(0));
+        assert_eq!(
+            from_sponge_name("minecraft:stone_bricks[x=y]"),
+            "Stone Bricks"
+        );
+    }
+
+    #[test]
+    fn region_sizes() {
+        let region = Region::new(Vec3(2, 0, -1), Vec3(0, 3, 1));
+        assert_eq!(region.size(), (3, 4, 3));
+        assert_eq!(region.volume(), Some(36));
+        assert!(region.check_schematic().is_ok());
+
+        // corners as far apart as they go don't wrap around
+        let region = Region::new(Vec3(i32::MIN, i32::MIN, 0), Vec3(i32::MAX, i32::MAX, 0));
+        assert_eq!(region.size(), (1 << 32, 1 << 32, 1));
+        assert_eq!(region.volume(), None);
+        assert!(region.check_schematic().is_err());
+    }
+}
@@ -0,0 +1,15 @@
+use server_core::gen::snapshot::{diff, load_golden, save_golden, snapshot_worlds, GOLDEN_FILE};
+
+/// Regenerate the golden chunk hashes, listing the chunks that changed
+fn main() {
+    let golden = load_golden();
+    let current = snapshot_worlds("assets/metadata/worlds.json");
+
+    for mismatch in diff(&golden, &current) {
+        println!("changed {}", mismatch);
+    }
+
+    save_golden(&current);
+
+    println!("Wrote {} chunk hashes to {}.", current.len(), GOLDEN_FILE);
+}
@@ -1,13 +1,7 @@
-use log::info;
+use server_core::{cli, network::admin::ConsoleLog};
 
-use actix::SystemService;
-use actix_cors::Cors;
-use actix_files as fs;
-use actix_web::{web, App, HttpServer};
-
-use server_core::network::{message, routes, server::WsServer};
-
-fn setup_logger() -> Result<(), fern::InitError> {
+/// Log to stdout, and into the console log the admin API streams
+fn setup_logger(console: ConsoleLog) -> Result<(), fern::InitError> {
     fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
@@ -20,6 +14,9 @@ fn setup_logger() -> Result<(), fern::InitError> {
         })
         .level(log::LevelFilter::Debug)
         .chain(std::io::stdout())
+        .chain(fern::Output::call(move |record| {
+            console.push(record.args().to_string())
+        }))
         // .chain(fern::log_file("output.log")?)
         .apply()?;
 
@@ -28,35 +25,13 @@ fn setup_logger() -> Result<(), fern::InitError> {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    setup_logger().expect("Something went wrong with fern...");
-
-    let addr = "localhost:4000";
-
-    let srv = HttpServer::new(move || {
-        let cors = Cors::permissive();
-
-        App::new()
-            .wrap(cors)
-            .route("/", web::get().to(routes::index))
-            .route("/biomes", web::get().to(routes::index))
-            .service(routes::worlds)
-            .service(routes::world)
-            .service(routes::time)
-            .service(web::resource("/ws/").to(routes::ws_route))
-            .service(fs::Files::new("/atlas/", "assets/textures/generated/").show_files_listing())
-            .service(
-                fs::Files::new("/procedural/", "assets/textures/procedural").show_files_listing(),
-            )
-            .service(fs::Files::new("/packs/", "assets/textures/packs/").show_files_listing())
-            .service(fs::Files::new("/models/", "assets/models/objects/").show_files_listing())
-            .service(fs::Files::new("/", "public/").show_files_listing())
-    })
-    .bind(&addr)?;
-
-    info!("🚀  MineJS running on http://{}", &addr);
+    let console = ConsoleLog::new();
+    setup_logger(console.clone()).expect("Something went wrong with fern...");
 
-    // Wake up the sever
-    WsServer::from_registry().do_send(message::Noop);
+    let args = std::env::args().collect::<Vec<_>>();
+    if cli::run(&args[1..]) {
+        return Ok(());
+    }
 
-    srv.run().await
+    server_core::network::app::serve(console).await
 }
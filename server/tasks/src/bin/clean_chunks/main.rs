@@ -4,7 +4,7 @@ use server_tasks::loop_through_chunks;
 fn main() {
     println!("Cleaning ./data and removing non-existent blocks + corrupted chunk files...\n");
 
-    loop_through_chunks(&|chunk, registry| {
+    loop_through_chunks(&|chunk, registry, storage| {
         let Vec3(start_x, start_y, start_z) = chunk.min;
         let Vec3(end_x, end_y, end_z) = chunk.max;
 
@@ -20,6 +20,6 @@ fn main() {
             }
         }
 
-        chunk.save();
+        chunk.save(storage);
     });
 }
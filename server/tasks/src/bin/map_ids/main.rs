@@ -28,7 +28,7 @@ fn main() {
             block_map.insert(old, new);
         }
 
-        loop_through_chunks(&|chunk, _| {
+        loop_through_chunks(&|chunk, _, storage| {
             let Vec3(start_x, start_y, start_z) = chunk.min;
             let Vec3(end_x, end_y, end_z) = chunk.max;
 
@@ -49,7 +49,7 @@ fn main() {
                 }
             }
 
-            chunk.save();
+            chunk.save(storage);
         });
 
         let j = serde_json::to_string(&fixes).unwrap();
@@ -1,4 +1,6 @@
 pub mod convert;
+pub mod hash;
 pub mod json;
 pub mod raycast;
+pub mod secret;
 pub mod sweep;
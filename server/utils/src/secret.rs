@@ -0,0 +1,9 @@
+/// Compare secrets in a time that only depends on their length, so that timing a guess doesn't
+/// tell how much of it is right
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
@@ -0,0 +1,376 @@
+//! Collision, block picking and lighting of the server, compiled to `wasm32-unknown-unknown`
+//!
+//! Web clients mirror the chunks they have loaded into a `Prediction`, then sweep their body,
+//! cast look rays and relight the blocks they place against it with the exact logic the server
+//! runs, instead of a JavaScript port that drifts from it. Chunks are kept in the server's own
+//! `ChunkData`, and blocks are the registry sent on join.
+//!
+//! Built with `wasm-pack build server/wasm`.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use wasm_bindgen::prelude::*;
+
+use server_common::{
+    aabb::Aabb,
+    chunk::{insert_id, ChunkData, DATA_PADDING},
+    lights::{LightColor, LightSpace, Lights},
+    math::approx_equals,
+    types::Block,
+    vec::{Vec2, Vec3},
+};
+use server_utils::{convert::map_voxel_to_chunk, raycast, sweep::sweep};
+
+/// The chunks a client has loaded, along with the blocks of the world
+#[wasm_bindgen]
+pub struct Prediction {
+    chunk_size: usize,
+    max_height: usize,
+    min_height: i32,
+    max_light_level: u32,
+    blocks: HashMap<u32, Block>,
+    /// Air that light doesn't pass, standing in for the voxels of unloaded chunks
+    void: Block,
+    chunks: HashMap<Vec2<i32>, ChunkData>,
+}
+
+#[wasm_bindgen]
+impl Prediction {
+    /// Takes the dimensions and the JSON of the blocks of the world data sent on join
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        chunk_size: usize,
+        max_height: usize,
+        min_height: i32,
+        max_light_level: u32,
+        blocks: &str,
+    ) -> Result<Prediction, JsValue> {
+        let blocks: HashMap<u32, Block> =
+            serde_json::from_str(blocks).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        // voxels of unknown ids are taken for air
+        if !blocks.contains_key(&0) {
+            return Err(JsValue::from_str("Blocks have no air of id 0"));
+        }
+
+        let mut void = blocks[&0].clone();
+        void.is_transparent = false;
+
+        Ok(Prediction {
+            chunk_size,
+            max_height,
+            min_height,
+            max_light_level,
+            blocks,
+            void,
+            chunks: HashMap::new(),
+        })
+    }
+
+    /// Mirror a chunk's voxels and lights as sent by the server, padding included
+    #[wasm_bindgen(js_name = setChunk)]
+    pub fn set_chunk(&mut self, cx: i32, cz: i32, voxels: Vec<u32>, lights: Vec<u32>) {
+        let coords = Vec2(cx, cz);
+        let mut chunk = ChunkData::new(&coords, self.chunk_size, self.max_height, self.min_height);
+
+        if voxels.len() == chunk.voxels.data.len() && lights.len() == chunk.lights.data.len() {
+            chunk.voxels.data = voxels;
+            chunk.lights.data = lights;
+            self.chunks.insert(coords, chunk);
+        }
+    }
+
+    /// Forget a chunk the client unloaded
+    #[wasm_bindgen(js_name = removeChunk)]
+    pub fn remove_chunk(&mut self, cx: i32, cz: i32) {
+        self.chunks.remove(&Vec2(cx, cz));
+    }
+
+    /// Change a voxel, as block updates do, relighting the voxels around it
+    #[wasm_bindgen(js_name = setVoxel)]
+    pub fn set_voxel(&mut self, vx: i32, vy: i32, vz: i32, id: u32) {
+        if !self.contains_height(vy) || !self.chunks.contains_key(&self.coords_of(vx, vz)) {
+            return;
+        }
+
+        let current = self.get_block_by_voxel(vx, vy, vz).clone();
+        let updated = self.get_block_by_id(id).clone();
+
+        // keep the rotation and stage bits above the id
+        for chunk in self.chunks_holding(vx, vy, vz) {
+            let value = insert_id(chunk.get_raw_voxel(vx, vy, vz), id);
+            chunk.set_raw_voxel(vx, vy, vz, value);
+        }
+
+        Lights::update_light(self, vx, vy, vz, &current, &updated);
+    }
+
+    /// Whether bodies collide with a voxel, which unloaded voxels don't
+    #[wasm_bindgen(js_name = isSolid)]
+    pub fn is_solid(&self, vx: i32, vy: i32, vz: i32) -> bool {
+        match self.chunk_with(vx, vy, vz) {
+            Some(chunk) => !self
+                .get_block_by_id(chunk.get_voxel(vx, vy, vz))
+                .is_walkable(),
+            None => false,
+        }
+    }
+
+    /// Light of a voxel, packed as the lights of the chunks the server sends
+    #[wasm_bindgen(js_name = getLight)]
+    pub fn get_light(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.chunk_with(vx, vy, vz)
+            .map_or(0, |chunk| chunk.get_raw_light(vx, vy, vz))
+    }
+
+    /// Lights of a chunk, padding included, or nothing if it isn't loaded
+    #[wasm_bindgen(js_name = getChunkLights)]
+    pub fn get_chunk_lights(&self, cx: i32, cz: i32) -> Vec<u32> {
+        self.chunks
+            .get(&Vec2(cx, cz))
+            .map_or(vec![], |chunk| chunk.lights.data.to_owned())
+    }
+
+    /// Move a box by `d` until it collides, returning its new base followed by the axes it came
+    /// to rest against, -1 or 1 for each of x, y and z, 0 if free
+    #[allow(clippy::too_many_arguments)]
+    pub fn sweep(
+        &self,
+        base_x: f32,
+        base_y: f32,
+        base_z: f32,
+        width: f32,
+        height: f32,
+        depth: f32,
+        dx: f32,
+        dy: f32,
+        dz: f32,
+    ) -> Vec<f32> {
+        let solid = |vx: i32, vy: i32, vz: i32| self.is_solid(vx, vy, vz);
+
+        let mut aabb = Aabb::new(&Vec3(base_x, base_y, base_z), &Vec3(width, height, depth));
+        let resting = Arc::new(Mutex::new([0.0; 3]));
+        let temp = resting.clone();
+
+        sweep(
+            &solid,
+            &mut aabb,
+            &Vec3(dx, dy, dz),
+            &mut move |_, axis: usize, dir: i32, vec: &mut Vec3<f32>| {
+                temp.lock().unwrap()[axis] = dir as f32;
+                vec[axis] = 0.0;
+                false
+            },
+            false,
+        );
+
+        let resting = *resting.lock().unwrap();
+
+        vec![
+            aabb.base.0,
+            aabb.base.1,
+            aabb.base.2,
+            resting[0],
+            resting[1],
+            resting[2],
+        ]
+    }
+
+    /// Cast a ray, returning the voxel hit followed by the normal of the face hit, or nothing
+    #[allow(clippy::too_many_arguments)]
+    pub fn raycast(
+        &self,
+        px: f32,
+        py: f32,
+        pz: f32,
+        dx: f32,
+        dy: f32,
+        dz: f32,
+        max_distance: f32,
+    ) -> Vec<i32> {
+        if approx_equals(&(dx * dx + dy * dy + dz * dz), &0.0) {
+            return vec![];
+        }
+
+        let solid = |vx: i32, vy: i32, vz: i32| self.is_solid(vx, vy, vz);
+
+        let mut hit_pos = Vec3::default();
+        let mut hit_norm = Vec3::default();
+
+        if !raycast::trace(
+            max_distance,
+            &solid,
+            &mut Vec3(px, py, pz),
+            &mut Vec3(dx, dy, dz),
+            &mut hit_pos,
+            &mut hit_norm,
+        ) {
+            return vec![];
+        }
+
+        // the hit lies on the face, step half a block inwards to land in the voxel
+        let inwards = |hit: f32, norm: i32| (hit - norm as f32 * 0.5).floor() as i32;
+
+        vec![
+            inwards(hit_pos.0, hit_norm.0),
+            inwards(hit_pos.1, hit_norm.1),
+            inwards(hit_pos.2, hit_norm.2),
+            hit_norm.0,
+            hit_norm.1,
+            hit_norm.2,
+        ]
+    }
+}
+
+impl Prediction {
+    fn contains_height(&self, vy: i32) -> bool {
+        vy >= self.min_height && vy < self.top()
+    }
+
+    fn coords_of(&self, vx: i32, vz: i32) -> Vec2<i32> {
+        map_voxel_to_chunk(vx, 0, vz, self.chunk_size)
+    }
+
+    fn get_block_by_id(&self, id: u32) -> &Block {
+        self.blocks.get(&id).unwrap_or(&self.blocks[&0])
+    }
+
+    /// The loaded chunk a voxel is in, or else one holding it as padding
+    fn chunk_with(&self, vx: i32, vy: i32, vz: i32) -> Option<&ChunkData> {
+        if let Some(chunk) = self.chunks.get(&self.coords_of(vx, vz)) {
+            return Some(chunk);
+        }
+
+        let padding = DATA_PADDING as i32;
+        let Vec2(min_x, min_z) = self.coords_of(vx - padding, vz - padding);
+        let Vec2(max_x, max_z) = self.coords_of(vx + padding, vz + padding);
+
+        (min_x..=max_x)
+            .flat_map(|cx| (min_z..=max_z).map(move |cz| Vec2(cx, cz)))
+            .filter_map(|coords| self.chunks.get(&coords))
+            .find(|chunk| chunk.contains(vx, vy, vz))
+    }
+
+    /// The loaded chunks holding a voxel, the padding of the ones beside it included
+    fn chunks_holding(&mut self, vx: i32, vy: i32, vz: i32) -> Vec<&mut ChunkData> {
+        let padding = DATA_PADDING as i32;
+        let min = self.coords_of(vx - padding, vz - padding);
+        let max = self.coords_of(vx + padding, vz + padding);
+
+        self.chunks
+            .iter_mut()
+            .filter(|(Vec2(cx, cz), chunk)| {
+                (min.0..=max.0).contains(cx)
+                    && (min.1..=max.1).contains(cz)
+                    && chunk.contains(vx, vy, vz)
+            })
+            .map(|(_, chunk)| chunk)
+            .collect()
+    }
+}
+
+impl LightSpace for Prediction {
+    fn min_height(&self) -> i32 {
+        self.min_height
+    }
+
+    fn top(&self) -> i32 {
+        self.min_height + self.max_height as i32
+    }
+
+    fn max_light_level(&self) -> u32 {
+        self.max_light_level
+    }
+
+    /// Voxels no loaded chunk holds are opaque, light stopping at the edge of what's loaded
+    fn get_block_by_voxel(&self, vx: i32, vy: i32, vz: i32) -> &Block {
+        match self.chunk_with(vx, vy, vz) {
+            Some(chunk) => self.get_block_by_id(chunk.get_voxel(vx, vy, vz)),
+            None => &self.void,
+        }
+    }
+
+    fn get_sunlight(&self, vx: i32, vy: i32, vz: i32) -> u32 {
+        self.chunk_with(vx, vy, vz)
+            .map_or(0, |chunk| chunk.get_sunlight(vx, vy, vz))
+    }
+
+    fn set_sunlight(&mut self, vx: i32, vy: i32, vz: i32, level: u32) {
+        if !self.contains_height(vy) {
+            return;
+        }
+
+        for chunk in self.chunks_holding(vx, vy, vz) {
+            chunk.set_sunlight(vx, vy, vz, level);
+        }
+    }
+
+    fn get_torch_light(&self, vx: i32, vy: i32, vz: i32, color: &LightColor) -> u32 {
+        self.chunk_with(vx, vy, vz)
+            .map_or(0, |chunk| chunk.get_torch_light(vx, vy, vz, color))
+    }
+
+    fn set_torch_light(&mut self, vx: i32, vy: i32, vz: i32, level: u32, color: &LightColor) {
+        if !self.contains_height(vy) {
+            return;
+        }
+
+        for chunk in self.chunks_holding(vx, vy, vz) {
+            chunk.set_torch_light(vx, vy, vz, level, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(name: &str, extra: &str) -> String {
+        format!(
+            r#"{{
+                "name": "{}", "rotatable": false, "yRotatable": false, "isBlock": true,
+                "isEmpty": false, "isFluid": false, "isPlant": false, "isPlantable": false,
+                "transparentStandalone": false{}
+            }}"#,
+            name, extra
+        )
+    }
+
+    #[test]
+    fn relight() {
+        let blocks = format!(
+            r#"{{ "0": {}, "1": {}, "2": {} }}"#,
+            block("Air", r#", "isSolid": false, "isTransparent": true"#),
+            block("Stone", r#", "isSolid": true, "isTransparent": false"#),
+            block(
+                "Lamp",
+                r#", "isSolid": true, "isTransparent": false, "isLight": true, "redLightLevel": 10"#
+            ),
+        );
+
+        let mut prediction = Prediction::new(4, 8, 0, 15, &blocks).unwrap();
+
+        // an empty chunk in full sun, as the server would send it
+        let shape = ChunkData::shape(4, 8).iter().product();
+        let sunlit = Lights::insert_sunlight(0, 15);
+        prediction.set_chunk(0, 0, vec![0; shape], vec![sunlit; shape]);
+
+        prediction.set_voxel(1, 7, 1, 1);
+        assert!(prediction.is_solid(1, 7, 1));
+        assert!(!prediction.is_solid(1, 6, 1));
+        assert_eq!(prediction.get_sunlight(1, 7, 1), 0);
+        assert!(prediction.get_sunlight(1, 6, 1) < 15);
+        assert_eq!(prediction.get_sunlight(2, 6, 1), 15);
+
+        prediction.set_voxel(2, 3, 2, 2);
+        assert_eq!(prediction.get_torch_light(2, 3, 2, &LightColor::Red), 10);
+        assert_eq!(prediction.get_torch_light(2, 3, 3, &LightColor::Red), 9);
+
+        // voxels past the loaded chunks aren't changed
+        prediction.set_voxel(40, 3, 2, 1);
+        assert!(!prediction.is_solid(40, 3, 2));
+    }
+}